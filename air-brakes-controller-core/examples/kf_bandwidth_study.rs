@@ -21,7 +21,7 @@ use icao_units::si::Pascals;
 use nalgebra::{Vector2, Vector3};
 
 use air_brakes_controller_core::{
-    AirBrakesMPC, ImuSample, RocketParameters,
+    AirBrakesMPC, Atmosphere, ImuSample, RocketParameters,
     airbrakes_estimator::{AirbrakesConfig, AirbrakesEstimator, MachLockoutConfig},
 };
 
@@ -415,11 +415,11 @@ impl Score {
 }
 
 fn lc25_rocket() -> RocketParameters {
-    RocketParameters {
-        burnout_mass: 17.607,
-        cd: [0.47044, 0.5082, 0.57784, 0.665, 0.74313],
-        reference_area: 0.008982476,
-    }
+    RocketParameters::mach_independent(
+        17.607,
+        [0.47044, 0.5082, 0.57784, 0.665, 0.74313],
+        0.008982476,
+    )
 }
 
 /// The 4 g both replayed flights clear with margin. A constant here rather
@@ -433,11 +433,12 @@ fn run_flight(name: &str, rows: Vec<Row>, config: AirbrakesConfig) {
     let ign_i = find_ignition(&rows);
 
     // ---- pass 1: the flown estimator, and where it is born
-    let mut est = AirbrakesEstimator::new(config.clone(), IGNITION_ACC_THRESHOLD);
+    let mut est =
+        AirbrakesEstimator::new(config.clone(), IGNITION_ACC_THRESHOLD, Atmosphere::standard());
     let mut flown: Vec<(usize, f32, Vector2<f32>)> = Vec::new();
     let mut birth: Option<(u64, bool, usize)> = None;
     for (i, r) in rows.iter().enumerate() {
-        est.update(r.timestamp_us, &r.imu, r.altitude_asl);
+        est.update(r.timestamp_us, &r.imu, r.altitude_asl, None);
         if birth.is_none()
             && let Some((t, forced)) = est.birth()
         {
@@ -592,11 +593,11 @@ fn run_flight(name: &str, rows: Vec<Row>, config: AirbrakesConfig) {
     // early-coast window, so the command sits in the live (unclamped)
     // region rather than pinned at 0% or 100%.
     let (ref_alt0, ref_vv0) = good_reference(&rows, windows[0].1).expect("no reference at start");
-    let target = AirBrakesMPC::new(rocket.clone(), 1e9)
+    let target = AirBrakesMPC::new(rocket.clone(), Atmosphere::standard(), 1e9)
         .update(ref_alt0, Vector2::new(0.0, ref_vv0))
         .predicted_apogee_asl
         - 150.0;
-    let mpc = AirBrakesMPC::new(rocket.clone(), target);
+    let mpc = AirBrakesMPC::new(rocket.clone(), Atmosphere::standard(), target);
 
     println!(
         "\n=== {name} ===\n  ignition t={:.1}s | filter born ignition+{:.2}s (forced: {forced}) \
@@ -827,10 +828,10 @@ fn run_flight(name: &str, rows: Vec<Row>, config: AirbrakesConfig) {
         .find(|(i, _, _)| *i >= windows[0].1)
         .map(|(_, _, v)| v.x)
         .unwrap_or(0.0);
-    let ap_with = AirBrakesMPC::new(rocket.clone(), 1e9)
+    let ap_with = AirBrakesMPC::new(rocket.clone(), Atmosphere::standard(), 1e9)
         .update(a0, Vector2::new(vx0, v0))
         .predicted_apogee_asl;
-    let ap_without = AirBrakesMPC::new(rocket.clone(), 1e9)
+    let ap_without = AirBrakesMPC::new(rocket.clone(), Atmosphere::standard(), 1e9)
         .update(a0, Vector2::new(0.0, v0))
         .predicted_apogee_asl;
     println!(
@@ -0,0 +1,238 @@
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion, Vector3};
+
+/// The full 3-D attitude: the rotation taking a vector written in the
+/// avionics (IMU chip) frame to the same vector written in the earth frame,
+/// `q * v_av = v_earth`.
+///
+/// The earth frame is east-north-up. UP is exact — it is the pad's own
+/// gravity. NORTH is magnetic north when the pad had a magnetometer (see
+/// [`Self::from_pad`]), and otherwise an arbitrary but fixed horizontal
+/// direction picked from the mounting: gravity says nothing about heading,
+/// so without a compass there is no heading to know, only changes of it.
+///
+/// Solved once on the pad, then gyro-only for the whole flight. Nothing
+/// corrects it in the air — the accelerometer reads thrust and drag there,
+/// not gravity, and the magnetometer reads whatever the motor case and the
+/// airframe's own currents make of the field — so both corrections are
+/// what the pad solution is made of, and neither is a filter input after
+/// ignition.
+///
+/// The quaternion IS renormalized, on every step. That is the difference
+/// from the one [`super::dead_reckoner::DeadReckoner`] carried until
+/// 2026-08-17: nalgebra composes with `Unit::new_unchecked`,
+/// and that one never renormalized, so its norm drifted (1.7e-2 over 10^6
+/// steps of a fast tumble) and the transform amplified the drift. Measured
+/// over the same 10^6 steps, renormalized: worst norm error 1.2e-7, worst
+/// error in UP 0.014 deg, against 4.8e-4 deg for the half-angle Rodrigues
+/// rotation of a single vector it replaces. Over a whole flight (8300
+/// steps) the two are 1.4e-4 and 8.2e-5 deg — both five orders inside the
+/// 5 deg tilt budget, and the quaternion carries all three axes instead of
+/// one.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone)]
+pub struct Attitude {
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    av_to_earth: UnitQuaternion<f32>,
+    /// Whether north is magnetic north, i.e. whether the pad solution had a
+    /// magnetometer reading to take it from.
+    magnetic_north: bool,
+}
+
+/// Roll, pitch and yaw of the airframe, radians.
+///
+/// Written the way the flight sims write an attitude — an axis direction
+/// plus a spin about it, `Rz(90 deg - yaw) * Ry(90 deg - pitch) * Rz(roll)`
+/// in east-north-up — rather than as aircraft Euler angles, whose pitch
+/// singularity sits at 90 deg of pitch: exactly where a rocket on the rail
+/// points.
+///
+/// The price is the same singularity moved to the same place: at exactly
+/// vertical the axis has no azimuth, so yaw is undefined there and roll
+/// absorbs it (their sum is still well defined). Both are meaningful once
+/// the airframe has tilted a few degrees, which every flight does within
+/// seconds of the rail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AttitudeAngles {
+    /// Spin about the airframe axis, right-handed about the nose (clockwise
+    /// seen from behind). Referenced to the avionics frame (see
+    /// [`Attitude::angles`]), so zero means the board, not the airframe.
+    pub roll: f32,
+    /// Elevation of the airframe axis above the horizon: 90 deg on a
+    /// vertical rail, and `90 deg - tilt` always.
+    pub pitch: f32,
+    /// Heading of the airframe axis, clockwise from north, 0 - 360 deg.
+    pub yaw: f32,
+    /// `yaw` is a compass heading (clockwise from magnetic north). When
+    /// false it is measured from an arbitrary pad reference, and only its
+    /// changes mean anything.
+    pub yaw_is_magnetic: bool,
+}
+
+impl Attitude {
+    /// Solve the pad attitude by TRIAD: UP is the pad's mean specific force
+    /// (an accelerometer at rest reads +1 g along up), and NORTH is the
+    /// horizontal part of `mag_av`, the magnetic field in the avionics
+    /// frame. Only directions are read, so either vector may be in any
+    /// units — `MagMeasurementMessage::mag`'s tesla go in as they are.
+    ///
+    /// UP is taken whole and north only has its vertical part removed, so
+    /// the magnetometer cannot tilt the solution: tilt is gravity's alone,
+    /// exactly as it was before there was a heading.
+    ///
+    /// Without a field — or with one too close to vertical to have a
+    /// horizontal part — north is the avionics axis least aligned with UP,
+    /// made horizontal. Arbitrary, and deterministic, which is all a
+    /// heading with no reference can be.
+    pub fn from_pad(gravity_av: &Vector3<f32>, mag_av: Option<&Vector3<f32>>) -> Self {
+        let up = gravity_av.normalize();
+        let horizontal = |v: &Vector3<f32>| {
+            let h = v - up * up.dot(v);
+            let norm = h.magnitude();
+            // A field within ~3 deg of vertical has nothing horizontal left
+            // to read a heading from.
+            (norm > 0.05 * v.magnitude()).then(|| h / norm)
+        };
+        let magnetic = mag_av.and_then(horizontal);
+        let north = magnetic.unwrap_or_else(|| {
+            let least = up.iamin();
+            horizontal(&Vector3::ith(least, 1.0)).unwrap()
+        });
+        let east = north.cross(&up);
+
+        // Rows are the earth axes written in the avionics frame, so this
+        // takes an avionics vector to its earth components.
+        let av_to_earth =
+            Matrix3::from_rows(&[east.transpose(), north.transpose(), up.transpose()]);
+        Self {
+            av_to_earth: UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(
+                av_to_earth,
+            )),
+            magnetic_north: magnetic.is_some(),
+        }
+    }
+
+    /// Turn by one gyro step: `gyro` in the avionics frame (rad/s), bias
+    /// already removed, over the measured `dt` (s). Renormalized every
+    /// step — see the type's docs for why, and what it measured.
+    pub fn propagate(&mut self, gyro: &Vector3<f32>, dt: f32) {
+        self.av_to_earth *= UnitQuaternion::from_scaled_axis(gyro * dt);
+        self.av_to_earth.renormalize_fast();
+    }
+
+    /// Earth UP written in the avionics frame, unit length — the direction
+    /// the accelerometer reads +1 g along while the airframe is still.
+    pub fn up_av(&self) -> Vector3<f32> {
+        self.av_to_earth.inverse_transform_vector(&Vector3::z())
+    }
+
+    /// The angles of the airframe whose axis is `axis_av` (unit, in the
+    /// avionics frame — the stage-1 thrust axis).
+    ///
+    /// Roll needs a body direction perpendicular to the axis to measure the
+    /// spin of, and the airframe has no marked one; this takes the avionics
+    /// axis least aligned with `axis_av`, made perpendicular to it. That is
+    /// fixed in the airframe, so roll's changes are the airframe's, and its
+    /// zero is a property of how the board is mounted.
+    pub fn angles(&self, axis_av: &Vector3<f32>) -> AttitudeAngles {
+        let reference_av = {
+            let x = Vector3::ith(axis_av.iamin(), 1.0);
+            (x - axis_av * axis_av.dot(&x)).normalize()
+        };
+        let axis = self.av_to_earth * axis_av;
+        let reference = self.av_to_earth * reference_av;
+
+        let tilt = libm::acosf(axis.z.clamp(-1.0, 1.0));
+        let azimuth = libm::atan2f(axis.y, axis.x);
+        // Where the reference direction would point at zero roll: the body
+        // X axis carried through `Rz(azimuth) * Ry(tilt)`.
+        let (sin_t, cos_t) = (libm::sinf(tilt), libm::cosf(tilt));
+        let (sin_a, cos_a) = (libm::sinf(azimuth), libm::cosf(azimuth));
+        let zero_roll = Vector3::new(cos_t * cos_a, cos_t * sin_a, -sin_t);
+        let roll = libm::atan2f(
+            axis.dot(&zero_roll.cross(&reference)),
+            zero_roll.dot(&reference),
+        );
+
+        // Azimuth is counterclockwise from east, in -180 - 180 deg; heading
+        // is clockwise from north, in 0 - 360.
+        let yaw = core::f32::consts::FRAC_PI_2 - azimuth;
+        let yaw = if yaw < 0.0 {
+            yaw + core::f32::consts::TAU
+        } else {
+            yaw
+        };
+        AttitudeAngles {
+            roll,
+            pitch: core::f32::consts::FRAC_PI_2 - tilt,
+            yaw,
+            yaw_is_magnetic: self.magnetic_north,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// A board mounted upside down and rolled, on a vertical rail, with the
+    /// field dipping 60 deg into the ground from the north.
+    fn pad() -> (UnitQuaternion<f32>, Vector3<f32>, Vector3<f32>) {
+        let av_to_earth = UnitQuaternion::from_euler_angles(3.0, 0.4, -1.2);
+        let gravity_av = av_to_earth.inverse_transform_vector(&Vector3::new(0.0, 0.0, 9.81));
+        let field = Vector3::new(0.0, 0.5, -0.866) * 50e-6;
+        let mag_av = av_to_earth.inverse_transform_vector(&field);
+        (av_to_earth, gravity_av, mag_av)
+    }
+
+    #[test]
+    fn a_magnetometer_recovers_the_whole_pad_attitude() {
+        let (truth, gravity_av, mag_av) = pad();
+        let attitude = Attitude::from_pad(&gravity_av, Some(&mag_av));
+        assert!(attitude.av_to_earth.angle_to(&truth) < 1e-3);
+        assert!(attitude.magnetic_north);
+
+        // Without one, tilt is unchanged and only heading is lost.
+        let blind = Attitude::from_pad(&gravity_av, None);
+        assert!(!blind.magnetic_north);
+        assert_relative_eq!(blind.up_av(), attitude.up_av(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn angles_follow_a_tilted_rolled_airframe() {
+        // Airframe pointing 30 deg off vertical toward the east-northeast
+        // (heading 60 deg), rolled 0.7 rad, board mounted arbitrarily.
+        let (heading, tilt, roll) = (60f32.to_radians(), 30f32.to_radians(), 0.7);
+        let body_to_earth = UnitQuaternion::from_axis_angle(
+            &Vector3::z_axis(),
+            core::f32::consts::FRAC_PI_2 - heading,
+        ) * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), tilt)
+            * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), roll);
+        let mount = UnitQuaternion::from_euler_angles(0.31, -0.22, 2.4);
+        let attitude = Attitude {
+            av_to_earth: body_to_earth * mount,
+            magnetic_north: true,
+        };
+        let axis_av = mount.inverse_transform_vector(&Vector3::z());
+
+        let angles = attitude.angles(&axis_av);
+        assert_relative_eq!(angles.pitch, 60f32.to_radians(), epsilon = 1e-5);
+        assert_relative_eq!(angles.yaw, heading, epsilon = 1e-5);
+
+        // Rolling the airframe further moves roll, and only roll, by the
+        // same amount.
+        let more = Attitude {
+            av_to_earth: attitude.av_to_earth
+                * mount.inverse()
+                * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.5)
+                * mount,
+            magnetic_north: true,
+        };
+        let rolled = more.angles(&axis_av);
+        let turned = (rolled.roll - angles.roll).rem_euclid(core::f32::consts::TAU);
+        assert_relative_eq!(turned, 0.5, epsilon = 1e-5);
+        assert_relative_eq!(rolled.pitch, angles.pitch, epsilon = 1e-5);
+        assert_relative_eq!(rolled.yaw, angles.yaw, epsilon = 1e-5);
+    }
+}
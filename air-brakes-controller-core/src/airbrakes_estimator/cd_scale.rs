@@ -0,0 +1,175 @@
+/// The airframe's drag, learned in flight: one scale factor on the whole
+/// configured cd table, estimated from the axial deceleration the
+/// accelerometer measures against the deceleration the table predicts for
+/// the same airspeed, altitude and flap position.
+///
+/// A one-state Kalman filter, not a bigger model. The measurement is linear
+/// in the scale — `a_measured = scale * a_modelled` — so there is no
+/// Jacobian to get wrong, and the state is a random walk because what it
+/// absorbs is a table error that moves with Mach rather than a constant:
+/// the configured table is only ever right at the Mach it was tabulated at
+/// (`osiris_sim::mach_dependent_cd_tracks_the_openrocket_coast` measures
+/// Table 10 3.6% above OpenRocket at Mach 0.8 and 18% above it at 0.5).
+///
+/// Bounded, because the regressor is built out of the estimator's own
+/// outputs and a wrong one must not be able to fly the brakes on an
+/// arbitrary airframe. The bound is a clamp on the state after every
+/// update, so the MPC can never be handed anything outside it.
+///
+/// All steps take measured dt; nothing assumes a sample rate.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone)]
+pub struct CdScaleFilter {
+    scale: f32,
+    /// Variance of `scale`.
+    p: f32,
+    /// The extension the flaps are modelled at, 0.0 - 1.0: the last command
+    /// followed at the servo's measured slew rate.
+    extension: f32,
+}
+
+/// The scale is clamped to this range.
+///
+/// The two airframes the tree has truth for sit well inside it: LC'25's
+/// measured drag over dynamic pressure spans -8%/+8% of its configured
+/// stowed Cd across the subsonic coast, and Table 10 reads 4-18% above
+/// Osiris's OpenRocket drag over the Mach the brakes fly. Three tenths
+/// either way is what a config can plausibly be wrong by; past that the
+/// config is the bug.
+pub const CD_SCALE_MIN: f32 = 0.7;
+pub const CD_SCALE_MAX: f32 = 1.3;
+
+/// Prior std of the scale at birth: the configured table is believed to
+/// about a tenth.
+const CD_SCALE_PRIOR_STD: f32 = 0.1;
+/// Random-walk std of the scale (per sqrt(s)). Over a ten-second coast that
+/// lets it wander ~0.06, which is the size of the Mach drift it has to
+/// follow.
+const CD_SCALE_WALK_STD: f32 = 0.02;
+/// Std of one raw axial accelerometer sample against the modelled drag
+/// (m/s^2): sensor noise plus airframe vibration, which in the coast is the
+/// larger of the two.
+const AXIAL_NOISE_STD: f32 = 1.0;
+/// No update while the table predicts less drag than this (m/s^2). Near
+/// apogee the drag vanishes and the airspeed the regressor is built from is
+/// mostly tilt — the measurement carries nothing about the scale there, and
+/// everything about the tilt's error.
+const MIN_FIT_DRAG_M_S2: f32 = 1.0;
+/// How fast the modelled flaps follow a command (full strokes per s).
+///
+/// Icarus's measured slew, from the deploy and retract in VLF5's HIL log —
+/// see `ServoModel::ICARUS` in `controller::servo`, which flies the MPC's
+/// plan on the same stroke. Without it every command
+/// step would be charged to the scale for the 0.4 s the flaps take to get
+/// there.
+const SERVO_SLEW_PER_S: f32 = crate::controller::ServoModel::ICARUS.slew_per_s;
+
+impl CdScaleFilter {
+    /// At the configured table, flaps stowed.
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            p: CD_SCALE_PRIOR_STD * CD_SCALE_PRIOR_STD,
+            extension: 0.0,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// The flap position the next fit will be made at.
+    pub fn extension(&self) -> f32 {
+        self.extension
+    }
+
+    /// Step the random walk and the modelled servo over measured `dt`.
+    /// `commanded_extension` is `None` until anything has been commanded, in
+    /// which case the flaps stay where they were.
+    pub fn predict(&mut self, commanded_extension: Option<f32>, dt: f32) {
+        self.p += CD_SCALE_WALK_STD * CD_SCALE_WALK_STD * dt;
+        if let Some(command) = commanded_extension {
+            let step = SERVO_SLEW_PER_S * dt;
+            self.extension += (command.clamp(0.0, 1.0) - self.extension).clamp(-step, step);
+        }
+    }
+
+    /// Fuse one axial deceleration (m/s^2, deceleration-positive) against
+    /// the deceleration the configured table predicts at scale 1.
+    ///
+    /// Skipped, not fused, when the prediction is too small to carry
+    /// anything or either number is not finite.
+    pub fn update(&mut self, measured_drag: f32, modelled_drag: f32) {
+        if !modelled_drag.is_finite()
+            || modelled_drag < MIN_FIT_DRAG_M_S2
+            || !measured_drag.is_finite()
+        {
+            return;
+        }
+        let r = AXIAL_NOISE_STD * AXIAL_NOISE_STD;
+        let s = modelled_drag * self.p * modelled_drag + r;
+        let k = self.p * modelled_drag / s;
+        self.scale = (self.scale + k * (measured_drag - modelled_drag * self.scale))
+            .clamp(CD_SCALE_MIN, CD_SCALE_MAX);
+        // Joseph form, H = modelled_drag
+        let a = 1.0 - k * modelled_drag;
+        self.p = a * a * self.p + k * k * r;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A table 20% heavy: the filter must find 1/1.2 within the first
+    /// second of coast, from noiseless samples at 416 Hz.
+    #[test]
+    fn learns_a_constant_scale() {
+        let mut f = CdScaleFilter::new();
+        let dt = 1.0 / 416.0;
+        for _ in 0..416 {
+            f.predict(Some(0.0), dt);
+            f.update(12.0, 12.0 * 1.2);
+        }
+        assert!(
+            (f.scale() - 1.0 / 1.2).abs() < 0.01,
+            "scale {} after 1 s",
+            f.scale()
+        );
+    }
+
+    /// However wrong the measurement, the scale leaves the clamp range
+    /// never, and nothing is fused where the table predicts almost no drag.
+    #[test]
+    fn the_correction_is_bounded() {
+        let mut f = CdScaleFilter::new();
+        let dt = 1.0 / 416.0;
+        for _ in 0..416 {
+            f.predict(None, dt);
+            f.update(100.0, 10.0);
+        }
+        assert_eq!(f.scale(), CD_SCALE_MAX);
+        for _ in 0..416 {
+            f.predict(None, dt);
+            f.update(0.0, 10.0);
+        }
+        assert_eq!(f.scale(), CD_SCALE_MIN);
+
+        let before = f.scale();
+        f.update(50.0, 0.5 * MIN_FIT_DRAG_M_S2);
+        f.update(f32::NAN, 10.0);
+        assert_eq!(f.scale(), before);
+    }
+
+    /// The modelled flaps follow a step at the servo's slew, not at once.
+    #[test]
+    fn the_modelled_servo_slews_to_the_command() {
+        let mut f = CdScaleFilter::new();
+        f.predict(Some(1.0), 0.1);
+        assert!((f.extension() - 0.267).abs() < 1e-4);
+        f.predict(Some(1.0), 1.0);
+        assert_eq!(f.extension(), 1.0);
+        f.predict(None, 1.0);
+        assert_eq!(f.extension(), 1.0);
+    }
+}
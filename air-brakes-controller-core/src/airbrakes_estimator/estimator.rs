@@ -3,13 +3,18 @@ use nalgebra::{SVector, Vector2, Vector3};
 
 use firmware_common_new::flight_data_record::AirbrakesState;
 
+#[cfg(feature = "debug-internals")]
+use crate::{BaroGateOutcome, kf_internals::KfInternals};
 use crate::{
     airbrakes_estimator::{
-        AirbrakesConfig, ImuSample, MAX_DT_S, dead_reckoner::DeadReckoner,
+        AirbrakesConfig, ImuSample, MAX_DT_S,
+        attitude::{Attitude, AttitudeAngles},
+        cd_scale::CdScaleFilter,
+        dead_reckoner::DeadReckoner,
         vertical_kf::VerticalKF,
     },
+    atmosphere::Atmosphere,
     ignition_detector::IgnitionDetector,
-    utils::{approximate_air_density, approximate_speed_of_sound},
 };
 
 // --- Pad calibration (Piece 1) ---------------------------------------------
@@ -170,11 +175,11 @@ const STAGE1_DURATION_S: f32 = 0.5;
 /// 2 m/s^2 rather than 0 keeps the latch clear of the crossing itself.
 /// Coast drag is 7-21 m/s^2 over the region that matters, and the pad noise
 /// floor is ~0.04 m/s^2, so there is no contest.
-const BURNOUT_DECEL_M_S2: f32 = 2.0;
+pub(crate) const BURNOUT_DECEL_M_S2: f32 = 2.0;
 /// How long the axial channel must stay decelerating before burnout latches.
 /// Measured latch times: LC'25 ignition+6.38 s, Void Lake +1.96 s — both
 /// about this long after true burnout, i.e. erring late.
-const BURNOUT_SUSTAIN_S: f32 = 0.3;
+pub(crate) const BURNOUT_SUSTAIN_S: f32 = 0.3;
 /// Time constant of the low pass on the drag channel. The channel is a
 /// single raw sample, so it carries the full accelerometer noise and
 /// airframe vibration; unfiltered, one noisy sample trips the threshold
@@ -314,6 +319,13 @@ enum State {
         /// Latest screening result; `None` until enough windows agree.
         /// Ignition detection is refused while this is `None`.
         calibration: Option<PadCalibration>,
+        /// Low-passed magnetometer reading (avionics frame), `None` until
+        /// [`AirbrakesEstimator::update_mag`] is first called. Not part of
+        /// the screened calibration, and not required by it: it only gives
+        /// the pad attitude a north, and a flight without it is the flight
+        /// this estimator always flew.
+        mag_lp: Option<Vector3<f32>>,
+        mag_timestamp_us: Option<u64>,
     },
 
     /// First half second of powered flight: the thrust direction tells us
@@ -365,7 +377,8 @@ enum State {
     /// The brakes may open, and will be allowed to for the rest of the
     /// flight: the baro is honest, the 2-state vertical filter exists and
     /// runs to apogee, and the Mach limit was cleared on the way in. Tilt
-    /// still comes from the gyro dead reckoner.
+    /// still comes from the gyro dead reckoner, and the drag the MPC flies
+    /// is refitted against the accelerometer on every sample.
     ///
     /// This is the LAST state. There is no apogee state to move on to: the
     /// whole estimator is dropped by [`FlightEstimators::update`] the first
@@ -378,6 +391,7 @@ enum State {
         reckoner: DeadReckoner,
         gyro_bias: Vector3<f32>,
         kf: VerticalKF,
+        cd_scale: CdScaleFilter,
         born_t_us: u64,
         born_forced: bool,
     },
@@ -391,7 +405,12 @@ pub struct AirbrakesEstimator {
     #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
     state: State,
     config: AirbrakesConfig,
+    atmosphere: Atmosphere,
     prev_timestamp_us: Option<u64>,
+    /// What this sample's baro update did inside the vertical filter; see
+    /// [`Self::kf_internals`].
+    #[cfg(feature = "debug-internals")]
+    kf_internals: Option<KfInternals>,
 }
 
 impl AirbrakesEstimator {
@@ -400,7 +419,16 @@ impl AirbrakesEstimator {
     /// [`FlightConfig::ignition_detection_acc_threshold`](crate::FlightConfig::ignition_detection_acc_threshold),
     /// the one number both halves detect ignition at, handed down by
     /// [`FlightEstimators::new`](crate::FlightEstimators::new).
-    pub fn new(config: AirbrakesConfig, ignition_detection_acc_threshold: f32) -> Self {
+    ///
+    /// `atmosphere` is the air the drag fits are made in, and the frame of
+    /// the baro altitudes this is fed. `FlightEstimators` starts it on the
+    /// standard day and replaces it at launch with the pad's own (see
+    /// [`FlightEstimators::atmosphere`](crate::FlightEstimators::atmosphere)).
+    pub fn new(
+        config: AirbrakesConfig,
+        ignition_detection_acc_threshold: f32,
+        atmosphere: Atmosphere,
+    ) -> Self {
         Self {
             state: State::Armed {
                 pad_ring: Deque::new(),
@@ -410,21 +438,47 @@ impl AirbrakesEstimator {
                 window_n: 0,
                 window_elapsed: 0.0,
                 calibration: None,
+                mag_lp: None,
+                mag_timestamp_us: None,
             },
             config,
+            atmosphere,
             prev_timestamp_us: None,
+            #[cfg(feature = "debug-internals")]
+            kf_internals: None,
         }
     }
 
+    /// Fly on `atmosphere` from the next sample on:
+    /// [`FlightEstimators::atmosphere`](crate::FlightEstimators::atmosphere),
+    /// calibrated at launch. The pad is where it was in the new frame, and
+    /// nothing above it has been measured yet, so there is nothing to
+    /// convert.
+    pub(crate) fn set_atmosphere(&mut self, atmosphere: Atmosphere) {
+        self.atmosphere = atmosphere;
+    }
+
     /// Feed one IMU sample, the time it was taken (us, one monotonic clock)
     /// and the baro altitude ASL (m) from the same instant.
     ///
+    /// `commanded_extension` is the brake extension last commanded, 0.0 -
+    /// 1.0, or `None` while nothing has been — the same field the SD log's
+    /// airbrakes record carries. It is read only by the drag fit behind
+    /// [`Self::cd_scale`], which models the flaps following it at the
+    /// servo's slew; a `None` leaves them where they were (stowed, at birth).
+    ///
     /// Returns nothing. It used to return what the vertical filter's
     /// innovation gate made of this sample's baro reading; the filter has no
     /// gate any more (see [`super::vertical_kf::VerticalKF`]), and there is
     /// nothing else about one sample that a caller cannot ask the estimator
     /// for afterwards.
-    pub fn update(&mut self, timestamp_us: u64, imu: &ImuSample, altitude_asl: f32) {
+    pub fn update(
+        &mut self,
+        timestamp_us: u64,
+        imu: &ImuSample,
+        altitude_asl: f32,
+        commanded_extension: Option<f32>,
+    ) {
         // The very first sample has no predecessor to difference against, so
         // it carries no elapsed time and is stepped by 0. That is not a
         // special case anyone has to reason about: `saturating_sub` plus this
@@ -447,6 +501,10 @@ impl AirbrakesEstimator {
             None => 0.0,
         };
         self.prev_timestamp_us = Some(timestamp_us);
+        #[cfg(feature = "debug-internals")]
+        {
+            self.kf_internals = None;
+        }
 
         let acc = imu.acc;
         let gyro = imu.gyro;
@@ -460,6 +518,8 @@ impl AirbrakesEstimator {
                 window_n,
                 window_elapsed,
                 calibration,
+                mag_lp,
+                ..
             } => {
                 // Run every sample so the low pass and the sustain are
                 // already warm when the motor lights; the result is only
@@ -559,11 +619,12 @@ impl AirbrakesEstimator {
 
                 // The pad's own mean specific force IS earth UP in the
                 // avionics frame — an accelerometer at rest reads +1 g
-                // along up — so the pad attitude is a `normalize()` with no
-                // rotation to solve and no degenerate case for a mounting
-                // that happens to sit exactly inverted.
+                // along up — and the magnetometer, when there is one, only
+                // turns the solution about it. No degenerate case for a
+                // mounting that happens to sit exactly inverted.
                 let pad_up_av = cal.gravity_av_frame.normalize();
-                let mut reckoner = DeadReckoner::new(pad_up_av);
+                let mut reckoner =
+                    DeadReckoner::new(Attitude::from_pad(&cal.gravity_av_frame, mag_lp.as_ref()));
 
                 // Rewind: ignition was detected late (low-pass lag +
                 // threshold), so the buffer's tail holds the first moments
@@ -784,13 +845,15 @@ impl AirbrakesEstimator {
                             let altitude = lockout.subsonic_crossing_altitude_asl;
                             let subsonic = match drag_airspeed(
                                 a_drag,
-                                altitude,
-                                self.config.rocket.subsonic_cda_over_mass(),
+                                self.atmosphere.air_density(altitude),
+                                self.config
+                                    .rocket
+                                    .subsonic_cda_over_mass(self.config.max_open_mach),
                             ) {
                                 Some(airspeed) => {
                                     airspeed
                                         < self.config.max_open_mach
-                                            * approximate_speed_of_sound(altitude)
+                                            * self.atmosphere.speed_of_sound(altitude)
                                 }
                                 // Nonsensical drag parameter: never pass,
                                 // fall through to the T_max backstop.
@@ -864,15 +927,18 @@ impl AirbrakesEstimator {
                     reckoner: reckoner.clone(),
                     gyro_bias: *gyro_bias,
                     kf,
+                    cd_scale: CdScaleFilter::new(),
                     born_t_us: timestamp_us,
                     born_forced: forced,
                 };
             }
 
             State::AirbrakesEnabled {
+                thrust_axis_av,
                 reckoner,
                 gyro_bias,
                 kf,
+                cd_scale,
                 ..
             } => {
                 // The dead reckoner runs for two things here: the attitude
@@ -890,13 +956,82 @@ impl AirbrakesEstimator {
                 // horizontal component, and a drifting gyro cannot corrupt
                 // what the MPC flies on.
                 //
+                #[cfg(feature = "debug-internals")]
+                let (innovation, innovation_variance) = kf.innovation(altitude_asl);
+                kf.update(altitude_asl);
+                #[cfg(feature = "debug-internals")]
+                {
+                    let (altitude_variance, velocity_variance) = kf.covariance_diagonal();
+                    self.kf_internals = Some(KfInternals {
+                        innovation,
+                        innovation_variance,
+                        altitude_variance,
+                        velocity_variance,
+                        gate: BaroGateOutcome::Accepted,
+                    });
+                }
+
+                // The drag fit: the same axial channel the burnout latch and
+                // the drag check read, raw, against what the table predicts
+                // at the filter's airspeed and the modelled flap position.
+                // Airspeed is rebuilt the way `velocity()` hands it to the
+                // MPC — vertical velocity over cos(tilt), tilt capped — so
+                // the scale corrects the drag the MPC will actually fly.
+                //
                 // Nothing follows this. There is no apogee transition to
                 // make: the estimator runs until the wrapper drops it.
-                kf.update(altitude_asl);
+                cd_scale.predict(commanded_extension, dt);
+                let altitude = kf.altitude_asl();
+                let tilt = axis_tilt(thrust_axis_av, reckoner).min(TILT_CAP_RAD);
+                let speed = kf.vertical_velocity() / libm::cosf(tilt);
+                let modelled_drag = 0.5
+                    * self.atmosphere.air_density(altitude)
+                    * speed
+                    * speed
+                    * self.config.rocket.cda_over_mass(
+                        cd_scale.extension(),
+                        speed / self.atmosphere.speed_of_sound(altitude),
+                    );
+                cd_scale.update(-acc.dot(thrust_axis_av), modelled_drag);
             }
         }
     }
 
+    /// Feed one magnetometer reading, in the avionics frame and any units
+    /// (`MagMeasurementMessage::mag` as it arrives), with the time it was
+    /// taken on the same clock as [`Self::update`]'s.
+    ///
+    /// Read on the pad only, where it gives the pad attitude its north (see
+    /// [`Attitude::from_pad`]); from ignition on it is ignored, since a
+    /// motor case and a flight's worth of currents are not a compass. The
+    /// axes must be the IMU's — a chip mounted differently is rotated at the
+    /// edge, the way units are — and hard/soft-iron corrected.
+    ///
+    /// Low-passed over the calibration window's span: the pad heading is
+    /// the mean field of the last few seconds, the same span the gravity it
+    /// is solved against is averaged over.
+    pub fn update_mag(&mut self, timestamp_us: u64, mag: &Vector3<f32>) {
+        let State::Armed {
+            mag_lp,
+            mag_timestamp_us,
+            ..
+        } = &mut self.state
+        else {
+            return;
+        };
+        let dt = match *mag_timestamp_us {
+            Some(prev) => {
+                ((timestamp_us.saturating_sub(prev)) as f32 * 1e-6).clamp(0.0, MAX_DT_S)
+            }
+            None => 0.0,
+        };
+        *mag_timestamp_us = Some(timestamp_us);
+        *mag_lp = Some(match *mag_lp {
+            Some(prev) => prev + (mag - prev) * (dt / PAD_WINDOW_S).min(1.0),
+            None => *mag,
+        });
+    }
+
     /// Altitude ASL from the vertical filter, `None` until it is born.
     ///
     /// Absent — not stale, not integrated — for the whole boost and lockout.
@@ -951,6 +1086,69 @@ impl AirbrakesEstimator {
         }
     }
 
+    /// Vertical velocity (m/s, + up) from the dead reckoner: the IMU
+    /// integrated from the pad with nothing correcting it. Present from the
+    /// end of stage 1, the same samples as [`Self::tilt`].
+    ///
+    /// Not an MPC input and never handed to one — that is [`Self::velocity`],
+    /// which waits for the baro. It is what the sustainer inhibit in
+    /// [`crate::staging`] reads between burns, where there is no filter yet
+    /// and the question is only whether the airframe is still going up fast.
+    pub fn dead_reckoned_vertical_velocity(&self) -> Option<f32> {
+        match &self.state {
+            State::DeadReckoning { reckoner, .. } | State::AirbrakesEnabled { reckoner, .. } => {
+                Some(reckoner.vertical_velocity)
+            }
+            _ => None,
+        }
+    }
+
+    /// The in-flight drag fit's scale on the configured cd table, `None`
+    /// until the vertical filter is born — see
+    /// [`CdScaleFilter`](super::cd_scale::CdScaleFilter). 1.0 at birth, and
+    /// never outside [`CD_SCALE_MIN`](super::CD_SCALE_MIN) -
+    /// [`CD_SCALE_MAX`](super::CD_SCALE_MAX) after.
+    ///
+    /// What the MPC should fly on is
+    /// [`AirBrakesMPC::update_with_cd_scale`](crate::AirBrakesMPC::update_with_cd_scale)
+    /// with this.
+    pub fn cd_scale(&self) -> Option<f32> {
+        match &self.state {
+            State::AirbrakesEnabled { cd_scale, .. } => Some(cd_scale.scale()),
+            _ => None,
+        }
+    }
+
+    /// The vertical filter's innovation, its variance and its covariance
+    /// diagonal from the last [`Self::update`], `None` on every sample the
+    /// filter did not exist for. The gate is always `Accepted`: this filter
+    /// has none.
+    #[cfg(feature = "debug-internals")]
+    pub fn kf_internals(&self) -> Option<KfInternals> {
+        self.kf_internals
+    }
+
+    /// Roll, pitch and yaw of the airframe (gyro dead reckoning from the
+    /// pad attitude), from the same moment as [`Self::tilt`]: the angles are
+    /// of the airframe axis, which stage 1 is what finds. `pitch` is
+    /// `90 deg - tilt`; `yaw` is a compass heading only if
+    /// [`Self::update_mag`] was fed on the pad.
+    pub fn attitude(&self) -> Option<AttitudeAngles> {
+        match &self.state {
+            State::DeadReckoning {
+                thrust_axis_av,
+                reckoner,
+                ..
+            }
+            | State::AirbrakesEnabled {
+                thrust_axis_av,
+                reckoner,
+                ..
+            } => Some(reckoner.attitude.angles(thrust_axis_av)),
+            _ => None,
+        }
+    }
+
     /// Rocket axis tilt from vertical, radians (gyro dead reckoning).
     pub fn tilt(&self) -> Option<f32> {
         match &self.state {
@@ -1043,6 +1241,16 @@ impl AirbrakesEstimator {
         }
     }
 
+    /// Seconds from the vertical filter's birth — the moment the brakes were
+    /// permitted to open — to the latest sample, `None` before it. The clock
+    /// [`ControllerMode::Scheduled`](crate::ControllerMode::Scheduled) runs
+    /// on.
+    pub fn enabled_for_s(&self) -> Option<f32> {
+        let (born_t_us, _) = self.birth()?;
+        let now_us = self.prev_timestamp_us?;
+        Some(now_us.saturating_sub(born_t_us) as f32 * 1e-6)
+    }
+
     /// True once the pad screening has produced a trustworthy calibration:
     /// at least `MIN_CALIBRATION_WINDOWS` (3) finished 2 s windows each
     /// read 1 g and no net rotation, i.e. 6 s of data taken while the
@@ -1133,11 +1341,10 @@ fn ring_span_s(ring: &Deque<(u64, f32), BARO_RING_CAP>) -> f32 {
 }
 
 /// Airspeed (m/s) implied by the measured drag deceleration, inverting
-/// `a = 0.5 * rho * v^2 * cda_over_mass`. `None` if the atmosphere or the
+/// `a = 0.5 * rho * v^2 * cda_over_mass`. `None` if the air density or the
 /// drag parameter is degenerate, which makes a misconfigured airframe fail
 /// toward "never pass" rather than toward "always subsonic".
-fn drag_airspeed(a_drag: f32, altitude_asl: f32, cda_over_mass: f32) -> Option<f32> {
-    let rho = approximate_air_density(altitude_asl);
+fn drag_airspeed(a_drag: f32, rho: f32, cda_over_mass: f32) -> Option<f32> {
     if !(cda_over_mass > 0.0) || !(rho > 0.0) || !(a_drag >= 0.0) {
         return None;
     }
@@ -1202,5 +1409,5 @@ fn ring_median(
 /// axis and earth UP, both written in the avionics frame — which is the
 /// frame both are already in, so no rotation is applied to take it.
 fn axis_tilt(thrust_axis_av: &Vector3<f32>, reckoner: &DeadReckoner) -> f32 {
-    reckoner.up_av.angle(thrust_axis_av)
+    reckoner.up_av().angle(thrust_axis_av)
 }
@@ -1,10 +1,12 @@
 //! Airbrakes estimator.
-//! Gives the airbrakes MPC altitude, vertical velocity, and tilt.
+//! Gives the airbrakes MPC altitude, vertical velocity, and tilt, and the
+//! log the airframe's roll, pitch and yaw.
 //! Only needs to be accurate after the rocket decelerates below Mach 0.8
 //! post-burnout, until apogee.
 //!
-//! Design in one line: gyro-only tilt (no filter) + inertial dead reckoning
-//! while the baro lies (transonic/supersonic shock at the static port) +
+//! Design in one line: gyro-only attitude from a pad solution (no filter) +
+//! inertial dead reckoning while the baro lies (transonic/supersonic shock
+//! at the static port) +
 //! a drag measurement that says when the flow is subsonic again + a small
 //! [altitude, vertical velocity] filter constructed fresh at that moment
 //! ("born subsonic" — no state that existed during the garbage period
@@ -72,13 +74,18 @@ use nalgebra::Vector3;
 
 use crate::controller::RocketParameters;
 
+pub(crate) mod attitude;
+mod cd_scale;
 mod dead_reckoner;
 mod estimator;
 #[cfg(test)]
 mod tests;
 mod vertical_kf;
 
+pub use attitude::AttitudeAngles;
+pub use cd_scale::{CD_SCALE_MAX, CD_SCALE_MIN};
 pub use estimator::AirbrakesEstimator;
+pub(crate) use estimator::{BURNOUT_DECEL_M_S2, BURNOUT_SUSTAIN_S};
 
 /// Per-sample dt clamp: a gap longer than this is integrated as this long
 /// (protects the integrators from a bogus timestamp jump). Long enough to
@@ -136,13 +143,16 @@ pub struct AirbrakesConfig {
     ///   is what actually keeps the flaps shut.
     ///
     /// Per-airframe, and no longer tied to the Mach [`Self::rocket`]'s stowed
-    /// `cd[0]` is tabulated at. Those were required to be equal until
+    /// Cd is tabulated at. Those were required to be equal until
     /// 2026-08-18, on the argument that the check inverts the drag with that
     /// Cd to decide whether it is below this speed. They are now allowed to
     /// differ, and Osiris sets this ABOVE the tabulation Mach (0.83 against a
     /// Cd table taken at 0.8), because the direction of the resulting error
     /// is the safe one and the control window it buys is worth ~38 m of
-    /// apogee authority.
+    /// apogee authority. Now that the table has a Mach axis the check reads
+    /// it AT this value, so a table that reaches this Mach removes the
+    /// mismatch outright; one that stops short holds its top row, and the
+    /// argument below is what covers the gap.
     ///
     /// Why it is safe to set this high: inverting with a Cd measured at a
     /// LOWER Mach than the airframe is actually flying under-reads the drag
@@ -208,15 +218,17 @@ pub struct AirbrakesConfig {
     ///
     /// The drag check needs `Cd * A / m`, and it derives that here rather
     /// than taking it as a number, so the lockout and the apogee
-    /// prediction cannot be given different airframes. It reads `cd[0]`,
-    /// the brakes-stowed entry, and that is what makes the check one-sided:
-    /// the true Cd is higher transonically, so the inverted speed reads
-    /// high exactly while supersonic and the check errs toward keeping the
-    /// lockout shut. Measured on LC'25, inverting the low-passed axial
-    /// channel at the configured crossing altitude, the inverted Mach peaks
-    /// at 1.31 where the truth is 1.03. Projecting onto the axis instead of
-    /// taking `|acc|` costs 2% of that headroom (the magnitude peaks at
-    /// 1.34), which buys the sign the check needs to reject thrust outright.
+    /// prediction cannot be given different airframes. It reads the
+    /// brakes-stowed column at [`Self::max_open_mach`] -- the Cd at the very
+    /// speed the check is deciding about -- and that is what makes the check
+    /// one-sided: the true Cd is higher transonically, above the table's top
+    /// row, so the inverted speed reads high exactly while supersonic and
+    /// the check errs toward keeping the lockout shut. Measured on LC'25,
+    /// inverting the low-passed axial channel at the configured crossing
+    /// altitude, the inverted Mach peaks at 1.31 where the truth is 1.03.
+    /// Projecting onto the axis instead of taking `|acc|` costs 2% of that
+    /// headroom (the magnitude peaks at 1.34), which buys the sign the check
+    /// needs to reject thrust outright.
     pub rocket: RocketParameters,
 }
 
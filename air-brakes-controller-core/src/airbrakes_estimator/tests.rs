@@ -5,7 +5,7 @@ use nalgebra::Vector3;
 use super::*;
 use super::estimator::BARO_RING_SPAN_S;
 use crate::{
-    FlightConfig, FlightEstimators, ImuSample,
+    Atmosphere, ControllerMode, DeploymentPolicy, FlightConfig, FlightEstimators, ImuSample,
     tests::fixtures::{IGNITION_ACC_THRESHOLD, lc25_airbrakes, subsonic_profile},
     tests::init_logger,
 };
@@ -220,7 +220,8 @@ struct ReplayResult {
 }
 
 fn replay(rows: &[Row], config: AirbrakesConfig) -> ReplayResult {
-    let mut estimator = AirbrakesEstimator::new(config, IGNITION_ACC_THRESHOLD);
+    let mut estimator =
+        AirbrakesEstimator::new(config, IGNITION_ACC_THRESHOLD, Atmosphere::standard());
     let mut result = ReplayResult {
         birth: None,
         apogee_i: None,
@@ -234,7 +235,7 @@ fn replay(rows: &[Row], config: AirbrakesConfig) -> ReplayResult {
     };
     let mut subsonic_span_start: Option<f32> = None;
     for (i, z) in rows.iter().enumerate() {
-        estimator.update(z.timestamp_us, &z.imu, z.altitude_asl);
+        estimator.update(z.timestamp_us, &z.imu, z.altitude_asl, None);
 
         let now = t_s(rows, i);
         match (estimator.burnout_detected(), result.burnout_s) {
@@ -648,9 +649,13 @@ fn lc25_clipped_accel_replay() {
 fn short_pad_refuses_ignition() {
     init_logger();
     let rows = lc25_rows();
-    let mut estimator = AirbrakesEstimator::new(lc25_config(), IGNITION_ACC_THRESHOLD);
+    let mut estimator = AirbrakesEstimator::new(
+        lc25_config(),
+        IGNITION_ACC_THRESHOLD,
+        Atmosphere::standard(),
+    );
     for (i, z) in rows.iter().enumerate() {
-        estimator.update(z.timestamp_us, &z.imu, z.altitude_asl);
+        estimator.update(z.timestamp_us, &z.imu, z.altitude_asl, None);
         assert!(
             !estimator.calibration_complete(),
             "calibration claimed complete at t={:.1}s on a 1.8 s pad",
@@ -693,7 +698,7 @@ fn a_knock_on_the_pad_does_not_latch_ignition() {
     // against a 4 g threshold, so the number it argues from stays here.
     const THRESHOLD: f32 = 4.0 * 9.81;
 
-    let mut estimator = AirbrakesEstimator::new(lc25_config(), THRESHOLD);
+    let mut estimator = AirbrakesEstimator::new(lc25_config(), THRESHOLD, Atmosphere::standard());
     let gyro = Vector3::zeros();
 
     // Phase 1: 10 s of quiet rail, enough for >= 3 screened 2 s windows.
@@ -703,7 +708,7 @@ fn a_knock_on_the_pad_does_not_latch_ignition() {
             acc: Vector3::new(0.0, 0.0, 9.81),
             gyro,
         };
-        estimator.update(t_us, &imu, 200.0);
+        estimator.update(t_us, &imu, 200.0, None);
         t_us += dt_us;
     }
     assert!(
@@ -725,7 +730,7 @@ fn a_knock_on_the_pad_does_not_latch_ignition() {
         } else {
             Vector3::new(0.0, 0.0, 9.81)
         };
-        estimator.update(t_us, &ImuSample { acc, gyro }, 200.0);
+        estimator.update(t_us, &ImuSample { acc, gyro }, 200.0, None);
         t_us += dt_us;
 
         // 0.0159 s is `ignition_detector::LP_TAU_S`.
@@ -820,12 +825,15 @@ fn airbrakes_half_retires_at_apogee_and_stays_retired() {
         ignition_detection_acc_threshold: IGNITION_ACC_THRESHOLD,
         profile: subsonic_profile(),
         airbrakes: lc25_airbrakes(),
+        controller_mode: ControllerMode::TargetApogee,
+        deployment_policy: DeploymentPolicy::PrimaryOnly,
+        staging: None,
     });
 
     let mut retired_i: Option<usize> = None;
     let mut last_mpc_states_i: Option<usize> = None;
     for (i, z) in rows.iter().enumerate() {
-        let (_pyro, log) = est.update(z.timestamp_us, Some(&z.imu), z.altitude_asl);
+        let (_pyro, _staging, log) = est.update(z.timestamp_us, Some(&z.imu), &[Some(z.altitude_asl)], None, None);
 
         // The log sample is built after retirement, so the airbrakes group
         // goes absent on the SAME sample the half is dropped — no record
@@ -915,7 +923,7 @@ fn drag_check_run(
     // Scaling `cd` scales `Cd*A/m`, which is the only thing the check takes
     // from the airframe. `v` goes as `1/sqrt(k)`, so a k that is too HIGH
     // reads the speed LOW and calls subsonic early — the unsafe direction.
-    for c in config.rocket.cd.iter_mut() {
+    for c in config.rocket.cd.iter_mut().flatten() {
         *c *= cd_scale;
     }
     let result = replay(rows, config);
@@ -1147,7 +1155,7 @@ fn forced_birth_backstop_flies_the_rest_of_the_flight() {
     let ign_s = t_s(&rows, find_ignition(&rows));
 
     let mut config = lc25_config();
-    for c in config.rocket.cd.iter_mut() {
+    for c in config.rocket.cd.iter_mut().flatten() {
         *c *= 0.2;
     }
     let t_max_s = match &config.mach_lockout {
@@ -62,6 +62,22 @@ impl VerticalKF {
         self.x[1]
     }
 
+    /// The innovation a baro altitude would have against the current prior,
+    /// and its expected variance `P[0][0] + R`, for the `debug-internals` log.
+    #[cfg(feature = "debug-internals")]
+    pub fn innovation(&self, corrected_alt_asl: f32) -> (f32, f32) {
+        (
+            corrected_alt_asl - self.x[0],
+            self.p[(0, 0)] + self.r_alt_std * self.r_alt_std,
+        )
+    }
+
+    /// `(P[0][0], P[1][1])`: the altitude and velocity variances.
+    #[cfg(feature = "debug-internals")]
+    pub fn covariance_diagonal(&self) -> (f32, f32) {
+        (self.p[(0, 0)], self.p[(1, 1)])
+    }
+
     /// Predict over measured `dt` with the earth-frame vertical linear
     /// acceleration (gravity already removed by the dead reckoner).
     pub fn predict(&mut self, accel_up: f32, dt: f32) {
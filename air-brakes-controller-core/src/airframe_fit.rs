@@ -0,0 +1,718 @@
+//! Post-flight identification of the airframe: the cd table
+//! [`RocketParameters`] flies, and the thrust curve the motor delivered,
+//! solved from a smoothed flight.
+//!
+//! Both come out of the one channel the lockout exit already reads: the
+//! specific force along the airframe axis. It excludes gravity, so on the
+//! coast it is drag over the burnout mass and nothing else, and in the burn
+//! it is thrust minus drag over the mass of the moment. Neither needs the
+//! trajectory to be integrated or differentiated. What the trajectory —
+//! the [`crate::smoother`]'s, which has the whole flight to go on — is
+//! needed for is the flow: the airspeed is its vertical velocity over the
+//! axis's tilt, and the density and the Mach number follow from its
+//! altitude.
+//!
+//! # The cd table
+//!
+//! `cd(extension, Mach)` in [`RocketParameters`] is bilinear in its twenty
+//! entries: the extension walks a row's five points and the Mach walks the
+//! rows, each sample putting its weight on at most four cells. So every
+//! coast sample is one linear equation in the table, and the fit is linear
+//! least squares — in the deceleration each sample measured, not in a cd
+//! inverted from it, so a sample at low dynamic pressure, where one m/s^2
+//! of vibration is a large cd, counts for as little as it says.
+//!
+//! A flight flies a path through the table, not the table. The flaps sit at
+//! the few extensions the MPC chose, and Mach only falls, so most cells see
+//! nothing. Each entry is therefore pulled toward the table being refined,
+//! with a tenth of its value as the prior's spread — the same belief
+//! [`AirbrakesEstimator`](crate::airbrakes_estimator::AirbrakesEstimator)'s
+//! drag fit starts from. A cell with flight under it moves to the flight; a
+//! cell without keeps its prior value exactly, and [`CellFit::support`]
+//! says which is which.
+//!
+//! Samples above the table's top Mach are left out. The table holds its top
+//! row past it, so a transonic coast would otherwise drag that row up to a
+//! drag rise the brakes are never opened in. They are not wasted: with the
+//! flaps stowed, every coast sample is also one point of the stowed cd
+//! against Mach, binned into [`DragFit::stowed_cd`] the way
+//! `osiris_cd.csv` bins OpenRocket's, over the whole Mach range flown —
+//! the curve the simulator's airframe reads. Samples where the table
+//! predicts less than [`FitConfig::min_drag`] are left out for the reason
+//! the in-flight fit leaves them out: near apogee the airspeed is mostly
+//! tilt, and the deceleration is mostly noise.
+//!
+//! # The thrust curve
+//!
+//! Thrust is the axial specific force times the mass, plus the drag the
+//! fitted table puts on the stowed airframe — or, above the table, the
+//! stowed curve the coast measured on its way back down through the same
+//! Mach. The mass is the burnout mass plus the propellant still unburnt,
+//! which burns off in proportion to the impulse delivered — the
+//! simulator's `Motor` model. That needs the impulse it is solving for, so
+//! the curve is solved a few times over, each pass taking its masses from
+//! the last pass's curve.
+//!
+//! The curve starts where the accelerometer first saw the motor move the
+//! rocket. Thrust below the liftoff weight is invisible to it — standing on
+//! the pad, the rocket reads 1 g whatever the motor is doing — so a
+//! measured curve starts on its way up, not from zero. It ends where
+//! burnout latches, the first of a sustained run of deceleration, exactly
+//! as the airbrakes estimator latches it; a second burn is not modelled.
+//!
+//! Wind is not modelled either. The airspeed is the trajectory's speed
+//! along the axis, which is the airspeed in still air and reads a headwind
+//! as a lower cd. Fit calm flights.
+
+use core::fmt;
+
+use nalgebra::{SMatrix, SVector};
+
+use crate::airbrakes_estimator::{BURNOUT_DECEL_M_S2, BURNOUT_SUSTAIN_S};
+use crate::smoother::{PAD_WINDOW_S, SmoothedState};
+use crate::utils::interpolate;
+use crate::{Atmosphere, CD_MACH_POINTS, RocketParameters};
+
+/// Points in a cd row: 0, 25, 50, 75 and 100% extension.
+const EXTENSION_POINTS: usize = 5;
+const CELLS: usize = CD_MACH_POINTS * EXTENSION_POINTS;
+
+/// A cell whose coast samples' interpolation weights add up to less than
+/// this is reported as resting on the prior. At the fast rate it is a
+/// quarter of a second of flight spent on the cell.
+pub const MIN_CELL_SUPPORT: f32 = 100.0;
+
+/// Fewer coast samples than this are not a fit.
+const MIN_COAST_SAMPLES: usize = 100;
+
+/// First motion is the axial specific force this far above its pad level
+/// (m/s^2): the burnout latch's margin, the other way up.
+const FIRST_MOTION_M_S2: f32 = BURNOUT_DECEL_M_S2;
+
+/// Width of the Mach bins the stowed curve is averaged in, centred on its
+/// multiples — `osiris_cd.csv`'s.
+const STOWED_BIN_MACH: f32 = 0.05;
+/// Fewer samples than this in a Mach bin leave it out of the stowed curve.
+const MIN_STOWED_BIN_SAMPLES: usize = 20;
+/// Flaps this close to stowed count as stowed.
+const STOWED_EXTENSION: f32 = 0.01;
+
+/// Passes over the burn: each takes its masses from the last one's impulse.
+/// The mass's share of the thrust is a few percent, and its error shrinks
+/// by that factor every pass.
+const THRUST_PASSES: usize = 4;
+
+/// One row of a smoothed flight, as the fit reads it.
+#[derive(Debug, Clone)]
+pub struct FitSample {
+    pub timestamp_us: u64,
+    pub state: SmoothedState,
+    /// The flaps' position, 0.0 (stowed) - 1.0 (full).
+    pub extension: f32,
+}
+
+/// What the fit starts from.
+#[derive(Debug, Clone)]
+pub struct FitConfig {
+    /// The airframe: its burnout mass, reference area and Mach breakpoints
+    /// are the fit's, and its cd table is the prior.
+    pub rocket: RocketParameters,
+    /// Propellant mass (kg), burnt off over the burn.
+    pub propellant_mass: f32,
+    pub atmosphere: Atmosphere,
+    /// Prior 1-sigma of each cd entry, as a fraction of it.
+    pub prior_fraction: f32,
+    /// Std of one axial sample against the drag the table models (m/s^2):
+    /// vibration, mostly.
+    pub axial_sigma: f32,
+    /// No coast sample where the prior table predicts less drag (m/s^2).
+    pub min_drag: f32,
+    /// No coast sample with the axis's tilt cosine below this: the airspeed
+    /// is the vertical velocity divided by it.
+    pub min_tilt_cos: f32,
+    /// Width of the bins the thrust curve is averaged over (s).
+    pub thrust_bin_s: f32,
+}
+
+impl FitConfig {
+    /// A prior believed to a tenth, as the in-flight drag fit believes its
+    /// table; a sample believed to 1 m/s^2, its axial noise; nothing under
+    /// 1 m/s^2 of drag or past 60 degrees of tilt; and the thrust in
+    /// 50 ms bins, which keeps a motor's start-up spike and smooths its
+    /// chuffing.
+    pub fn new(rocket: RocketParameters, propellant_mass: f32, atmosphere: Atmosphere) -> Self {
+        Self {
+            rocket,
+            propellant_mass,
+            atmosphere,
+            prior_fraction: 0.1,
+            axial_sigma: 1.0,
+            min_drag: 1.0,
+            min_tilt_cos: 0.5,
+            thrust_bin_s: 0.05,
+        }
+    }
+}
+
+/// One entry of the fitted table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellFit {
+    pub cd: f32,
+    /// The value it was pulled toward.
+    pub prior: f32,
+    /// 1-sigma. Counts every sample as independent, which the vibration
+    /// that dominates them is not, so it is a floor on the error rather
+    /// than the error.
+    pub sigma: f32,
+    /// The coast samples' interpolation weights on this cell, summed: how
+    /// many samples' worth of flight it was fitted to.
+    pub support: f32,
+}
+
+impl CellFit {
+    /// Whether the flight, rather than the prior, says what this cell is.
+    pub fn from_flight(&self) -> bool {
+        self.support >= MIN_CELL_SUPPORT
+    }
+}
+
+/// The fitted table, and how well it explains the coast.
+#[derive(Debug, Clone)]
+pub struct DragFit {
+    /// [`FitConfig::rocket`] with the fitted table.
+    pub rocket: RocketParameters,
+    /// `cells[row][column]`, laid out as [`RocketParameters::cd`].
+    pub cells: [[CellFit; EXTENSION_POINTS]; CD_MACH_POINTS],
+    /// Coast samples fitted to.
+    pub samples: usize,
+    /// Coast samples left out above the table's top Mach.
+    pub above_table: usize,
+    /// Mach range the fitted samples span.
+    pub mach_range: (f32, f32),
+    /// RMS of measured minus modelled deceleration over the fitted samples
+    /// (m/s^2), with the fitted table and with the prior.
+    pub residual_rms: f32,
+    pub prior_residual_rms: f32,
+    /// `(mach, cd)` with the flaps stowed, strictly increasing in Mach: the
+    /// coast's measured cd averaged in each Mach bin it spent long enough
+    /// in, as `Airframe::load_stowed_cd` reads a `mach,cd` CSV.
+    pub stowed_cd: Vec<(f32, f32)>,
+}
+
+/// The thrust the motor delivered.
+#[derive(Debug, Clone)]
+pub struct ThrustFit {
+    /// `(time_s, thrust_n)` from first motion, one point per bin at its
+    /// samples' mean time, ending on zero at burnout: a RASP curve.
+    pub curve: Vec<(f32, f32)>,
+    pub total_impulse: f32,
+    pub burn_time: f32,
+    pub peak: f32,
+    /// Burn samples without an axial reading — clipped, mostly, which
+    /// leaves the bins they fell in reading low.
+    pub missing: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AirframeFit {
+    pub drag: DragFit,
+    pub thrust: ThrustFit,
+    /// Sample indices of first motion, burnout and apogee.
+    pub first_motion: usize,
+    pub burnout: usize,
+    pub apogee: usize,
+}
+
+/// Why a flight cannot be fitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitError {
+    /// `liftoff` is not a sample index.
+    Liftoff { liftoff: usize, samples: usize },
+    /// The prior table has an entry that is not a positive number.
+    BadPrior,
+    /// The axial channel never stayed decelerating: no burnout, no coast.
+    NoBurnout,
+    /// Too few coast samples survived the cuts to fit to.
+    NoCoast { samples: usize },
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Liftoff { liftoff, samples } => {
+                write!(f, "liftoff at sample {liftoff}, but there are {samples}")
+            }
+            Self::BadPrior => write!(f, "the prior cd table has an entry that is not positive"),
+            Self::NoBurnout => write!(
+                f,
+                "the axial acceleration never stayed below -{BURNOUT_DECEL_M_S2} m/s^2 for \
+                 {BURNOUT_SUSTAIN_S} s: no burnout to start the coast from"
+            ),
+            Self::NoCoast { samples } => write!(
+                f,
+                "{samples} coast sample(s) under the table's top Mach with drag to measure; \
+                 at least {MIN_COAST_SAMPLES} are needed"
+            ),
+        }
+    }
+}
+
+/// The weight each table entry carries in `cd(extension, mach)`, flattened
+/// row by row: the interpolation [`RocketParameters::cda_over_mass`] does,
+/// written as the linear function of the table it is. The extension walks a
+/// row as [`crate::lerp`] does, and the Mach is held at the end rows.
+fn cell_weights(rocket: &RocketParameters, extension: f32, mach: f32) -> SVector<f64, CELLS> {
+    let e = extension.clamp(0.0, 1.0) * (EXTENSION_POINTS - 1) as f32;
+    let column = (e as usize).min(EXTENSION_POINTS - 2);
+    let te = e - column as f32;
+
+    let m = &rocket.cd_mach;
+    let mach = mach.clamp(m[0], m[CD_MACH_POINTS - 1]);
+    let mut row = 0;
+    while row + 2 < CD_MACH_POINTS && mach > m[row + 1] {
+        row += 1;
+    }
+    let tm = (mach - m[row]) / (m[row + 1] - m[row]);
+
+    let mut weights = SVector::zeros();
+    for (r, wr) in [(row, 1.0 - tm), (row + 1, tm)] {
+        for (c, wc) in [(column, 1.0 - te), (column + 1, te)] {
+            weights[r * EXTENSION_POINTS + c] += (wr * wc) as f64;
+        }
+    }
+    weights
+}
+
+fn flatten(cd: &[[f32; EXTENSION_POINTS]; CD_MACH_POINTS]) -> SVector<f64, CELLS> {
+    SVector::from_iterator(cd.iter().flatten().map(|&c| c as f64))
+}
+
+/// Impulse (N s) a RASP curve has delivered by `t`, from an implied zero
+/// at t = 0.
+fn impulse_until(curve: &[(f32, f32)], t: f32) -> f32 {
+    let mut impulse = 0.0;
+    let mut last = (0.0f32, 0.0f32);
+    for &(pt, pf) in curve {
+        if t <= last.0 {
+            return impulse;
+        }
+        if t < pt {
+            let f = last.1 + (pf - last.1) * (t - last.0) / (pt - last.0);
+            return impulse + 0.5 * (last.1 + f) * (t - last.0);
+        }
+        impulse += 0.5 * (last.1 + pf) * (pt - last.0);
+        last = (pt, pf);
+    }
+    impulse
+}
+
+/// Fit `samples`, one smoothed flight in time order, with the rocket leaving
+/// the rail at sample `liftoff`.
+pub fn fit(
+    samples: &[FitSample],
+    liftoff: usize,
+    config: &FitConfig,
+) -> Result<AirframeFit, FitError> {
+    if liftoff >= samples.len() {
+        return Err(FitError::Liftoff {
+            liftoff,
+            samples: samples.len(),
+        });
+    }
+    let prior = &config.rocket;
+    if prior
+        .cd
+        .iter()
+        .flatten()
+        .any(|c| !(c.is_finite() && *c > 0.0))
+    {
+        return Err(FitError::BadPrior);
+    }
+    let seconds = |i: usize| samples[i].timestamp_us as f64 * 1e-6;
+    let axial = |i: usize| samples[i].state.axial_specific_force;
+
+    // First motion: back from liftoff while the axial channel is above its
+    // pad level, which is gravity along the rail.
+    let pad: Vec<f32> = (0..liftoff)
+        .filter(|&i| seconds(i) >= seconds(liftoff) - PAD_WINDOW_S as f64)
+        .filter_map(axial)
+        .collect();
+    let pad_level = if pad.is_empty() {
+        9.80665
+    } else {
+        pad.iter().sum::<f32>() / pad.len() as f32
+    };
+    let mut first_motion = liftoff;
+    while first_motion > 0
+        && axial(first_motion - 1).is_some_and(|a| a > pad_level + FIRST_MOTION_M_S2)
+    {
+        first_motion -= 1;
+    }
+
+    // Burnout: the first of a sustained run of deceleration. A sample
+    // without a reading neither extends the run nor breaks it.
+    let mut run: Option<usize> = None;
+    let mut burnout = None;
+    for i in first_motion..samples.len() {
+        let Some(a) = axial(i) else { continue };
+        if a > -BURNOUT_DECEL_M_S2 {
+            run = None;
+            continue;
+        }
+        let first = *run.get_or_insert(i);
+        if seconds(i) - seconds(first) >= BURNOUT_SUSTAIN_S as f64 {
+            burnout = Some(first);
+            break;
+        }
+    }
+    let burnout = burnout.ok_or(FitError::NoBurnout)?;
+    let apogee = (burnout..samples.len())
+        .max_by(|&a, &b| {
+            samples[a]
+                .state
+                .altitude_asl
+                .total_cmp(&samples[b].state.altitude_asl)
+        })
+        .unwrap_or(burnout);
+
+    // The flow on a sample: dynamic pressure times area over burnout mass
+    // (so that times cd is a deceleration), and Mach.
+    let flow = |state: &SmoothedState| {
+        let airspeed = state.vertical_velocity.max(0.0) / state.tilt_cos.max(config.min_tilt_cos);
+        let q = 0.5 * config.atmosphere.air_density(state.altitude_asl) * airspeed * airspeed;
+        let mach = airspeed / config.atmosphere.speed_of_sound(state.altitude_asl);
+        (q * prior.reference_area / prior.burnout_mass, mach)
+    };
+
+    // The coast, as normal equations: one row per sample, the flattened
+    // table's weights scaled to a deceleration.
+    let prior_cd = flatten(&prior.cd);
+    let mut normal = SMatrix::<f64, CELLS, CELLS>::zeros();
+    let mut rhs = SVector::<f64, CELLS>::zeros();
+    let mut support = SVector::<f64, CELLS>::zeros();
+    let (mut yy, mut used, mut above_table) = (0.0f64, 0usize, 0usize);
+    let mut mach_range = (f32::INFINITY, f32::NEG_INFINITY);
+    let mut stowed_bins: Vec<(f64, usize)> = Vec::new();
+    for sample in &samples[burnout..=apogee] {
+        let state = &sample.state;
+        let Some(a) = state.axial_specific_force.filter(|a| a.is_finite()) else {
+            continue;
+        };
+        if state.tilt_cos < config.min_tilt_cos || state.vertical_velocity <= 0.0 {
+            continue;
+        }
+        let (scale, mach) = flow(state);
+        let weights = cell_weights(prior, sample.extension, mach);
+        let row = weights * scale as f64;
+        // Judged on the drag the prior predicts, not the drag measured, so
+        // that the noise on a sample cannot select it.
+        let predicted = row.dot(&prior_cd);
+        if predicted.is_nan() || predicted < config.min_drag as f64 {
+            continue;
+        }
+        let y = -a as f64;
+        if sample.extension <= STOWED_EXTENSION {
+            let bin = (mach / STOWED_BIN_MACH).round() as usize;
+            if stowed_bins.len() <= bin {
+                stowed_bins.resize(bin + 1, (0.0, 0));
+            }
+            stowed_bins[bin].0 += y / scale as f64;
+            stowed_bins[bin].1 += 1;
+        }
+        if mach > prior.cd_mach[CD_MACH_POINTS - 1] {
+            above_table += 1;
+            continue;
+        }
+        normal += row * row.transpose();
+        rhs += row * y;
+        support += weights;
+        yy += y * y;
+        used += 1;
+        mach_range = (mach_range.0.min(mach), mach_range.1.max(mach));
+    }
+    if used < MIN_COAST_SAMPLES {
+        return Err(FitError::NoCoast { samples: used });
+    }
+
+    let noise = (config.axial_sigma as f64).powi(2);
+    let prior_precision =
+        prior_cd.map(|c| 1.0 / (config.prior_fraction as f64 * c).max(1e-3).powi(2));
+    let information = normal / noise + SMatrix::from_diagonal(&prior_precision);
+    let chol = information.cholesky().ok_or(FitError::BadPrior)?;
+    let cd = chol.solve(&(rhs / noise + prior_precision.component_mul(&prior_cd)));
+    let covariance = chol.inverse();
+    // Sum of squared residuals without keeping the rows: |y - Jc|^2.
+    let residual_rms = |c: &SVector<f64, CELLS>| {
+        let sum = yy - 2.0 * c.dot(&rhs) + c.dot(&(normal * c));
+        (sum.max(0.0) / used as f64).sqrt() as f32
+    };
+
+    let mut fitted = prior.clone();
+    let mut cells = [[CellFit {
+        cd: 0.0,
+        prior: 0.0,
+        sigma: 0.0,
+        support: 0.0,
+    }; EXTENSION_POINTS]; CD_MACH_POINTS];
+    for r in 0..CD_MACH_POINTS {
+        for c in 0..EXTENSION_POINTS {
+            let k = r * EXTENSION_POINTS + c;
+            fitted.cd[r][c] = cd[k] as f32;
+            cells[r][c] = CellFit {
+                cd: cd[k] as f32,
+                prior: prior.cd[r][c],
+                sigma: covariance[(k, k)].max(0.0).sqrt() as f32,
+                support: support[k] as f32,
+            };
+        }
+    }
+    let drag = DragFit {
+        rocket: fitted,
+        cells,
+        samples: used,
+        above_table,
+        mach_range,
+        residual_rms: residual_rms(&cd),
+        prior_residual_rms: residual_rms(&prior_cd),
+        stowed_cd: stowed_bins
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, n))| *n >= MIN_STOWED_BIN_SAMPLES)
+            .map(|(bin, &(sum, n))| (bin as f32 * STOWED_BIN_MACH, (sum / n as f64) as f32))
+            .collect(),
+    };
+
+    // The burn: thrust is mass times the axial channel plus the drag the
+    // fitted table puts on the airframe, in bins of measured time.
+    let t0 = seconds(first_motion);
+    let burn_time = (seconds(burnout) - t0) as f32;
+    let mut burn = Vec::with_capacity(burnout - first_motion);
+    let mut missing = 0;
+    for sample in &samples[first_motion..burnout] {
+        let Some(a) = sample.state.axial_specific_force else {
+            missing += 1;
+            continue;
+        };
+        let (scale, mach) = flow(&sample.state);
+        let cd_here = if mach > prior.cd_mach[CD_MACH_POINTS - 1] && !drag.stowed_cd.is_empty() {
+            interpolate(&drag.stowed_cd, mach)
+        } else {
+            cell_weights(prior, sample.extension, mach).dot(&cd) as f32
+        };
+        let drag_n = scale * prior.burnout_mass * cd_here;
+        let t = (sample.timestamp_us as f64 * 1e-6 - t0) as f32;
+        burn.push((t, a, drag_n));
+    }
+    let bins = ((burn_time / config.thrust_bin_s).ceil() as usize).max(1);
+    let mut curve: Vec<(f32, f32)> = Vec::new();
+    for _ in 0..THRUST_PASSES {
+        // The first pass has no curve to go on and takes half the
+        // propellant as burnt throughout.
+        let total = impulse_until(&curve, burn_time);
+        let mut sums = vec![(0.0f64, 0.0f64, 0usize); bins];
+        for &(t, a, drag_n) in &burn {
+            let burnt = if total > 0.0 {
+                (impulse_until(&curve, t) / total).clamp(0.0, 1.0)
+            } else {
+                0.5
+            };
+            let mass = prior.burnout_mass + config.propellant_mass * (1.0 - burnt);
+            let bin = ((t / config.thrust_bin_s) as usize).min(bins - 1);
+            sums[bin].0 += t as f64;
+            sums[bin].1 += (mass * a + drag_n) as f64;
+            sums[bin].2 += 1;
+        }
+        curve = sums
+            .iter()
+            .filter(|(_, _, n)| *n > 0)
+            .map(|&(t, f, n)| ((t / n as f64) as f32, ((f / n as f64) as f32).max(0.0)))
+            .collect();
+        curve.push((burn_time, 0.0));
+    }
+    let thrust = ThrustFit {
+        total_impulse: impulse_until(&curve, burn_time),
+        peak: curve.iter().map(|&(_, f)| f).fold(0.0, f32::max),
+        burn_time,
+        curve,
+        missing,
+    };
+
+    Ok(AirframeFit {
+        drag,
+        thrust,
+        first_motion,
+        burnout,
+        apogee,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT_US: u64 = 2404;
+    const BURNOUT_MASS: f32 = 20.0;
+    const PROPELLANT: f32 = 4.0;
+    const THRUST: f32 = 3000.0;
+    const BURN_S: f64 = 2.0;
+    const COAST_S: f64 = 15.0;
+
+    /// cd rising 0.05 a Mach row and 0.1 a quarter of extension.
+    fn truth() -> RocketParameters {
+        let mut cd = [[0.0; EXTENSION_POINTS]; CD_MACH_POINTS];
+        for (r, row) in cd.iter_mut().enumerate() {
+            for (c, entry) in row.iter_mut().enumerate() {
+                *entry = 0.5 + 0.05 * r as f32 + 0.1 * c as f32;
+            }
+        }
+        RocketParameters {
+            burnout_mass: BURNOUT_MASS,
+            cd_mach: [0.3, 0.5, 0.7, 0.9],
+            cd,
+            reference_area: 0.02,
+        }
+    }
+
+    fn cd(rocket: &RocketParameters, extension: f32, mach: f32) -> f32 {
+        rocket.cda_over_mass(extension, mach) * rocket.burnout_mass / rocket.reference_area
+    }
+
+    /// A vertical flight through [`truth`]: 2 s on the pad, a 3000 N burn
+    /// with the propellant burning off evenly, and a coast decelerating
+    /// evenly from 250 m/s to apogee with the flaps stepping through 0, 50
+    /// and 100%. Only the axial channel has to be consistent with the
+    /// drag; the fit never integrates the trajectory.
+    fn flight() -> (Vec<FitSample>, usize) {
+        let rocket = truth();
+        let atmosphere = Atmosphere::standard();
+        let dt = DT_US as f64 * 1e-6;
+        let liftoff = (2.0 / dt) as usize;
+        let count = liftoff + ((BURN_S + COAST_S) / dt) as usize;
+        let samples = (0..count)
+            .map(|i| {
+                let t = (i as f64 - liftoff as f64) * dt;
+                let (altitude, velocity, extension) = if t < 0.0 {
+                    (1000.0, 0.0, 0.0)
+                } else if t < BURN_S {
+                    (1000.0 + 62.5 * t * t, 125.0 * t, 0.0)
+                } else {
+                    let s = t - BURN_S;
+                    let step = [0.0, 0.5, 1.0][(s / 0.5) as usize % 3];
+                    (
+                        1250.0 + 250.0 * s - 250.0 / COAST_S / 2.0 * s * s,
+                        250.0 - 250.0 / COAST_S * s,
+                        step,
+                    )
+                };
+                let (altitude, velocity, extension) =
+                    (altitude as f32, velocity as f32, extension as f32);
+                let mach = velocity / atmosphere.speed_of_sound(altitude);
+                let drag = 0.5
+                    * atmosphere.air_density(altitude)
+                    * velocity
+                    * velocity
+                    * rocket.reference_area
+                    * cd(&rocket, extension, mach);
+                let axial = if t < 0.0 {
+                    9.81
+                } else if t < BURN_S {
+                    let mass = BURNOUT_MASS + PROPELLANT * (1.0 - (t / BURN_S) as f32);
+                    (THRUST - drag) / mass
+                } else {
+                    -drag / BURNOUT_MASS
+                };
+                FitSample {
+                    timestamp_us: 1_000_000 + i as u64 * DT_US,
+                    state: SmoothedState {
+                        altitude_asl: altitude,
+                        vertical_velocity: velocity,
+                        vertical_acceleration: 0.0,
+                        altitude_sigma: 0.0,
+                        velocity_sigma: 0.0,
+                        acceleration_sigma: 0.0,
+                        axial_specific_force: Some(axial),
+                        tilt_cos: 1.0,
+                    },
+                    extension,
+                }
+            })
+            .collect();
+        (samples, liftoff)
+    }
+
+    /// The weights are the table's own interpolation: dotted with the
+    /// flattened table they give the cd `RocketParameters` flies.
+    #[test]
+    fn the_weights_interpolate_as_the_table_does() {
+        let rocket = truth();
+        let table = flatten(&rocket.cd);
+        for extension in [0.0, 0.1, 0.25, 0.6, 0.99, 1.0, 1.2] {
+            for mach in [0.1, 0.3, 0.45, 0.7, 0.85, 1.5] {
+                let weights = cell_weights(&rocket, extension, mach);
+                assert!((weights.sum() - 1.0).abs() < 1e-6);
+                let expected = cd(&rocket, extension, mach);
+                let got = weights.dot(&table) as f32;
+                assert!(
+                    (got - expected).abs() < 1e-5,
+                    "cd({extension}, {mach}): {got} vs {expected}"
+                );
+            }
+        }
+    }
+
+    /// From a prior 20% heavy, the cells the coast flew come back to the
+    /// truth and the ones it did not stay at the prior; the burn comes back
+    /// as the 3000 N it was, to within what the bins round off its ends.
+    #[test]
+    fn a_clean_flight_gives_back_its_table_and_its_thrust() {
+        let (samples, liftoff) = flight();
+        let prior = truth().with_cd_scale(1.2);
+        let config = FitConfig::new(prior.clone(), PROPELLANT, Atmosphere::standard());
+        let fit = fit(&samples, liftoff, &config).unwrap();
+
+        assert_eq!(fit.first_motion, liftoff);
+        let burn = fit.thrust.burn_time as f64;
+        assert!((burn - BURN_S).abs() < 0.01, "burn {burn} s");
+        let truth = truth();
+        for r in 0..3 {
+            for c in [0, 2, 4] {
+                let cell = fit.drag.cells[r][c];
+                assert!(cell.from_flight(), "[{r}][{c}] support {}", cell.support);
+                let error = cell.cd / truth.cd[r][c] - 1.0;
+                assert!(
+                    error.abs() < 0.015,
+                    "[{r}][{c}] {} vs {}",
+                    cell.cd,
+                    truth.cd[r][c]
+                );
+            }
+            for c in [1, 3] {
+                let cell = fit.drag.cells[r][c];
+                assert_eq!(cell.support, 0.0);
+                assert!((cell.cd - prior.cd[r][c]).abs() < 1e-5);
+            }
+        }
+        assert!(fit.drag.residual_rms < 0.1 * fit.drag.prior_residual_rms);
+        assert_eq!(fit.drag.above_table, 0);
+
+        let middle = fit.thrust.curve[fit.thrust.curve.len() / 2].1;
+        assert!((middle / THRUST - 1.0).abs() < 0.01, "thrust {middle} N");
+        let impulse = fit.thrust.total_impulse / (THRUST * BURN_S as f32);
+        assert!(
+            (impulse - 1.0).abs() < 0.02,
+            "impulse {impulse} of the truth's"
+        );
+        assert_eq!(fit.thrust.missing, 0);
+    }
+
+    #[test]
+    fn a_flight_that_never_coasts_is_refused() {
+        let (mut samples, liftoff) = flight();
+        samples.truncate(liftoff + (BURN_S * 1e6 / DT_US as f64) as usize);
+        let config = FitConfig::new(truth(), PROPELLANT, Atmosphere::standard());
+        assert_eq!(
+            fit(&samples, liftoff, &config).unwrap_err(),
+            FitError::NoBurnout
+        );
+    }
+}
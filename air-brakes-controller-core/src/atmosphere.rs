@@ -0,0 +1,355 @@
+//! [`Atmosphere`] — the one air model both the estimators and the MPC read,
+//! calibrated on the pad.
+//!
+//! Everything in the flight path used to assume the standard day: the
+//! barometer's altitude is ICAO ISA pressure altitude
+//! (`BaroData::altitude_asl`), and the drag model's density and speed of
+//! sound were ISA at that altitude. The pressure half of that is exact — a
+//! pressure altitude is just a name for a pressure — but the temperature
+//! half is not, and it is wrong in two places at once on a hot pad:
+//!
+//! * **density.** At a given pressure, density goes as 1/T. A 35 C pad is
+//!   ~7% thinner than ISA says, and the MPC charges the coast with 7% too
+//!   much drag.
+//! * **height.** A metre of pressure altitude is T/T_ISA metres of real
+//!   climb. The same 35 C pad stretches the first kilometres above it by
+//!   ~7%, so a target AGL is flown to the wrong pressure altitude span —
+//!   or, read the other way, the apogee the MPC predicts is in the wrong
+//!   unit.
+//!
+//! The model is the troposphere as a straight line in temperature, from the
+//! pad's measured pressure and temperature up to the standard tropopause
+//! (216.65 K at 11 km ASL). The tropopause is the anchor rather than the
+//! standard lapse rate because it is the end that does not move: a hot
+//! afternoon heats the bottom of the column, not the top, so a hot pad
+//! means a steeper lapse rather than the same air shifted warmer. That is
+//! also the launch-day atmosphere OpenRocket simulates, and
+//! `osiris_sim::a_pad_calibrated_atmosphere_matches_the_launch_day` holds
+//! this model to it.
+//!
+//! Everything the flight path evaluates per sample stays a polynomial —
+//! no `powf`, which is what `crate::utils::approximate_air_density`'s
+//! history is about:
+//!
+//! * height above the pad is pressure-altitude height times the stretch
+//!   `T_pad / T_ISA(pad)`, times a short series in the pressure altitude
+//!   that corrects for the lapse rate differing from ISA's (the identity on
+//!   a day that does not);
+//! * density is the binomial series of `(1 - u)^n` in the pad's own
+//!   temperature ratio `u`, with the exponent `n` the lapse rate sets;
+//! * speed of sound is the standard day's linear fit, rebased on
+//!   `sqrt(gamma R T_pad)` and steepened with the lapse rate.
+//!
+//! [`Atmosphere::standard`] is the standard day, and reproduces the ISA
+//! functions in `utils` bit for bit — which is what every test in the tree
+//! that is not about the atmosphere runs on.
+
+use crate::utils::{ISA_DENSITY_SERIES, SERIES_LEN, density_curve, horner, sqrt};
+
+/// Temperature lapse rate of the ISA troposphere (K/m).
+const LAPSE_RATE: f32 = 0.0065;
+/// Specific gas constant of dry air (J/(kg K)).
+const R_AIR: f32 = 287.053;
+const GRAVITY: f32 = 9.80665;
+/// `sqrt(gamma * R_AIR)` for dry air: speed of sound is this times sqrt(T).
+const SOUND_PER_SQRT_KELVIN: f32 = 20.0468;
+const ISA_SEA_LEVEL_TEMPERATURE_K: f32 = 288.15;
+const ISA_SEA_LEVEL_PRESSURE_PA: f32 = 101_325.0;
+/// `R_AIR * LAPSE_RATE / GRAVITY`: the exponent of the troposphere's
+/// pressure law.
+const PRESSURE_EXPONENT: f32 = 0.190_263;
+/// The standard tropopause, where every calibrated temperature line ends.
+const TROPOPAUSE_ALTITUDE_ASL: f32 = 11_000.0;
+const TROPOPAUSE_TEMPERATURE_K: f32 = 216.65;
+/// Bounds on the calibrated lapse rate (K/m). The top is the dry adiabat,
+/// the steepest a column can stand without overturning; the bottom keeps a
+/// cold pad (an inversion, or a thermometer in the shade of a freezer) from
+/// driving the density exponent towards an isothermal infinity.
+const MIN_LAPSE_RATE: f32 = 0.003;
+const MAX_LAPSE_RATE: f32 = 0.0098;
+/// The standard day's speed of sound at sea level (m/s) and its linear fall
+/// with altitude (m/s per m) — [`crate::utils::approximate_speed_of_sound`]'s
+/// fit, steeper than the curve's tangent so that it holds 0.3% to 8 km.
+const ISA_SPEED_OF_SOUND: f32 = 340.29;
+const ISA_SPEED_OF_SOUND_LAPSE: f32 = 0.003903;
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    /// The pad's altitude ASL (m) in the frame every altitude out of this
+    /// model is in.
+    pad_altitude_asl: f32,
+    /// The pad's ISA pressure altitude (m): what the barometer reads there.
+    pad_pressure_altitude: f32,
+    /// `T_pad / T_ISA(pad)`: metres of height per metre of pressure altitude
+    /// at the pad.
+    stretch: f32,
+    /// `LAPSE_RATE / T_ISA(pad)` (1/m): the height series' argument per
+    /// metre of pressure altitude above the pad.
+    pressure_lapse_over_temperature: f32,
+    /// How the stretch changes with height when the lapse rate is not ISA's.
+    height_series: [f32; SERIES_LEN],
+    /// Lapse rate over `T_pad` (1/m): the density series' argument per
+    /// metre above the pad.
+    lapse_over_temperature: f32,
+    /// Density (kg/m^3) in that argument.
+    density_series: [f32; SERIES_LEN],
+    /// Speed of sound at the pad (m/s), and its fall per metre above it.
+    pad_speed_of_sound: f32,
+    speed_of_sound_lapse: f32,
+    /// The pad's static pressure (Pa), air temperature (K) and the lapse
+    /// rate to the tropopause (K/m), for [`Self::pressure`] and
+    /// [`Self::temperature`].
+    pad_pressure: f32,
+    pad_temperature: f32,
+    lapse_rate: f32,
+}
+
+impl Atmosphere {
+    /// The standard day: ISA at sea level, altitudes are pressure altitudes.
+    pub const fn standard() -> Self {
+        let mut height_series = [0.0; SERIES_LEN];
+        height_series[0] = 1.0;
+        Self {
+            pad_altitude_asl: 0.0,
+            pad_pressure_altitude: 0.0,
+            stretch: 1.0,
+            pressure_lapse_over_temperature: 2.25577e-5,
+            height_series,
+            lapse_over_temperature: 2.25577e-5,
+            density_series: ISA_DENSITY_SERIES,
+            pad_speed_of_sound: ISA_SPEED_OF_SOUND,
+            speed_of_sound_lapse: ISA_SPEED_OF_SOUND_LAPSE,
+            pad_pressure: ISA_SEA_LEVEL_PRESSURE_PA,
+            pad_temperature: ISA_SEA_LEVEL_TEMPERATURE_K,
+            lapse_rate: LAPSE_RATE,
+        }
+    }
+
+    /// Calibrate on the pad: its static pressure (Pa) and the air
+    /// temperature there (K).
+    ///
+    /// The temperature is the AIR's — a site thermometer or the weather
+    /// station — and not the barometer die's, which sits in an avionics bay
+    /// that has been in the sun since morning and reads whatever the bay
+    /// does.
+    ///
+    /// `site_elevation_asl` (m) anchors the altitude frame on the surveyed
+    /// pad instead of on the pad's pressure altitude, so altitudes come out
+    /// as real metres ASL. Without it they stay anchored where the barometer
+    /// already reads the pad, and only heights *above* the pad change —
+    /// which is all any AGL number, deployment altitude or apogee target
+    /// ever reads.
+    ///
+    /// Called once, so the one `powf` (pad pressure to pressure altitude)
+    /// is `libm`'s by name.
+    pub fn from_pad(
+        pad_pressure: f32,
+        pad_temperature: f32,
+        site_elevation_asl: Option<f32>,
+    ) -> Self {
+        let pad_pressure_altitude = ISA_SEA_LEVEL_TEMPERATURE_K / LAPSE_RATE
+            * (1.0 - libm::powf(pad_pressure / ISA_SEA_LEVEL_PRESSURE_PA, PRESSURE_EXPONENT));
+        let pad_altitude_asl = site_elevation_asl.unwrap_or(pad_pressure_altitude);
+        let isa_pad_temperature = ISA_SEA_LEVEL_TEMPERATURE_K - LAPSE_RATE * pad_pressure_altitude;
+        let lapse_rate = ((pad_temperature - TROPOPAUSE_TEMPERATURE_K)
+            / (TROPOPAUSE_ALTITUDE_ASL - pad_altitude_asl))
+            .clamp(MIN_LAPSE_RATE, MAX_LAPSE_RATE);
+        let lapse_ratio = lapse_rate / LAPSE_RATE;
+
+        // Height above the pad is `T_pad / L' * (1 - (p / p_pad)^(R L' / g))`,
+        // and the pressure ratio raised to that power is the ISA
+        // temperature ratio to the power `L' / L`. Expanding `(1 - v)^q`
+        // and dividing out the first-order term, which is the stretch,
+        // leaves a series that starts at exactly 1.
+        let power = binomial_series(lapse_ratio);
+        let mut height_series = [0.0; SERIES_LEN];
+        for (h, c) in height_series.iter_mut().zip(&power[1..]) {
+            *h = -c / lapse_ratio;
+        }
+
+        // At a given lapse rate, density goes as the temperature ratio to
+        // `g / (R L') - 1`.
+        let pad_density = pad_pressure / (R_AIR * pad_temperature);
+        let mut density_series = [0.0; SERIES_LEN];
+        let density_power = binomial_series(GRAVITY / (R_AIR * lapse_rate) - 1.0);
+        for (d, c) in density_series.iter_mut().zip(density_power) {
+            *d = pad_density * c;
+        }
+
+        let pad_speed_of_sound = SOUND_PER_SQRT_KELVIN * sqrt(pad_temperature);
+        Self {
+            pad_altitude_asl,
+            pad_pressure_altitude,
+            stretch: pad_temperature / isa_pad_temperature,
+            pressure_lapse_over_temperature: LAPSE_RATE / isa_pad_temperature,
+            height_series,
+            lapse_over_temperature: lapse_rate / pad_temperature,
+            density_series,
+            pad_speed_of_sound,
+            speed_of_sound_lapse: ISA_SPEED_OF_SOUND_LAPSE * lapse_ratio * ISA_SPEED_OF_SOUND
+                / pad_speed_of_sound,
+            pad_pressure,
+            pad_temperature,
+            lapse_rate,
+        }
+    }
+
+    /// Altitude ASL (m) from an ISA pressure altitude (m) — what
+    /// `BaroData::altitude_asl` reports. The identity on the standard day.
+    pub fn altitude_asl(&self, pressure_altitude: f32) -> f32 {
+        let above_pad = pressure_altitude - self.pad_pressure_altitude;
+        self.pad_altitude_asl
+            + self.stretch
+                * above_pad
+                * horner(
+                    &self.height_series,
+                    self.pressure_lapse_over_temperature * above_pad,
+                )
+    }
+
+    /// Air density (kg/m^3) at altitude ASL (m).
+    pub fn air_density(&self, altitude_asl: f32) -> f32 {
+        density_curve(
+            &self.density_series,
+            self.lapse_over_temperature * (altitude_asl - self.pad_altitude_asl),
+        )
+    }
+
+    /// Speed of sound (m/s) at altitude ASL (m).
+    pub fn speed_of_sound(&self, altitude_asl: f32) -> f32 {
+        self.pad_speed_of_sound - (altitude_asl - self.pad_altitude_asl) * self.speed_of_sound_lapse
+    }
+
+    /// Air temperature (K) at altitude ASL (m): the calibrated line, held at
+    /// the tropopause's temperature once it gets there.
+    pub fn temperature(&self, altitude_asl: f32) -> f32 {
+        (self.pad_temperature - self.lapse_rate * (altitude_asl - self.pad_altitude_asl))
+            .max(TROPOPAUSE_TEMPERATURE_K)
+    }
+
+    /// Static pressure (Pa) at altitude ASL (m), exact on the same
+    /// temperature line, and isothermal above it.
+    ///
+    /// The flight asks for it once, on the standard day, to turn the pad's
+    /// pressure altitude back into the pressure it calibrates on — so it is
+    /// `libm`'s `powf` and `expf`. It is also what the simulator's barometer
+    /// reads.
+    pub fn pressure(&self, altitude_asl: f32) -> f32 {
+        let exponent = GRAVITY / (R_AIR * self.lapse_rate);
+        let temperature = self.temperature(altitude_asl);
+        let pressure = self.pad_pressure * libm::powf(temperature / self.pad_temperature, exponent);
+        if temperature > TROPOPAUSE_TEMPERATURE_K {
+            return pressure;
+        }
+        let tropopause_asl = self.pad_altitude_asl
+            + (self.pad_temperature - TROPOPAUSE_TEMPERATURE_K) / self.lapse_rate;
+        pressure
+            * libm::expf(
+                -GRAVITY * (altitude_asl - tropopause_asl).max(0.0)
+                    / (R_AIR * TROPOPAUSE_TEMPERATURE_K),
+            )
+    }
+}
+
+/// The first `SERIES_LEN + 1` coefficients of `(1 - x)^exponent`. Six terms
+/// past the constant leave under 1e-5 at the top of the troposphere
+/// (`x` < 0.3) for any exponent a lapse rate in the clamp range produces.
+fn binomial_series(exponent: f32) -> [f32; SERIES_LEN + 1] {
+    let mut series = [1.0; SERIES_LEN + 1];
+    for k in 1..series.len() {
+        series[k] = series[k - 1] * ((k - 1) as f32 - exponent) / k as f32;
+    }
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{approximate_air_density, approximate_speed_of_sound};
+    use approx::assert_relative_eq;
+
+    /// The standard day is the ISA functions exactly, so nothing that runs
+    /// on it moved when the model went in.
+    #[test]
+    fn standard_is_isa_bit_for_bit() {
+        let atmosphere = Atmosphere::standard();
+        for altitude in [-500.0, 0.0, 363.6, 3000.0, 8359.9, 11000.0] {
+            assert_eq!(atmosphere.altitude_asl(altitude), altitude);
+            assert_eq!(
+                atmosphere.air_density(altitude),
+                approximate_air_density(altitude)
+            );
+            assert_eq!(
+                atmosphere.speed_of_sound(altitude),
+                approximate_speed_of_sound(altitude)
+            );
+        }
+    }
+
+    /// The standard day's pressure is ISA's, so a barometer reading it
+    /// reports the altitude it was read at.
+    #[test]
+    fn standard_pressure_is_the_isa_pressure_altitude() {
+        let atmosphere = Atmosphere::standard();
+        assert_relative_eq!(atmosphere.pressure(0.0), ISA_SEA_LEVEL_PRESSURE_PA);
+        assert_relative_eq!(atmosphere.temperature(11_000.0), TROPOPAUSE_TEMPERATURE_K);
+        for altitude in [363.6, 3000.0, 8359.9, 11_000.0] {
+            let pressure = atmosphere.pressure(altitude);
+            let pressure_altitude = ISA_SEA_LEVEL_TEMPERATURE_K / LAPSE_RATE
+                * (1.0 - libm::powf(pressure / ISA_SEA_LEVEL_PRESSURE_PA, PRESSURE_EXPONENT));
+            assert_relative_eq!(pressure_altitude, altitude, epsilon = 0.5);
+        }
+        // 22632 Pa at 11 km, 12045 Pa at 15 km.
+        assert_relative_eq!(atmosphere.pressure(15_000.0), 12_045.0, max_relative = 2e-3);
+    }
+
+    /// A pad on the standard day calibrates to the standard atmosphere.
+    #[test]
+    fn an_isa_pad_calibrates_to_isa() {
+        // 1500 m pressure altitude, at ISA's temperature there.
+        let atmosphere = Atmosphere::from_pad(84_556.0, 278.4, None);
+        for altitude in [1500.0, 3000.0, 6000.0] {
+            assert_relative_eq!(atmosphere.altitude_asl(altitude), altitude, epsilon = 1.0);
+            assert_relative_eq!(
+                atmosphere.air_density(altitude),
+                approximate_air_density(altitude),
+                max_relative = 1e-3
+            );
+            assert_relative_eq!(
+                atmosphere.speed_of_sound(altitude),
+                approximate_speed_of_sound(altitude),
+                max_relative = 1e-3
+            );
+        }
+    }
+
+    /// 35 C on a sea-level pad: the air there is thinner by T_ISA / T, the
+    /// first metres above it longer by T / T_ISA, and the lapse to the
+    /// tropopause steeper than ISA's — which brings the column back
+    /// towards the standard day with height, but not all the way.
+    #[test]
+    fn a_hot_pad_is_thinner_and_taller() {
+        let atmosphere = Atmosphere::from_pad(ISA_SEA_LEVEL_PRESSURE_PA, 308.15, Some(0.0));
+        let warm = 308.15 / ISA_SEA_LEVEL_TEMPERATURE_K;
+        assert_relative_eq!(
+            atmosphere.altitude_asl(10.0),
+            10.0 * warm,
+            max_relative = 1e-4
+        );
+        assert_relative_eq!(
+            atmosphere.air_density(0.0),
+            approximate_air_density(0.0) / warm,
+            max_relative = 1e-4
+        );
+        let height = atmosphere.altitude_asl(3000.0);
+        assert!(height > 3000.0 && height < 3000.0 * warm, "{height}");
+        let density = atmosphere.air_density(height);
+        assert!(
+            density < approximate_air_density(3000.0)
+                && density > approximate_air_density(3000.0) / warm,
+            "{density}"
+        );
+        assert_relative_eq!(atmosphere.speed_of_sound(0.0), 351.9, epsilon = 0.1);
+    }
+}
@@ -0,0 +1,700 @@
+//! The backup deployment channel: a second opinion on apogee that shares
+//! nothing with [`RocketStateEstimator`](crate::RocketStateEstimator) but
+//! the accelerometer's wire, and the policy that decides what the two
+//! opinions add up to.
+//!
+//! Until this module recovery had exactly one opinion. The deployment half
+//! is barometric from the moment ignition latches, so everything it knows
+//! about apogee comes through the static port — and [`crate::baro_vote`]
+//! only helps where there is a second port to vote with. A board with one
+//! barometer, or a flight on which every port freezes (iced, blocked, a
+//! cracked bay that reads cabin pressure), holds the filtered altitude flat,
+//! the peak-drop test never trips, and no drogue fires. A port that *lies*
+//! — a resync onto a bad reading — fires it early instead.
+//!
+//! Two detectors here, and neither reads a barometer:
+//!
+//! * **Accelerometer-integrated vertical velocity.** The specific force is
+//!   projected onto the pad's "up" — the gravity vector the IMU measured on
+//!   the rail — gravity is taken off, and what is left is integrated twice
+//!   from the pad. Apogee is where the velocity crosses zero. On Osiris that
+//!   crossing lands within 0.03 s of the OpenRocket apogee on both motors:
+//!   the airframe stays within a few degrees of the pad's up for the whole
+//!   climb, so "along the body axis" and "up" are the same number to well
+//!   inside a second. It is the better detector of the two and also the
+//!   fragile one — a clipped accelerometer integrates the burn short, and
+//!   a short burn is an early crossing. Hence
+//!   [`BackupDeploymentConfig::earliest_accel_apogee_us`].
+//! * **Liftoff timer.** A fixed time after this channel's own ignition
+//!   latch — the COTS timer altimeter. Crude, and impossible to lie to: it
+//!   needs nothing after ignition but a clock.
+//!
+//! Neither may call apogee until the channel is **armed**: its own
+//! integrated altitude has passed
+//! [`BackupDeploymentConfig::arming_altitude_agl`]. That is the backup's
+//! version of the deployment half's minimum-apogee check, and it is what
+//! keeps a knock on the rail that happened to latch ignition from ending in
+//! a timer firing a drogue on the pad.
+//!
+//! **Independent in the same way the two halves are.** Its own
+//! [`IgnitionDetector`] instance — same code, same threshold, separate
+//! history — its own clock, its own pad reference. Nothing the deployment
+//! half believes reaches it, and it reaches the pyros only through
+//! [`DeploymentArbiter`], which is where the policy lives.
+
+use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
+use nalgebra::Vector3;
+
+use crate::ignition_detector::IgnitionDetector;
+use crate::utils::sqrt;
+
+/// Length of one pad gravity averaging window (s of measured time). The
+/// windows are handed out one behind, for the reason
+/// `baro_state_estimator::PadReference` gives: at the instant ignition
+/// latches, the gravity the flight is integrated against cannot contain a
+/// single sample taken within a second of the motor lighting.
+const PAD_WINDOW_S: f32 = 1.0;
+
+/// Time constant (s) of the leak on the pad integrators.
+///
+/// On the pad the integration runs, but leaks back toward rest, so that
+/// the first tenths of a second of the burn — before ignition has latched
+/// — are already in the velocity when it does. Without it the channel would
+/// start integrating ~0.15 s late and miss the ~20 m/s the O3400 puts on
+/// the airframe in that time, which is two seconds of early apogee. With
+/// it, rail sway and noise settle to millimetres per second, and the burn
+/// loses ~3% of what it put in before the latch.
+const PAD_LEAK_TAU_S: f32 = 5.0;
+
+/// The backup channel's configuration. Only the non-primary policies carry
+/// one — see [`DeploymentPolicy`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupDeploymentConfig {
+    /// The liftoff timer: apogee is called this long after this channel's
+    /// ignition latch, whatever the accelerometer says.
+    ///
+    /// Set it past the latest apogee any motor the airframe might fly could
+    /// reach — it is the backstop, not the estimate. On Osiris that is the
+    /// O3400's 39.6 s with margin; the N2900 reaches apogee two seconds
+    /// earlier, and the timer must not care which motor is in the case.
+    pub apogee_timer_us: u32,
+    /// The accelerometer's zero crossing is ignored before this long after
+    /// ignition.
+    ///
+    /// The way the integration fails in practice makes its crossing early:
+    /// an accelerometer clipped mid-burn leaves burn out of the velocity,
+    /// and nothing after it puts it back. So its crossing is
+    /// floored well after burnout and well before the earliest honest
+    /// apogee, and a crossing before it is not an apogee call at all.
+    pub earliest_accel_apogee_us: u32,
+    /// Neither detector may call apogee until the channel's own integrated
+    /// altitude has passed this (m above the pad).
+    pub arming_altitude_agl: f32,
+}
+
+/// How the deployment half's drogue call and the backup channel's combine
+/// into the one drogue command.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeploymentPolicy {
+    /// The deployment half alone, passed through untouched: no backup
+    /// channel is even built. What every flight flew before the backup
+    /// existed.
+    PrimaryOnly,
+    /// The drogue fires on the first of the deployment half's drogue, the
+    /// accelerometer's apogee and the liftoff timer. A dead barometer costs
+    /// nothing; a lying one fires early, because nothing here can tell a lie
+    /// from an early apogee.
+    FirstOf(BackupDeploymentConfig),
+    /// The drogue fires once the deployment half's drogue and the
+    /// accelerometer's apogee have both been called within `window_us` of
+    /// each other, on the sample the second one arrives — or at the liftoff
+    /// timer, if they never do. A lying barometer then costs a drogue at the
+    /// timer rather than one at speed; a dead one costs the same.
+    ///
+    /// Agreement needs a channel that can vote. One that stood down on the
+    /// pad, or has not armed by the time the deployment half calls its
+    /// drogue, will call neither apogee nor the timer, so the deployment
+    /// half's drogue is fired as it comes instead of being held forever —
+    /// and the main with it.
+    ///
+    /// The deployment half calls apogee late by design (a 30 m drop,
+    /// sustained, through a ~1 s filter) and then adds its drogue delay; the
+    /// accelerometer calls it on the crossing. On Osiris that puts the two
+    /// ~4 s apart on an honest flight, which is what the window has to
+    /// cover.
+    BothAgreeWithin {
+        window_us: u32,
+        backup: BackupDeploymentConfig,
+    },
+}
+
+/// The pad gravity reference: the mean specific force over one
+/// [`PAD_WINDOW_S`] window, handed out one window late. The same shape as
+/// `baro_state_estimator::PadReference`, without its degradation — a
+/// reference that might contain thrust is worse than none here, so until
+/// two windows have closed there is none.
+///
+/// The burn tracker in [`crate::ignition_detector`] measures thrust and drag
+/// along the same up, from its own instance.
+#[derive(Debug, Clone)]
+pub(crate) struct PadGravity {
+    /// `(up, g)`: the unit vector the rail's gravity points away from, in
+    /// the IMU's axes, and its magnitude. From the second-most-recently
+    /// closed window.
+    pub(crate) reference: Option<(Vector3<f32>, f32)>,
+    pending: Option<(Vector3<f32>, f32)>,
+    window_start_us: Option<u64>,
+    sum: Vector3<f32>,
+    count: u32,
+}
+
+impl PadGravity {
+    pub(crate) fn new() -> Self {
+        Self {
+            reference: None,
+            pending: None,
+            window_start_us: None,
+            sum: Vector3::zeros(),
+            count: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, timestamp_us: u64, acc: Vector3<f32>) {
+        let start = *self.window_start_us.get_or_insert(timestamp_us);
+        if (timestamp_us.saturating_sub(start)) as f32 * 1e-6 >= PAD_WINDOW_S && self.count > 0 {
+            let mean = self.sum / self.count as f32;
+            let g = sqrt(mean.magnitude_squared());
+            self.reference = self.pending;
+            // A window averaging to nothing is a dead accelerometer, not a
+            // direction; it closes without a reference rather than
+            // dividing by zero.
+            self.pending = (g > 0.0).then(|| (mean / g, g));
+            self.window_start_us = Some(timestamp_us);
+            self.sum = Vector3::zeros();
+            self.count = 0;
+        }
+        self.sum += acc;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Stage {
+    OnPad {
+        gravity: PadGravity,
+    },
+    Flight {
+        ignition_us: u64,
+        up: Vector3<f32>,
+        g: f32,
+    },
+    /// Ignition latched before the pad had a gravity reference: a board
+    /// powered up under two seconds before the motor lit. There is nothing
+    /// honest to integrate against, and the channel calls nothing for the
+    /// rest of the flight rather than integrating against thrust.
+    StoodDown,
+}
+
+/// The backup channel itself. Call [`Self::update`] on every sample; read
+/// its calls back with [`Self::accel_apogee_us`] and
+/// [`Self::timer_expired`].
+#[derive(Debug, Clone)]
+pub struct BackupDeployment {
+    config: BackupDeploymentConfig,
+    ignition: IgnitionDetector,
+    stage: Stage,
+    prev_timestamp_us: Option<u64>,
+    prev_imu_us: Option<u64>,
+    /// Along the pad's up, from the pad (m/s, m). Leaky on the pad, plain
+    /// integrals from ignition on.
+    velocity: f32,
+    altitude: f32,
+    armed: bool,
+    accel_apogee_us: Option<u64>,
+    timer_expired: bool,
+}
+
+impl BackupDeployment {
+    pub fn new(config: BackupDeploymentConfig, ignition_detection_acc_threshold: f32) -> Self {
+        Self {
+            config,
+            ignition: IgnitionDetector::new(ignition_detection_acc_threshold),
+            stage: Stage::OnPad {
+                gravity: PadGravity::new(),
+            },
+            prev_timestamp_us: None,
+            prev_imu_us: None,
+            velocity: 0.0,
+            altitude: 0.0,
+            armed: false,
+            accel_apogee_us: None,
+            timer_expired: false,
+        }
+    }
+
+    /// Advance by one sample. `acc` is the raw accelerometer vector, the
+    /// same one the deployment half is handed, `None` on a sample without
+    /// one.
+    ///
+    /// The integration steps over the measured time since the previous
+    /// accelerometer sample, so a stall is bridged by holding the sample
+    /// that ends it over the whole gap — the way the airbrakes half bridges
+    /// one — rather than by losing the gap's gravity.
+    pub fn update(&mut self, timestamp_us: u64, acc: Option<Vector3<f32>>) {
+        let dt = match self.prev_timestamp_us {
+            Some(prev) => (timestamp_us.saturating_sub(prev)) as f32 * 1e-6,
+            None => 0.0,
+        };
+        self.prev_timestamp_us = Some(timestamp_us);
+        let accel_says_ignition = self.ignition.update(acc, dt);
+
+        let imu_dt = acc.map(|_| {
+            let dt = match self.prev_imu_us {
+                Some(prev) => (timestamp_us.saturating_sub(prev)) as f32 * 1e-6,
+                None => 0.0,
+            };
+            self.prev_imu_us = Some(timestamp_us);
+            dt
+        });
+
+        match &mut self.stage {
+            Stage::OnPad { gravity } => {
+                if let (Some(acc), Some(dt)) = (acc, imu_dt) {
+                    if let Some((up, g)) = gravity.reference {
+                        let leak = 1.0 - (dt / PAD_LEAK_TAU_S).min(1.0);
+                        self.velocity = (self.velocity + (acc.dot(&up) - g) * dt) * leak;
+                        self.altitude = (self.altitude + self.velocity * dt) * leak;
+                    }
+                    gravity.push(timestamp_us, acc);
+                }
+                if accel_says_ignition {
+                    self.stage = match gravity.reference {
+                        Some((up, g)) => {
+                            log_info!("backup deployment: ignition, integrating from the pad");
+                            Stage::Flight {
+                                ignition_us: timestamp_us,
+                                up,
+                                g,
+                            }
+                        }
+                        None => {
+                            log_warn!(
+                                "backup deployment: ignition with no pad gravity, standing down"
+                            );
+                            Stage::StoodDown
+                        }
+                    };
+                }
+            }
+            Stage::Flight { ignition_us, up, g } => {
+                if let (Some(acc), Some(dt)) = (acc, imu_dt) {
+                    self.velocity += (acc.dot(up) - *g) * dt;
+                    self.altitude += self.velocity * dt;
+                }
+                let since_ignition_us = timestamp_us.saturating_sub(*ignition_us);
+                if !self.armed && self.altitude >= self.config.arming_altitude_agl {
+                    log_info!(
+                        "backup deployment: armed at {}s",
+                        since_ignition_us as f32 * 1e-6
+                    );
+                    self.armed = true;
+                }
+                if !self.armed {
+                    return;
+                }
+
+                if self.accel_apogee_us.is_none()
+                    && since_ignition_us >= self.config.earliest_accel_apogee_us as u64
+                    && self.velocity <= 0.0
+                {
+                    log_info!(
+                        "backup deployment: accelerometer apogee at {}m",
+                        self.altitude
+                    );
+                    self.accel_apogee_us = Some(timestamp_us);
+                }
+                if !self.timer_expired && since_ignition_us >= self.config.apogee_timer_us as u64 {
+                    log_info!("backup deployment: liftoff timer expired");
+                    self.timer_expired = true;
+                }
+            }
+            Stage::StoodDown => {}
+        }
+    }
+
+    /// When the accelerometer called apogee, on the clock [`Self::update`]
+    /// is handed. Latched: it is called once.
+    pub fn accel_apogee_us(&self) -> Option<u64> {
+        self.accel_apogee_us
+    }
+
+    /// Whether the liftoff timer has run out on an armed channel.
+    pub fn timer_expired(&self) -> bool {
+        self.timer_expired
+    }
+
+    /// Whether the channel has armed, i.e. may call anything at all. Never
+    /// true on a channel that stood down.
+    pub fn armed(&self) -> bool {
+        self.armed
+    }
+
+    /// The integrated vertical velocity (m/s), from the pad on. Zero-ish on
+    /// the pad and meaningless after the drogue opens.
+    pub fn vertical_velocity(&self) -> f32 {
+        self.velocity
+    }
+}
+
+/// [`DeploymentPolicy`], applied: the deployment half's pyro command in,
+/// the one the pyros see out.
+///
+/// Two rules hold under every policy. **The drogue fires once**, so the
+/// deployment half's own drogue, arriving after the backup already fired
+/// one, is swallowed. **The main never precedes the drogue**: the main is
+/// still the deployment half's alone — the backup knows no altitude to open
+/// it at — and one called before the drogue has fired (a single-deployment
+/// profile fires them back to back) is held and fired on the sample after
+/// it.
+///
+/// So a dead barometer buys a drogue and no main on a dual-deployment
+/// airframe, and a drogue-only descent on a single one. That is the trade
+/// the request for a backup was making: a drogue descent is survivable.
+#[derive(Debug, Clone)]
+pub struct DeploymentArbiter {
+    policy: DeploymentPolicy,
+    /// `None` exactly when the policy is [`DeploymentPolicy::PrimaryOnly`].
+    backup: Option<BackupDeployment>,
+    primary_drogue_us: Option<u64>,
+    drogue_fired: bool,
+    main_held: bool,
+    main_fired: bool,
+}
+
+impl DeploymentArbiter {
+    pub fn new(policy: DeploymentPolicy, ignition_detection_acc_threshold: f32) -> Self {
+        let backup = match &policy {
+            DeploymentPolicy::PrimaryOnly => None,
+            DeploymentPolicy::FirstOf(backup)
+            | DeploymentPolicy::BothAgreeWithin { backup, .. } => Some(BackupDeployment::new(
+                backup.clone(),
+                ignition_detection_acc_threshold,
+            )),
+        };
+        Self {
+            policy,
+            backup,
+            primary_drogue_us: None,
+            drogue_fired: false,
+            main_held: false,
+            main_fired: false,
+        }
+    }
+
+    /// Step the backup channel on this sample and decide what fires.
+    /// `primary` is what [`RocketStateEstimator::update`] returned for the
+    /// same sample.
+    ///
+    /// Under [`DeploymentPolicy::PrimaryOnly`] it is returned as it came,
+    /// without so much as a look.
+    ///
+    /// [`RocketStateEstimator::update`]:
+    ///     crate::baro_state_estimator::RocketStateEstimator::update
+    pub fn update(
+        &mut self,
+        timestamp_us: u64,
+        acc: Option<Vector3<f32>>,
+        primary: Option<PyroSelect>,
+    ) -> Option<PyroSelect> {
+        let Some(backup) = self.backup.as_mut() else {
+            return primary;
+        };
+        backup.update(timestamp_us, acc);
+
+        match primary {
+            Some(PyroSelect::PyroDrogue) => {
+                self.primary_drogue_us.get_or_insert(timestamp_us);
+            }
+            Some(PyroSelect::PyroMain) => self.main_held = true,
+            // The deployment half fires nothing else; the other channels
+            // are the pyro-event table's, downstream of here.
+            Some(_) | None => {}
+        }
+
+        if !self.drogue_fired {
+            let accel_apogee_us = backup.accel_apogee_us();
+            let fire = backup.timer_expired()
+                || match &self.policy {
+                    DeploymentPolicy::PrimaryOnly => self.primary_drogue_us.is_some(),
+                    DeploymentPolicy::FirstOf(_) => {
+                        self.primary_drogue_us.is_some() || accel_apogee_us.is_some()
+                    }
+                    DeploymentPolicy::BothAgreeWithin { window_us, .. } => {
+                        match (self.primary_drogue_us, accel_apogee_us) {
+                            // An unarmed channel has no vote to withhold.
+                            (Some(_), _) if !backup.armed() => true,
+                            (Some(primary_us), Some(accel_us)) => {
+                                primary_us.abs_diff(accel_us) <= *window_us as u64
+                            }
+                            _ => false,
+                        }
+                    }
+                };
+            if fire {
+                log_info!(
+                    "drogue by policy (primary: {}, accelerometer: {}, timer: {}, backup armed: {})",
+                    self.primary_drogue_us.is_some(),
+                    accel_apogee_us.is_some(),
+                    backup.timer_expired(),
+                    backup.armed()
+                );
+                self.drogue_fired = true;
+                return Some(PyroSelect::PyroDrogue);
+            }
+            return None;
+        }
+
+        if self.main_held && !self.main_fired {
+            self.main_fired = true;
+            return Some(PyroSelect::PyroMain);
+        }
+        None
+    }
+
+    /// The backup channel, `None` under [`DeploymentPolicy::PrimaryOnly`].
+    pub fn backup(&self) -> Option<&BackupDeployment> {
+        self.backup.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT_US: u64 = 2404;
+    const G: f32 = 9.81;
+    const THRESHOLD: f32 = 4.0 * G;
+
+    fn backup_config() -> BackupDeploymentConfig {
+        BackupDeploymentConfig {
+            apogee_timer_us: 32_000_000,
+            earliest_accel_apogee_us: 15_000_000,
+            arming_altitude_agl: 500.0,
+        }
+    }
+
+    /// A vertical flight as the accelerometer sees it, tilted into the IMU's
+    /// axes so the pad's up has to be found: `pad_s` of pad, 3 s at
+    /// 80 m/s^2, then ballistic. Yields `(t_us, acc, true velocity)`.
+    fn flight(pad_s: f32, seconds: f32) -> Vec<(u64, Vector3<f32>, f32)> {
+        let up = Vector3::new(0.3, -0.2, 0.93).normalize();
+        let mut out = Vec::new();
+        let mut velocity = 0.0f32;
+        let mut t_us = 0u64;
+        let dt = DT_US as f32 * 1e-6;
+        while (t_us as f32) * 1e-6 < seconds {
+            let t = t_us as f32 * 1e-6 - pad_s;
+            let specific_force = if t < 0.0 {
+                G
+            } else if t < 3.0 {
+                80.0 + G
+            } else {
+                0.0
+            };
+            if t >= 0.0 {
+                velocity += (specific_force - G) * dt;
+            }
+            out.push((t_us, up * specific_force, velocity));
+            t_us += DT_US;
+        }
+        out
+    }
+
+    /// Apogee at 3 s + 240/9.81 s = 27.5 s after ignition; the crossing has
+    /// to land on it, the lag of the ignition latch included.
+    #[test]
+    fn accelerometer_apogee_is_the_true_apogee() {
+        let mut backup = BackupDeployment::new(backup_config(), THRESHOLD);
+        let mut true_apogee_us = None;
+        for (t_us, acc, velocity) in flight(10.0, 45.0) {
+            backup.update(t_us, Some(acc));
+            if true_apogee_us.is_none() && t_us > 11_000_000 && velocity <= 0.0 {
+                true_apogee_us = Some(t_us);
+            }
+        }
+        let called = backup.accel_apogee_us().expect("no accelerometer apogee");
+        let truth = true_apogee_us.unwrap();
+        assert!(
+            called.abs_diff(truth) < 100_000,
+            "accelerometer apogee {}us off the true one",
+            called as i64 - truth as i64
+        );
+    }
+
+    /// A knock hard and long enough to latch ignition moves nothing, so the
+    /// channel never arms and the timer never fires.
+    #[test]
+    fn a_knock_on_the_rail_never_arms() {
+        let up = Vector3::new(0.0, 0.0, 1.0);
+        let mut backup = BackupDeployment::new(backup_config(), THRESHOLD);
+        let mut t_us = 0u64;
+        while t_us < 40_000_000 {
+            let t = t_us as f32 * 1e-6;
+            // 0.3 s at 7 g then 0.3 s at -5 g: latches ignition, and takes
+            // back every m/s it gave.
+            let specific_force = if (5.0..5.3).contains(&t) {
+                7.0 * G
+            } else if (5.3..5.6).contains(&t) {
+                -5.0 * G
+            } else {
+                G
+            };
+            backup.update(t_us, Some(up * specific_force));
+            t_us += DT_US;
+        }
+        assert!(
+            matches!(backup.stage, Stage::Flight { .. }),
+            "the knock did not latch"
+        );
+        assert!(backup.accel_apogee_us().is_none());
+        assert!(!backup.timer_expired());
+    }
+
+    /// Drive an arbiter over [`flight`] with a scripted primary: a drogue at
+    /// `primary_drogue_s` after ignition if any, and a main on the next
+    /// sample, the way a single-deployment profile fires them. Returns the
+    /// pyros and when they fired (s after ignition).
+    fn arbitrate(
+        policy: DeploymentPolicy,
+        primary_drogue_s: Option<f32>,
+    ) -> Vec<(f32, PyroSelect)> {
+        arbitrate_on(policy, 10.0, flight(10.0, 45.0), primary_drogue_s)
+    }
+
+    /// [`arbitrate`] over `samples`, a flight whose motor lights `pad_s`
+    /// into it.
+    fn arbitrate_on(
+        policy: DeploymentPolicy,
+        pad_s: f32,
+        samples: Vec<(u64, Vector3<f32>, f32)>,
+        primary_drogue_s: Option<f32>,
+    ) -> Vec<(f32, PyroSelect)> {
+        let mut arbiter = DeploymentArbiter::new(policy, THRESHOLD);
+        let mut fired = Vec::new();
+        let mut script = primary_drogue_s
+            .map(|at| [(at, PyroSelect::PyroDrogue), (at, PyroSelect::PyroMain)])
+            .into_iter()
+            .flatten()
+            .peekable();
+        for (t_us, acc, _) in samples {
+            let t = t_us as f32 * 1e-6 - pad_s;
+            let primary = script.next_if(|(at, _)| t >= *at).map(|(_, pyro)| pyro);
+            if let Some(pyro) = arbiter.update(t_us, Some(acc), primary) {
+                fired.push((t, pyro));
+            }
+        }
+        fired
+    }
+
+    #[test]
+    fn policies_decide_the_drogue() {
+        let agree = |window_us| DeploymentPolicy::BothAgreeWithin {
+            window_us,
+            backup: backup_config(),
+        };
+
+        // Primary only: the script, untouched.
+        let fired = arbitrate(DeploymentPolicy::PrimaryOnly, Some(30.0));
+        assert_eq!(fired.len(), 2);
+        assert!((fired[0].0 - 30.0).abs() < 0.01 && fired[0].1 == PyroSelect::PyroDrogue);
+
+        // Dead primary: first-of fires on the crossing, agreement at the
+        // timer, and neither ever fires a main it was never given.
+        let fired = arbitrate(DeploymentPolicy::FirstOf(backup_config()), None);
+        assert_eq!(fired.len(), 1);
+        assert!(
+            (fired[0].0 - 27.5).abs() < 0.2,
+            "first-of drogue at {}s",
+            fired[0].0
+        );
+        let fired = arbitrate(agree(4_000_000), None);
+        assert_eq!(fired.len(), 1);
+        assert!(
+            (fired[0].0 - 32.0).abs() < 0.5,
+            "agreed drogue at {}s",
+            fired[0].0
+        );
+
+        // A primary 12 s early: first-of believes it, agreement waits for
+        // the timer.
+        let fired = arbitrate(DeploymentPolicy::FirstOf(backup_config()), Some(15.5));
+        assert!((fired[0].0 - 15.5).abs() < 0.01);
+        let fired = arbitrate(agree(4_000_000), Some(15.5));
+        assert!((fired[0].0 - 32.0).abs() < 0.5);
+
+        // An honest primary 2 s after the crossing: agreement fires on it,
+        // and the main it held follows on the next sample.
+        let fired = arbitrate(agree(4_000_000), Some(29.5));
+        assert_eq!(fired.len(), 2);
+        assert!((fired[0].0 - 29.5).abs() < 0.01 && fired[0].1 == PyroSelect::PyroDrogue);
+        assert_eq!(fired[1].1, PyroSelect::PyroMain);
+        assert!(fired[1].0 > fired[0].0);
+    }
+
+    /// A backup that stood down — ignition a second into the session, before
+    /// the pad had a gravity reference — has no vote, so agreement fires an
+    /// honest primary's drogue, and its main, as they come.
+    #[test]
+    fn a_stood_down_backup_does_not_hold_the_primary() {
+        let policy = DeploymentPolicy::BothAgreeWithin {
+            window_us: 4_000_000,
+            backup: backup_config(),
+        };
+        let mut arbiter = DeploymentArbiter::new(policy.clone(), THRESHOLD);
+        for (t_us, acc, _) in flight(1.0, 5.0) {
+            arbiter.update(t_us, Some(acc), None);
+        }
+        assert!(matches!(arbiter.backup().unwrap().stage, Stage::StoodDown));
+
+        let fired = arbitrate_on(policy, 1.0, flight(1.0, 36.0), Some(29.5));
+        assert_eq!(fired.len(), 2);
+        assert!((fired[0].0 - 29.5).abs() < 0.01 && fired[0].1 == PyroSelect::PyroDrogue);
+        assert_eq!(fired[1].1, PyroSelect::PyroMain);
+    }
+
+    /// A backup that never arms — an accelerometer clipped at 5 g integrates
+    /// the burn to ~880 m, short of an Osiris-sized 2000 m arming altitude —
+    /// calls neither apogee nor the timer, so agreement fires an honest
+    /// primary's drogue, and its main, as they come.
+    #[test]
+    fn an_unarmed_backup_does_not_hold_the_primary() {
+        let backup = BackupDeploymentConfig {
+            arming_altitude_agl: 2000.0,
+            ..backup_config()
+        };
+        let policy = DeploymentPolicy::BothAgreeWithin {
+            window_us: 4_000_000,
+            backup,
+        };
+        let clipped = || {
+            flight(10.0, 45.0)
+                .into_iter()
+                .map(|(t_us, acc, velocity)| (t_us, acc.cap_magnitude(5.0 * G), velocity))
+                .collect::<Vec<_>>()
+        };
+        let mut arbiter = DeploymentArbiter::new(policy.clone(), THRESHOLD);
+        for (t_us, acc, _) in clipped() {
+            arbiter.update(t_us, Some(acc), None);
+        }
+        let channel = arbiter.backup().unwrap();
+        assert!(matches!(channel.stage, Stage::Flight { .. }));
+        assert!(!channel.armed());
+
+        let fired = arbitrate_on(policy, 10.0, clipped(), Some(29.5));
+        assert_eq!(fired.len(), 2);
+        assert!((fired[0].0 - 29.5).abs() < 0.01 && fired[0].1 == PyroSelect::PyroDrogue);
+        assert_eq!(fired[1].1, PyroSelect::PyroMain);
+    }
+}
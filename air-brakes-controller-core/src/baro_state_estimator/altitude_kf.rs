@@ -1,3 +1,5 @@
+use firmware_common_new::flight_data_record::GpsFusion;
+
 use crate::baro_gate::BaroGateOutcome;
 use crate::baro_state_estimator::{DT, SAMPLES_PER_S};
 
@@ -36,6 +38,8 @@ pub struct BaroAltitudeKF {
     p11: f32,
     /// Consecutive measurements rejected by the innovation gate
     rejected_streak: u32,
+    /// Consecutive GPS fixes rejected by the GPS innovation gate
+    gps_rejected_streak: u32,
 }
 
 /// Altitude noise variance of the MS5607 on the VLF5 board at pressure OSR=512.
@@ -86,6 +90,18 @@ const INNOVATION_GATE_M: f32 = 500.0;
 /// re-converge instead of flying blind.
 const MAX_REJECTED_SAMPLES: u32 = SAMPLES_PER_S as u32;
 
+/// GPS innovation gate. Tighter than the baro's, because under canopy — the
+/// only place a fix is fused — the filter has no boost lag to allow for: the
+/// baro keeps it within a few metres of the truth, and a fix 150 m away from
+/// it is a multipath or reacquisition glitch, not information.
+const GPS_INNOVATION_GATE_M: f32 = 150.0;
+
+/// Force-accept a fix after this many consecutive rejections. Five fixes is
+/// 1-5 s at the receiver's rates: long enough to ride out a glitch, short
+/// enough that a baro which has walked the filter away (a stuck or blocked
+/// port) is overruled before the main altitude goes by.
+const MAX_REJECTED_GPS_FIXES: u32 = 5;
+
 /// Velocity variance used by [`BaroAltitudeKF::born_in_flight`]: the
 /// uncertainty of the climb rate the ring measured, not "unknown".
 ///
@@ -114,6 +130,7 @@ impl BaroAltitudeKF {
             p01: 0.0,
             p11: 0.1,
             rejected_streak: 0,
+            gps_rejected_streak: 0,
         }
     }
 
@@ -163,13 +180,58 @@ impl BaroAltitudeKF {
             outcome = BaroGateOutcome::Resynced;
         }
         self.rejected_streak = 0;
+        self.correct(y, BARO_ALTITUDE_MEASUREMENT_VARIANCE);
+        outcome
+    }
+
+    /// Incorporate a GPS altitude (m, on the baro's datum — see
+    /// [`RocketStateEstimator::update_gps`](super::RocketStateEstimator::update_gps))
+    /// with measurement variance `variance` (m²).
+    ///
+    /// Gated like [`Self::update`] but on its own streak and its own numbers:
+    /// a fix is a few times a second rather than 416, so the gate counts
+    /// fixes, not samples, and a GPS that disagrees with the baro for a
+    /// while does not hold back a baro that agrees with the filter.
+    ///
+    /// Unlike the baro's, the run of rejections only ends in a resync when
+    /// `may_resync` — when no barometer is voted healthy. A healthy baro
+    /// 150 m away from a GPS is a GPS with a bias or multipath, and a resync
+    /// onto it is a one-sample jump that the main altitude check acts on.
+    /// The streak stays full meanwhile, so the first offset fix after the
+    /// vote loses its last sensor takes over.
+    pub fn update_gps(&mut self, z_gps: f32, variance: f32, may_resync: bool) -> GpsFusion {
+        let y = z_gps - self.altitude;
+
+        let mut fusion = GpsFusion::Fused;
+        if y.abs() > GPS_INNOVATION_GATE_M {
+            if self.gps_rejected_streak < MAX_REJECTED_GPS_FIXES {
+                self.gps_rejected_streak += 1;
+                return GpsFusion::Rejected;
+            }
+            if !may_resync {
+                return GpsFusion::Rejected;
+            }
+            // As for the baro: the filter is what is wrong, so let go of its
+            // altitude and take the fix.
+            self.p00 += GPS_INNOVATION_GATE_M * GPS_INNOVATION_GATE_M;
+            fusion = GpsFusion::Resynced;
+        }
+        self.gps_rejected_streak = 0;
+        self.correct(y, variance.max(BARO_ALTITUDE_MEASUREMENT_VARIANCE));
+        fusion
+    }
 
+    /// The measurement update proper, for innovation `y` of an altitude
+    /// measurement with variance `r`. Shared by both sensors; the operation
+    /// orderings below are the baro path's bit-identity.
+    fn correct(&mut self, y: f32, r: f32) {
         // Innovation covariance S = H P₋ Hᵀ + R, a scalar: p00 + R. p00 is a
-        // variance and R is 0.45, so S >= 0.45 and the reciprocal below can
-        // never blow up. (The matrix form's `try_inverse().unwrap()` here was
-        // unreachable for exactly that reason: nalgebra's 1×1 `try_inverse`
-        // carries no epsilon and returns `None` only at exactly ±0.0.)
-        let s = self.p00 + BARO_ALTITUDE_MEASUREMENT_VARIANCE;
+        // variance and R is at least the baro's 0.45 (`update_gps` floors its
+        // R there), so S >= 0.45 and the reciprocal below can never blow up.
+        // (The matrix form's `try_inverse().unwrap()` here was unreachable for
+        // exactly that reason: nalgebra's 1×1 `try_inverse` carries no epsilon
+        // and returns `None` only at exactly ±0.0.)
+        let s = self.p00 + r;
 
         // Kalman gain K = P₋ Hᵀ S⁻¹ = ⎡p00⎤ · (1/S).
         //                             ⎣p01⎦
@@ -205,7 +267,6 @@ impl BaroAltitudeKF {
         self.p11 -= k1 * self.p01;
         self.p00 *= one_minus_k0;
         self.p01 = 0.5 * (p01_upper + p01_lower);
-        outcome
     }
 
     /// Build a filter for a rocket that is already flying — the Mach
@@ -240,4 +301,22 @@ impl BaroAltitudeKF {
     pub fn vertical_velocity(&self) -> f32 {
         self.velocity
     }
+
+    /// The innovation a baro reading `z_baro` would have against the current
+    /// prior, and its expected variance `p00 + R`. Recomputed here rather
+    /// than captured inside [`Self::update`] so that the update's arithmetic
+    /// is the same expression whether or not anyone is watching.
+    #[cfg(feature = "debug-internals")]
+    pub fn innovation(&self, z_baro: f32) -> (f32, f32) {
+        (
+            z_baro - self.altitude,
+            self.p00 + BARO_ALTITUDE_MEASUREMENT_VARIANCE,
+        )
+    }
+
+    /// `(P[0][0], P[1][1])`: the altitude and velocity variances.
+    #[cfg(feature = "debug-internals")]
+    pub fn covariance_diagonal(&self) -> (f32, f32) {
+        (self.p00, self.p11)
+    }
 }
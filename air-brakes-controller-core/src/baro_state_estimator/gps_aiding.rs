@@ -0,0 +1,144 @@
+//! GPS altitude for the deployment filter, under canopy only.
+//!
+//! The filter is baro-only by design, and that design has one blind spot
+//! the vote cannot close on a single-barometer board: a port that stops
+//! telling the truth *after apogee* — packed with ejection debris, or stuck
+//! — holds the filter wherever it stopped, and the main altitude is then
+//! never crossed. The receiver is the one sensor on the airframe that
+//! measures altitude without a static port, and under canopy, at tens of
+//! metres per second, it is at its best: no boost dynamics for its tracking
+//! loops to lag, and the sky in view.
+//!
+//! So a fix is fused into [`BaroAltitudeKF`](super::BaroAltitudeKF) — from
+//! the apogee call to landing, and nowhere else — when it passes
+//! [`GpsAiding`]'s quality gate. Before apogee it is only watched: the baro
+//! is the filter's truth through boost and coast, the Mach lockout has no
+//! filter to fuse into, and a fix taken at Mach 1 from a receiver without
+//! its high-dynamics mode is not a number to put near a pyro decision.
+//!
+//! **The GPS datum is not the barometer's.** MSL from the geoid model and a
+//! pressure altitude from the standard atmosphere differ by tens of metres
+//! on any given day, so a raw fix can never be compared to the filter. The
+//! pad supplies the offset: fixes that pass the gate while the rocket sits
+//! on the rail are averaged into a GPS pad reference — [`PadReference`],
+//! over [`GPS_PAD_WINDOW_S`] windows — and every fix in descent goes in as
+//! its height above that reference, put on the baro pad reference. What is
+//! fused is therefore GPS *height above the pad*, which is the only
+//! altitude the deployment decisions read anyway.
+//!
+//! **When the barometer is distrusted, the GPS carries the filter.** A
+//! board whose every sensor is flagged still fuses the median of all of
+//! them (see [`crate::baro_vote`]); that rule is right when nothing else
+//! can speak, and wrong when a fix fused within [`GPS_CARRY_S`] can. In
+//! descent, with a recent fix in the filter and no sensor voted healthy,
+//! the baro update is skipped and the filter runs on prediction and GPS.
+//! This is the case the aiding exists for: a stuck port is flagged within
+//! a second, and from then on only the receiver moves the filter.
+
+use firmware_common_new::gps::GPSData;
+
+use super::PadReference;
+
+/// Length of one GPS pad-reference window (s of measured time). The pad
+/// reference is a mean of fixes, and GPS altitude error is correlated over
+/// minutes, not samples: ten seconds of a 5 Hz receiver is fifty fixes,
+/// and the window handed out one behind is still well inside the minutes a
+/// rocket sits armed.
+pub(crate) const GPS_PAD_WINDOW_S: f32 = 10.0;
+
+/// How recently a fix must have been fused for the GPS to carry the filter
+/// on its own when no barometer is voted healthy (s of measured time). Two
+/// seconds is several fixes at any rate the receiver is run at, and ~50 m
+/// of drogue descent: past it, a filter on prediction alone is worse than
+/// the median of distrusted sensors it would otherwise fuse.
+pub(super) const GPS_CARRY_S: f32 = 2.0;
+
+/// GPS aiding of the deployment filter: which fixes are good enough to
+/// fuse, and how much each one is believed. See the module docs for when
+/// a fix is fused at all.
+///
+/// A fix is fused only with all three quality fields reported and within
+/// limits; a receiver that leaves a field blank has not said the fix is
+/// good. Its measurement standard deviation is its VDOP times
+/// `vertical_sigma_per_vdop_m`, the receiver's user range error.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpsAiding {
+    /// Fewest satellites in the fix.
+    pub min_satellites: u8,
+    /// Largest horizontal DOP. Not read by the vertical model, and still
+    /// gated: a fix with poor horizontal geometry is a fix the receiver is
+    /// struggling to hold.
+    pub max_hdop: f32,
+    /// Largest vertical DOP.
+    pub max_vdop: f32,
+    /// Vertical error, 1 sigma, per unit of VDOP (m).
+    pub vertical_sigma_per_vdop_m: f32,
+}
+
+impl Default for GpsAiding {
+    /// 6 satellites, HDOP 2.5, VDOP 3.0, 5 m per unit of VDOP. The limits
+    /// are the usual "good fix" ones; 5 m is a conservative user range
+    /// error for a patch-antenna receiver: at the VDOP limit a fix is
+    /// believed to 15 m, against the filter's own few metres under canopy,
+    /// so an honest baro still sets the altitude and the receiver only takes
+    /// over once the baro is out of the picture.
+    fn default() -> Self {
+        Self {
+            min_satellites: 6,
+            max_hdop: 2.5,
+            max_vdop: 3.0,
+            vertical_sigma_per_vdop_m: 5.0,
+        }
+    }
+}
+
+impl GpsAiding {
+    /// The fix's altitude (m MSL) and its measurement variance (m^2), or
+    /// `None` if it fails the gate.
+    pub(crate) fn measurement(&self, gps: &GPSData) -> Option<(f32, f32)> {
+        let altitude = gps.gps_altitude_asl.filter(|a| a.is_finite())?;
+        let hdop = gps.hdop?;
+        let vdop = gps.vdop?;
+        // Written so a NaN DOP fails.
+        let good = gps.num_of_fix_satellites >= self.min_satellites
+            && hdop <= self.max_hdop
+            && vdop <= self.max_vdop;
+        if !good {
+            return None;
+        }
+        let sigma = vdop * self.vertical_sigma_per_vdop_m;
+        Some((altitude, sigma * sigma))
+    }
+}
+
+/// [`GpsAiding`] in flight: the config, the GPS pad reference, when a fix
+/// last moved the filter, and whether the last baro sample's vote had a
+/// healthy sensor in it.
+#[derive(Debug, Clone)]
+pub(super) struct GpsAider {
+    pub(super) config: GpsAiding,
+    pub(super) pad: PadReference,
+    pub(super) last_fused_us: Option<u64>,
+    /// Only a fix that arrives with no barometer voted healthy may resync
+    /// the filter; see [`BaroAltitudeKF::update_gps`](super::BaroAltitudeKF::update_gps).
+    pub(super) baro_voted: bool,
+}
+
+impl GpsAider {
+    pub(super) const fn new(config: GpsAiding) -> Self {
+        Self {
+            config,
+            pad: PadReference::new(GPS_PAD_WINDOW_S),
+            last_fused_us: None,
+            baro_voted: true,
+        }
+    }
+
+    /// Whether a fix has been fused within [`GPS_CARRY_S`] of `timestamp_us`.
+    pub(super) fn carrying(&self, timestamp_us: u64) -> bool {
+        self.last_fused_us.is_some_and(|fused_us| {
+            (timestamp_us.saturating_sub(fused_us)) as f32 * 1e-6 <= GPS_CARRY_S
+        })
+    }
+}
@@ -1,6 +1,6 @@
 //! *Deployment* state machine, baro-driven.
 //!
-//! Detects apogee and landing from barometric altitude alone via a
+//! Detects apogee and landing from barometric altitude via a
 //! deliberately slow (~1 s bandwidth) 2-state Kalman filter whose output is
 //! trusted outright — the COTS-altimeter shape: innovation gate as bus input
 //! validation, a timed Mach lockout started at ignition detection, apogee by
@@ -19,6 +19,9 @@
 //! *code* with the airbrakes half's detector but not the *instance*, so
 //! neither half can hold the other's ignition decision hostage. The
 //! threshold is not even per-half: it is one field above both of them.
+//! The accelerometer has one other say, at the far end of the flight: a
+//! rocket still swinging under its canopy is not landed, whatever the
+//! barometer reads ([`LandingDetection`]).
 //!
 //! [`FlightConfig::ignition_detection_acc_threshold`]:
 //!     crate::FlightConfig::ignition_detection_acc_threshold
@@ -36,6 +39,12 @@
 //! That is a deliberate trade: one detector that is right about the moment
 //! that matters, over a second one that is specifically wrong about it.
 //!
+//! **Nor is "the barometer" one barometer.** Every sample carries one
+//! reading per sensor on the bus, and [`crate::baro_vote`] turns them into
+//! the one altitude everything below reads, flagging any sensor that is
+//! stuck, noisy or disagreeing with the rest. Nothing below knows how many
+//! there were.
+//!
 //! # What the filter is for, and when it exists
 //!
 //! Only for apogee and landing, and so only from the moment the barometer
@@ -58,21 +67,37 @@
 //!
 //! `Option<BaroAltitudeKF>` is the whole of that rule. Absence is a fact
 //! about the type rather than something every reader has to remember.
+//!
+//! From the apogee call on, the filter can also take GPS altitude, when the
+//! profile asks for it and the fix is good enough — see [`GpsAiding`]. It is
+//! the one measurement that is not a barometer, and it is fused nowhere
+//! before descent.
 
 mod altitude_kf;
+pub(crate) mod gps_aiding;
 
 #[cfg(test)]
 mod tests;
 
 pub use altitude_kf::BaroAltitudeKF;
+pub use gps_aiding::GpsAiding;
 
+use firmware_common_new::flight_storage::{AvionicsConfig, DeploymentConfig};
+use firmware_common_new::gps::GPSData;
+use firmware_common_new::variance::VarianceEstimator;
 use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
 use heapless::Deque;
 use nalgebra::Vector3;
 
 use crate::ignition_detector::IgnitionDetector;
+use crate::pyro_events::PyroEvents;
 
 use crate::baro_gate::BaroGateOutcome;
+use crate::baro_vote::{BaroVoter, MAX_BAROS};
+#[cfg(feature = "debug-internals")]
+use crate::kf_internals::KfInternals;
+use firmware_common_new::flight_data_record::{BaroHealth, GpsFusion};
+use gps_aiding::GpsAider;
 
 /// Baro sample rate the KF is designed for (matches IMU ODR).
 ///
@@ -98,16 +123,9 @@ const APOGEE_DROP_M: f32 = 30.0; // m
 /// How long the altitude has to stay below (peak - APOGEE_DROP_M) before
 /// descent is acted upon
 const APOGEE_DROP_SUSTAIN_S: f32 = 0.5;
-/// |KF vertical velocity| below this counts as standing still. The slow
-/// filter's stationary velocity noise is ~0.012 m/s std (peaks ~0.05 m/s), so
-/// this is sized by canopy-swing and post-touchdown-drift rejection, not
-/// noise; descent under main (>= ~4.5 m/s) keeps the counter pinned at zero.
-const LANDED_VELOCITY_THRESHOLD: f32 = 2.0; // m/s
-/// How long the rocket has to stand still before it is considered landed
-const LANDED_DETECTION_S: f32 = 5.0;
 /// Length of one pad-reference averaging window (s of measured time). See
 /// [`PadReference`] for why the windows are handed out one behind.
-const PAD_WINDOW_S: f32 = 1.0;
+pub(crate) const PAD_WINDOW_S: f32 = 1.0;
 
 /// Ring of recent raw baro samples kept through the Mach lockout, used for
 /// exactly one thing: the state the filter is born in when the lockout ends
@@ -227,6 +245,23 @@ pub struct FlightProfile {
     pub mach_lockout_duration_us: Option<u32>,
 
     pub deployment: DeploymentProfile,
+
+    /// When the rocket under its main counts as on the ground. See
+    /// [`LandingDetection`].
+    pub landing: LandingDetection,
+
+    /// Every pyro channel beyond [`Self::deployment`]'s two, and what fires
+    /// it — see [`crate::pyro_events`]. Empty on a two-pyro airframe.
+    ///
+    /// This estimator never reads it: it fires its drogue and main whatever
+    /// the table says, and [`FlightEstimators`](crate::FlightEstimators)
+    /// runs the table downstream of it.
+    pub pyro_events: PyroEvents,
+
+    /// GPS altitude in descent, and the fixes good enough for it — see
+    /// [`GpsAiding`]. `None` keeps this estimator barometer-only, whatever
+    /// [`RocketStateEstimator::update_gps`] is handed.
+    pub gps_aiding: Option<GpsAiding>,
 }
 
 /// Deployment scheme: single (both pyros at apogee) or dual (drogue at
@@ -249,6 +284,167 @@ pub enum DeploymentProfile {
     },
 }
 
+impl From<DeploymentConfig> for DeploymentProfile {
+    /// The profile stored in the SD config block. Spelled out field by field
+    /// so that a field added on either side stops this compiling.
+    fn from(config: DeploymentConfig) -> Self {
+        match config {
+            DeploymentConfig::Single {
+                minimum_deployment_altitude_agl,
+                delay_us,
+            } => Self::Single {
+                minimum_deployment_altitude_agl,
+                delay_us,
+            },
+            DeploymentConfig::Dual {
+                drogue_chute_minimum_altitude_agl,
+                drogue_chute_delay_us,
+                main_chute_altitude_agl,
+                main_chute_delay_us,
+            } => Self::Dual {
+                drogue_chute_minimum_altitude_agl,
+                drogue_chute_delay_us,
+                main_chute_altitude_agl,
+                main_chute_delay_us,
+            },
+        }
+    }
+}
+
+/// The landing detector: what has to hold, and for how long, before the
+/// rocket under its main is declared [`RocketState::Landed`].
+///
+/// Landing is the one transition nothing fires on, and it is still the one
+/// the firmware acts on hardest: it is the signal to drop to the landed
+/// telemetry packet and close the SD log. Called early, the descent tail of
+/// the log is gone; never called, the log runs until the battery does and
+/// the downlink keeps spending its budget on a rocket lying in a field. So
+/// it asks three things, all of which must hold continuously for one whole
+/// `window_us`:
+///
+/// * **Near the pad**: filtered altitude within `max_altitude_agl` of the
+///   pad reference. A main that hangs up in a thermal, or a canopy drifting
+///   in still air at 300 m, has a slow filter reading a standing start; the
+///   ground is where the pad is, give or take the terrain.
+/// * **Not moving**: |filtered vertical velocity| under
+///   `max_vertical_velocity`. The slow filter's stationary velocity noise is
+///   ~0.012 m/s std (peaks ~0.05 m/s), so this is sized by canopy-swing and
+///   post-touchdown-drift rejection, not noise; descent under main
+///   (>= ~4.5 m/s) keeps the window from ever starting.
+/// * **Not swinging**: the accelerometer's spread over the window, the root
+///   of the summed per-axis population variances from a
+///   [`VarianceEstimator`], under `max_acc_std`. An airframe on a canopy is
+///   never still — it swings, spins and is buffeted — while one lying in the
+///   dirt reads the same vector sample after sample. The barometer cannot
+///   tell those apart at a few metres up; this can.
+///
+/// Anything failing restarts the window from the next sample. A window
+/// with no accelerometer samples in it has zero variance and passes: a
+/// silent accelerometer says nothing against landing, and the two baro
+/// conditions still stand.
+///
+/// Not on the SD config card: [`Default`] is what every profile built from
+/// one gets.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LandingDetection {
+    /// How long all three conditions must hold, measured time.
+    pub window_us: u32,
+    /// |filtered altitude - pad reference| at most this, m.
+    pub max_altitude_agl: f32,
+    /// |filtered vertical velocity| at most this, m/s.
+    pub max_vertical_velocity: f32,
+    /// Accelerometer spread over the window at most this, m/s^2.
+    pub max_acc_std: f32,
+}
+
+impl Default for LandingDetection {
+    /// 5 s, 150 m, 2 m/s, 0.5 m/s^2. The window and the velocity are the
+    /// persistence and threshold the velocity-only detector had before the
+    /// other two conditions existed. 150 m covers the terrain between any
+    /// pad and any landing site flown so far. 0.5 m/s^2 is several times an
+    /// IMU's noise at rest and a small fraction of what a swinging airframe
+    /// reads.
+    fn default() -> Self {
+        Self {
+            window_us: 5_000_000,
+            max_altitude_agl: 150.0,
+            max_vertical_velocity: 2.0,
+            max_acc_std: 0.5,
+        }
+    }
+}
+
+/// One [`LandingDetection`] window in progress.
+#[derive(Debug, Clone)]
+struct LandingWindow {
+    /// First sample of the current window. `None` while a condition fails.
+    start_us: Option<u64>,
+    /// The accelerometer over the current window.
+    acc: VarianceEstimator<3>,
+}
+
+impl LandingWindow {
+    const fn new() -> Self {
+        Self {
+            start_us: None,
+            acc: VarianceEstimator::new(),
+        }
+    }
+
+    /// Feed one sample under the main. `true` once a whole window has held.
+    fn update(
+        &mut self,
+        config: &LandingDetection,
+        timestamp_us: u64,
+        altitude_agl: f32,
+        vertical_velocity: f32,
+        acc: Option<Vector3<f32>>,
+    ) -> bool {
+        if altitude_agl.abs() > config.max_altitude_agl
+            || vertical_velocity.abs() > config.max_vertical_velocity
+        {
+            *self = Self::new();
+            return false;
+        }
+
+        let start = *self.start_us.get_or_insert(timestamp_us);
+        if let Some(acc) = acc {
+            self.acc.update([acc.x, acc.y, acc.z]);
+        }
+        if timestamp_us.saturating_sub(start) < config.window_us as u64 {
+            return false;
+        }
+
+        // A whole window near the ground and not moving; the accelerometer
+        // decides. Still swinging starts a fresh window here rather than
+        // sliding this one, which a Welford accumulator cannot do.
+        let acc_var: f32 = self.acc.variance().iter().sum();
+        if acc_var <= config.max_acc_std * config.max_acc_std {
+            return true;
+        }
+        self.start_us = Some(timestamp_us);
+        self.acc.clear();
+        false
+    }
+}
+
+impl From<&AvionicsConfig> for FlightProfile {
+    fn from(config: &AvionicsConfig) -> Self {
+        Self {
+            mach_lockout_duration_us: config.mach_lockout.deployment_us,
+            deployment: config.deployment.into(),
+            landing: LandingDetection::default(),
+            // The SD config block has no table, so a board configured from
+            // it flies drogue and main only.
+            pyro_events: PyroEvents::new(),
+            // Nor does it say anything about the receiver, so a board
+            // configured from it flies on its barometers alone.
+            gps_aiding: None,
+        }
+    }
+}
+
 impl DeploymentProfile {
     fn minimum_deployment_agl(&self) -> f32 {
         match self {
@@ -337,6 +533,10 @@ pub enum RocketState {
         altitude_asl: f32,
         launch_pad_altitude_asl: f32,
     },
+    /// On the ground, by [`LandingDetection`]; terminal. The one state
+    /// change the firmware acts on without a pyro behind it: from here it
+    /// sends [`LandedTelemetryPacket`](firmware_common_new::vlp::packets::landed_telemetry::LandedTelemetryPacket)
+    /// instead of flight telemetry and closes the SD log.
     Landed,
     FailedToReachMinApogee,
 }
@@ -388,8 +588,17 @@ pub enum RocketState {
 /// such a sample; if one ever appears, the answer is a median of the
 /// window rather than a mean, which rejects nothing and needs no
 /// threshold.
+///
+/// The GPS pad reference is the same type over longer windows
+/// ([`gps_aiding::GPS_PAD_WINDOW_S`]); everything above holds of it, with
+/// fixes in place of barometer samples. So is the pad air temperature
+/// [`crate::FlightEstimators`] calibrates the atmosphere from, over the
+/// barometer's windows, with kelvin in place of metres.
 #[derive(Debug, Clone)]
-struct PadReference {
+pub(crate) struct PadReference {
+    /// Length of one averaging window (s of measured time):
+    /// [`PAD_WINDOW_S`] for the barometer.
+    window_s: f32,
     /// The reference itself: the mean of the second-most-recently closed
     /// window. `None` until two have closed, i.e. for the first ~2 s.
     reference_asl: Option<f32>,
@@ -407,8 +616,9 @@ struct PadReference {
 }
 
 impl PadReference {
-    const fn new() -> Self {
+    pub(crate) const fn new(window_s: f32) -> Self {
         Self {
+            window_s,
             reference_asl: None,
             pending_asl: None,
             window_start_us: None,
@@ -419,9 +629,9 @@ impl PadReference {
 
     /// Feed one pad sample. Closes the current window first if this sample
     /// is past its end, so a window never contains a sample taken after it.
-    fn push(&mut self, timestamp_us: u64, baro_altitude_asl: f32) {
+    pub(crate) fn push(&mut self, timestamp_us: u64, baro_altitude_asl: f32) {
         let start = *self.window_start_us.get_or_insert(timestamp_us);
-        if (timestamp_us.saturating_sub(start)) as f32 * 1e-6 >= PAD_WINDOW_S && self.count > 0 {
+        if (timestamp_us.saturating_sub(start)) as f32 * 1e-6 >= self.window_s && self.count > 0 {
             self.reference_asl = self.pending_asl;
             self.pending_asl = Some((self.sum / self.count as f64) as f32);
             self.window_start_us = Some(timestamp_us);
@@ -435,7 +645,7 @@ impl PadReference {
     /// The reference: the lagged window once there is one, else the best
     /// thing there is, else `None` before the very first sample. See the
     /// type doc for why this degrades rather than going absent.
-    fn reference_asl(&self) -> Option<f32> {
+    pub(crate) fn reference_asl(&self) -> Option<f32> {
         self.reference_asl
             .or(self.pending_asl)
             .or_else(|| (self.count > 0).then(|| (self.sum / self.count as f64) as f32))
@@ -486,9 +696,7 @@ enum Stage {
     },
     MainDeployed {
         launch_pad_altitude_asl: f32,
-        /// how long |velocity| has been continuously below the landed
-        /// threshold, in seconds of measured time
-        still_s: f32,
+        landing: LandingWindow,
     },
     Landed {
         launch_pad_altitude_asl: f32,
@@ -500,7 +708,9 @@ enum Stage {
 
 /// Deployment state estimator + flight state machine.
 ///
-/// Feed it every timestamped baro altitude ASL sample via [`Self::update`].
+/// Feed it every timestamped sample of baro altitudes ASL — one per
+/// barometer on the bus, voted down to one by [`BaroVoter`] — via
+/// [`Self::update`].
 /// The KF wants them at roughly [`SAMPLES_PER_S`] — it steps a fixed `DT`
 /// per sample — but the state machine's timers read the timestamps, so the
 /// actual rate does not have to be exactly that, and does not have to be
@@ -516,6 +726,15 @@ pub struct RocketStateEstimator {
     /// This half's ignition detector. Its own instance, not shared with the
     /// airbrakes half's — see [`IgnitionDetector::update`].
     ignition: IgnitionDetector,
+    /// This half's barometer vote, and every sensor's history behind it.
+    /// Its own instance for the same reason as `ignition`.
+    baro_vote: BaroVoter,
+    /// GPS aiding, when the profile has it. See [`Self::update_gps`].
+    gps: Option<GpsAider>,
+    /// What this sample's baro update did inside the filter; see
+    /// [`Self::kf_internals`].
+    #[cfg(feature = "debug-internals")]
+    kf_internals: Option<KfInternals>,
 }
 
 impl RocketStateEstimator {
@@ -525,14 +744,19 @@ impl RocketStateEstimator {
     /// the one number both halves detect ignition at, handed down by
     /// [`FlightEstimators::new`](crate::FlightEstimators::new).
     pub fn new(profile: FlightProfile, ignition_detection_acc_threshold: f32) -> Self {
+        let profile_gps = profile.gps_aiding.clone();
         Self {
             profile,
             kf: None,
             stage: Stage::OnPad {
-                pad: PadReference::new(),
+                pad: PadReference::new(PAD_WINDOW_S),
             },
             prev_timestamp_us: None,
             ignition: IgnitionDetector::new(ignition_detection_acc_threshold),
+            baro_vote: BaroVoter::new(),
+            gps: profile_gps.map(GpsAider::new),
+            #[cfg(feature = "debug-internals")]
+            kf_internals: None,
         }
     }
 
@@ -559,25 +783,40 @@ impl RocketStateEstimator {
         dt
     }
 
-    /// Process one baro altitude ASL sample (m) with the timestamp it was
-    /// taken at (us, same monotonic clock every call), and the raw
+    /// Process one sample of baro altitudes ASL (m) with the timestamp it
+    /// was taken at (us, same monotonic clock every call), and the raw
     /// accelerometer specific force from the same sample if there was one.
     ///
+    /// `baro_altitudes_asl` holds one slot per barometer, `None` for a
+    /// sensor with no fresh reading this sample, in the same order every
+    /// call; see [`BaroVoter::vote`]. Everything below reads the one
+    /// altitude the vote returns, and nothing else about the sensors — so a
+    /// single-barometer caller passing `&[Some(altitude)]` gets exactly the
+    /// estimator it always had. A sample on which no sensor read at all is
+    /// not an error: the filter coasts on its prediction, the pad reference
+    /// and the lockout ring skip it, and the timers still run.
+    ///
     /// `acc` is the RAW sensor vector, not anything another estimator
-    /// derived from it, and it feeds exactly one decision: the ignition
+    /// derived from it, and it feeds exactly two decisions: the ignition
     /// magnitude check (see
-    /// [`FlightConfig::ignition_detection_acc_threshold`](crate::FlightConfig::ignition_detection_acc_threshold)).
+    /// [`FlightConfig::ignition_detection_acc_threshold`](crate::FlightConfig::ignition_detection_acc_threshold))
+    /// and the stillness check that ends the flight ([`LandingDetection`]).
     /// Nothing else in this estimator reads it, and it is the ONLY thing
     /// that can start a flight: `None` on every sample, or a dead IMU, and
-    /// this estimator stays on the pad forever.
+    /// this estimator stays on the pad forever. It cannot hold a flight
+    /// open the same way — missing samples say nothing against landing.
     ///
     /// Returns the pyro command for this sample — `Some(pyro)` when a pyro
-    /// channel should be fired — and what the innovation gate did with this
-    /// sample's baro reading. The gate outcome is returned rather than stored
-    /// because a resync happens on exactly one sample and there is nowhere
-    /// for it to go stale: see [`crate::BaroGateOutcome`]. `Accepted` covers
-    /// every path where no gate ran at all — the Mach lockout, where nothing
-    /// is fused, and the (unreachable) missing-filter fallback.
+    /// channel should be fired — what the innovation gate did with this
+    /// sample's voted reading, and the vote's verdict on each sensor. The
+    /// gate outcome is returned rather than stored because a resync happens
+    /// on exactly one sample and there is nowhere for it to go stale: see
+    /// [`crate::BaroGateOutcome`]. `Accepted` covers every path where no
+    /// gate ran at all — the Mach lockout, where nothing is fused, the
+    /// (unreachable) missing-filter fallback, a sample no barometer read on,
+    /// and a descent sample the GPS is carrying with no healthy barometer in
+    /// the vote (see [`GpsAiding`]). The verdicts are returned for the same
+    /// reason as the gate outcome: they describe this sample.
     ///
     /// The timestamp drives every *duration* below — the Mach lockout, the
     /// pyro delays, the apogee and landing persistence, the pad-altitude
@@ -590,13 +829,30 @@ impl RocketStateEstimator {
         &mut self,
         timestamp_us: u64,
         acc: Option<Vector3<f32>>,
-        baro_altitude_asl: f32,
-    ) -> (Option<PyroSelect>, BaroGateOutcome) {
+        baro_altitudes_asl: &[Option<f32>],
+    ) -> (Option<PyroSelect>, BaroGateOutcome, [BaroHealth; MAX_BAROS]) {
         let dt = self.timer_dt(timestamp_us);
+        #[cfg(feature = "debug-internals")]
+        {
+            self.kf_internals = None;
+        }
         // Run every sample so the low pass and the sustain are already warm
         // when the motor lights; the result is only consulted on the pad.
         let accel_says_ignition = self.ignition.update(acc, dt);
 
+        // The vote runs every sample too, for the same reason: a sensor's
+        // stuck run and noise estimate must already be warm when they
+        // matter. The filter's altitude is its reference where there is a
+        // filter — the previous sample's, which is within a metre of this
+        // one's prediction and a hundred metres inside the outlier margin.
+        let vote = self.baro_vote.vote(
+            timestamp_us,
+            baro_altitudes_asl,
+            self.kf.as_ref().map(|kf| kf.altitude_asl()),
+        );
+        let baro_altitude_asl = vote.altitude_asl;
+        let health = vote.health;
+
         // Mach lockout, handled here and not in the stage machine below,
         // because there is no filter during it to run that machine on.
         //
@@ -623,20 +879,29 @@ impl RocketStateEstimator {
             // innovation gate was dropped at ignition — and nothing reads
             // this ring except the median below, which does not care what
             // the samples it outvotes look like.
-            if baro_ring.is_full() {
-                baro_ring.pop_front();
-            }
-            let _ = baro_ring.push_back((timestamp_us, baro_altitude_asl));
-            while let Some(front) = baro_ring.front() {
-                if (timestamp_us.saturating_sub(front.0)) as f32 * 1e-6 > BARO_RING_SPAN_S {
+            if let Some(baro_altitude_asl) = baro_altitude_asl {
+                if baro_ring.is_full() {
                     baro_ring.pop_front();
-                } else {
-                    break;
+                }
+                let _ = baro_ring.push_back((timestamp_us, baro_altitude_asl));
+                while let Some(front) = baro_ring.front() {
+                    if (timestamp_us.saturating_sub(front.0)) as f32 * 1e-6 > BARO_RING_SPAN_S {
+                        baro_ring.pop_front();
+                    } else {
+                        break;
+                    }
                 }
             }
 
             *remaining_s -= dt;
-            if *remaining_s <= 0.0 {
+            // An empty ring means no barometer has read since ignition, and
+            // then there is nothing to be born from: the lockout runs on
+            // until one speaks. It is a condition rather than an `expect`
+            // because this is the code path that fires pyros, and a filter
+            // born late beats a panic that resets the board mid-flight.
+            if *remaining_s <= 0.0
+                && let Some((seed_asl, seed_velocity)) = ring_birth_state(baro_ring, timestamp_us)
+            {
                 // Born from the last BARO_RING_SPAN_S rather than from this
                 // one sample, so that one bad reading landing on the exit
                 // sample cannot decide both where the filter starts and what
@@ -645,20 +910,11 @@ impl RocketStateEstimator {
                 // as an altitude, which is what lets the altitude be the one
                 // at *this* sample rather than the one half a ring span ago;
                 // see [`ring_birth_state`].
-                //
-                // `unwrap_or` cannot fire: this sample was just pushed. It is
-                // spelt as a fallback rather than an `expect` because this is
-                // the code path that fires pyros, and the honest degradation
-                // of an empty ring is the old single-sample behaviour, not a
-                // panic that resets the board mid-flight.
-                let (seed_asl, seed_velocity) =
-                    ring_birth_state(baro_ring, timestamp_us).unwrap_or((baro_altitude_asl, 0.0));
                 log_info!(
-                    "mach lockout over, building KF in flight at {}m climbing {}m/s (from the last {}s; this sample read {}m)",
+                    "mach lockout over, building KF in flight at {}m climbing {}m/s (from the last {}s)",
                     seed_asl,
                     seed_velocity,
-                    BARO_RING_SPAN_S,
-                    baro_altitude_asl
+                    BARO_RING_SPAN_S
                 );
                 self.kf = Some(BaroAltitudeKF::born_in_flight(seed_asl, seed_velocity));
                 self.stage = Stage::Ascent {
@@ -668,22 +924,28 @@ impl RocketStateEstimator {
                 };
             }
             // Nothing is fused during the lockout, so nothing is rejected.
-            return (None, BaroGateOutcome::Accepted);
+            return (None, BaroGateOutcome::Accepted, health);
         }
 
         // On the pad, also handled before the stage machine, and for the
         // same reason: no filter exists yet. The barometer's only job here
         // is the pad altitude reference, tracked directly.
         if let Stage::OnPad { pad } = &mut self.stage {
-            // Every sample goes in, ungated — see `PadReference`. Nothing
-            // is fused on the pad, so nothing can be rejected here.
-            pad.push(timestamp_us, baro_altitude_asl);
+            // Every voted sample goes in, ungated — see `PadReference`.
+            // Nothing is fused on the pad, so nothing can be rejected here.
+            if let Some(baro_altitude_asl) = baro_altitude_asl {
+                pad.push(timestamp_us, baro_altitude_asl);
+            }
             // Copied out so the borrow of `self.stage` ends here. The
             // fallback is for a launch inside the first two windows, which
             // means an armed board that has been powered for under two
             // seconds; the honest degradation is this one reading, not a
-            // number from a window that does not exist.
-            let pad = pad.reference_asl().unwrap_or(baro_altitude_asl);
+            // number from a window that does not exist. With neither — no
+            // barometer has spoken since power-up — there is no pad to
+            // latch, and the launch waits for the first sample that has one.
+            let Some(pad) = pad.reference_asl().or(baro_altitude_asl) else {
+                return (None, BaroGateOutcome::Accepted, health);
+            };
 
             if accel_says_ignition {
                 log_info!("ignition detected by accel, pad asl={}m", pad);
@@ -719,17 +981,54 @@ impl RocketStateEstimator {
                     }
                 };
             }
-            return (None, BaroGateOutcome::Accepted);
+            return (None, BaroGateOutcome::Accepted, health);
         }
 
         // Past the pad and past the lockout, a filter always exists. If it
         // somehow does not, do nothing rather than panic: this is the code
         // path that fires pyros. Nothing was fused, so nothing was rejected.
+        //
+        // In descent, a vote with no healthy sensor in it is dropped outright
+        // while the GPS is carrying the filter — see `gps_aiding` — rather
+        // than fused as the median of the distrusted. It reports `Accepted`,
+        // like a silent bus: nothing was gated.
+        let baro_voted = health.iter().any(BaroHealth::voted);
+        if let Some(gps) = self.gps.as_mut() {
+            gps.baro_voted = baro_voted;
+        }
+        let gps_carrying = self.descending()
+            && !baro_voted
+            && self
+                .gps
+                .as_ref()
+                .is_some_and(|gps| gps.carrying(timestamp_us));
         let Some(kf) = self.kf.as_mut() else {
-            return (None, BaroGateOutcome::Accepted);
+            return (None, BaroGateOutcome::Accepted, health);
         };
         kf.predict();
-        let gate = kf.update(baro_altitude_asl);
+        // No reading at all coasts exactly as a gate rejection does, but
+        // without counting toward the rejection run: a silent bus says
+        // nothing against the filter.
+        let gate = match baro_altitude_asl {
+            Some(baro_altitude_asl) if !gps_carrying => {
+                #[cfg(feature = "debug-internals")]
+                let (innovation, innovation_variance) = kf.innovation(baro_altitude_asl);
+                let gate = kf.update(baro_altitude_asl);
+                #[cfg(feature = "debug-internals")]
+                {
+                    let (altitude_variance, velocity_variance) = kf.covariance_diagonal();
+                    self.kf_internals = Some(KfInternals {
+                        innovation,
+                        innovation_variance,
+                        altitude_variance,
+                        velocity_variance,
+                        gate,
+                    });
+                }
+                gate
+            }
+            _ => BaroGateOutcome::Accepted,
+        };
         let altitude_asl = kf.altitude_asl();
         let velocity = kf.vertical_velocity();
 
@@ -825,7 +1124,7 @@ impl RocketStateEstimator {
                     deploy_pyro = Some(PyroSelect::PyroMain);
                     self.stage = Stage::MainDeployed {
                         launch_pad_altitude_asl: *launch_pad_altitude_asl,
-                        still_s: 0.0,
+                        landing: LandingWindow::new(),
                     };
                 } else {
                     *remaining_s -= dt;
@@ -833,15 +1132,15 @@ impl RocketStateEstimator {
             }
             Stage::MainDeployed {
                 launch_pad_altitude_asl,
-                still_s,
+                landing,
             } => {
-                if velocity.abs() < LANDED_VELOCITY_THRESHOLD {
-                    *still_s += dt;
-                } else {
-                    *still_s = 0.0;
-                }
-
-                if *still_s >= LANDED_DETECTION_S {
+                if landing.update(
+                    &self.profile.landing,
+                    timestamp_us,
+                    altitude_asl - *launch_pad_altitude_asl,
+                    velocity,
+                    acc,
+                ) {
                     log_info!("landed");
                     self.stage = Stage::Landed {
                         launch_pad_altitude_asl: *launch_pad_altitude_asl,
@@ -851,7 +1150,62 @@ impl RocketStateEstimator {
             Stage::Landed { .. } | Stage::FailedToReachMinApogee { .. } => {}
         }
 
-        (deploy_pyro, gate)
+        (deploy_pyro, gate, health)
+    }
+
+    /// Offer one fresh GPS fix, taken at `timestamp_us` on the clock
+    /// [`Self::update`] reads. Call it after that sample's `update`, and
+    /// only on samples the receiver produced a new fix on: a fix handed in
+    /// twice is fused twice.
+    ///
+    /// Returns what became of it, checked in [`GpsFusion`]'s order: a fix
+    /// that fails the quality gate is `PoorFix` wherever the rocket is, one
+    /// that passes goes into the GPS pad reference on the pad, is only
+    /// watched until the apogee call, and from there is fused on the
+    /// barometer's datum — see [`GpsAiding`]. `NoMeasurement` without
+    /// aiding in the profile.
+    pub fn update_gps(&mut self, timestamp_us: u64, gps: &GPSData) -> GpsFusion {
+        let descending = self.descending();
+        let launch_pad_altitude_asl = self.launch_pad_altitude_asl();
+        let Some(aider) = self.gps.as_mut() else {
+            return GpsFusion::NoMeasurement;
+        };
+        let Some((altitude_msl, variance)) = aider.config.measurement(gps) else {
+            return GpsFusion::PoorFix;
+        };
+        if let Stage::OnPad { .. } = self.stage {
+            aider.pad.push(timestamp_us, altitude_msl);
+            return GpsFusion::PadReference;
+        }
+        if !descending {
+            return GpsFusion::NotDescending;
+        }
+        // Frozen since the rocket left the pad: nothing pushes to it after.
+        let Some(gps_pad_msl) = aider.pad.reference_asl() else {
+            return GpsFusion::NoPadReference;
+        };
+        // A filter exists in every descending stage; see `update`.
+        let Some(kf) = self.kf.as_mut() else {
+            return GpsFusion::NotDescending;
+        };
+        let fusion = kf.update_gps(
+            altitude_msl - gps_pad_msl + launch_pad_altitude_asl,
+            variance,
+            !aider.baro_voted,
+        );
+        if fusion.fused() {
+            aider.last_fused_us = Some(timestamp_us);
+        }
+        fusion
+    }
+
+    /// From the apogee call on: every stage the drogue delay starts, and the
+    /// ones a failed apogee ends in.
+    fn descending(&self) -> bool {
+        !matches!(
+            self.stage,
+            Stage::OnPad { .. } | Stage::Ascent { .. } | Stage::MachLockout { .. }
+        )
     }
 
     pub fn state(&self) -> RocketState {
@@ -966,6 +1320,21 @@ impl RocketStateEstimator {
         self.kf.as_ref().map(|kf| kf.vertical_velocity())
     }
 
+    /// The innovation, its variance, the covariance diagonal and the gate's
+    /// verdict from the last [`Self::update`]'s baro measurement, or `None`
+    /// if no reading reached the filter on that sample.
+    ///
+    /// A field read back after the call, where the gate outcome is a return
+    /// value — against [`crate::BaroGateOutcome`]'s own argument — only
+    /// because a feature cannot change a signature: `update`'s callers are
+    /// built with and without `debug-internals` in one workspace. So the
+    /// field is cleared at the top of every `update` and can never describe
+    /// an older sample than the last one.
+    #[cfg(feature = "debug-internals")]
+    pub fn kf_internals(&self) -> Option<KfInternals> {
+        self.kf_internals
+    }
+
     /// Launch pad altitude ASL (m), available in every stage: while on the
     /// pad this is [`PadReference`]'s current mean — one second of
     /// barometer, stepping once a second, from a window that ended a second
@@ -82,7 +82,11 @@ fn simulate_flight(
     let mut sample_i = 0usize;
 
     let mut feed = |estimator: &mut RocketStateEstimator, altitude_asl: f32, specific_force: f32| {
-        let (pyro, _gate) = estimator.update(clock.tick(), sf(specific_force), altitude_asl + noise.next());
+        let (pyro, _gate, _health) = estimator.update(
+            clock.tick(),
+            sf(specific_force),
+            &[Some(altitude_asl + noise.next())],
+        );
         // Pyros only fire in chute stages, where the KF is live, so the
         // altitude is present on exactly the samples recorded below. A
         // `None` at a fire would mean a pyro went off inside the Mach
@@ -100,7 +104,7 @@ fn simulate_flight(
                 assert!(main.is_none(), "main fired more than once");
                 main = Some((sample_i, kf_agl.expect("no KF altitude at main fire")));
             }
-            None => {}
+            _ => {}
         }
         sample_i += 1;
     };
@@ -232,6 +236,82 @@ fn single_deploys_both_near_apogee() {
     assert!(matches!(result.final_state, RocketState::Landed));
 }
 
+/// One barometer freezing mid-coast. On its own it holds the altitude flat
+/// through apogee and the drogue never fires; voted against two honest ones
+/// it is flagged within a second and the drogue fires as if it were not
+/// there.
+#[test]
+fn a_stuck_barometer_is_outvoted() {
+    const PAD_ASL: f32 = 200.0;
+    const STUCK_FROM_S: f32 = 10.0;
+
+    /// (drogue time, apogee time, the vote's verdicts at the drogue or at
+    /// touchdown), in seconds from ignition.
+    fn fly(sensors: usize) -> (Option<f32>, f32, [BaroHealth; MAX_BAROS]) {
+        let mut estimator = RocketStateEstimator::new(subsonic_profile(), IGNITION_ACC_THRESHOLD);
+        let mut clock = SampleClock::new();
+        let mut noise = [
+            NoiseGen::new(0.5),
+            NoiseGen { state: 777, std: 0.5 },
+            NoiseGen { state: 4242, std: 0.5 },
+        ];
+        for _ in 0..(5 * SAMPLES_PER_S) {
+            let readings: [Option<f32>; 3] =
+                core::array::from_fn(|i| (i < sensors).then(|| PAD_ASL + noise[i].next()));
+            estimator.update(clock.tick(), sf(PAD_SF), &readings);
+        }
+
+        let (mut altitude_asl, mut velocity, mut t) = (PAD_ASL, 0.0f32, 0.0f32);
+        let mut apogee_s = 0.0;
+        let mut stuck_at = None;
+        let mut health = [BaroHealth::Absent; MAX_BAROS];
+        while altitude_asl >= PAD_ASL {
+            let burning = t < 3.0;
+            velocity += if burning { 80.0 } else { -9.81 } * DT;
+            altitude_asl += velocity * DT;
+            t += DT;
+            if velocity > 0.0 {
+                apogee_s = t;
+            }
+            // Sensor 0 freezes on whatever it read at STUCK_FROM_S.
+            let readings: [Option<f32>; 3] = core::array::from_fn(|i| {
+                (i < sensors).then(|| {
+                    let honest = altitude_asl + noise[i].next();
+                    if i == 0 && t >= STUCK_FROM_S {
+                        *stuck_at.get_or_insert(honest)
+                    } else {
+                        honest
+                    }
+                })
+            });
+            let specific_force = if burning { 80.0 + 9.81 } else { 0.0 };
+            let (pyro, _gate, verdicts) =
+                estimator.update(clock.tick(), sf(specific_force), &readings);
+            health = verdicts;
+            if matches!(pyro, Some(PyroSelect::PyroDrogue)) {
+                return (Some(t), apogee_s, health);
+            }
+        }
+        (None, apogee_s, health)
+    }
+
+    let (drogue, apogee_s, health) = fly(1);
+    assert_eq!(drogue, None, "a lone stuck barometer fired the drogue");
+    assert_eq!(health[0], BaroHealth::Stuck);
+
+    let (drogue, apogee_s_voted, health) = fly(3);
+    assert_eq!(apogee_s, apogee_s_voted);
+    let drogue = drogue.expect("the drogue never fired with two honest barometers");
+    assert!(
+        drogue > apogee_s && drogue - apogee_s < 5.0,
+        "drogue at {drogue:.2}s, apogee at {apogee_s:.2}s"
+    );
+    assert_eq!(
+        health,
+        [BaroHealth::Stuck, BaroHealth::Healthy, BaroHealth::Healthy]
+    );
+}
+
 #[test]
 fn below_min_apogee_does_not_deploy() {
     let mut estimator = RocketStateEstimator::new(
@@ -267,7 +347,7 @@ fn no_false_ignition_on_pad() {
 
     // 2 minutes armed on the pad
     for _ in 0..(120 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), sf(PAD_SF), 200.0 + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(200.0 + noise.next())]);
     }
     assert!(matches!(estimator.state(), RocketState::OnPad));
 }
@@ -300,7 +380,7 @@ fn pad_reference_excludes_the_second_before_ignition() {
     // downstream reads this as "absent means no estimator sample", so a
     // young session must not look like a pad at sea level.
     for _ in 0..(SAMPLES_PER_S / 5) {
-        estimator.update(clock.tick(), sf(PAD_SF), PAD_ASL + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(PAD_ASL + noise.next())]);
     }
     let young = estimator.launch_pad_altitude_asl();
     assert!(
@@ -310,7 +390,7 @@ fn pad_reference_excludes_the_second_before_ignition() {
 
     // 30 s of quiet rail.
     for _ in 0..(30 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), sf(PAD_SF), PAD_ASL + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(PAD_ASL + noise.next())]);
     }
     let quiet = estimator.launch_pad_altitude_asl();
     assert!(
@@ -323,7 +403,7 @@ fn pad_reference_excludes_the_second_before_ignition() {
         estimator.update(
             clock.tick(),
             sf(PAD_SF),
-            PAD_ASL + DISTURBANCE_M + noise.next(),
+            &[Some(PAD_ASL + DISTURBANCE_M + noise.next())],
         );
     }
 
@@ -335,7 +415,7 @@ fn pad_reference_excludes_the_second_before_ignition() {
         estimator.update(
             clock.tick(),
             sf(20.0 * 9.81),
-            PAD_ASL + DISTURBANCE_M + noise.next(),
+            &[Some(PAD_ASL + DISTURBANCE_M + noise.next())],
         );
         i += 1;
         assert!(i < SAMPLES_PER_S, "ignition never latched at 20 g");
@@ -362,7 +442,7 @@ fn blast_transient_during_coast_does_not_deploy_early() {
     let pad = 200.0f32;
 
     for _ in 0..(30 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), sf(PAD_SF), pad + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(pad + noise.next())]);
     }
 
     // 2 s burn at 80 m/s^2 -> 160 m/s; apogee ~16.3 s after burnout
@@ -403,7 +483,8 @@ fn blast_transient_during_coast_does_not_deploy_early() {
         } else {
             0.0
         };
-        let (pyro, _gate) = estimator.update(clock.tick(), sf(specific_force), measured);
+        let (pyro, _gate, _health) =
+            estimator.update(clock.tick(), sf(specific_force), &[Some(measured)]);
         if let Some(PyroSelect::PyroDrogue) = pyro {
             assert!(drogue_i.is_none());
             drogue_i = Some((i, altitude_asl - pad));
@@ -452,7 +533,7 @@ fn mach_lockout_survives_supersonic_garbage() {
     let pad = 200.0f32;
 
     for _ in 0..(30 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), sf(PAD_SF), pad + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(pad + noise.next())]);
     }
     assert!(matches!(estimator.state(), RocketState::OnPad));
 
@@ -491,7 +572,8 @@ fn mach_lockout_survives_supersonic_garbage() {
         } else {
             0.0
         };
-        let (pyro, _gate) = estimator.update(clock.tick(), sf(specific_force), measured);
+        let (pyro, _gate, _health) =
+            estimator.update(clock.tick(), sf(specific_force), &[Some(measured)]);
         // While locked out the estimator must say so honestly: the reported
         // state is MachLockout carrying only the pad altitude — the frozen
         // KF altitude/velocity are not reachable through `state()` at all.
@@ -542,7 +624,7 @@ fn mach_lockout_survives_supersonic_garbage() {
 
     // 10 s on the ground -> landed
     for _ in 0..(10 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), sf(PAD_SF), pad + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(pad + noise.next())]);
     }
     assert!(matches!(estimator.state(), RocketState::Landed));
 }
@@ -573,7 +655,7 @@ fn kf_accessors_absent_before_birth_and_during_lockout() {
     let mut noise = NoiseGen::new(0.5);
     let pad = 200.0f32;
     for _ in 0..(30 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), sf(PAD_SF), pad + noise.next());
+        estimator.update(clock.tick(), sf(PAD_SF), &[Some(pad + noise.next())]);
     }
 
     // There is no filter on the pad either: half a minute of samples in, the
@@ -596,7 +678,7 @@ fn kf_accessors_absent_before_birth_and_during_lockout() {
     while !matches!(estimator.state(), RocketState::MachLockout { .. }) {
         velocity += 80.0 * DT;
         altitude_asl += velocity * DT;
-        estimator.update(clock.tick(), sf(80.0 + 9.81), altitude_asl + noise.next());
+        estimator.update(clock.tick(), sf(80.0 + 9.81), &[Some(altitude_asl + noise.next())]);
         assert!(
             altitude_asl - pad < 1000.0,
             "lockout never engaged by {}m agl",
@@ -609,7 +691,7 @@ fn kf_accessors_absent_before_birth_and_during_lockout() {
     for _ in 0..SAMPLES_PER_S {
         velocity += 80.0 * DT;
         altitude_asl += velocity * DT;
-        estimator.update(clock.tick(), sf(80.0 + 9.81), altitude_asl + noise.next());
+        estimator.update(clock.tick(), sf(80.0 + 9.81), &[Some(altitude_asl + noise.next())]);
         assert!(matches!(estimator.state(), RocketState::MachLockout { .. }));
         assert_eq!(estimator.kf_altitude_asl(), None);
         assert_eq!(estimator.kf_vertical_velocity(), None);
@@ -720,7 +802,7 @@ fn void_lake_flight_replay() {
     let mut drogue = None;
     let mut main = None;
     for (i, &(alt, acc)) in grid.iter().enumerate() {
-        let (pyro, _gate) = estimator.update(clock.tick(), acc, alt);
+        let (pyro, _gate, _health) = estimator.update(clock.tick(), acc, &[Some(alt)]);
         assert!(!matches!(
             estimator.state(),
             RocketState::MachLockout { .. }
@@ -737,7 +819,7 @@ fn void_lake_flight_replay() {
                 assert!(main.is_none());
                 main = Some((i, alt));
             }
-            None => {}
+            _ => {}
         }
     }
     // The log ends shortly after touchdown; keep feeding the final altitude
@@ -745,7 +827,7 @@ fn void_lake_flight_replay() {
     let (last_alt, last_acc) = *grid.last().unwrap();
     let mut noise = NoiseGen::new(0.5);
     for _ in 0..(15 * SAMPLES_PER_S) {
-        estimator.update(clock.tick(), last_acc, last_alt + noise.next());
+        estimator.update(clock.tick(), last_acc, &[Some(last_alt + noise.next())]);
     }
 
     let ignition_i = ignition_i.expect("ignition never detected");
@@ -968,10 +1050,10 @@ fn fly_at_rate(hz: f64, profile: FlightProfile, flight: &AnalyticFlight) -> Even
         // would shave half a microsecond off every measured dt — 2.3 ms
         // over a 12 s lockout, which is the test injecting a bias rather
         // than measuring one.
-        let (pyro, _gate) = estimator.update(
+        let (pyro, _gate, _health) = estimator.update(
             (ns + 500) / 1000,
             sf(flight.specific_force(t)),
-            flight.altitude_asl(t) + noise.next(),
+            &[Some(flight.altitude_asl(t) + noise.next())],
         );
 
         match estimator.state() {
@@ -991,7 +1073,7 @@ fn fly_at_rate(hz: f64, profile: FlightProfile, flight: &AnalyticFlight) -> Even
         match pyro {
             Some(PyroSelect::PyroDrogue) => set(&mut ev.drogue_fire, t),
             Some(PyroSelect::PyroMain) => set(&mut ev.main_fire, t),
-            None => {}
+            _ => {}
         }
         ns += dt_ns;
     }
@@ -1092,8 +1174,8 @@ fn timers_are_independent_of_the_sample_rate() {
         landed.push(ld);
     }
 
-    // The landed latch is a pure 5 s timer, but what it counts from is the
-    // KF's velocity settling under the threshold — and the KF is
+    // The landed latch is one whole 5 s window, but what it counts from is
+    // the KF's velocity settling under the threshold — and the KF is
     // sample-clocked, so this is where the split's cost shows up.
     //
     // Touchdown happens at the same wall-clock instant at every rate, but
@@ -1113,6 +1195,79 @@ fn timers_are_independent_of_the_sample_rate() {
     );
 }
 
+/// Landing takes the ground, a standing filter and a quiet accelerometer,
+/// all three for one whole window.
+///
+/// Each failing case is paired with the one it differs from: the quiet
+/// landing is the control, so "never landed" below means the condition
+/// under test held it off and not that the flight never got down. A silent
+/// IMU is the other direction — it must not hold a flight open forever,
+/// because the firmware closes its log on this call.
+#[test]
+fn landing_waits_for_the_ground_and_a_still_airframe() {
+    let flight = AnalyticFlight { pad_asl: 200.0 };
+    let window_s = LandingDetection::default().window_us as f32 * 1e-6;
+
+    // Fly the analytic descent down to `stop_agl`, then hold there for
+    // `after_s` with the accelerometer reading `ground(s since stopping)`.
+    // Returns how long after stopping the estimator called it landed.
+    let fly = |stop_agl: f32, ground: &dyn Fn(f32) -> Option<Vector3<f32>>, after_s: f32| {
+        let mut estimator = RocketStateEstimator::new(subsonic_profile(), IGNITION_ACC_THRESHOLD);
+        let mut noise = NoiseGen::new(0.5);
+        let mut clock = SampleClock::new();
+        let stop = flight.touchdown_s() - stop_agl / AnalyticFlight::TERMINAL_V;
+        loop {
+            let t_us = clock.tick();
+            let t = t_us as f32 * 1e-6;
+            if t > stop + after_s {
+                return None;
+            }
+            let (acc, altitude_asl) = if t < stop {
+                (sf(flight.specific_force(t)), flight.altitude_asl(t))
+            } else {
+                (ground(t - stop), flight.pad_asl + stop_agl)
+            };
+            estimator.update(t_us, acc, &[Some(altitude_asl + noise.next())]);
+            if matches!(estimator.state(), RocketState::Landed) {
+                return Some(t - stop);
+            }
+        }
+    };
+
+    let quiet = fly(0.0, &|_| sf(PAD_SF), 60.0).expect("a quiet landing never landed");
+    assert!(
+        quiet > window_s && quiet < window_s + 8.0,
+        "quiet landing called {quiet:.2} s after touchdown"
+    );
+
+    // Swinging on the canopy's lines — on the ground as far as the
+    // barometer can tell — for 20 s, then lying still. The window running
+    // when the swing stops still holds it, so it is the next one that lands.
+    let swinging = |s: f32| {
+        let swing = if s < 20.0 {
+            3.0 * (core::f32::consts::TAU * 0.5 * s).sin()
+        } else {
+            0.0
+        };
+        Some(Vector3::new(swing, 0.0, PAD_SF))
+    };
+    let swung = fly(0.0, &swinging, 60.0).expect("never landed once the swing stopped");
+    assert!(
+        swung > 20.0 + window_s && swung <= 20.0 + 2.0 * window_s + 0.1,
+        "swinging landing called {swung:.2} s after touchdown"
+    );
+
+    // Standing still, quietly, 300 m above the pad: not the ground.
+    assert_eq!(fly(300.0, &|_| sf(PAD_SF), 60.0), None);
+
+    // No accelerometer at all from touchdown on says nothing against it.
+    let silent = fly(0.0, &|_| None, 60.0).expect("a silent IMU held the flight open");
+    assert!(
+        silent > window_s && silent < window_s + 8.0,
+        "silent-IMU landing called {silent:.2} s after touchdown"
+    );
+}
+
 /// A gap in the sample stream is real elapsed time, and the timers count
 /// it. This is the case the sample-counted machine could not get right: a
 /// delay only advanced when a sample arrived, so half a second of lost
@@ -1161,10 +1316,10 @@ fn a_sensor_stall_does_not_stretch_a_pyro_delay() {
             }
         }
 
-        let (pyro, _gate) = estimator.update(
+        let (pyro, _gate, _health) = estimator.update(
             t_us,
             sf(flight.specific_force(t)),
-            flight.altitude_asl(t) + noise.next(),
+            &[Some(flight.altitude_asl(t) + noise.next())],
         );
 
         if matches!(
@@ -1217,7 +1372,7 @@ fn ignition_comes_from_the_accelerometer_alone() {
             } else {
                 None
             };
-            estimator.update(t_us, acc, flight.altitude_asl(t) + noise.next());
+            estimator.update(t_us, acc, &[Some(flight.altitude_asl(t) + noise.next())]);
             if !matches!(estimator.state(), RocketState::OnPad) {
                 return Some(t);
             }
@@ -1280,7 +1435,7 @@ fn a_knock_on_the_pad_does_not_latch_ignition() {
         } else {
             Vector3::new(0.0, 0.0, 9.81)
         };
-        estimator.update(t_us, Some(acc), flight.altitude_asl(t) + noise.next());
+        estimator.update(t_us, Some(acc), &[Some(flight.altitude_asl(t) + noise.next())]);
 
         // Mirror of the estimator's low pass, to show the transient really
         // does cross the threshold and that the sustain is what holds.
@@ -1394,7 +1549,8 @@ fn a_garbage_sample_at_lockout_exit_does_not_fire_the_drogue() {
             } else {
                 honest
             };
-            let (pyro, _gate) = estimator.update(t_us, sf(flight.specific_force(t)), measured);
+            let (pyro, _gate, _health) =
+                estimator.update(t_us, sf(flight.specific_force(t)), &[Some(measured)]);
 
             match estimator.state() {
                 RocketState::MachLockout { .. } => in_lockout = true,
@@ -1447,3 +1603,230 @@ fn a_garbage_sample_at_lockout_exit_does_not_fire_the_drogue() {
         dirty_t - clean_t
     );
 }
+
+/// The receiver's datum against the barometer's for the GPS tests below:
+/// MSL sits 27 m under the standard day's pressure altitude. Any number
+/// would do, as long as it is not zero — a GPS fused without the pad offset
+/// is 27 m out on every fix.
+const GPS_DATUM_OFFSET_M: f32 = -27.0;
+
+/// A fix the default [`GpsAiding`] gate passes: ten satellites, good
+/// geometry.
+fn good_fix(altitude_msl: f32) -> GPSData {
+    GPSData {
+        timestamp: None,
+        lat_lon: None,
+        gps_altitude_asl: Some(altitude_msl),
+        num_of_fix_satellites: 10,
+        hdop: Some(0.9),
+        vdop: Some(1.4),
+        pdop: Some(1.7),
+    }
+}
+
+/// A ~1900 m dual-deploy flight whose one barometer sticks 3 s after the
+/// drogue, with a 5 Hz receiver beside it. Returns the true AGL the main
+/// fired at, if it fired before the ground, and how many fixes were fused.
+fn fly_with_stuck_baro(gps_aiding: Option<GpsAiding>) -> (Option<f32>, u32) {
+    let (main_agl, fused, _) = fly_with_gps(gps_aiding, true, 0.0);
+    (main_agl, fused)
+}
+
+/// The flight [`fly_with_stuck_baro`] flies, with the sticking optional and
+/// every fix from the drogue on `gps_bias_m` off the truth. Also returns
+/// how many fixes resynced the filter.
+fn fly_with_gps(
+    gps_aiding: Option<GpsAiding>,
+    baro_sticks: bool,
+    gps_bias_m: f32,
+) -> (Option<f32>, u32, u32) {
+    let pad_asl = 1000.0;
+    let mut estimator = RocketStateEstimator::new(
+        FlightProfile {
+            deployment: DeploymentProfile::Dual {
+                drogue_chute_minimum_altitude_agl: 1000.0,
+                drogue_chute_delay_us: 1_000_000,
+                main_chute_altitude_agl: 400.0,
+                main_chute_delay_us: 0,
+            },
+            gps_aiding,
+            ..subsonic_profile()
+        },
+        IGNITION_ACC_THRESHOLD,
+    );
+    let mut clock = SampleClock::new();
+    let mut baro_noise = NoiseGen::new(0.5);
+    let mut gps_noise = NoiseGen {
+        state: 777,
+        std: 3.0,
+    };
+
+    // 20 s on the pad, a 3 s burn at 6 g, a ballistic coast, and 25 m/s
+    // under the drogue from whenever the fall reaches it.
+    let mut t = -20.0f32;
+    let mut altitude_agl = 0.0f32;
+    let mut velocity = 0.0f32;
+    let mut next_fix_t = t;
+    let mut drogue_t = None;
+    let mut stuck_reading = None;
+    let mut fused = 0;
+    let mut resynced = 0;
+    loop {
+        let (acceleration, specific_force) = if t < 0.0 {
+            (0.0, PAD_SF)
+        } else if t < 3.0 {
+            (60.0, 60.0 + 9.81)
+        } else if velocity > -25.0 {
+            (-9.81, 0.0)
+        } else {
+            (0.0, 9.81)
+        };
+        if t >= 0.0 {
+            velocity = (velocity + acceleration * DT).max(-25.0);
+            altitude_agl += velocity * DT;
+        }
+        if altitude_agl < 0.0 {
+            return (None, fused, resynced);
+        }
+        let now_us = clock.tick();
+        t += DT;
+
+        let baro = match stuck_reading {
+            Some(reading) => reading,
+            None => pad_asl + altitude_agl + baro_noise.next(),
+        };
+        if baro_sticks && drogue_t.is_some_and(|drogue_t| t - drogue_t >= 3.0) {
+            stuck_reading.get_or_insert(baro);
+        }
+        let (pyro, _gate, _health) = estimator.update(now_us, sf(specific_force), &[Some(baro)]);
+        if t >= next_fix_t {
+            next_fix_t += 0.2;
+            let bias = if drogue_t.is_some() { gps_bias_m } else { 0.0 };
+            let fix =
+                good_fix(pad_asl + GPS_DATUM_OFFSET_M + altitude_agl + bias + gps_noise.next());
+            let fusion = estimator.update_gps(now_us, &fix);
+            if fusion.fused() {
+                fused += 1;
+            }
+            if fusion == GpsFusion::Resynced {
+                resynced += 1;
+            }
+        }
+        match pyro {
+            Some(PyroSelect::PyroDrogue) => drogue_t = Some(t),
+            Some(PyroSelect::PyroMain) => return (Some(altitude_agl), fused, resynced),
+            _ => {}
+        }
+    }
+}
+
+/// The case GPS aiding exists for. Baro-only, a port that sticks under the
+/// drogue holds the filter where it stuck and the main never fires; with a
+/// receiver, the stuck sensor is dropped once the vote flags it and the
+/// fixes carry the filter down to the main altitude.
+#[test]
+fn gps_fires_the_main_past_a_stuck_barometer() {
+    let (baro_only, _) = fly_with_stuck_baro(None);
+    assert_eq!(
+        baro_only, None,
+        "the main fired baro-only at {baro_only:?} m: the stuck port is not stuck"
+    );
+
+    let (main_agl, fused) = fly_with_stuck_baro(Some(GpsAiding::default()));
+    let main_agl = main_agl.expect("no main with GPS aiding: the receiver never took over");
+    eprintln!("stuck baro, GPS aided: main at {main_agl:.1} m true AGL, {fused} fixes fused");
+    assert!(
+        (main_agl - 400.0).abs() < 30.0,
+        "the main fired at {main_agl:.1} m true AGL against 400 m: the GPS \
+         altitude is not on the barometer's datum"
+    );
+}
+
+/// A receiver that reads 200 m low under the drogue — a bias, or multipath
+/// — next to a barometer that is fine. Every fix fails the gate, and with
+/// the baro voted healthy none of them may resync the filter onto the GPS:
+/// a jump 200 m down would fire the main 200 m early.
+#[test]
+fn a_biased_gps_does_not_resync_over_a_healthy_barometer() {
+    let (main_agl, fused, resynced) = fly_with_gps(Some(GpsAiding::default()), false, -200.0);
+    let main_agl = main_agl.expect("no main: the biased GPS held the filter up");
+    eprintln!(
+        "healthy baro, GPS 200 m low: main at {main_agl:.1} m true AGL, {fused} fixes \
+         fused, {resynced} resyncs"
+    );
+    assert_eq!(resynced, 0, "the GPS resynced the filter over a healthy baro");
+    assert!(
+        (main_agl - 400.0).abs() < 30.0,
+        "the main fired at {main_agl:.1} m true AGL against 400 m: the biased \
+         GPS moved the filter"
+    );
+}
+
+/// Every decision before the filter is asked, in [`GpsFusion`]'s order.
+#[test]
+fn gps_decisions_before_fusion() {
+    let profile = FlightProfile {
+        gps_aiding: Some(GpsAiding::default()),
+        ..subsonic_profile()
+    };
+    let mut clock = SampleClock::new();
+
+    // Without aiding in the profile a fix is not a measurement at all.
+    let mut unaided = RocketStateEstimator::new(subsonic_profile(), IGNITION_ACC_THRESHOLD);
+    assert_eq!(
+        unaided.update_gps(clock.tick(), &good_fix(1000.0)),
+        GpsFusion::NoMeasurement
+    );
+
+    let mut estimator = RocketStateEstimator::new(profile.clone(), IGNITION_ACC_THRESHOLD);
+    let _ = estimator.update(clock.tick(), sf(PAD_SF), &[Some(1000.0)]);
+    // The quality gate comes first, wherever the rocket is. A field the
+    // receiver left blank fails it like a bad one.
+    let few_satellites = GPSData {
+        num_of_fix_satellites: 4,
+        ..good_fix(1000.0)
+    };
+    let no_vdop = GPSData {
+        vdop: None,
+        ..good_fix(1000.0)
+    };
+    assert_eq!(estimator.update_gps(clock.tick(), &few_satellites), GpsFusion::PoorFix);
+    assert_eq!(estimator.update_gps(clock.tick(), &no_vdop), GpsFusion::PoorFix);
+    assert_eq!(
+        estimator.update_gps(clock.tick(), &good_fix(1000.0)),
+        GpsFusion::PadReference
+    );
+
+    // Lit: the filter is climbing, and a fix is only watched.
+    for _ in 0..SAMPLES_PER_S / 2 {
+        let _ = estimator.update(clock.tick(), sf(60.0), &[Some(1000.0)]);
+    }
+    assert!(matches!(estimator.state(), RocketState::Ascent { .. }));
+    assert_eq!(
+        estimator.update_gps(clock.tick(), &good_fix(1000.0)),
+        GpsFusion::NotDescending
+    );
+
+    // A flight whose pad saw no good fix has nothing to put one on the
+    // barometer's datum with. Flown here to the apogee call on a baro that
+    // climbs 100 m and falls back.
+    let mut no_pad = RocketStateEstimator::new(profile, IGNITION_ACC_THRESHOLD);
+    let mut clock = SampleClock::new();
+    for _ in 0..SAMPLES_PER_S {
+        let _ = no_pad.update(clock.tick(), sf(PAD_SF), &[Some(1000.0)]);
+    }
+    for i in 0..20 * SAMPLES_PER_S {
+        let t = i as f32 * DT;
+        let altitude = 1000.0 + 100.0 - 4.0 * (t - 5.0) * (t - 5.0);
+        let specific_force = if t < 0.5 { 60.0 } else { 0.0 };
+        let _ = no_pad.update(clock.tick(), sf(specific_force), &[Some(altitude.max(1000.0))]);
+        if !matches!(no_pad.state(), RocketState::OnPad | RocketState::Ascent { .. }) {
+            break;
+        }
+    }
+    assert!(!matches!(no_pad.state(), RocketState::OnPad | RocketState::Ascent { .. }));
+    assert_eq!(
+        no_pad.update_gps(clock.tick(), &good_fix(1050.0)),
+        GpsFusion::NoPadReference
+    );
+}
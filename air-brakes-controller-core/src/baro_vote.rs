@@ -0,0 +1,423 @@
+//! The barometer vote: N readings in, one altitude out, and a verdict on
+//! every sensor that went into it.
+//!
+//! VLF5 has its own MS5607 and every other node with a barometer publishes
+//! a `BaroMeasurementMessage` on the bus, so a flight carries two or three
+//! static-port readings per sample. Until this module the estimators read
+//! exactly one of them, which made that one sensor a single point of
+//! failure for recovery in both directions: stuck, it holds the altitude
+//! flat and apogee never reads as a drop; failed to a garbage value, it
+//! drags the filter wherever the garbage points once the innovation gate
+//! gives up and resyncs to it.
+//!
+//! Three checks, each on one sensor's own history, and then a vote:
+//!
+//! * **Stuck** — the reading has not changed by a single bit for
+//!   [`STUCK_S`] and at least [`STUCK_MIN_READINGS`] readings. An honest
+//!   MS5607 cannot do that even sitting on the pad: its noise is twice its
+//!   resolution.
+//! * **Noisy** — the scatter of the sensor's second difference, low-passed
+//!   over [`NOISE_TAU_S`], is above [`NOISY_M`]. A second difference and
+//!   not a first, so that a climb at any constant rate reads as zero and a
+//!   boost barely registers; what is left is the sensor.
+//! * **Outlier** — the sensor passed both of the above and still sits more
+//!   than [`OUTLIER_M`] from the others. See [`BaroVoter::vote`] for how
+//!   "the others" is decided when there are only two.
+//!
+//! The vote is then the median of the sensors that passed all three. A
+//! median and not a weighted mean, for the reason the Mach-lockout ring uses
+//! one: it needs no weights, no threshold of its own, and a bad sensor has
+//! to outvote the good ones to move it, not merely outweigh them.
+//!
+//! **When every sensor is flagged, the vote is the median of all of them.**
+//! That is the one rule that keeps a single-barometer board exactly what it
+//! was before this module existed: its one sensor is fused whatever the
+//! checks say of it, with the verdict logged beside it, and the innovation
+//! gate behind the vote is the defence it always was. It is also what the
+//! transonic regime and an ejection charge look like on any board — every
+//! port disturbed at once — and there the median of everything is the best
+//! answer available, not a failure.
+//!
+//! A sensor with no reading on a sample is `Absent` and nothing more: its
+//! history is left exactly where it was, because a message that did not
+//! arrive is not evidence about the port. One instance per estimator half,
+//! for the reason [`crate::ignition_detector`] gives — same code, separate
+//! histories, so neither half's verdicts can be the other's.
+
+use firmware_common_new::flight_data_record::{BARO_HEALTH_SLOTS, BaroHealth};
+
+/// The most barometers a vote takes. Readings past this many are ignored —
+/// it is the number of slots the SD record has to log a verdict in, and a
+/// sensor the log cannot account for must not be able to decide a pyro.
+pub const MAX_BAROS: usize = BARO_HEALTH_SLOTS;
+
+/// How long a bit-identical run of readings has to last before the sensor
+/// is judged stuck (s of measured time).
+///
+/// The MS5607 at OSR=512 resolves ~0.35 m and scatters ~0.67 m std, so two
+/// equal readings in a row are common and a second of them is not: at
+/// 416 Hz that is hundreds of coin flips that all came up the same. A
+/// second is also short against anything it protects — the apogee drop
+/// test alone sustains for half that, after a 30 m drop the slow filter
+/// takes seconds to develop.
+const STUCK_S: f32 = 1.0;
+
+/// The fewest identical readings that can be called stuck, whatever the
+/// clock says. A bus barometer publishing at 10 Hz fits ten readings in
+/// [`STUCK_S`], and ten repeats out of a slow, quiet sensor on a still pad
+/// are merely unlikely; twenty are not going to happen.
+const STUCK_MIN_READINGS: u32 = 20;
+
+/// Time constant of the noise estimate (s of measured time) — long enough
+/// that one bad read is a blip in it rather than the whole of it, short
+/// enough that a sensor which recovers is trusted again within seconds.
+const NOISE_TAU_S: f32 = 0.5;
+
+/// Scatter, in the sensor's own standard deviation, above which it is
+/// noisy (m). Seven times the part's bench noise: comfortably past anything
+/// it produces honestly, far below the hundreds of metres a failing read or
+/// a struck port produces.
+///
+/// One 30 km read (pressure ~0) lands in three second differences and lifts
+/// the estimate to ~2 km, flagging the sensor on the sample it arrives and
+/// holding it out for ~6 s while it decays — long enough to be sure it was
+/// one read, and short against anything in the flight that reads it.
+const NOISY_M: f32 = 5.0;
+
+/// Disagreement with the other sensors beyond which a sensor is an outlier
+/// (m).
+///
+/// Sized against the honest spread, not the failure: sensors on different
+/// nodes are calibrated separately and sit at different ports, which is
+/// ~10-20 m on the pad and more through the boost, and an outlier verdict
+/// must not fall on one of them for being the end of the spread. Anything a
+/// failure produces — a factor-of-2 pressure error is kilometres — is far
+/// past it.
+const OUTLIER_M: f32 = 100.0;
+
+/// One vote: the altitude the estimator should read, and why.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaroVote {
+    /// The voted altitude (m, in whatever frame the readings were in).
+    /// `None` only when no sensor had a reading on this sample.
+    pub altitude_asl: Option<f32>,
+    /// One verdict per slot, in the order the readings were handed over.
+    pub health: [BaroHealth; MAX_BAROS],
+}
+
+/// One sensor's history, for the checks that need more than one reading.
+#[derive(Debug, Clone, Copy)]
+struct SensorTrack {
+    /// The last two readings as (timestamp_us, altitude), newest first.
+    /// Only readings that arrived are kept, so the second difference spans
+    /// whatever gap the sensor's own rate leaves.
+    last: Option<(u64, f32)>,
+    before_last: Option<(u64, f32)>,
+    /// Low-passed square of the second difference (m^2).
+    second_diff_sq: f32,
+    /// Start of the current run of bit-identical readings, and its length.
+    same_since_us: u64,
+    same_count: u32,
+}
+
+impl SensorTrack {
+    const fn new() -> Self {
+        Self {
+            last: None,
+            before_last: None,
+            second_diff_sq: 0.0,
+            same_since_us: 0,
+            same_count: 0,
+        }
+    }
+
+    /// Feed one reading and return what the sensor's own history says of
+    /// it — `Healthy` here meaning only "not stuck and not noisy"; the
+    /// outlier check needs the other sensors and happens in the vote.
+    fn push(&mut self, timestamp_us: u64, altitude: f32) -> BaroHealth {
+        match self.last {
+            Some((_, last)) if last.to_bits() == altitude.to_bits() => self.same_count += 1,
+            _ => {
+                self.same_since_us = timestamp_us;
+                self.same_count = 1;
+            }
+        }
+
+        // Second difference across unevenly spaced readings: the departure
+        // of this reading from the straight line through the previous two.
+        // Exactly zero for any constant rate of climb, whatever the gaps.
+        if let (Some((t1, x1)), Some((t0, x0))) = (self.last, self.before_last) {
+            let dt_new = (timestamp_us.saturating_sub(t1)) as f32 * 1e-6;
+            let dt_old = (t1.saturating_sub(t0)) as f32 * 1e-6;
+            if dt_old > 0.0 {
+                let second_diff = (altitude - x1) - (x1 - x0) * (dt_new / dt_old);
+                // One pole on the measured dt, clamped at alpha = 1, as in
+                // the ignition detector.
+                let alpha = (dt_new / NOISE_TAU_S).min(1.0);
+                self.second_diff_sq += alpha * (second_diff * second_diff - self.second_diff_sq);
+            }
+        }
+        self.before_last = self.last;
+        self.last = Some((timestamp_us, altitude));
+
+        let stuck = self.same_count >= STUCK_MIN_READINGS
+            && (timestamp_us.saturating_sub(self.same_since_us)) as f32 * 1e-6 >= STUCK_S;
+        // White noise of std s has a second difference of variance 6 s^2
+        // (1 + 4 + 1), so this compares against the sensor's own scatter.
+        // Squared on both sides: no square root on this path.
+        let noisy = self.second_diff_sq > 6.0 * NOISY_M * NOISY_M;
+        if stuck {
+            BaroHealth::Stuck
+        } else if noisy {
+            BaroHealth::Noisy
+        } else {
+            BaroHealth::Healthy
+        }
+    }
+}
+
+/// The vote and every sensor's history behind it.
+#[derive(Debug, Clone)]
+pub struct BaroVoter {
+    tracks: [SensorTrack; MAX_BAROS],
+}
+
+impl Default for BaroVoter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BaroVoter {
+    pub const fn new() -> Self {
+        Self {
+            tracks: [SensorTrack::new(); MAX_BAROS],
+        }
+    }
+
+    /// Vote one sample's readings, taken at `timestamp_us` (us, the clock
+    /// every other call uses). `readings[i]` is sensor `i`'s altitude, or
+    /// `None` when it had no fresh reading for this sample — pass `None`
+    /// rather than repeating the last one, or a slow sensor will read as
+    /// stuck. Keep each sensor in the same slot for the whole flight: the
+    /// slot is what its history and its logged verdict are kept under.
+    ///
+    /// `reference` is where the caller believes the airframe is, if it
+    /// believes anything — the deployment filter's altitude — and it
+    /// decides exactly one case: **two** sensors, both passing their own
+    /// checks, further apart than [`OUTLIER_M`]. Two sensors cannot outvote
+    /// each other, so the one further from the reference is the outlier.
+    /// With no reference — on the pad, through the Mach lockout — the lower
+    /// slot wins, which is why VLF5's own barometer goes first: a pair that
+    /// cannot be told apart then does exactly what the board did when that
+    /// sensor was the only one it read. With three or more the median of
+    /// them is the reference and the caller's is not consulted; with one
+    /// there is nothing to disagree with.
+    ///
+    /// The previous vote is deliberately not a stand-in for a missing
+    /// reference. It was, briefly: a pair that disagreed from the first
+    /// sample averaged to a point equidistant from both, and whichever the
+    /// tie-break kept from there was the reference every sample after, so
+    /// the vote could lock onto a dead sensor for the whole pad.
+    pub fn vote(
+        &mut self,
+        timestamp_us: u64,
+        readings: &[Option<f32>],
+        reference: Option<f32>,
+    ) -> BaroVote {
+        let mut health = [BaroHealth::Absent; MAX_BAROS];
+        let mut values = [0.0f32; MAX_BAROS];
+        for (i, (track, reading)) in self.tracks.iter_mut().zip(readings).enumerate() {
+            if let Some(altitude) = *reading {
+                health[i] = track.push(timestamp_us, altitude);
+                values[i] = altitude;
+            }
+        }
+
+        // Outliers, among the sensors whose own histories look honest.
+        let mut candidates = [0.0f32; MAX_BAROS];
+        let mut n = 0;
+        for (h, v) in health.iter().zip(values) {
+            if *h == BaroHealth::Healthy {
+                candidates[n] = v;
+                n += 1;
+            }
+        }
+        if n == 2 && (candidates[0] - candidates[1]).abs() > OUTLIER_M {
+            // Exactly one of a disagreeing pair goes, even when both are far
+            // from the reference: the nearer one is still the better guess,
+            // and losing both would hand the vote to whatever the flagged
+            // sensors say.
+            let mut pair = (0..MAX_BAROS).filter(|&i| health[i] == BaroHealth::Healthy);
+            if let (Some(a), Some(b)) = (pair.next(), pair.next()) {
+                let b_goes =
+                    reference.is_none_or(|r| (values[b] - r).abs() >= (values[a] - r).abs());
+                health[if b_goes { b } else { a }] = BaroHealth::Outlier;
+            }
+        } else if n >= 3
+            && let Some(consensus) = median(&mut candidates[..n])
+        {
+            for (h, v) in health.iter_mut().zip(values) {
+                if *h == BaroHealth::Healthy && (v - consensus).abs() > OUTLIER_M {
+                    *h = BaroHealth::Outlier;
+                }
+            }
+        }
+
+        // The vote: the median of what survived, else of everything read.
+        let mut voted = [0.0f32; MAX_BAROS];
+        let mut n = 0;
+        for (h, v) in health.iter().zip(values) {
+            if h.voted() {
+                voted[n] = v;
+                n += 1;
+            }
+        }
+        if n == 0 {
+            for (h, v) in health.iter().zip(values) {
+                if *h != BaroHealth::Absent {
+                    voted[n] = v;
+                    n += 1;
+                }
+            }
+        }
+        let altitude_asl = median(&mut voted[..n]);
+        BaroVote {
+            altitude_asl,
+            health,
+        }
+    }
+}
+
+/// Median of a few values, the mean of the middle two for an even count.
+/// `None` when empty.
+///
+/// `total_cmp` for the reason `median_by_altitude` in the deployment
+/// estimator gives: a NaN must make the answer wrong, never a panic.
+fn median(values: &mut [f32]) -> Option<f32> {
+    let n = values.len();
+    if n == 0 {
+        return None;
+    }
+    values.sort_unstable_by(|a, b| a.total_cmp(b));
+    Some(if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        0.5 * (values[n / 2 - 1] + values[n / 2])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 416 Hz, like the VLF5 barometer.
+    const STEP_US: u64 = 2404;
+
+    /// A deterministic +-0.5 m wobble, so no reading repeats.
+    fn wobble(i: u64) -> f32 {
+        ((i * 7919) % 101) as f32 / 100.0 - 0.5
+    }
+
+    #[test]
+    fn one_sensor_is_always_the_vote() {
+        let mut voter = BaroVoter::new();
+        // Stuck at a constant, and flagged for it — but fused regardless,
+        // which is what a single-barometer board has always done.
+        let mut last = None;
+        for i in 0..1000 {
+            last = Some(voter.vote(i * STEP_US, &[Some(200.0)], None));
+        }
+        let last = last.unwrap();
+        assert_eq!(last.altitude_asl, Some(200.0));
+        assert_eq!(
+            last.health,
+            [BaroHealth::Stuck, BaroHealth::Absent, BaroHealth::Absent]
+        );
+    }
+
+    #[test]
+    fn stuck_sensor_is_outvoted_in_a_climb() {
+        let mut voter = BaroVoter::new();
+        let mut last = None;
+        for i in 0..1000 {
+            let climb = 200.0 + 100.0 * (i as f32 * STEP_US as f32 * 1e-6);
+            last = Some(voter.vote(
+                i * STEP_US,
+                &[
+                    Some(climb + wobble(i)),
+                    Some(1234.5),
+                    Some(climb - wobble(i)),
+                ],
+                None,
+            ));
+        }
+        let last = last.unwrap();
+        assert_eq!(last.health[1], BaroHealth::Stuck);
+        assert!(last.health[0].voted() && last.health[2].voted());
+        let climb = 200.0 + 100.0 * (999.0 * STEP_US as f32 * 1e-6);
+        assert!((last.altitude_asl.unwrap() - climb).abs() < 1.0);
+    }
+
+    #[test]
+    fn noisy_sensor_is_held_out_until_it_settles() {
+        let mut voter = BaroVoter::new();
+        let mut vote = |i: u64, spike: f32| {
+            voter.vote(
+                i * STEP_US,
+                &[Some(200.0 + wobble(i)), Some(200.0 - wobble(i) + spike)],
+                None,
+            )
+        };
+        for i in 0..400 {
+            vote(i, 0.0);
+        }
+        // One 30 km read on sensor 1 is noise on the very sample it lands
+        // on, and the vote is sensor 0 alone.
+        let spiked = vote(400, 30_000.0);
+        assert_eq!(spiked.health[1], BaroHealth::Noisy);
+        assert!((spiked.altitude_asl.unwrap() - 200.0).abs() < 1.0);
+        let after = vote(401, 0.0);
+        assert_eq!(after.health[1], BaroHealth::Noisy);
+        // ~6 s of honest readings later it is trusted again.
+        let mut last = after;
+        for i in 402..(402 + 416 * 8) {
+            last = vote(i, 0.0);
+        }
+        assert_eq!(
+            last.health,
+            [BaroHealth::Healthy, BaroHealth::Healthy, BaroHealth::Absent]
+        );
+    }
+
+    #[test]
+    fn outlier_is_outvoted_by_two_and_decided_by_the_reference_between_two() {
+        let mut voter = BaroVoter::new();
+        let three = voter.vote(0, &[Some(200.0), Some(2400.0), Some(201.0)], None);
+        assert_eq!(three.health[1], BaroHealth::Outlier);
+        assert_eq!(three.altitude_asl, Some(200.5));
+
+        let mut voter = BaroVoter::new();
+        let two = voter.vote(0, &[Some(2400.0), Some(200.0)], Some(210.0));
+        assert_eq!(two.health[..2], [BaroHealth::Outlier, BaroHealth::Healthy]);
+        assert_eq!(two.altitude_asl, Some(200.0));
+
+        // No reference: the first slot is believed, right or wrong, every
+        // sample — never whichever one an average happened to land nearer.
+        let mut voter = BaroVoter::new();
+        for i in 0..10 {
+            let two = voter.vote(i * STEP_US, &[Some(2400.0 + wobble(i)), Some(200.0)], None);
+            assert_eq!(two.health[..2], [BaroHealth::Healthy, BaroHealth::Outlier]);
+        }
+    }
+
+    #[test]
+    fn absent_readings_leave_the_vote_to_the_rest() {
+        let mut voter = BaroVoter::new();
+        let vote = voter.vote(0, &[None, Some(300.0), None, Some(9999.0)], None);
+        // The fourth reading has no slot and no say.
+        assert_eq!(vote.altitude_asl, Some(300.0));
+        assert_eq!(vote.health[0], BaroHealth::Absent);
+        assert_eq!(voter.vote(1, &[None, None], None).altitude_asl, None);
+    }
+}
@@ -1,24 +1,66 @@
-use nalgebra::Vector2;
+use nalgebra::{Vector2, Vector3};
 
-use crate::{controller::rocket_dynamics::simulate_apogee_rk2, utils::lerp};
+use crate::{
+    atmosphere::Atmosphere,
+    controller::rocket_dynamics::{Actuation, HORIZON_STEPS, simulate_apogee_rk2},
+    flight_estimators::AirbrakesMPCStates,
+    utils::lerp,
+};
 
 const DT: f32 = 0.1;
 
+mod mode;
 pub(crate) mod rocket_dynamics;
+mod servo;
+mod wind;
+
+pub use mode::{ControllerMode, ControllerModeKind, ExtensionSchedule, SCHEDULE_POINTS};
+pub use servo::{ServoModel, ServoState};
+pub use wind::{WIND_BINS, WindProfile};
 
 pub struct AirBrakesMPC {
     parameters: RocketParameters,
+    atmosphere: Atmosphere,
     target_apogee_asl: f32,
+    wind: WindProfile,
+    servo: ServoModel,
 }
 
 impl AirBrakesMPC {
-    pub fn new(parameters: RocketParameters, target_apogee_asl: f32) -> Self {
+    /// `atmosphere` is the air the apogee simulation flies through on the
+    /// bare-state updates ([`Self::update`] through [`Self::update_in_wind`]),
+    /// and the frame of the altitudes they are handed. Pass
+    /// [`FlightEstimators::atmosphere`](crate::FlightEstimators::atmosphere)
+    /// when they come from the estimators. [`Self::update_with_servo`] does
+    /// not read it: it flies the atmosphere its states carry, which is the
+    /// one the estimators calibrated at launch whenever the MPC was built.
+    pub fn new(
+        parameters: RocketParameters,
+        atmosphere: Atmosphere,
+        target_apogee_asl: f32,
+    ) -> Self {
         Self {
             parameters,
+            atmosphere,
             target_apogee_asl,
+            wind: WindProfile::calm(),
+            servo: ServoModel::ICARUS,
         }
     }
 
+    /// The same controller with its apogee simulation flying the brakes on
+    /// `servo` instead of [`ServoModel::ICARUS`].
+    pub fn with_servo(self, servo: ServoModel) -> Self {
+        Self { servo, ..self }
+    }
+
+    /// The same controller flying its apogee simulation through `wind`
+    /// instead of calm air: the pre-launch upload, fixed for the flight
+    /// like the target. See [`Self::update_in_wind`] for what it changes.
+    pub fn with_wind(self, wind: WindProfile) -> Self {
+        Self { wind, ..self }
+    }
+
     /// The apogee (m ASL) this MPC is solving for, as it was handed to
     /// [`Self::new`].
     ///
@@ -35,77 +77,315 @@ impl AirBrakesMPC {
     ///
     /// `current_velocity` is (horizontal, vertical) m/s, vertical positive
     /// up — the airbrakes estimator's `velocity()` output. The apogee
-    /// simulation flies the full 2D ballistic arc with drag on total
-    /// speed, so tilt (carried in the horizontal component) is accounted
-    /// for.
+    /// simulation flies the full ballistic arc with drag on airspeed, so
+    /// tilt (carried in the horizontal component) is accounted for.
+    ///
+    /// Flies the uploaded wind (if any) with no heading, i.e. as a headwind
+    /// along the flight path — see [`Self::update_in_wind`].
     pub fn update(&self, current_altitude_asl: f32, current_velocity: Vector2<f32>) -> MpcSolution {
-        let initial_state = State {
+        self.update_in_wind(1.0, None, current_altitude_asl, current_velocity)
+    }
+
+    /// [`Self::update`] on the configured airframe with its cd table scaled
+    /// by `cd_scale` -- the airbrakes estimator's in-flight drag fit,
+    /// [`AirbrakesEstimator::cd_scale`]. A scale of 1.0 is exactly
+    /// [`Self::update`].
+    ///
+    /// The scale is taken per call rather than stored because it moves
+    /// every tick and belongs to the estimator: the MPC stays a function of
+    /// what it is handed.
+    ///
+    /// [`AirbrakesEstimator::cd_scale`]: crate::airbrakes_estimator::AirbrakesEstimator::cd_scale
+    pub fn update_with_cd_scale(
+        &self,
+        cd_scale: f32,
+        current_altitude_asl: f32,
+        current_velocity: Vector2<f32>,
+    ) -> MpcSolution {
+        self.update_in_wind(cd_scale, None, current_altitude_asl, current_velocity)
+    }
+
+    /// [`Self::update_with_cd_scale`] with the airframe's compass `heading`
+    /// (rad, clockwise from magnetic north — [`AttitudeAngles::yaw`] when
+    /// [`AttitudeAngles::yaw_is_magnetic`]), so the wind profile can be
+    /// split into the head/tail and cross components this flight sees.
+    /// `None` when there is no compass heading; the profile is then flown
+    /// as if the airframe were heading straight into the wind, which is
+    /// where weathercocking points it (see [`WindProfile`]). On a calm
+    /// profile the heading changes nothing, and this is exactly the 2-D
+    /// model the MPC flew before it had a wind.
+    ///
+    /// `current_velocity.x` is read as the horizontal AIRSPEED along the
+    /// heading, not ground speed. That is what the estimator's horizontal
+    /// velocity is: it is the vertical velocity times the tangent of the
+    /// airframe's tilt, and the airframe's axis is a weathervane — it points
+    /// along the air-relative velocity. The ground velocity the simulation
+    /// starts from is that airspeed plus the wind at the current altitude;
+    /// from there drag acts on the air-relative velocity at every step, in
+    /// three dimensions. A wind that is the same at every altitude therefore
+    /// changes nothing but the ground track; the prediction moves by the
+    /// airspeed the shear adds or takes away over the rest of the coast.
+    ///
+    /// [`AttitudeAngles::yaw`]: crate::AttitudeAngles::yaw
+    /// [`AttitudeAngles::yaw_is_magnetic`]: crate::AttitudeAngles::yaw_is_magnetic
+    pub fn update_in_wind(
+        &self,
+        cd_scale: f32,
+        heading: Option<f32>,
+        current_altitude_asl: f32,
+        current_velocity: Vector2<f32>,
+    ) -> MpcSolution {
+        let states = AirbrakesMPCStates {
             altitude_asl: current_altitude_asl,
             velocity: current_velocity,
+            cd_scale,
+            heading,
+            enabled_for_s: 0.0,
+            mode: ControllerMode::TargetApogee,
+            atmosphere: self.atmosphere,
         };
+        self.solve(&states, None)
+    }
+
+    /// [`Self::update_in_wind`] on everything the estimators hand out, plus
+    /// what the servo is doing: `servo` is Icarus's latest reported extension
+    /// and the extension last commanded, or `None` until Icarus has reported
+    /// (which is then exactly [`Self::update_in_wind`]).
+    ///
+    /// With the servo known the solver stops holding one candidate. It plans
+    /// a command for each of the next [`HORIZON_STEPS`] ticks, flies them on
+    /// the [`ServoModel`] from where the flaps actually are, and picks the
+    /// plan that lands on the target while moving the command least — the
+    /// least sum of squared tick-to-tick command changes, starting from the
+    /// command in force. Flaps still in transit are in the prediction, so
+    /// the solver does not re-command them, and the move penalty spreads a
+    /// correction over the horizon instead of taking it all on this tick.
+    ///
+    /// On a long coast that changes little: the held solve re-solves every
+    /// tick and so absorbs the transit it ignores. It is the model to fly
+    /// when that is not enough — a slower actuator, or a shorter coast. The
+    /// `servo_aware_mpc_does_not_thrash_the_flaps` Osiris test flies both.
+    ///
+    /// Solved on the linearisation, which keeps it the same one-dimensional
+    /// bisection: with `g_k` the apogee's sensitivity to the command at tick
+    /// `k`, the least-change plan moves every command by
+    /// `s * w_k`, where `w_k` is the running sum over `j <= k` of
+    /// `g_j + ... + g_last`, normalised to end at 1. The first tick moves
+    /// least — its command is in force least long before the next one
+    /// replaces it — and every command rises with `s`, so apogee still falls
+    /// monotonically along the family and FDR section 16.7.4.3's bisection
+    /// runs on `s` unchanged. The sensitivities cost one simulation per
+    /// planned tick plus the baseline, so a solve is eleven simulations
+    /// against the held candidate's six.
+    ///
+    /// Only the first planned command is issued; the rest are re-planned on
+    /// the next tick, from the servo as Icarus reports it then.
+    ///
+    /// This is also the one update that flies `states.mode` rather than the
+    /// target alone (see [`ControllerMode`]): [`ControllerMode::MaximiseDrag`]
+    /// solves the same way with the flaps stowed after the horizon, and the
+    /// [`ControllerMode::Scheduled`] and [`ControllerMode::Fixed`] commands
+    /// are issued as they are, with the apogee they are predicted to reach.
+    /// The solution's `mode` says which produced it.
+    ///
+    /// [`HORIZON_STEPS`]: rocket_dynamics::HORIZON_STEPS
+    pub fn update_with_servo(
+        &self,
+        states: &AirbrakesMPCStates,
+        servo: Option<ServoState>,
+    ) -> MpcSolution {
+        self.solve(states, servo)
+    }
 
-        // Search interval for drag percentage [-1.0, 1.0]
-        let mut low_drag = -1.0f32;
-        let mut high_drag = 1.0f32;
-
-        let mut ap_low_asl = simulate_apogee_rk2(low_drag, &initial_state, &self.parameters);
-        let mut ap_high_asl = simulate_apogee_rk2(high_drag, &initial_state, &self.parameters);
-
-        // Perform up to 3 iterations of bisection
-        for _ in 0..3 {
-            let mid_drag = 0.5 * (low_drag + high_drag);
-            let ap_mid_asl = simulate_apogee_rk2(mid_drag, &initial_state, &self.parameters);
-
-            // Monotonic: higher drag -> lower apogee
-            if ap_mid_asl > self.target_apogee_asl {
-                // Need more drag to reduce apogee
-                low_drag = mid_drag;
-                ap_low_asl = ap_mid_asl;
-            } else {
-                // Too much drag, reduce it
-                high_drag = mid_drag;
-                ap_high_asl = ap_mid_asl;
+    fn solve(&self, states: &AirbrakesMPCStates, servo: Option<ServoState>) -> MpcSolution {
+        let AirbrakesMPCStates {
+            altitude_asl: current_altitude_asl,
+            velocity: current_velocity,
+            cd_scale,
+            heading,
+            enabled_for_s,
+            mode,
+            atmosphere,
+        } = *states;
+        let scaled;
+        let parameters = if cd_scale == 1.0 {
+            &self.parameters
+        } else {
+            scaled = self.parameters.with_cd_scale(cd_scale);
+            &scaled
+        };
+        // In the flight frame from here on, `(downrange, crossrange)`.
+        let wind = self.wind.in_flight_frame(heading, current_altitude_asl);
+        let wind_here = wind.at(current_altitude_asl);
+        let initial_state = State {
+            altitude_asl: current_altitude_asl,
+            velocity: Vector3::new(
+                current_velocity.x + wind_here.x,
+                current_velocity.y,
+                wind_here.y,
+            ),
+        };
+        let apogee = |actuation: &Actuation| {
+            simulate_apogee_rk2(actuation, &initial_state, parameters, &atmosphere, &wind)
+        };
+
+        // Past the horizon the flaps go to neutral, except when the point is
+        // drag: then they are stowed, and the target is only a floor.
+        let tail = match mode {
+            ControllerMode::MaximiseDrag => 0.0,
+            _ => parameters.neutral_extension_percentage(),
+        };
+
+        // The open-loop modes: the command is given, and only its apogee is
+        // solved for. A schedule is flown ahead over the horizon and then
+        // held at its last step, a fixed extension held throughout.
+        let given = match mode {
+            ControllerMode::TargetApogee | ControllerMode::MaximiseDrag => None,
+            ControllerMode::Scheduled(schedule) => Some((
+                core::array::from_fn(|k| schedule.at(enabled_for_s + k as f32 * DT)),
+                schedule.at(f32::INFINITY),
+            )),
+            ControllerMode::Fixed { extension } => {
+                let extension = extension.clamp(0.0, 1.0);
+                Some(([extension; HORIZON_STEPS], extension))
             }
+        };
+        if let Some((plan, tail)) = given {
+            let start = servo.unwrap_or(ServoState {
+                actual_extension: plan[0],
+                commanded_extension: plan[0],
+            });
+            return MpcSolution {
+                extension_percentage: plan[0],
+                predicted_apogee_asl: apogee(&Actuation {
+                    servo: self.servo,
+                    start,
+                    plan,
+                    tail,
+                }),
+                mode: mode.kind(),
+            };
         }
 
-        // After 3 iterations, linearly interpolate between the bracket endpoints
-        // ap_low_asl corresponds to low_drag (higher apogee), ap_high_asl to high_drag (lower apogee)
-        let denom = ap_low_asl - ap_high_asl;
-        let t = if denom.abs() < 1e-6 {
-            0.5
-        } else {
-            ((self.target_apogee_asl - ap_high_asl) / denom).clamp(0.0, 1.0)
+        let Some(servo) = servo else {
+            let held = |drag: f32| Actuation {
+                tail,
+                ..Actuation::held(drag, parameters, self.servo)
+            };
+            let drag_percentage = bisect(self.target_apogee_asl, -1.0, 1.0, |drag| {
+                apogee(&held(drag))
+            });
+
+            // Predict at the drag the COMMANDED extension actually delivers,
+            // so a command pinned at a rail reads on the ground as a visible
+            // miss rather than as target-equals-prediction. No clamp is
+            // needed or wanted: `drag_percentage` is a convex combination of
+            // two bracket ends that never leave [-1, 1], and
+            // `drag_percentage_to_extension_percentage` inverts this very cd,
+            // so the reported apogee and the commanded extension agree by
+            // construction.
+            //
+            // This used to clamp to [0, 1] -- the *extension* range applied
+            // to the drag axis. Every command below neutral, i.e. anything
+            // under ~60% extension, was then reported at drag 0.0: ask for
+            // stowed flaps and the downlink answered with the apogee for 60%
+            // flaps.
+            return MpcSolution {
+                extension_percentage: parameters
+                    .drag_percentage_to_extension_percentage(drag_percentage),
+                predicted_apogee_asl: apogee(&held(drag_percentage)),
+                mode: mode.kind(),
+            };
         };
-        let drag_percentage = high_drag + t * (low_drag - high_drag);
-
-        // Convert to extension percentage and clamp to [0,1]
-        let extension_percentage = self
-            .parameters
-            .drag_percentage_to_extension_percentage(drag_percentage);
-
-        // Predict at the drag the COMMANDED extension actually delivers, so a
-        // command pinned at a rail reads on the ground as a visible miss
-        // rather than as target-equals-prediction. No clamp is needed or
-        // wanted: `drag_percentage` is a convex combination of two bracket
-        // ends that never leave [-1, 1], and
-        // `drag_percentage_to_extension_percentage` inverts this very cd, so
-        // the reported apogee and the commanded extension agree by
-        // construction.
-        //
-        // This used to clamp to [0, 1] -- the *extension* range applied to the
-        // drag axis. Every command below neutral, i.e. anything under ~60%
-        // extension, was then reported at drag 0.0: ask for stowed flaps and
-        // the downlink answered with the apogee for 60% flaps.
-        let predicted_apogee_asl =
-            simulate_apogee_rk2(drag_percentage, &initial_state, &self.parameters);
 
+        // The least-change family, in drag percentage like the held search:
+        // cd, and so apogee, is close to linear in it.
+        let reference =
+            parameters.extension_percentage_to_drag_percentage(servo.commanded_extension);
+        let actuation = |drags: [f32; HORIZON_STEPS]| Actuation {
+            servo: self.servo,
+            start: servo,
+            plan: drags.map(|drag| parameters.drag_percentage_to_extension_percentage(drag)),
+            tail,
+        };
+        let baseline = apogee(&actuation([reference; HORIZON_STEPS]));
+        // Perturb toward the middle of the stroke, so the step is never
+        // clamped away.
+        let h = if reference > 0.0 { -0.25 } else { 0.25 };
+        let mut sensitivity = [0.0f32; HORIZON_STEPS];
+        for (k, g) in sensitivity.iter_mut().enumerate() {
+            let mut drags = [reference; HORIZON_STEPS];
+            drags[k] += h;
+            *g = ((apogee(&actuation(drags)) - baseline) / h).abs();
+        }
+        let mut weights = [0.0f32; HORIZON_STEPS];
+        let mut running = 0.0;
+        for (k, w) in weights.iter_mut().enumerate() {
+            running += sensitivity[k..].iter().sum::<f32>();
+            *w = running;
+        }
+        // A coast with no authority left has nothing to weigh the ticks by;
+        // any increasing family will do, and a held one is the simplest.
+        let last = weights[HORIZON_STEPS - 1];
+        if last.is_nan() || last <= 1e-3 {
+            weights = [1.0; HORIZON_STEPS];
+        }
+        let weights = weights.map(|w| w / weights[HORIZON_STEPS - 1]);
+        let plan = |s: f32| weights.map(|w| (reference + s * w).clamp(-1.0, 1.0));
+
+        // The first tick moves least, so these are the two rails: every
+        // command stowed at one end, every one full at the other.
+        let s = bisect(
+            self.target_apogee_asl,
+            (-1.0 - reference) / weights[0],
+            (1.0 - reference) / weights[0],
+            |s| apogee(&actuation(plan(s))),
+        );
+        let planned = actuation(plan(s));
         MpcSolution {
-            extension_percentage,
-            predicted_apogee_asl,
+            extension_percentage: planned.plan[0],
+            predicted_apogee_asl: apogee(&planned),
+            mode: mode.kind(),
         }
     }
 }
 
+/// FDR section 16.7.4.3's search for the target, on any one-parameter family
+/// of plans whose apogee falls as `s` rises: both ends, three bisections, then
+/// a linear interpolation inside the last bracket. Returns `s`.
+fn bisect(target_apogee_asl: f32, low: f32, high: f32, apogee: impl Fn(f32) -> f32) -> f32 {
+    let (mut low, mut high) = (low, high);
+    let mut ap_low_asl = apogee(low);
+    let mut ap_high_asl = apogee(high);
+
+    // Perform up to 3 iterations of bisection
+    for _ in 0..3 {
+        let mid = 0.5 * (low + high);
+        let ap_mid_asl = apogee(mid);
+
+        // Monotonic: higher s -> lower apogee
+        if ap_mid_asl > target_apogee_asl {
+            // Need more drag to reduce apogee
+            low = mid;
+            ap_low_asl = ap_mid_asl;
+        } else {
+            // Too much drag, reduce it
+            high = mid;
+            ap_high_asl = ap_mid_asl;
+        }
+    }
+
+    // After 3 iterations, linearly interpolate between the bracket endpoints
+    // ap_low_asl corresponds to low (higher apogee), ap_high_asl to high (lower apogee)
+    let denom = ap_low_asl - ap_high_asl;
+    let t = if denom.abs() < 1e-6 {
+        0.5
+    } else {
+        ((target_apogee_asl - ap_high_asl) / denom).clamp(0.0, 1.0)
+    };
+    high + t * (low - high)
+}
+
 /// One MPC step's output.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -115,61 +395,161 @@ pub struct MpcSolution {
     /// Apogee ASL (m) predicted at `extension_percentage`. Equal to the
     /// target while the target is reachable; above it on an overshoot the
     /// brakes cannot fix, below it on an undershoot.
+    ///
+    /// In the other [`ControllerMode`]s the target means less or nothing,
+    /// and this is simply where the command is predicted to take the
+    /// rocket: at or above the floor while the drag is maximised, and
+    /// wherever the schedule or the fixed extension leads.
     pub predicted_apogee_asl: f32,
+    /// The mode that produced this command, for the log: a scheduled or
+    /// fixed command next to a prediction far from target is the mode
+    /// working, not the controller failing.
+    pub mode: ControllerModeKind,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct State {
     pub(crate) altitude_asl: f32,
-    /// (horizontal, vertical) m/s, vertical positive up
-    pub(crate) velocity: Vector2<f32>,
+    /// Ground velocity, (downrange, vertical, crossrange) m/s, vertical
+    /// positive up. Ordered so `.y` stays vertical, as it was when the
+    /// state was 2-D; crossrange is zero unless there is a crosswind.
+    pub(crate) velocity: Vector3<f32>,
 }
 
 /// d/dt of a `State`: the `altitude_asl` slot holds d(altitude)/dt (m/s),
 /// `velocity` holds acceleration.
 pub(crate) struct Derivative<T>(pub(crate) T);
 
+/// How many Mach numbers [`RocketParameters::cd`] is tabulated at.
+///
+/// Fixed at compile time so the table stays a plain array on the board. Four
+/// rows span the coast the brakes can fly -- from [`max_open_mach`] down to
+/// the low-subsonic tail before apogee -- at the spacing the
+/// OpenRocket/RASAero curves need: stowed Cd on Osiris falls 13% between
+/// Mach 0.8 and 0.4, most of it above 0.6.
+///
+/// [`max_open_mach`]: crate::airbrakes_estimator::AirbrakesConfig::max_open_mach
+pub const CD_MACH_POINTS: usize = 4;
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Clone, Debug)]
 pub struct RocketParameters {
     pub burnout_mass: f32,
-    /// cd is a look up table from extension percentage to cd
-    /// e.g. `cd[2]` is cd at 50% extension percentage
-    pub cd: [f32; 5],
+    /// The Mach numbers the rows of `cd` are tabulated at, strictly
+    /// increasing. Outside them the nearest row is held, never extrapolated:
+    /// the transonic rise above the table is the one place a straight line
+    /// would be confidently wrong.
+    pub cd_mach: [f32; CD_MACH_POINTS],
+    /// cd is a look up table from (Mach, extension percentage) to cd
+    /// e.g. `cd[1][2]` is cd at `cd_mach[1]` and 50% extension percentage
+    ///
+    /// Mach is the whole flow-condition axis. Reynolds number is not a
+    /// second one: along a coast it moves with Mach, and the tabulated
+    /// curves from a trajectory sim already carry it in.
+    pub cd: [[f32; 5]; CD_MACH_POINTS],
     pub reference_area: f32,
 }
 
 impl RocketParameters {
-    /// `Cd * A / m` (m^2/kg) with the brakes stowed — the parameter the
-    /// airbrakes estimator's drag check inverts, see
+    /// An airframe whose cd depends on extension only: every Mach row is
+    /// the same five-point table.
+    ///
+    /// This is what the drag model was before it had a Mach axis, so it is
+    /// also what a cd table from a single CFD Mach number means.
+    pub const fn mach_independent(burnout_mass: f32, cd: [f32; 5], reference_area: f32) -> Self {
+        Self {
+            burnout_mass,
+            cd_mach: [0.3, 0.5, 0.7, 0.9],
+            cd: [cd; CD_MACH_POINTS],
+            reference_area,
+        }
+    }
+
+    /// `Cd * A / m` (m^2/kg) with the brakes stowed, at `mach` — the
+    /// parameter the airbrakes estimator's drag check inverts, see
     /// [`AirbrakesConfig::rocket`](crate::airbrakes_estimator::AirbrakesConfig::rocket).
     ///
     /// Deriving it here rather than configuring it separately is the
     /// point: the Mach-lockout exit and the apogee prediction then cannot
-    /// disagree about the airframe. `cd[0]` is the 0%-extension entry, so
-    /// this is the clean-airframe subsonic value the check requires.
-    pub const fn subsonic_cda_over_mass(&self) -> f32 {
-        self.cd[0] * self.reference_area / self.burnout_mass
+    /// disagree about the airframe. Column 0 is the 0%-extension entry, so
+    /// this is the clean-airframe value the check requires.
+    pub fn subsonic_cda_over_mass(&self, mach: f32) -> f32 {
+        self.cda_over_mass(0.0, mach)
+    }
+
+    /// `Cd * A / m` (m^2/kg) at a physical extension, 0.0 (stowed) - 1.0
+    /// (full), and `mach`: the drag the apogee simulation flies, per unit
+    /// dynamic pressure.
+    pub fn cda_over_mass(&self, extension_percentage: f32, mach: f32) -> f32 {
+        let column = self.get_cd_from_extension_percentage(extension_percentage);
+        self.cd_at_mach(&column, mach) * self.reference_area / self.burnout_mass
+    }
+
+    /// The same airframe with every entry of the cd table multiplied by
+    /// `scale` -- what the airbrakes estimator's in-flight drag fit hands
+    /// the MPC, see [`AirbrakesEstimator::cd_scale`].
+    ///
+    /// The whole table, not the stowed column: the fit cannot tell the
+    /// clean airframe's share of a wrong deceleration from the flaps', and
+    /// a table whose rows scale together keeps the drag-percentage curve
+    /// [`Self::drag_percentage_to_extension_percentage`] inverts, so the
+    /// same drag percentage still means the same extension.
+    ///
+    /// [`AirbrakesEstimator::cd_scale`]: crate::airbrakes_estimator::AirbrakesEstimator::cd_scale
+    pub fn with_cd_scale(&self, scale: f32) -> Self {
+        Self {
+            cd: self.cd.map(|row| row.map(|cd| cd * scale)),
+            ..self.clone()
+        }
     }
 
     /// drag percentage: -1.0 - 1.0
+    ///
+    /// Defined on the top Mach row. The drag axis is only the solver's
+    /// search coordinate -- what it becomes physically is an extension, via
+    /// [`Self::drag_percentage_to_extension_percentage`] -- so it needs one
+    /// curve, not one per Mach, and the top row is where the flaps first
+    /// open. On a table whose rows scale together every row gives the same
+    /// curve.
     fn get_cd_from_drag_percentage(&self, drag_percentage: f32) -> f32 {
-        lerp(
-            (drag_percentage + 1.0) / 2.0,
-            &[self.cd[0], self.cd[self.cd.len() - 1]],
-        )
+        let row = &self.cd[CD_MACH_POINTS - 1];
+        lerp((drag_percentage + 1.0) / 2.0, &[row[0], row[row.len() - 1]])
     }
 
-    /// cd at a physical extension percentage, 0.0 (stowed) - 1.0 (full).
+    /// cd at a physical extension percentage, 0.0 (stowed) - 1.0 (full),
+    /// for every Mach row: the column [`Self::cd_at_mach`] interpolates.
     ///
     /// The five-point table walked directly, so unlike
     /// [`Self::get_cd_from_drag_percentage`] this sees the interior entries.
-    /// The two agree wherever they meet:
+    /// On the top row the two agree wherever they meet:
     /// `get_cd_from_extension_percentage(drag_percentage_to_extension_percentage(d))`
     /// is `get_cd_from_drag_percentage(d)` by construction, because the
     /// conversion inverts this very interpolation.
-    fn get_cd_from_extension_percentage(&self, extension_percentage: f32) -> f32 {
-        lerp(extension_percentage.clamp(0.0, 1.0), &self.cd)
+    ///
+    /// Split from the Mach lookup because the two change at different
+    /// rates: the extension moves only while the modelled servo does, Mach
+    /// moves every step.
+    fn get_cd_from_extension_percentage(&self, extension_percentage: f32) -> [f32; CD_MACH_POINTS] {
+        let extension_percentage = extension_percentage.clamp(0.0, 1.0);
+        self.cd.map(|row| lerp(extension_percentage, &row))
+    }
+
+    /// cd at `mach` from one value per Mach row, held at the end rows
+    /// outside the table.
+    ///
+    /// A walk over [`CD_MACH_POINTS`] breakpoints and one divide -- no
+    /// transcendental, so it runs on the FPU like the rest of the RK2 step.
+    /// A non-finite Mach comes back as a non-finite cd, which the apogee
+    /// simulation's divergence guard already handles.
+    fn cd_at_mach(&self, column: &[f32; CD_MACH_POINTS], mach: f32) -> f32 {
+        let m = &self.cd_mach;
+        let mach = mach.clamp(m[0], m[CD_MACH_POINTS - 1]);
+        let mut i = 0;
+        while i + 2 < CD_MACH_POINTS && mach > m[i + 1] {
+            i += 1;
+        }
+        let t = (mach - m[i]) / (m[i + 1] - m[i]);
+        column[i] + t * (column[i + 1] - column[i])
     }
 
     /// The extension the apogee simulation's terminal policy holds: drag 0.0,
@@ -184,16 +564,31 @@ impl RocketParameters {
         self.drag_percentage_to_extension_percentage(0.0)
     }
 
+    /// The inverse of [`Self::drag_percentage_to_extension_percentage`]:
+    /// where an extension sits between stowed (-1.0) and full (+1.0) drag on
+    /// the top row.
+    pub(crate) fn extension_percentage_to_drag_percentage(&self, extension_percentage: f32) -> f32 {
+        let row = &self.cd[CD_MACH_POINTS - 1];
+        let cd = lerp(extension_percentage.clamp(0.0, 1.0), row);
+        let (first, last) = (row[0], row[row.len() - 1]);
+        if last > first {
+            2.0 * (cd - first) / (last - first) - 1.0
+        } else {
+            0.0
+        }
+    }
+
     /// drag percentage: -1.0 - 1.0
     /// returns 0.0 to 1.0
     fn drag_percentage_to_extension_percentage(&self, drag_percentage: f32) -> f32 {
         let cd = self.get_cd_from_drag_percentage(drag_percentage);
+        let row = &self.cd[CD_MACH_POINTS - 1];
 
         // cd is strictly increasing; map back to extension percentage in [0,1]
-        let n = self.cd.len();
+        let n = row.len();
 
-        let first = self.cd[0];
-        let last = self.cd[n - 1];
+        let first = row[0];
+        let last = row[n - 1];
         let cd = cd.clamp(first, last);
 
         if cd <= first {
@@ -205,8 +600,8 @@ impl RocketParameters {
 
         let segment_width = 1.0 / (n as f32 - 1.0);
         for i in 0..(n - 1) {
-            let a = self.cd[i];
-            let b = self.cd[i + 1];
+            let a = row[i];
+            let b = row[i + 1];
             // Since strictly increasing, cd will be <= b at the matching segment
             if cd <= b {
                 let t = (cd - a) / (b - a);
@@ -220,15 +615,16 @@ impl RocketParameters {
 
 #[cfg(test)]
 mod tests {
-    use super::RocketParameters;
+    use super::{
+        AirBrakesMPC, CD_MACH_POINTS, ControllerMode, ControllerModeKind, ExtensionSchedule,
+        RocketParameters, ServoState, WindProfile,
+    };
+    use crate::{atmosphere::Atmosphere, flight_estimators::AirbrakesMPCStates};
+    use nalgebra::Vector2;
     use approx::assert_relative_eq;
 
     fn params() -> RocketParameters {
-        RocketParameters {
-            burnout_mass: 10.0,
-            cd: [0.3, 0.4, 0.5, 0.65, 0.8],
-            reference_area: 0.02,
-        }
+        RocketParameters::mach_independent(10.0, [0.3, 0.4, 0.5, 0.65, 0.8], 0.02)
     }
 
     #[test]
@@ -271,4 +667,205 @@ mod tests {
         let e_q1 = p.drag_percentage_to_extension_percentage(-0.5);
         assert_relative_eq!(e_q1, 0.3125, max_relative = 1e-6);
     }
+
+    #[test]
+    fn mach_rows_interpolate_and_hold_at_the_ends() {
+        let mut p = params();
+        p.cd_mach = [0.4, 0.6, 0.7, 0.8];
+        for (row, scale) in p.cd.iter_mut().zip([0.8, 0.9, 0.95, 1.0]) {
+            for c in row.iter_mut() {
+                *c *= scale;
+            }
+        }
+        let stowed = p.get_cd_from_extension_percentage(0.0);
+
+        // On a breakpoint, the row itself.
+        assert_relative_eq!(p.cd_at_mach(&stowed, 0.6), 0.27, max_relative = 1e-6);
+        // Between two, linear in Mach: a quarter of the way from 0.4 to 0.6.
+        assert_relative_eq!(p.cd_at_mach(&stowed, 0.45), 0.2475, max_relative = 1e-6);
+        assert_relative_eq!(p.cd_at_mach(&stowed, 0.75), 0.2925, max_relative = 1e-6);
+        // Held, not extrapolated, either side of the table.
+        assert_relative_eq!(p.cd_at_mach(&stowed, 0.1), 0.24, max_relative = 1e-6);
+        assert_relative_eq!(p.cd_at_mach(&stowed, 1.5), 0.3, max_relative = 1e-6);
+        // The drag check reads the same stowed column.
+        assert_relative_eq!(
+            p.subsonic_cda_over_mass(0.45),
+            0.2475 * 0.02 / 10.0,
+            max_relative = 1e-6
+        );
+    }
+
+    #[test]
+    fn mach_independent_table_ignores_mach() {
+        let p = params();
+        let column = p.get_cd_from_extension_percentage(0.5);
+        assert_eq!(column, [0.5; CD_MACH_POINTS]);
+        for mach in [0.0, 0.3, 0.65, 0.9, 2.0] {
+            assert_relative_eq!(p.cd_at_mach(&column, mach), 0.5, max_relative = 1e-6);
+        }
+    }
+
+    #[test]
+    fn cd_scale_moves_the_drag_but_not_the_extension_map() {
+        let p = params();
+        let scaled = p.with_cd_scale(1.2);
+        assert_relative_eq!(
+            scaled.cda_over_mass(0.5, 0.7),
+            1.2 * p.cda_over_mass(0.5, 0.7),
+            max_relative = 1e-6
+        );
+        for d in [-1.0, -0.3, 0.0, 0.4, 1.0] {
+            assert_relative_eq!(
+                scaled.drag_percentage_to_extension_percentage(d),
+                p.drag_percentage_to_extension_percentage(d),
+                epsilon = 1e-5
+            );
+        }
+
+        // A scale of 1.0 is the configured airframe, bit for bit.
+        let mpc = AirBrakesMPC::new(p, Atmosphere::standard(), 3000.0);
+        let velocity = Vector2::new(10.0, 150.0);
+        assert_eq!(
+            mpc.update_with_cd_scale(1.0, 2000.0, velocity),
+            mpc.update(2000.0, velocity)
+        );
+    }
+
+    /// Calm air is the model the MPC flew before it had a wind, whatever the
+    /// heading. A constant wind changes nothing either: the MPC is handed
+    /// airspeed, and drag only sees airspeed. Shear is what moves the
+    /// answer — a headwind that strengthens on the way up is airspeed the
+    /// calm model never flew against.
+    #[test]
+    fn only_wind_shear_moves_the_prediction() {
+        let velocity = Vector2::new(10.0, 150.0);
+        let mpc =
+            |wind| AirBrakesMPC::new(params(), Atmosphere::standard(), 3000.0).with_wind(wind);
+        let still = mpc(WindProfile::calm()).update(2000.0, velocity);
+        assert_eq!(
+            mpc(WindProfile::calm()).update_in_wind(1.0, Some(1.0), 2000.0, velocity),
+            still
+        );
+
+        let constant = mpc(WindProfile::constant(3.0, -8.0)).update(2000.0, velocity);
+        assert_relative_eq!(
+            constant.predicted_apogee_asl,
+            still.predicted_apogee_asl,
+            epsilon = 0.01
+        );
+
+        // 1 m/s toward the south here, 8 m/s 1 km up: heading north is
+        // straight into it, and so is no heading at all.
+        let shear = mpc(WindProfile::binned(&[(2000.0, 0.0, -1.0), (3000.0, 0.0, -8.0)]).unwrap());
+        let upwind = shear.update_in_wind(1.0, Some(0.0), 2000.0, velocity);
+        assert_eq!(upwind, shear.update(2000.0, velocity));
+        assert!(upwind.predicted_apogee_asl < still.predicted_apogee_asl);
+        // Turned round, the same shear is a strengthening tailwind.
+        let downwind = shear.update_in_wind(1.0, Some(core::f32::consts::PI), 2000.0, velocity);
+        assert!(downwind.predicted_apogee_asl > still.predicted_apogee_asl);
+    }
+
+    #[test]
+    fn extension_and_drag_percentage_invert() {
+        let p = params();
+        for d in [-1.0, -0.5, 0.0, 0.3, 1.0] {
+            let e = p.drag_percentage_to_extension_percentage(d);
+            assert_relative_eq!(
+                p.extension_percentage_to_drag_percentage(e),
+                d,
+                epsilon = 1e-5
+            );
+        }
+    }
+
+    /// Without the servo the horizon solve is the held one. With the flaps at
+    /// rest on the held command it agrees with it; with them still stowed it
+    /// ramps them in, the first command short of the held one and the rest
+    /// of the horizon making it up — and either way it lands on the target.
+    #[test]
+    fn servo_aware_solve_lands_on_the_target() {
+        let states = AirbrakesMPCStates {
+            altitude_asl: 2000.0,
+            velocity: Vector2::new(10.0, 150.0),
+            cd_scale: 1.0,
+            heading: None,
+            enabled_for_s: 0.0,
+            mode: ControllerMode::TargetApogee,
+            atmosphere: Atmosphere::standard(),
+        };
+        let mpc = |target| AirBrakesMPC::new(params(), Atmosphere::standard(), target);
+        let rail = |target| {
+            mpc(target)
+                .update(2000.0, states.velocity)
+                .predicted_apogee_asl
+        };
+        // A quarter of the way down from stowed, so the stowed flaps can
+        // still catch up within the horizon.
+        let target = rail(1e6) - 0.25 * (rail(1e6) - rail(0.0));
+        let mpc = mpc(target);
+        let held = mpc.update_with_servo(&states, None);
+        assert_eq!(held, mpc.update(states.altitude_asl, states.velocity));
+
+        let at_rest = ServoState {
+            actual_extension: held.extension_percentage,
+            commanded_extension: held.extension_percentage,
+        };
+        let settled = mpc.update_with_servo(&states, Some(at_rest));
+        assert_relative_eq!(
+            settled.extension_percentage,
+            held.extension_percentage,
+            epsilon = 0.03
+        );
+        assert_relative_eq!(settled.predicted_apogee_asl, target, epsilon = 1.0);
+
+        let stowed = ServoState {
+            actual_extension: 0.0,
+            commanded_extension: 0.0,
+        };
+        let catching_up = mpc.update_with_servo(&states, Some(stowed));
+        assert!(catching_up.extension_percentage < held.extension_percentage);
+        assert_relative_eq!(catching_up.predicted_apogee_asl, target, epsilon = 1.0);
+    }
+
+    /// The same state flown in every mode. Maximising drag opens the flaps
+    /// wider than landing on the target does — here all the way, since a
+    /// stowed tail leaves the whole coast to give the drag back in — and never
+    /// plans below the floor; the open-loop modes command what they were given, whatever it does to
+    /// the apogee. Each says which mode it was.
+    #[test]
+    fn every_mode_commands_what_it_is_for() {
+        let mpc = |target| AirBrakesMPC::new(params(), Atmosphere::standard(), target);
+        let velocity = Vector2::new(10.0, 150.0);
+        let rail = |target| mpc(target).update(2000.0, velocity).predicted_apogee_asl;
+        let target = rail(1e6) - 0.25 * (rail(1e6) - rail(0.0));
+        let mpc = mpc(target);
+        let states = |mode| AirbrakesMPCStates {
+            altitude_asl: 2000.0,
+            velocity,
+            cd_scale: 1.0,
+            heading: None,
+            enabled_for_s: 1.0,
+            mode,
+            atmosphere: Atmosphere::standard(),
+        };
+
+        let on_target = mpc.update_with_servo(&states(ControllerMode::TargetApogee), None);
+        assert_eq!(on_target.mode, ControllerModeKind::TargetApogee);
+        let most_drag = mpc.update_with_servo(&states(ControllerMode::MaximiseDrag), None);
+        assert_eq!(most_drag.mode, ControllerModeKind::MaximiseDrag);
+        assert!(most_drag.extension_percentage > on_target.extension_percentage);
+        assert!(most_drag.predicted_apogee_asl > target - 1.0);
+
+        let fixed = mpc.update_with_servo(&states(ControllerMode::Fixed { extension: 0.7 }), None);
+        assert_eq!(fixed.mode, ControllerModeKind::Fixed);
+        assert_eq!(fixed.extension_percentage, 0.7);
+
+        let schedule = ExtensionSchedule::from_points(&[(0.0, 0.2), (0.5, 0.9)]).unwrap();
+        let scheduled = mpc.update_with_servo(&states(ControllerMode::Scheduled(schedule)), None);
+        assert_eq!(scheduled.mode, ControllerModeKind::Scheduled);
+        assert_eq!(scheduled.extension_percentage, 0.9);
+        // Held at 0.9 to apogee, which is what `fixed` at 0.9 predicts.
+        let fixed = mpc.update_with_servo(&states(ControllerMode::Fixed { extension: 0.9 }), None);
+        assert_eq!(scheduled.predicted_apogee_asl, fixed.predicted_apogee_asl);
+    }
 }
@@ -0,0 +1,161 @@
+//! [`ControllerMode`] — what the brakes are flown for.
+//!
+//! The MPC exists to land on a target apogee, and that stays the default.
+//! The other modes are for flights whose point is the brakes themselves:
+//! drag data, a characterisation profile, a fixed deploy. Every mode is
+//! selected in [`FlightConfig`](crate::FlightConfig) and reaches the MPC
+//! only inside [`AirbrakesMPCStates`](crate::AirbrakesMPCStates), so none
+//! of them can command anything outside the window
+//! [`FlightEstimators::airbrakes_mpc_states`](crate::FlightEstimators::airbrakes_mpc_states)
+//! opens.
+
+/// How many breakpoints an [`ExtensionSchedule`] holds. Fixed so the
+/// schedule stays a plain array on the board, like the wind profile.
+pub const SCHEDULE_POINTS: usize = 16;
+
+/// What the MPC flies the brakes for. See the module docs.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ControllerMode {
+    /// Land on the target apogee: FDR section 16.7.4's controller.
+    #[default]
+    TargetApogee,
+    /// Fly as much drag as possible without the apogee falling below the
+    /// target, which is read as a floor.
+    ///
+    /// The same search as [`Self::TargetApogee`] with a different tail:
+    /// after the planned ticks the simulation stows the flaps instead of
+    /// parking them at neutral. The command is then the most drag that can
+    /// still be undone by stowing later, so the brakes open early and wide
+    /// and close as the floor comes near — drag data over as much of the
+    /// coast and the Mach range as the floor allows.
+    MaximiseDrag,
+    /// Command the extension [`ExtensionSchedule`] gives for the time since
+    /// the brakes were permitted to open, with no regard to apogee. For
+    /// characterising the flaps and the servo in flight.
+    Scheduled(ExtensionSchedule),
+    /// Hold `extension` (0.0 stowed - 1.0 full) from the moment the brakes
+    /// are permitted to open, which is after burnout and below
+    /// `max_open_mach`, to apogee.
+    Fixed { extension: f32 },
+}
+
+/// [`ControllerMode`] without its data, for the log: one byte saying which
+/// mode produced a command.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ControllerModeKind {
+    TargetApogee = 0,
+    MaximiseDrag = 1,
+    Scheduled = 2,
+    Fixed = 3,
+}
+
+impl ControllerMode {
+    pub fn kind(&self) -> ControllerModeKind {
+        match self {
+            Self::TargetApogee => ControllerModeKind::TargetApogee,
+            Self::MaximiseDrag => ControllerModeKind::MaximiseDrag,
+            Self::Scheduled(_) => ControllerModeKind::Scheduled,
+            Self::Fixed { .. } => ControllerModeKind::Fixed,
+        }
+    }
+}
+
+/// Brake extension against seconds since the brakes were permitted to
+/// open: each breakpoint's extension is held from its time until the next
+/// one's, and the last is held to apogee. Stowed before the first.
+///
+/// Steps rather than ramps because a characterisation profile is a series
+/// of step responses — the edges are the measurement, and the servo's own
+/// slew is the ramp.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtensionSchedule {
+    /// Breakpoint times (s), strictly increasing; the first `len` are used.
+    time_s: [f32; SCHEDULE_POINTS],
+    /// The extension from each breakpoint on, 0.0 - 1.0.
+    extension: [f32; SCHEDULE_POINTS],
+    /// At least 1.
+    len: usize,
+}
+
+impl ExtensionSchedule {
+    /// A schedule from `(time_s, extension)` breakpoints, times strictly
+    /// increasing. `None` for one this cannot fly: no breakpoints, more than
+    /// [`SCHEDULE_POINTS`], times out of order or negative, an extension
+    /// outside 0.0 - 1.0, or anything non-finite.
+    pub fn from_points(points: &[(f32, f32)]) -> Option<Self> {
+        if points.is_empty() || points.len() > SCHEDULE_POINTS {
+            return None;
+        }
+        let mut schedule = Self {
+            time_s: [0.0; SCHEDULE_POINTS],
+            extension: [0.0; SCHEDULE_POINTS],
+            len: points.len(),
+        };
+        for (i, &(time_s, extension)) in points.iter().enumerate() {
+            let in_order = if i == 0 {
+                time_s >= 0.0
+            } else {
+                time_s > schedule.time_s[i - 1]
+            };
+            // The range checks are false on NaN, and an infinite time can
+            // only be out of order or never reached.
+            if !in_order || !time_s.is_finite() || !(0.0..=1.0).contains(&extension) {
+                return None;
+            }
+            schedule.time_s[i] = time_s;
+            schedule.extension[i] = extension;
+        }
+        Some(schedule)
+    }
+
+    /// The `(time_s, extension)` breakpoints [`Self::from_points`] was
+    /// given, in order.
+    pub fn points(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.time_s[..self.len]
+            .iter()
+            .copied()
+            .zip(self.extension.iter().copied())
+    }
+
+    /// The extension commanded `time_s` after the brakes were permitted to
+    /// open.
+    pub fn at(&self, time_s: f32) -> f32 {
+        self.time_s[..self.len]
+            .iter()
+            .zip(&self.extension)
+            .take_while(|(t, _)| **t <= time_s)
+            .last()
+            .map_or(0.0, |(_, extension)| *extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_holds_each_step_and_the_last_to_apogee() {
+        let schedule =
+            ExtensionSchedule::from_points(&[(0.5, 0.3), (2.0, 1.0), (4.0, 0.0)]).unwrap();
+        assert_eq!(schedule.at(0.0), 0.0);
+        assert_eq!(schedule.at(0.5), 0.3);
+        assert_eq!(schedule.at(1.99), 0.3);
+        assert_eq!(schedule.at(3.0), 1.0);
+        assert_eq!(schedule.at(100.0), 0.0);
+    }
+
+    #[test]
+    fn schedule_refuses_what_it_cannot_fly() {
+        assert!(ExtensionSchedule::from_points(&[]).is_none());
+        assert!(ExtensionSchedule::from_points(&[(1.0, 0.5), (1.0, 0.6)]).is_none());
+        assert!(ExtensionSchedule::from_points(&[(-1.0, 0.5)]).is_none());
+        assert!(ExtensionSchedule::from_points(&[(1.0, 1.5)]).is_none());
+        assert!(ExtensionSchedule::from_points(&[(f32::NAN, 0.5)]).is_none());
+        assert!(ExtensionSchedule::from_points(&[(1.0, f32::NAN)]).is_none());
+        assert!(ExtensionSchedule::from_points(&[(0.0, 0.0); SCHEDULE_POINTS + 1]).is_none());
+    }
+}
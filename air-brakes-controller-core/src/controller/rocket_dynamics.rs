@@ -1,37 +1,83 @@
+use nalgebra::Vector3;
+
 use crate::{
-    controller::{DT, Derivative, RocketParameters, State},
-    utils::approximate_air_density,
+    atmosphere::Atmosphere,
+    controller::{
+        CD_MACH_POINTS, DT, Derivative, RocketParameters, ServoModel, ServoState, State,
+        WindProfile,
+    },
 };
 
-/// 2D ballistic dynamics: drag on total speed, opposing the velocity
-/// vector; gravity on the vertical component.
+/// Ballistic dynamics: drag on airspeed, opposing the velocity relative to
+/// the air; gravity on the vertical component. cd is looked up at the
+/// state's own Mach, in `atmosphere`'s air. `wind` is in the state's frame,
+/// `(downrange, crossrange)` (see [`WindProfile::in_flight_frame`]); on a
+/// calm profile this is the 2-D model exactly, with crossrange staying 0.
 pub fn calculate_state_derivatives(
     air_brakes_drag_percentage: f32,
     state: &State,
     rocket_param: &RocketParameters,
+    atmosphere: &Atmosphere,
+    wind: &WindProfile,
 ) -> Derivative<State> {
+    let column = rocket_param.get_cd_from_extension_percentage(
+        rocket_param.drag_percentage_to_extension_percentage(air_brakes_drag_percentage),
+    );
     calculate_state_derivatives_at_cd(
-        rocket_param.get_cd_from_drag_percentage(air_brakes_drag_percentage),
+        cd_at_state(&column, state, rocket_param, atmosphere, wind),
         state,
         rocket_param,
+        atmosphere,
+        wind,
     )
 }
 
+/// The state's velocity relative to the air. Until 2026-10-18 the
+/// simulation had no wind, and this was the ground velocity itself.
+fn airspeed(state: &State, wind: &WindProfile) -> Vector3<f32> {
+    let wind = wind.at(state.altitude_asl);
+    Vector3::new(
+        state.velocity.x - wind.x,
+        state.velocity.y,
+        state.velocity.z - wind.y,
+    )
+}
+
+/// cd for one extension's column of the table at the Mach `state` is flying.
+///
+/// One sqrt (`VSQRT` on the board, see `utils::sqrt`) and two divides.
+fn cd_at_state(
+    column: &[f32; CD_MACH_POINTS],
+    state: &State,
+    rocket_param: &RocketParameters,
+    atmosphere: &Atmosphere,
+    wind: &WindProfile,
+) -> f32 {
+    let mach = crate::utils::sqrt(airspeed(state, wind).magnitude_squared())
+        / atmosphere.speed_of_sound(state.altitude_asl);
+    rocket_param.cd_at_mach(column, mach)
+}
+
 /// The same dynamics with cd already resolved.
 ///
-/// [`simulate_apogee_rk2`] holds cd fixed across both stages of an RK2 step and
-/// changes it only while the modelled servo is moving -- a handful of steps out
-/// of a coast that runs to a couple of hundred -- so resolving it per step
-/// instead of per derivative evaluation halves the interpolation work on the
-/// hot path and lets the tail run with no interpolation at all.
+/// [`simulate_apogee_rk2`] holds cd fixed across both stages of an RK2 step:
+/// Mach moves by well under 1% in a 0.1 s step, so resolving it per step
+/// instead of per derivative evaluation halves the lookup work on the hot
+/// path for no visible change in the answer. The extension half of the
+/// lookup changes only while the modelled servo is moving -- a handful of
+/// steps out of a coast that runs to a couple of hundred -- and is cached
+/// across the rest.
 fn calculate_state_derivatives_at_cd(
     cd: f32,
     state: &State,
     rocket_param: &RocketParameters,
+    atmosphere: &Atmosphere,
+    wind: &WindProfile,
 ) -> Derivative<State> {
-    let air_density = approximate_air_density(state.altitude_asl);
+    let air_density = atmosphere.air_density(state.altitude_asl);
 
-    let speed_squared = state.velocity.magnitude_squared();
+    let airspeed = airspeed(state, wind);
+    let speed_squared = airspeed.magnitude_squared();
     // Drag acceleration is -(v/|v|) * k*|v|^2, which is just -k*|v|*v. Written
     // that way it costs one sqrt and a scalar multiply; written with
     // `normalize()` it cost a sqrt plus two divides, and nalgebra's sqrt comes
@@ -39,9 +85,9 @@ fn calculate_state_derivatives_at_cd(
     // and on this M7 the pair was worth ~1.7 ms of a solve.
     let k = 0.5 * cd * air_density * rocket_param.reference_area / rocket_param.burnout_mass;
     let mut acceleration = if speed_squared > 1e-6 {
-        state.velocity * (-k * crate::utils::sqrt(speed_squared))
+        airspeed * (-k * crate::utils::sqrt(speed_squared))
     } else {
-        nalgebra::Vector2::zeros()
+        Vector3::zeros()
     };
     acceleration.y -= 9.81;
 
@@ -62,18 +108,16 @@ fn calculate_state_derivatives_at_cd(
 /// finiteness check below does not catch still terminates.
 const MAX_APOGEE_STEPS: usize = 2000;
 
-/// How many 0.1 s steps the solver holds its candidate before letting the
-/// modelled servo retract to the neutral tail.
+/// How many 0.1 s steps of command the solver plans before handing the flaps
+/// to the neutral tail.
 ///
 /// FDR section 16.7.4.2's rule 1 is a single tick, which is the right model for
-/// an instantaneous actuator. Icarus's servo is not one. Measured off both
-/// transitions in VLF5's HIL log -- the deploy at 236.534 s and the retract at
-/// 258.336 s, which agree to 1% -- it takes 25.8 ms of dead time and then
-/// 0.376 s of slew at 2.67 /s to cross the full stroke: 0.40 s, four MPC
-/// ticks. A command issued now is therefore still in transit four ticks later,
-/// and the receding horizon re-issues it on every one of them. Holding the
-/// candidate for the stroke time is what the plant does with a command; holding
-/// it for one tick is what nothing does.
+/// an instantaneous actuator. Icarus's servo is not one: a full stroke takes
+/// 0.40 s, four MPC ticks (see [`ServoModel::ICARUS`]). A command issued now is
+/// therefore still in transit four ticks later, and the receding horizon
+/// re-issues it on every one of them. Holding the candidate for the stroke
+/// time is what the plant does with a command; holding it for one tick is
+/// what nothing does.
 ///
 /// Against the one-tick hold, simulated on the rate-limited plant from the
 /// LC'25 birth state: apogee tracking is unchanged -- 0.00 m at every reachable
@@ -83,35 +127,74 @@ const MAX_APOGEE_STEPS: usize = 2000;
 /// the final interpolation resolves the command out of.
 ///
 /// Feeding the servo's *measured* extension into the prediction was tried
-/// instead and rejected: it closes a loop from servo position to command that
-/// the one-tick horizon cannot damp, and multiplied commanded travel by 10-20x
-/// for no tracking gain.
-const CANDIDATE_HOLD_STEPS: usize = 4;
-
-/// Extension the modelled servo covers in one step on its way back to neutral.
+/// next and rejected: it closes a loop from servo position to command that a
+/// single held candidate cannot damp, and multiplied commanded travel by 10-20x
+/// for no tracking gain. The measured extension now goes in again,
+/// through [`AirBrakesMPC::update_with_servo`], which plans these steps'
+/// commands one by one and charges for moving them, so the loop is damped by
+/// the plan rather than by the hold.
 ///
-/// The full stroke is [`CANDIDATE_HOLD_STEPS`] steps by definition of that
-/// constant, so one step is that fraction of it. This makes the handover as
-/// long as the travel demands rather than a fixed number of steps: from full
-/// deploy the flaps reach neutral in two steps, from stowed in three.
-const RETRACT_PER_STEP: f32 = 1.0 / CANDIDATE_HOLD_STEPS as f32;
+/// [`AirBrakesMPC::update_with_servo`]: crate::AirBrakesMPC::update_with_servo
+pub(crate) const HORIZON_STEPS: usize = 4;
+
+/// The brakes over one apogee simulation: the servo, what it is doing at the
+/// start, the extension commanded on each of the first [`HORIZON_STEPS`]
+/// steps, and the one commanded from then on.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Actuation {
+    pub(crate) servo: ServoModel,
+    pub(crate) start: ServoState,
+    pub(crate) plan: [f32; HORIZON_STEPS],
+    /// Neutral for FDR's sequence; see [`simulate_apogee_rk2`] and
+    /// [`ControllerMode`](crate::ControllerMode) for the others.
+    pub(crate) tail: f32,
+}
+
+impl Actuation {
+    /// FDR rule 1 on an ideal start: `candidate_drag_percentage` held for
+    /// the whole horizon, with the flaps already there. What the solver
+    /// flies when it is not told what the servo is doing. Neutral after.
+    pub(crate) fn held(
+        candidate_drag_percentage: f32,
+        rocket_param: &RocketParameters,
+        servo: ServoModel,
+    ) -> Self {
+        let extension =
+            rocket_param.drag_percentage_to_extension_percentage(candidate_drag_percentage);
+        Self {
+            servo,
+            start: ServoState {
+                actual_extension: extension,
+                commanded_extension: extension,
+            },
+            plan: [extension; HORIZON_STEPS],
+            tail: rocket_param.neutral_extension_percentage(),
+        }
+    }
+}
 
 /// RK2 the rocket to apogee -- the first step where v_y <= 0 -- and return
-/// that altitude, ASL (m).
+/// that altitude, ASL (m), flying through `wind` (in the state's frame, see
+/// [`calculate_state_derivatives`]) with the brakes doing what `actuation`
+/// commands.
 ///
-/// The schedule below is FDR section 16.7.4.2's optimal extension sequence, and
-/// every number in it is a *drag* percentage (-1 stowed, +1 full), never an
-/// extension percentage. The two are different axes; see
-/// [`RocketParameters::get_cd_from_drag_percentage`].
+/// The schedule is FDR section 16.7.4.2's optimal extension sequence:
 ///
-/// - steps 0..CANDIDATE_HOLD_STEPS: the candidate the solver is testing
-///   (the report's rule 1, stretched to the servo's stroke time -- see that
+/// - steps 0..HORIZON_STEPS: the commands the solver is testing (the
+///   report's rule 1, stretched to the servo's stroke time -- see that
 ///   constant)
-/// - then the flaps retract to neutral at the servo's rate, in *extension*,
-///   which is where the rate limit physically lives (rule 3's intermediate
-///   ticks, as many as the travel needs rather than a single half-drag step)
+/// - then neutral is commanded, and the modelled servo takes the flaps there
+///   at its own pace (rule 3's intermediate ticks, as many as the travel
+///   needs rather than a single half-drag step)
 /// - once neutral: drag 0.0, held all the way to apogee (rule 2)
 ///
+/// That is `actuation.tail` at neutral, which is what the solver flies to a
+/// target. The other controller modes move the tail, and the sequence with
+/// it.
+///
+/// The flaps are wherever [`ServoModel`] puts them, from `actuation.start`:
+/// each step flies the mean of the extension it starts and ends at.
+///
 /// Drag 0.0 is **not** stowed. It is the neutral position -- half the brakes'
 /// full drag contribution, ~60% extension on VLF5's cd table -- and the report
 /// picks it deliberately: parked mid-authority, a later disturbance can be
@@ -120,62 +203,146 @@ const RETRACT_PER_STEP: f32 = 1.0 / CANDIDATE_HOLD_STEPS as f32;
 /// brake; both are rejected by name in the sequence table.
 ///
 /// Two consequences to know before reading the number this returns:
-/// - it means "apogee if I fly neutral from here", which is the report's
-///   *nominal apogee*, not a forecast of where the rocket ends up;
-/// - only the held steps differ between candidates, so sweeping the whole
-///   [-1, +1] range moves the answer ~30 m against ~330 m of real brake
-///   authority. The loop closes by re-solving every tick, not by this
-///   gradient.
-pub fn simulate_apogee_rk2(
-    candidate_air_brakes_drag_percentage: f32,
+/// - it means "apogee if I fly this plan and then neutral", which is the
+///   report's *nominal apogee*, not a forecast of where the rocket ends up;
+/// - only the planned steps differ between candidates, so sweeping the whole
+///   stroke moves the answer ~30 m against ~330 m of real brake authority.
+///   The loop closes by re-solving every tick, not by this gradient.
+pub(crate) fn simulate_apogee_rk2(
+    actuation: &Actuation,
     initial_state: &State,
     rocket_param: &RocketParameters,
+    atmosphere: &Atmosphere,
+    wind: &WindProfile,
 ) -> f32 {
-    // A non-finite entry state has no trajectory to fly, and the normal exit
-    // path would hand the caller `NaN + delta_alt` = NaN. That is the one
-    // return value worth ruling out everywhere in this function: `NaN >
-    // target` is false at every bisection step in `AirBrakesMPC::update`, the
-    // NaN survives into `drag_percentage_to_extension_percentage`, and that
-    // table walk falls through to 1.0 — full flap deploy off a garbage state
-    // (measured pre-guard: vx = inf in, extension 1.0 out).
-    //
-    // 0 m ASL is below every reachable target apogee, so the bisection reads
-    // this as an undershoot and stows instead.
-    if !initial_state.altitude_asl.is_finite()
-        || !initial_state.velocity.x.is_finite()
-        || !initial_state.velocity.y.is_finite()
-    {
-        return 0.0;
+    match rk2_to_apogee(actuation, initial_state, rocket_param, atmosphere, wind) {
+        Some((apogee_asl, _)) => apogee_asl,
+        // A non-finite entry state has no trajectory to fly, and the normal
+        // exit path would hand the caller `NaN + delta_alt` = NaN. That is
+        // the one return value worth ruling out everywhere in this function:
+        // `NaN > target` is false at every bisection step in
+        // `AirBrakesMPC::update`, the NaN survives into
+        // `drag_percentage_to_extension_percentage`, and that table walk
+        // falls through to 1.0 — full flap deploy off a garbage state
+        // (measured pre-guard: vx = inf in, extension 1.0 out).
+        //
+        // 0 m ASL is below every reachable target apogee, so the bisection
+        // reads this as an undershoot and stows instead.
+        None if !is_finite(initial_state) => 0.0,
+        // A divergence, or the step cap. Hand back the *entry* altitude, not
+        // the propagated one: by the time the state goes non-finite the
+        // propagated altitude may have run off to 1e30, which the caller
+        // would read as an enormous overshoot and answer with full deploy —
+        // exactly the wrong way round. The entry altitude means "no apogee
+        // above here can be predicted", the same answer the
+        // already-descending case gives, and it sits below any reachable
+        // target so the bisection stows the flaps.
+        None => initial_state.altitude_asl,
+    }
+}
+
+/// RK2 a coast from `initial_state` to apogee in calm air with the flaps
+/// held at `extension_percentage` throughout, and return the apogee ASL (m)
+/// and how long after the start it is reached (s).
+///
+/// [`simulate_apogee_rk2`]'s integration on a held actuation: this is for
+/// the config check, which asks how soon an apogee could come, not for the
+/// controller. `None` wherever that function falls back to a fixed
+/// altitude — a non-finite start, a divergence, or the step cap — since no
+/// time can be read off those.
+pub(crate) fn coast_to_apogee_rk2(
+    extension_percentage: f32,
+    initial_state: &State,
+    rocket_param: &RocketParameters,
+    atmosphere: &Atmosphere,
+) -> Option<(f32, f32)> {
+    // The flaps start where they are commanded to stay, so the servo never
+    // moves them and its model does not enter.
+    let actuation = Actuation {
+        servo: ServoModel::ICARUS,
+        start: ServoState {
+            actual_extension: extension_percentage,
+            commanded_extension: extension_percentage,
+        },
+        plan: [extension_percentage; HORIZON_STEPS],
+        tail: extension_percentage,
+    };
+    rk2_to_apogee(
+        &actuation,
+        initial_state,
+        rocket_param,
+        atmosphere,
+        &WindProfile::calm(),
+    )
+}
+
+fn is_finite(state: &State) -> bool {
+    state.altitude_asl.is_finite() && state.velocity.iter().all(|v| v.is_finite())
+}
+
+/// The integration behind [`simulate_apogee_rk2`] and
+/// [`coast_to_apogee_rk2`]: the apogee ASL (m) and how long after the start
+/// it is reached (s), the start itself when already descending, and `None`
+/// for a non-finite start, a divergence or the step cap.
+fn rk2_to_apogee(
+    actuation: &Actuation,
+    initial_state: &State,
+    rocket_param: &RocketParameters,
+    atmosphere: &Atmosphere,
+    wind: &WindProfile,
+) -> Option<(f32, f32)> {
+    if !is_finite(initial_state) {
+        return None;
     }
 
     // If we are already descending or stationary, return current altitude
     if initial_state.velocity.y <= 0.0 {
-        return initial_state.altitude_asl;
+        return Some((initial_state.altitude_asl, 0.0));
     }
 
     let mut state = initial_state.clone();
 
-    // The schedule, carried as an extension rather than as a drag percentage.
-    // The retract is rate limited, and the rate limit lives in the linkage, so
-    // it is linear in extension and not in drag: ramping the drag percentage
-    // instead would walk the flaps back along the wrong curve, slowly at the
-    // deployed end where cd changes fastest. Both coordinates agree at the
-    // ends, so the held steps are unchanged -- only the handover moves.
-    let neutral_extension = rocket_param.neutral_extension_percentage();
-    let mut extension = rocket_param
-        .drag_percentage_to_extension_percentage(candidate_air_brakes_drag_percentage);
-    let mut cd = rocket_param.get_cd_from_extension_percentage(extension);
+    // The schedule is carried in extension rather than in drag percentage.
+    // The servo's rate limit lives in the linkage, so it is linear in
+    // extension and not in drag: moving the drag percentage instead would
+    // walk the flaps along the wrong curve, slowly at the deployed end where
+    // cd changes fastest.
+    let servo = &actuation.servo;
+    let mut extension = actuation.start.actual_extension.clamp(0.0, 1.0);
+    let mut command = actuation.start.commanded_extension;
+    let mut flown_extension = f32::NAN;
+    let mut column = [0.0; CD_MACH_POINTS];
 
     for step_index in 0..MAX_APOGEE_STEPS {
+        // The servo over this step. Once it has arrived, the mean stops
+        // moving, and with it the extension half of the cd lookup.
+        let next_command = actuation
+            .plan
+            .get(step_index)
+            .copied()
+            .unwrap_or(actuation.tail);
+        let next_extension = servo.step(extension, command, next_command, DT);
+        let mean_extension = 0.5 * (extension + next_extension);
+        if mean_extension != flown_extension {
+            flown_extension = mean_extension;
+            column = rocket_param.get_cd_from_extension_percentage(flown_extension);
+        }
+        command = next_command;
+        extension = next_extension;
+
+        let cd = cd_at_state(&column, &state, rocket_param, atmosphere, wind);
+
         // RK2 (midpoint) integration
-        let Derivative(k1) = calculate_state_derivatives_at_cd(cd, &state, rocket_param);
+        let Derivative(k1) =
+            calculate_state_derivatives_at_cd(cd, &state, rocket_param, atmosphere, wind);
 
         let mid_state = State {
             altitude_asl: state.altitude_asl + k1.altitude_asl * (0.5 * DT),
             velocity: state.velocity + k1.velocity * (0.5 * DT),
         };
 
-        let Derivative(k2) = calculate_state_derivatives_at_cd(cd, &mid_state, rocket_param);
+        let Derivative(k2) =
+            calculate_state_derivatives_at_cd(cd, &mid_state, rocket_param, atmosphere, wind);
 
         let next_state = State {
             altitude_asl: state.altitude_asl + k2.altitude_asl * DT,
@@ -189,60 +356,37 @@ pub fn simulate_apogee_rk2(
         // apogee test below never fires and the loop runs to the step cap
         // (before the cap existed, forever: measured, vx = 458196 m/s hung a
         // 3 s watchdog).
-        //
-        // Hand back the *entry* altitude, not `state.altitude_asl`: by the
-        // time the state goes non-finite the propagated altitude may have run
-        // off to 1e30, which the caller would read as an enormous overshoot
-        // and answer with full deploy — exactly the wrong way round. The entry
-        // altitude means "no apogee above here can be predicted", the same
-        // answer the already-descending case above gives, and it sits below
-        // any reachable target so the bisection stows the flaps.
-        if !next_state.altitude_asl.is_finite()
-            || !next_state.velocity.x.is_finite()
-            || !next_state.velocity.y.is_finite()
-        {
-            return initial_state.altitude_asl;
+        if !is_finite(&next_state) {
+            return None;
         }
 
         // Check for apogee crossing within this step
         let vy0 = state.velocity.y;
         let vy1 = next_state.velocity.y;
         if vy1 <= 0.0 {
+            let step_start_s = step_index as f32 * DT;
             // Linearly interpolate vertical velocity over the step to estimate
             // the exact time t_zero where v_y crosses zero, then integrate
             // velocity to get altitude at apogee.
             let denom = vy1 - vy0;
             if denom.abs() < core::f32::EPSILON {
-                return next_state.altitude_asl.max(state.altitude_asl);
+                return Some((
+                    next_state.altitude_asl.max(state.altitude_asl),
+                    step_start_s + DT,
+                ));
             }
             let t_zero = DT * (-vy0) / denom; // 0 <= t_zero <= DT
             let delta_alt = vy0 * t_zero + 0.5 * (denom / DT) * t_zero * t_zero;
-            return state.altitude_asl + delta_alt;
+            return Some((state.altitude_asl + delta_alt, step_start_s + t_zero));
         }
 
         state = next_state;
-
-        // Step the servo for the next step. Exact assignment on arrival, so
-        // the comparison above stops the interpolation for good rather than
-        // creeping on float residue.
-        if step_index + 1 >= CANDIDATE_HOLD_STEPS && extension != neutral_extension {
-            let remaining = neutral_extension - extension;
-            extension = if remaining.abs() <= RETRACT_PER_STEP {
-                neutral_extension
-            } else if remaining > 0.0 {
-                extension + RETRACT_PER_STEP
-            } else {
-                extension - RETRACT_PER_STEP
-            };
-            cd = rocket_param.get_cd_from_extension_percentage(extension);
-        }
     }
 
     // Step cap exhausted: 200 s of simulated coast without v_y reaching zero.
     // No flyable trajectory gets here (see `MAX_APOGEE_STEPS`), so there is no
-    // better answer available than the same "no apogee above here" the guards
-    // above return.
-    initial_state.altitude_asl
+    // apogee to report.
+    None
 }
 
 #[cfg(test)]
@@ -255,33 +399,43 @@ mod test {
 
     use super::*;
 
-    /// The 2D sim must account for tilt: the same total speed with a
+    /// Every test here flies the standard day, and all but the wind tests
+    /// fly it in calm air.
+    const ISA: Atmosphere = Atmosphere::standard();
+    const CALM: WindProfile = WindProfile::calm();
+
+    /// `drag` held over the horizon on Icarus, flaps already there.
+    fn held(drag: f32, rocket: &RocketParameters) -> Actuation {
+        Actuation::held(drag, rocket, ServoModel::ICARUS)
+    }
+
+    /// The sim must account for tilt: the same total speed with a
     /// horizontal component reaches a lower apogee than flying straight
     /// up.
     #[test]
     fn tilted_flight_reaches_lower_apogee() {
         init_logger();
-        let rocket_param = RocketParameters {
-            burnout_mass: 19.417,
-            cd: [0.5; 5],
-            reference_area: 0.0136,
-        };
+        let rocket_param = RocketParameters::mach_independent(19.417, [0.5; 5], 0.0136);
         let straight = simulate_apogee_rk2(
-            0.0,
+            &held(0.0, &rocket_param),
             &State {
                 altitude_asl: 1000.0,
-                velocity: Vector2::new(0.0, 250.0),
+                velocity: Vector3::new(0.0, 250.0, 0.0),
             },
             &rocket_param,
+            &ISA,
+            &CALM,
         );
         let tilted = simulate_apogee_rk2(
-            0.0,
+            &held(0.0, &rocket_param),
             &State {
                 altitude_asl: 1000.0,
                 // same total speed, 30 deg tilt
-                velocity: Vector2::new(125.0, 216.5),
+                velocity: Vector3::new(125.0, 216.5, 0.0),
             },
             &rocket_param,
+            &ISA,
+            &CALM,
         );
         log_info!("straight {straight}, tilted {tilted}");
         assert!(tilted < straight - 100.0);
@@ -290,18 +444,51 @@ mod test {
     /// VLF5's hil-dual airframe at the LC'25 airbrakes birth state.
     fn vlf5() -> (RocketParameters, State) {
         (
-            RocketParameters {
-                burnout_mass: 18.696,
-                cd: [0.61365, 0.69816, 0.8084, 0.96641, 1.12441],
-                reference_area: 0.009854945,
-            },
+            RocketParameters::mach_independent(
+                18.696,
+                [0.61365, 0.69816, 0.8084, 0.96641, 1.12441],
+                0.009854945,
+            ),
             State {
                 altitude_asl: 6802.0474,
-                velocity: Vector2::new(31.1866, 244.46007),
+                velocity: Vector3::new(31.1866, 244.46007, 0.0),
             },
         )
     }
 
+    /// A cd that falls as the coast slows must carry the apogee above the same
+    /// table held at its top row, and below the same table held at its
+    /// bottom one: the simulation has to look the row up as Mach falls, not
+    /// once at entry. The birth state here is Mach 0.78.
+    #[test]
+    fn apogee_follows_cd_down_the_mach_table() {
+        init_logger();
+        let (flat, state) = vlf5();
+        let mut tabled = flat.clone();
+        tabled.cd_mach = [0.4, 0.6, 0.7, 0.8];
+        for (row, scale) in tabled.cd.iter_mut().zip([0.87, 0.9, 0.93, 1.0]) {
+            for c in row.iter_mut() {
+                *c *= scale;
+            }
+        }
+        let mut low = flat.clone();
+        for c in low.cd.iter_mut().flatten() {
+            *c *= 0.87;
+        }
+
+        for drag in [-1.0, 0.0, 1.0] {
+            let top = simulate_apogee_rk2(&held(drag, &flat), &state, &flat, &ISA, &CALM);
+            let mach_aware =
+                simulate_apogee_rk2(&held(drag, &tabled), &state, &tabled, &ISA, &CALM);
+            let bottom = simulate_apogee_rk2(&held(drag, &low), &state, &low, &ISA, &CALM);
+            log_info!("drag {drag}: top row {top}, Mach table {mach_aware}, bottom row {bottom}");
+            assert!(
+                top < mach_aware && mach_aware < bottom,
+                "drag {drag}: {mach_aware} is not between {top} and {bottom}"
+            );
+        }
+    }
+
     /// The bisection in `AirBrakesMPC::update` is only valid because apogee
     /// falls monotonically as the candidate's drag rises -- FDR section
     /// 16.7.4.3 rests the whole solver on it. Holding the candidate for the
@@ -314,7 +501,7 @@ mod test {
         let mut previous = f32::INFINITY;
         for i in 0..=40 {
             let drag = -1.0 + 2.0 * (i as f32) / 40.0;
-            let apogee = simulate_apogee_rk2(drag, &state, &rocket);
+            let apogee = simulate_apogee_rk2(&held(drag, &rocket), &state, &rocket, &ISA, &CALM);
             assert!(
                 apogee < previous,
                 "apogee {apogee} at drag {drag} did not fall below {previous}"
@@ -322,8 +509,8 @@ mod test {
             previous = apogee;
         }
 
-        let spread =
-            simulate_apogee_rk2(-1.0, &state, &rocket) - simulate_apogee_rk2(1.0, &state, &rocket);
+        let spread = simulate_apogee_rk2(&held(-1.0, &rocket), &state, &rocket, &ISA, &CALM)
+            - simulate_apogee_rk2(&held(1.0, &rocket), &state, &rocket, &ISA, &CALM);
         log_info!("rail-to-rail spread {spread} m");
         // One tick of hold gave 10.3 m here, which is what the final
         // interpolation has to resolve a command out of. The stroke-length
@@ -343,16 +530,16 @@ mod test {
         let (rocket, state) = vlf5();
 
         // Far above anything this coast can reach, so the solve stows.
-        let solution = AirBrakesMPC::new(rocket.clone(), 20000.0)
-            .update(state.altitude_asl, state.velocity);
+        let solution = AirBrakesMPC::new(rocket.clone(), ISA, 20000.0)
+            .update(state.altitude_asl, state.velocity.xy());
         assert!(
             solution.extension_percentage < 1e-6,
             "expected a stowed command, got {}",
             solution.extension_percentage
         );
 
-        let stowed = simulate_apogee_rk2(-1.0, &state, &rocket);
-        let neutral = simulate_apogee_rk2(0.0, &state, &rocket);
+        let stowed = simulate_apogee_rk2(&held(-1.0, &rocket), &state, &rocket, &ISA, &CALM);
+        let neutral = simulate_apogee_rk2(&held(0.0, &rocket), &state, &rocket, &ISA, &CALM);
         log_info!(
             "reported {}, stowed {stowed}, neutral {neutral}",
             solution.predicted_apogee_asl
@@ -388,11 +575,8 @@ mod test {
         // Strictly increasing cd table so the extension mapping is actually
         // exercised; a flat table short-circuits to 0.0 on its first branch
         // and would hide a bad answer.
-        let rocket_param = RocketParameters {
-            burnout_mass: 19.417,
-            cd: [0.3, 0.4, 0.5, 0.65, 0.8],
-            reference_area: 0.0136,
-        };
+        let rocket_param =
+            RocketParameters::mach_independent(19.417, [0.3, 0.4, 0.5, 0.65, 0.8], 0.0136);
         const ALT_ASL: f32 = 1032.0 + 251.0;
         const TARGET_ASL: f32 = 3048.0; // 10000 ft, Osiris's target
 
@@ -404,14 +588,16 @@ mod test {
             let param = rocket_param.clone();
             std::thread::spawn(move || {
                 let apogee = simulate_apogee_rk2(
-                    0.5,
+                    &held(0.5, &param),
                     &State {
                         altitude_asl: ALT_ASL,
-                        velocity: Vector2::new(vx, 308.7624),
+                        velocity: Vector3::new(vx, 308.7624, 0.0),
                     },
                     &param,
+                    &ISA,
+                    &CALM,
                 );
-                let solution = AirBrakesMPC::new(param, TARGET_ASL)
+                let solution = AirBrakesMPC::new(param, ISA, TARGET_ASL)
                     .update(ALT_ASL, Vector2::new(vx, 308.7624));
                 let _ = tx.send((apogee, solution));
             });
@@ -442,4 +628,28 @@ mod test {
             );
         }
     }
+
+    /// Drag acts on the velocity relative to the air. Flying the same ground
+    /// velocity into a headwind is more airspeed and so more drag, and with
+    /// a tailwind less; a crosswind adds airspeed whichever side it comes
+    /// from, and the two sides mirror exactly.
+    #[test]
+    fn wind_moves_apogee_through_the_airspeed() {
+        init_logger();
+        let (rocket, state) = vlf5();
+        let apogee = |wind: WindProfile| {
+            simulate_apogee_rk2(&held(0.0, &rocket), &state, &rocket, &ISA, &wind)
+        };
+
+        let calm = apogee(CALM);
+        let head = apogee(WindProfile::constant(-10.0, 0.0));
+        let tail = apogee(WindProfile::constant(10.0, 0.0));
+        let left = apogee(WindProfile::constant(0.0, 10.0));
+        let right = apogee(WindProfile::constant(0.0, -10.0));
+        log_info!("calm {calm}, head {head}, tail {tail}, cross {left} / {right}");
+
+        assert!(head < calm && calm < tail);
+        assert!(left < calm);
+        assert_eq!(left, right);
+    }
 }
@@ -0,0 +1,157 @@
+//! [`ServoModel`] — how the brake flaps follow a command, for the MPC's
+//! apogee simulation.
+
+/// The brake servo as the apogee simulation flies it: a command reaches the
+/// servo after `dead_time_s`, and the flaps then follow it as a first-order
+/// lag of `time_constant_s`, never faster than `slew_per_s`.
+///
+/// A large step is all slew: the flaps leave at the rate limit and only
+/// finish on the lag, in the last `slew_per_s * time_constant_s` of travel.
+/// A small one never reaches the rate limit and is all lag.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoModel {
+    /// From a command leaving the flight computer to the servo moving on it
+    /// (s). Modelled within one simulation step, so anything above
+    /// [`DT`](super::DT) is taken as one step.
+    pub dead_time_s: f32,
+    /// The lag's time constant (s); 0.0 is a pure rate limit.
+    pub time_constant_s: f32,
+    /// Fastest the flaps move, in full strokes per second.
+    pub slew_per_s: f32,
+}
+
+/// What the servo is doing as the MPC is asked for a command: the extension
+/// Icarus last reported (`IcarusStatusMessage::actual_extension_percentage`)
+/// and the one last commanded, both 0.0 (stowed) - 1.0 (full).
+///
+/// The commanded extension is still in flight for up to the dead time, so
+/// the simulation keeps following it until the new command lands.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoState {
+    pub actual_extension: f32,
+    pub commanded_extension: f32,
+}
+
+/// Within this of the command the flaps are taken to have arrived (0.01%
+/// of the stroke, a tenth of Icarus's reporting resolution). The lag never
+/// arrives on its own, and while the extension moves the simulation pays a
+/// cd table lookup every step.
+const ARRIVED: f32 = 1e-4;
+
+impl ServoModel {
+    /// Icarus, measured off both transitions in VLF5's HIL log — the deploy
+    /// at 236.534 s and the retract at 258.336 s, which agree to 1%: 25.8 ms
+    /// of dead time, then 0.376 s of slew at 2.67 strokes/s to cross the
+    /// full stroke.
+    ///
+    /// The two transitions are full strokes, and a full stroke is dead time
+    /// plus slew: no lag tail was resolved after either. The time constant is
+    /// therefore one Icarus control cycle, 10 ms — the least lag a 100 Hz
+    /// position loop can have. A small step in a v19 log, where commanded
+    /// and actual extension share the 100 Hz record, is what would measure
+    /// it.
+    pub const ICARUS: Self = Self {
+        dead_time_s: 0.0258,
+        time_constant_s: 0.01,
+        slew_per_s: 2.67,
+    };
+
+    /// The extension `dt` seconds on from `extension`, with `previous` the
+    /// command in force until `next` replaces it at the start of the
+    /// interval.
+    pub(crate) fn step(&self, extension: f32, previous: f32, next: f32, dt: f32) -> f32 {
+        let dead_time = self.dead_time_s.clamp(0.0, dt);
+        let extension = self.follow(extension, previous, dead_time);
+        self.follow(extension, next, dt - dead_time)
+    }
+
+    /// Follow a constant `command` for `dt`, exactly: slew until within the
+    /// rate limit's reach of the lag, then decay.
+    fn follow(&self, extension: f32, command: f32, dt: f32) -> f32 {
+        let command = command.clamp(0.0, 1.0);
+        let error = command - extension;
+        if error.abs() <= ARRIVED {
+            return command;
+        }
+        let mut extension = extension;
+        let mut dt = dt;
+        // Past this distance from the command the lag asks for more than
+        // the rate limit gives.
+        let band = self.slew_per_s * self.time_constant_s;
+        if error.abs() > band {
+            let slew_time = (error.abs() - band) / self.slew_per_s;
+            if slew_time >= dt {
+                return extension + error.signum() * self.slew_per_s * dt;
+            }
+            extension = command - error.signum() * band;
+            dt -= slew_time;
+        }
+        if self.time_constant_s <= 0.0 {
+            return command;
+        }
+        let extension = command + (extension - command) * libm::expf(-dt / self.time_constant_s);
+        if (command - extension).abs() <= ARRIVED {
+            command
+        } else {
+            extension
+        }
+    }
+}
+
+impl Default for ServoModel {
+    fn default() -> Self {
+        Self::ICARUS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// A full stroke takes Icarus's measured 0.40 s: dead time, then slew,
+    /// then arrived.
+    #[test]
+    fn a_full_stroke_is_dead_time_and_slew() {
+        let servo = ServoModel::ICARUS;
+        // Nothing moves inside the dead time.
+        assert_eq!(servo.step(0.0, 0.0, 1.0, 0.02), 0.0);
+
+        let mut extension = servo.step(0.0, 0.0, 1.0, 0.1);
+        assert_relative_eq!(extension, (0.1 - 0.0258) * 2.67, epsilon = 1e-5);
+        for _ in 0..2 {
+            extension = servo.step(extension, 1.0, 1.0, 0.1);
+        }
+        assert_relative_eq!(extension, (0.3 - 0.0258) * 2.67, epsilon = 1e-5);
+        for _ in 0..2 {
+            extension = servo.step(extension, 1.0, 1.0, 0.1);
+        }
+        assert_eq!(extension, 1.0);
+    }
+
+    /// One step of the simulation agrees with the same interval cut fine,
+    /// in both regimes — so the MPC's 0.1 s steps lose nothing.
+    #[test]
+    fn one_coarse_step_is_many_fine_ones() {
+        let servo = ServoModel::ICARUS;
+        for (from, to) in [(0.2, 0.9), (0.5, 0.52), (0.9, 0.0)] {
+            let coarse = servo.step(from, from, to, 0.1);
+            let dt = 1e-4;
+            let mut fine = from;
+            for i in 0..1000 {
+                let command = if (i as f32) * dt < servo.dead_time_s {
+                    from
+                } else {
+                    to
+                };
+                fine = servo.follow(fine, command, dt);
+            }
+            assert_relative_eq!(coarse, fine, epsilon = 2e-3);
+        }
+        // A small step is all lag: well within the step, and not past it.
+        let small = servo.step(0.5, 0.5, 0.52, 0.1);
+        assert!(small <= 0.52 && small > 0.519);
+    }
+}
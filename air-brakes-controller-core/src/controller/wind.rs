@@ -0,0 +1,190 @@
+//! [`WindProfile`] — the wind the MPC's apogee simulation flies through.
+//!
+//! The coast is flown on AIRSPEED, not ground speed: drag opposes the
+//! velocity relative to the air. The MPC is already handed an airspeed —
+//! the airframe weathercocks, so its axis, and with it the estimator's
+//! horizontal velocity, follows the relative wind — and in a wind that is
+//! the same at every altitude that is all it needs: a constant wind moves
+//! the ground track and leaves the coast above it untouched. What the calm
+//! model gets wrong is SHEAR. A headwind that strengthens on the way up is
+//! airspeed gained during the coast. It is a small correction: OpenRocket's
+//! Osiris flight picks up ~6 m/s of shear between burnout and apogee, and
+//! it moves the stowed apogee prediction by under a metre (see the
+//! `wind_shear_moves_the_stowed_prediction` Osiris test). The bigger the
+//! shear and the lower the airspeed, the more it is worth.
+//!
+//! The profile is uploaded before launch and never estimated in flight:
+//! nothing on the airframe measures the wind. Calm is the default, and
+//! flies exactly the model the MPC always flew.
+
+use nalgebra::Vector2;
+
+/// How many altitude bins a [`WindProfile`] holds. Fixed so the profile
+/// stays a plain array on the board; a sounding or a forecast for the few
+/// kilometres a coast spans does not resolve finer than this.
+pub const WIND_BINS: usize = 8;
+
+/// The wind as the velocity the air moves with, `(east, north)` m/s, against
+/// altitude ASL: constant, or linear between altitude bins and held beyond
+/// the first and last. Air moving toward the north-east is `(+, +)` — the
+/// direction it blows TO, not the meteorological "from".
+///
+/// The same struct also carries the profile turned into the apogee
+/// simulation's own frame, `(downrange, crossrange)` along the airframe's
+/// heading — see [`Self::in_flight_frame`]; nothing outside the controller
+/// sees that one.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindProfile {
+    /// Bin altitudes ASL (m), strictly increasing; the first `len` are used.
+    altitude_asl: [f32; WIND_BINS],
+    /// The wind at each bin.
+    wind: [[f32; 2]; WIND_BINS],
+    /// At least 1.
+    len: usize,
+}
+
+impl WindProfile {
+    /// No wind: the model the MPC flew before it had one.
+    pub const fn calm() -> Self {
+        Self::constant(0.0, 0.0)
+    }
+
+    /// The same wind at every altitude, `(east, north)` m/s.
+    pub const fn constant(east: f32, north: f32) -> Self {
+        Self {
+            altitude_asl: [0.0; WIND_BINS],
+            wind: [[east, north]; WIND_BINS],
+            len: 1,
+        }
+    }
+
+    /// A profile from `(altitude_asl, east, north)` bins, altitudes strictly
+    /// increasing. `None` for an upload this cannot fly: no bins, more than
+    /// [`WIND_BINS`], altitudes out of order, or anything non-finite.
+    pub fn binned(bins: &[(f32, f32, f32)]) -> Option<Self> {
+        if bins.is_empty() || bins.len() > WIND_BINS {
+            return None;
+        }
+        let mut profile = Self::calm();
+        for (i, &(altitude_asl, east, north)) in bins.iter().enumerate() {
+            let finite = altitude_asl.is_finite() && east.is_finite() && north.is_finite();
+            if !finite || (i > 0 && altitude_asl <= profile.altitude_asl[i - 1]) {
+                return None;
+            }
+            profile.altitude_asl[i] = altitude_asl;
+            profile.wind[i] = [east, north];
+        }
+        profile.len = bins.len();
+        Some(profile)
+    }
+
+    /// True when there is no wind at any altitude.
+    pub fn is_calm(&self) -> bool {
+        self.wind[..self.len].iter().all(|w| *w == [0.0, 0.0])
+    }
+
+    /// The wind at `altitude_asl`, `(east, north)` m/s — or, on a profile
+    /// from [`Self::in_flight_frame`], `(downrange, crossrange)`.
+    ///
+    /// A walk over at most [`WIND_BINS`] breakpoints and one divide, like
+    /// the cd table's Mach lookup: it runs twice per RK2 step.
+    pub fn at(&self, altitude_asl: f32) -> Vector2<f32> {
+        let h = &self.altitude_asl;
+        let n = self.len;
+        if n == 1 || altitude_asl <= h[0] {
+            return Vector2::from(self.wind[0]);
+        }
+        if altitude_asl >= h[n - 1] {
+            return Vector2::from(self.wind[n - 1]);
+        }
+        let mut i = 0;
+        while altitude_asl > h[i + 1] {
+            i += 1;
+        }
+        let t = (altitude_asl - h[i]) / (h[i + 1] - h[i]);
+        let (a, b) = (self.wind[i], self.wind[i + 1]);
+        Vector2::new(a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1]))
+    }
+
+    /// This profile turned into the apogee simulation's frame: downrange
+    /// along `heading` (rad, clockwise from north — the airframe axis's
+    /// compass heading), and crossrange 90 deg counterclockwise of it.
+    ///
+    /// With no heading there is no compass to turn by, and the airframe is
+    /// assumed to be heading straight upwind at `altitude_asl`. That is what
+    /// weathercocking does to it: the axis turns into the relative wind off
+    /// the rail, and the gravity turn then carries it on in that direction
+    /// (OpenRocket's Osiris flight, with up to 10 m/s of wind over the coast,
+    /// heads within 31 deg of straight into it all the way up). Where it is
+    /// calm at `altitude_asl` the direction is moot at the start, and north
+    /// is used.
+    pub(crate) fn in_flight_frame(&self, heading: Option<f32>, altitude_asl: f32) -> Self {
+        let downrange = match heading {
+            Some(heading) => Vector2::new(libm::sinf(heading), libm::cosf(heading)),
+            None => {
+                let here = self.at(altitude_asl);
+                let speed = crate::utils::sqrt(here.magnitude_squared());
+                if speed > 1e-3 {
+                    -here / speed
+                } else {
+                    Vector2::new(0.0, 1.0)
+                }
+            }
+        };
+        let crossrange = Vector2::new(-downrange.y, downrange.x);
+        Self {
+            wind: self.wind.map(|w| {
+                let w = Vector2::from(w);
+                [w.dot(&downrange), w.dot(&crossrange)]
+            }),
+            ..*self
+        }
+    }
+}
+
+impl Default for WindProfile {
+    fn default() -> Self {
+        Self::calm()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn bins_interpolate_and_hold_at_the_ends() {
+        let profile =
+            WindProfile::binned(&[(1000.0, 2.0, 0.0), (3000.0, 6.0, -4.0), (5000.0, 6.0, 0.0)])
+                .unwrap();
+        assert_eq!(profile.at(0.0), Vector2::new(2.0, 0.0));
+        assert_relative_eq!(profile.at(2000.0), Vector2::new(4.0, -2.0));
+        assert_relative_eq!(profile.at(4500.0), Vector2::new(6.0, -1.0));
+        assert_eq!(profile.at(9000.0), Vector2::new(6.0, 0.0));
+
+        assert!(WindProfile::binned(&[]).is_none());
+        assert!(WindProfile::binned(&[(1000.0, 1.0, 1.0), (1000.0, 2.0, 2.0)]).is_none());
+        assert!(WindProfile::binned(&[(f32::NAN, 1.0, 1.0)]).is_none());
+        assert!(WindProfile::binned(&[(0.0, 0.0, 0.0); WIND_BINS + 1]).is_none());
+    }
+
+    #[test]
+    fn the_flight_frame_is_along_the_heading_or_upwind() {
+        // 5 m/s blowing toward the east.
+        let east = WindProfile::constant(5.0, 0.0);
+        // Heading east: all tailwind.
+        let tail = east.in_flight_frame(Some(core::f32::consts::FRAC_PI_2), 0.0);
+        assert_relative_eq!(tail.at(0.0), Vector2::new(5.0, 0.0), epsilon = 1e-5);
+        // Heading north: all crosswind, toward the right of the heading
+        // (crossrange is to its left).
+        let cross = east.in_flight_frame(Some(0.0), 0.0);
+        assert_relative_eq!(cross.at(0.0), Vector2::new(0.0, -5.0), epsilon = 1e-5);
+        // No heading: into the wind.
+        let upwind = east.in_flight_frame(None, 0.0);
+        assert_relative_eq!(upwind.at(0.0), Vector2::new(-5.0, 0.0), epsilon = 1e-5);
+
+        assert!(WindProfile::calm().in_flight_frame(None, 0.0).is_calm());
+    }
+}
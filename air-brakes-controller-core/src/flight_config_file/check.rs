@@ -0,0 +1,517 @@
+//! Physical sanity of a [`FlightConfig`]: the numbers a flight config file
+//! can hold that no rocket can fly.
+//!
+//! Every check is one a past config got wrong or a field's own docs ask for
+//! by hand — the main above the drogue, a lockout still running at apogee, a
+//! backup timer that fires before the rocket could have got there. Each
+//! finding is a [`ConfigIssue`], an [`Severity::Error`] when the flight
+//! would go wrong and a [`Severity::Warning`] when it might.
+//!
+//! **The earliest apogee.** Three checks need to know when apogee could
+//! come, and the config carries everything to bound it: the airbrakes
+//! lockout says the rocket is subsonic no earlier than
+//! `earliest_subsonic_after_ignition_us`, at
+//! [`AirbrakesConfig::max_open_mach`] and around
+//! `subsonic_crossing_altitude_asl`. From there the coast is flown through
+//! the controller's own dynamics (`controller::rocket_dynamics`), straight
+//! up and with the flaps fully out — the most drag the table allows, so the
+//! shortest coast the airframe can fly from that state. The sum is the
+//! earliest apogee the config itself implies. Not a forecast, and only as
+//! good as the lockout floor it starts from: on Osiris it comes out at about
+//! 37.7 s, level with the N2900's simulated apogee and two seconds ahead of
+//! the O3400's. The coast is flown through the atmosphere [`check`] is
+//! handed, which is the forecast and not the flight's: that one is only
+//! calibrated at launch (see [`FlightEstimators::atmosphere`]).
+//!
+//! [`FlightEstimators::atmosphere`]: crate::FlightEstimators::atmosphere
+//!
+//! [`AirbrakesConfig::max_open_mach`]: crate::airbrakes_estimator::AirbrakesConfig::max_open_mach
+
+use core::fmt;
+
+use firmware_common_new::vlp::packets::fire_pyro::{PYRO_CHANNELS, PyroSelect};
+use nalgebra::Vector3;
+
+use crate::atmosphere::Atmosphere;
+use crate::backup_deployment::{BackupDeploymentConfig, DeploymentPolicy};
+use crate::baro_state_estimator::DeploymentProfile;
+use crate::controller::rocket_dynamics::coast_to_apogee_rk2;
+use crate::controller::{CD_MACH_POINTS, RocketParameters, State};
+use crate::flight_config_file::us_to_s;
+use crate::flight_estimators::FlightConfig;
+use crate::pyro_events::PyroTrigger;
+
+/// How long before the earliest apogee every lockout must have ended (s):
+/// the ">5 s before apogee" both lockouts' docs ask for.
+pub const APOGEE_MARGIN_S: f32 = 5.0;
+
+/// More than [`check`] can report on any config: every field check fires at
+/// most once, and the pyro table at most twice per channel.
+const MAX_CONFIG_ISSUES: usize = 48;
+
+const G: f32 = 9.81;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The flight would go wrong.
+    Error,
+    /// The flight might; worth a look.
+    Warning,
+}
+
+/// Which lockout a [`ConfigIssue::LockoutIntoApogee`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lockout {
+    /// [`FlightProfile::mach_lockout_duration_us`](crate::FlightProfile::mach_lockout_duration_us).
+    Deployment,
+    /// [`MachLockoutConfig::force_birth_after_ignition_us`](crate::airbrakes_estimator::MachLockoutConfig::force_birth_after_ignition_us).
+    AirbrakesForceBirth,
+}
+
+/// One finding. Times are seconds from ignition detection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigIssue {
+    /// A quantity that only means something above zero, or is not finite.
+    NotPositive { field: &'static str, value: f32 },
+    /// The ignition threshold is at or under the 1 g the pad itself reads.
+    IgnitionBelowGravity { threshold: f32 },
+    /// The main is at or above the drogue's minimum altitude.
+    MainNotBelowDrogue {
+        main_agl: f32,
+        drogue_minimum_agl: f32,
+    },
+    /// `cd_mach` does not strictly increase.
+    CdMachNotIncreasing,
+    /// A row of the cd table does not strictly increase with extension,
+    /// which the drag-to-extension map needs to invert. 0-based row.
+    CdNotIncreasingWithExtension { row: usize },
+    /// `max_open_mach` outside 0 - 1: the table and the lockout are both
+    /// subsonic by construction.
+    MaxOpenMach { mach: f32 },
+    /// The airbrakes drag check may speak no earlier than the forced birth.
+    AirbrakesLockoutOrder {
+        earliest_subsonic_s: f32,
+        force_birth_s: f32,
+    },
+    /// The deployment lockout ends before the rocket could be below Mach
+    /// `max_open_mach`, let alone the 0.75 it is timed for.
+    DeploymentLockoutEndsSupersonic {
+        lockout_s: f32,
+        earliest_subsonic_s: f32,
+    },
+    /// A lockout ends less than [`APOGEE_MARGIN_S`] before the earliest
+    /// apogee.
+    LockoutIntoApogee {
+        lockout: Lockout,
+        ends_s: f32,
+        earliest_apogee_s: f32,
+    },
+    /// No earliest apogee to check against: no airbrakes lockout to start
+    /// the coast from, or a coast that does not integrate.
+    ApogeeNotEstimated,
+    /// The backup channel's accelerometer floor is at or after its own
+    /// liftoff timer, so the accelerometer can never call apogee.
+    BackupFloorAfterTimer { floor_s: f32, timer_s: f32 },
+    /// The backup's liftoff timer fires before the earliest apogee — a
+    /// drogue at speed on every flight.
+    BackupTimerBeforeApogee {
+        timer_s: f32,
+        earliest_apogee_s: f32,
+    },
+    /// The backup's accelerometer floor is after the earliest apogee, so an
+    /// early honest apogee falls to the timer.
+    BackupFloorAfterApogee {
+        floor_s: f32,
+        earliest_apogee_s: f32,
+    },
+    /// A channel listed twice in the pyro table; the second row can never
+    /// fire.
+    PyroChannelTwice { channel: PyroSelect },
+    /// A pyro-table row that backs up its own channel, and so never fires.
+    PyroBackupOfItself { channel: PyroSelect },
+    /// A staging tilt limit outside 0 - 90 degrees.
+    StagingTilt { max_tilt_rad: f32 },
+}
+
+impl ConfigIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::ApogeeNotEstimated | Self::BackupFloorAfterApogee { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotPositive { field, value } => {
+                write!(f, "{field} must be positive, got {value}")
+            }
+            Self::IgnitionBelowGravity { threshold } => write!(
+                f,
+                "ignition_detection_acc_threshold {threshold} m/s^2 is at or under 1 g; \
+                 ignition would latch on the pad"
+            ),
+            Self::MainNotBelowDrogue {
+                main_agl,
+                drogue_minimum_agl,
+            } => write!(
+                f,
+                "main altitude {main_agl} m AGL is not below the drogue minimum \
+                 {drogue_minimum_agl} m AGL"
+            ),
+            Self::CdMachNotIncreasing => write!(f, "airbrakes.rocket.cd_mach must increase"),
+            Self::CdNotIncreasingWithExtension { row } => write!(
+                f,
+                "airbrakes.rocket.cd row {row} must increase from stowed to full extension"
+            ),
+            Self::MaxOpenMach { mach } => {
+                write!(
+                    f,
+                    "airbrakes.max_open_mach must be between 0 and 1, got {mach}"
+                )
+            }
+            Self::AirbrakesLockoutOrder {
+                earliest_subsonic_s,
+                force_birth_s,
+            } => write!(
+                f,
+                "airbrakes lockout: earliest subsonic {earliest_subsonic_s} s is not before \
+                 the forced birth {force_birth_s} s"
+            ),
+            Self::DeploymentLockoutEndsSupersonic {
+                lockout_s,
+                earliest_subsonic_s,
+            } => write!(
+                f,
+                "deployment lockout ends at {lockout_s} s, before the earliest subsonic time \
+                 {earliest_subsonic_s} s"
+            ),
+            Self::LockoutIntoApogee {
+                lockout,
+                ends_s,
+                earliest_apogee_s,
+            } => write!(
+                f,
+                "{} ends at {ends_s} s, less than {APOGEE_MARGIN_S} s before the earliest \
+                 apogee at {earliest_apogee_s:.1} s",
+                match lockout {
+                    Lockout::Deployment => "deployment lockout",
+                    Lockout::AirbrakesForceBirth => "airbrakes forced birth",
+                }
+            ),
+            Self::ApogeeNotEstimated => write!(
+                f,
+                "no earliest apogee (needs an airbrakes lockout to start the coast from); \
+                 the lockout and backup timers were not checked against it"
+            ),
+            Self::BackupFloorAfterTimer { floor_s, timer_s } => write!(
+                f,
+                "backup accelerometer floor {floor_s} s is not before its timer {timer_s} s"
+            ),
+            Self::BackupTimerBeforeApogee {
+                timer_s,
+                earliest_apogee_s,
+            } => write!(
+                f,
+                "backup timer {timer_s} s fires before the earliest apogee at \
+                 {earliest_apogee_s:.1} s"
+            ),
+            Self::BackupFloorAfterApogee {
+                floor_s,
+                earliest_apogee_s,
+            } => write!(
+                f,
+                "backup accelerometer floor {floor_s} s is after the earliest apogee at \
+                 {earliest_apogee_s:.1} s"
+            ),
+            Self::PyroChannelTwice { channel } => {
+                write!(f, "pyro table lists {channel:?} twice")
+            }
+            Self::PyroBackupOfItself { channel } => {
+                write!(f, "pyro table: {channel:?} backs up itself")
+            }
+            Self::StagingTilt { max_tilt_rad } => write!(
+                f,
+                "staging.max_tilt_rad must be between 0 and pi/2, got {max_tilt_rad}"
+            ),
+        }
+    }
+}
+
+/// What [`check`] found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigCheck {
+    pub issues: heapless::Vec<ConfigIssue, MAX_CONFIG_ISSUES>,
+    /// Seconds from ignition detection; see the module docs.
+    pub earliest_apogee_s: Option<f32>,
+}
+
+impl ConfigCheck {
+    /// No [`Severity::Error`]s. Warnings do not count.
+    pub fn passed(&self) -> bool {
+        self.issues
+            .iter()
+            .all(|issue| issue.severity() != Severity::Error)
+    }
+
+    fn report(&mut self, issue: ConfigIssue) {
+        // Sized so this cannot fail; see MAX_CONFIG_ISSUES.
+        let _ = self.issues.push(issue);
+    }
+
+    fn positive(&mut self, field: &'static str, value: f32) -> bool {
+        // Written so a NaN fails.
+        let ok = value > 0.0 && value.is_finite();
+        if !ok {
+            self.report(ConfigIssue::NotPositive { field, value });
+        }
+        ok
+    }
+}
+
+/// Check `config` on the day `atmosphere` describes; see the module docs.
+pub fn check(config: &FlightConfig, atmosphere: &Atmosphere) -> ConfigCheck {
+    let mut check = ConfigCheck {
+        issues: heapless::Vec::new(),
+        earliest_apogee_s: None,
+    };
+
+    let threshold = config.ignition_detection_acc_threshold;
+    if check.positive("ignition_detection_acc_threshold", threshold) && threshold <= G {
+        check.report(ConfigIssue::IgnitionBelowGravity { threshold });
+    }
+
+    check_deployment(&mut check, &config.profile.deployment);
+    let landing = &config.profile.landing;
+    check.positive("profile.landing.max_altitude_agl", landing.max_altitude_agl);
+    check.positive(
+        "profile.landing.max_vertical_velocity",
+        landing.max_vertical_velocity,
+    );
+    check.positive("profile.landing.max_acc_std", landing.max_acc_std);
+    if let Some(gps) = &config.profile.gps_aiding {
+        check.positive("profile.gps_aiding.max_hdop", gps.max_hdop);
+        check.positive("profile.gps_aiding.max_vdop", gps.max_vdop);
+        check.positive(
+            "profile.gps_aiding.vertical_sigma_per_vdop_m",
+            gps.vertical_sigma_per_vdop_m,
+        );
+    }
+    check_pyro_events(&mut check, config);
+
+    let rocket_ok = check_rocket(&mut check, &config.airbrakes.rocket);
+    let max_open_mach = config.airbrakes.max_open_mach;
+    let mach_ok = max_open_mach > 0.0 && max_open_mach < 1.0;
+    if !mach_ok {
+        check.report(ConfigIssue::MaxOpenMach {
+            mach: max_open_mach,
+        });
+    }
+
+    if let Some(lockout) = &config.airbrakes.mach_lockout {
+        let earliest_subsonic_s = us_to_s(lockout.earliest_subsonic_after_ignition_us) as f32;
+        let force_birth_s = us_to_s(lockout.force_birth_after_ignition_us) as f32;
+        if earliest_subsonic_s >= force_birth_s {
+            check.report(ConfigIssue::AirbrakesLockoutOrder {
+                earliest_subsonic_s,
+                force_birth_s,
+            });
+        }
+        if let Some(lockout_us) = config.profile.mach_lockout_duration_us
+            && (us_to_s(lockout_us) as f32) < earliest_subsonic_s
+        {
+            check.report(ConfigIssue::DeploymentLockoutEndsSupersonic {
+                lockout_s: us_to_s(lockout_us) as f32,
+                earliest_subsonic_s,
+            });
+        }
+        if rocket_ok && mach_ok {
+            let altitude_asl = lockout.subsonic_crossing_altitude_asl;
+            let speed = max_open_mach * atmosphere.speed_of_sound(altitude_asl);
+            let crossing = State {
+                altitude_asl,
+                velocity: Vector3::new(0.0, speed, 0.0),
+            };
+            check.earliest_apogee_s =
+                coast_to_apogee_rk2(1.0, &crossing, &config.airbrakes.rocket, atmosphere)
+                    .map(|(_, coast_s)| earliest_subsonic_s + coast_s);
+        }
+    }
+
+    check_against_apogee(&mut check, config);
+
+    if let Some(staging) = &config.staging {
+        check.positive(
+            "staging.sustainer_ignition_acc_threshold",
+            staging.sustainer_ignition_acc_threshold,
+        );
+        let tilt = staging.max_tilt_rad;
+        if !(tilt > 0.0 && tilt <= core::f32::consts::FRAC_PI_2) {
+            check.report(ConfigIssue::StagingTilt { max_tilt_rad: tilt });
+        }
+    }
+
+    check
+}
+
+fn check_deployment(check: &mut ConfigCheck, deployment: &DeploymentProfile) {
+    match *deployment {
+        DeploymentProfile::Single {
+            minimum_deployment_altitude_agl,
+            ..
+        } => {
+            check.positive(
+                "profile.deployment.minimum_deployment_altitude_agl",
+                minimum_deployment_altitude_agl,
+            );
+        }
+        DeploymentProfile::Dual {
+            drogue_chute_minimum_altitude_agl,
+            main_chute_altitude_agl,
+            ..
+        } => {
+            let drogue_ok = check.positive(
+                "profile.deployment.drogue_chute_minimum_altitude_agl",
+                drogue_chute_minimum_altitude_agl,
+            );
+            let main_ok = check.positive(
+                "profile.deployment.main_chute_altitude_agl",
+                main_chute_altitude_agl,
+            );
+            if drogue_ok && main_ok && main_chute_altitude_agl >= drogue_chute_minimum_altitude_agl
+            {
+                check.report(ConfigIssue::MainNotBelowDrogue {
+                    main_agl: main_chute_altitude_agl,
+                    drogue_minimum_agl: drogue_chute_minimum_altitude_agl,
+                });
+            }
+        }
+    }
+}
+
+fn check_pyro_events(check: &mut ConfigCheck, config: &FlightConfig) {
+    let mut listed = [false; PYRO_CHANNELS];
+    for event in &config.profile.pyro_events {
+        let seen = &mut listed[event.channel.index()];
+        if *seen {
+            check.report(ConfigIssue::PyroChannelTwice {
+                channel: event.channel,
+            });
+        }
+        *seen = true;
+        match event.trigger {
+            PyroTrigger::BackupOf { channel, .. } if channel == event.channel => {
+                check.report(ConfigIssue::PyroBackupOfItself { channel });
+            }
+            PyroTrigger::DescentAltitude { altitude_agl, .. } => {
+                check.positive("profile.pyro_events.trigger.altitude_agl", altitude_agl);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the airframe is good enough to fly the earliest-apogee coast on.
+fn check_rocket(check: &mut ConfigCheck, rocket: &RocketParameters) -> bool {
+    let mass_ok = check.positive("airbrakes.rocket.burnout_mass", rocket.burnout_mass);
+    let area_ok = check.positive("airbrakes.rocket.reference_area", rocket.reference_area);
+    let mut ok = mass_ok && area_ok;
+
+    let mach = &rocket.cd_mach;
+    if !(0..CD_MACH_POINTS - 1).all(|i| mach[i] < mach[i + 1])
+        || !mach.iter().all(|m| m.is_finite())
+    {
+        check.report(ConfigIssue::CdMachNotIncreasing);
+        ok = false;
+    }
+    for (row, cd) in rocket.cd.iter().enumerate() {
+        if !check.positive("airbrakes.rocket.cd", cd[0]) {
+            ok = false;
+        } else if !cd
+            .windows(2)
+            .all(|pair| pair[0] < pair[1] && pair[1].is_finite())
+        {
+            check.report(ConfigIssue::CdNotIncreasingWithExtension { row });
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// The lockouts and the backup channel against [`ConfigCheck::earliest_apogee_s`].
+fn check_against_apogee(check: &mut ConfigCheck, config: &FlightConfig) {
+    let backup = match &config.deployment_policy {
+        DeploymentPolicy::PrimaryOnly => None,
+        DeploymentPolicy::FirstOf(backup) | DeploymentPolicy::BothAgreeWithin { backup, .. } => {
+            Some(backup)
+        }
+    };
+    if let Some(BackupDeploymentConfig {
+        apogee_timer_us,
+        earliest_accel_apogee_us,
+        arming_altitude_agl,
+    }) = backup
+    {
+        check.positive(
+            "deployment_policy.backup.arming_altitude_agl",
+            *arming_altitude_agl,
+        );
+        if earliest_accel_apogee_us >= apogee_timer_us {
+            check.report(ConfigIssue::BackupFloorAfterTimer {
+                floor_s: us_to_s(*earliest_accel_apogee_us) as f32,
+                timer_s: us_to_s(*apogee_timer_us) as f32,
+            });
+        }
+    }
+
+    let lockouts = [
+        (Lockout::Deployment, config.profile.mach_lockout_duration_us),
+        (
+            Lockout::AirbrakesForceBirth,
+            config
+                .airbrakes
+                .mach_lockout
+                .as_ref()
+                .map(|lockout| lockout.force_birth_after_ignition_us),
+        ),
+    ];
+    let needs_apogee = backup.is_some() || lockouts.iter().any(|(_, us)| us.is_some());
+    let Some(earliest_apogee_s) = check.earliest_apogee_s else {
+        // A broken airframe has already been reported; this is only for a
+        // config that is otherwise fine and simply cannot be checked.
+        if needs_apogee && check.passed() {
+            check.report(ConfigIssue::ApogeeNotEstimated);
+        }
+        return;
+    };
+
+    for (lockout, ends_us) in lockouts {
+        if let Some(ends_us) = ends_us
+            && us_to_s(ends_us) as f32 + APOGEE_MARGIN_S > earliest_apogee_s
+        {
+            check.report(ConfigIssue::LockoutIntoApogee {
+                lockout,
+                ends_s: us_to_s(ends_us) as f32,
+                earliest_apogee_s,
+            });
+        }
+    }
+    if let Some(backup) = backup {
+        let timer_s = us_to_s(backup.apogee_timer_us) as f32;
+        if timer_s < earliest_apogee_s {
+            check.report(ConfigIssue::BackupTimerBeforeApogee {
+                timer_s,
+                earliest_apogee_s,
+            });
+        }
+        let floor_s = us_to_s(backup.earliest_accel_apogee_us) as f32;
+        if floor_s > earliest_apogee_s {
+            check.report(ConfigIssue::BackupFloorAfterApogee {
+                floor_s,
+                earliest_apogee_s,
+            });
+        }
+    }
+}
@@ -0,0 +1,708 @@
+//! The flight config as a file: every field of [`FlightConfig`] in a
+//! versioned, serde-backed form that `rocket-cli flight-config`, the replay
+//! tools and the WASM harness all read.
+//!
+//! Until this module a flight config only existed as Rust:
+//! `sim::osiris::osiris_config` for the host, VLF5's `FLIGHT_CONFIG` for the
+//! board, and forty-odd positional floats for the harness. A sweep that
+//! wanted to fly a different airframe had to edit one of the three, and
+//! nothing checked that the three still described the same rocket.
+//!
+//! [`FlightConfigFile`] is the file, and the only place it meets the
+//! runtime types — the same split `rocket-cli`'s avionics `ConfigFile` makes
+//! for the card. Durations are seconds here and microseconds there, and
+//! every choice ([`DeploymentProfile`], [`Atmosphere`], [`ControllerMode`],
+//! [`DeploymentPolicy`], a pyro trigger) is a table tagged by `kind`.
+//! Nothing here parses text: the types are plain serde, so each caller
+//! brings its own TOML parser and this crate stays `no_std`.
+//!
+//! **Versioned.** A file names the [`FLIGHT_CONFIG_FILE_VERSION`] it was
+//! written at, and a file at any other version is refused rather than
+//! guessed at: unknown keys are refused too, so a field renamed between
+//! versions cannot quietly fall back to its default.
+//!
+//! **Converting is not checking.** [`FlightConfigFile::to_flight_config`]
+//! refuses only what cannot be represented at all — a negative duration, a
+//! schedule out of order. Whether the numbers describe a rocket that can
+//! fly is [`check`]'s question, and it asks it of the [`FlightConfig`] and
+//! the file's atmosphere, so a config built in code is checked by the same
+//! rules.
+
+mod check;
+
+#[cfg(test)]
+mod tests;
+
+pub use check::{APOGEE_MARGIN_S, ConfigCheck, ConfigIssue, Lockout, Severity, check};
+
+use core::fmt;
+
+use firmware_common_new::vlp::packets::fire_pyro::{PYRO_CHANNELS, PyroSelect};
+use serde::{Deserialize, Serialize};
+
+use crate::airbrakes_estimator::{AirbrakesConfig, MachLockoutConfig};
+use crate::atmosphere::Atmosphere;
+use crate::backup_deployment::{BackupDeploymentConfig, DeploymentPolicy};
+use crate::baro_state_estimator::{DeploymentProfile, FlightProfile, GpsAiding, LandingDetection};
+use crate::controller::{
+    CD_MACH_POINTS, ControllerMode, ExtensionSchedule, RocketParameters, SCHEDULE_POINTS,
+};
+use crate::flight_estimators::FlightConfig;
+use crate::pyro_events::{PyroEvent, PyroEvents, PyroTrigger};
+use crate::staging::StagingConfig;
+
+/// The file format this build reads and writes. Bump it whenever a key is
+/// added, removed or changes meaning.
+pub const FLIGHT_CONFIG_FILE_VERSION: u32 = 1;
+
+/// [`FlightConfig`] as a person edits it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlightConfigFile {
+    /// [`FLIGHT_CONFIG_FILE_VERSION`] when written.
+    pub version: u32,
+    pub ignition_detection_acc_threshold: f32,
+    /// Left out, the deployment half alone.
+    #[serde(default)]
+    pub deployment_policy: DeploymentPolicyFile,
+    /// Left out, the target apogee.
+    #[serde(default)]
+    pub controller_mode: ControllerModeFile,
+    /// The day the ground tools work on — [`check`]'s earliest apogee and
+    /// `rocket-cli fit-airframe`'s drag fit. Not part of the
+    /// [`FlightConfig`]: the flight calibrates its own at launch (see
+    /// [`FlightEstimators::atmosphere`](crate::FlightEstimators::atmosphere)).
+    pub atmosphere: AtmosphereFile,
+    pub profile: FlightProfileFile,
+    pub airbrakes: AirbrakesConfigFile,
+    /// Left out for a single burn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staging: Option<StagingConfigFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlightProfileFile {
+    /// Left out for a subsonic airframe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mach_lockout_s: Option<f64>,
+    pub deployment: DeploymentProfileFile,
+    /// Left out, [`LandingDetection::default`].
+    #[serde(default)]
+    pub landing: LandingDetectionFile,
+    #[serde(default, skip_serializing_if = "is_empty")]
+    pub pyro_events: heapless::Vec<PyroEventFile, PYRO_CHANNELS>,
+    /// Left out, no GPS aiding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gps_aiding: Option<GpsAidingFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DeploymentProfileFile {
+    Single {
+        minimum_deployment_altitude_agl: f32,
+        delay_s: f64,
+    },
+    Dual {
+        drogue_chute_minimum_altitude_agl: f32,
+        drogue_chute_delay_s: f64,
+        main_chute_altitude_agl: f32,
+        main_chute_delay_s: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LandingDetectionFile {
+    pub window_s: f64,
+    pub max_altitude_agl: f32,
+    pub max_vertical_velocity: f32,
+    pub max_acc_std: f32,
+}
+
+impl Default for LandingDetectionFile {
+    fn default() -> Self {
+        Self::from(&LandingDetection::default())
+    }
+}
+
+/// A pyro channel by its function, as the table names it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PyroChannelFile {
+    Main,
+    Drogue,
+    Separation,
+    BackupDrogue,
+    BackupMain,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PyroEventFile {
+    pub channel: PyroChannelFile,
+    pub trigger: PyroTriggerFile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PyroTriggerFile {
+    Apogee {
+        delay_s: f64,
+    },
+    DescentAltitude {
+        altitude_agl: f32,
+        delay_s: f64,
+    },
+    Burnout {
+        delay_s: f64,
+    },
+    BackupOf {
+        channel: PyroChannelFile,
+        delay_s: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GpsAidingFile {
+    pub min_satellites: u8,
+    pub max_hdop: f32,
+    pub max_vdop: f32,
+    pub vertical_sigma_per_vdop_m: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AirbrakesConfigFile {
+    pub max_open_mach: f32,
+    /// Left out for a subsonic airframe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mach_lockout: Option<MachLockoutFile>,
+    pub rocket: RocketParametersFile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachLockoutFile {
+    pub earliest_subsonic_after_ignition_s: f64,
+    pub force_birth_after_ignition_s: f64,
+    pub subsonic_crossing_altitude_asl: f32,
+}
+
+/// [`RocketParameters`]: `cd` is one row per `cd_mach` entry, each the cd
+/// at 0, 25, 50, 75 and 100% extension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RocketParametersFile {
+    pub burnout_mass: f32,
+    pub reference_area: f32,
+    pub cd_mach: [f32; CD_MACH_POINTS],
+    pub cd: [[f32; 5]; CD_MACH_POINTS],
+}
+
+/// [`Atmosphere`] by what it was built from, since that is all a person
+/// knows about one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AtmosphereFile {
+    Standard,
+    /// [`Atmosphere::from_pad`].
+    Pad {
+        pressure_pa: f32,
+        temperature_k: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        site_elevation_asl: Option<f32>,
+    },
+}
+
+impl AtmosphereFile {
+    pub fn to_atmosphere(&self) -> Atmosphere {
+        match *self {
+            Self::Standard => Atmosphere::standard(),
+            Self::Pad {
+                pressure_pa,
+                temperature_k,
+                site_elevation_asl,
+            } => Atmosphere::from_pad(pressure_pa, temperature_k, site_elevation_asl),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ControllerModeFile {
+    #[default]
+    TargetApogee,
+    MaximiseDrag,
+    /// `[time_s, extension]` breakpoints, as [`ExtensionSchedule::from_points`].
+    Scheduled {
+        points: heapless::Vec<[f32; 2], SCHEDULE_POINTS>,
+    },
+    Fixed {
+        extension: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DeploymentPolicyFile {
+    #[default]
+    PrimaryOnly,
+    FirstOf {
+        backup: BackupDeploymentFile,
+    },
+    BothAgreeWithin {
+        window_s: f64,
+        backup: BackupDeploymentFile,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupDeploymentFile {
+    pub apogee_timer_s: f64,
+    pub earliest_accel_apogee_s: f64,
+    pub arming_altitude_agl: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StagingConfigFile {
+    pub sustainer_ignition_delay_s: f64,
+    pub sustainer_ignition_acc_threshold: f32,
+    pub max_tilt_rad: f32,
+    pub min_vertical_velocity: f32,
+}
+
+/// Why a file does not convert. Each names the key, as a dotted path from
+/// the top of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlightConfigFileError {
+    /// Written at a version this build does not read.
+    Version { found: u32 },
+    /// Negative, non-finite, or past `u32::MAX` microseconds.
+    Duration { key: &'static str, seconds: f64 },
+    /// A schedule [`ExtensionSchedule::from_points`] refuses.
+    Schedule,
+    /// A fixed extension outside 0.0 - 1.0.
+    FixedExtension(f32),
+}
+
+impl fmt::Display for FlightConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version { found } => write!(
+                f,
+                "written at flight config version {found}; this build reads version \
+                 {FLIGHT_CONFIG_FILE_VERSION}"
+            ),
+            Self::Duration { key, seconds } => write!(
+                f,
+                "{key} must be between 0 and {:.0} s, got {seconds}",
+                u32::MAX as f64 / 1e6
+            ),
+            Self::Schedule => write!(
+                f,
+                "controller_mode.points must be 1 to {SCHEDULE_POINTS} breakpoints, times \
+                 increasing from 0, extensions 0.0 - 1.0"
+            ),
+            Self::FixedExtension(extension) => write!(
+                f,
+                "controller_mode.extension must be 0.0 - 1.0, got {extension}"
+            ),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl std::error::Error for FlightConfigFileError {}
+
+fn is_empty<T, const N: usize>(vec: &heapless::Vec<T, N>) -> bool {
+    vec.is_empty()
+}
+
+/// Seconds as a file writes them to the whole microseconds a config holds,
+/// refusing what a `u32` cannot: `key` names the field in the error. The
+/// avionics config file reads its durations through this too.
+pub fn s_to_us(key: &'static str, seconds: f64) -> Result<u32, FlightConfigFileError> {
+    let us = libm::round(seconds * 1e6);
+    if !us.is_finite() || us < 0.0 || us > u32::MAX as f64 {
+        return Err(FlightConfigFileError::Duration { key, seconds });
+    }
+    Ok(us as u32)
+}
+
+/// Microseconds back to seconds, exactly: every `u32` of them is an `f64`.
+pub fn us_to_s(us: u32) -> f64 {
+    us as f64 / 1e6
+}
+
+impl From<PyroSelect> for PyroChannelFile {
+    fn from(channel: PyroSelect) -> Self {
+        match channel {
+            PyroSelect::PyroMain => Self::Main,
+            PyroSelect::PyroDrogue => Self::Drogue,
+            PyroSelect::PyroSeparation => Self::Separation,
+            PyroSelect::PyroBackupDrogue => Self::BackupDrogue,
+            PyroSelect::PyroBackupMain => Self::BackupMain,
+        }
+    }
+}
+
+impl From<PyroChannelFile> for PyroSelect {
+    fn from(channel: PyroChannelFile) -> Self {
+        match channel {
+            PyroChannelFile::Main => Self::PyroMain,
+            PyroChannelFile::Drogue => Self::PyroDrogue,
+            PyroChannelFile::Separation => Self::PyroSeparation,
+            PyroChannelFile::BackupDrogue => Self::PyroBackupDrogue,
+            PyroChannelFile::BackupMain => Self::PyroBackupMain,
+        }
+    }
+}
+
+impl From<&LandingDetection> for LandingDetectionFile {
+    fn from(landing: &LandingDetection) -> Self {
+        Self {
+            window_s: us_to_s(landing.window_us),
+            max_altitude_agl: landing.max_altitude_agl,
+            max_vertical_velocity: landing.max_vertical_velocity,
+            max_acc_std: landing.max_acc_std,
+        }
+    }
+}
+
+impl From<&PyroTrigger> for PyroTriggerFile {
+    fn from(trigger: &PyroTrigger) -> Self {
+        match *trigger {
+            PyroTrigger::Apogee { delay_us } => Self::Apogee {
+                delay_s: us_to_s(delay_us),
+            },
+            PyroTrigger::DescentAltitude {
+                altitude_agl,
+                delay_us,
+            } => Self::DescentAltitude {
+                altitude_agl,
+                delay_s: us_to_s(delay_us),
+            },
+            PyroTrigger::Burnout { delay_us } => Self::Burnout {
+                delay_s: us_to_s(delay_us),
+            },
+            PyroTrigger::BackupOf { channel, delay_us } => Self::BackupOf {
+                channel: channel.into(),
+                delay_s: us_to_s(delay_us),
+            },
+        }
+    }
+}
+
+impl From<&BackupDeploymentConfig> for BackupDeploymentFile {
+    fn from(backup: &BackupDeploymentConfig) -> Self {
+        Self {
+            apogee_timer_s: us_to_s(backup.apogee_timer_us),
+            earliest_accel_apogee_s: us_to_s(backup.earliest_accel_apogee_us),
+            arming_altitude_agl: backup.arming_altitude_agl,
+        }
+    }
+}
+
+impl BackupDeploymentFile {
+    fn to_config(&self) -> Result<BackupDeploymentConfig, FlightConfigFileError> {
+        Ok(BackupDeploymentConfig {
+            apogee_timer_us: s_to_us(
+                "deployment_policy.backup.apogee_timer_s",
+                self.apogee_timer_s,
+            )?,
+            earliest_accel_apogee_us: s_to_us(
+                "deployment_policy.backup.earliest_accel_apogee_s",
+                self.earliest_accel_apogee_s,
+            )?,
+            arming_altitude_agl: self.arming_altitude_agl,
+        })
+    }
+}
+
+impl From<&RocketParameters> for RocketParametersFile {
+    fn from(rocket: &RocketParameters) -> Self {
+        Self {
+            burnout_mass: rocket.burnout_mass,
+            reference_area: rocket.reference_area,
+            cd_mach: rocket.cd_mach,
+            cd: rocket.cd,
+        }
+    }
+}
+
+impl From<&RocketParametersFile> for RocketParameters {
+    fn from(rocket: &RocketParametersFile) -> Self {
+        Self {
+            burnout_mass: rocket.burnout_mass,
+            cd_mach: rocket.cd_mach,
+            cd: rocket.cd,
+            reference_area: rocket.reference_area,
+        }
+    }
+}
+
+impl FlightConfigFile {
+    /// `config` as a file, at this build's version.
+    ///
+    /// The atmosphere is handed in because a [`FlightConfig`] has none, and
+    /// an [`Atmosphere`] keeps only the coefficients it was calibrated to,
+    /// not the pad it was calibrated on.
+    pub fn new(config: &FlightConfig, atmosphere: AtmosphereFile) -> Self {
+        let profile = &config.profile;
+        Self {
+            version: FLIGHT_CONFIG_FILE_VERSION,
+            ignition_detection_acc_threshold: config.ignition_detection_acc_threshold,
+            deployment_policy: match &config.deployment_policy {
+                DeploymentPolicy::PrimaryOnly => DeploymentPolicyFile::PrimaryOnly,
+                DeploymentPolicy::FirstOf(backup) => DeploymentPolicyFile::FirstOf {
+                    backup: backup.into(),
+                },
+                DeploymentPolicy::BothAgreeWithin { window_us, backup } => {
+                    DeploymentPolicyFile::BothAgreeWithin {
+                        window_s: us_to_s(*window_us),
+                        backup: backup.into(),
+                    }
+                }
+            },
+            controller_mode: match &config.controller_mode {
+                ControllerMode::TargetApogee => ControllerModeFile::TargetApogee,
+                ControllerMode::MaximiseDrag => ControllerModeFile::MaximiseDrag,
+                ControllerMode::Scheduled(schedule) => ControllerModeFile::Scheduled {
+                    points: schedule.points().map(|(t, e)| [t, e]).collect(),
+                },
+                ControllerMode::Fixed { extension } => ControllerModeFile::Fixed {
+                    extension: *extension,
+                },
+            },
+            atmosphere,
+            profile: FlightProfileFile {
+                mach_lockout_s: profile.mach_lockout_duration_us.map(us_to_s),
+                deployment: match profile.deployment {
+                    DeploymentProfile::Single {
+                        minimum_deployment_altitude_agl,
+                        delay_us,
+                    } => DeploymentProfileFile::Single {
+                        minimum_deployment_altitude_agl,
+                        delay_s: us_to_s(delay_us),
+                    },
+                    DeploymentProfile::Dual {
+                        drogue_chute_minimum_altitude_agl,
+                        drogue_chute_delay_us,
+                        main_chute_altitude_agl,
+                        main_chute_delay_us,
+                    } => DeploymentProfileFile::Dual {
+                        drogue_chute_minimum_altitude_agl,
+                        drogue_chute_delay_s: us_to_s(drogue_chute_delay_us),
+                        main_chute_altitude_agl,
+                        main_chute_delay_s: us_to_s(main_chute_delay_us),
+                    },
+                },
+                landing: (&profile.landing).into(),
+                pyro_events: profile
+                    .pyro_events
+                    .iter()
+                    .map(|event| PyroEventFile {
+                        channel: event.channel.into(),
+                        trigger: (&event.trigger).into(),
+                    })
+                    .collect(),
+                gps_aiding: profile.gps_aiding.as_ref().map(|gps| GpsAidingFile {
+                    min_satellites: gps.min_satellites,
+                    max_hdop: gps.max_hdop,
+                    max_vdop: gps.max_vdop,
+                    vertical_sigma_per_vdop_m: gps.vertical_sigma_per_vdop_m,
+                }),
+            },
+            airbrakes: AirbrakesConfigFile {
+                max_open_mach: config.airbrakes.max_open_mach,
+                mach_lockout: config.airbrakes.mach_lockout.as_ref().map(|lockout| {
+                    MachLockoutFile {
+                        earliest_subsonic_after_ignition_s: us_to_s(
+                            lockout.earliest_subsonic_after_ignition_us,
+                        ),
+                        force_birth_after_ignition_s: us_to_s(
+                            lockout.force_birth_after_ignition_us,
+                        ),
+                        subsonic_crossing_altitude_asl: lockout.subsonic_crossing_altitude_asl,
+                    }
+                }),
+                rocket: (&config.airbrakes.rocket).into(),
+            },
+            staging: config.staging.as_ref().map(|staging| StagingConfigFile {
+                sustainer_ignition_delay_s: us_to_s(staging.sustainer_ignition_delay_us),
+                sustainer_ignition_acc_threshold: staging.sustainer_ignition_acc_threshold,
+                max_tilt_rad: staging.max_tilt_rad,
+                min_vertical_velocity: staging.min_vertical_velocity,
+            }),
+        }
+    }
+
+    /// The [`FlightConfig`] this file describes. Refuses a file at another
+    /// version and anything the runtime types cannot hold; does **not**
+    /// [`check`] it.
+    pub fn to_flight_config(&self) -> Result<FlightConfig, FlightConfigFileError> {
+        if self.version != FLIGHT_CONFIG_FILE_VERSION {
+            return Err(FlightConfigFileError::Version {
+                found: self.version,
+            });
+        }
+
+        let profile = &self.profile;
+        let deployment = match profile.deployment {
+            DeploymentProfileFile::Single {
+                minimum_deployment_altitude_agl,
+                delay_s,
+            } => DeploymentProfile::Single {
+                minimum_deployment_altitude_agl,
+                delay_us: s_to_us("profile.deployment.delay_s", delay_s)?,
+            },
+            DeploymentProfileFile::Dual {
+                drogue_chute_minimum_altitude_agl,
+                drogue_chute_delay_s,
+                main_chute_altitude_agl,
+                main_chute_delay_s,
+            } => DeploymentProfile::Dual {
+                drogue_chute_minimum_altitude_agl,
+                drogue_chute_delay_us: s_to_us(
+                    "profile.deployment.drogue_chute_delay_s",
+                    drogue_chute_delay_s,
+                )?,
+                main_chute_altitude_agl,
+                main_chute_delay_us: s_to_us(
+                    "profile.deployment.main_chute_delay_s",
+                    main_chute_delay_s,
+                )?,
+            },
+        };
+        let mut pyro_events = PyroEvents::new();
+        for event in &profile.pyro_events {
+            let key = "profile.pyro_events.trigger.delay_s";
+            let trigger = match event.trigger {
+                PyroTriggerFile::Apogee { delay_s } => PyroTrigger::Apogee {
+                    delay_us: s_to_us(key, delay_s)?,
+                },
+                PyroTriggerFile::DescentAltitude {
+                    altitude_agl,
+                    delay_s,
+                } => PyroTrigger::DescentAltitude {
+                    altitude_agl,
+                    delay_us: s_to_us(key, delay_s)?,
+                },
+                PyroTriggerFile::Burnout { delay_s } => PyroTrigger::Burnout {
+                    delay_us: s_to_us(key, delay_s)?,
+                },
+                PyroTriggerFile::BackupOf { channel, delay_s } => PyroTrigger::BackupOf {
+                    channel: channel.into(),
+                    delay_us: s_to_us(key, delay_s)?,
+                },
+            };
+            // Both are sized to the channels, so this cannot overflow.
+            let _ = pyro_events.push(PyroEvent {
+                channel: event.channel.into(),
+                trigger,
+            });
+        }
+
+        let mach_lockout = match &self.airbrakes.mach_lockout {
+            Some(lockout) => Some(MachLockoutConfig {
+                earliest_subsonic_after_ignition_us: s_to_us(
+                    "airbrakes.mach_lockout.earliest_subsonic_after_ignition_s",
+                    lockout.earliest_subsonic_after_ignition_s,
+                )?,
+                force_birth_after_ignition_us: s_to_us(
+                    "airbrakes.mach_lockout.force_birth_after_ignition_s",
+                    lockout.force_birth_after_ignition_s,
+                )?,
+                subsonic_crossing_altitude_asl: lockout.subsonic_crossing_altitude_asl,
+            }),
+            None => None,
+        };
+
+        let controller_mode = match &self.controller_mode {
+            ControllerModeFile::TargetApogee => ControllerMode::TargetApogee,
+            ControllerModeFile::MaximiseDrag => ControllerMode::MaximiseDrag,
+            ControllerModeFile::Scheduled { points } => {
+                let mut pairs = [(0.0, 0.0); SCHEDULE_POINTS];
+                for (pair, [t, e]) in pairs.iter_mut().zip(points) {
+                    *pair = (*t, *e);
+                }
+                ControllerMode::Scheduled(
+                    ExtensionSchedule::from_points(&pairs[..points.len()])
+                        .ok_or(FlightConfigFileError::Schedule)?,
+                )
+            }
+            ControllerModeFile::Fixed { extension } => {
+                // Written so a NaN fails.
+                if !(0.0..=1.0).contains(extension) {
+                    return Err(FlightConfigFileError::FixedExtension(*extension));
+                }
+                ControllerMode::Fixed {
+                    extension: *extension,
+                }
+            }
+        };
+
+        let deployment_policy = match &self.deployment_policy {
+            DeploymentPolicyFile::PrimaryOnly => DeploymentPolicy::PrimaryOnly,
+            DeploymentPolicyFile::FirstOf { backup } => {
+                DeploymentPolicy::FirstOf(backup.to_config()?)
+            }
+            DeploymentPolicyFile::BothAgreeWithin { window_s, backup } => {
+                DeploymentPolicy::BothAgreeWithin {
+                    window_us: s_to_us("deployment_policy.window_s", *window_s)?,
+                    backup: backup.to_config()?,
+                }
+            }
+        };
+
+        let staging = match &self.staging {
+            Some(staging) => Some(StagingConfig {
+                sustainer_ignition_delay_us: s_to_us(
+                    "staging.sustainer_ignition_delay_s",
+                    staging.sustainer_ignition_delay_s,
+                )?,
+                sustainer_ignition_acc_threshold: staging.sustainer_ignition_acc_threshold,
+                max_tilt_rad: staging.max_tilt_rad,
+                min_vertical_velocity: staging.min_vertical_velocity,
+            }),
+            None => None,
+        };
+
+        Ok(FlightConfig {
+            ignition_detection_acc_threshold: self.ignition_detection_acc_threshold,
+            profile: FlightProfile {
+                mach_lockout_duration_us: profile
+                    .mach_lockout_s
+                    .map(|s| s_to_us("profile.mach_lockout_s", s))
+                    .transpose()?,
+                deployment,
+                landing: LandingDetection {
+                    window_us: s_to_us("profile.landing.window_s", profile.landing.window_s)?,
+                    max_altitude_agl: profile.landing.max_altitude_agl,
+                    max_vertical_velocity: profile.landing.max_vertical_velocity,
+                    max_acc_std: profile.landing.max_acc_std,
+                },
+                pyro_events,
+                gps_aiding: profile.gps_aiding.as_ref().map(|gps| GpsAiding {
+                    min_satellites: gps.min_satellites,
+                    max_hdop: gps.max_hdop,
+                    max_vdop: gps.max_vdop,
+                    vertical_sigma_per_vdop_m: gps.vertical_sigma_per_vdop_m,
+                }),
+            },
+            airbrakes: AirbrakesConfig {
+                mach_lockout,
+                max_open_mach: self.airbrakes.max_open_mach,
+                rocket: (&self.airbrakes.rocket).into(),
+            },
+            controller_mode,
+            deployment_policy,
+            staging,
+        })
+    }
+}
@@ -0,0 +1,218 @@
+use super::*;
+use crate::sim::osiris::{osiris_backup_deployment, osiris_config};
+use crate::tests::init_logger;
+
+fn osiris_file() -> FlightConfigFile {
+    FlightConfigFile::new(&osiris_config(), AtmosphereFile::Standard)
+}
+
+fn errors(config: &FlightConfig) -> Vec<ConfigIssue> {
+    check(config, &Atmosphere::standard())
+        .issues
+        .into_iter()
+        .filter(|issue| issue.severity() == Severity::Error)
+        .collect()
+}
+
+/// The reference config survives the file both ways, and every field that
+/// has a duration in it comes back to the microsecond.
+#[test]
+fn osiris_round_trips_through_the_file() {
+    init_logger();
+    let file = osiris_file();
+    let config = file.to_flight_config().unwrap();
+    assert_eq!(
+        FlightConfigFile::new(&config, AtmosphereFile::Standard),
+        file
+    );
+
+    let reference = osiris_config();
+    assert_eq!(config.profile, reference.profile);
+    assert_eq!(
+        config
+            .airbrakes
+            .mach_lockout
+            .unwrap()
+            .force_birth_after_ignition_us,
+        25_000_000
+    );
+    assert_eq!(config.airbrakes.rocket.cd, reference.airbrakes.rocket.cd);
+    assert_eq!(file.atmosphere.to_atmosphere(), Atmosphere::standard());
+}
+
+/// Every variant with a table of its own goes through too: a backup
+/// policy, a schedule, a pyro table, GPS aiding and a second burn.
+#[test]
+fn every_choice_round_trips_through_the_file() {
+    let mut config = osiris_config();
+    config.deployment_policy = DeploymentPolicy::BothAgreeWithin {
+        window_us: 6_000_000,
+        backup: osiris_backup_deployment(),
+    };
+    config.controller_mode = ControllerMode::Scheduled(
+        ExtensionSchedule::from_points(&[(0.0, 0.2), (1.5, 0.8)]).unwrap(),
+    );
+    config
+        .profile
+        .pyro_events
+        .push(PyroEvent {
+            channel: PyroSelect::PyroBackupDrogue,
+            trigger: PyroTrigger::BackupOf {
+                channel: PyroSelect::PyroDrogue,
+                delay_us: 2_000_000,
+            },
+        })
+        .unwrap();
+    config.profile.gps_aiding = Some(GpsAiding::default());
+    config.staging = Some(StagingConfig {
+        sustainer_ignition_delay_us: 1_500_000,
+        sustainer_ignition_acc_threshold: 2.0 * 9.81,
+        max_tilt_rad: 0.35,
+        min_vertical_velocity: 100.0,
+    });
+    let pad = AtmosphereFile::Pad {
+        pressure_pa: 86_000.0,
+        temperature_k: 305.0,
+        site_elevation_asl: Some(1401.0),
+    };
+
+    let file = FlightConfigFile::new(&config, pad.clone());
+    let back = file.to_flight_config().unwrap();
+    assert_eq!(FlightConfigFile::new(&back, pad), file);
+    assert_eq!(back.profile, config.profile);
+    assert_eq!(back.deployment_policy, config.deployment_policy);
+    assert_eq!(back.controller_mode, config.controller_mode);
+    assert_eq!(back.staging, config.staging);
+    assert_eq!(
+        file.atmosphere.to_atmosphere(),
+        Atmosphere::from_pad(86_000.0, 305.0, Some(1401.0))
+    );
+}
+
+#[test]
+fn what_the_runtime_types_cannot_hold_is_refused() {
+    let mut file = osiris_file();
+    file.version = FLIGHT_CONFIG_FILE_VERSION + 1;
+    assert_eq!(
+        file.to_flight_config().unwrap_err(),
+        FlightConfigFileError::Version {
+            found: FLIGHT_CONFIG_FILE_VERSION + 1
+        }
+    );
+
+    let mut file = osiris_file();
+    file.profile.mach_lockout_s = Some(-1.0);
+    assert!(matches!(
+        file.to_flight_config(),
+        Err(FlightConfigFileError::Duration {
+            key: "profile.mach_lockout_s",
+            ..
+        })
+    ));
+
+    let mut file = osiris_file();
+    file.controller_mode = ControllerModeFile::Scheduled {
+        points: heapless::Vec::from_slice(&[[2.0, 0.5], [1.0, 0.5]]).unwrap(),
+    };
+    assert_eq!(
+        file.to_flight_config().unwrap_err(),
+        FlightConfigFileError::Schedule
+    );
+}
+
+/// The config every test and every dispersion run starts from is clean,
+/// with its backup channel too, and the earliest apogee lands between the
+/// lockouts and the simulated apogees (N2900 37.7 s, O3400 39.6 s).
+#[test]
+fn osiris_passes_the_check() {
+    init_logger();
+    let mut config = osiris_config();
+    config.deployment_policy = DeploymentPolicy::FirstOf(osiris_backup_deployment());
+    let result = check(&config, &Atmosphere::standard());
+    log_info!("{:?}", result);
+    assert!(result.issues.is_empty());
+    let earliest_apogee_s = result.earliest_apogee_s.unwrap();
+    assert!(
+        (31.0..39.6).contains(&earliest_apogee_s),
+        "{earliest_apogee_s}"
+    );
+}
+
+#[test]
+fn a_main_above_the_drogue_is_refused() {
+    let mut config = osiris_config();
+    config.profile.deployment = DeploymentProfile::Dual {
+        drogue_chute_minimum_altitude_agl: 400.0,
+        drogue_chute_delay_us: 1_000_000,
+        main_chute_altitude_agl: 457.2,
+        main_chute_delay_us: 0,
+    };
+    assert_eq!(
+        errors(&config).as_slice(),
+        &[ConfigIssue::MainNotBelowDrogue {
+            main_agl: 457.2,
+            drogue_minimum_agl: 400.0,
+        }]
+    );
+}
+
+/// A lockout timed for a longer burn than the airframe's still runs when
+/// the coast from the crossing is over, and both clocks are caught.
+#[test]
+fn a_lockout_running_into_apogee_is_refused() {
+    let mut config = osiris_config();
+    config.profile.mach_lockout_duration_us = Some(36_000_000);
+    config
+        .airbrakes
+        .mach_lockout
+        .as_mut()
+        .unwrap()
+        .force_birth_after_ignition_us = 34_000_000;
+    let errors = errors(&config);
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(
+        errors
+            .iter()
+            .all(|issue| matches!(issue, ConfigIssue::LockoutIntoApogee { .. }))
+    );
+}
+
+/// A backup timer set for a subsonic hop fires the drogue at speed on
+/// Osiris; the floor past it is never reached either.
+#[test]
+fn a_backup_timer_before_apogee_is_refused() {
+    let mut config = osiris_config();
+    config.deployment_policy = DeploymentPolicy::FirstOf(BackupDeploymentConfig {
+        apogee_timer_us: 20_000_000,
+        earliest_accel_apogee_us: 25_000_000,
+        arming_altitude_agl: 2000.0,
+    });
+    let errors = errors(&config);
+    assert!(errors.contains(&ConfigIssue::BackupFloorAfterTimer {
+        floor_s: 25.0,
+        timer_s: 20.0,
+    }));
+    assert!(errors.iter().any(|issue| matches!(
+        issue,
+        ConfigIssue::BackupTimerBeforeApogee { timer_s, .. } if *timer_s == 20.0
+    )));
+}
+
+#[test]
+fn a_cd_table_the_controller_cannot_invert_is_refused() {
+    let mut config = osiris_config();
+    config.airbrakes.rocket.cd[2] = [0.7, 0.7, 0.8, 0.9, 1.0];
+    config.airbrakes.rocket.burnout_mass = 0.0;
+    let result = check(&config, &Atmosphere::standard());
+    assert!(
+        result
+            .issues
+            .contains(&ConfigIssue::CdNotIncreasingWithExtension { row: 2 })
+    );
+    assert!(result.issues.contains(&ConfigIssue::NotPositive {
+        field: "airbrakes.rocket.burnout_mass",
+        value: 0.0,
+    }));
+    // And no coast is flown on it.
+    assert_eq!(result.earliest_apogee_s, None);
+}
@@ -32,6 +32,11 @@
 //! *ends* the airbrakes window, never extends or informs it, and it flows
 //! one way — nothing the airbrakes half computes can reach the pyros.
 //!
+//! The atmosphere flows the same way: it is calibrated here at launch,
+//! from the deployment half's pad altitude and the pad's air temperature
+//! (see [`FlightEstimators::atmosphere`]), and every baro altitude after
+//! that goes through it before either half's vote sees it.
+//!
 //! There are deliberately no `&mut` component accessors, so nothing beyond
 //! the read above can be wired up from outside this module.
 //!
@@ -39,16 +44,47 @@
 //! [`FlightEstimators::airbrakes_mpc_states`] fails toward `None` — if
 //! anything is missing, stale, or out of range, the brakes stay shut.
 //! Recovery (the pyro path) does not depend on the airbrakes half at all.
+//!
+//! It can depend on a third party: the backup deployment channel in
+//! [`crate::backup_deployment`], which calls apogee from the accelerometer
+//! and a liftoff timer, and sees the deployment half's pyro command only
+//! after that half has issued it. [`FlightConfig::deployment_policy`] says
+//! what the two opinions add up to; the default in every profile in the
+//! tree is the deployment half alone, passed through untouched.
+//!
+//! The channels beyond drogue and main come last, from
+//! [`FlightProfile::pyro_events`] through [`crate::pyro_events`]: after the
+//! policy, so that a drogue the backup fired is an apogee to the table too.
+//!
+//! A staged flight adds a second crossing, and it runs the other way: where
+//! [`FlightConfig::staging`] is set, the airbrakes half's tilt and vertical
+//! velocity decide whether the sustainer may be lit (see
+//! [`crate::staging`]). It is a veto and nothing more — a half that has no
+//! answer, or has been retired, withholds the igniter — and it reaches no
+//! pyro. The same sequence holds the brakes shut until the last burn is out.
 
 use core::f32::consts::FRAC_PI_2;
 
-use firmware_common_new::flight_data_record::AirbrakesState;
+#[cfg(feature = "debug-internals")]
+use firmware_common_new::flight_data_record::EstimatorInternalsRecord;
+use firmware_common_new::flight_data_record::{AirbrakesState, BaroHealth, GpsFusion};
+use firmware_common_new::gps::GPSData;
 use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
-use nalgebra::Vector2;
+use nalgebra::{Vector2, Vector3};
 
-use crate::airbrakes_estimator::{AirbrakesConfig, AirbrakesEstimator, ImuSample};
+use crate::airbrakes_estimator::{AirbrakesConfig, AirbrakesEstimator, AttitudeAngles, ImuSample};
+use crate::atmosphere::Atmosphere;
+use crate::backup_deployment::{DeploymentArbiter, DeploymentPolicy};
 use crate::baro_gate::BaroGateOutcome;
-use crate::baro_state_estimator::{FlightProfile, RocketState, RocketStateEstimator};
+use crate::baro_vote::{BaroVoter, MAX_BAROS};
+use crate::baro_state_estimator::{
+    FlightProfile, PAD_WINDOW_S, PadReference, RocketState, RocketStateEstimator,
+};
+use crate::controller::ControllerMode;
+#[cfg(feature = "debug-internals")]
+use crate::kf_internals::KfInternals;
+use crate::pyro_events::PyroSequencer;
+use crate::staging::{StagingConfig, StagingEvent, StagingSequencer};
 
 /// The MPC's input state, handed out by
 /// [`FlightEstimators::airbrakes_mpc_states`] exactly when the airbrakes
@@ -60,6 +96,29 @@ pub struct AirbrakesMPCStates {
     pub altitude_asl: f32,
     /// Velocity `[horizontal, vertical]` (m/s), from the airbrakes filter.
     pub velocity: Vector2<f32>,
+    /// The airbrakes half's in-flight scale on the configured cd table —
+    /// hand it to
+    /// [`AirBrakesMPC::update_with_cd_scale`](crate::AirBrakesMPC::update_with_cd_scale).
+    pub cd_scale: f32,
+    /// Compass heading of the airframe axis (rad, clockwise from magnetic
+    /// north), for splitting the wind profile along the flight — hand it to
+    /// [`AirBrakesMPC::update_in_wind`](crate::AirBrakesMPC::update_in_wind).
+    /// `None` when the pad had no magnetometer reading, and the heading is
+    /// only relative to the pad.
+    pub heading: Option<f32>,
+    /// Seconds since the brakes were permitted to open — the clock an
+    /// [`ExtensionSchedule`](crate::ExtensionSchedule) is read on.
+    pub enabled_for_s: f32,
+    /// [`FlightConfig::controller_mode`], handed out here rather than to the
+    /// MPC directly so that no mode can command the brakes outside this
+    /// window — hand it to
+    /// [`AirBrakesMPC::update_with_servo`](crate::AirBrakesMPC::update_with_servo).
+    pub mode: ControllerMode,
+    /// [`FlightEstimators::atmosphere`]: the air calibrated on the pad at
+    /// launch, which `altitude_asl` is in the frame of — the MPC's apogee
+    /// simulation flies through it
+    /// ([`AirBrakesMPC::update_with_servo`](crate::AirBrakesMPC::update_with_servo)).
+    pub atmosphere: Atmosphere,
 }
 
 /// Everything [`FlightEstimators`] is configured with, in one value.
@@ -107,6 +166,9 @@ pub struct FlightConfig {
     /// low, neither half detects a launch. That is the trade, one detector
     /// that is right about the moment that matters over a barometric second
     /// one that is specifically wrong about it.
+    ///
+    /// The backup deployment channel, where a policy builds one, runs a
+    /// third instance on the same field, for the same reason.
     pub ignition_detection_acc_threshold: f32,
     /// The deployment estimator's profile: Mach lockout, burn timer, and
     /// the drogue/main deployment scheme. This half fires the pyros.
@@ -114,6 +176,30 @@ pub struct FlightConfig {
     /// The airbrakes estimator's config, including the airframe it shares
     /// with the MPC.
     pub airbrakes: AirbrakesConfig,
+    /// What the brakes are flown for once they may open: the target apogee
+    /// by default, or one of the characterisation modes. Neither estimator
+    /// reads it; it rides out through
+    /// [`FlightEstimators::airbrakes_mpc_states`], so it is gated exactly
+    /// like the target is.
+    pub controller_mode: ControllerMode,
+    /// What fires the drogue: the deployment half alone, or it and the
+    /// backup channel under one of two policies (see [`DeploymentPolicy`]).
+    /// The backup's own configuration rides inside the policy, so
+    /// [`DeploymentPolicy::PrimaryOnly`] has none to get wrong.
+    ///
+    /// Configured beside [`Self::profile`] rather than inside it because it
+    /// is not the deployment half's to read: that half fires what it fires
+    /// whatever this says, and never learns what became of it.
+    pub deployment_policy: DeploymentPolicy,
+    /// The burns after the first, and when the sustainer is lit — `None` for
+    /// a single-burn flight, which is every profile in the tree that flies.
+    ///
+    /// Neither half reads it. The deployment half's Mach lockout is a
+    /// duration from the launch, so on a staged airframe it is configured to
+    /// cover both burns and needs nothing from here; the airbrakes half is
+    /// held shut by [`FlightEstimators::airbrakes_mpc_states`] until the
+    /// sequence says the last burn is out.
+    pub staging: Option<StagingConfig>,
 }
 
 /// The two flight estimators plus the policy connecting them. See the
@@ -121,11 +207,34 @@ pub struct FlightConfig {
 #[derive(Debug)]
 pub struct FlightEstimators {
     deployment: RocketStateEstimator,
+    /// The deployment half's pyro command goes through here, and the backup
+    /// channel with it, if the policy has one.
+    deployment_arbiter: DeploymentArbiter,
+    /// ...and then through the pyro-event table, which adds the channels
+    /// the deployment half does not know.
+    pyro_sequencer: PyroSequencer,
+    /// `None` on a single-burn flight.
+    staging: Option<StagingSequencer>,
     /// `None` once the airbrakes window has closed for good — see
     /// [`FlightEstimators::update`]. Retirement is destructive on purpose:
     /// there is no state left to re-open the brakes from, so the window
     /// cannot reopen no matter what any later sample looks like.
     airbrakes: Option<AirbrakesEstimator>,
+    /// The airbrakes half's barometer vote. That half takes one altitude per
+    /// sample, so its vote is taken here on its behalf — but it is its own
+    /// instance, with its own sensor histories, exactly as the ignition
+    /// detector is: the deployment half's verdicts never reach it, and
+    /// nothing the airbrakes half believes can reach the pyro half's vote.
+    airbrakes_baro_vote: BaroVoter,
+    /// The air every baro altitude is converted through: the standard day
+    /// until launch, then calibrated on the pad — see [`Self::atmosphere`].
+    atmosphere: Atmosphere,
+    /// The pad's air temperature (K), averaged over windows as long as the
+    /// deployment half's and handed out one window late like its pad
+    /// altitude. `None` from the launch on, when it has been spent on
+    /// [`Self::atmosphere`].
+    pad_air_temperature: Option<PadReference>,
+    controller_mode: ControllerMode,
 }
 
 impl FlightEstimators {
@@ -134,27 +243,70 @@ impl FlightEstimators {
         // that is genuinely shared — the ignition threshold, by value into
         // each half's own detector. Nothing crosses past this point.
         Self {
+            pyro_sequencer: PyroSequencer::new(config.profile.pyro_events.clone()),
             deployment: RocketStateEstimator::new(
                 config.profile,
                 config.ignition_detection_acc_threshold,
             ),
+            deployment_arbiter: DeploymentArbiter::new(
+                config.deployment_policy,
+                config.ignition_detection_acc_threshold,
+            ),
             airbrakes: Some(AirbrakesEstimator::new(
                 config.airbrakes,
                 config.ignition_detection_acc_threshold,
+                Atmosphere::standard(),
             )),
+            staging: config.staging.map(StagingSequencer::new),
+            airbrakes_baro_vote: BaroVoter::new(),
+            atmosphere: Atmosphere::standard(),
+            pad_air_temperature: Some(PadReference::new(PAD_WINDOW_S)),
+            controller_mode: config.controller_mode,
         }
     }
 
-    /// The ONLY mutating function — call once per sensor sample, with the
-    /// timestamp that sample was taken at (us, one monotonic clock).
+    /// The main mutating function — call once per sensor sample, with the
+    /// timestamp that sample was taken at (us, one monotonic clock). The
+    /// only others are [`Self::update_mag`] and
+    /// [`Self::update_air_temperature`], neither of which reaches a pyro.
+    ///
+    /// The deployment estimator's KF steps once per call and must see every
+    /// sample, barometer or not. IMU is optional: when `imu` is `None` the
+    /// airbrakes estimator is skipped entirely for this sample — its
+    /// measured-dt integration bridges the gap at the next IMU sample — and
+    /// the same goes for a sample on which no barometer read at all.
+    ///
+    /// `commanded_extension` is the brake extension last commanded (0.0 -
+    /// 1.0), `None` before the first command — the SD airbrakes record's
+    /// field of the same name. Only the airbrakes half's drag fit reads it;
+    /// the deployment half never sees it.
+    ///
+    /// `baro_altitudes_asl` holds each barometer's ISA pressure altitude, as
+    /// `BaroData::altitude_asl` reports it, one slot per sensor in a fixed
+    /// order and `None` for a sensor with no fresh reading — VLF5's own
+    /// first, then each `BaroMeasurementMessage` source. At most
+    /// [`MAX_BAROS`] are read. Each is converted to [`Self::atmosphere`]'s
+    /// frame here, before either half's vote sees it, and each half then
+    /// votes them down to one (see [`crate::baro_vote`]).
+    ///
+    /// The sample the deployment half latches ignition on is also the one
+    /// the atmosphere is calibrated on, from its pad altitude and
+    /// [`Self::update_air_temperature`]'s pad mean; the next sample is the
+    /// first converted through it.
     ///
-    /// Baro is always present: the deployment estimator's KF steps once per
-    /// call and must see every sample. IMU is optional: when `imu` is
-    /// `None` the airbrakes estimator is skipped entirely for this sample —
-    /// its measured-dt integration bridges the gap at the next IMU sample.
+    /// `gps` is a fix the receiver produced since the previous call, or
+    /// `None` — not the latest fix on every sample, which would fuse one fix
+    /// hundreds of times. Only the deployment half reads it, and only with
+    /// [`FlightProfile::gps_aiding`] set; see
+    /// [`RocketStateEstimator::update_gps`].
     ///
-    /// Returns the deployment estimator's pyro command passed through
-    /// UNTOUCHED — this struct adds no policy to recovery — paired with
+    /// Returns the deployment estimator's pyro command as
+    /// [`FlightConfig::deployment_policy`] leaves it — UNTOUCHED under
+    /// [`DeploymentPolicy::PrimaryOnly`], and nothing from the airbrakes half
+    /// can reach it under any policy — or, on a sample it has nothing for,
+    /// whatever [`FlightProfile::pyro_events`] has due; then whatever the
+    /// staging sequence did on this sample (see [`crate::staging`]), always
+    /// `None` without [`FlightConfig::staging`]; and last
     /// [`EstimatorLogSample`]: everything a consumer wants from this sample,
     /// which is the SD log's whole estimator half and every estimator field
     /// the telemetry packet carries.
@@ -173,7 +325,8 @@ impl FlightEstimators {
     /// `Option` return nor one inside a tuple), so putting the pyro command
     /// in a tuple would otherwise have left dropping it entirely silent. That
     /// would be catastrophic: this is the only place a drogue or main command
-    /// exists.
+    /// exists. The staging event is the same: [`StagingEvent::IgniteSustainer`]
+    /// is sent once, and only here.
     ///
     /// This is also where the airbrakes half is **retired**: dropped
     /// outright, never to return, as soon as any of three things is true.
@@ -197,10 +350,17 @@ impl FlightEstimators {
         &mut self,
         timestamp_us: u64,
         imu: Option<&ImuSample>,
-        baro_altitude_asl: f32,
-    ) -> (Option<PyroSelect>, EstimatorLogSample) {
+        baro_altitudes_asl: &[Option<f32>],
+        gps: Option<&GPSData>,
+        commanded_extension: Option<f32>,
+    ) -> (Option<PyroSelect>, Option<StagingEvent>, EstimatorLogSample) {
+        let mut converted = [None; MAX_BAROS];
+        for (slot, reading) in converted.iter_mut().zip(baro_altitudes_asl) {
+            *slot = reading.map(|altitude| self.atmosphere.altitude_asl(altitude));
+        }
+
         // (a) Deployment first, trusted outright. Its pyro command is
-        // returned as-is at the bottom.
+        // returned at the bottom as the deployment policy leaves it.
         //
         // It gets the RAW accelerometer vector, straight off the wire —
         // never anything the airbrakes half derived from it. That half is
@@ -208,19 +368,92 @@ impl FlightEstimators {
         // moment this value matters. See
         // `FlightConfig::ignition_detection_acc_threshold` for what it does
         // with it, which is one magnitude check and nothing else.
-        let (pyro, deployment_baro_gate) =
+        let (pyro, deployment_baro_gate, deployment_baro_health) =
             self.deployment
-                .update(timestamp_us, imu.map(|imu| imu.acc), baro_altitude_asl);
+                .update(timestamp_us, imu.map(|imu| imu.acc), &converted);
+        // ...then this sample's fix, if there is one. After the baro sample
+        // rather than before it, so the fix is fused at the filter's own
+        // time step; what it moves is read by the next sample's state
+        // machine, one sample — 2.4 ms — later.
+        let deployment_gps_fusion = gps.map_or(GpsFusion::NoMeasurement, |gps| {
+            self.deployment.update_gps(timestamp_us, gps)
+        });
+        // ...and then the policy, which steps the backup channel on the same
+        // raw vector and decides what of the two actually fires.
+        let pyro = self
+            .deployment_arbiter
+            .update(timestamp_us, imu.map(|imu| imu.acc), pyro);
+        // ...and last the pyro-event table, which has the final say on which
+        // one channel fires on this sample.
+        let pyro = self.pyro_sequencer.update(
+            timestamp_us,
+            imu.map(|imu| imu.acc),
+            &self.deployment.state(),
+            pyro,
+        );
+        // The pad reference latched with ignition, so this is where the
+        // pad's air is known: the pressure its altitude names, and the
+        // temperature averaged the same way. Without a temperature the
+        // flight stays on the standard day.
+        if !matches!(self.deployment.state(), RocketState::OnPad)
+            && let Some(pad_air_temperature) = self.pad_air_temperature.take()
+            && let Some(pad_temperature) = pad_air_temperature.reference_asl()
+        {
+            let pad_pressure =
+                Atmosphere::standard().pressure(self.deployment.launch_pad_altitude_asl());
+            self.atmosphere = Atmosphere::from_pad(pad_pressure, pad_temperature, None);
+            if let Some(airbrakes) = self.airbrakes.as_mut() {
+                airbrakes.set_atmosphere(self.atmosphere);
+            }
+            log_info!(
+                "atmosphere calibrated on the pad ({} Pa, {} K)",
+                pad_pressure,
+                pad_temperature
+            );
+        }
 
         // (b) Airbrakes, only when this sample actually carries IMU data. A
         // sample without it is skipped whole: the vertical filter predicts
         // with the dead reckoner's attitude, so there is nothing to step it
-        // with and nothing to fuse against.
-        if let (Some(airbrakes), Some(imu)) = (self.airbrakes.as_mut(), imu) {
-            airbrakes.update(timestamp_us, imu, baro_altitude_asl);
+        // with and nothing to fuse against. Likewise a sample no barometer
+        // read on, because that half takes an altitude on every update.
+        //
+        // Its vote runs on every sample, IMU or not, so a sensor's history
+        // is warm whatever the IMU is doing.
+        let vote = self.airbrakes_baro_vote.vote(
+            timestamp_us,
+            &converted,
+            self.airbrakes.as_ref().and_then(|ab| ab.altitude_asl()),
+        );
+        if let (Some(airbrakes), Some(imu), Some(baro_altitude_asl)) =
+            (self.airbrakes.as_mut(), imu, vote.altitude_asl)
+        {
+            airbrakes.update(timestamp_us, imu, baro_altitude_asl, commanded_extension);
         }
 
-        // (c) Retirement. Checked every sample, IMU or not, so clause 3
+        // (c) Staging, on the airbrakes half as this sample left it and
+        // before retirement can drop it: a half retired on this very sample
+        // is one that has seen the rocket stop climbing, and the sustainer is
+        // inhibited from that reading, not from its absence. Velocity is the
+        // filter's once it exists and the dead reckoner's before — on a
+        // staged flight the sustainer is usually due before the vertical
+        // filter's birth.
+        let staging = self.staging.as_mut().and_then(|staging| {
+            let airbrakes = self.airbrakes.as_ref();
+            staging.update(
+                timestamp_us,
+                imu.map(|imu| imu.acc),
+                &self.deployment.state(),
+                airbrakes.and_then(|ab| ab.tilt()),
+                airbrakes.and_then(|ab| {
+                    ab.velocity()
+                        .map(|v| v.y)
+                        .or_else(|| ab.dead_reckoned_vertical_velocity())
+                }),
+            )
+        });
+
+        // (d) Retirement. Checked every sample, IMU or not, so clause 3
         // still bites while the airbrakes half is starved of IMU data.
         if let Some(airbrakes) = self.airbrakes.as_ref() {
             let descending = airbrakes.velocity().is_some_and(|v| v.y <= 0.0);
@@ -240,7 +473,7 @@ impl FlightEstimators {
             }
         }
 
-        // (d) The log sample, built AFTER retirement so that the sample the
+        // (e) The log sample, built AFTER retirement so that the sample the
         // airbrakes half is dropped on already reports the whole airbrakes
         // group absent — the same instant the SD record and the downlink go
         // absent, with nothing in between.
@@ -249,17 +482,55 @@ impl FlightEstimators {
             deployment_vertical_velocity: self.deployment.kf_vertical_velocity(),
             deployment_launch_pad_altitude_asl: self.deployment.launch_pad_altitude_asl(),
             deployment_baro_gate,
+            deployment_baro_health,
+            deployment_gps_fusion,
+            #[cfg(feature = "debug-internals")]
+            deployment_internals: self.deployment.kf_internals(),
             airbrakes: self.airbrakes.as_ref().map(|ab| AirbrakesLogSample {
                 altitude_asl: ab.altitude_asl(),
                 vertical_velocity: ab.velocity().map(|v| v.y),
                 tilt_rad: ab.tilt(),
+                attitude: ab.attitude(),
                 burnout_detected: ab.burnout_detected(),
                 state: ab.state(),
                 calibration_complete: ab.calibration_complete(),
+                cd_scale: ab.cd_scale(),
+                #[cfg(feature = "debug-internals")]
+                internals: ab.kf_internals(),
             }),
         };
 
-        (pyro, log_sample)
+        (pyro, staging, log_sample)
+    }
+
+    /// Feed one magnetometer reading (`MagMeasurementMessage::mag`, in the
+    /// IMU's axes) with the time it was taken, on the clock [`Self::update`]
+    /// uses. Its own call because the magnetometer is its own message at its
+    /// own rate.
+    ///
+    /// Airbrakes half only, and on the pad only — it gives the attitude a
+    /// north (see [`AirbrakesEstimator::update_mag`]). Never calling it is
+    /// fine: yaw is then measured from an arbitrary pad reference, and
+    /// nothing the MPC flies on changes.
+    pub fn update_mag(&mut self, timestamp_us: u64, mag: &Vector3<f32>) {
+        if let Some(airbrakes) = self.airbrakes.as_mut() {
+            airbrakes.update_mag(timestamp_us, mag);
+        }
+    }
+
+    /// Feed one air temperature reading (K) with the time it was taken, on
+    /// the clock [`Self::update`] uses — the AIR's, from a probe in the
+    /// free stream or the pad's weather station, never the barometer die's
+    /// (see [`Atmosphere::from_pad`]).
+    ///
+    /// On the pad only: it is averaged like the pad altitude and spent at
+    /// launch on [`Self::atmosphere`], and readings after that are ignored.
+    /// Never calling it is fine: the flight then stays on the standard day,
+    /// as every flight did before the atmosphere was calibrated.
+    pub fn update_air_temperature(&mut self, timestamp_us: u64, temperature: f32) {
+        if let Some(pad_air_temperature) = self.pad_air_temperature.as_mut() {
+            pad_air_temperature.push(timestamp_us, temperature);
+        }
     }
 
     /// `Some` exactly when the airbrakes are permitted to open, carrying
@@ -267,12 +538,17 @@ impl FlightEstimators {
     /// "permitted but no state" cannot be expressed — the MPC's run/stop
     /// condition and its state source are the same value.
     ///
-    /// Two clauses, and neither is decided here:
+    /// Three clauses, and none is decided here:
     /// * the airbrakes half has not been retired — everything about *when
     ///   the window ends* lives in [`Self::update`];
     /// * the half is in its last state, which is what
     ///   [`AirbrakesEstimator::airbrakes_enabled`] reports and what
-    ///   `altitude_asl` and `velocity` being present already imply.
+    ///   `altitude_asl` and `velocity` being present already imply;
+    /// * on a staged flight, every burn is over (see
+    ///   [`StagingSequencer::burns_complete`]). The airbrakes half latches
+    ///   its burnout once, on the booster, and can open in the gap before
+    ///   the sustainer lights; this is what keeps the brakes shut through
+    ///   the second burn.
     ///
     /// Everything the permission depends on — motor out, drag check passed,
     /// filter alive, airframe under `max_open_mach` — was decided on the way
@@ -285,18 +561,55 @@ impl FlightEstimators {
     /// transition, where the estimator now asks it once.
     ///
     /// So the window is opened by the airbrakes half and closed by
-    /// retirement, and nothing in between can narrow it.
+    /// retirement, and nothing in between can narrow it. The configured
+    /// [`ControllerMode`] rides inside, which is what holds every mode —
+    /// a fixed deploy included — to the same window.
     ///
     /// [`AirbrakesEstimator::airbrakes_enabled`]:
     ///     crate::airbrakes_estimator::AirbrakesEstimator::airbrakes_enabled
     pub fn airbrakes_mpc_states(&self) -> Option<AirbrakesMPCStates> {
         let airbrakes = self.airbrakes.as_ref()?;
+        if !self
+            .staging
+            .as_ref()
+            .is_none_or(StagingSequencer::burns_complete)
+        {
+            return None;
+        }
         Some(AirbrakesMPCStates {
             altitude_asl: airbrakes.altitude_asl()?,
             velocity: airbrakes.velocity()?,
+            cd_scale: airbrakes.cd_scale()?,
+            heading: airbrakes
+                .attitude()
+                .filter(|attitude| attitude.yaw_is_magnetic)
+                .map(|attitude| attitude.yaw),
+            enabled_for_s: airbrakes.enabled_for_s()?,
+            mode: self.controller_mode,
+            atmosphere: self.atmosphere,
         })
     }
 
+    /// The air the flight is flown in: [`Atmosphere::standard`] on the pad,
+    /// and from the sample ignition latches on,
+    /// [`Atmosphere::from_pad`] on the pad's pressure and air temperature
+    /// — if [`Self::update_air_temperature`] was fed, and the standard day
+    /// still if not.
+    ///
+    /// Calibrated at launch rather than configured before it: both numbers
+    /// are the pad's on the day, and the pressure is the one the deployment
+    /// half's latched pad altitude names. The frame stays anchored on that
+    /// pressure altitude, so the pad, and any target latched against it,
+    /// stays where it was and only heights above it stretch. Both filters
+    /// and the MPC's state are in its frame from then on, and the airbrakes
+    /// half's drag fits read their density and speed of sound from it. The
+    /// MPC gets the same one in [`AirbrakesMPCStates`]: an apogee
+    /// prediction in one atmosphere's metres is off by the pad's
+    /// temperature ratio in another's.
+    pub fn atmosphere(&self) -> Atmosphere {
+        self.atmosphere
+    }
+
     /// The deployment estimator's rocket state — the honest variant set,
     /// including [`RocketState::MachLockout`].
     pub fn state(&self) -> RocketState {
@@ -367,10 +680,40 @@ pub struct EstimatorLogSample {
     /// where the two fields above go absent.
     pub deployment_launch_pad_altitude_asl: f32,
     pub deployment_baro_gate: BaroGateOutcome,
+    /// The deployment half's vote on each barometer this sample — which of
+    /// them the altitude above was made of. The airbrakes half's verdicts
+    /// are not logged: they are the same checks on the same readings, and
+    /// the deployment half's are the ones a pyro depended on.
+    pub deployment_baro_health: [BaroHealth; MAX_BAROS],
+    /// What the deployment half made of this sample's GPS fix —
+    /// [`GpsFusion::NoMeasurement`] on every sample without one.
+    pub deployment_gps_fusion: GpsFusion,
+    /// What the deployment filter's baro update did on this sample — see
+    /// [`RocketStateEstimator::kf_internals`]. `None` wherever no reading
+    /// reached it; a rejected reading is here, with its gate verdict.
+    #[cfg(feature = "debug-internals")]
+    pub deployment_internals: Option<KfInternals>,
     /// `None` once the airbrakes half is retired at apogee — absent, not zero.
     pub airbrakes: Option<AirbrakesLogSample>,
 }
 
+#[cfg(feature = "debug-internals")]
+impl EstimatorLogSample {
+    /// Both filters' internals as the SD record that goes out just before
+    /// this sample's fast record, stamped with the same `timestamp_us`.
+    pub fn internals_record(&self, timestamp_us: u64) -> EstimatorInternalsRecord {
+        EstimatorInternalsRecord {
+            timestamp_us,
+            deployment: self.deployment_internals.as_ref().map(Into::into),
+            airbrakes: self
+                .airbrakes
+                .and_then(|ab| ab.internals)
+                .as_ref()
+                .map(Into::into),
+        }
+    }
+}
+
 /// The airbrakes half of [`EstimatorLogSample`]. The `Option` fields are
 /// absent until the piece of the estimator that produces them is alive.
 #[derive(Debug, Clone, Copy)]
@@ -378,6 +721,14 @@ pub struct AirbrakesLogSample {
     pub altitude_asl: Option<f32>,
     pub vertical_velocity: Option<f32>,
     pub tilt_rad: Option<f32>,
+    /// Roll, pitch and yaw (see [`AirbrakesEstimator::attitude`]), present
+    /// from the end of stage 1 — the same samples as `tilt_rad`, which is
+    /// its pitch from the other side. Logged for the ground, never flown on:
+    /// the MPC reads tilt alone.
+    ///
+    /// [`AirbrakesEstimator::attitude`]:
+    ///     crate::airbrakes_estimator::AirbrakesEstimator::attitude
+    pub attitude: Option<AttitudeAngles>,
     pub burnout_detected: bool,
     /// Which of the four states produced this sample.
     ///
@@ -407,6 +758,20 @@ pub struct AirbrakesLogSample {
     /// [`AirbrakesEstimator::calibration_complete`]:
     ///     crate::airbrakes_estimator::AirbrakesEstimator::calibration_complete
     pub calibration_complete: bool,
+    /// The in-flight drag fit's scale on the configured cd table (see
+    /// [`AirbrakesEstimator::cd_scale`]), present from the vertical filter's
+    /// birth. Logged because it is the one MPC input the ground cannot
+    /// recompute: a scale pinned at a bound says the configured airframe was
+    /// wrong, and by how much.
+    ///
+    /// [`AirbrakesEstimator::cd_scale`]:
+    ///     crate::airbrakes_estimator::AirbrakesEstimator::cd_scale
+    pub cd_scale: Option<f32>,
+    /// The vertical filter's innovation and covariance on this sample, from
+    /// its birth — see
+    /// [`AirbrakesEstimator::kf_internals`](crate::airbrakes_estimator::AirbrakesEstimator::kf_internals).
+    #[cfg(feature = "debug-internals")]
+    pub internals: Option<KfInternals>,
 }
 
 #[cfg(test)]
@@ -432,6 +797,9 @@ mod tests {
             ignition_detection_acc_threshold: IGNITION_ACC_THRESHOLD,
             profile: subsonic_profile(),
             airbrakes: lc25_airbrakes(),
+            controller_mode: ControllerMode::TargetApogee,
+            deployment_policy: DeploymentPolicy::PrimaryOnly,
+            staging: None,
         });
         let imu = ImuSample {
             acc: Vector3::new(0.0, 0.0, 9.81),
@@ -440,7 +808,7 @@ mod tests {
 
         let mut t_us = 0u64;
         for _ in 0..(5 * SAMPLES_PER_S) {
-            let (pyro, _log) = est.update(t_us, Some(&imu), 200.0);
+            let (pyro, _staging, _log) = est.update(t_us, Some(&imu), &[Some(200.0)], None, None);
             assert!(pyro.is_none());
             assert!(est.airbrakes_mpc_states().is_none());
             t_us += SAMPLE_DT_US;
@@ -461,6 +829,9 @@ mod tests {
             ignition_detection_acc_threshold: IGNITION_ACC_THRESHOLD,
             profile: subsonic_profile(),
             airbrakes: lc25_airbrakes(),
+            controller_mode: ControllerMode::TargetApogee,
+            deployment_policy: DeploymentPolicy::PrimaryOnly,
+            staging: None,
         });
 
         // Clean point-mass trajectory: 5 s pad hold, 3 s burn at
@@ -504,12 +875,12 @@ mod tests {
         for (i, (&alt, &sf)) in samples.iter().zip(specific_force.iter()).enumerate() {
             let t_us = i as u64 * SAMPLE_DT_US;
             let acc = Some(Vector3::new(0.0, 0.0, sf));
-            let (expected, _expected_gate) = bare.update(t_us, acc, alt);
+            let (expected, _expected_gate, _expected_health) = bare.update(t_us, acc, &[Some(alt)]);
             let imu = ImuSample {
                 acc: acc.unwrap(),
                 gyro: Vector3::zeros(),
             };
-            let (got, _log) = composed.update(t_us, Some(&imu), alt);
+            let (got, _staging, _log) = composed.update(t_us, Some(&imu), &[Some(alt)], None, None);
             assert_eq!(expected, got, "pyro mismatch at sample {i}");
             if let Some(pyro) = got {
                 fires.push(pyro);
@@ -519,4 +890,62 @@ mod tests {
         // Single deployment: drogue at apogee, main on the next sample.
         assert_eq!(fires, vec![PyroSelect::PyroDrogue, PyroSelect::PyroMain]);
     }
+
+    /// The atmosphere is the standard day on the pad and is calibrated on
+    /// the sample ignition latches, from the pad altitude's pressure and the
+    /// pad's air temperature — and the pad stays where it was, so nothing
+    /// measured against it moves. Without a temperature it never changes.
+    #[test]
+    fn atmosphere_is_calibrated_at_launch_from_the_pad() {
+        let pad_altitude_asl = 200.0f32;
+        let launch = |air_temperature: Option<f32>| {
+            let mut est = FlightEstimators::new(FlightConfig {
+                ignition_detection_acc_threshold: IGNITION_ACC_THRESHOLD,
+                profile: subsonic_profile(),
+                airbrakes: lc25_airbrakes(),
+                controller_mode: ControllerMode::TargetApogee,
+                deployment_policy: DeploymentPolicy::PrimaryOnly,
+                staging: None,
+            });
+            let mut t_us = 0u64;
+            let mut altitude_asl = pad_altitude_asl;
+            let mut velocity = 0.0f32;
+            for i in 0..(6 * SAMPLES_PER_S) {
+                let on_rail = i < 5 * SAMPLES_PER_S;
+                if !on_rail {
+                    velocity += 80.0 * DT;
+                    altitude_asl += velocity * DT;
+                }
+                if let Some(temperature) = air_temperature {
+                    est.update_air_temperature(t_us, temperature);
+                }
+                let imu = ImuSample {
+                    acc: Vector3::new(0.0, 0.0, if on_rail { 9.81 } else { 80.0 + 9.81 }),
+                    gyro: Vector3::zeros(),
+                };
+                let (_pyro, _staging, _log) =
+                    est.update(t_us, Some(&imu), &[Some(altitude_asl)], None, None);
+                if matches!(est.state(), RocketState::OnPad) {
+                    assert_eq!(est.atmosphere(), Atmosphere::standard());
+                }
+                t_us += SAMPLE_DT_US;
+            }
+            assert!(!matches!(est.state(), RocketState::OnPad));
+            est
+        };
+
+        let hot = launch(Some(308.15));
+        let pad = hot.launch_pad_altitude_asl();
+        assert_eq!(pad, pad_altitude_asl);
+        assert_eq!(
+            hot.atmosphere(),
+            Atmosphere::from_pad(Atmosphere::standard().pressure(pad), 308.15, None)
+        );
+        assert!((hot.atmosphere().altitude_asl(pad) - pad).abs() < 0.01);
+        // A 35 C pad stretches the first kilometre above it by ~7%.
+        let stretched = hot.atmosphere().altitude_asl(pad + 1000.0) - pad;
+        assert!((1060.0..1080.0).contains(&stretched), "{stretched}");
+
+        assert_eq!(launch(None).atmosphere(), Atmosphere::standard());
+    }
 }
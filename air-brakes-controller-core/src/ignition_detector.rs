@@ -11,7 +11,9 @@
 //! One implementation, two instances. Sharing the *type* is what makes the
 //! two halves provably agree about what ignition means; sharing an
 //! *instance* would be wrong, because the halves are not allowed to detect
-//! at the same instant — see [`IgnitionDetector::update`].
+//! at the same instant — see [`IgnitionDetector::update`]. A third instance
+//! lives in the backup deployment channel, where a policy builds one
+//! ([`crate::backup_deployment`]), for the same reason.
 //!
 //! The threshold is genuinely per-airframe — sized against the motor's
 //! thrust curve, and a bench profile whose scripted motor reads 9.15 g
@@ -29,9 +31,15 @@
 //!
 //! [`FlightConfig::ignition_detection_acc_threshold`]:
 //!     crate::FlightConfig::ignition_detection_acc_threshold
+//!
+//! A flight with more than one burn asks the question again in the air, and
+//! there the magnitude check is the wrong instrument: see [`BurnTracker`],
+//! which answers it from the pad's answer onward.
 
 use nalgebra::Vector3;
 
+use crate::backup_deployment::PadGravity;
+
 /// Time constant of the low pass in front of the threshold — a 10 Hz corner
 /// (`1 / 2*pi*10`).
 ///
@@ -125,3 +133,162 @@ impl IgnitionDetector {
         self.sustain_s >= SUSTAIN_S
     }
 }
+
+/// Axial specific force (m/s^2, along the pad's up, positive down the nose)
+/// past which the motor counts as out. The airbrakes half's burnout
+/// threshold: comfortably above accelerometer noise, and reached within a
+/// few tenths of a second of the thrust tail-off on every motor flown so far.
+const BURNOUT_DECEL_M_S2: f32 = 2.0;
+/// How long it has to hold (s of measured time).
+const BURNOUT_SUSTAIN_S: f32 = 0.3;
+
+/// One edge of a burn, as [`BurnTracker`] saw it. `burn` counts from 0, the
+/// motor that left the pad.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BurnEdge {
+    Ignition { burn: u8 },
+    Burnout { burn: u8 },
+}
+
+#[derive(Debug, Clone)]
+enum BurnPhase {
+    OnPad {
+        gravity: PadGravity,
+    },
+    Burning {
+        up: Vector3<f32>,
+        since_us: Option<u64>,
+    },
+    Coasting {
+        up: Vector3<f32>,
+        since_us: Option<u64>,
+    },
+    /// The flight left the pad before the pad had a gravity reference, so
+    /// there is no up to measure thrust or drag along. No burnout is ever
+    /// called, and no later ignition.
+    StoodDown,
+}
+
+/// Every burn of the flight, ignition to burnout and round again.
+///
+/// [`IgnitionDetector`] answers "has the motor lit?" once, from the pad, and
+/// that is all the two halves ask of it. A two-stage or airstart flight asks
+/// it again in the air, where the magnitude check cannot: a few seconds past
+/// burnout at Mach 1.5 the drag alone reads several g. So after the first
+/// burn this tracks the **sign** of the specific force along the pad's up —
+/// drag points down the nose and thrust up it — and it is the same channel
+/// the burnout latch reads:
+///
+/// * the **first ignition** is not its to detect. It is handed `launched`,
+///   the deployment half's ignition, so there is still one answer to "the
+///   motor lit" for the flight;
+/// * **burnout** is that channel held below `-BURNOUT_DECEL_M_S2` for
+///   `BURNOUT_SUSTAIN_S`;
+/// * **each later ignition** is that channel held above
+///   `relight_acc_threshold` for [`SUSTAIN_S`], the sustain every other
+///   ignition latch uses. `None` is a single-burn flight, and nothing after
+///   the first burnout is watched for.
+///
+/// The pad's up is the one the rail's gravity measured, from its own
+/// [`PadGravity`]. The airframe leans off it as the flight turns over, and
+/// both thresholds read the lean as a cosine: a few percent at the tilts a
+/// sustainer is permitted to light at (see [`crate::staging`]).
+///
+/// [`crate::pyro_events`] runs one for its burnout rows, single-burn or not;
+/// [`crate::staging`] runs another for the staging events.
+#[derive(Debug, Clone)]
+pub(crate) struct BurnTracker {
+    relight_acc_threshold: Option<f32>,
+    phase: BurnPhase,
+    /// Burns lit so far, the first included.
+    burns: u8,
+}
+
+impl BurnTracker {
+    pub(crate) fn new(relight_acc_threshold: Option<f32>) -> Self {
+        Self {
+            relight_acc_threshold,
+            phase: BurnPhase::OnPad {
+                gravity: PadGravity::new(),
+            },
+            burns: 0,
+        }
+    }
+
+    /// Advance by one sample. `launched` is whether the deployment half has
+    /// left the pad, as of this sample.
+    ///
+    /// A sample without an accelerometer says nothing either way, and every
+    /// sustain carries on over it.
+    pub(crate) fn update(
+        &mut self,
+        timestamp_us: u64,
+        acc: Option<Vector3<f32>>,
+        launched: bool,
+    ) -> Option<BurnEdge> {
+        let held = |since_us: &mut Option<u64>, holds: bool, sustain_s: f32| {
+            if !holds {
+                *since_us = None;
+                return false;
+            }
+            let since = *since_us.get_or_insert(timestamp_us);
+            timestamp_us.saturating_sub(since) as f32 * 1e-6 >= sustain_s
+        };
+
+        match &mut self.phase {
+            BurnPhase::OnPad { gravity } => {
+                if !launched {
+                    if let Some(acc) = acc {
+                        gravity.push(timestamp_us, acc);
+                    }
+                    return None;
+                }
+                self.phase = match gravity.reference {
+                    Some((up, _)) => BurnPhase::Burning { up, since_us: None },
+                    None => {
+                        log_warn!("burn tracker: ignition with no pad gravity, no burnout");
+                        BurnPhase::StoodDown
+                    }
+                };
+                self.burns = 1;
+                Some(BurnEdge::Ignition { burn: 0 })
+            }
+            BurnPhase::Burning { up, since_us } => {
+                let acc = acc?;
+                if !held(since_us, acc.dot(up) < -BURNOUT_DECEL_M_S2, BURNOUT_SUSTAIN_S) {
+                    return None;
+                }
+                let burn = self.burns - 1;
+                log_info!("burn tracker: burnout of burn {}", burn);
+                self.phase = BurnPhase::Coasting {
+                    up: *up,
+                    since_us: None,
+                };
+                Some(BurnEdge::Burnout { burn })
+            }
+            BurnPhase::Coasting { up, since_us } => {
+                let threshold = self.relight_acc_threshold?;
+                let acc = acc?;
+                if !held(since_us, acc.dot(up) > threshold, SUSTAIN_S) {
+                    return None;
+                }
+                let burn = self.burns;
+                log_info!("burn tracker: ignition of burn {}", burn);
+                self.burns = self.burns.saturating_add(1);
+                self.phase = BurnPhase::Burning {
+                    up: *up,
+                    since_us: None,
+                };
+                Some(BurnEdge::Ignition { burn })
+            }
+            BurnPhase::StoodDown => None,
+        }
+    }
+
+    /// No motor is burning: on the pad, coasting between burns, or out for
+    /// good. `false` while stood down after a launch, where it cannot tell.
+    pub(crate) fn coasting(&self) -> bool {
+        matches!(self.phase, BurnPhase::OnPad { .. } | BurnPhase::Coasting { .. })
+    }
+}
@@ -0,0 +1,287 @@
+//! What one Kalman measurement update did, and whether a run of them says
+//! the filter believes its own covariance.
+//!
+//! Both altitude filters carry a 2x2 covariance that nothing downstream
+//! reads: the apogee predictor and the deployment logic take the means and
+//! trust them. Tuning Q and R against flight data therefore has nothing to
+//! look at but the means, and a mean that tracks well says nothing about
+//! whether P is right — a filter can be badly overconfident and still
+//! follow a clean baro. The innovation is what says it. For a consistent
+//! filter the innovation `y` is zero-mean with variance `S = H P Hᵀ + R`,
+//! so the normalised innovation squared `y² / S` is χ² with one degree of
+//! freedom: mean 1, and above 3.84 one sample in twenty.
+//!
+//! [`KfInternals`] is one update's worth, carried out through the log
+//! samples when the `debug-internals` feature is on and written to SD as
+//! [`KfInternalsRecord`]. [`NisSummary`] is the check: feed it a flight's
+//! worth and it says whether the mean NIS sits inside the band a consistent
+//! filter would produce over that many samples. It is here, not in the
+//! CLI, so that the sim, replay and anything reading a card all apply the
+//! same test; it allocates nothing, so it compiles without the feature.
+
+use core::fmt;
+
+use firmware_common_new::flight_data_record::{
+    DEPLOYMENT_BARO_GATE_REJECT, DEPLOYMENT_BARO_RESYNC, KfInternalsRecord,
+};
+
+use crate::BaroGateOutcome;
+
+/// The 95th percentile of χ² with one degree of freedom.
+pub const NIS_95_BOUND: f32 = 3.841;
+
+/// One measurement update: the innovation against the prior, its expected
+/// variance, and the covariance diagonal after the update.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KfInternals {
+    /// Measurement minus predicted altitude (m).
+    pub innovation: f32,
+    /// `H P⁻ Hᵀ + R` with the prior covariance (m²).
+    pub innovation_variance: f32,
+    /// `P[0][0]` after the update (m²). After a rejection that is the
+    /// prior's, since nothing was fused; after a resync it includes the
+    /// gate-sized inflation.
+    pub altitude_variance: f32,
+    /// `P[1][1]` after the update (m²/s²).
+    pub velocity_variance: f32,
+    /// The gate's verdict. Always `Accepted` for the airbrakes filter.
+    pub gate: BaroGateOutcome,
+}
+
+impl KfInternals {
+    /// Normalised innovation squared, `y² / S`.
+    pub fn nis(&self) -> f32 {
+        self.innovation * self.innovation / self.innovation_variance
+    }
+}
+
+impl From<&KfInternals> for KfInternalsRecord {
+    fn from(internals: &KfInternals) -> Self {
+        let gate = internals.gate;
+        KfInternalsRecord {
+            innovation: internals.innovation,
+            innovation_variance: internals.innovation_variance,
+            altitude_variance: internals.altitude_variance,
+            velocity_variance: internals.velocity_variance,
+            gate_flags: if gate.rejected() {
+                DEPLOYMENT_BARO_GATE_REJECT
+            } else {
+                0
+            } | if gate.resynced() {
+                DEPLOYMENT_BARO_RESYNC
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// Back from the SD record, for the tools that read a card: the flags
+/// decode to the gate outcome they were packed from.
+impl From<&KfInternalsRecord> for KfInternals {
+    fn from(record: &KfInternalsRecord) -> Self {
+        KfInternals {
+            innovation: record.innovation,
+            innovation_variance: record.innovation_variance,
+            altitude_variance: record.altitude_variance,
+            velocity_variance: record.velocity_variance,
+            gate: if record.gate_flags & DEPLOYMENT_BARO_RESYNC != 0 {
+                BaroGateOutcome::Resynced
+            } else if record.gate_flags & DEPLOYMENT_BARO_GATE_REJECT != 0 {
+                BaroGateOutcome::Rejected
+            } else {
+                BaroGateOutcome::Accepted
+            },
+        }
+    }
+}
+
+/// Whether a run of NIS values is what a consistent filter would produce.
+///
+/// Samples the gate rejected are left out: the gate exists to throw away
+/// what the model does not explain, so counting them would fail every
+/// filter that flew through an ejection blast. Judging those samples is the
+/// gate's job, not this one's.
+///
+/// The band on the mean assumes the innovations are independent, which is
+/// itself part of what consistency means — a filter whose innovations are
+/// correlated is mistuned whatever their mean says. So the band is the
+/// necessary condition, not the whole test; the innovation plot is the rest.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NisSummary {
+    count: u32,
+    sum: f64,
+    above_bound: u32,
+}
+
+/// Which side of the band a mean NIS fell on.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NisVerdict {
+    /// Too few samples to say anything.
+    Insufficient,
+    Consistent,
+    /// Mean above the band: the innovations are bigger than P and R claim.
+    /// Q or R is too small.
+    Overconfident,
+    /// Mean below the band: the filter doubts itself more than it needs
+    /// to, and lags for it. Q or R is too large.
+    Underconfident,
+}
+
+impl NisSummary {
+    /// Fewer samples than this give a band too wide to mean anything.
+    pub const MIN_SAMPLES: u32 = 20;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, internals: &KfInternals) {
+        if internals.gate.rejected() {
+            return;
+        }
+        let nis = internals.nis();
+        if !nis.is_finite() {
+            return;
+        }
+        self.count += 1;
+        self.sum += nis as f64;
+        if nis > NIS_95_BOUND {
+            self.above_bound += 1;
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<f32> {
+        (self.count > 0).then(|| (self.sum / self.count as f64) as f32)
+    }
+
+    /// The fraction of samples above [`NIS_95_BOUND`]; 5% for a consistent
+    /// filter.
+    pub fn fraction_above_bound(&self) -> Option<f32> {
+        (self.count > 0).then(|| self.above_bound as f32 / self.count as f32)
+    }
+
+    /// The 95% band on the mean of `count` independent χ²(1) samples,
+    /// `1 ± 1.96 √(2 / N)`.
+    pub fn band(&self) -> Option<(f32, f32)> {
+        (self.count > 0).then(|| {
+            let half = 1.96 * libm::sqrtf(2.0 / self.count as f32);
+            (1.0 - half, 1.0 + half)
+        })
+    }
+
+    pub fn verdict(&self) -> NisVerdict {
+        match (self.mean(), self.band()) {
+            (Some(mean), Some((low, high))) if self.count >= Self::MIN_SAMPLES => {
+                if mean > high {
+                    NisVerdict::Overconfident
+                } else if mean < low {
+                    NisVerdict::Underconfident
+                } else {
+                    NisVerdict::Consistent
+                }
+            }
+            _ => NisVerdict::Insufficient,
+        }
+    }
+}
+
+impl fmt::Display for NisVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Insufficient => "too few samples",
+            Self::Consistent => "consistent",
+            Self::Overconfident => "overconfident (Q or R too small)",
+            Self::Underconfident => "underconfident (Q or R too large)",
+        })
+    }
+}
+
+impl fmt::Display for NisSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.mean(), self.band(), self.fraction_above_bound()) {
+            (Some(mean), Some((low, high)), Some(above)) => write!(
+                f,
+                "mean NIS {mean:.2} over {} samples (band {low:.2}..{high:.2}), {:.1}% above {NIS_95_BOUND}: {}",
+                self.count,
+                above * 100.0,
+                self.verdict(),
+            ),
+            _ => write!(f, "no fused samples"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(innovation: f32, gate: BaroGateOutcome) -> KfInternals {
+        KfInternals {
+            innovation,
+            innovation_variance: 1.0,
+            altitude_variance: 0.5,
+            velocity_variance: 0.5,
+            gate,
+        }
+    }
+
+    /// Innovations of ±1σ average to NIS 1 exactly; scaling them up or down
+    /// moves the mean out of the band on the side the name says.
+    #[test]
+    fn the_verdict_follows_the_mean_against_the_band() {
+        let mut summary = NisSummary::new();
+        for i in 0..200 {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            summary.add(&sample(sign, BaroGateOutcome::Accepted));
+        }
+        assert_eq!(summary.count(), 200);
+        assert!((summary.mean().unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(summary.verdict(), NisVerdict::Consistent);
+
+        let mut hot = NisSummary::new();
+        let mut cold = NisSummary::new();
+        for _ in 0..200 {
+            hot.add(&sample(2.0, BaroGateOutcome::Accepted));
+            cold.add(&sample(0.3, BaroGateOutcome::Accepted));
+        }
+        assert_eq!(hot.verdict(), NisVerdict::Overconfident);
+        assert_eq!(hot.fraction_above_bound(), Some(1.0));
+        assert_eq!(cold.verdict(), NisVerdict::Underconfident);
+    }
+
+    #[test]
+    fn rejected_samples_and_short_runs_do_not_count() {
+        let mut summary = NisSummary::new();
+        for _ in 0..50 {
+            summary.add(&sample(100.0, BaroGateOutcome::Rejected));
+        }
+        summary.add(&sample(100.0, BaroGateOutcome::Resynced));
+        assert_eq!(summary.count(), 0);
+        assert_eq!(summary.verdict(), NisVerdict::Insufficient);
+        for _ in 0..NisSummary::MIN_SAMPLES - 1 {
+            summary.add(&sample(1.0, BaroGateOutcome::Accepted));
+        }
+        assert_eq!(summary.verdict(), NisVerdict::Insufficient);
+    }
+
+    #[test]
+    fn the_gate_survives_the_trip_through_the_record_flags() {
+        let record = KfInternalsRecord::from(&sample(3.0, BaroGateOutcome::Resynced));
+        assert_eq!(
+            record.gate_flags,
+            DEPLOYMENT_BARO_GATE_REJECT | DEPLOYMENT_BARO_RESYNC
+        );
+        assert_eq!(record.innovation, 3.0);
+        assert_eq!(
+            KfInternals::from(&record),
+            sample(3.0, BaroGateOutcome::Resynced)
+        );
+    }
+}
@@ -15,23 +15,48 @@
 mod fmt;
 
 pub mod airbrakes_estimator;
+#[cfg(any(test, feature = "std"))]
+pub mod airframe_fit;
+pub mod atmosphere;
+pub mod backup_deployment;
 pub mod baro_gate;
+pub mod baro_vote;
 pub mod baro_state_estimator;
 mod controller;
+pub mod flight_config_file;
 pub mod flight_estimators;
 pub mod ignition_detector;
+pub mod kf_internals;
+pub mod pyro_events;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+#[cfg(any(test, feature = "std"))]
+pub mod smoother;
+pub mod staging;
 mod utils;
 
 pub use baro_state_estimator::{
-    DeploymentProfile, FlightProfile, RocketState, RocketStateEstimator,
+    DeploymentProfile, FlightProfile, GpsAiding, LandingDetection, RocketState,
+    RocketStateEstimator,
 };
+pub use atmosphere::Atmosphere;
+pub use backup_deployment::{BackupDeploymentConfig, DeploymentPolicy};
 pub use baro_gate::BaroGateOutcome;
+pub use baro_vote::{BaroVote, BaroVoter, MAX_BAROS};
 pub use ignition_detector::IgnitionDetector;
-pub use airbrakes_estimator::ImuSample;
+pub use kf_internals::{KfInternals, NisSummary, NisVerdict};
+pub use pyro_events::{PyroEvent, PyroEvents, PyroSequencer, PyroTrigger};
+pub use staging::{StagingConfig, StagingEvent, StagingSequencer};
+pub use airbrakes_estimator::{AttitudeAngles, ImuSample};
+pub use flight_config_file::{FLIGHT_CONFIG_FILE_VERSION, FlightConfigFile};
 pub use flight_estimators::{
     AirbrakesLogSample, AirbrakesMPCStates, EstimatorLogSample, FlightConfig, FlightEstimators,
 };
-pub use controller::{AirBrakesMPC, MpcSolution, RocketParameters};
+pub use controller::{
+    AirBrakesMPC, CD_MACH_POINTS, ControllerMode, ControllerModeKind, ExtensionSchedule,
+    MpcSolution, RocketParameters, SCHEDULE_POINTS, ServoModel, ServoState, WIND_BINS,
+    WindProfile,
+};
 pub use utils::{approximate_air_density, approximate_speed_of_sound, lerp};
 
 #[cfg(test)]
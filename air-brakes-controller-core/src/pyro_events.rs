@@ -0,0 +1,317 @@
+//! The pyro-event table: every channel beyond the deployment half's drogue
+//! and main, and what fires each one.
+//!
+//! The deployment half knows two pyros, and it is right to: it decides
+//! apogee and the main altitude, and those are the only two questions a
+//! barometer answers. A separation charge, a backup drogue and a backup main
+//! ask nothing new of it — each one is "some time after something the flight
+//! already knows about" — so none of them is taught to that half. They are
+//! listed in [`FlightProfile::pyro_events`](crate::FlightProfile::pyro_events)
+//! instead, one [`PyroEvent`] per channel, and [`PyroSequencer`] reads them
+//! against what the flight has done so far:
+//!
+//! * **Apogee**: the sample the deployment half calls descent — the start of
+//!   its drogue delay, not the drogue — or the first drogue to go out by any
+//!   route, whichever is first. So a backup channel that fired the drogue
+//!   under [`DeploymentPolicy`](crate::DeploymentPolicy) is an apogee here
+//!   too.
+//! * **Descent altitude**: the first sample after apogee on which the
+//!   deployment filter reads at or below the altitude. The same single
+//!   comparison the main altitude is, against the same filter.
+//! * **Burnout**: the sequencer's own latch, on the accelerometer — the
+//!   specific force along the pad's up held negative, from the deployment
+//!   half's ignition on (see [`BurnTracker`]). Drag is the only force an
+//!   accelerometer can feel on a coasting airframe, and it points down the
+//!   nose. The airbrakes half latches the same thing, and better, but
+//!   nothing it computes may reach a pyro. On a flight with more than one
+//!   burn it is the **first** burnout — the booster's, which is the one a
+//!   separation charge is timed from.
+//! * **Backup of** another channel: a delay after that channel fired, by
+//!   whatever fired it.
+//!
+//! Every trigger carries a delay, and the channel fires that long after its
+//! condition was met. Each channel fires **once**; one that already has —
+//! the table's drogue before the deployment half's, say — swallows the
+//! second command. And **one pyro per sample**, as everywhere else: the
+//! deployment half's command goes first on its sample, and anything the
+//! table has due waits for the next one, in table order.
+//!
+//! A table entry for the drogue or the main is therefore a first-of: it
+//! fires the channel if the deployment half has not yet. Nothing here holds
+//! a main back for its drogue the way the arbiter does, so a table that asks
+//! for a main before its drogue gets one.
+
+use firmware_common_new::vlp::packets::fire_pyro::{PYRO_CHANNELS, PyroSelect};
+use nalgebra::Vector3;
+
+use crate::baro_state_estimator::RocketState;
+use crate::ignition_detector::{BurnEdge, BurnTracker};
+
+/// When a channel in the table fires.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PyroTrigger {
+    /// `delay_us` after apogee (see the module docs for which apogee).
+    Apogee { delay_us: u32 },
+    /// `delay_us` after the deployment filter first reads at or below
+    /// `altitude_agl` (m above the pad) on the way down.
+    DescentAltitude { altitude_agl: f32, delay_us: u32 },
+    /// `delay_us` after burnout.
+    Burnout { delay_us: u32 },
+    /// `delay_us` after `channel` fired, by this table or otherwise. A
+    /// channel that never fires never fires its backup.
+    BackupOf { channel: PyroSelect, delay_us: u32 },
+}
+
+impl PyroTrigger {
+    pub fn delay_us(&self) -> u32 {
+        match self {
+            Self::Apogee { delay_us }
+            | Self::DescentAltitude { delay_us, .. }
+            | Self::Burnout { delay_us }
+            | Self::BackupOf { delay_us, .. } => *delay_us,
+        }
+    }
+}
+
+/// One row of the table: a channel and what fires it.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyroEvent {
+    pub channel: PyroSelect,
+    pub trigger: PyroTrigger,
+}
+
+/// The table itself. Sized to the channels, since a channel fires once and
+/// a second row for it could never do anything.
+pub type PyroEvents = heapless::Vec<PyroEvent, PYRO_CHANNELS>;
+
+/// [`PyroEvents`], applied: the arbiter's pyro command in, the one the pyros
+/// see out. Call [`Self::update`] on every sample.
+#[derive(Debug, Clone)]
+pub struct PyroSequencer {
+    events: PyroEvents,
+    /// Single-burn whatever the flight is: the first burnout is the one the
+    /// table means.
+    burns: BurnTracker,
+    burnout_us: Option<u64>,
+    apogee_us: Option<u64>,
+    /// When each row's condition was met, by row.
+    met_us: [Option<u64>; PYRO_CHANNELS],
+    /// When each channel fired, by [`PyroSelect::index`].
+    fired_us: [Option<u64>; PYRO_CHANNELS],
+}
+
+impl PyroSequencer {
+    pub fn new(events: PyroEvents) -> Self {
+        Self {
+            events,
+            burns: BurnTracker::new(None),
+            burnout_us: None,
+            apogee_us: None,
+            met_us: [None; PYRO_CHANNELS],
+            fired_us: [None; PYRO_CHANNELS],
+        }
+    }
+
+    /// Advance by one sample. `acc` is the raw accelerometer vector, `state`
+    /// the deployment half's state after this sample, and `primary` what the
+    /// deployment arbiter decided fires on it.
+    ///
+    /// With an empty table this is `primary`, less any repeat of a channel
+    /// that already fired — which the deployment half never sends.
+    pub fn update(
+        &mut self,
+        timestamp_us: u64,
+        acc: Option<Vector3<f32>>,
+        state: &RocketState,
+        primary: Option<PyroSelect>,
+    ) -> Option<PyroSelect> {
+        let launched = !matches!(state, RocketState::OnPad);
+        if let Some(BurnEdge::Burnout { .. }) = self.burns.update(timestamp_us, acc, launched) {
+            self.burnout_us.get_or_insert(timestamp_us);
+        }
+
+        let descending = matches!(
+            state,
+            RocketState::DrogueChute { .. } | RocketState::MainChute { .. } | RocketState::Landed
+        );
+        if self.apogee_us.is_none() && (descending || primary == Some(PyroSelect::PyroDrogue)) {
+            self.apogee_us = Some(timestamp_us);
+        }
+        let altitude_agl = match state {
+            RocketState::DrogueChute {
+                altitude_asl,
+                launch_pad_altitude_asl,
+                ..
+            }
+            | RocketState::MainChute {
+                altitude_asl,
+                launch_pad_altitude_asl,
+                ..
+            } => Some(altitude_asl - launch_pad_altitude_asl),
+            _ => None,
+        };
+        let burnout_us = self.burnout_us;
+
+        for (event, met_us) in self.events.iter().zip(self.met_us.iter_mut()) {
+            if met_us.is_some() {
+                continue;
+            }
+            *met_us = match event.trigger {
+                PyroTrigger::Apogee { .. } => self.apogee_us,
+                PyroTrigger::DescentAltitude {
+                    altitude_agl: at, ..
+                } => altitude_agl.filter(|agl| *agl <= at).map(|_| timestamp_us),
+                PyroTrigger::Burnout { .. } => burnout_us,
+                PyroTrigger::BackupOf { channel, .. } => self.fired_us[channel.index()],
+            };
+        }
+
+        let fired_us = &self.fired_us;
+        let fire = primary
+            .filter(|channel| fired_us[channel.index()].is_none())
+            .or_else(|| {
+                self.events
+                    .iter()
+                    .zip(&self.met_us)
+                    .find(|(event, met_us)| {
+                        fired_us[event.channel.index()].is_none()
+                            && met_us.is_some_and(|met_us| {
+                                timestamp_us.saturating_sub(met_us)
+                                    >= event.trigger.delay_us() as u64
+                            })
+                    })
+                    .map(|(event, _)| event.channel)
+            });
+        if let Some(channel) = fire {
+            if primary != Some(channel) {
+                log_info!("pyro event: {:?}", channel);
+            }
+            self.fired_us[channel.index()] = Some(timestamp_us);
+        }
+        fire
+    }
+
+    /// When `channel` fired, on the clock [`Self::update`] is handed.
+    pub fn fired_us(&self, channel: PyroSelect) -> Option<u64> {
+        self.fired_us[channel.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT_US: u64 = 2404;
+    const G: f32 = 9.81;
+
+    fn ascent() -> RocketState {
+        RocketState::MachLockout {
+            launch_pad_altitude_asl: 0.0,
+        }
+    }
+
+    fn descent(altitude_agl: f32) -> RocketState {
+        RocketState::DrogueChute {
+            deployed: true,
+            vertical_velocity: -30.0,
+            altitude_asl: altitude_agl,
+            launch_pad_altitude_asl: 0.0,
+        }
+    }
+
+    /// A flight as the sequencer sees it: 5 s on the pad, a 3 s burn, 20 s
+    /// of coast to apogee, then a drogue descent at 30 m/s from 3000 m. The
+    /// deployment half's drogue goes out 1 s after its apogee call. Returns
+    /// what fired and when (s after ignition).
+    fn sequence(events: &[PyroEvent]) -> Vec<(f32, PyroSelect)> {
+        let up = Vector3::new(0.2, 0.1, 0.97).normalize();
+        let mut sequencer = PyroSequencer::new(PyroEvents::from_slice(events).unwrap());
+        let mut fired = Vec::new();
+        let mut t_us = 0u64;
+        while t_us < 130_000_000 {
+            let t = t_us as f32 * 1e-6 - 5.0;
+            let (state, specific_force) = if t < 0.0 {
+                (RocketState::OnPad, G)
+            } else if t < 3.0 {
+                (ascent(), 60.0)
+            } else if t < 23.0 {
+                (ascent(), -4.0)
+            } else {
+                (descent(3000.0 - (t - 23.0) * 30.0), 0.0)
+            };
+            let primary = (24.0..24.0 + DT_US as f32 * 1e-6)
+                .contains(&t)
+                .then_some(PyroSelect::PyroDrogue);
+            if let Some(pyro) = sequencer.update(t_us, Some(up * specific_force), &state, primary) {
+                fired.push((t, pyro));
+            }
+            t_us += DT_US;
+        }
+        fired
+    }
+
+    #[test]
+    fn the_table_fires_each_channel_once_on_its_trigger() {
+        let fired = sequence(&[
+            PyroEvent {
+                channel: PyroSelect::PyroSeparation,
+                trigger: PyroTrigger::Burnout { delay_us: 500_000 },
+            },
+            PyroEvent {
+                channel: PyroSelect::PyroBackupDrogue,
+                trigger: PyroTrigger::BackupOf {
+                    channel: PyroSelect::PyroDrogue,
+                    delay_us: 2_000_000,
+                },
+            },
+            PyroEvent {
+                channel: PyroSelect::PyroMain,
+                trigger: PyroTrigger::DescentAltitude {
+                    altitude_agl: 450.0,
+                    delay_us: 0,
+                },
+            },
+            PyroEvent {
+                channel: PyroSelect::PyroBackupMain,
+                trigger: PyroTrigger::BackupOf {
+                    channel: PyroSelect::PyroMain,
+                    delay_us: 1_000_000,
+                },
+            },
+        ]);
+        let at = |channel| {
+            let times: Vec<f32> = fired
+                .iter()
+                .filter(|(_, c)| *c == channel)
+                .map(|(t, _)| *t)
+                .collect();
+            assert_eq!(times.len(), 1, "{channel:?} fired {times:?}");
+            times[0]
+        };
+
+        // Burnout is the 3 s tail-off, latched 0.3 s later, and the
+        // separation half a second after that.
+        assert!((at(PyroSelect::PyroSeparation) - 3.8).abs() < 0.02);
+        assert!((at(PyroSelect::PyroDrogue) - 24.0).abs() < 0.01);
+        assert!((at(PyroSelect::PyroBackupDrogue) - 26.0).abs() < 0.01);
+        // 3000 m down to 450 m at 30 m/s from 23 s.
+        assert!((at(PyroSelect::PyroMain) - 108.0).abs() < 0.01);
+        assert!((at(PyroSelect::PyroBackupMain) - 109.0).abs() < 0.01);
+        assert_eq!(fired.len(), 5);
+    }
+
+    /// A table drogue at apogee beats the deployment half's delayed one, and
+    /// that one, when it comes, fires nothing.
+    #[test]
+    fn a_channel_fired_by_the_table_swallows_the_primary() {
+        let fired = sequence(&[PyroEvent {
+            channel: PyroSelect::PyroDrogue,
+            trigger: PyroTrigger::Apogee { delay_us: 0 },
+        }]);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].1, PyroSelect::PyroDrogue);
+        assert!((fired[0].0 - 23.0).abs() < 0.01);
+    }
+}
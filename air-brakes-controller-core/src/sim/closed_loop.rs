@@ -0,0 +1,755 @@
+//! Closed loop: a flight integrated here rather than read off OpenRocket, so
+//! the brakes the MPC commands change the trajectory it is flying.
+//!
+//! Everything else in `sim` replays a trajectory that was decided before the
+//! estimators saw it — the brakes in a [`replay`](crate::sim::replay) are
+//! commanded into nothing. Closing the loop used to mean the OpenRocket
+//! plugin calling the WASM exports through Chicory; this is the same loop in
+//! Rust, on the same public API the firmware calls:
+//!
+//! * a [`Motor`] burns its RASP thrust curve, and loses its propellant in
+//!   proportion to the impulse delivered;
+//! * the [`Airframe`] flies as a point mass at zero angle of attack — the
+//!   nose always into the relative wind once off the rail — with its stowed
+//!   Cd against Mach, the brake table's extension curve on top, and the
+//!   canopies' drag areas after each pyro;
+//! * the sensors are read at the IMU's rate through
+//!   [`SensorModel::read`], the same chips [`synthesize`] models;
+//! * [`FlightEstimators`] runs on every read, the MPC every
+//!   [`CONTROL_PERIOD_US`], and the command reaches the flaps through the
+//!   [`ServoModel`] — dead time, slew and lag — with Icarus reporting the
+//!   extension back every [`ICARUS_PERIOD_US`].
+//!
+//! What comes out is the flight as the SD card would hold it: fast records
+//! at the sensor rate, slow records at the control rate and an event for
+//! every stage change and pyro, in [`SimulatedFlight::log`], ending on the
+//! sample the estimators call the landing. `rocket-cli
+//! simulate` merges and writes it exactly the way `download-flight-log`
+//! writes a real card, so `plot-flight-log` draws a simulated flight with
+//! nothing to tell it apart.
+//!
+//! What a point mass leaves out is everything a 6-DOF run would react with:
+//! there is no weathercocking transient, no coning and no roll damping, and
+//! the roll the gyro sees is [`roll_rate`]'s invention, as in
+//! [`synthesize`]. The atmosphere is the standard day, so the barometer's
+//! pressure altitude is the true altitude and every number scores directly;
+//! no air temperature is fed, so the estimators stay on it through launch.
+//!
+//! [`synthesize`]: crate::sim::sensors::synthesize
+
+use std::error::Error;
+
+use firmware_common_new::can_bus::messages::vl_status::FlightStage;
+use firmware_common_new::flight_data_record::{
+    self, AIRBRAKES_BURNOUT, AIRBRAKES_PAD_CALIBRATED, AirBrakesActuationRecord, AirBrakesRecord,
+    AirbrakesEstimatorRecord, BaroHealth, DEPLOYMENT_BARO_GATE_REJECT, DEPLOYMENT_BARO_RESYNC,
+    DeploymentEstimatorRecord, EstimatorInternalsRecord, FlightDataFastRecord,
+    FlightDataSlowRecord, FlightEvent, FlightEventRecord, ImuRecord, LogRecord, ParsedLogRecord,
+    PayloadRecord, pyro_continuity_flag, pyro_fire_flag,
+};
+use firmware_common_new::vlp::packets::fire_pyro::{PYRO_CHANNELS, PyroSelect};
+use nalgebra::{UnitQuaternion, Vector2, Vector3};
+
+use crate::atmosphere::Atmosphere;
+use crate::baro_state_estimator::RocketState;
+use crate::controller::{AirBrakesMPC, RocketParameters, ServoModel, ServoState};
+use crate::flight_estimators::{EstimatorLogSample, FlightConfig, FlightEstimators};
+use crate::sim::eng::Motor;
+use crate::sim::sensors::{Rng, SensorModel, roll_rate};
+use crate::utils::interpolate;
+
+/// The control loop's period: the firmware commands the brakes at 10 Hz, and
+/// the slow record is written on the same tick.
+pub const CONTROL_PERIOD_US: u64 = 100_000;
+/// Icarus measures the servo every cycle of its 100 Hz loop and reports each
+/// one.
+pub const ICARUS_PERIOD_US: u64 = 10_000;
+/// Icarus reports extension in tenths of a percent.
+const ICARUS_RESOLUTION: f32 = 1e-3;
+/// How long a canopy takes to reach its full drag area after its pyro
+/// (s). Invented: opening it in one step would put a 40 g spike on the
+/// accelerometer that no real canopy does.
+const CANOPY_INFLATION_S: f32 = 1.0;
+/// How long a pyro's fire bit reads set after it is commanded (s), and after
+/// which its continuity reads open. Invented, and only the log sees it.
+const PYRO_FIRE_S: f32 = 1.0;
+/// The flight goes on at most this long after touchdown, at rest on the
+/// ground. It normally ends sooner, on the sample the deployment estimator
+/// calls the landing — where the firmware closes its log; this is only for
+/// a detector that never does.
+const LANDED_S: f32 = 30.0;
+/// Below this airspeed (m/s) the nose direction is not defined by the wind,
+/// and the last one is held.
+const MIN_AXIS_AIRSPEED: f32 = 1.0;
+/// No flight here lasts an hour; one that does has lost its ground.
+const MAX_FLIGHT_S: f32 = 3600.0;
+
+const GRAVITY: f32 = 9.80665;
+
+/// The airframe as the closed loop flies it.
+#[derive(Debug, Clone)]
+pub struct Airframe {
+    /// Everything but the motor (kg).
+    pub dry_mass_kg: f32,
+    pub reference_area_m2: f32,
+    /// Stowed Cd against Mach, `(mach, cd)` strictly increasing in Mach, as
+    /// [`Self::load_stowed_cd`] reads it. Linear between the points and held
+    /// past either end.
+    pub stowed_cd: Vec<(f32, f32)>,
+    /// The brake table the MPC flies. Only its shape in extension is used
+    /// here: the brakes multiply the stowed Cd by the table's own ratio of
+    /// Cd at an extension to Cd stowed, at the same Mach. So the MPC's model
+    /// and this airframe agree on what the flaps do, and may disagree on the
+    /// body — which is the error a real airframe brings.
+    pub brakes: RocketParameters,
+    /// Drag area of the drogue canopy, fully open (m^2).
+    pub drogue_cda_m2: f32,
+    /// Drag area of the main canopy, fully open (m^2). The drogue stays out
+    /// once the main is.
+    pub main_cda_m2: f32,
+}
+
+impl Airframe {
+    /// A `mach,cd` CSV, one row per Mach.
+    pub fn load_stowed_cd(path: &str) -> Result<Vec<(f32, f32)>, Box<dyn Error + Send + Sync>> {
+        #[derive(serde::Deserialize)]
+        struct CsvRow {
+            mach: f32,
+            cd: f32,
+        }
+        let mut points = Vec::new();
+        for row in csv::Reader::from_path(path)?.deserialize::<CsvRow>() {
+            let row = row?;
+            if points.last().is_some_and(|&(mach, _)| row.mach <= mach) {
+                return Err(format!("{path}: Mach {} does not increase", row.mach).into());
+            }
+            points.push((row.mach, row.cd));
+        }
+        if points.is_empty() {
+            return Err(format!("{path}: no rows").into());
+        }
+        Ok(points)
+    }
+
+    /// Cd at `extension` (0.0 stowed - 1.0 full) and `mach`.
+    pub fn cd(&self, extension: f32, mach: f32) -> f32 {
+        let stowed = interpolate(&self.stowed_cd, mach);
+        if extension <= 0.0 {
+            return stowed;
+        }
+        stowed * self.brakes.cda_over_mass(extension, mach) / self.brakes.cda_over_mass(0.0, mach)
+    }
+}
+
+/// The pad and the day.
+#[derive(Debug, Clone, Copy)]
+pub struct Launch {
+    pub pad_altitude_asl: f32,
+    /// How far the airframe travels before it is free to turn (m).
+    pub rail_length_m: f32,
+    /// Rail angle from vertical (rad).
+    pub rail_tilt_rad: f32,
+    /// Direction the rail leans, counterclockwise from world X (east), like
+    /// `TruthRow::azimuth`.
+    pub rail_azimuth_rad: f32,
+    /// Wind (m/s) in `(east, north)`, the way it blows, the same at every
+    /// altitude.
+    pub wind_mps: Vector2<f32>,
+}
+
+/// One closed-loop flight, fully specified.
+#[derive(Clone)]
+pub struct ClosedLoop<'a> {
+    pub motor: &'a Motor,
+    pub airframe: &'a Airframe,
+    /// How the flaps really move. The MPC is told the same model, so a
+    /// mismatch between the two is made by changing this one.
+    pub servo: ServoModel,
+    pub launch: Launch,
+    pub config: FlightConfig,
+    /// MPC target apogee (m AGL), added to the pad altitude the deployment
+    /// half latched at ignition, as the firmware builds its MPC.
+    pub target_apogee_agl: f32,
+    /// The chips, the sample interval, the pad time before ignition and the
+    /// seed. `until_s` cuts the flight short.
+    pub sensors: SensorModel,
+}
+
+/// The true state behind one fast record.
+#[derive(Debug, Clone, Copy)]
+pub struct TrueState {
+    /// Seconds since ignition; negative on the pad.
+    pub t: f32,
+    pub altitude_asl: f32,
+    /// `(east, north, up)` ground velocity (m/s).
+    pub velocity: Vector3<f32>,
+    pub mach: f32,
+    /// The flaps' physical extension, 0.0 - 1.0.
+    pub extension: f32,
+}
+
+pub struct SimulatedFlight {
+    /// Fast, slow and event records in the order the logger would have
+    /// written them, every one from a good block.
+    pub log: Vec<ParsedLogRecord>,
+    /// One per fast record, in the same order.
+    pub truth: Vec<TrueState>,
+    pub pad_altitude_asl: f32,
+    /// True apogee (m AGL) and when (s since ignition).
+    pub apogee_agl: f32,
+    pub apogee_t: f32,
+    pub max_mach: f32,
+    /// `None` when `until_s` ended the flight in the air.
+    pub touchdown_t: Option<f32>,
+}
+
+impl SimulatedFlight {
+    pub fn fast_records(&self) -> impl Iterator<Item = &FlightDataFastRecord> {
+        self.log.iter().filter_map(|parsed| match &parsed.record {
+            LogRecord::Fast(fast) => Some(fast),
+            _ => None,
+        })
+    }
+
+    /// The estimator internals records, one every
+    /// [`INTERNALS_RECORD_INTERVAL`](flight_data_record::INTERNALS_RECORD_INTERVAL)
+    /// samples. Empty unless the sim was built with `debug-internals`.
+    pub fn internals(&self) -> impl Iterator<Item = &EstimatorInternalsRecord> {
+        self.log.iter().filter_map(|parsed| match &parsed.record {
+            LogRecord::Internals(internals) => Some(internals),
+            _ => None,
+        })
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &FlightEventRecord> {
+        self.log.iter().filter_map(|parsed| match &parsed.record {
+            LogRecord::Event(event) => Some(event),
+            _ => None,
+        })
+    }
+}
+
+/// Where the rocket is, and what is hanging off it.
+struct Body {
+    position: Vector3<f32>,
+    velocity: Vector3<f32>,
+    /// Nose direction, world frame.
+    axis: Vector3<f32>,
+    /// Distance travelled up the rail; free once past its length.
+    rail_travel: f32,
+    drogue_t: Option<f32>,
+    main_t: Option<f32>,
+    touchdown_t: Option<f32>,
+}
+
+/// The brake servo as it really moves: commands land after the dead time,
+/// in order, and the flaps follow the one in force.
+struct Servo {
+    model: ServoModel,
+    extension: f32,
+    in_force: f32,
+    /// `(flight time it lands, command)`, oldest first.
+    pending: Vec<(f32, f32)>,
+}
+
+impl Servo {
+    fn command(&mut self, t: f32, extension: f32) {
+        self.pending.push((t + self.model.dead_time_s, extension));
+    }
+
+    fn advance(&mut self, from: f32, to: f32) {
+        let follow = ServoModel {
+            dead_time_s: 0.0,
+            ..self.model
+        };
+        let mut t = from;
+        while let Some(&(lands, command)) = self.pending.first() {
+            if lands > to {
+                break;
+            }
+            if lands > t {
+                self.extension =
+                    follow.step(self.extension, self.in_force, self.in_force, lands - t);
+                t = lands;
+            }
+            self.in_force = command;
+            self.pending.remove(0);
+        }
+        self.extension = follow.step(self.extension, self.in_force, self.in_force, to - t);
+    }
+}
+
+impl ClosedLoop<'_> {
+    pub fn fly(&self) -> SimulatedFlight {
+        let model = &self.sensors;
+        let launch = &self.launch;
+        let mut rng = Rng(model.seed | 1);
+        let mut est = FlightEstimators::new(self.config.clone());
+        let mut mpc: Option<AirBrakesMPC> = None;
+
+        let rail_axis = Vector3::new(
+            launch.rail_tilt_rad.sin() * launch.rail_azimuth_rad.cos(),
+            launch.rail_tilt_rad.sin() * launch.rail_azimuth_rad.sin(),
+            launch.rail_tilt_rad.cos(),
+        );
+        let mut body = Body {
+            position: Vector3::new(0.0, 0.0, launch.pad_altitude_asl),
+            velocity: Vector3::zeros(),
+            axis: rail_axis,
+            rail_travel: 0.0,
+            drogue_t: None,
+            main_t: None,
+            touchdown_t: None,
+        };
+        let mut servo = Servo {
+            model: self.servo,
+            extension: 0.0,
+            in_force: 0.0,
+            pending: Vec::new(),
+        };
+        // The nose's attitude without roll, carried by the smallest rotation
+        // from one step's axis to the next, so that nothing but `roll_rate`
+        // ever turns the airframe about its own axis.
+        let mut q_axis = UnitQuaternion::rotation_between(&Vector3::z(), &rail_axis)
+            .unwrap_or_else(UnitQuaternion::identity);
+        let mut roll = 0.0f32;
+        let mut q = q_axis;
+
+        let mut out = SimulatedFlight {
+            log: Vec::new(),
+            truth: Vec::new(),
+            pad_altitude_asl: launch.pad_altitude_asl,
+            apogee_agl: 0.0,
+            apogee_t: 0.0,
+            max_mach: 0.0,
+            touchdown_t: None,
+        };
+        let mut sequence = 0u32;
+        let mut t_us = 0u64;
+        let mut t_prev = -model.pad_s;
+        let mut stage = FlightStage::Armed;
+        let mut commanded = 0.0f32;
+        let mut prediction: Option<f32> = None;
+        let mut reported: Option<f32> = None;
+        let mut next_control_us = 0u64;
+        let mut next_icarus_us = 0u64;
+        let mut fired: [Option<f32>; PYRO_CHANNELS] = [None; PYRO_CHANNELS];
+        // Drogue and main are always wired; anything else only if the
+        // profile's pyro-event table uses it.
+        let mut wired = [false; PYRO_CHANNELS];
+        wired[PyroSelect::PyroDrogue.index()] = true;
+        wired[PyroSelect::PyroMain.index()] = true;
+        for event in &self.config.profile.pyro_events {
+            wired[event.channel.index()] = true;
+        }
+
+        loop {
+            let t = (t_us as f32) * 1e-6 - model.pad_s;
+            let dt = t - t_prev;
+            if t > model.until_s
+                || t > MAX_FLIGHT_S
+                || body
+                    .touchdown_t
+                    .is_some_and(|touchdown| t > touchdown + LANDED_S)
+            {
+                break;
+            }
+
+            // --- the world, from the last sample to this one -------------
+            servo.advance(t_prev, t);
+            let acc = if dt > 0.0 {
+                self.step(&mut body, t_prev, dt, servo.extension)
+            } else {
+                Vector3::zeros()
+            };
+            if body.touchdown_t.is_none() && body.rail_travel > 0.0 {
+                let agl = body.position.z - launch.pad_altitude_asl;
+                if agl > out.apogee_agl {
+                    out.apogee_agl = agl;
+                    out.apogee_t = t;
+                }
+            }
+            roll += roll_rate(t, self.motor.burn_time()) * dt;
+            let turn = UnitQuaternion::rotation_between(&(q_axis * Vector3::z()), &body.axis)
+                .unwrap_or_else(UnitQuaternion::identity);
+            q_axis = turn * q_axis;
+            let q_next = q_axis * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), roll);
+            let w_body = if dt > 0.0 {
+                (q.inverse() * q_next).scaled_axis() / dt
+            } else {
+                Vector3::zeros()
+            };
+            q = q_next;
+
+            // --- the chips ------------------------------------------------
+            let air = air_at(body.position.z);
+            let airspeed = (body.velocity - wind3(launch.wind_mps)).norm();
+            let mach = airspeed / air.speed_of_sound;
+            out.max_mach = out.max_mach.max(mach);
+            let specific_force = acc + Vector3::new(0.0, 0.0, GRAVITY);
+            let port_pressure = model.port_pressure(air.pressure, air.density, airspeed, mach);
+            let reading = model.read(&mut rng, &q, &w_body, &specific_force, port_pressure);
+
+            // --- the flight computer -------------------------------------
+            est.update_mag(t_us, &reading.mag);
+            let (pyro, staging, log) = est.update(
+                t_us,
+                Some(&reading.imu),
+                &[Some(reading.baro_altitude_asl)],
+                // No receiver is modelled: every profile here is baro-only.
+                None,
+                Some(commanded),
+            );
+            if let Some(pyro) = pyro {
+                fired[pyro.index()].get_or_insert(t);
+                // A backup charge opens the same canopy as the one it backs
+                // up, so whichever goes first deploys it. Separation opens
+                // nothing this airframe models.
+                match pyro {
+                    PyroSelect::PyroDrogue | PyroSelect::PyroBackupDrogue => {
+                        body.drogue_t.get_or_insert(t);
+                    }
+                    PyroSelect::PyroMain | PyroSelect::PyroBackupMain => {
+                        body.main_t.get_or_insert(t);
+                    }
+                    PyroSelect::PyroSeparation => {}
+                }
+                out.log.push(event(
+                    t_us,
+                    FlightEvent::PyroCommanded {
+                        channel: pyro as u8,
+                        by_uplink: false,
+                    },
+                ));
+            }
+            // Logged and nothing more: this airframe flies one motor, and an
+            // igniter commanded here lights nothing.
+            if let Some(staging) = staging {
+                let (kind, burn) = staging.to_parts();
+                out.log
+                    .push(event(t_us, FlightEvent::Staging { kind, burn }));
+            }
+            let state = est.state();
+            if mpc.is_none() && !matches!(state, RocketState::OnPad) {
+                mpc = Some(
+                    AirBrakesMPC::new(
+                        self.config.airbrakes.rocket.clone(),
+                        est.atmosphere(),
+                        est.launch_pad_altitude_asl() + self.target_apogee_agl,
+                    )
+                    .with_servo(self.servo),
+                );
+            }
+            let next_stage = flight_stage(&state);
+            if next_stage != stage {
+                out.log.push(event(
+                    t_us,
+                    FlightEvent::StageChanged {
+                        from: stage,
+                        to: next_stage,
+                    },
+                ));
+                stage = next_stage;
+            }
+
+            if t_us >= next_icarus_us {
+                reported = Some((servo.extension / ICARUS_RESOLUTION).round() * ICARUS_RESOLUTION);
+                next_icarus_us += ICARUS_PERIOD_US;
+            }
+            let control_tick = t_us >= next_control_us;
+            if control_tick {
+                let solution = est
+                    .airbrakes_mpc_states()
+                    .zip(mpc.as_ref())
+                    .map(|(states, mpc)| {
+                        mpc.update_with_servo(
+                            &states,
+                            Some(ServoState {
+                                actual_extension: reported.unwrap_or(0.0),
+                                commanded_extension: commanded,
+                            }),
+                        )
+                    });
+                // Outside the window the brakes are stowed, and the MPC has
+                // nothing to predict.
+                commanded = solution.map_or(0.0, |s| s.extension_percentage);
+                prediction = solution.map(|s| s.predicted_apogee_asl);
+                servo.command(t, commanded);
+                next_control_us += CONTROL_PERIOD_US;
+            }
+
+            // --- the log --------------------------------------------------
+            let mut pyro_flags = 0u16;
+            for channel in PyroSelect::ALL.into_iter().filter(|c| wired[c.index()]) {
+                let continuity = pyro_continuity_flag(channel);
+                match fired[channel.index()] {
+                    None => pyro_flags |= continuity,
+                    Some(at) if t - at < PYRO_FIRE_S => {
+                        pyro_flags |= continuity | pyro_fire_flag(channel)
+                    }
+                    Some(_) => {}
+                }
+            }
+            // The firmware's `debug-internals` build writes these on the
+            // same decimation, just ahead of the fast record they belong to.
+            #[cfg(feature = "debug-internals")]
+            if sequence % flight_data_record::INTERNALS_RECORD_INTERVAL == 0 {
+                out.log.push(ParsedLogRecord::good(LogRecord::Internals(
+                    log.internals_record(t_us),
+                )));
+            }
+            out.log.push(ParsedLogRecord::good(LogRecord::Fast(
+                FlightDataFastRecord {
+                    sequence,
+                    timestamp_us: t_us,
+                    unix_time_us: None,
+                    imu: Some(ImuRecord {
+                        acc: reading.imu.acc.into(),
+                        gyro: (reading.imu.gyro * (180.0 / core::f32::consts::PI)).into(),
+                    }),
+                    pressure: reading.pressure,
+                    mag: Some((reading.mag * 1e6).into()),
+                    deployment: Some(deployment_record(&log)),
+                    airbrakes: airbrakes_record(&log),
+                    flight_stage: stage,
+                    pyro_flags: Some(pyro_flags),
+                    air_brakes: AirBrakesActuationRecord {
+                        commanded_extension: Some(commanded),
+                        actual_extension: reported,
+                        validation_deploy: false,
+                    },
+                },
+            )));
+            sequence += 1;
+            if control_tick {
+                out.log.push(ParsedLogRecord::good(LogRecord::Slow(
+                    FlightDataSlowRecord {
+                        timestamp_us: t_us,
+                        temperature: air.temperature - 273.15,
+                        battery_voltage: None,
+                        lat_lon: None,
+                        gps_altitude_asl: None,
+                        num_of_fix_satellites: 0,
+                        hdop: None,
+                        vdop: None,
+                        pdop: None,
+                        launch_pad_altitude_asl: Some(log.deployment_launch_pad_altitude_asl),
+                        air_brakes: AirBrakesRecord {
+                            predicted_apogee_asl: prediction,
+                            servo_temp: None,
+                            target_apogee_asl: mpc.as_ref().map(|mpc| mpc.target_apogee_asl()),
+                        },
+                        amp: None,
+                        payload: PayloadRecord {
+                            epm_batt_mv: None,
+                            rail_ma: [None; 6],
+                            actuator_steps: [None; 3],
+                            load_cell_cn: [None; 3],
+                            experiment_flags: 0,
+                        },
+                        amp_node: None,
+                        icarus_node: None,
+                        ozys_node: None,
+                        payload_sdrm_node: None,
+                    },
+                )));
+            }
+            out.truth.push(TrueState {
+                t,
+                altitude_asl: body.position.z,
+                velocity: body.velocity,
+                mach,
+                extension: servo.extension,
+            });
+            if stage == FlightStage::Landed {
+                break;
+            }
+
+            t_prev = t;
+            // The sensor task's rate, with the same jitter `synthesize` gives
+            // it.
+            t_us += model.sample_dt_us + (rng.uniform() * 120.0) as u64;
+        }
+
+        out.touchdown_t = body.touchdown_t;
+        out
+    }
+
+    /// Move `body` on by `dt` from flight time `t`, and return the
+    /// kinematic acceleration it ended the step with (m/s^2, world frame).
+    fn step(&self, body: &mut Body, t: f32, dt: f32, extension: f32) -> Vector3<f32> {
+        let pad = self.launch.pad_altitude_asl;
+        if body.touchdown_t.is_some() {
+            return Vector3::zeros();
+        }
+
+        if body.rail_travel < self.launch.rail_length_m {
+            // On the rail: one degree of freedom, along it, and held down by
+            // the rail until the motor out-pulls gravity.
+            let axis = body.axis;
+            let speed = body.velocity.dot(&axis);
+            let along = |t: f32, speed: f32, altitude: f32| {
+                let air = air_at(altitude);
+                let mach = speed / air.speed_of_sound;
+                let drag = 0.5
+                    * air.density
+                    * speed
+                    * speed
+                    * self.airframe.cd(0.0, mach)
+                    * self.airframe.reference_area_m2;
+                (self.motor.thrust_at(t) - drag) / self.mass(t) - GRAVITY * axis.z
+            };
+            let a0 = along(t, speed, body.position.z);
+            let mid_speed = speed + a0 * dt / 2.0;
+            let a = along(
+                t + dt / 2.0,
+                mid_speed,
+                body.position.z + mid_speed * axis.z * dt / 2.0,
+            );
+            if speed <= 0.0 && a <= 0.0 {
+                body.velocity = Vector3::zeros();
+                return Vector3::zeros();
+            }
+            let speed = (speed + a * dt).max(0.0);
+            body.rail_travel += mid_speed * dt;
+            body.velocity = axis * speed;
+            body.position += axis * mid_speed * dt;
+            return axis * a;
+        }
+
+        // Free flight: midpoint.
+        let a0 = self.acceleration(body, t, &body.position, &body.velocity, extension);
+        let mid_velocity = body.velocity + a0 * (dt / 2.0);
+        let mid_position = body.position + body.velocity * (dt / 2.0);
+        let a = self.acceleration(body, t + dt / 2.0, &mid_position, &mid_velocity, extension);
+        body.position += mid_velocity * dt;
+        body.velocity += a * dt;
+
+        let airspeed = body.velocity - wind3(self.launch.wind_mps);
+        if airspeed.norm() > MIN_AXIS_AIRSPEED {
+            body.axis = airspeed.normalize();
+        }
+        if body.position.z <= pad && body.velocity.z < 0.0 {
+            body.position.z = pad;
+            body.velocity = Vector3::zeros();
+            body.touchdown_t = Some(t + dt);
+            return Vector3::zeros();
+        }
+        a
+    }
+
+    fn acceleration(
+        &self,
+        body: &Body,
+        t: f32,
+        position: &Vector3<f32>,
+        velocity: &Vector3<f32>,
+        extension: f32,
+    ) -> Vector3<f32> {
+        let air = air_at(position.z);
+        let airspeed = velocity - wind3(self.launch.wind_mps);
+        let speed = airspeed.norm();
+        let axis = if speed > MIN_AXIS_AIRSPEED {
+            airspeed / speed
+        } else {
+            body.axis
+        };
+        let mach = speed / air.speed_of_sound;
+        let canopy = |deployed: Option<f32>, cda: f32| {
+            deployed.map_or(0.0, |at| {
+                cda * ((t - at) / CANOPY_INFLATION_S).clamp(0.0, 1.0)
+            })
+        };
+        let cda = self.airframe.cd(extension, mach) * self.airframe.reference_area_m2
+            + canopy(body.drogue_t, self.airframe.drogue_cda_m2)
+            + canopy(body.main_t, self.airframe.main_cda_m2);
+        let drag = -airspeed * (0.5 * air.density * speed * cda);
+        (axis * self.motor.thrust_at(t) + drag) / self.mass(t) - Vector3::new(0.0, 0.0, GRAVITY)
+    }
+
+    fn mass(&self, t: f32) -> f32 {
+        self.airframe.dry_mass_kg + self.motor.mass_at(t)
+    }
+}
+
+fn wind3(wind: Vector2<f32>) -> Vector3<f32> {
+    Vector3::new(wind.x, wind.y, 0.0)
+}
+
+/// The firmware's mirror of the deployment estimator's state, with its Mach
+/// lockout folded into `Ascent` (see `FlightDataFastRecord::flight_stage`).
+pub fn flight_stage(state: &RocketState) -> FlightStage {
+    match state {
+        RocketState::OnPad => FlightStage::Armed,
+        RocketState::Ascent { .. } | RocketState::MachLockout { .. } => FlightStage::Ascent,
+        RocketState::DrogueChute { .. } => FlightStage::DrogueChute,
+        RocketState::MainChute { .. } => FlightStage::MainChute,
+        RocketState::Landed => FlightStage::Landed,
+        RocketState::FailedToReachMinApogee => FlightStage::FailedToReachMinApogee,
+    }
+}
+
+fn deployment_record(log: &EstimatorLogSample) -> DeploymentEstimatorRecord {
+    let gate = log.deployment_baro_gate;
+    DeploymentEstimatorRecord {
+        kf_altitude_asl: log.deployment_altitude_asl,
+        kf_vertical_velocity: log.deployment_vertical_velocity,
+        flags: if gate.rejected() {
+            DEPLOYMENT_BARO_GATE_REJECT
+        } else {
+            0
+        } | if gate.resynced() {
+            DEPLOYMENT_BARO_RESYNC
+        } else {
+            0
+        } | log.deployment_gps_fusion.to_flags(),
+        baro_health: BaroHealth::pack(&log.deployment_baro_health),
+    }
+}
+
+fn airbrakes_record(log: &EstimatorLogSample) -> Option<AirbrakesEstimatorRecord> {
+    log.airbrakes.map(|ab| AirbrakesEstimatorRecord {
+        kf_altitude_asl: ab.altitude_asl,
+        kf_vertical_velocity: ab.vertical_velocity,
+        kf_tilt_deg: ab.tilt_rad.map(f32::to_degrees),
+        flags: ab.state.to_flags()
+            | if ab.burnout_detected {
+                AIRBRAKES_BURNOUT
+            } else {
+                0
+            }
+            | if ab.calibration_complete {
+                AIRBRAKES_PAD_CALIBRATED
+            } else {
+                0
+            },
+    })
+}
+
+fn event(timestamp_us: u64, event: FlightEvent) -> ParsedLogRecord {
+    ParsedLogRecord::good(LogRecord::Event(FlightEventRecord {
+        timestamp_us,
+        event,
+    }))
+}
+
+/// The day the simulator flies: the standard one, from the same
+/// [`Atmosphere`] the estimators and the MPC read.
+const ATMOSPHERE: Atmosphere = Atmosphere::standard();
+
+/// Air at a geometric altitude.
+struct Air {
+    pressure: f32,
+    density: f32,
+    temperature: f32,
+    speed_of_sound: f32,
+}
+
+fn air_at(altitude_asl: f32) -> Air {
+    Air {
+        pressure: ATMOSPHERE.pressure(altitude_asl),
+        density: ATMOSPHERE.air_density(altitude_asl),
+        temperature: ATMOSPHERE.temperature(altitude_asl),
+        speed_of_sound: ATMOSPHERE.speed_of_sound(altitude_asl),
+    }
+}
@@ -0,0 +1,358 @@
+//! Dispersion: one OpenRocket flight re-flown many times with everything the
+//! config cannot know in advance drawn at random, and the estimators scored
+//! on each.
+//!
+//! A single simulated flight says a config works on the flight OpenRocket
+//! predicted. What is worth knowing before a change flies is how it does on
+//! the flights that actually happen: a motor a few percent hot, a Cd table a
+//! few percent off, a windier day, a noisier board mounted some other way.
+//! [`DispersionRun`] flies [`Dispersion`]'s spread of those and [`Summary`]
+//! says how the apogee and the deployments came out across them, including
+//! how often the deployment half called ignition on the pad or apogee on the
+//! way up.
+//!
+//! Every flight is still open loop: the brakes are commanded but stay stowed
+//! on the trajectory, because the trajectory is OpenRocket's, rescaled (see
+//! [`Truth::dispersed`]). What is scored is what the estimators made of it.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+
+use crate::baro_state_estimator::DeploymentProfile;
+use crate::flight_estimators::FlightConfig;
+use crate::sim::replay::replay;
+use crate::sim::sensors::{Rng, SensorModel, synthesize};
+use crate::sim::truth::{TrajectoryDispersion, Truth};
+
+/// An apogee call this far ahead of the true apogee (s) is a false one. The
+/// same bound `nominal_o3400_flight` holds the deployment half to.
+pub const FALSE_APOGEE_LEAD_S: f32 = 1.0;
+
+/// Seconds of flight kept past the true main-altitude crossing, so a late
+/// main still lands in the replay. The descent after it is not scored and
+/// is minutes long, so it is not synthesised.
+const AFTER_MAIN_S: f32 = 20.0;
+
+/// The spread each flight's unknowns are drawn from. Every field is one
+/// standard deviation of a normal draw; a zero turns that one off.
+///
+/// The defaults are a starting point rather than a measurement — they are
+/// the size of spread worth a config surviving, and each is meant to be
+/// replaced by the real number once there is one: the motor's
+/// reload-to-reload spread, the Cd table's disagreement with flight, the
+/// range's forecast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dispersion {
+    /// Of the thrust scale, as a fraction: 0.03 is +-3 % thrust.
+    pub thrust_sigma: f32,
+    /// Of the drag-area scale, as a fraction.
+    pub cd_sigma: f32,
+    /// Of the wind added along the drift (m/s).
+    pub wind_sigma_mps: f32,
+    /// Of the log of the factor every sensor's noise is scaled by, so 0.3
+    /// puts two thirds of the boards between 0.74x and 1.35x as noisy as
+    /// the characterised one.
+    pub noise_sigma: f32,
+    /// Of each axis of the constant gyro bias (rad/s). Replaces
+    /// [`SensorModel::gyro_bias_rad_s`] rather than adding to it.
+    pub gyro_bias_sigma_rad_s: f32,
+    /// Mount the IMU at a uniformly random orientation on every flight,
+    /// instead of [`SensorModel::mount`]'s fixed one. The pad calibration
+    /// claims to find any mounting; this holds it to that.
+    pub random_mounting: bool,
+}
+
+impl Default for Dispersion {
+    fn default() -> Self {
+        Self {
+            thrust_sigma: 0.03,
+            cd_sigma: 0.05,
+            wind_sigma_mps: 3.0,
+            noise_sigma: 0.3,
+            gyro_bias_sigma_rad_s: 1.0f32.to_radians(),
+            random_mounting: true,
+        }
+    }
+}
+
+/// One flight's draw from a [`Dispersion`].
+#[derive(Debug, Clone)]
+pub struct DrawnFlight {
+    pub trajectory: TrajectoryDispersion,
+    pub noise_scale: f32,
+    pub sensors: SensorModel,
+}
+
+impl Dispersion {
+    /// The flight `seed` draws. The same seed always draws the same flight,
+    /// sensor noise included, so any flight in a run's CSV can be flown
+    /// again on its own.
+    pub fn draw(&self, seed: u64) -> DrawnFlight {
+        let mut rng = Rng(seed | 1);
+        let trajectory = TrajectoryDispersion {
+            thrust_scale: 1.0 + rng.normal() * self.thrust_sigma,
+            cd_scale: 1.0 + rng.normal() * self.cd_sigma,
+            extra_wind_mps: rng.normal() * self.wind_sigma_mps,
+            airstart: None,
+        };
+        let noise_scale = (rng.normal() * self.noise_sigma).exp();
+        let gyro_bias_rad_s =
+            Vector3::new(rng.normal(), rng.normal(), rng.normal()) * self.gyro_bias_sigma_rad_s;
+        let defaults = SensorModel::default();
+        let mount = if self.random_mounting {
+            // Four independent normals, normalised, are a uniformly random
+            // rotation.
+            UnitQuaternion::from_quaternion(Quaternion::new(
+                rng.normal(),
+                rng.normal(),
+                rng.normal(),
+                rng.normal(),
+            ))
+        } else {
+            defaults.mount
+        };
+        let sensors = SensorModel {
+            accel_noise: defaults.accel_noise * noise_scale,
+            gyro_noise_rad_s: defaults.gyro_noise_rad_s * noise_scale,
+            gyro_bias_rad_s,
+            pressure_noise_pa: defaults.pressure_noise_pa * noise_scale,
+            mag_noise_t: defaults.mag_noise_t * noise_scale,
+            mount,
+            seed: rng.next_u64(),
+            ..defaults
+        };
+        DrawnFlight {
+            trajectory,
+            noise_scale,
+            sensors,
+        }
+    }
+}
+
+/// How one dispersed flight came out. One row of a run's CSV, so every
+/// field is a number or empty.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FlightOutcome {
+    /// Index in the run, from 0.
+    pub run: usize,
+    /// [`Dispersion::draw`]'s seed for this flight.
+    pub seed: u64,
+    pub thrust_scale: f32,
+    pub cd_scale: f32,
+    pub extra_wind_mps: f32,
+    pub noise_scale: f32,
+    /// The dispersed flight's own apogee, pressure altitude AGL (m).
+    pub true_apogee_agl: f32,
+    /// The airbrakes filter's apogee less the true one (m); empty if it
+    /// never reported an altitude.
+    pub apogee_error: Option<f32>,
+    /// When the airbrakes filter peaked, less when the rocket did (s).
+    pub apogee_time_error_s: Option<f32>,
+    /// The deployment half's apogee call, less the true apogee time (s).
+    pub apogee_call_delay_s: Option<f32>,
+    /// True altitude when the drogue fired, less the true apogee (m):
+    /// how far below apogee it came out.
+    pub drogue_altitude_error: Option<f32>,
+    /// True altitude AGL when the main fired, less the configured main
+    /// altitude (m). Empty for a single-deployment profile.
+    pub main_altitude_error: Option<f32>,
+    /// The deployment half called ignition while still on the pad.
+    pub false_ignition: bool,
+    /// It called apogee more than [`FALSE_APOGEE_LEAD_S`] early.
+    pub false_apogee: bool,
+}
+
+/// Everything a run's flights share: the flight to disperse, the config
+/// under test, and what to draw.
+#[derive(Clone, Copy)]
+pub struct DispersionRun<'a> {
+    pub truth: &'a Truth,
+    pub config: &'a FlightConfig,
+    /// The MPC's target, pressure altitude ASL (m).
+    pub target_apogee_asl: f32,
+    pub dispersion: &'a Dispersion,
+    /// Flight `i` is drawn from a seed that depends only on this and `i`,
+    /// so a run is the same however many threads fly it.
+    pub seed: u64,
+}
+
+impl DispersionRun<'_> {
+    /// Fly flights `0..flights` across `threads` threads, calling
+    /// `on_flown` as each finishes, and return them in order.
+    pub fn run(
+        &self,
+        flights: usize,
+        threads: usize,
+        on_flown: impl Fn(&FlightOutcome) + Sync,
+    ) -> Vec<FlightOutcome> {
+        let next = AtomicUsize::new(0);
+        let outcomes = Mutex::new(Vec::with_capacity(flights));
+        std::thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= flights {
+                            break;
+                        }
+                        let outcome = self.fly(i);
+                        on_flown(&outcome);
+                        outcomes.lock().unwrap().push(outcome);
+                    }
+                });
+            }
+        });
+        let mut outcomes = outcomes.into_inner().unwrap();
+        outcomes.sort_by_key(|o| o.run);
+        outcomes
+    }
+
+    /// Fly flight `run` alone.
+    pub fn fly(&self, run: usize) -> FlightOutcome {
+        let seed = self.flight_seed(run);
+        let drawn = self.dispersion.draw(seed);
+        let flown = self.truth.dispersed(&drawn.trajectory);
+        let (apogee_t, apogee_asl) = flown.apogee();
+        let pad_asl = flown.pad_asl();
+        let true_apogee_agl = apogee_asl - pad_asl;
+
+        let main_agl = match self.config.profile.deployment {
+            DeploymentProfile::Dual {
+                main_chute_altitude_agl,
+                ..
+            } if main_chute_altitude_agl < true_apogee_agl => Some(main_chute_altitude_agl),
+            _ => None,
+        };
+        let until_s = main_agl.map_or(flown.last_t(), |agl| {
+            flown.descent_crossing_agl(agl) + AFTER_MAIN_S
+        });
+        let samples = synthesize(
+            &flown,
+            &SensorModel {
+                until_s,
+                ..drawn.sensors
+            },
+        );
+        let r = replay(&samples, self.config.clone(), self.target_apogee_asl);
+
+        let agl_at = |t: f32| flown.at(t).altitude_asl - pad_asl;
+        let fired = |name: &str| r.pyros.iter().find(|(_, p)| *p == name).map(|(t, _)| *t);
+        let estimated = r.estimated_apogee();
+        FlightOutcome {
+            run,
+            seed,
+            thrust_scale: drawn.trajectory.thrust_scale,
+            cd_scale: drawn.trajectory.cd_scale,
+            extra_wind_mps: drawn.trajectory.extra_wind_mps,
+            noise_scale: drawn.noise_scale,
+            true_apogee_agl,
+            apogee_error: estimated.map(|(_, alt)| alt - apogee_asl),
+            apogee_time_error_s: estimated.map(|(t, _)| t - apogee_t),
+            apogee_call_delay_s: r.deployment_apogee_t.map(|t| t - apogee_t),
+            drogue_altitude_error: fired("drogue").map(|t| agl_at(t) - true_apogee_agl),
+            main_altitude_error: main_agl.zip(fired("main")).map(|(agl, t)| agl_at(t) - agl),
+            false_ignition: r.ignition_t.is_some_and(|t| t < 0.0),
+            false_apogee: r
+                .deployment_apogee_t
+                .is_some_and(|t| t < apogee_t - FALSE_APOGEE_LEAD_S),
+        }
+    }
+
+    /// Flight `i`'s seed: SplitMix64's increment, so neighbouring flights'
+    /// xorshift streams start far apart.
+    fn flight_seed(&self, i: usize) -> u64 {
+        self.seed ^ (i as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+}
+
+/// Where a set of numbers fell.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub mean: f32,
+    pub std_dev: f32,
+    pub min: f32,
+    pub p05: f32,
+    pub p50: f32,
+    pub p95: f32,
+    pub max: f32,
+}
+
+impl Distribution {
+    /// `None` for no values.
+    pub fn of(values: impl Iterator<Item = f32>) -> Option<Self> {
+        let mut values: Vec<f32> = values.collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f32::total_cmp);
+        let n = values.len() as f32;
+        let mean = values.iter().sum::<f32>() / n;
+        let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / n;
+        // Linear between closest ranks.
+        let quantile = |q: f32| {
+            let x = q * (n - 1.0);
+            let (lo, hi) = (x.floor() as usize, x.ceil() as usize);
+            values[lo] + (values[hi] - values[lo]) * (x - lo as f32)
+        };
+        Some(Self {
+            count: values.len(),
+            mean,
+            std_dev: variance.sqrt(),
+            min: values[0],
+            p05: quantile(0.05),
+            p50: quantile(0.5),
+            p95: quantile(0.95),
+            max: values[values.len() - 1],
+        })
+    }
+}
+
+/// A run, summarised: each [`FlightOutcome`] column's distribution over
+/// the flights that have it, and the false-call counts.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub flights: usize,
+    pub apogee_error: Option<Distribution>,
+    pub apogee_time_error_s: Option<Distribution>,
+    pub apogee_call_delay_s: Option<Distribution>,
+    pub drogue_altitude_error: Option<Distribution>,
+    pub main_altitude_error: Option<Distribution>,
+    pub false_ignitions: usize,
+    pub false_apogees: usize,
+    /// Flights whose drogue never fired.
+    pub no_drogue: usize,
+}
+
+impl Summary {
+    pub fn of(outcomes: &[FlightOutcome]) -> Self {
+        let column =
+            |f: fn(&FlightOutcome) -> Option<f32>| Distribution::of(outcomes.iter().filter_map(f));
+        Self {
+            flights: outcomes.len(),
+            apogee_error: column(|o| o.apogee_error),
+            apogee_time_error_s: column(|o| o.apogee_time_error_s),
+            apogee_call_delay_s: column(|o| o.apogee_call_delay_s),
+            drogue_altitude_error: column(|o| o.drogue_altitude_error),
+            main_altitude_error: column(|o| o.main_altitude_error),
+            false_ignitions: outcomes.iter().filter(|o| o.false_ignition).count(),
+            false_apogees: outcomes.iter().filter(|o| o.false_apogee).count(),
+            no_drogue: outcomes
+                .iter()
+                .filter(|o| o.drogue_altitude_error.is_none())
+                .count(),
+        }
+    }
+
+    /// Fraction of the flights that called ignition on the pad.
+    pub fn false_ignition_rate(&self) -> f32 {
+        self.false_ignitions as f32 / self.flights.max(1) as f32
+    }
+
+    /// Fraction of the flights that called apogee early.
+    pub fn false_apogee_rate(&self) -> f32 {
+        self.false_apogees as f32 / self.flights.max(1) as f32
+    }
+}
@@ -0,0 +1,239 @@
+//! Motors: a RASP `.eng` thrust curve, which is what OpenRocket, RockSim
+//! and thrustcurve.org all exchange, and what a [`closed_loop`] flight burns.
+//!
+//! The format is a header line — `name diameter_mm length_mm delays
+//! propellant_kg total_kg maker` — then one `time_s thrust_n` pair per
+//! line, ending on zero thrust. `;` starts a comment line. The curve starts
+//! at zero thrust at t = 0 whether or not the file says so.
+//!
+//! [`closed_loop`]: crate::sim::closed_loop
+
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct Motor {
+    pub name: String,
+    pub maker: String,
+    pub diameter_m: f32,
+    pub length_m: f32,
+    /// Propellant mass (kg): what the motor weighs less at burnout.
+    pub propellant_kg: f32,
+    /// Loaded mass (kg), hardware and propellant.
+    pub total_kg: f32,
+    /// `(time_s, thrust_n)`, strictly increasing in time, starting at
+    /// `(0, 0)` and ending on zero thrust.
+    pub thrust: Vec<(f32, f32)>,
+    /// Impulse delivered by each point of `thrust` (N s), for the mass.
+    impulse: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngError {
+    /// No header line before the data, or nothing at all.
+    MissingHeader,
+    /// The header did not have its seven fields, or one did not parse.
+    BadHeader(String),
+    /// A data line that is not two numbers. 1-based line number.
+    BadPoint { line: usize, text: String },
+    /// Time went backwards or stood still. 1-based line number.
+    NotIncreasing { line: usize },
+    /// Fewer than two thrust points, or no impulse at all.
+    NoThrust,
+}
+
+impl fmt::Display for EngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "no motor header line"),
+            Self::BadHeader(header) => write!(
+                f,
+                "bad motor header {header:?}: expected `name diameter length delays \
+                 propellant_kg total_kg maker`"
+            ),
+            Self::BadPoint { line, text } => {
+                write!(f, "line {line}: {text:?} is not a `time thrust` pair")
+            }
+            Self::NotIncreasing { line } => write!(f, "line {line}: time does not increase"),
+            Self::NoThrust => write!(f, "the curve delivers no impulse"),
+        }
+    }
+}
+
+impl std::error::Error for EngError {}
+
+impl Motor {
+    pub fn parse(text: &str) -> Result<Self, EngError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with(';'));
+
+        let (_, header) = lines.next().ok_or(EngError::MissingHeader)?;
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let bad_header = || EngError::BadHeader(header.to_string());
+        if fields.len() < 7 {
+            return Err(bad_header());
+        }
+        let number = |field: &str| field.parse::<f32>().map_err(|_| bad_header());
+        let diameter_m = number(fields[1])? * 1e-3;
+        let length_m = number(fields[2])? * 1e-3;
+        let propellant_kg = number(fields[4])?;
+        let total_kg = number(fields[5])?;
+        if !(propellant_kg >= 0.0 && total_kg >= propellant_kg) {
+            return Err(bad_header());
+        }
+
+        let mut thrust = vec![(0.0f32, 0.0f32)];
+        for (line, text) in lines {
+            let bad_point = || EngError::BadPoint {
+                line,
+                text: text.to_string(),
+            };
+            let mut pair = text.split_whitespace().map(str::parse::<f32>);
+            let (Some(Ok(t)), Some(Ok(f)), None) = (pair.next(), pair.next(), pair.next()) else {
+                return Err(bad_point());
+            };
+            let (last_t, _) = thrust[thrust.len() - 1];
+            if t == 0.0 && thrust.len() == 1 {
+                // An explicit first point at ignition replaces the implied one.
+                thrust[0] = (0.0, f);
+                continue;
+            }
+            if t <= last_t {
+                return Err(EngError::NotIncreasing { line });
+            }
+            thrust.push((t, f.max(0.0)));
+        }
+        if thrust.len() < 2 {
+            return Err(EngError::NoThrust);
+        }
+        let (end_t, end_f) = thrust[thrust.len() - 1];
+        if end_f > 0.0 {
+            // A curve cut off mid-burn: close it the instant after.
+            thrust.push((end_t + 1e-3, 0.0));
+        }
+
+        let mut impulse = Vec::with_capacity(thrust.len());
+        let mut total = 0.0;
+        impulse.push(0.0);
+        for pair in thrust.windows(2) {
+            let ((t0, f0), (t1, f1)) = (pair[0], pair[1]);
+            total += 0.5 * (f0 + f1) * (t1 - t0);
+            impulse.push(total);
+        }
+        if total <= 0.0 {
+            return Err(EngError::NoThrust);
+        }
+
+        Ok(Self {
+            name: fields[0].to_string(),
+            maker: fields[6..].join(" "),
+            diameter_m,
+            length_m,
+            propellant_kg,
+            total_kg,
+            thrust,
+            impulse,
+        })
+    }
+
+    /// Read and [`Self::parse`] a `.eng` file.
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        Ok(Self::parse(&text).map_err(|e| format!("{path}: {e}"))?)
+    }
+
+    /// This motor with another thrust curve — a measured one, say — and the
+    /// same hardware and propellant. `thrust` is read as a `.eng` file's
+    /// points are, so it gets the same checks and the same closing zero.
+    pub fn with_thrust(&self, thrust: &[(f32, f32)]) -> Result<Self, EngError> {
+        let mut text = self.eng_header();
+        for &(t, f) in thrust {
+            text.push_str(&format!("{t} {f}\n"));
+        }
+        Self::parse(&text)
+    }
+
+    /// The motor as a `.eng` file [`Self::parse`] reads back. The file does
+    /// not keep the delays, so the header says `P`, plugged.
+    pub fn to_eng(&self) -> String {
+        let mut text = self.eng_header();
+        for &(t, f) in &self.thrust {
+            if t == 0.0 && f == 0.0 {
+                // The implied first point.
+                continue;
+            }
+            text.push_str(&format!("{t:.4} {f:.1}\n"));
+        }
+        text
+    }
+
+    fn eng_header(&self) -> String {
+        format!(
+            "{} {} {} P {} {} {}\n",
+            self.name,
+            self.diameter_m * 1e3,
+            self.length_m * 1e3,
+            self.propellant_kg,
+            self.total_kg,
+            self.maker
+        )
+    }
+
+    /// Thrust (N) `t` seconds after ignition, linear between the points and
+    /// zero outside the burn.
+    pub fn thrust_at(&self, t: f32) -> f32 {
+        match self.segment(t) {
+            Some((i, frac)) => {
+                let (_, f0) = self.thrust[i];
+                let (_, f1) = self.thrust[i + 1];
+                f0 + (f1 - f0) * frac
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Loaded mass (kg) `t` seconds after ignition. Propellant burns off in
+    /// proportion to the impulse delivered, which is what a constant
+    /// exhaust velocity means and what OpenRocket assumes.
+    pub fn mass_at(&self, t: f32) -> f32 {
+        self.total_kg - self.propellant_kg * self.impulse_at(t) / self.total_impulse()
+    }
+
+    /// Time of the last thrust point, where the curve returns to zero (s).
+    pub fn burn_time(&self) -> f32 {
+        self.thrust[self.thrust.len() - 1].0
+    }
+
+    pub fn total_impulse(&self) -> f32 {
+        self.impulse[self.impulse.len() - 1]
+    }
+
+    fn impulse_at(&self, t: f32) -> f32 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+        match self.segment(t) {
+            Some((i, frac)) => {
+                let (t0, f0) = self.thrust[i];
+                let (t1, f1) = self.thrust[i + 1];
+                let f = f0 + (f1 - f0) * frac;
+                self.impulse[i] + 0.5 * (f0 + f) * (t1 - t0) * frac
+            }
+            None => self.total_impulse(),
+        }
+    }
+
+    /// The segment `t` falls in and how far along it, `None` outside the
+    /// burn.
+    fn segment(&self, t: f32) -> Option<(usize, f32)> {
+        if t < 0.0 || t >= self.burn_time() {
+            return None;
+        }
+        let i = self.thrust.partition_point(|&(pt, _)| pt <= t) - 1;
+        let (t0, _) = self.thrust[i];
+        let (t1, _) = self.thrust[i + 1];
+        Some((i, (t - t0) / (t1 - t0)))
+    }
+}
@@ -0,0 +1,35 @@
+//! Host-side flight simulation: OpenRocket trajectories turned into the
+//! sensor stream the board would have read, replayed through
+//! [`FlightEstimators`](crate::FlightEstimators) and the MPC, and scored
+//! against the trajectory they came from.
+//!
+//! This began as the body of `tests::osiris_sim` and is still what every
+//! test there flies. It is a module of its own so the same machinery can
+//! run outside `cargo test`: [`dispersion`] flies one trajectory thousands
+//! of times with the motor, the airframe, the wind and the sensors
+//! randomised, which is how a config change is judged before it flies.
+//! `rocket-cli dispersion` is the front end.
+//!
+//! [`closed_loop`] is the other direction: a flight integrated here from a
+//! [`Motor`] and an [`Airframe`] instead of read off OpenRocket, so the
+//! brakes the MPC commands change the flight it is flying. It writes the
+//! SD card's records, and `rocket-cli simulate` turns them into a flight
+//! log.
+//!
+//! Behind the `sim` feature, which needs `std`. Nothing here runs on the
+//! board.
+
+pub mod closed_loop;
+pub mod dispersion;
+pub mod eng;
+pub mod osiris;
+pub mod replay;
+pub mod sensors;
+pub mod truth;
+
+pub use closed_loop::{Airframe, ClosedLoop, Launch, SimulatedFlight, TrueState};
+pub use dispersion::{Dispersion, DispersionRun, Distribution, FlightOutcome, Summary};
+pub use eng::{EngError, Motor};
+pub use replay::{Replay, replay};
+pub use sensors::{SensorModel, synthesize};
+pub use truth::{Airstart, TrajectoryDispersion, Truth, TruthRow};
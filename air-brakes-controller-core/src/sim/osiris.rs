@@ -0,0 +1,181 @@
+//! Osiris (CTI O3400, CTI N2900 as the backup motor): the airframe and the
+//! flight config it flies, and the OpenRocket simulations in
+//! `2026_06_26 - Osiris LC FDR.ork` that [`Truth::load`] reads.
+//!
+//! Everything here is what `tests::osiris_sim` checks against those
+//! simulations, so a dispersion run built on it starts from a config that is
+//! already known to fly the nominal flight.
+
+use nalgebra::Vector2;
+
+use crate::airbrakes_estimator::{AirbrakesConfig, MachLockoutConfig};
+use crate::backup_deployment::{BackupDeploymentConfig, DeploymentPolicy};
+use crate::baro_state_estimator::{DeploymentProfile, FlightProfile, LandingDetection};
+use crate::controller::{CD_MACH_POINTS, ControllerMode, RocketParameters};
+use crate::flight_estimators::FlightConfig;
+use crate::pyro_events::PyroEvents;
+use crate::sim::closed_loop::{Airframe, Launch};
+use crate::sim::eng::Motor;
+use crate::sim::truth::Truth;
+
+/// The airframe as flown, copied from `VLF5/firmware/src/main.rs`
+/// (`FLIGHT_CONFIG.airbrakes.rocket`). `cd` is the STAR-CCM+ table from FDR
+/// Table 10 converted to coefficients; `reference_area` is OpenRocket's own
+/// reference area for this airframe, which the CSVs carry in
+/// `reference_area_m2` — `config_matches_the_simulated_airframe` checks
+/// the two against each other.
+///
+/// Table 10 is one Mach number (0.8), so this is the same table at every
+/// Mach. [`osiris_rocket_mach_table`] is the same airframe with a Mach axis.
+pub fn osiris_rocket() -> RocketParameters {
+    RocketParameters::mach_independent(
+        18.696,
+        [0.61365, 0.69816, 0.8084, 0.96641, 1.12441],
+        0.009854945,
+    )
+}
+
+/// The Mach rows of [`osiris_rocket_mach_table`]: the top of the range the
+/// flaps fly ([`MAX_OPEN_MACH`] holds the top row) down to where stowed Cd
+/// stops falling.
+pub const OSIRIS_CD_MACH: [f32; CD_MACH_POINTS] = [0.4, 0.6, 0.7, 0.8];
+
+/// [`osiris_rocket`] with a Mach axis: each row is the Table 10 extension
+/// curve scaled by OpenRocket's own stowed Cd at that Mach over its stowed
+/// Cd at 0.8, the Mach the CFD was run at.
+///
+/// Only the shape in Mach comes from OpenRocket. The level at 0.8 is still
+/// the CFD's, and the flaps scale with the body -- which is an assumption,
+/// and the one a multi-Mach CFD run would replace.
+pub fn osiris_rocket_mach_table(truth: &Truth) -> RocketParameters {
+    let mut rocket = osiris_rocket();
+    let reference = truth.stowed_cd_at_mach(0.8);
+    rocket.cd_mach = OSIRIS_CD_MACH;
+    for (row, mach) in rocket.cd.iter_mut().zip(OSIRIS_CD_MACH) {
+        let scale = truth.stowed_cd_at_mach(mach) / reference;
+        for c in row.iter_mut() {
+            *c *= scale;
+        }
+    }
+    rocket
+}
+
+/// `FLIGHT_CONFIG` from `VLF5/firmware/src/main.rs`, verbatim.
+///
+/// Duplicated rather than imported because that constant lives in the
+/// firmware crate, which does not build for the host. Everything the
+/// numbers claim about the trajectory is asserted against the simulations
+/// in `mach_lockout_timers_bracket_every_simulation`, so a copy that
+/// drifts out of date fails loudly rather than quietly passing.
+pub fn osiris_config() -> FlightConfig {
+    FlightConfig {
+        // Mirrors VLF5's `FLIGHT_CONFIG`. One field, both halves — see
+        // `crate::ignition_detector`.
+        ignition_detection_acc_threshold: 8.0 * 9.81,
+        profile: FlightProfile {
+            mach_lockout_duration_us: Some(26_000_000),
+            deployment: DeploymentProfile::Dual {
+                drogue_chute_minimum_altitude_agl: 2000.0,
+                drogue_chute_delay_us: 1_000_000,
+                main_chute_altitude_agl: 457.2,
+                main_chute_delay_us: 0,
+            },
+            landing: LandingDetection::default(),
+            pyro_events: PyroEvents::new(),
+            gps_aiding: None,
+        },
+        airbrakes: AirbrakesConfig {
+            mach_lockout: Some(MachLockoutConfig {
+                earliest_subsonic_after_ignition_us: 17_200_000,
+                force_birth_after_ignition_us: 25_000_000,
+                subsonic_crossing_altitude_asl: 6800.0,
+            }),
+            max_open_mach: MAX_OPEN_MACH,
+            rocket: osiris_rocket(),
+        },
+        controller_mode: ControllerMode::TargetApogee,
+        // VLF5 flies the deployment half alone. The backup it would fly is
+        // `osiris_backup_deployment`, and `tests::osiris_sim` flies it.
+        deployment_policy: DeploymentPolicy::PrimaryOnly,
+        // Osiris is one motor.
+        staging: None,
+    }
+}
+
+/// The backup deployment channel for Osiris, for the policies in
+/// [`DeploymentPolicy`] that build one.
+///
+/// Both times are from ignition and bracket every motor in the document:
+/// the O3400 reaches apogee at 39.6 s and the N2900 at 37.7 s, so the
+/// accelerometer is believed from 30 s — long after either burns out — and
+/// the timer runs to 45 s, past the deployment half's own nominal drogue
+/// (43.6 s) so that on an honest flight it is never what fires.
+/// `a_dead_barometer_still_fires_the_drogue` asserts the bracket against the
+/// simulations. Armed at the drogue's own 2000 m minimum.
+pub fn osiris_backup_deployment() -> BackupDeploymentConfig {
+    BackupDeploymentConfig {
+        apogee_timer_us: 45_000_000,
+        earliest_accel_apogee_us: 30_000_000,
+        arming_altitude_agl: 2000.0,
+    }
+}
+
+/// The Mach the flaps are permitted to open at, in one place: the config
+/// above and every assertion that references "the crossing" read it, so
+/// raising it cannot leave a test still asserting against the old value.
+///
+/// It is deliberately ABOVE the Mach the Cd table is tabulated at (0.8) —
+/// see `AirbrakesConfig::max_open_mach`. 0.83 is not a round number: it is
+/// the largest value `transonic_static_port_error_is_absorbed_by_the_lockout`
+/// still passes at, and that test is what bounds it.
+pub const MAX_OPEN_MACH: f32 = 0.83;
+
+/// The OpenRocket exports, relative to this crate's root — where `cargo test`
+/// runs.
+pub const O3400_CSV: &str = "./test_data/osiris_o3400.csv";
+pub const N2900_CSV: &str = "./test_data/osiris_n2900.csv";
+
+/// The O3400 as OpenRocket burned it, as a RASP file: its thrust column,
+/// resampled. The file's header says where its masses come from.
+pub const O3400_ENG: &str = "./test_data/cti_o3400.eng";
+/// Stowed Cd against Mach, as OpenRocket flew it: drag over dynamic pressure
+/// and reference area on the O3400 coast, averaged in 0.05 Mach bins. The
+/// burn is left out, where OpenRocket's base drag is the motor's business,
+/// and so is everything under Mach 0.25, where the dynamic pressure is too
+/// small to divide by.
+pub const STOWED_CD_CSV: &str = "./test_data/osiris_cd.csv";
+
+/// Osiris for [`ClosedLoop`](crate::sim::closed_loop::ClosedLoop), around
+/// `motor`, with the stowed Cd [`Airframe::load_stowed_cd`] read from
+/// [`STOWED_CD_CSV`].
+///
+/// The dry mass is OpenRocket's liftoff mass less the motor's, so the
+/// rocket weighs what OpenRocket's did on [`O3400_ENG`]. The canopies are
+/// the drag areas OpenRocket's steady descents imply, `2 m g / (rho v^2)`:
+/// 27.3 m/s under the drogue at 4.6 km, and 4.35 m/s under both at 300 m.
+pub fn osiris_airframe(motor: &Motor, stowed_cd: Vec<(f32, f32)>) -> Airframe {
+    const LIFTOFF_MASS_KG: f32 = 29.9677;
+    Airframe {
+        dry_mass_kg: LIFTOFF_MASS_KG - motor.total_kg,
+        reference_area_m2: osiris_rocket().reference_area,
+        stowed_cd,
+        brakes: osiris_rocket(),
+        drogue_cda_m2: 0.638,
+        main_cda_m2: 16.1,
+    }
+}
+
+/// The pad OpenRocket launched from: 4 deg off vertical, leaning to 350 deg
+/// counterclockwise of east, and free to turn 4.45 m up the rail — where the
+/// simulation's own attitude first changes. Calm: OpenRocket's wind is
+/// baked into its trajectory, not into any number here, so set `wind_mps`
+/// to fly one.
+pub fn osiris_launch() -> Launch {
+    Launch {
+        pad_altitude_asl: 363.6,
+        rail_length_m: 4.45,
+        rail_tilt_rad: 4.0f32.to_radians(),
+        rail_azimuth_rad: 6.108_652,
+        wind_mps: Vector2::zeros(),
+    }
+}
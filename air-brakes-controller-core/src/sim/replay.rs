@@ -0,0 +1,211 @@
+//! Replay: a synthesised sensor stream through [`FlightEstimators`] and the
+//! MPC, with everything worth scoring recorded against truth time.
+
+use crate::airbrakes_estimator::AttitudeAngles;
+use crate::atmosphere::Atmosphere;
+use crate::controller::AirBrakesMPC;
+use crate::flight_estimators::{FlightConfig, FlightEstimators};
+use crate::sim::sensors::Sample;
+use crate::staging::StagingEvent;
+
+#[derive(Default)]
+pub struct Replay {
+    /// (truth time, forced?) when the airbrakes filter was born
+    pub birth: Option<(f32, bool)>,
+    /// truth time the pad calibration first completed
+    pub calibration_t: Option<f32>,
+    /// truth time `burnout_detected()` first latched
+    pub burnout_t: Option<f32>,
+    pub burnout_unlatched: bool,
+    /// continuous spans of truth time where `subsonic_by_drag()` was true
+    pub subsonic_spans: Vec<(f32, f32)>,
+    /// truth time the airbrakes half was retired (dropped by
+    /// `FlightEstimators::update`)
+    pub retired_t: Option<f32>,
+    /// truth time the DEPLOYMENT half left the pad (its ignition call)
+    pub ignition_t: Option<f32>,
+    /// truth time the DEPLOYMENT half left ascent (its apogee call)
+    pub deployment_apogee_t: Option<f32>,
+    /// (truth time, PyroSelect) for every pyro command, in order
+    pub pyros: Vec<(f32, &'static str)>,
+    /// (truth time, event) for every staging event, in order
+    pub staging: Vec<(f32, StagingEvent)>,
+    /// (truth time, estimated vv, true vv) while the airbrakes filter was alive
+    pub vv_track: Vec<(f32, f32, f32)>,
+    /// (truth time, estimated altitude ASL, true altitude ASL) likewise
+    pub alt_track: Vec<(f32, f32, f32)>,
+    /// (truth time, commanded extension) for every tick the gate was open
+    pub mpc: Vec<(f32, f32)>,
+    /// truth time the MPC gate first opened / last closed
+    pub mpc_window: Option<(f32, f32)>,
+    /// worst |baro altitude - true altitude| seen during the lockout
+    pub worst_baro_error_in_lockout: f32,
+    pub clipped_samples: usize,
+    /// truth time of the FIRST sample to hit the accelerometer rail
+    pub first_clip_t: Option<f32>,
+    /// (truth time, estimated tilt, true tilt) in radians, every sample the
+    /// estimator reported a tilt
+    pub tilt_track: Vec<(f32, f32, f32)>,
+    /// (truth time, estimated angles, true pitch, true heading, synthetic
+    /// roll) in radians, every sample the estimator reported an attitude
+    pub attitude_track: Vec<(f32, AttitudeAngles, f32, f32, f32)>,
+}
+
+/// Drive [`FlightEstimators`] exactly the way `armed_mode.rs` does, plus the
+/// MPC on the states the gate hands out.
+///
+/// No air temperature is fed, so the flight stays on the standard day rather
+/// than this launch day's: every number the sim scores is a pressure
+/// altitude (see the note on `TruthRow`), and only the standard atmosphere
+/// leaves those as they are. `a_pad_calibrated_atmosphere_matches_the_launch_day`
+/// is where the calibrated one is tested against the day's truth.
+pub fn replay(samples: &[Sample], config: FlightConfig, target_apogee_asl: f32) -> Replay {
+    let mpc = AirBrakesMPC::new(
+        config.airbrakes.rocket.clone(),
+        Atmosphere::standard(),
+        target_apogee_asl,
+    );
+    let mut est = FlightEstimators::new(config);
+    let mut out = Replay::default();
+    let mut span_start: Option<f32> = None;
+
+    for s in samples {
+        est.update_mag(s.t_us, &s.mag);
+        let (pyro, staging, _log) = est.update(s.t_us, Some(&s.imu), &[Some(s.baro_altitude_asl)], None, None);
+        let t = s.truth_t;
+
+        if s.clipped {
+            out.clipped_samples += 1;
+            if out.first_clip_t.is_none() {
+                out.first_clip_t = Some(t);
+            }
+        }
+        if let Some(p) = pyro {
+            out.pyros.push((t, pyro_name(p)));
+        }
+        if let Some(event) = staging {
+            out.staging.push((t, event));
+        }
+
+        if out.retired_t.is_none()
+            && est.airbrakes_estimator().is_none()
+            && !out.alt_track.is_empty()
+        {
+            out.retired_t = Some(t);
+        }
+        if let Some(ab) = est.airbrakes_estimator() {
+            if out.calibration_t.is_none() && ab.calibration_complete() {
+                out.calibration_t = Some(t);
+            }
+            match (ab.burnout_detected(), out.burnout_t) {
+                (true, None) => out.burnout_t = Some(t),
+                (false, Some(_)) => out.burnout_unlatched = true,
+                _ => {}
+            }
+            match (ab.subsonic_by_drag(), span_start) {
+                (Some(true), None) => span_start = Some(t),
+                (Some(true), Some(_)) => {}
+                (_, Some(start)) => {
+                    out.subsonic_spans.push((start, t));
+                    span_start = None;
+                }
+                _ => {}
+            }
+            if out.birth.is_none()
+                && let Some((born_us, forced)) = ab.birth()
+            {
+                out.birth = Some(((born_us as f32) * 1e-6 - samples[0].truth_t.abs(), forced));
+            }
+            if !ab.airbrakes_enabled() && out.birth.is_none() && t > 0.0 {
+                let err = (s.baro_altitude_asl - s.truth.altitude_asl).abs();
+                out.worst_baro_error_in_lockout = out.worst_baro_error_in_lockout.max(err);
+            }
+            if let Some(tilt) = ab.tilt() {
+                out.tilt_track
+                    .push((t, tilt, core::f32::consts::FRAC_PI_2 - s.truth.zenith));
+            }
+            if let Some(angles) = ab.attitude() {
+                let heading = core::f32::consts::FRAC_PI_2 - s.truth.azimuth;
+                out.attitude_track.push((
+                    t,
+                    angles,
+                    s.truth.zenith,
+                    heading.rem_euclid(core::f32::consts::TAU),
+                    s.roll,
+                ));
+            }
+            if let (Some(v), Some(a)) = (ab.velocity(), ab.altitude_asl()) {
+                out.vv_track.push((t, v.y, s.truth.vv));
+                out.alt_track.push((t, a, s.truth.altitude_asl));
+            }
+        }
+
+        if out.ignition_t.is_none() && !matches!(est.state(), crate::RocketState::OnPad) {
+            out.ignition_t = Some(t);
+        }
+        if out.deployment_apogee_t.is_none()
+            && !matches!(
+                est.state(),
+                crate::RocketState::OnPad
+                    | crate::RocketState::Ascent { .. }
+                    | crate::RocketState::MachLockout { .. }
+            )
+        {
+            out.deployment_apogee_t = Some(t);
+        }
+
+        if let Some(states) = est.airbrakes_mpc_states() {
+            let sol = mpc.update(states.altitude_asl, states.velocity);
+            out.mpc.push((t, sol.extension_percentage));
+            out.mpc_window = Some(match out.mpc_window {
+                None => (t, t),
+                Some((a, _)) => (a, t),
+            });
+        }
+    }
+
+    out
+}
+
+pub fn pyro_name(p: firmware_common_new::vlp::packets::fire_pyro::PyroSelect) -> &'static str {
+    use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
+    match p {
+        PyroSelect::PyroDrogue => "drogue",
+        PyroSelect::PyroMain => "main",
+        PyroSelect::PyroSeparation => "separation",
+        PyroSelect::PyroBackupDrogue => "backup-drogue",
+        PyroSelect::PyroBackupMain => "backup-main",
+    }
+}
+
+impl Replay {
+    /// The airbrakes filter's own apogee: the peak of the altitude it
+    /// reported, and when. This is the number worth scoring, because the
+    /// filter is retired at zero vertical velocity and the peak it reached
+    /// right before that IS its apogee estimate.
+    ///
+    /// There has never been anything else to score it against here. The
+    /// estimator's own 0.5 s apogee latch — deleted on 2026-08-17 — needed
+    /// 0.5 s below 1 m/s and the airbrakes half is dropped at 0 m/s, so it
+    /// never once fired in a composed flight; this field recorded `None` on
+    /// every simulation in this file.
+    pub fn estimated_apogee(&self) -> Option<(f32, f32)> {
+        self.alt_track
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(t, alt, _)| (*t, *alt))
+    }
+}
+
+/// Mean |estimate - truth| over a truth-time window.
+pub fn mean_error(track: &[(f32, f32, f32)], from: f32, to: f32) -> (f32, usize) {
+    let mut sum = 0.0;
+    let mut n = 0;
+    for (t, est, truth) in track {
+        if *t >= from && *t < to {
+            sum += (est - truth).abs();
+            n += 1;
+        }
+    }
+    (if n > 0 { sum / n as f32 } else { f32::NAN }, n)
+}
@@ -0,0 +1,389 @@
+//! Sensors: what the board would have read along a [`Truth`], through the
+//! IMU, magnetometer and barometer the drivers configure.
+//!
+//! Invented here, and only here:
+//!
+//! * **Sensor noise, bias and quantisation.** See [`SensorModel`] for the
+//!   measured numbers, quantised to the LSM6DSM/MS5607 LSBs the drivers
+//!   actually configure.
+//! * **Roll.** OpenRocket reports roll rate identically zero for Osiris,
+//!   which is not a rocket. A spin-up/decay profile peaking at 1 rev/s is
+//!   added, and the accelerometer and gyro are both generated from the
+//!   same rolling attitude.
+//! * **The magnetic field.** OpenRocket has none. A fixed field is used,
+//!   with the world Y axis as magnetic north — see [`EARTH_FIELD_T`].
+//! * **The IMU mounting orientation.** [`SensorModel::mount`].
+//! * **Pad time.** OpenRocket starts at ignition; the rocket sits armed on
+//!   the rail for minutes. A quiet pad segment (with a little rail sway) is
+//!   prepended.
+//! * **The transonic static-port error**, when asked for. OpenRocket
+//!   reports the true freestream static pressure; a real port on a Mach
+//!   1.9 airframe does not. See [`SensorModel::transonic_port_error`].
+
+use core::f32::consts::PI;
+
+use icao_isa::calculate_isa_altitude;
+use icao_units::si::Pascals;
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::airbrakes_estimator::ImuSample;
+use crate::sim::truth::{Truth, TruthRow};
+
+/// xorshift64*, so the runs are deterministic without a `rand` dependency.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn uniform(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Standard normal, Box-Muller.
+    pub fn normal(&mut self) -> f32 {
+        let u1 = self.uniform().max(1e-7);
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
+
+/// Everything invented about the sensors, in one place.
+///
+/// The IMU noise is measured, not guessed: `VLF5/firmware`'s `imu_bench`
+/// binary run against the flight board's own LSM6DSM, 57 stationary 2 s
+/// windows over 114 s, median window sigma per axis. It is quite unlike the
+/// Void Lake pad figures these tests first used — the accelerometer is
+/// 4-10x quieter than that flight's pad (which had rail sway and a live
+/// motor next to it), while gyro X is nearly twice as noisy. Static
+/// pressure is still the Void Lake pad's 5.5 Pa RMS; the MS5607 bench
+/// measurement lives in `hil/baro_sim.rs` as 0.36 m, which is the same
+/// number in altitude form.
+#[derive(Debug, Clone)]
+pub struct SensorModel {
+    /// Accelerometer full scale, m/s^2. The LSM6DSM is configured for
+    /// +-16 g in `drivers/lsm6dsm.rs`, and Osiris exceeds that in the
+    /// middle of the burn — see `clipped_accel_still_flies_the_profile`.
+    pub accel_full_scale: f32,
+    /// Per-axis, because the measured axes are genuinely not equal.
+    pub accel_noise: Vector3<f32>,
+    pub gyro_noise_rad_s: Vector3<f32>,
+    pub gyro_bias_rad_s: Vector3<f32>,
+    /// RMS of the pressure noise, Pa.
+    pub pressure_noise_pa: f32,
+    /// Per-axis RMS of the magnetometer noise, T.
+    pub mag_noise_t: f32,
+    /// Peak static-port pressure error as a fraction of dynamic pressure.
+    /// Zero disables the transonic error entirely.
+    pub transonic_port_error: f32,
+    /// Rotation from the airframe frame into the IMU chip frame.
+    pub mount: UnitQuaternion<f32>,
+    /// Seconds of pad prepended before ignition.
+    pub pad_s: f32,
+    /// Stop generating samples after this truth time.
+    pub until_s: f32,
+    /// Nominal sample interval (us). 2404 is the 416 Hz the firmware
+    /// assumes; the flight board's LSM6DSM actually delivers 2342 (427 Hz),
+    /// which `imu_bench` measured.
+    pub sample_dt_us: u64,
+    pub seed: u64,
+}
+
+impl Default for SensorModel {
+    fn default() -> Self {
+        Self {
+            accel_full_scale: f32::INFINITY,
+            accel_noise: Vector3::new(0.0147, 0.0190, 0.0359),
+            gyro_noise_rad_s: Vector3::new(0.448, 0.181, 0.048) * (PI / 180.0),
+            // a real, constant, uncalibrated gyro bias — around a degree
+            // per second per axis, which is what the pad calibration is for
+            gyro_bias_rad_s: Vector3::new(1.15, -1.93, -0.45) * (PI / 180.0),
+            pressure_noise_pa: 5.5,
+            // invented: no board magnetometer is characterised in this
+            // tree, and a few tenths of a uT is the class of noise a 3-axis
+            // part has at its most sensitive range
+            mag_noise_t: 0.35e-6,
+            mount: imu_mounting(),
+            transonic_port_error: 0.0,
+            sample_dt_us: 2404,
+            pad_s: 60.0,
+            until_s: f32::INFINITY,
+            seed: 0x0517_2026_0626_0001,
+        }
+    }
+}
+
+/// The earth's field in the world frame (T): world Y is magnetic north and
+/// world X east, so the sim's own frame is east-north-up and an azimuth
+/// `az` (from X toward Y) is the compass heading `90 deg - az`. 50 uT,
+/// dipping 65 deg — mid-latitude North America. Invented, and constant:
+/// nothing here moves far enough for the real field to change.
+pub const EARTH_FIELD_T: Vector3<f32> = Vector3::new(0.0, 21.1e-6, -45.3e-6);
+
+/// LSM6DSM +-16 g / +-2000 dps LSBs, from `drivers/lsm6dsm.rs`.
+pub const ACCEL_LSB: f32 = 16.0 / 32768.0 * 9.81;
+pub const GYRO_LSB_RAD_S: f32 = (2000.0 / 32768.0) * PI / 180.0;
+/// The MS5607 driver reports pressure as a whole number of Pa.
+pub const PRESSURE_LSB_PA: f32 = 1.0;
+
+/// The default mounting rotation between the airframe and the IMU chip.
+/// Chosen to be nothing like identity so the pad self-calibration has real
+/// work to do.
+pub fn imu_mounting() -> UnitQuaternion<f32> {
+    UnitQuaternion::from_euler_angles(0.31, -0.22, 2.4)
+}
+
+/// A board mounted the way a board actually gets mounted: chip +Z along the
+/// airframe axis, a few degrees out. This is the WORST case for
+/// accelerometer clipping, because the whole axial specific force lands on
+/// one channel instead of being shared across three.
+pub fn axis_aligned_mounting() -> UnitQuaternion<f32> {
+    UnitQuaternion::from_euler_angles(0.05, -0.03, 0.7)
+}
+
+/// Roll rate (rad/s) at truth time `t`: spin-up over the burn to 1 rev/s,
+/// then a slow decay. Entirely invented — OpenRocket reports zero roll for
+/// this design, and a rocket that does not roll is not a useful test of a
+/// gyro-integrating estimator.
+pub fn roll_rate(t: f32, burnout_t: f32) -> f32 {
+    if t <= 0.0 {
+        return 0.0;
+    }
+    let spin_up = 1.0 - (-t / 2.0).exp();
+    let decay = (-(t - burnout_t).max(0.0) / 25.0).exp();
+    2.0 * PI * spin_up * decay
+}
+
+/// Airframe attitude at truth time `t`: `q * body_vector = world_vector`,
+/// with body +Z along the nose.
+///
+/// `Rz(azimuth) * Ry(tilt) * Rz(roll)`, so body +Z lands on
+/// `(sin(tilt)cos(az), sin(tilt)sin(az), cos(tilt))` — the airframe axis —
+/// and the last factor spins the chip about it.
+pub fn attitude(truth: &TruthRow, roll: f32) -> UnitQuaternion<f32> {
+    let tilt = core::f32::consts::FRAC_PI_2 - truth.zenith;
+    UnitQuaternion::from_axis_angle(&Vector3::z_axis(), truth.azimuth)
+        * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), tilt)
+        * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), roll)
+}
+
+/// Body-frame angular velocity for the same parameterisation, analytically:
+/// `w_world = az_dot * Z + tilt_dot * Rz(az) Y + roll_dot * axis`, rotated
+/// into the body frame. `orientation_model_matches_openrocket` checks
+/// this against OpenRocket's own pitch and yaw rates.
+pub fn body_rates(
+    truth: &TruthRow,
+    az_dot: f32,
+    tilt_dot: f32,
+    roll_dot: f32,
+    q: &UnitQuaternion<f32>,
+) -> Vector3<f32> {
+    let az = truth.azimuth;
+    let tilt = core::f32::consts::FRAC_PI_2 - truth.zenith;
+    let axis_world = Vector3::new(tilt.sin() * az.cos(), tilt.sin() * az.sin(), tilt.cos());
+    let w_world = Vector3::z() * az_dot
+        + Vector3::new(-az.sin(), az.cos(), 0.0) * tilt_dot
+        + axis_world * roll_dot;
+    q.inverse_transform_vector(&w_world)
+}
+
+/// One generated sample: what the firmware's estimator loop would see,
+/// plus the truth it was generated from so the test can score it.
+pub struct Sample {
+    pub t_us: u64,
+    /// Truth time; negative while still on the pad.
+    pub truth_t: f32,
+    pub imu: ImuSample,
+    /// Magnetometer, chip frame (T).
+    pub mag: Vector3<f32>,
+    pub baro_altitude_asl: f32,
+    pub truth: TruthRow,
+    /// The synthetic roll the attitude was built with (rad, unwrapped).
+    pub roll: f32,
+    /// Set when the accelerometer full scale clipped this sample.
+    pub clipped: bool,
+}
+
+/// One read of the IMU, magnetometer and barometer: what the drivers hand
+/// the estimator loop, noise, bias, clipping and quantisation included.
+pub struct Reading {
+    pub imu: ImuSample,
+    /// Magnetometer, chip frame (T).
+    pub mag: Vector3<f32>,
+    /// Static pressure as the MS5607 driver reports it (Pa).
+    pub pressure: f32,
+    /// ISA pressure altitude of `pressure`, as `BaroData::altitude_asl`
+    /// computes it.
+    pub baro_altitude_asl: f32,
+    /// Set when the accelerometer full scale clipped this read.
+    pub clipped: bool,
+}
+
+impl SensorModel {
+    /// Read every chip once, with the airframe at attitude `q` (`q * body =
+    /// world`, body +Z along the nose) turning at `w_body` (rad/s, body
+    /// frame) under specific force `specific_force_world` (m/s^2, world
+    /// frame), and `port_pressure` (Pa) at the static port.
+    ///
+    /// [`synthesize`] reads along a [`Truth`]; `sim::closed_loop` reads along
+    /// its own integration. Both draw from `rng` in the same order, so a
+    /// stream is reproducible from the seed alone.
+    pub fn read(
+        &self,
+        rng: &mut Rng,
+        q: &UnitQuaternion<f32>,
+        w_body: &Vector3<f32>,
+        specific_force_world: &Vector3<f32>,
+        port_pressure: f32,
+    ) -> Reading {
+        // Into the chip frame, then through the chip.
+        let sf_body = q.inverse_transform_vector(specific_force_world);
+        let mut acc = self.mount.inverse_transform_vector(&sf_body);
+        let mut gyro = self.mount.inverse_transform_vector(w_body);
+        let mut mag = self
+            .mount
+            .inverse_transform_vector(&q.inverse_transform_vector(&EARTH_FIELD_T));
+
+        for k in 0..3 {
+            acc[k] += rng.normal() * self.accel_noise[k];
+            gyro[k] += rng.normal() * self.gyro_noise_rad_s[k] + self.gyro_bias_rad_s[k];
+            mag[k] += rng.normal() * self.mag_noise_t;
+        }
+        let mut clipped = false;
+        for k in 0..3 {
+            if acc[k].abs() > self.accel_full_scale {
+                acc[k] = acc[k].clamp(-self.accel_full_scale, self.accel_full_scale);
+                clipped = true;
+            }
+            acc[k] = (acc[k] / ACCEL_LSB).round() * ACCEL_LSB;
+            gyro[k] = (gyro[k] / GYRO_LSB_RAD_S).round() * GYRO_LSB_RAD_S;
+        }
+
+        let mut pressure = port_pressure + rng.normal() * self.pressure_noise_pa;
+        pressure = (pressure / PRESSURE_LSB_PA).round() * PRESSURE_LSB_PA;
+        let baro_altitude_asl = calculate_isa_altitude(Pascals(pressure as f64)).0 as f32;
+
+        Reading {
+            imu: ImuSample { acc, gyro },
+            mag,
+            pressure,
+            baro_altitude_asl,
+            clipped,
+        }
+    }
+
+    /// The pressure at the static port: the freestream `pressure` (Pa), less
+    /// the transonic error when [`Self::transonic_port_error`] asks for one.
+    /// `airspeed` is m/s; nothing is subtracted at rest.
+    pub fn port_pressure(&self, pressure: f32, density: f32, airspeed: f32, mach: f32) -> f32 {
+        if self.transonic_port_error > 0.0 {
+            let q_dyn = 0.5 * density * airspeed * airspeed;
+            pressure - self.transonic_port_error * q_dyn * transonic_shape(mach)
+        } else {
+            pressure
+        }
+    }
+}
+
+/// Generate the sensor stream: 416 Hz nominal with jitter, a quiet pad
+/// segment, then the trajectory.
+pub fn synthesize(truth: &Truth, model: &SensorModel) -> Vec<Sample> {
+    let mut rng = Rng(model.seed | 1);
+    let burnout_t = truth.burnout_t();
+    let end_t = model.until_s.min(truth.last_t());
+
+    // Attitude derivatives come from differentiating the truth at the
+    // sample time; h is well under one OpenRocket step (5 ms on ascent).
+    const H: f32 = 0.001;
+
+    let mut samples = Vec::new();
+    let mut t_us: u64 = 0;
+    let mut roll = 0.0f32;
+    let mut prev_truth_t = -model.pad_s;
+
+    loop {
+        let truth_t = (t_us as f32) * 1e-6 - model.pad_s;
+        if truth_t > end_t {
+            break;
+        }
+
+        roll += roll_rate(truth_t, burnout_t) * (truth_t - prev_truth_t);
+        prev_truth_t = truth_t;
+
+        // --- truth state, with the pad standing in before ignition ------
+        let on_pad = truth_t < 0.0;
+        let mut r = truth.at(truth_t.max(0.0));
+        if on_pad {
+            // Rail sway: a small, slow tilt oscillation, and no motion.
+            r.vv = 0.0;
+            r.lateral_velocity = 0.0;
+            r.acc_world = Vector3::zeros();
+            r.zenith += 0.03f32.to_radians() * (2.0 * PI * 0.7 * truth_t).sin();
+            r.mach = 0.0;
+        }
+
+        // --- attitude and rates ----------------------------------------
+        let q = attitude(&r, roll);
+        let (tilt_dot, az_dot) = {
+            let a = truth.at((truth_t - H).max(0.0));
+            let b = truth.at((truth_t + H).max(0.0));
+            if on_pad {
+                // differentiate the sway analytically instead
+                let w = 2.0 * PI * 0.7;
+                (-0.03f32.to_radians() * w * (w * truth_t).cos(), 0.0)
+            } else {
+                (
+                    -(b.zenith - a.zenith) / (2.0 * H),
+                    (b.azimuth - a.azimuth) / (2.0 * H),
+                )
+            }
+        };
+        let w_body = body_rates(&r, az_dot, tilt_dot, roll_rate(truth_t, burnout_t), &q);
+
+        // --- specific force --------------------------------------------
+        // The accelerometer measures specific force: kinematic acceleration
+        // minus gravity. On the pad that is exactly +g up.
+        let sf_world = r.acc_world + Vector3::new(0.0, 0.0, r.gravity);
+
+        // --- through the chips ------------------------------------------
+        let port_pressure =
+            model.port_pressure(r.pressure, r.density, r.mach * r.speed_of_sound, r.mach);
+        let reading = model.read(&mut rng, &q, &w_body, &sf_world, port_pressure);
+
+        samples.push(Sample {
+            t_us,
+            truth_t,
+            imu: reading.imu,
+            mag: reading.mag,
+            baro_altitude_asl: reading.baro_altitude_asl,
+            truth: r,
+            roll,
+            clipped: reading.clipped,
+        });
+
+        // 416 Hz with a little jitter, the way a real sensor task delivers.
+        t_us += model.sample_dt_us + (rng.uniform() * 120.0) as u64;
+    }
+
+    samples
+}
+
+/// Shape of the static-port error against Mach: nothing subsonic, rising
+/// through the transonic region, held supersonic. Invented — its only job
+/// is to make the barometer as dishonest through the lockout as a real
+/// port is, so the tests below prove the lockout is what saves the answer.
+pub fn transonic_shape(mach: f32) -> f32 {
+    if mach < 0.7 {
+        0.0
+    } else if mach < 1.0 {
+        (mach - 0.7) / 0.3
+    } else {
+        1.0
+    }
+}
@@ -0,0 +1,504 @@
+//! Truth: an OpenRocket trajectory, as the CSVs in `test_data/` carry it,
+//! and [`Truth::dispersed`] for re-flying it with the airframe, the motor
+//! or the weather changed.
+//!
+//! The estimators never see a geometric altitude — they see whatever
+//! `calculate_isa_altitude` makes of the pressure, and so does every number
+//! they are scored against here. That is not the same as the site's real
+//! altitude: the Osiris launch day is 102066 Pa at a site 363.6 m ASL,
+//! which plain ISA reads as -63 m. Scoring against the geometric altitude
+//! instead would charge the estimator hundreds of metres for the atmosphere
+//! being warmer than standard, which is not its job to know.
+
+use icao_isa::calculate_isa_altitude;
+use icao_units::si::Pascals;
+use nalgebra::{Vector2, Vector3};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TruthRow {
+    pub t: f32,
+    /// Geometric altitude above the pad, from OpenRocket. Reported only;
+    /// nothing is scored against it — see [`TruthRow::altitude_asl`].
+    pub altitude_agl: f32,
+    /// Pressure altitude: the ISA altitude of this row's static pressure.
+    /// This is the truth every estimator output is compared against,
+    /// because it is the only altitude the barometer can express.
+    pub altitude_asl: f32,
+    pub vv: f32,
+    pub lateral_velocity: f32,
+    /// Direction of `lateral_velocity`, radians, counterclockwise from
+    /// world X like `azimuth`.
+    pub lateral_direction: f32,
+    /// Angle of the airframe from the horizontal plane (OpenRocket's
+    /// "vertical orientation (zenith)"), radians. Tilt from vertical is
+    /// `FRAC_PI_2 - zenith`.
+    pub zenith: f32,
+    pub azimuth: f32,
+    /// OpenRocket's own body rates, used only to validate the attitude model.
+    pub pitch_rate: Option<f32>,
+    pub yaw_rate: Option<f32>,
+    pub mass: f32,
+    pub thrust: f32,
+    pub drag: f32,
+    pub pressure: f32,
+    pub density: f32,
+    pub temperature: f32,
+    pub speed_of_sound: f32,
+    pub mach: f32,
+    pub gravity: f32,
+    pub reference_area: f32,
+    /// World-frame kinematic acceleration, differentiated from the
+    /// reconstructed velocity vector at load time (see [`Truth::load`]).
+    pub acc_world: Vector3<f32>,
+}
+
+/// What [`Truth::dispersed`] changes about a flight. The default changes
+/// nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryDispersion {
+    /// Multiplies the motor's thrust at every instant of the burn. The
+    /// burn's length and the propellant mass are the motor's and do not
+    /// change.
+    pub thrust_scale: f32,
+    /// Multiplies the airframe's drag area at every instant, stowed and
+    /// under canopy alike.
+    pub cd_scale: f32,
+    /// Wind added to the one OpenRocket flew through (m/s), the same at
+    /// every altitude, along the drift azimuth: positive pushes the rocket
+    /// further the way it drifted.
+    pub extra_wind_mps: f32,
+    /// Splits the burn in two with a coast between, as a booster and a
+    /// sustainer would fly it. `None` burns the motor through.
+    pub airstart: Option<Airstart>,
+}
+
+impl Default for TrajectoryDispersion {
+    fn default() -> Self {
+        Self {
+            thrust_scale: 1.0,
+            cd_scale: 1.0,
+            extra_wind_mps: 0.0,
+            airstart: None,
+        }
+    }
+}
+
+/// A burn cut at `at_s` after ignition and relit `coast_s` later, where it
+/// carries on from the same point in the motor.
+///
+/// There is no two-motor OpenRocket export in the tree; this is the nearest
+/// honest thing, one motor's own thrust curve flown as two burns. Through the
+/// coast the flight holds OpenRocket's airframe at `at_s` — its attitude, its
+/// mass and its drag area — with the thrust off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Airstart {
+    pub at_s: f32,
+    pub coast_s: f32,
+}
+
+pub struct Truth {
+    pub rows: Vec<TruthRow>,
+}
+
+impl Truth {
+    pub fn load(path: &str) -> Self {
+        #[derive(serde::Deserialize)]
+        struct CsvRow {
+            time_s: f32,
+            altitude_agl_m: f32,
+            vv_mps: f32,
+            lateral_velocity_mps: f32,
+            lateral_direction_rad: f32,
+            zenith_rad: f32,
+            azimuth_rad: f32,
+            pitch_rate_rps: Option<f32>,
+            yaw_rate_rps: Option<f32>,
+            mass_kg: f32,
+            thrust_n: f32,
+            drag_n: f32,
+            pressure_pa: f32,
+            density_kgm3: f32,
+            temperature_k: f32,
+            speed_of_sound_mps: f32,
+            mach: f32,
+            gravity_mps2: f32,
+            reference_area_m2: f32,
+        }
+
+        let mut rows: Vec<TruthRow> = csv::Reader::from_path(path)
+            .unwrap()
+            .deserialize::<CsvRow>()
+            .map(|r| r.unwrap())
+            .map(|r| TruthRow {
+                t: r.time_s,
+                altitude_agl: r.altitude_agl_m,
+                altitude_asl: calculate_isa_altitude(Pascals(r.pressure_pa as f64)).0 as f32,
+                vv: r.vv_mps,
+                lateral_velocity: r.lateral_velocity_mps,
+                lateral_direction: r.lateral_direction_rad,
+                zenith: r.zenith_rad,
+                azimuth: r.azimuth_rad,
+                pitch_rate: r.pitch_rate_rps,
+                yaw_rate: r.yaw_rate_rps,
+                mass: r.mass_kg,
+                thrust: r.thrust_n,
+                drag: r.drag_n,
+                pressure: r.pressure_pa,
+                density: r.density_kgm3,
+                temperature: r.temperature_k,
+                speed_of_sound: r.speed_of_sound_mps,
+                mach: r.mach,
+                gravity: r.gravity_mps2,
+                reference_area: r.reference_area_m2,
+                acc_world: Vector3::zeros(),
+            })
+            .collect();
+        assert!(rows.len() > 1000, "{path}: only {} rows", rows.len());
+
+        differentiate(&mut rows);
+        Self { rows }
+    }
+
+    /// This flight re-flown as a point mass with `dispersion` applied, on
+    /// the same columns [`Truth::load`] reads.
+    ///
+    /// Nothing new about the airframe is invented: every force is the
+    /// recorded one, rescaled. Thrust is OpenRocket's at the same time
+    /// since ignition, along OpenRocket's airframe axis. Drag is
+    /// OpenRocket's drag area at the same point in the flight — the same
+    /// time on the way up, the same time after apogee on the way down, so
+    /// the recovery canopies open as far past this flight's apogee as they
+    /// did past OpenRocket's — on this flight's own airspeed and air.
+    /// The air is OpenRocket's ascent column looked up at this flight's
+    /// altitude, extended hydrostatically above OpenRocket's apogee.
+    ///
+    /// What that leaves out is everything a 6-DOF run would react with:
+    /// the airframe's attitude is OpenRocket's against time whatever this
+    /// flight does, so a changed wind moves the drift and the airspeed but
+    /// does not weathercock the rocket. For a few percent of thrust or drag
+    /// that is small next to what it changes; it is not a substitute for
+    /// re-running OpenRocket on a different airframe.
+    ///
+    /// The flight is stepped at 5 ms, OpenRocket's own ascent step, until
+    /// it is back on the ground.
+    ///
+    /// With an [`Airstart`], the recorded flight is read at the time the
+    /// motor has burned rather than the time since ignition, and everything
+    /// above holds of each side of the coast.
+    pub fn dispersed(&self, dispersion: &TrajectoryDispersion) -> Truth {
+        const DT: f32 = 0.005;
+        const R_AIR: f32 = 287.05;
+        /// Below this dynamic pressure OpenRocket's drag is too small to
+        /// divide by, and the last drag area measured above it is carried.
+        const MIN_Q_PA: f32 = 50.0;
+
+        let drift_azimuth = drift_azimuth(&self.rows);
+        let (apogee_t, _) = self.apogee();
+        let burnout_t = self.burnout_t();
+        let pad = self.rows[0];
+        let coast_s = dispersion.airstart.map_or(0.0, |a| a.coast_s);
+        // The recorded flight's time for this flight's `t` on the way up, and
+        // whether the motor is lit at it.
+        let ascent_source_t = |t: f32| match dispersion.airstart {
+            Some(Airstart { at_s, coast_s }) if t >= at_s => {
+                if t < at_s + coast_s {
+                    (at_s, false)
+                } else {
+                    (t - coast_s, true)
+                }
+            }
+            _ => (t, true),
+        };
+
+        // (altitude AGL, pressure, temperature), strictly climbing.
+        let mut column: Vec<(f32, f32, f32)> = Vec::new();
+        for r in self.rows.iter().filter(|r| r.t <= apogee_t) {
+            if column.last().is_none_or(|(h, _, _)| r.altitude_agl > *h) {
+                column.push((r.altitude_agl, r.pressure, r.temperature));
+            }
+        }
+        let air_at = |h: f32| -> (f32, f32) {
+            let top = column[column.len() - 1];
+            if h <= column[0].0 {
+                return (column[0].1, column[0].2);
+            }
+            if h >= top.0 {
+                let scale_height = R_AIR * top.2 / pad.gravity;
+                return (top.1 * (-(h - top.0) / scale_height).exp(), top.2);
+            }
+            let i = column.partition_point(|(a, _, _)| *a <= h);
+            let (a, b) = (column[i - 1], column[i]);
+            let s = (h - a.0) / (b.0 - a.0);
+            (a.1 + (b.1 - a.1) * s, a.2 + (b.2 - a.2) * s)
+        };
+
+        let mut rows = Vec::new();
+        let mut t = 0.0f32;
+        let mut altitude = pad.altitude_agl;
+        let mut velocity = Vector2::<f32>::zeros(); // (downrange, up)
+        let mut apex_t: Option<f32> = None;
+        let mut drag_area = 0.0f32;
+        loop {
+            let (source_t, lit) = match apex_t {
+                None => ascent_source_t(t),
+                Some(apex) => (apogee_t + (t - apex), true),
+            };
+            let source = self.at(source_t);
+            let source_airspeed = source.mach * source.speed_of_sound;
+            let source_q = 0.5 * source.density * source_airspeed * source_airspeed;
+            if source_q > MIN_Q_PA {
+                drag_area = source.drag / source_q;
+            }
+            // Downrange component of the airframe axis, and the wind
+            // OpenRocket flew through, recovered as the drift it had beyond
+            // what its airspeed along that axis explains.
+            let downrange = source.zenith.cos() * (source.azimuth - drift_azimuth).cos();
+            let wind =
+                source.lateral_velocity - source_airspeed * downrange + dispersion.extra_wind_mps;
+
+            let (pressure, temperature) = air_at(altitude);
+            let density = pressure / (R_AIR * temperature);
+            let speed_of_sound = (1.4 * R_AIR * temperature).sqrt();
+            let air = velocity - Vector2::new(wind, 0.0);
+            let airspeed = air.norm();
+            let drag = dispersion.cd_scale * drag_area * 0.5 * density * airspeed * airspeed;
+            let thrust = if lit {
+                dispersion.thrust_scale * source.thrust
+            } else {
+                0.0
+            };
+
+            let axis = Vector2::new(downrange, source.zenith.sin());
+            let drag_dir = if airspeed > 0.0 {
+                air / airspeed
+            } else {
+                Vector2::zeros()
+            };
+            let mut acc =
+                (axis * thrust - drag_dir * drag) / source.mass - Vector2::new(0.0, source.gravity);
+            if apex_t.is_none() && altitude <= pad.altitude_agl && acc.y <= 0.0 {
+                // still sitting on the rail
+                acc = Vector2::zeros();
+            }
+
+            rows.push(TruthRow {
+                t,
+                altitude_agl: altitude,
+                altitude_asl: calculate_isa_altitude(Pascals(pressure as f64)).0 as f32,
+                vv: velocity.y,
+                lateral_velocity: velocity.x,
+                lateral_direction: drift_azimuth,
+                zenith: source.zenith,
+                azimuth: source.azimuth,
+                pitch_rate: None,
+                yaw_rate: None,
+                mass: source.mass,
+                thrust,
+                drag,
+                pressure,
+                density,
+                temperature,
+                speed_of_sound,
+                mach: airspeed / speed_of_sound,
+                gravity: source.gravity,
+                reference_area: source.reference_area,
+                acc_world: Vector3::zeros(),
+            });
+
+            if apex_t.is_some() && altitude < pad.altitude_agl {
+                break;
+            }
+            assert!(
+                t < 3.0 * self.last_t(),
+                "the dispersed flight never came down: {dispersion:?}"
+            );
+
+            velocity += acc * DT;
+            altitude += velocity.y * DT;
+            t += DT;
+            if apex_t.is_none() && t > burnout_t + coast_s && velocity.y <= 0.0 {
+                apex_t = Some(t);
+            }
+        }
+
+        differentiate(&mut rows);
+        Self { rows }
+    }
+
+    /// Linear interpolation, clamped at both ends.
+    pub fn at(&self, t: f32) -> TruthRow {
+        let rows = &self.rows;
+        if t <= rows[0].t {
+            return rows[0];
+        }
+        if t >= rows[rows.len() - 1].t {
+            return rows[rows.len() - 1];
+        }
+        let i = rows.partition_point(|r| r.t <= t).max(1);
+        let (a, b) = (&rows[i - 1], &rows[i]);
+        let s = (t - a.t) / (b.t - a.t);
+        let l = |x: f32, y: f32| x + (y - x) * s;
+        TruthRow {
+            t,
+            altitude_agl: l(a.altitude_agl, b.altitude_agl),
+            altitude_asl: l(a.altitude_asl, b.altitude_asl),
+            vv: l(a.vv, b.vv),
+            lateral_velocity: l(a.lateral_velocity, b.lateral_velocity),
+            lateral_direction: l(a.lateral_direction, b.lateral_direction),
+            zenith: l(a.zenith, b.zenith),
+            azimuth: l(a.azimuth, b.azimuth),
+            pitch_rate: match (a.pitch_rate, b.pitch_rate) {
+                (Some(x), Some(y)) => Some(l(x, y)),
+                _ => None,
+            },
+            yaw_rate: match (a.yaw_rate, b.yaw_rate) {
+                (Some(x), Some(y)) => Some(l(x, y)),
+                _ => None,
+            },
+            mass: l(a.mass, b.mass),
+            thrust: l(a.thrust, b.thrust),
+            drag: l(a.drag, b.drag),
+            pressure: l(a.pressure, b.pressure),
+            density: l(a.density, b.density),
+            temperature: l(a.temperature, b.temperature),
+            speed_of_sound: l(a.speed_of_sound, b.speed_of_sound),
+            mach: l(a.mach, b.mach),
+            gravity: l(a.gravity, b.gravity),
+            reference_area: l(a.reference_area, b.reference_area),
+            acc_world: a.acc_world + (b.acc_world - a.acc_world) * s,
+        }
+    }
+
+    pub fn last_t(&self) -> f32 {
+        self.rows[self.rows.len() - 1].t
+    }
+
+    /// Pad pressure altitude — the estimators' zero.
+    pub fn pad_asl(&self) -> f32 {
+        self.rows[0].altitude_asl
+    }
+
+    /// (time, pressure altitude ASL) of the true apogee.
+    pub fn apogee(&self) -> (f32, f32) {
+        let r = self
+            .rows
+            .iter()
+            .max_by(|a, b| a.altitude_asl.total_cmp(&b.altitude_asl))
+            .unwrap();
+        (r.t, r.altitude_asl)
+    }
+
+    pub fn burnout_t(&self) -> f32 {
+        // last row still producing thrust
+        self.rows
+            .iter()
+            .filter(|r| r.thrust > 0.0)
+            .map(|r| r.t)
+            .fold(0.0, f32::max)
+    }
+
+    /// Time the axial specific force `(thrust - drag)/mass` crosses zero
+    /// downward — the signal the estimator's burnout latch actually
+    /// watches, which on a long tail-off at high Mach is NOT the motor
+    /// burning out. See `nominal_o3400_flight` in `tests::osiris_sim`.
+    pub fn axial_zero_crossing(&self) -> f32 {
+        for w in self.rows.windows(2) {
+            let (a, b) = (w[0].thrust - w[0].drag, w[1].thrust - w[1].drag);
+            if w[0].t > 1.0 && a > 0.0 && b <= 0.0 {
+                return w[0].t + a / (a - b) * (w[1].t - w[0].t);
+            }
+        }
+        panic!("the axial channel never went negative");
+    }
+
+    /// Time of the coast-side downward crossing of `mach`.
+    pub fn mach_down_crossing(&self, mach: f32) -> f32 {
+        let burnout = self.burnout_t();
+        for w in self.rows.windows(2) {
+            if w[0].t <= burnout {
+                continue;
+            }
+            if w[0].mach > mach && w[1].mach <= mach {
+                let s = (w[0].mach - mach) / (w[0].mach - w[1].mach);
+                return w[0].t + s * (w[1].t - w[0].t);
+            }
+        }
+        panic!("never crossed Mach {mach} on the coast");
+    }
+
+    /// OpenRocket's drag coefficient at the coast-side crossing of `mach`,
+    /// on its own reference area. OpenRocket flies the airframe with the
+    /// flaps stowed, so this is the stowed Cd.
+    pub fn stowed_cd_at_mach(&self, mach: f32) -> f32 {
+        let r = self.at(self.mach_down_crossing(mach));
+        let airspeed = r.mach * r.speed_of_sound;
+        r.drag / (0.5 * r.density * airspeed * airspeed * r.reference_area)
+    }
+
+    /// Time the descent passes down through `agl` metres.
+    pub fn descent_crossing_agl(&self, agl: f32) -> f32 {
+        let (apogee_t, _) = self.apogee();
+        for w in self.rows.windows(2) {
+            if w[0].t <= apogee_t {
+                continue;
+            }
+            let (a0, a1) = (
+                w[0].altitude_asl - self.pad_asl(),
+                w[1].altitude_asl - self.pad_asl(),
+            );
+            if a0 > agl && a1 <= agl {
+                let s = (a0 - agl) / (a0 - a1);
+                return w[0].t + s * (w[1].t - w[0].t);
+            }
+        }
+        panic!("descent never reached {agl} m AGL");
+    }
+}
+
+/// The one fixed azimuth the horizontal velocity is laid along: the
+/// airframe's at burnout. See [`differentiate`].
+fn drift_azimuth(rows: &[TruthRow]) -> f32 {
+    let burnout_t = rows
+        .iter()
+        .filter(|r| r.thrust > 0.0)
+        .map(|r| r.t)
+        .fold(0.0f32, f32::max);
+    rows.iter()
+        .find(|r| r.t >= burnout_t)
+        .map(|r| r.azimuth)
+        .unwrap_or(0.0)
+}
+
+/// Fill in every row's `acc_world`.
+///
+/// Kinematic acceleration, central-differenced from the world velocity
+/// vector the same columns define. Doing it here rather than reading
+/// OpenRocket's acceleration columns keeps the accelerometer exactly
+/// consistent with the velocity and altitude the rest of the model uses —
+/// the sensor stream cannot disagree with the trajectory it came from.
+/// `sensor_model_matches_openrocket_forces` checks the result against
+/// OpenRocket's independent thrust and drag.
+///
+/// The horizontal component is laid along ONE fixed azimuth, taken at
+/// burnout, rather than along each row's own airframe azimuth. The airframe
+/// azimuth is the direction the rocket is *tilted*, and it swings by
+/// ~10 deg over the coast; hanging the drift velocity off it would rotate
+/// that vector and manufacture a lateral acceleration of several m/s^2 that
+/// the rocket never felt. The real drift direction barely moves, so a
+/// constant is both simpler and closer to the truth — and the vertical
+/// channel, which is the one every estimator output depends on, is
+/// untouched by the choice.
+fn differentiate(rows: &mut [TruthRow]) {
+    let drift_azimuth = drift_azimuth(rows);
+    let (caz, saz) = (drift_azimuth.cos(), drift_azimuth.sin());
+    let vel = |r: &TruthRow| Vector3::new(r.lateral_velocity * caz, r.lateral_velocity * saz, r.vv);
+    for i in 0..rows.len() {
+        let (lo, hi) = (i.saturating_sub(1), (i + 1).min(rows.len() - 1));
+        let dt = rows[hi].t - rows[lo].t;
+        rows[i].acc_world = if dt > 0.0 {
+            (vel(&rows[hi]) - vel(&rows[lo])) / dt
+        } else {
+            Vector3::zeros()
+        };
+    }
+}
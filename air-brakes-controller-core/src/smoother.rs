@@ -0,0 +1,548 @@
+//! Post-flight trajectory reconstruction: a Rauch-Tung-Striebel smoother over
+//! a whole flight's IMU, barometer and GPS.
+//!
+//! Everything the board flies is causal, and has to be: the deployment
+//! filter is deliberately slow and lags the boost by hundreds of metres, the
+//! airbrakes filter does not exist until the Mach lockout ends, and neither
+//! may look at a sample it has not been handed yet. After the flight none of
+//! that holds. Every reading is on the card, so the estimate at any instant
+//! can use what came after it as well as what came before — which is exactly
+//! what a forward Kalman pass followed by the RTS backward pass computes, and
+//! the best linear estimate the data supports. It is the reference a flown
+//! estimate is judged against when there is no OpenRocket truth to judge it
+//! by, and the trajectory [`crate::controller`]'s drag tables are fitted to.
+//!
+//! # Model
+//!
+//! Four states: `[altitude ASL, vertical velocity, vertical acceleration,
+//! accelerometer bias]`. The acceleration is a state rather than an input —
+//! unlike [`VerticalKF`](crate::airbrakes_estimator), which predicts with
+//! it — because smoothing it is half of what is asked for: its process
+//! noise is white jerk, and the accelerometer measures acceleration plus
+//! bias. The bias is a slow random walk and soaks up what a gyro-only
+//! attitude gets wrong about which way is up, which is the one error in the
+//! vertical specific force that grows over a flight. Where the barometer is
+//! out — the Mach lockout, typically — the bias is pinned by the readings on
+//! both sides of the gap, which is the case a causal filter cannot have and
+//! the reason the dead-reckoned stretch of a smoothed flight closes onto the
+//! baro instead of stepping to it.
+//!
+//! The vertical specific force comes from the raw IMU the way the airbrakes
+//! estimator's dead reckoner takes it: an [`Attitude`] solved from the pad's
+//! mean gravity, then propagated on bias-corrected gyro from liftoff on.
+//! Gravity is the pad's own magnitude, not 9.81, so the accelerometer's
+//! scale error along the vertical cancels on the pad rather than becoming a
+//! bias the filter has to find.
+//!
+//! Each state also carries the two numbers about the airframe's axis that
+//! [`crate::airframe_fit`] needs and only this pass has: the raw specific
+//! force along the axis, and the axis's tilt from vertical. The axis is the
+//! mean thrust direction over the first half second from liftoff, which is
+//! how the airbrakes estimator's Stage 1 solves the mounting — on the pad
+//! the accelerometer sees the rail's lean, not the airframe's.
+//!
+//! # What the caller decides
+//!
+//! Which readings to offer. A barometer reading the caller knows to be
+//! wrong — inside the Mach lockout, where the static port reads the shock,
+//! or one the deployment gate threw out — is left `None` on its sample,
+//! because this model has no way to tell a lying port from a flight that
+//! did something. The same goes for GPS fixes taken where the receiver's
+//! tracking loops cannot follow the airframe. Every offered fix still goes
+//! through [`GpsAiding`]'s quality gate, and fixes on the pad put the GPS
+//! on the barometer's datum the way the deployment filter does; with none,
+//! no fix is fused.
+
+use core::fmt;
+
+use firmware_common_new::gps::GPSData;
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+use crate::airbrakes_estimator::attitude::Attitude;
+use crate::airbrakes_estimator::{ImuSample, MAX_DT_S};
+use crate::baro_state_estimator::GpsAiding;
+use crate::baro_state_estimator::gps_aiding::GPS_PAD_WINDOW_S;
+
+/// Length of the pad window the attitude, the gyro bias and the altitude
+/// datum are averaged over (s before liftoff) — the airbrakes estimator's
+/// calibration window.
+pub const PAD_WINDOW_S: f32 = 2.0;
+
+/// Length of the boost the airframe axis is taken from (s after liftoff) —
+/// the airbrakes estimator's Stage 1.
+pub const THRUST_AXIS_WINDOW_S: f32 = 0.5;
+
+/// One row of a logged flight, as the smoother reads it.
+#[derive(Debug, Clone)]
+pub struct SmootherSample {
+    pub timestamp_us: u64,
+    pub imu: Option<ImuSample>,
+    /// ISA pressure altitude (m), or `None` where the caller does not trust
+    /// the port — see the module docs.
+    pub baro_altitude_asl: Option<f32>,
+    /// A fix that is new on this sample, or `None`.
+    pub gps: Option<GPSData>,
+}
+
+/// How much each sensor is believed, and how fast the flight may change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmootherConfig {
+    /// Barometric altitude noise, 1 sigma (m).
+    pub baro_sigma_m: f32,
+    /// Vertical specific-force noise, 1 sigma (m/s^2).
+    pub accel_sigma: f32,
+    /// Power spectral density of the white jerk driving the acceleration
+    /// state (m^2/s^5).
+    pub jerk_psd: f32,
+    /// Random walk of the accelerometer bias (m/s^2 per root second).
+    pub bias_walk: f32,
+    /// Accelerometer full scale (m/s^2). A sample with any axis at or past
+    /// it is not fused: a clipped reading says only that the force was at
+    /// least this much.
+    pub accel_full_scale: f32,
+    /// The gate and error model for GPS fixes; `None` fuses none.
+    pub gps: Option<GpsAiding>,
+}
+
+impl Default for SmootherConfig {
+    /// 1 m of baro — the MS5607's bench floor is 0.67 m, and in flight the
+    /// port adds more — and 0.5 m/s^2 of accelerometer, which is vibration,
+    /// not the chip's noise floor. A jerk PSD of 1 puts the acceleration's
+    /// bandwidth near 6 Hz at the fast rate: the drag's slow change and a
+    /// canopy opening come through, the airframe's ringing does not, and a
+    /// step — ignition, burnout — is rounded over a few tens of
+    /// milliseconds either side. The IMU clips at the ±16 g the LSM6DSM is
+    /// configured for.
+    fn default() -> Self {
+        Self {
+            baro_sigma_m: 1.0,
+            accel_sigma: 0.5,
+            jerk_psd: 1.0,
+            bias_walk: 0.05,
+            accel_full_scale: 16.0 * 9.81,
+            gps: Some(GpsAiding::default()),
+        }
+    }
+}
+
+/// The smoothed state on one sample, with its 1-sigma uncertainty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothedState {
+    pub altitude_asl: f32,
+    pub vertical_velocity: f32,
+    /// Kinematic vertical acceleration, gravity and bias removed (m/s^2).
+    pub vertical_acceleration: f32,
+    pub altitude_sigma: f32,
+    pub velocity_sigma: f32,
+    pub acceleration_sigma: f32,
+    /// Specific force along the airframe axis, thrust-positive (m/s^2). The
+    /// sample's own reading, not smoothed; `None` without one, or where it
+    /// clipped. In free flight it is thrust minus drag over mass.
+    pub axial_specific_force: Option<f32>,
+    /// Cosine of the airframe axis's angle from vertical, from the same
+    /// attitude the vertical specific force is taken with.
+    pub tilt_cos: f32,
+}
+
+/// A smoothed flight: one state per input sample, and how well it fits
+/// what it was built from.
+#[derive(Debug, Clone)]
+pub struct Smoothed {
+    pub states: Vec<SmoothedState>,
+    pub baro_fused: usize,
+    pub accel_fused: usize,
+    pub gps_fused: usize,
+    /// Barometer minus GPS on the pad (m): what was added to every fix to
+    /// put it on the baro's datum. `None` when no pad fix passed the gate.
+    pub gps_datum_offset_m: Option<f32>,
+    /// RMS of each fused reading minus the smoothed altitude at its sample.
+    /// A baro residual well above [`SmootherConfig::baro_sigma_m`] says the
+    /// model could not follow the flight, or a lying reading was offered.
+    pub baro_residual_rms_m: Option<f32>,
+    pub gps_residual_rms_m: Option<f32>,
+}
+
+/// Why a flight cannot be smoothed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmootherError {
+    /// `liftoff` is not a sample index.
+    Liftoff { liftoff: usize, samples: usize },
+    /// No IMU sample in the pad window: no gravity to solve the attitude
+    /// from.
+    NoPadImu,
+    /// No barometer reading in the pad window: no altitude to start from.
+    NoPadBaro,
+}
+
+impl fmt::Display for SmootherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Liftoff { liftoff, samples } => {
+                write!(f, "liftoff at sample {liftoff}, but there are {samples}")
+            }
+            Self::NoPadImu => write!(
+                f,
+                "no IMU sample in the {PAD_WINDOW_S} s before liftoff to solve the pad attitude from"
+            ),
+            Self::NoPadBaro => write!(
+                f,
+                "no barometer reading in the {PAD_WINDOW_S} s before liftoff to start the altitude from"
+            ),
+        }
+    }
+}
+
+/// `F` for a step of `dt`: constant acceleration, constant bias.
+#[rustfmt::skip]
+fn transition(dt: f64) -> Matrix4<f64> {
+    Matrix4::new(
+        1.0, dt, 0.5 * dt * dt, 0.0,
+        0.0, 1.0, dt, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// `Q` for a step of `dt`: white jerk integrated into the first three
+/// states, a random walk in the fourth.
+#[rustfmt::skip]
+fn process_noise(dt: f64, config: &SmootherConfig) -> Matrix4<f64> {
+    let q = config.jerk_psd as f64;
+    let (dt2, dt3) = (dt * dt, dt * dt * dt);
+    let (dt4, dt5) = (dt3 * dt, dt3 * dt2);
+    let walk = config.bias_walk as f64;
+    Matrix4::new(
+        q * dt5 / 20.0, q * dt4 / 8.0, q * dt3 / 6.0, 0.0,
+        q * dt4 / 8.0, q * dt3 / 3.0, q * dt2 / 2.0, 0.0,
+        q * dt3 / 6.0, q * dt2 / 2.0, q * dt, 0.0,
+        0.0, 0.0, 0.0, walk * walk * dt,
+    )
+}
+
+/// One scalar measurement `z = h·x + v`, `v ~ N(0, r)`, Joseph form.
+fn fuse(x: &mut Vector4<f64>, p: &mut Matrix4<f64>, h: &Vector4<f64>, z: f64, r: f64) {
+    let ph = *p * h;
+    let s = h.dot(&ph) + r;
+    let k = ph / s;
+    let innovation = z - h.dot(x);
+    *x += k * innovation;
+    let a = Matrix4::identity() - k * h.transpose();
+    *p = a * *p * a.transpose() + k * k.transpose() * r;
+    *p = (*p + p.transpose()) * 0.5;
+}
+
+fn rms(sum_sq: f64, count: usize) -> Option<f32> {
+    (count > 0).then(|| (sum_sq / count as f64).sqrt() as f32)
+}
+
+/// Smooth `samples`, in time order, with the rocket leaving the rail at
+/// sample `liftoff`. Returns one state for every sample.
+pub fn smooth(
+    samples: &[SmootherSample],
+    liftoff: usize,
+    config: &SmootherConfig,
+) -> Result<Smoothed, SmootherError> {
+    if liftoff >= samples.len() {
+        return Err(SmootherError::Liftoff {
+            liftoff,
+            samples: samples.len(),
+        });
+    }
+    let seconds = |i: usize| samples[i].timestamp_us as f64 * 1e-6;
+    let liftoff_s = seconds(liftoff);
+    let before_liftoff =
+        |window_s: f32| (0..liftoff).filter(move |&i| seconds(i) >= liftoff_s - window_s as f64);
+
+    // The pad: gravity and the gyro bias off the IMU, the datum off the baro.
+    let (mut gravity, mut gyro_bias, mut imu_count) = (Vector3::zeros(), Vector3::zeros(), 0);
+    let (mut baro_sum, mut baro_count) = (0.0f64, 0);
+    for i in before_liftoff(PAD_WINDOW_S) {
+        if let Some(imu) = &samples[i].imu {
+            gravity += imu.acc.cast::<f64>();
+            gyro_bias += imu.gyro.cast::<f64>();
+            imu_count += 1;
+        }
+        if let Some(altitude) = samples[i].baro_altitude_asl {
+            baro_sum += altitude as f64;
+            baro_count += 1;
+        }
+    }
+    if imu_count == 0 {
+        return Err(SmootherError::NoPadImu);
+    }
+    if baro_count == 0 {
+        return Err(SmootherError::NoPadBaro);
+    }
+    let gravity = (gravity / imu_count as f64).cast::<f32>();
+    let gyro_bias = (gyro_bias / imu_count as f64).cast::<f32>();
+    let pad_altitude = baro_sum / baro_count as f64;
+    let g = gravity.magnitude();
+    let mut attitude = Attitude::from_pad(&gravity, None);
+    let clipped = |imu: &ImuSample| imu.acc.iter().any(|a| a.abs() >= config.accel_full_scale);
+    let thrust_axis = samples[liftoff..]
+        .iter()
+        .enumerate()
+        .take_while(|&(j, _)| seconds(liftoff + j) < liftoff_s + THRUST_AXIS_WINDOW_S as f64)
+        .filter_map(|(_, sample)| sample.imu.as_ref().filter(|imu| !clipped(imu)))
+        .fold(Vector3::zeros(), |sum, imu| sum + imu.acc)
+        .try_normalize(1e-6)
+        .unwrap_or_else(|| gravity.normalize());
+
+    // The GPS datum: pad fixes against the pad baro, as the deployment
+    // filter's GPS pad reference does it.
+    let gps_datum_offset = config.gps.as_ref().and_then(|gate| {
+        let fixes: Vec<f64> = before_liftoff(GPS_PAD_WINDOW_S)
+            .filter_map(|i| samples[i].gps.as_ref())
+            .filter_map(|fix| gate.measurement(fix))
+            .map(|(altitude, _)| altitude as f64)
+            .collect();
+        (!fixes.is_empty()).then(|| pad_altitude - fixes.iter().sum::<f64>() / fixes.len() as f64)
+    });
+
+    let baro_r = (config.baro_sigma_m as f64).powi(2);
+    let accel_r = (config.accel_sigma as f64).powi(2);
+    let h_altitude = Vector4::new(1.0, 0.0, 0.0, 0.0);
+    let h_accel = Vector4::new(0.0, 0.0, 1.0, 1.0);
+
+    // Forward: the prior and the posterior at every sample, and the step
+    // that led to it, for the backward pass.
+    let n = samples.len();
+    let mut x = Vector4::new(pad_altitude, 0.0, 0.0, 0.0);
+    let mut p = Matrix4::from_diagonal(&Vector4::new(
+        baro_r,
+        1.0,
+        accel_r,
+        (config.accel_sigma as f64).powi(2),
+    ));
+    let mut dts = Vec::with_capacity(n);
+    let mut priors = Vec::with_capacity(n);
+    let mut posteriors = Vec::with_capacity(n);
+    // What was fused on each sample, for the residuals.
+    let mut fused: Vec<(Option<f64>, Option<f64>)> = Vec::with_capacity(n);
+    let mut axial = Vec::with_capacity(n);
+    let (mut accel_fused, mut last_attitude_s) = (0, liftoff_s);
+    for (i, sample) in samples.iter().enumerate() {
+        let dt = if i == 0 {
+            0.0
+        } else {
+            (seconds(i) - seconds(i - 1)).clamp(0.0, MAX_DT_S as f64)
+        };
+        let f = transition(dt);
+        x = f * x;
+        p = f * p * f.transpose() + process_noise(dt, config);
+        dts.push(dt);
+        priors.push((x, p));
+
+        if let Some(imu) = &sample.imu {
+            // On the rail nothing turns; from liftoff the gyro turns the
+            // pad attitude, each step over the time since the last one.
+            if i >= liftoff {
+                let step = (seconds(i) - last_attitude_s).clamp(0.0, MAX_DT_S as f64);
+                attitude.propagate(&(imu.gyro - gyro_bias), step as f32);
+                last_attitude_s = seconds(i);
+            }
+            if !clipped(imu) {
+                let vertical = attitude.up_av().dot(&imu.acc) - g;
+                fuse(&mut x, &mut p, &h_accel, vertical as f64, accel_r);
+                accel_fused += 1;
+            }
+        }
+        axial.push((
+            sample
+                .imu
+                .as_ref()
+                .filter(|imu| !clipped(imu))
+                .map(|imu| imu.acc.dot(&thrust_axis)),
+            attitude.up_av().dot(&thrust_axis),
+        ));
+        let baro = sample
+            .baro_altitude_asl
+            .filter(|a| a.is_finite())
+            .map(|a| a as f64);
+        if let Some(z) = baro {
+            fuse(&mut x, &mut p, &h_altitude, z, baro_r);
+        }
+        let gps = gps_datum_offset
+            .zip(config.gps.as_ref())
+            .and_then(|(offset, gate)| {
+                let (altitude, variance) = gate.measurement(sample.gps.as_ref()?)?;
+                let z = altitude as f64 + offset;
+                fuse(&mut x, &mut p, &h_altitude, z, variance as f64);
+                Some(z)
+            });
+        posteriors.push((x, p));
+        fused.push((baro, gps));
+    }
+
+    // Backward: each posterior corrected by how far the smoothed next state
+    // landed from the prediction made for it. A prior too degenerate to
+    // factor leaves its sample at the forward estimate.
+    for k in (0..n - 1).rev() {
+        let (x_post, p_post) = posteriors[k];
+        let (x_prior, p_prior) = priors[k + 1];
+        let (x_next, p_next) = posteriors[k + 1];
+        let Some(chol) = p_prior.cholesky() else {
+            continue;
+        };
+        let c = chol.solve(&(transition(dts[k + 1]) * p_post)).transpose();
+        let p_smoothed = p_post + c * (p_next - p_prior) * c.transpose();
+        posteriors[k] = (
+            x_post + c * (x_next - x_prior),
+            (p_smoothed + p_smoothed.transpose()) * 0.5,
+        );
+    }
+
+    let (mut baro_sq, mut baro_fused, mut gps_sq, mut gps_fused) = (0.0, 0, 0.0, 0);
+    let states = posteriors
+        .iter()
+        .zip(&fused)
+        .zip(&axial)
+        .map(|(((x, p), (baro, gps)), &(axial_specific_force, tilt_cos))| {
+            if let Some(z) = baro {
+                baro_sq += (z - x[0]).powi(2);
+                baro_fused += 1;
+            }
+            if let Some(z) = gps {
+                gps_sq += (z - x[0]).powi(2);
+                gps_fused += 1;
+            }
+            SmoothedState {
+                altitude_asl: x[0] as f32,
+                vertical_velocity: x[1] as f32,
+                vertical_acceleration: x[2] as f32,
+                altitude_sigma: p[(0, 0)].max(0.0).sqrt() as f32,
+                velocity_sigma: p[(1, 1)].max(0.0).sqrt() as f32,
+                acceleration_sigma: p[(2, 2)].max(0.0).sqrt() as f32,
+                axial_specific_force,
+                tilt_cos,
+            }
+        })
+        .collect();
+    Ok(Smoothed {
+        states,
+        baro_fused,
+        accel_fused,
+        gps_fused,
+        gps_datum_offset_m: gps_datum_offset.map(|o| o as f32),
+        baro_residual_rms_m: rms(baro_sq, baro_fused),
+        gps_residual_rms_m: rms(gps_sq, gps_fused),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::sensors::Rng;
+
+    const DT_US: u64 = 2404;
+
+    /// A vertical flight with an axis-aligned IMU: 5 s on the pad, 3 s of
+    /// 60 m/s^2 boost, then a -12 m/s^2 coast. `(altitude AGL, velocity,
+    /// acceleration)` at `t` seconds after liftoff.
+    fn truth(t: f64) -> (f64, f64, f64) {
+        const BURN_S: f64 = 3.0;
+        if t < 0.0 {
+            (0.0, 0.0, 0.0)
+        } else if t < BURN_S {
+            (30.0 * t * t, 60.0 * t, 60.0)
+        } else {
+            let (h0, v0, s) = (30.0 * BURN_S * BURN_S, 60.0 * BURN_S, t - BURN_S);
+            (h0 + v0 * s - 6.0 * s * s, v0 - 12.0 * s, -12.0)
+        }
+    }
+
+    /// Samples of [`truth`] with noise on every sensor and a 0.1 m/s^2
+    /// vertical accelerometer bias from liftoff, and no barometer from
+    /// `gap.0` to `gap.1` seconds after liftoff. Returns the samples and the
+    /// liftoff index.
+    fn flight(gap: (f64, f64)) -> (Vec<SmootherSample>, usize) {
+        let mut rng = Rng(0x0517_2026_1018_0049);
+        let pad_s = 5.0;
+        let liftoff = (pad_s * 1e6 / DT_US as f64) as usize;
+        let samples = (0..liftoff + (14.0 * 1e6 / DT_US as f64) as usize)
+            .map(|i| {
+                let t = (i as f64 - liftoff as f64) * DT_US as f64 * 1e-6;
+                let (h, _, a) = truth(t);
+                let bias = if t >= 0.0 { 0.1 } else { 0.0 };
+                let acc = Vector3::new(
+                    0.02 * rng.normal(),
+                    0.02 * rng.normal(),
+                    (a + 9.81 + bias) as f32 + 0.3 * rng.normal(),
+                );
+                let baro = 1000.0 + h as f32 + 0.7 * rng.normal();
+                SmootherSample {
+                    timestamp_us: 1_000_000 + i as u64 * DT_US,
+                    imu: Some(ImuSample {
+                        acc,
+                        gyro: Vector3::new(0.01, -0.02, 0.005),
+                    }),
+                    baro_altitude_asl: (t < gap.0 || t >= gap.1).then_some(baro),
+                    gps: None,
+                }
+            })
+            .collect();
+        (samples, liftoff)
+    }
+
+    /// Through a 6 s stretch with no barometer — a Mach lockout — the
+    /// smoothed altitude and velocity stay on the truth, because the
+    /// readings after the gap reach back into it.
+    #[test]
+    fn the_smoother_bridges_a_baro_gap() {
+        let (samples, liftoff) = flight((2.0, 8.0));
+        let smoothed = smooth(&samples, liftoff, &SmootherConfig::default()).unwrap();
+        assert_eq!(smoothed.states.len(), samples.len());
+        let mut worst = (0.0f64, 0.0f64, 0.0f64);
+        for (i, state) in smoothed.states.iter().enumerate() {
+            let t = (i as f64 - liftoff as f64) * DT_US as f64 * 1e-6;
+            // Away from the two steps in acceleration, which a
+            // constant-acceleration model rounds off by construction.
+            if t.abs() < 0.1 || (t - 3.0).abs() < 0.1 {
+                continue;
+            }
+            let (h, v, a) = truth(t);
+            worst.0 = worst.0.max((state.altitude_asl as f64 - 1000.0 - h).abs());
+            worst.1 = worst.1.max((state.vertical_velocity as f64 - v).abs());
+            worst.2 = worst.2.max((state.vertical_acceleration as f64 - a).abs());
+        }
+        assert!(worst.0 < 2.0, "altitude off by up to {} m", worst.0);
+        assert!(worst.1 < 1.0, "velocity off by up to {} m/s", worst.1);
+        assert!(worst.2 < 0.5, "acceleration off by up to {} m/s^2", worst.2);
+        // The axis is the boost's own direction, and the flight never
+        // leaves it.
+        let boost = &smoothed.states[liftoff + (1e6 / DT_US as f64) as usize];
+        let axial = boost.axial_specific_force.unwrap();
+        assert!((axial - 69.91).abs() < 1.5, "axial {axial} m/s^2 in boost");
+        assert!(boost.tilt_cos > 0.999, "tilt cos {}", boost.tilt_cos);
+        let residual = smoothed.baro_residual_rms_m.unwrap();
+        assert!(residual < 1.0, "baro residual {residual} m");
+        assert_eq!(smoothed.gps_fused, 0);
+    }
+
+    #[test]
+    fn a_flight_without_a_pad_is_refused() {
+        let (mut samples, liftoff) = flight((f64::INFINITY, f64::INFINITY));
+        let config = SmootherConfig::default();
+        assert_eq!(
+            smooth(&samples, samples.len(), &config).unwrap_err(),
+            SmootherError::Liftoff {
+                liftoff: samples.len(),
+                samples: samples.len()
+            }
+        );
+        for sample in &mut samples[..liftoff] {
+            sample.baro_altitude_asl = None;
+        }
+        assert_eq!(
+            smooth(&samples, liftoff, &config).unwrap_err(),
+            SmootherError::NoPadBaro
+        );
+        for sample in &mut samples[..liftoff] {
+            sample.imu = None;
+        }
+        assert_eq!(
+            smooth(&samples, liftoff, &config).unwrap_err(),
+            SmootherError::NoPadImu
+        );
+    }
+}
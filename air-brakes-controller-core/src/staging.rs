@@ -0,0 +1,373 @@
+//! Staging: the burns of a two-stage or airstart flight, and the one
+//! decision the flight computer makes about them — whether to light the
+//! sustainer.
+//!
+//! Everything else in the estimators assumes one burn and a coast, and for
+//! most of them that stays true on a staged flight, because they only ever
+//! asked about the first burn or the last one:
+//!
+//! * the deployment half's ignition is the launch. Its Mach lockout runs from
+//!   there, and on a staged airframe it is configured to cover both burns —
+//!   it is a duration, not a measurement, so it needs no burnout;
+//! * the pyro-event table's burnout is the booster's, which is what a
+//!   separation charge is timed from;
+//! * the airbrakes half latches its burnout once and may be born before the
+//!   sustainer lights, which is why [`FlightEstimators`] holds the brakes
+//!   shut until this module says the last burn is out.
+//!
+//! What is new is the sequence between the burns. [`StagingSequencer`] runs
+//! its own [`BurnTracker`] — so ignition and burnout are called again for
+//! every burn, not latched once — and [`StagingConfig::sustainer_ignition_delay_us`]
+//! after the booster's burnout it commands the sustainer igniter, **if** the
+//! airframe is still fit to light a motor: no further off vertical than
+//! [`StagingConfig::max_tilt_rad`] and climbing at least
+//! [`StagingConfig::min_vertical_velocity`]. A sustainer lit on a rocket
+//! that has weathercocked, or that is already falling, drives it into the
+//! ground. Otherwise the sustainer is **inhibited**, and stays so.
+//!
+//! Both numbers come from the airbrakes half — its tilt, and its vertical
+//! velocity, dead-reckoned until its filter exists. That is the one place
+//! that half's output reaches a command, and it can only ever withhold one:
+//! a missing tilt or velocity — a half that never calibrated, or one already
+//! retired at apogee — inhibits. Nothing the airbrakes half computes can
+//! light a motor that the timer alone would not have.
+//!
+//! Everything here is reported as a [`StagingEvent`], one per sample, from
+//! [`FlightEstimators::update`] beside the pyro command. The igniter is the
+//! firmware's to wire; [`StagingEvent::IgniteSustainer`] is the command.
+//!
+//! [`FlightEstimators`]: crate::FlightEstimators
+//! [`FlightEstimators::update`]: crate::FlightEstimators::update
+
+use nalgebra::Vector3;
+
+use crate::baro_state_estimator::RocketState;
+use crate::ignition_detector::{BurnEdge, BurnTracker};
+
+/// How long after the igniter is commanded the sustainer has to be seen
+/// lighting (s of measured time). An igniter lights a motor in tens to a few
+/// hundred milliseconds; one that has done nothing in a second has failed,
+/// and the brakes should not wait on it for the rest of the climb.
+const SUSTAINER_LIGHT_WINDOW_S: f32 = 1.0;
+
+/// A staged flight's configuration. `None` in
+/// [`FlightConfig::staging`](crate::FlightConfig::staging) is a single burn.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagingConfig {
+    /// The sustainer igniter is commanded this long after the booster's
+    /// burnout is called — which itself lags the tail-off by a few tenths of
+    /// a second. Long enough for the separation charge and the stages to
+    /// clear, on a two-stage airframe.
+    pub sustainer_ignition_delay_us: u32,
+    /// Axial specific force (m/s^2, along the pad's up) that counts as a
+    /// motor burning again, once the first has gone out. Unlike the launch
+    /// threshold this is a signed test — drag reads on the other side of
+    /// zero — so it only has to sit clear of noise and well under the
+    /// sustainer's thrust over the airframe's mass.
+    pub sustainer_ignition_acc_threshold: f32,
+    /// The sustainer is inhibited if the airframe is further than this off
+    /// vertical (rad) when it is due.
+    pub max_tilt_rad: f32,
+    /// ...or climbing slower than this (m/s).
+    pub min_vertical_velocity: f32,
+}
+
+/// Something the staging sequence did on this sample.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StagingEvent {
+    /// Burn `burn` lit. Burn 0 is the launch, on the deployment half's
+    /// ignition call.
+    Ignition { burn: u8 },
+    /// Burn `burn` went out.
+    Burnout { burn: u8 },
+    /// Fire the sustainer igniter. Sent once.
+    IgniteSustainer,
+    /// The sustainer was due and the airframe was too far off vertical or
+    /// too slow to light it. It will not be commanded.
+    SustainerInhibited,
+    /// The igniter was commanded and no burn followed within
+    /// `SUSTAINER_LIGHT_WINDOW_S`.
+    SustainerNotLit,
+}
+
+impl StagingEvent {
+    /// `(kind, burn)`, as the SD log's
+    /// [`FlightEvent::Staging`](firmware_common_new::flight_data_record::FlightEvent::Staging)
+    /// stores it. `burn` is 0 for the kinds that have none.
+    pub fn to_parts(self) -> (u8, u8) {
+        match self {
+            Self::Ignition { burn } => (0, burn),
+            Self::Burnout { burn } => (1, burn),
+            Self::IgniteSustainer => (2, 0),
+            Self::SustainerInhibited => (3, 0),
+            Self::SustainerNotLit => (4, 0),
+        }
+    }
+
+    /// The inverse of [`Self::to_parts`]; `None` for a kind this build does
+    /// not know.
+    pub fn from_parts(kind: u8, burn: u8) -> Option<Self> {
+        Some(match kind {
+            0 => Self::Ignition { burn },
+            1 => Self::Burnout { burn },
+            2 => Self::IgniteSustainer,
+            3 => Self::SustainerInhibited,
+            4 => Self::SustainerNotLit,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sustainer {
+    /// Not due yet.
+    Waiting,
+    Commanded {
+        at_us: u64,
+    },
+    Lit,
+    Inhibited,
+    NotLit,
+}
+
+/// [`StagingConfig`], applied. Call [`Self::update`] on every sample.
+#[derive(Debug, Clone)]
+pub struct StagingSequencer {
+    config: StagingConfig,
+    burns: BurnTracker,
+    booster_burnout_us: Option<u64>,
+    sustainer: Sustainer,
+}
+
+impl StagingSequencer {
+    pub fn new(config: StagingConfig) -> Self {
+        Self {
+            burns: BurnTracker::new(Some(config.sustainer_ignition_acc_threshold)),
+            config,
+            booster_burnout_us: None,
+            sustainer: Sustainer::Waiting,
+        }
+    }
+
+    /// Advance by one sample. `acc` is the raw accelerometer vector and
+    /// `state` the deployment half's state after this sample; `tilt_rad` and
+    /// `vertical_velocity` are the airbrakes half's, `None` wherever it has
+    /// none.
+    ///
+    /// One event per sample: a burn edge goes first, and a command due on
+    /// the same sample waits for the next one.
+    pub fn update(
+        &mut self,
+        timestamp_us: u64,
+        acc: Option<Vector3<f32>>,
+        state: &RocketState,
+        tilt_rad: Option<f32>,
+        vertical_velocity: Option<f32>,
+    ) -> Option<StagingEvent> {
+        let launched = !matches!(state, RocketState::OnPad);
+        if let Some(edge) = self.burns.update(timestamp_us, acc, launched) {
+            return Some(match edge {
+                BurnEdge::Ignition { burn } => {
+                    // Lit by our igniter, or by anything else: a motor
+                    // burning is not one to command again.
+                    if burn > 0
+                        && matches!(
+                            self.sustainer,
+                            Sustainer::Waiting | Sustainer::Commanded { .. }
+                        )
+                    {
+                        self.sustainer = Sustainer::Lit;
+                    }
+                    StagingEvent::Ignition { burn }
+                }
+                BurnEdge::Burnout { burn } => {
+                    if burn == 0 {
+                        self.booster_burnout_us = Some(timestamp_us);
+                    }
+                    StagingEvent::Burnout { burn }
+                }
+            });
+        }
+
+        let elapsed_us = |since_us: u64| timestamp_us.saturating_sub(since_us);
+        match self.sustainer {
+            Sustainer::Waiting => {
+                let burnout_us = self.booster_burnout_us?;
+                if elapsed_us(burnout_us) < self.config.sustainer_ignition_delay_us as u64 {
+                    return None;
+                }
+                let upright = tilt_rad.is_some_and(|tilt| tilt <= self.config.max_tilt_rad);
+                let climbing = vertical_velocity
+                    .is_some_and(|velocity| velocity >= self.config.min_vertical_velocity);
+                if upright && climbing {
+                    log_info!("staging: igniting the sustainer");
+                    self.sustainer = Sustainer::Commanded {
+                        at_us: timestamp_us,
+                    };
+                    Some(StagingEvent::IgniteSustainer)
+                } else {
+                    log_warn!(
+                        "staging: sustainer inhibited (tilt {:?} rad, vertical velocity {:?} m/s)",
+                        tilt_rad,
+                        vertical_velocity
+                    );
+                    self.sustainer = Sustainer::Inhibited;
+                    Some(StagingEvent::SustainerInhibited)
+                }
+            }
+            Sustainer::Commanded { at_us }
+                if elapsed_us(at_us) as f32 * 1e-6 >= SUSTAINER_LIGHT_WINDOW_S =>
+            {
+                log_warn!("staging: the sustainer did not light");
+                self.sustainer = Sustainer::NotLit;
+                Some(StagingEvent::SustainerNotLit)
+            }
+            _ => None,
+        }
+    }
+
+    /// Every burn this flight will have is over: the sustainer has been lit
+    /// and gone out, or will not be lit, and nothing is burning now. What
+    /// holds the brakes shut until then.
+    pub fn burns_complete(&self) -> bool {
+        self.burns.coasting()
+            && matches!(
+                self.sustainer,
+                Sustainer::Lit | Sustainer::Inhibited | Sustainer::NotLit
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT_US: u64 = 2404;
+    const G: f32 = 9.81;
+
+    fn config() -> StagingConfig {
+        StagingConfig {
+            sustainer_ignition_delay_us: 1_000_000,
+            sustainer_ignition_acc_threshold: 3.0 * G,
+            max_tilt_rad: 10f32.to_radians(),
+            min_vertical_velocity: 150.0,
+        }
+    }
+
+    /// 5 s on the pad, a 3 s booster, and the sustainer lighting `relight_s`
+    /// after ignition (never, for `None`) for 4 s. Returns every event and
+    /// the time it came (s after ignition), and whether the burns were
+    /// complete at the end.
+    fn sequence(
+        config: StagingConfig,
+        relight_s: Option<f32>,
+        tilt_rad: f32,
+        vertical_velocity: f32,
+    ) -> (Vec<(f32, StagingEvent)>, bool) {
+        let up = Vector3::new(0.1, -0.2, 0.97).normalize();
+        let mut sequencer = StagingSequencer::new(config);
+        let mut events = Vec::new();
+        let mut t_us = 0u64;
+        while t_us < 20_000_000 {
+            let t = t_us as f32 * 1e-6 - 5.0;
+            let state = if t < 0.0 {
+                RocketState::OnPad
+            } else {
+                RocketState::MachLockout {
+                    launch_pad_altitude_asl: 0.0,
+                }
+            };
+            let burning =
+                (0.0..3.0).contains(&t) || relight_s.is_some_and(|at| (at..at + 4.0).contains(&t));
+            let specific_force = if t < 0.0 {
+                G
+            } else if burning {
+                80.0
+            } else {
+                -6.0
+            };
+            let flying = t >= 0.0;
+            if let Some(event) = sequencer.update(
+                t_us,
+                Some(up * specific_force),
+                &state,
+                flying.then_some(tilt_rad),
+                flying.then_some(vertical_velocity),
+            ) {
+                events.push((t, event));
+            }
+            t_us += DT_US;
+        }
+        (events, sequencer.burns_complete())
+    }
+
+    #[test]
+    fn every_burn_is_called_and_the_sustainer_commanded_once() {
+        let (events, complete) = sequence(config(), Some(4.5), 0.05, 300.0);
+        let kinds: Vec<StagingEvent> = events.iter().map(|(_, e)| *e).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StagingEvent::Ignition { burn: 0 },
+                StagingEvent::Burnout { burn: 0 },
+                StagingEvent::IgniteSustainer,
+                StagingEvent::Ignition { burn: 1 },
+                StagingEvent::Burnout { burn: 1 },
+            ]
+        );
+        // The booster's burnout latches 0.3 s after it, the command a second
+        // later, and the relight 0.1 s after it begins.
+        assert!((events[1].0 - 3.3).abs() < 0.01, "{events:?}");
+        assert!((events[2].0 - 4.3).abs() < 0.01, "{events:?}");
+        assert!((events[3].0 - 4.6).abs() < 0.01, "{events:?}");
+        assert!((events[4].0 - 8.8).abs() < 0.01, "{events:?}");
+        assert!(complete);
+    }
+
+    /// Too far off vertical, or too slow, and the igniter is never fired.
+    #[test]
+    fn an_unfit_airframe_inhibits_the_sustainer() {
+        for (tilt_rad, vertical_velocity) in [(0.5, 300.0), (0.05, 100.0)] {
+            let (events, complete) = sequence(config(), None, tilt_rad, vertical_velocity);
+            let kinds: Vec<StagingEvent> = events.iter().map(|(_, e)| *e).collect();
+            assert_eq!(
+                kinds,
+                vec![
+                    StagingEvent::Ignition { burn: 0 },
+                    StagingEvent::Burnout { burn: 0 },
+                    StagingEvent::SustainerInhibited,
+                ],
+                "tilt {tilt_rad}, velocity {vertical_velocity}"
+            );
+            assert!(complete);
+        }
+    }
+
+    /// A commanded sustainer that never lights is given up on, and the
+    /// burns are complete from then.
+    #[test]
+    fn a_sustainer_that_does_not_light_is_given_up_on() {
+        let (events, complete) = sequence(config(), None, 0.05, 300.0);
+        assert_eq!(events.len(), 4, "{events:?}");
+        assert_eq!(events[2].1, StagingEvent::IgniteSustainer);
+        assert_eq!(events[3].1, StagingEvent::SustainerNotLit);
+        assert!((events[3].0 - events[2].0 - SUSTAINER_LIGHT_WINDOW_S).abs() < 0.01);
+        assert!(complete);
+    }
+
+    #[test]
+    fn events_survive_their_log_encoding() {
+        for event in [
+            StagingEvent::Ignition { burn: 1 },
+            StagingEvent::Burnout { burn: 2 },
+            StagingEvent::IgniteSustainer,
+            StagingEvent::SustainerInhibited,
+            StagingEvent::SustainerNotLit,
+        ] {
+            let (kind, burn) = event.to_parts();
+            assert_eq!(StagingEvent::from_parts(kind, burn), Some(event));
+        }
+    }
+}
@@ -34,8 +34,9 @@
 //! the table.
 
 use crate::airbrakes_estimator::AirbrakesConfig;
-use crate::baro_state_estimator::{DeploymentProfile, FlightProfile};
+use crate::baro_state_estimator::{DeploymentProfile, FlightProfile, LandingDetection};
 use crate::controller::RocketParameters;
+use crate::pyro_events::PyroEvents;
 
 /// The ignition threshold for tests that are not about ignition.
 ///
@@ -72,24 +73,28 @@ pub fn subsonic_profile() -> FlightProfile {
             minimum_deployment_altitude_agl: 300.0,
             delay_us: 0,
         },
+        landing: LandingDetection::default(),
+        pyro_events: PyroEvents::new(),
+        gps_aiding: None,
     }
 }
 
 /// The LC'25 airframe — the one both replayed flights fly, and the one the
 /// drag check inverts.
 ///
-/// `cd[0] * reference_area / burnout_mass` = 2.4e-4, which the flight
+/// Stowed, `cd * reference_area / burnout_mass` = 2.4e-4, which the flight
 /// itself corroborates: measured drag deceleration over dynamic pressure
 /// sits at 0.00022-0.00026 across the whole subsonic coast, and rises about
 /// 40% through the transonic peak — which is why inverting with the
 /// SUBSONIC Cd makes the check read high exactly while supersonic (see
-/// [`AirbrakesConfig::rocket`]).
+/// [`AirbrakesConfig::rocket`]). The table is a single Mach point, so it is
+/// the same at every Mach.
 pub fn lc25_rocket() -> RocketParameters {
-    RocketParameters {
-        burnout_mass: 17.607,
-        cd: [0.47044, 0.5082, 0.57784, 0.665, 0.74313],
-        reference_area: 0.008982476,
-    }
+    RocketParameters::mach_independent(
+        17.607,
+        [0.47044, 0.5082, 0.57784, 0.665, 0.74313],
+        0.008982476,
+    )
 }
 
 /// The airbrakes half on the LC'25 airframe with **no Mach lockout**.
@@ -102,7 +107,7 @@ pub fn lc25_rocket() -> RocketParameters {
 /// test.
 ///
 /// `max_open_mach` is 0.8 because that is the Mach [`lc25_rocket`]'s stowed
-/// `cd[0]` is tabulated at; the two have to agree, and pairing them here is
+/// Cd is tabulated at; the two have to agree, and pairing them here is
 /// the reason they cannot drift apart across the four call sites.
 ///
 /// The supersonic replay overrides `mach_lockout` at its own call site —
@@ -22,7 +22,7 @@
 //! density, speed of sound, Mach, local gravity. That is the whole
 //! trajectory — nothing about it is guessed.
 //!
-//! Invented here, and only here:
+//! Invented in [`crate::sim::sensors`], and only there:
 //!
 //! * **Sensor noise, bias and quantisation.** Sized from the Void Lake pad
 //!   segment (see [`SensorModel`] for the measured numbers), and quantised
@@ -31,6 +31,9 @@
 //!   design, which is not a rocket. A spin-up/decay profile peaking at
 //!   1 rev/s is added, and the accelerometer and gyro are both generated
 //!   from the same rolling attitude.
+//! * **The magnetic field.** OpenRocket has none. A fixed field is used,
+//!   with the world Y axis as magnetic north — see
+//!   [`EARTH_FIELD_T`](crate::sim::sensors::EARTH_FIELD_T).
 //! * **The IMU mounting orientation.** A fixed, deliberately ugly rotation
 //!   between the airframe and the chip, so the estimator's pad
 //!   self-calibration has something to find.
@@ -46,817 +49,42 @@
 //! pitch/yaw rates, and [`sensor_model_matches_openrocket_forces`] checks
 //! the synthesised specific force against OpenRocket's own thrust and drag.
 //! If either drifts, every number below is worthless and the test says so.
+//!
+//! The truth, the sensors and the replay live in [`crate::sim`], which this
+//! file grew into: the same machinery flies `rocket-cli dispersion`'s
+//! randomised runs, and the tests here are what keep it honest.
 
 use core::f32::consts::PI;
 
-use icao_isa::calculate_isa_altitude;
-use icao_units::si::Pascals;
 use nalgebra::{UnitQuaternion, Vector2, Vector3};
 
-use crate::airbrakes_estimator::{AirbrakesConfig, ImuSample, MachLockoutConfig};
-use crate::baro_state_estimator::{DeploymentProfile, FlightProfile};
-use crate::controller::{AirBrakesMPC, RocketParameters};
+use crate::atmosphere::Atmosphere;
+use crate::backup_deployment::DeploymentPolicy;
+use crate::baro_state_estimator::DeploymentProfile;
+use crate::controller::{
+    AirBrakesMPC, ControllerMode, RocketParameters, ServoModel, ServoState, WIND_BINS, WindProfile,
+};
 use crate::flight_estimators::{FlightConfig, FlightEstimators};
+use crate::sim::osiris::{
+    MAX_OPEN_MACH, N2900_CSV, O3400_CSV, OSIRIS_CD_MACH, osiris_backup_deployment, osiris_config,
+    osiris_rocket, osiris_rocket_mach_table,
+};
+use crate::sim::replay::{mean_error, replay};
+use crate::sim::sensors::{
+    SensorModel, attitude, axis_aligned_mounting, body_rates, imu_mounting, synthesize,
+};
+use crate::sim::truth::{Airstart, TrajectoryDispersion, Truth};
+use crate::staging::{StagingConfig, StagingEvent};
 use crate::tests::init_logger;
 use crate::utils::{approximate_air_density, approximate_speed_of_sound};
 
-// ---------------------------------------------------------------------------
-// The flight config under test
-// ---------------------------------------------------------------------------
-
-/// The airframe as flown, copied from `VLF5/firmware/src/main.rs`
-/// (`FLIGHT_CONFIG.airbrakes.rocket`). `cd` is the STAR-CCM+ table from FDR
-/// Table 10 converted to coefficients; `reference_area` is OpenRocket's own
-/// reference area for this airframe, which the CSVs carry in
-/// `reference_area_m2` — [`config_matches_the_simulated_airframe`] checks
-/// the two against each other.
-fn osiris_rocket() -> RocketParameters {
-    RocketParameters {
-        burnout_mass: 18.696,
-        cd: [0.61365, 0.69816, 0.8084, 0.96641, 1.12441],
-        reference_area: 0.009854945,
-    }
-}
-
-/// `FLIGHT_CONFIG` from `VLF5/firmware/src/main.rs`, verbatim.
-///
-/// Duplicated rather than imported because that constant lives in the
-/// firmware crate, which does not build for the host. Everything the
-/// numbers claim about the trajectory is asserted against the simulations
-/// in [`mach_lockout_timers_bracket_every_simulation`], so a copy that
-/// drifts out of date fails loudly rather than quietly passing.
-fn osiris_config() -> FlightConfig {
-    FlightConfig {
-        // Mirrors VLF5's `FLIGHT_CONFIG`. One field, both halves — see
-        // `crate::ignition_detector`.
-        ignition_detection_acc_threshold: 8.0 * 9.81,
-        profile: FlightProfile {
-            mach_lockout_duration_us: Some(26_000_000),
-            deployment: DeploymentProfile::Dual {
-                drogue_chute_minimum_altitude_agl: 2000.0,
-                drogue_chute_delay_us: 1_000_000,
-                main_chute_altitude_agl: 457.2,
-                main_chute_delay_us: 0,
-            },
-        },
-        airbrakes: AirbrakesConfig {
-            mach_lockout: Some(MachLockoutConfig {
-                earliest_subsonic_after_ignition_us: 17_200_000,
-                force_birth_after_ignition_us: 25_000_000,
-                subsonic_crossing_altitude_asl: 6800.0,
-            }),
-            max_open_mach: MAX_OPEN_MACH,
-            rocket: osiris_rocket(),
-        },
-    }
-}
-
-/// The Mach the flaps are permitted to open at, in one place: the config
-/// below and every assertion that references "the crossing" read it, so
-/// raising it cannot leave a test still asserting against the old value.
-///
-/// It is deliberately ABOVE the Mach the Cd table is tabulated at (0.8) —
-/// see `AirbrakesConfig::max_open_mach`. 0.83 is not a round number: it is
-/// the largest value `transonic_static_port_error_is_absorbed_by_the_lockout`
-/// still passes at, and that test is what bounds it.
-const MAX_OPEN_MACH: f32 = 0.83;
-
-const O3400_CSV: &str = "./test_data/osiris_o3400.csv";
-const N2900_CSV: &str = "./test_data/osiris_n2900.csv";
-
-// The estimators never see a geometric altitude — they see whatever
-// `calculate_isa_altitude` makes of the pressure, and so does every number
-// they are scored against here. That is not the same as the site's real
-// altitude: this launch day is 102066 Pa at a site 363.6 m ASL, which plain
-// ISA reads as -63 m. Scoring against the geometric altitude instead would
-// charge the estimator hundreds of metres for the atmosphere being warmer
-// than standard, which is not its job to know.
-
-// ---------------------------------------------------------------------------
-// Truth: the OpenRocket trajectory
-// ---------------------------------------------------------------------------
-
-#[derive(Debug, Clone, Copy, Default)]
-struct TruthRow {
-    t: f32,
-    /// Geometric altitude above the pad, from OpenRocket. Reported only;
-    /// nothing is scored against it — see [`TruthRow::altitude_asl`].
-    altitude_agl: f32,
-    /// Pressure altitude: the ISA altitude of this row's static pressure.
-    /// This is the truth every estimator output is compared against,
-    /// because it is the only altitude the barometer can express.
-    altitude_asl: f32,
-    vv: f32,
-    lateral_velocity: f32,
-    /// Angle of the airframe from the horizontal plane (OpenRocket's
-    /// "vertical orientation (zenith)"), radians. Tilt from vertical is
-    /// `FRAC_PI_2 - zenith`.
-    zenith: f32,
-    azimuth: f32,
-    /// OpenRocket's own body rates, used only to validate the attitude model.
-    pitch_rate: Option<f32>,
-    yaw_rate: Option<f32>,
-    mass: f32,
-    thrust: f32,
-    drag: f32,
-    pressure: f32,
-    density: f32,
-    speed_of_sound: f32,
-    mach: f32,
-    gravity: f32,
-    reference_area: f32,
-    /// World-frame kinematic acceleration, differentiated from the
-    /// reconstructed velocity vector at load time (see [`Truth::load`]).
-    acc_world: Vector3<f32>,
-}
-
-struct Truth {
-    rows: Vec<TruthRow>,
-}
-
-impl Truth {
-    fn load(path: &str) -> Self {
-        #[derive(serde::Deserialize)]
-        struct CsvRow {
-            time_s: f32,
-            altitude_agl_m: f32,
-            vv_mps: f32,
-            lateral_velocity_mps: f32,
-            zenith_rad: f32,
-            azimuth_rad: f32,
-            pitch_rate_rps: Option<f32>,
-            yaw_rate_rps: Option<f32>,
-            mass_kg: f32,
-            thrust_n: f32,
-            drag_n: f32,
-            pressure_pa: f32,
-            density_kgm3: f32,
-            speed_of_sound_mps: f32,
-            mach: f32,
-            gravity_mps2: f32,
-            reference_area_m2: f32,
-        }
-
-        let mut rows: Vec<TruthRow> = csv::Reader::from_path(path)
-            .unwrap()
-            .deserialize::<CsvRow>()
-            .map(|r| r.unwrap())
-            .map(|r| TruthRow {
-                t: r.time_s,
-                altitude_agl: r.altitude_agl_m,
-                altitude_asl: calculate_isa_altitude(Pascals(r.pressure_pa as f64)).0 as f32,
-                vv: r.vv_mps,
-                lateral_velocity: r.lateral_velocity_mps,
-                zenith: r.zenith_rad,
-                azimuth: r.azimuth_rad,
-                pitch_rate: r.pitch_rate_rps,
-                yaw_rate: r.yaw_rate_rps,
-                mass: r.mass_kg,
-                thrust: r.thrust_n,
-                drag: r.drag_n,
-                pressure: r.pressure_pa,
-                density: r.density_kgm3,
-                speed_of_sound: r.speed_of_sound_mps,
-                mach: r.mach,
-                gravity: r.gravity_mps2,
-                reference_area: r.reference_area_m2,
-                acc_world: Vector3::zeros(),
-            })
-            .collect();
-        assert!(rows.len() > 1000, "{path}: only {} rows", rows.len());
-
-        // Kinematic acceleration, central-differenced from the world
-        // velocity vector the same columns define. Doing it here rather
-        // than reading OpenRocket's acceleration columns keeps the
-        // accelerometer exactly consistent with the velocity and altitude
-        // the rest of the model uses — the sensor stream cannot disagree
-        // with the trajectory it came from.
-        // `sensor_model_matches_openrocket_forces` checks the result
-        // against OpenRocket's independent thrust and drag.
-        //
-        // The horizontal component is laid along ONE fixed azimuth, taken
-        // at burnout, rather than along each row's own airframe azimuth.
-        // The airframe azimuth is the direction the rocket is *tilted*,
-        // and it swings by ~10 deg over the coast; hanging the drift
-        // velocity off it would rotate that vector and manufacture a
-        // lateral acceleration of several m/s^2 that the rocket never
-        // felt. The real drift direction barely moves, so a constant is
-        // both simpler and closer to the truth — and the vertical channel,
-        // which is the one every estimator output depends on, is
-        // untouched by the choice.
-        let drift_azimuth = {
-            let burnout_t = rows
-                .iter()
-                .filter(|r| r.thrust > 0.0)
-                .map(|r| r.t)
-                .fold(0.0f32, f32::max);
-            rows.iter()
-                .find(|r| r.t >= burnout_t)
-                .map(|r| r.azimuth)
-                .unwrap_or(0.0)
-        };
-        let (caz, saz) = (drift_azimuth.cos(), drift_azimuth.sin());
-        let vel = |r: &TruthRow| {
-            Vector3::new(r.lateral_velocity * caz, r.lateral_velocity * saz, r.vv)
-        };
-        for i in 0..rows.len() {
-            let (lo, hi) = (i.saturating_sub(1), (i + 1).min(rows.len() - 1));
-            let dt = rows[hi].t - rows[lo].t;
-            rows[i].acc_world = if dt > 0.0 {
-                (vel(&rows[hi]) - vel(&rows[lo])) / dt
-            } else {
-                Vector3::zeros()
-            };
-        }
-
-        Self { rows }
-    }
-
-    /// Linear interpolation, clamped at both ends.
-    fn at(&self, t: f32) -> TruthRow {
-        let rows = &self.rows;
-        if t <= rows[0].t {
-            return rows[0];
-        }
-        if t >= rows[rows.len() - 1].t {
-            return rows[rows.len() - 1];
-        }
-        let i = rows.partition_point(|r| r.t <= t).max(1);
-        let (a, b) = (&rows[i - 1], &rows[i]);
-        let s = (t - a.t) / (b.t - a.t);
-        let l = |x: f32, y: f32| x + (y - x) * s;
-        TruthRow {
-            t,
-            altitude_agl: l(a.altitude_agl, b.altitude_agl),
-            altitude_asl: l(a.altitude_asl, b.altitude_asl),
-            vv: l(a.vv, b.vv),
-            lateral_velocity: l(a.lateral_velocity, b.lateral_velocity),
-            zenith: l(a.zenith, b.zenith),
-            azimuth: l(a.azimuth, b.azimuth),
-            pitch_rate: match (a.pitch_rate, b.pitch_rate) {
-                (Some(x), Some(y)) => Some(l(x, y)),
-                _ => None,
-            },
-            yaw_rate: match (a.yaw_rate, b.yaw_rate) {
-                (Some(x), Some(y)) => Some(l(x, y)),
-                _ => None,
-            },
-            mass: l(a.mass, b.mass),
-            thrust: l(a.thrust, b.thrust),
-            drag: l(a.drag, b.drag),
-            pressure: l(a.pressure, b.pressure),
-            density: l(a.density, b.density),
-            speed_of_sound: l(a.speed_of_sound, b.speed_of_sound),
-            mach: l(a.mach, b.mach),
-            gravity: l(a.gravity, b.gravity),
-            reference_area: l(a.reference_area, b.reference_area),
-            acc_world: a.acc_world + (b.acc_world - a.acc_world) * s,
-        }
-    }
-
-    fn last_t(&self) -> f32 {
-        self.rows[self.rows.len() - 1].t
-    }
-
-    /// Pad pressure altitude — the estimators' zero.
-    fn pad_asl(&self) -> f32 {
-        self.rows[0].altitude_asl
-    }
-
-    /// (time, pressure altitude ASL) of the true apogee.
-    fn apogee(&self) -> (f32, f32) {
-        let r = self
-            .rows
-            .iter()
-            .max_by(|a, b| a.altitude_asl.total_cmp(&b.altitude_asl))
-            .unwrap();
-        (r.t, r.altitude_asl)
-    }
-
-    fn burnout_t(&self) -> f32 {
-        // last row still producing thrust
-        self.rows
-            .iter()
-            .filter(|r| r.thrust > 0.0)
-            .map(|r| r.t)
-            .fold(0.0, f32::max)
-    }
-
-    /// Time the axial specific force `(thrust - drag)/mass` crosses zero
-    /// downward — the signal the estimator's burnout latch actually
-    /// watches, which on a long tail-off at high Mach is NOT the motor
-    /// burning out. See [`nominal_o3400_flight`].
-    fn axial_zero_crossing(&self) -> f32 {
-        for w in self.rows.windows(2) {
-            let (a, b) = (w[0].thrust - w[0].drag, w[1].thrust - w[1].drag);
-            if w[0].t > 1.0 && a > 0.0 && b <= 0.0 {
-                return w[0].t + a / (a - b) * (w[1].t - w[0].t);
-            }
-        }
-        panic!("the axial channel never went negative");
-    }
-
-    /// Time of the coast-side downward crossing of `mach`.
-    fn mach_down_crossing(&self, mach: f32) -> f32 {
-        let burnout = self.burnout_t();
-        for w in self.rows.windows(2) {
-            if w[0].t <= burnout {
-                continue;
-            }
-            if w[0].mach > mach && w[1].mach <= mach {
-                let s = (w[0].mach - mach) / (w[0].mach - w[1].mach);
-                return w[0].t + s * (w[1].t - w[0].t);
-            }
-        }
-        panic!("never crossed Mach {mach} on the coast");
-    }
-
-    /// Time the descent passes down through `agl` metres.
-    fn descent_crossing_agl(&self, agl: f32) -> f32 {
-        let (apogee_t, _) = self.apogee();
-        for w in self.rows.windows(2) {
-            if w[0].t <= apogee_t {
-                continue;
-            }
-            let (a0, a1) = (
-                w[0].altitude_asl - self.pad_asl(),
-                w[1].altitude_asl - self.pad_asl(),
-            );
-            if a0 > agl && a1 <= agl {
-                let s = (a0 - agl) / (a0 - a1);
-                return w[0].t + s * (w[1].t - w[0].t);
-            }
-        }
-        panic!("descent never reached {agl} m AGL");
-    }
-}
-
-// ---------------------------------------------------------------------------
-// Sensors
-// ---------------------------------------------------------------------------
-
-/// xorshift64*, so the runs are deterministic without a `rand` dependency.
-struct Rng(u64);
-
-impl Rng {
-    fn next_u64(&mut self) -> u64 {
-        let mut x = self.0;
-        x ^= x >> 12;
-        x ^= x << 25;
-        x ^= x >> 27;
-        self.0 = x;
-        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
-    }
-
-    fn uniform(&mut self) -> f32 {
-        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
-    }
-
-    /// Standard normal, Box-Muller.
-    fn normal(&mut self) -> f32 {
-        let u1 = self.uniform().max(1e-7);
-        let u2 = self.uniform();
-        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
-    }
-}
-
-/// Everything invented about the sensors, in one place.
-///
-/// The IMU noise is measured, not guessed: `VLF5/firmware`'s `imu_bench`
-/// binary run against the flight board's own LSM6DSM, 57 stationary 2 s
-/// windows over 114 s, median window sigma per axis. It is quite unlike the
-/// Void Lake pad figures these tests first used — the accelerometer is
-/// 4-10x quieter than that flight's pad (which had rail sway and a live
-/// motor next to it), while gyro X is nearly twice as noisy. Static
-/// pressure is still the Void Lake pad's 5.5 Pa RMS; the MS5607 bench
-/// measurement lives in `hil/baro_sim.rs` as 0.36 m, which is the same
-/// number in altitude form.
-struct SensorModel {
-    /// Accelerometer full scale, m/s^2. The LSM6DSM is configured for
-    /// +-16 g in `drivers/lsm6dsm.rs`, and Osiris exceeds that in the
-    /// middle of the burn — see [`clipped_accel_still_flies_the_profile`].
-    accel_full_scale: f32,
-    /// Per-axis, because the measured axes are genuinely not equal.
-    accel_noise: Vector3<f32>,
-    gyro_noise_rad_s: Vector3<f32>,
-    gyro_bias_rad_s: Vector3<f32>,
-    /// RMS of the pressure noise, Pa.
-    pressure_noise_pa: f32,
-    /// Peak static-port pressure error as a fraction of dynamic pressure.
-    /// Zero disables the transonic error entirely.
-    transonic_port_error: f32,
-    /// Rotation from the airframe frame into the IMU chip frame.
-    mount: UnitQuaternion<f32>,
-    /// Seconds of pad prepended before ignition.
-    pad_s: f32,
-    /// Stop generating samples after this truth time.
-    until_s: f32,
-    /// Nominal sample interval (us). 2404 is the 416 Hz the firmware
-    /// assumes; the flight board's LSM6DSM actually delivers 2342 (427 Hz),
-    /// which `imu_bench` measured.
-    sample_dt_us: u64,
-    seed: u64,
-}
-
-impl Default for SensorModel {
-    fn default() -> Self {
-        Self {
-            accel_full_scale: f32::INFINITY,
-            accel_noise: Vector3::new(0.0147, 0.0190, 0.0359),
-            gyro_noise_rad_s: Vector3::new(0.448, 0.181, 0.048) * (PI / 180.0),
-            // a real, constant, uncalibrated gyro bias — around a degree
-            // per second per axis, which is what the pad calibration is for
-            gyro_bias_rad_s: Vector3::new(1.15, -1.93, -0.45) * (PI / 180.0),
-            pressure_noise_pa: 5.5,
-            mount: imu_mounting(),
-            transonic_port_error: 0.0,
-            sample_dt_us: 2404,
-            pad_s: 60.0,
-            until_s: f32::INFINITY,
-            seed: 0x0517_2026_0626_0001,
-        }
-    }
-}
-
-/// LSM6DSM +-16 g / +-2000 dps LSBs, from `drivers/lsm6dsm.rs`.
-const ACCEL_LSB: f32 = 16.0 / 32768.0 * 9.81;
-const GYRO_LSB_RAD_S: f32 = (2000.0 / 32768.0) * PI / 180.0;
-/// The MS5607 driver reports pressure as a whole number of Pa.
-const PRESSURE_LSB_PA: f32 = 1.0;
-
-/// The default mounting rotation between the airframe and the IMU chip.
-/// Chosen to be nothing like identity so the pad self-calibration has real
-/// work to do.
-fn imu_mounting() -> UnitQuaternion<f32> {
-    UnitQuaternion::from_euler_angles(0.31, -0.22, 2.4)
-}
-
-/// A board mounted the way a board actually gets mounted: chip +Z along the
-/// airframe axis, a few degrees out. This is the WORST case for
-/// accelerometer clipping, because the whole axial specific force lands on
-/// one channel instead of being shared across three.
-fn axis_aligned_mounting() -> UnitQuaternion<f32> {
-    UnitQuaternion::from_euler_angles(0.05, -0.03, 0.7)
-}
-
-/// Roll rate (rad/s) at truth time `t`: spin-up over the burn to 1 rev/s,
-/// then a slow decay. Entirely invented — OpenRocket reports zero roll for
-/// this design, and a rocket that does not roll is not a useful test of a
-/// gyro-integrating estimator.
-fn roll_rate(t: f32, burnout_t: f32) -> f32 {
-    if t <= 0.0 {
-        return 0.0;
-    }
-    let spin_up = 1.0 - (-t / 2.0).exp();
-    let decay = (-(t - burnout_t).max(0.0) / 25.0).exp();
-    2.0 * PI * spin_up * decay
-}
-
-/// Airframe attitude at truth time `t`: `q * body_vector = world_vector`,
-/// with body +Z along the nose.
-///
-/// `Rz(azimuth) * Ry(tilt) * Rz(roll)`, so body +Z lands on
-/// `(sin(tilt)cos(az), sin(tilt)sin(az), cos(tilt))` — the airframe axis —
-/// and the last factor spins the chip about it.
-fn attitude(truth: &TruthRow, roll: f32) -> UnitQuaternion<f32> {
-    let tilt = core::f32::consts::FRAC_PI_2 - truth.zenith;
-    UnitQuaternion::from_axis_angle(&Vector3::z_axis(), truth.azimuth)
-        * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), tilt)
-        * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), roll)
-}
-
-/// Body-frame angular velocity for the same parameterisation, analytically:
-/// `w_world = az_dot * Z + tilt_dot * Rz(az) Y + roll_dot * axis`, rotated
-/// into the body frame. [`orientation_model_matches_openrocket`] checks
-/// this against OpenRocket's own pitch and yaw rates.
-fn body_rates(
-    truth: &TruthRow,
-    az_dot: f32,
-    tilt_dot: f32,
-    roll_dot: f32,
-    q: &UnitQuaternion<f32>,
-) -> Vector3<f32> {
-    let az = truth.azimuth;
-    let tilt = core::f32::consts::FRAC_PI_2 - truth.zenith;
-    let axis_world = Vector3::new(
-        tilt.sin() * az.cos(),
-        tilt.sin() * az.sin(),
-        tilt.cos(),
-    );
-    let w_world = Vector3::z() * az_dot
-        + Vector3::new(-az.sin(), az.cos(), 0.0) * tilt_dot
-        + axis_world * roll_dot;
-    q.inverse_transform_vector(&w_world)
-}
-
-/// One generated sample: what the firmware's estimator loop would see,
-/// plus the truth it was generated from so the test can score it.
-struct Sample {
-    t_us: u64,
-    /// Truth time; negative while still on the pad.
-    truth_t: f32,
-    imu: ImuSample,
-    baro_altitude_asl: f32,
-    truth: TruthRow,
-    /// Set when the accelerometer full scale clipped this sample.
-    clipped: bool,
-}
-
-/// Generate the sensor stream: 416 Hz nominal with jitter, a quiet pad
-/// segment, then the trajectory.
-fn synthesize(truth: &Truth, model: &SensorModel) -> Vec<Sample> {
-    let mut rng = Rng(model.seed | 1);
-    let burnout_t = truth.burnout_t();
-    let end_t = model.until_s.min(truth.last_t());
-
-    // Attitude derivatives come from differentiating the truth at the
-    // sample time; h is well under one OpenRocket step (5 ms on ascent).
-    const H: f32 = 0.001;
-
-    let mut samples = Vec::new();
-    let mut t_us: u64 = 0;
-    let mut roll = 0.0f32;
-    let mut prev_truth_t = -model.pad_s;
-
-    loop {
-        let truth_t = (t_us as f32) * 1e-6 - model.pad_s;
-        if truth_t > end_t {
-            break;
-        }
-
-        roll += roll_rate(truth_t, burnout_t) * (truth_t - prev_truth_t);
-        prev_truth_t = truth_t;
-
-        // --- truth state, with the pad standing in before ignition ------
-        let on_pad = truth_t < 0.0;
-        let mut r = truth.at(truth_t.max(0.0));
-        if on_pad {
-            // Rail sway: a small, slow tilt oscillation, and no motion.
-            r.vv = 0.0;
-            r.lateral_velocity = 0.0;
-            r.acc_world = Vector3::zeros();
-            r.zenith += 0.03f32.to_radians() * (2.0 * PI * 0.7 * truth_t).sin();
-            r.mach = 0.0;
-        }
-
-        // --- attitude and rates ----------------------------------------
-        let q = attitude(&r, roll);
-        let (tilt_dot, az_dot) = {
-            let a = truth.at((truth_t - H).max(0.0));
-            let b = truth.at((truth_t + H).max(0.0));
-            if on_pad {
-                // differentiate the sway analytically instead
-                let w = 2.0 * PI * 0.7;
-                (
-                    -0.03f32.to_radians() * w * (w * truth_t).cos(),
-                    0.0,
-                )
-            } else {
-                (
-                    -(b.zenith - a.zenith) / (2.0 * H),
-                    (b.azimuth - a.azimuth) / (2.0 * H),
-                )
-            }
-        };
-        let w_body = body_rates(&r, az_dot, tilt_dot, roll_rate(truth_t, burnout_t), &q);
-
-        // --- specific force --------------------------------------------
-        // The accelerometer measures specific force: kinematic acceleration
-        // minus gravity. On the pad that is exactly +g up.
-        let sf_body = {
-            let sf_world = r.acc_world + Vector3::new(0.0, 0.0, r.gravity);
-            q.inverse_transform_vector(&sf_world)
-        };
-
-        // --- into the chip frame, then through the chip -----------------
-        let mut acc = model.mount.inverse_transform_vector(&sf_body);
-        let mut gyro = model.mount.inverse_transform_vector(&w_body);
-
-        for k in 0..3 {
-            acc[k] += rng.normal() * model.accel_noise[k];
-            gyro[k] += rng.normal() * model.gyro_noise_rad_s[k] + model.gyro_bias_rad_s[k];
-        }
-        let mut clipped = false;
-        for k in 0..3 {
-            if acc[k].abs() > model.accel_full_scale {
-                acc[k] = acc[k].clamp(-model.accel_full_scale, model.accel_full_scale);
-                clipped = true;
-            }
-            acc[k] = (acc[k] / ACCEL_LSB).round() * ACCEL_LSB;
-            gyro[k] = (gyro[k] / GYRO_LSB_RAD_S).round() * GYRO_LSB_RAD_S;
-        }
-
-        // --- barometer --------------------------------------------------
-        let mut pressure = r.pressure;
-        if model.transonic_port_error > 0.0 && !on_pad {
-            let q_dyn = 0.5 * r.density * (r.mach * r.speed_of_sound).powi(2);
-            pressure -= model.transonic_port_error * q_dyn * transonic_shape(r.mach);
-        }
-        pressure += rng.normal() * model.pressure_noise_pa;
-        pressure = (pressure / PRESSURE_LSB_PA).round() * PRESSURE_LSB_PA;
-        let baro_altitude_asl = calculate_isa_altitude(Pascals(pressure as f64)).0 as f32;
-
-        samples.push(Sample {
-            t_us,
-            truth_t,
-            imu: ImuSample { acc, gyro },
-            baro_altitude_asl,
-            truth: r,
-            clipped,
-        });
-
-        // 416 Hz with a little jitter, the way a real sensor task delivers.
-        t_us += model.sample_dt_us + (rng.uniform() * 120.0) as u64;
-    }
-
-    samples
-}
-
-/// Shape of the static-port error against Mach: nothing subsonic, rising
-/// through the transonic region, held supersonic. Invented — its only job
-/// is to make the barometer as dishonest through the lockout as a real
-/// port is, so the tests below prove the lockout is what saves the answer.
-fn transonic_shape(mach: f32) -> f32 {
-    if mach < 0.7 {
-        0.0
-    } else if mach < 1.0 {
-        (mach - 0.7) / 0.3
-    } else {
-        1.0
-    }
-}
-
-// ---------------------------------------------------------------------------
-// Replay
-// ---------------------------------------------------------------------------
-
-#[derive(Default)]
-struct Replay {
-    /// (truth time, forced?) when the airbrakes filter was born
-    birth: Option<(f32, bool)>,
-    /// truth time the pad calibration first completed
-    calibration_t: Option<f32>,
-    /// truth time `burnout_detected()` first latched
-    burnout_t: Option<f32>,
-    burnout_unlatched: bool,
-    /// continuous spans of truth time where `subsonic_by_drag()` was true
-    subsonic_spans: Vec<(f32, f32)>,
-    /// truth time the airbrakes half was retired (dropped by
-    /// `FlightEstimators::update`)
-    retired_t: Option<f32>,
-    /// truth time the DEPLOYMENT half left ascent (its apogee call)
-    deployment_apogee_t: Option<f32>,
-    /// (truth time, PyroSelect) for every pyro command, in order
-    pyros: Vec<(f32, &'static str)>,
-    /// (truth time, estimated vv, true vv) while the airbrakes filter was alive
-    vv_track: Vec<(f32, f32, f32)>,
-    /// (truth time, estimated altitude ASL, true altitude ASL) likewise
-    alt_track: Vec<(f32, f32, f32)>,
-    /// (truth time, commanded extension) for every tick the gate was open
-    mpc: Vec<(f32, f32)>,
-    /// truth time the MPC gate first opened / last closed
-    mpc_window: Option<(f32, f32)>,
-    /// worst |baro altitude - true altitude| seen during the lockout
-    worst_baro_error_in_lockout: f32,
-    clipped_samples: usize,
-    /// truth time of the FIRST sample to hit the accelerometer rail
-    first_clip_t: Option<f32>,
-    /// (truth time, estimated tilt, true tilt) in radians, every sample the
-    /// estimator reported a tilt
-    tilt_track: Vec<(f32, f32, f32)>,
-}
-
-/// Drive [`FlightEstimators`] exactly the way `armed_mode.rs` does, plus the
-/// MPC on the states the gate hands out.
-fn replay(samples: &[Sample], config: FlightConfig, target_apogee_asl: f32) -> Replay {
-    let mpc = AirBrakesMPC::new(config.airbrakes.rocket.clone(), target_apogee_asl);
-    let mut est = FlightEstimators::new(config);
-    let mut out = Replay::default();
-    let mut span_start: Option<f32> = None;
-
-    for s in samples {
-        let (pyro, _log) = est.update(s.t_us, Some(&s.imu), s.baro_altitude_asl);
-        let t = s.truth_t;
-
-        if s.clipped {
-            out.clipped_samples += 1;
-            if out.first_clip_t.is_none() {
-                out.first_clip_t = Some(t);
-            }
-        }
-        if let Some(p) = pyro {
-            out.pyros.push((t, pyro_name(p)));
-        }
-
-        if out.retired_t.is_none()
-            && est.airbrakes_estimator().is_none()
-            && !out.alt_track.is_empty()
-        {
-            out.retired_t = Some(t);
-        }
-        if let Some(ab) = est.airbrakes_estimator() {
-            if out.calibration_t.is_none() && ab.calibration_complete() {
-                out.calibration_t = Some(t);
-            }
-            match (ab.burnout_detected(), out.burnout_t) {
-                (true, None) => out.burnout_t = Some(t),
-                (false, Some(_)) => out.burnout_unlatched = true,
-                _ => {}
-            }
-            match (ab.subsonic_by_drag(), span_start) {
-                (Some(true), None) => span_start = Some(t),
-                (Some(true), Some(_)) => {}
-                (_, Some(start)) => {
-                    out.subsonic_spans.push((start, t));
-                    span_start = None;
-                }
-                _ => {}
-            }
-            if out.birth.is_none()
-                && let Some((born_us, forced)) = ab.birth()
-            {
-                out.birth = Some(((born_us as f32) * 1e-6 - samples[0].truth_t.abs(), forced));
-            }
-            if !ab.airbrakes_enabled() && out.birth.is_none() && t > 0.0 {
-                let err = (s.baro_altitude_asl - s.truth.altitude_asl).abs();
-                out.worst_baro_error_in_lockout = out.worst_baro_error_in_lockout.max(err);
-            }
-            if let Some(tilt) = ab.tilt() {
-                out.tilt_track.push((
-                    t,
-                    tilt,
-                    core::f32::consts::FRAC_PI_2 - s.truth.zenith,
-                ));
-            }
-            if let (Some(v), Some(a)) = (ab.velocity(), ab.altitude_asl()) {
-                out.vv_track.push((t, v.y, s.truth.vv));
-                out.alt_track.push((t, a, s.truth.altitude_asl));
-            }
-        }
-
-        if out.deployment_apogee_t.is_none()
-            && !matches!(
-                est.state(),
-                crate::RocketState::OnPad
-                    | crate::RocketState::Ascent { .. }
-                    | crate::RocketState::MachLockout { .. }
-            )
-        {
-            out.deployment_apogee_t = Some(t);
-        }
-
-        if let Some(states) = est.airbrakes_mpc_states() {
-            let sol = mpc.update(states.altitude_asl, states.velocity);
-            out.mpc.push((t, sol.extension_percentage));
-            out.mpc_window = Some(match out.mpc_window {
-                None => (t, t),
-                Some((a, _)) => (a, t),
-            });
-        }
-    }
-
-    out
-}
-
-fn pyro_name(p: firmware_common_new::vlp::packets::fire_pyro::PyroSelect) -> &'static str {
-    use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
-    match p {
-        PyroSelect::PyroDrogue => "drogue",
-        PyroSelect::PyroMain => "main",
-    }
-}
-
-impl Replay {
-    /// The airbrakes filter's own apogee: the peak of the altitude it
-    /// reported, and when. This is the number worth scoring, because the
-    /// filter is retired at zero vertical velocity and the peak it reached
-    /// right before that IS its apogee estimate.
-    ///
-    /// There has never been anything else to score it against here. The
-    /// estimator's own 0.5 s apogee latch — deleted on 2026-08-17 — needed
-    /// 0.5 s below 1 m/s and the airbrakes half is dropped at 0 m/s, so it
-    /// never once fired in a composed flight; this field recorded `None` on
-    /// every simulation in this file.
-    fn estimated_apogee(&self) -> Option<(f32, f32)> {
-        self.alt_track
-            .iter()
-            .max_by(|a, b| a.1.total_cmp(&b.1))
-            .map(|(t, alt, _)| (*t, *alt))
-    }
-}
+/// The standard atmosphere, for every simulation here that is not about
+/// the atmosphere.
+const ISA: Atmosphere = Atmosphere::standard();
 
-/// Mean |estimate - truth| over a truth-time window.
-fn mean_error(track: &[(f32, f32, f32)], from: f32, to: f32) -> (f32, usize) {
-    let mut sum = 0.0;
-    let mut n = 0;
-    for (t, est, truth) in track {
-        if *t >= from && *t < to {
-            sum += (est - truth).abs();
-            n += 1;
-        }
-    }
-    (if n > 0 { sum / n as f32 } else { f32::NAN }, n)
-}
+/// No wind, for every simulation here that is not about the wind. OpenRocket
+/// flew Osiris in one; see [`wind_shear_moves_the_stowed_prediction`].
+const CALM: WindProfile = WindProfile::calm();
 
 // ===========================================================================
 // 1. The model itself — if these fail, nothing below means anything
@@ -1017,6 +245,56 @@ fn config_matches_the_simulated_airframe() {
     }
 }
 
+/// [`Truth::dispersed`] re-flies OpenRocket's forces as a point mass, so
+/// with nothing dispersed it has to land back on OpenRocket's own
+/// trajectory — otherwise every dispersion run starts from a different
+/// flight than the one the rest of this file checks. And each knob has to
+/// move the apogee the way physics says it does.
+#[test]
+fn an_undispersed_refly_is_the_openrocket_flight() {
+    init_logger();
+    for path in [O3400_CSV, N2900_CSV] {
+        let truth = Truth::load(path);
+        let (apogee_t, apogee_asl) = truth.apogee();
+        let apogee_agl = apogee_asl - truth.pad_asl();
+        let refly = truth.dispersed(&TrajectoryDispersion::default());
+        let (refly_t, refly_asl) = refly.apogee();
+        let refly_agl = refly_asl - refly.pad_asl();
+        let max_mach = |t: &Truth| t.rows.iter().map(|r| r.mach).fold(0.0, f32::max);
+        eprintln!(
+            "{path}: re-flown apogee {refly_agl:.0} m AGL at {refly_t:.2}s vs \
+             OpenRocket {apogee_agl:.0} m at {apogee_t:.2}s; max Mach {:.3} vs {:.3}",
+            max_mach(&refly),
+            max_mach(&truth)
+        );
+        assert!(
+            (refly_agl - apogee_agl).abs() < 0.02 * apogee_agl,
+            "{path}: re-flown apogee {refly_agl} m vs {apogee_agl} m"
+        );
+        assert!((refly_t - apogee_t).abs() < 1.0, "{path}: apogee time");
+        assert!(
+            (max_mach(&refly) - max_mach(&truth)).abs() < 0.02 * max_mach(&truth),
+            "{path}: max Mach"
+        );
+
+        let apogee_with = |dispersion: TrajectoryDispersion| {
+            let flown = truth.dispersed(&dispersion);
+            flown.apogee().1 - flown.pad_asl()
+        };
+        let hot = apogee_with(TrajectoryDispersion {
+            thrust_scale: 1.05,
+            ..Default::default()
+        });
+        let draggy = apogee_with(TrajectoryDispersion {
+            cd_scale: 1.1,
+            ..Default::default()
+        });
+        eprintln!("{path}: +5% thrust {hot:.0} m, +10% drag {draggy:.0} m");
+        assert!(hot > refly_agl, "{path}: more thrust did not fly higher");
+        assert!(draggy < refly_agl, "{path}: more drag did not fly lower");
+    }
+}
+
 // ===========================================================================
 // 2. The config's timing constants against every simulation in the document
 // ===========================================================================
@@ -1299,9 +577,9 @@ fn nominal_o3400_flight() {
     assert!(open >= born, "gate opened at {open}s, before birth at {born}s");
     assert!(close <= apogee_t + 0.5, "gate still open at {close}s, past apogee");
     // Command profile. The MPC predicts apogee for "brake for the servo's
-    // stroke time, half-brake one tick, then hold neutral" -- neutral being
-    // drag 0.0, ~60% extension, not stowed, and the hold being
-    // `CANDIDATE_HOLD_STEPS`. That is worth tens of metres against a coast of
+    // stroke time, then let the servo carry the flaps to neutral" -- neutral
+    // being drag 0.0, ~60% extension, not stowed, and the hold being
+    // `HORIZON_STEPS`. That is worth tens of metres against a coast of
     // hundreds, so while the target is out of reach the solve simply
     // saturates: hold full extension until the prediction has fallen to the
     // target, then modulate off toward the neutral the tail already assumes.
@@ -1622,6 +900,155 @@ fn deployment_fires_drogue_then_main() {
     );
 }
 
+/// The barometer freezes at 20 s — inside the Mach lockout, so the
+/// deployment filter is born from a ring of one frozen number and never sees
+/// the port move again. The deployment half alone then fires nothing at all,
+/// which is the failure the backup channel exists for; with it, the drogue
+/// fires on the accelerometer's apogee under first-of, and at the liftoff
+/// timer under agreement, which has nothing to agree with.
+#[test]
+fn a_dead_barometer_still_fires_the_drogue() {
+    init_logger();
+    let backup = osiris_backup_deployment();
+    let earliest_t = backup.earliest_accel_apogee_us as f32 * 1e-6;
+    let timer_t = backup.apogee_timer_us as f32 * 1e-6;
+    // The bracket the config claims, on every motor in the document: the
+    // accelerometer believed well before apogee, the timer well after it.
+    for path in [O3400_CSV, N2900_CSV] {
+        let (apogee_t, _) = Truth::load(path).apogee();
+        assert!(
+            earliest_t < apogee_t - 5.0 && timer_t > apogee_t + 3.0,
+            "{path}: apogee at {apogee_t:.1}s is outside {earliest_t}s..{timer_t}s"
+        );
+    }
+
+    let truth = Truth::load(O3400_CSV);
+    let (apogee_t, _) = truth.apogee();
+    let mut samples = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: timer_t + 5.0,
+            ..Default::default()
+        },
+    );
+    let frozen = samples
+        .iter()
+        .find(|s| s.truth_t >= 20.0)
+        .expect("no samples past 20 s")
+        .baro_altitude_asl;
+    for s in samples.iter_mut().filter(|s| s.truth_t >= 20.0) {
+        s.baro_altitude_asl = frozen;
+    }
+    let fly = |deployment_policy| {
+        replay(
+            &samples,
+            FlightConfig {
+                deployment_policy,
+                ..osiris_config()
+            },
+            0.0,
+        )
+    };
+
+    // The premise: without the backup, nothing.
+    let primary = fly(DeploymentPolicy::PrimaryOnly);
+    assert!(
+        primary.pyros.is_empty(),
+        "a frozen barometer still fired {:?} — this test no longer tests anything",
+        primary.pyros
+    );
+
+    let first_of = fly(DeploymentPolicy::FirstOf(backup.clone()));
+    eprintln!("dead baro: first-of {:?}, true apogee {apogee_t:.2}s", first_of.pyros);
+    assert_eq!(first_of.pyros.len(), 1, "expected a drogue and nothing else");
+    assert_eq!(first_of.pyros[0].1, "drogue");
+    assert!(
+        (first_of.pyros[0].0 - apogee_t).abs() < 1.0,
+        "first-of drogue at {:.2}s, true apogee {apogee_t:.2}s",
+        first_of.pyros[0].0
+    );
+
+    let agreed = fly(DeploymentPolicy::BothAgreeWithin {
+        window_us: 8_000_000,
+        backup,
+    });
+    eprintln!("dead baro: agreement {:?}", agreed.pyros);
+    assert_eq!(agreed.pyros.len(), 1, "expected a drogue and nothing else");
+    assert_eq!(agreed.pyros[0].1, "drogue");
+    assert!(
+        (timer_t..timer_t + 0.5).contains(&agreed.pyros[0].0),
+        "agreed drogue at {:.2}s, timer at {timer_t}s",
+        agreed.pyros[0].0
+    );
+}
+
+/// The other failure: a barometer that reads 1500 m low from 27 s on. The
+/// deployment filter resyncs onto it a second later, reads a kilometre and a
+/// half of descent, and fires the drogue while the airframe is still
+/// climbing. First-of cannot tell that from an apogee; agreement can,
+/// because the accelerometer does not call apogee for another ten seconds,
+/// and holds the drogue to the timer.
+///
+/// And on an honest flight agreement changes nothing: the accelerometer
+/// calls apogee first, the deployment half's drogue lands inside the window
+/// of it, and fires on exactly the sample it would have alone.
+#[test]
+fn agreement_vetoes_a_lying_barometer() {
+    init_logger();
+    let backup = osiris_backup_deployment();
+    let timer_t = backup.apogee_timer_us as f32 * 1e-6;
+    let agree = DeploymentPolicy::BothAgreeWithin {
+        window_us: 8_000_000,
+        backup,
+    };
+
+    let truth = Truth::load(O3400_CSV);
+    let (apogee_t, _) = truth.apogee();
+    let honest = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: timer_t + 5.0,
+            ..Default::default()
+        },
+    );
+    let fly = |samples: &[_], deployment_policy| {
+        replay(
+            samples,
+            FlightConfig {
+                deployment_policy,
+                ..osiris_config()
+            },
+            0.0,
+        )
+    };
+
+    let primary = fly(&honest, DeploymentPolicy::PrimaryOnly);
+    let agreed = fly(&honest, agree.clone());
+    eprintln!("honest: primary {:?}, agreement {:?}", primary.pyros, agreed.pyros);
+    assert!(!primary.pyros.is_empty(), "no drogue on the honest flight");
+    assert_eq!(agreed.pyros, primary.pyros);
+
+    let mut lying = honest;
+    for s in lying.iter_mut().filter(|s| s.truth_t >= 27.0) {
+        s.baro_altitude_asl -= 1500.0;
+    }
+    let primary = fly(&lying, DeploymentPolicy::PrimaryOnly);
+    eprintln!("lying: primary {:?}, true apogee {apogee_t:.2}s", primary.pyros);
+    assert!(
+        primary.pyros.first().is_some_and(|&(t, pyro)| pyro == "drogue" && t < apogee_t - 5.0),
+        "the lie no longer fires an early drogue — this test no longer tests anything"
+    );
+
+    let agreed = fly(&lying, agree);
+    eprintln!("lying: agreement {:?}", agreed.pyros);
+    assert_eq!(agreed.pyros[0].1, "drogue");
+    assert!(
+        (timer_t..timer_t + 0.5).contains(&agreed.pyros[0].0),
+        "agreed drogue at {:.2}s, timer at {timer_t}s",
+        agreed.pyros[0].0
+    );
+}
+
 // ===========================================================================
 // 5. Airbrakes authority — can this airframe reach a target at all?
 // ===========================================================================
@@ -1657,7 +1084,7 @@ fn airbrakes_authority_and_mpc_convergence() {
     let b = truth.at(born + 0.05);
     let start = State {
         altitude_asl: b.altitude_asl,
-        velocity: Vector2::new(b.lateral_velocity, b.vv),
+        velocity: Vector3::new(b.lateral_velocity, b.vv, 0.0),
     };
     eprintln!(
         "authority: born at {born:.2}s, {:.0} m ASL, v = ({:.0}, {:.0}) m/s",
@@ -1672,7 +1099,13 @@ fn airbrakes_authority_and_mpc_convergence() {
         };
         let dt = 0.02f32;
         while s.velocity.y > 0.0 {
-            let Derivative(k) = calculate_state_derivatives(drag_percentage, &s, &rocket);
+            let Derivative(k) = calculate_state_derivatives(
+                drag_percentage,
+                &s,
+                &rocket,
+                &ISA,
+                &CALM,
+            );
             s = State {
                 altitude_asl: s.altitude_asl + k.altitude_asl * dt,
                 velocity: s.velocity + k.velocity * dt,
@@ -1716,7 +1149,7 @@ fn airbrakes_authority_and_mpc_convergence() {
     // extension, for a target inside the authority band.
     for frac in [0.25f32, 0.5, 0.75] {
         let target = stowed - (stowed - full) * frac;
-        let mpc = AirBrakesMPC::new(rocket.clone(), target);
+        let mpc = AirBrakesMPC::new(rocket.clone(), ISA, target);
         let mut s = State {
             altitude_asl: start.altitude_asl,
             velocity: start.velocity,
@@ -1726,10 +1159,16 @@ fn airbrakes_authority_and_mpc_convergence() {
         let mut ext = 0.0f32;
         while s.velocity.y > 0.0 {
             if ticks % 5 == 0 {
-                ext = mpc.update(s.altitude_asl, s.velocity).extension_percentage;
+                ext = mpc.update(s.altitude_asl, s.velocity.xy()).extension_percentage;
             }
             // extension 0..1 maps onto the dynamics' drag percentage -1..1
-            let Derivative(k) = calculate_state_derivatives(ext * 2.0 - 1.0, &s, &rocket);
+            let Derivative(k) = calculate_state_derivatives(
+                ext * 2.0 - 1.0,
+                &s,
+                &rocket,
+                &ISA,
+                &CALM,
+            );
             s = State {
                 altitude_asl: s.altitude_asl + k.altitude_asl * dt,
                 velocity: s.velocity + k.velocity * dt,
@@ -1750,27 +1189,365 @@ fn airbrakes_authority_and_mpc_convergence() {
     }
 }
 
-/// Stage 1 — the thrust-vector alignment — is the one place where a clipped
-/// accelerometer would do permanent damage. It averages the measured
-/// specific force over the first 0.5 s of boost and takes the mean
-/// DIRECTION as the airframe axis, producing two values that are latched
-/// once and never revisited: `q_av_to_rocket` (how the board is mounted)
-/// and `thrust_axis_av` (the axis the burnout latch projects onto). Nothing
-/// downstream re-derives them, so an error there is an error for the whole
-/// flight — unlike the dead-reckoned velocity, which the barometer erases
-/// within a second of birth.
+/// The closed loop of [`airbrakes_authority_and_mpc_convergence`], flown
+/// with the flaps following a servo instead of landing on every command the
+/// instant it is issued: the solve holding one candidate against the one
+/// planning over the servo from where it is reported.
 ///
-/// What saves it on Osiris is timing, and only just. The axial specific
-/// force during the alignment window is 14.8-15.5 g, and the +-16 g rail is
-/// not reached until ignition+0.86 s — a third of a second after Stage 1
-/// has already closed. This test pins that ordering, because it is the
-/// margin the whole "clipping is survivable" argument rests on.
+/// Both must still reach every target inside the authority band, on Icarus
+/// and on a servo five times slower. And the servo-aware solve must not buy
+/// that with travel: fed the measured extension, the single held candidate
+/// once multiplied commanded travel by 10-20x, and a plan that starts
+/// thrashing the flaps the same way shows up here.
 #[test]
-fn stage1_alignment_finishes_before_the_accelerometer_rails() {
+fn servo_aware_mpc_does_not_thrash_the_flaps() {
     init_logger();
-    const STAGE1_DURATION_S: f32 = 0.5;
-    let truth = Truth::load(O3400_CSV);
-
+    use crate::controller::rocket_dynamics::calculate_state_derivatives;
+    use crate::controller::{Derivative, State};
+    use crate::flight_estimators::AirbrakesMPCStates;
+
+    let truth = Truth::load(O3400_CSV);
+    let rocket = osiris_rocket();
+    let (apogee_t, _) = truth.apogee();
+    let samples = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: apogee_t + 15.0,
+            ..Default::default()
+        },
+    );
+    let r = replay(&samples, osiris_config(), 0.0);
+    let (born, _) = r.birth.unwrap();
+    let b = truth.at(born + 0.05);
+    let start = State {
+        altitude_asl: b.altitude_asl,
+        velocity: Vector3::new(b.lateral_velocity, b.vv, 0.0),
+    };
+    // Re-solve every 0.1 s; in between, the flaps follow the servo exactly,
+    // stowed at the start like the real coast. Returns the apogee, and the
+    // travel of the command and of the flaps, in full strokes.
+    let fly = |mpc: &AirBrakesMPC, servo: ServoModel, aware: bool| {
+        let mut s = State {
+            altitude_asl: start.altitude_asl,
+            velocity: start.velocity,
+        };
+        let dt = 0.02f32;
+        let (mut actual, mut commanded) = (0.0f32, 0.0f32);
+        let (mut tick_start, mut previous) = (0.0f32, 0.0f32);
+        let (mut command_travel, mut flap_travel) = (0.0f32, 0.0f32);
+        let mut ticks = 0usize;
+        while s.velocity.y > 0.0 {
+            if ticks % 5 == 0 {
+                let states = AirbrakesMPCStates {
+                    altitude_asl: s.altitude_asl,
+                    velocity: s.velocity.xy(),
+                    cd_scale: 1.0,
+                    heading: None,
+                    enabled_for_s: ticks as f32 * dt,
+                    mode: ControllerMode::TargetApogee,
+                    atmosphere: ISA,
+                };
+                let reported = ServoState {
+                    actual_extension: actual,
+                    commanded_extension: commanded,
+                };
+                let solution = mpc.update_with_servo(&states, aware.then_some(reported));
+                command_travel += (solution.extension_percentage - commanded).abs();
+                (tick_start, previous) = (actual, commanded);
+                commanded = solution.extension_percentage;
+            }
+            let drag_percentage = rocket.extension_percentage_to_drag_percentage(actual);
+            let Derivative(k) =
+                calculate_state_derivatives(drag_percentage, &s, &rocket, &ISA, &CALM);
+            s = State {
+                altitude_asl: s.altitude_asl + k.altitude_asl * dt,
+                velocity: s.velocity + k.velocity * dt,
+            };
+            ticks += 1;
+            // Exactly where the servo is this far into the tick.
+            let into_tick = ((ticks - 1) % 5 + 1) as f32 * dt;
+            let next = servo.step(tick_start, previous, commanded, into_tick);
+            flap_travel += (next - actual).abs();
+            actual = next;
+        }
+        (s.altitude_asl, command_travel, flap_travel)
+    };
+
+    // Icarus, and a servo five times slower with four times the dead time
+    // and fifteen times the lag; each flown by an MPC that knows it.
+    let sluggish = ServoModel {
+        dead_time_s: 0.1,
+        time_constant_s: 0.15,
+        slew_per_s: 0.5,
+    };
+    for (name, servo) in [("icarus", ServoModel::ICARUS), ("sluggish", sluggish)] {
+        let mpc = |target| AirBrakesMPC::new(rocket.clone(), ISA, target).with_servo(servo);
+        let stowed = fly(&mpc(1e6), servo, false).0;
+        let full = fly(&mpc(0.0), servo, false).0;
+        for frac in [0.25f32, 0.5, 0.75] {
+            let target = stowed - (stowed - full) * frac;
+            let (held, held_command, held_flaps) = fly(&mpc(target), servo, false);
+            let (aware, aware_command, aware_flaps) = fly(&mpc(target), servo, true);
+            eprintln!(
+                "servo: {name}, target {target:.0} m ASL ({:.0}% of authority): held \
+                 misses by {:+.1} m, commanding {held_command:.2} strokes for \
+                 {held_flaps:.2} of flap; servo-aware {:+.1} m, {aware_command:.2} for \
+                 {aware_flaps:.2}",
+                frac * 100.0,
+                held - target,
+                aware - target,
+            );
+            assert!(
+                (held - target).abs() < 100.0 && (aware - target).abs() < 100.0,
+                "{name}: missed a reachable target, held by {:+.1} m and servo-aware \
+                 by {:+.1} m",
+                held - target,
+                aware - target
+            );
+            assert!(
+                aware_command < 2.0 * held_command + 0.5,
+                "{name}: the servo-aware solve commanded {aware_command:.2} strokes \
+                 against the held solve's {held_command:.2}"
+            );
+        }
+    }
+}
+
+/// The coast the MPC predicts is only as good as the Cd it integrates, and
+/// Osiris's stowed Cd is not one number over it: OpenRocket has it falling
+/// from Mach 0.8 to 0.4 by more than a tenth. A table held at its Mach-0.8
+/// value charges the whole coast with the drag of its first second, and
+/// predicts apogee low from exactly where the flaps open.
+///
+/// From the Mach-0.8 crossing, stowed, on the crate's own dynamics
+/// (measured: stowed Cd 13.4% lower at 0.4 than at 0.8):
+///
+/// * handed OpenRocket's own Cd(Mach) -- rows taken straight off the
+///   trajectory -- the coast must land on OpenRocket's apogee, or the Mach
+///   lookup is not doing what it says. It lands 5.6 m low;
+/// * [`osiris_rocket_mach_table`] must land at least twice as close as
+///   [`osiris_rocket`]: 21.7 m low against 60.9 m. What is left is the CFD
+///   reading 3.6% above OpenRocket at 0.8, which no Mach axis can fix;
+/// * the MPC solving on that table must put a reachable target where the
+///   Mach-aware dynamics say it lands.
+#[test]
+fn mach_dependent_cd_tracks_the_openrocket_coast() {
+    init_logger();
+    use crate::controller::rocket_dynamics::calculate_state_derivatives;
+    use crate::controller::{Derivative, State};
+
+    let truth = Truth::load(O3400_CSV);
+    let (_, apogee_asl) = truth.apogee();
+    let b = truth.at(truth.mach_down_crossing(0.8));
+    let start = State {
+        altitude_asl: b.altitude_asl,
+        velocity: Vector3::new(b.lateral_velocity, b.vv, 0.0),
+    };
+
+    let fall = 1.0 - truth.stowed_cd_at_mach(0.4) / truth.stowed_cd_at_mach(0.8);
+    eprintln!(
+        "mach cd: OpenRocket stowed Cd {:.4} at Mach 0.8, {:.4} at 0.4 ({:.1}% lower)",
+        truth.stowed_cd_at_mach(0.8),
+        truth.stowed_cd_at_mach(0.4),
+        fall * 100.0
+    );
+    assert!(
+        fall > 0.1,
+        "stowed Cd only falls {:.1}% over the coast — this test no longer \
+         exercises the Mach axis",
+        fall * 100.0
+    );
+
+    let coast_to_apogee = |rocket: &RocketParameters, drag_percentage: f32| {
+        let mut s = start.clone();
+        let dt = 0.02f32;
+        while s.velocity.y > 0.0 {
+            let Derivative(k) = calculate_state_derivatives(
+                drag_percentage,
+                &s,
+                rocket,
+                &ISA,
+                &CALM,
+            );
+            s = State {
+                altitude_asl: s.altitude_asl + k.altitude_asl * dt,
+                velocity: s.velocity + k.velocity * dt,
+            };
+        }
+        s.altitude_asl
+    };
+
+    let mut openrocket = osiris_rocket();
+    openrocket.cd_mach = OSIRIS_CD_MACH;
+    for (row, mach) in openrocket.cd.iter_mut().zip(OSIRIS_CD_MACH) {
+        row[0] = truth.stowed_cd_at_mach(mach);
+    }
+    let mach_table = osiris_rocket_mach_table(&truth);
+
+    let own = coast_to_apogee(&openrocket, -1.0) - apogee_asl;
+    let flat = coast_to_apogee(&osiris_rocket(), -1.0) - apogee_asl;
+    let tabled = coast_to_apogee(&mach_table, -1.0) - apogee_asl;
+    eprintln!(
+        "mach cd: stowed apogee error from Mach 0.8 — OpenRocket's Cd {own:+.1} m, \
+         Table 10 held flat {flat:+.1} m, Table 10 with a Mach axis {tabled:+.1} m"
+    );
+    assert!(
+        own.abs() < 15.0,
+        "OpenRocket's own Cd(Mach) misses its apogee by {own:+.1} m"
+    );
+    assert!(
+        tabled.abs() < 0.5 * flat.abs(),
+        "the Mach axis did not close the coast: {tabled:+.1} m against {flat:+.1} m flat"
+    );
+
+    // The solver on the same table: a target mid-authority is reachable,
+    // so it must come back with a command inside the stroke that is
+    // predicted to land on it.
+    let stowed = coast_to_apogee(&mach_table, -1.0);
+    let full = coast_to_apogee(&mach_table, 1.0);
+    let target = 0.5 * (stowed + full);
+    let solution =
+        AirBrakesMPC::new(mach_table, ISA, target).update(start.altitude_asl, start.velocity.xy());
+    eprintln!("mach cd: target {target:.0} m ASL -> {solution:?}");
+    assert!(
+        solution.extension_percentage > 0.0 && solution.extension_percentage < 1.0,
+        "a mid-authority target pinned the command at a rail: {solution:?}"
+    );
+    assert!(
+        (solution.predicted_apogee_asl - target).abs() < 1.0,
+        "the solve does not land on a reachable target: {solution:?}"
+    );
+}
+
+/// The in-flight drag fit, on a config whose whole cd table is 25% heavy —
+/// the size of error a CFD table carried to the wrong airframe build makes.
+/// OpenRocket flies the coast stowed, so the command fed back is 0.0
+/// throughout.
+///
+/// At the Mach-0.6 crossing, from the estimator's own state, coasting
+/// stowed to apogee on the crate's dynamics (measured):
+///
+/// | table        | learned scale | apogee error fitted | as configured |
+/// |--------------|---------------|---------------------|---------------|
+/// | exact        | 0.969         | -18.1 m             | -22.8 m       |
+/// | 1.25x heavy  | 0.776         | -18.2 m             | -60.4 m       |
+/// | 2x heavy     | 0.700 (bound) | -81.8 m             | -159.9 m      |
+///
+/// The exact table's 0.969 is Table 10 reading 3.6% above OpenRocket at
+/// Mach 0.8 (see [`mach_dependent_cd_tracks_the_openrocket_coast`]), found
+/// again from the accelerometer; a heavy table must learn the same airframe,
+/// i.e. its scale times 1.25 must land on that, and then predict what the
+/// exact table does. The 18 m both are left with is not drag: it is the
+/// estimator's horizontal velocity at that instant, which reads 43 m/s
+/// against a true 34.
+///
+/// A table twice too heavy is past what the fit is allowed to believe. It
+/// must stop at the bound — and still be closer there than not fitting.
+#[test]
+fn in_flight_cd_scale_corrects_a_heavy_table() {
+    init_logger();
+    use crate::airbrakes_estimator::CD_SCALE_MIN;
+    use crate::controller::rocket_dynamics::calculate_state_derivatives;
+    use crate::controller::{Derivative, State};
+
+    let truth = Truth::load(O3400_CSV);
+    let (apogee_t, apogee_asl) = truth.apogee();
+    let samples = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: apogee_t + 1.0,
+            ..Default::default()
+        },
+    );
+    let check_t = truth.mach_down_crossing(0.6);
+
+    let stowed_apogee_error = |rocket: &RocketParameters, mut s: State| {
+        let dt = 0.02f32;
+        while s.velocity.y > 0.0 {
+            let Derivative(k) = calculate_state_derivatives(-1.0, &s, rocket, &ISA, &CALM);
+            s = State {
+                altitude_asl: s.altitude_asl + k.altitude_asl * dt,
+                velocity: s.velocity + k.velocity * dt,
+            };
+        }
+        s.altitude_asl - apogee_asl
+    };
+    // (learned scale, stowed apogee error on the fitted table, on the table
+    // as configured)
+    let fit = |heavy: f32| {
+        let rocket = osiris_rocket_mach_table(&truth).with_cd_scale(heavy);
+        let mut config = osiris_config();
+        config.airbrakes.rocket = rocket.clone();
+        let mut est = FlightEstimators::new(config);
+        let mut at_check = None;
+        for s in &samples {
+            let _ = est.update(s.t_us, Some(&s.imu), &[Some(s.baro_altitude_asl)], None, Some(0.0));
+            if at_check.is_none() && s.truth_t >= check_t {
+                at_check = est.airbrakes_mpc_states();
+            }
+        }
+        let states = at_check.expect("the MPC gate was shut at the Mach-0.6 crossing");
+        let state = State {
+            altitude_asl: states.altitude_asl,
+            velocity: states.velocity.push(0.0),
+        };
+        (
+            states.cd_scale,
+            stowed_apogee_error(&rocket.with_cd_scale(states.cd_scale), state.clone()),
+            stowed_apogee_error(&rocket, state),
+        )
+    };
+
+    let (exact, exact_fitted, exact_configured) = fit(1.0);
+    let (heavy, heavy_fitted, heavy_configured) = fit(1.25);
+    let (pinned, pinned_fitted, pinned_configured) = fit(2.0);
+    eprintln!(
+        "cd scale: exact {exact:.3} ({exact_fitted:+.1} / {exact_configured:+.1} m), \
+         1.25x {heavy:.3} ({heavy_fitted:+.1} / {heavy_configured:+.1} m), \
+         2x {pinned:.3} ({pinned_fitted:+.1} / {pinned_configured:+.1} m)"
+    );
+    assert!(
+        (heavy * 1.25 - exact).abs() < 0.02 * exact,
+        "a 1.25x table learned {heavy:.3}, which is not the airframe the exact one \
+         learned ({exact:.3} / 1.25 = {:.3})",
+        exact / 1.25
+    );
+    assert!(
+        (heavy_fitted - exact_fitted).abs() < 2.0,
+        "the fitted heavy table predicts {heavy_fitted:+.1} m, the exact one {exact_fitted:+.1} m"
+    );
+    assert!(
+        exact_fitted.abs() <= exact_configured.abs(),
+        "fitting made the exact table worse: {exact_fitted:+.1} m against {exact_configured:+.1} m"
+    );
+    assert_eq!(pinned, CD_SCALE_MIN, "a 2x table must pin the scale at its bound");
+    assert!(
+        pinned_fitted.abs() < pinned_configured.abs(),
+        "the bound did not help: {pinned_fitted:+.1} m against {pinned_configured:+.1} m"
+    );
+}
+
+/// Stage 1 — the thrust-vector alignment — is the one place where a clipped
+/// accelerometer would do permanent damage. It averages the measured
+/// specific force over the first 0.5 s of boost and takes the mean
+/// DIRECTION as the airframe axis, producing two values that are latched
+/// once and never revisited: `q_av_to_rocket` (how the board is mounted)
+/// and `thrust_axis_av` (the axis the burnout latch projects onto). Nothing
+/// downstream re-derives them, so an error there is an error for the whole
+/// flight — unlike the dead-reckoned velocity, which the barometer erases
+/// within a second of birth.
+///
+/// What saves it on Osiris is timing, and only just. The axial specific
+/// force during the alignment window is 14.8-15.5 g, and the +-16 g rail is
+/// not reached until ignition+0.86 s — a third of a second after Stage 1
+/// has already closed. This test pins that ordering, because it is the
+/// margin the whole "clipping is survivable" argument rests on.
+#[test]
+fn stage1_alignment_finishes_before_the_accelerometer_rails() {
+    init_logger();
+    const STAGE1_DURATION_S: f32 = 0.5;
+    let truth = Truth::load(O3400_CSV);
+
     // Where the airframe actually crosses the rail, from OpenRocket's own
     // forces rather than from the sensor model.
     let rail = 16.0 * 9.81;
@@ -1947,6 +1724,72 @@ fn any_mounting_orientation_flies_the_same_flight() {
     }
 }
 
+/// The attitude against the trajectory it was synthesised from: pitch
+/// against OpenRocket's own axis elevation, yaw against its azimuth as a
+/// compass heading (the sim's world Y is magnetic north — see
+/// [`EARTH_FIELD_T`]), and roll against the synthetic spin, all the way
+/// from the end of stage 1 to the airbrakes half's retirement.
+///
+/// Roll and yaw are only scored where the axis is at least 3 deg off
+/// vertical; nearer than that the axis has no azimuth to speak of, and the
+/// two trade off against each other (see [`AttitudeAngles`]). Roll is
+/// scored as the change since the first scored sample, because its zero is
+/// the board's and the synthetic roll's is the airframe's.
+///
+/// Measured over 22.6 turns of roll: pitch within 0.27 deg, yaw 2.3 deg,
+/// roll 3.7 deg. The yaw error swings once per turn, at about a degree
+/// where the axis is 7-10 deg off vertical and a tenth of that past 40:
+/// it is the stage-1 axis's own misalignment, a fraction of a degree,
+/// seen through an azimuth that gets more sensitive the nearer vertical
+/// it is. Roll carries the same swing against a ~2 deg offset taken on at
+/// the first, nearest-vertical samples.
+#[test]
+fn attitude_tracks_the_openrocket_truth() {
+    init_logger();
+    let truth = Truth::load(O3400_CSV);
+    let (apogee_t, apogee_asl) = truth.apogee();
+    let samples = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: apogee_t + 5.0,
+            ..Default::default()
+        },
+    );
+    let r = replay(&samples, osiris_config(), apogee_asl - 150.0);
+    assert!(!r.attitude_track.is_empty(), "no attitude was ever reported");
+
+    let wrap = |a: f32| (a + PI).rem_euclid(2.0 * PI) - PI;
+    let (mut pitch_err, mut yaw_err, mut roll_err) = (0.0f32, 0.0f32, 0.0f32);
+    let mut roll_zero: Option<(f32, f32)> = None;
+    let mut scored = 0;
+    for (_, angles, pitch, heading, roll) in &r.attitude_track {
+        assert!(angles.yaw_is_magnetic, "the pad magnetometer was not used");
+        pitch_err = pitch_err.max((angles.pitch - pitch).abs());
+        if core::f32::consts::FRAC_PI_2 - pitch < 3f32.to_radians() {
+            continue;
+        }
+        scored += 1;
+        yaw_err = yaw_err.max(wrap(angles.yaw - heading).abs());
+        let (est0, truth0) = *roll_zero.get_or_insert((angles.roll, *roll));
+        roll_err = roll_err.max(wrap((angles.roll - est0) - (roll - truth0)).abs());
+    }
+    let turns = (r.attitude_track.last().unwrap().4 - roll_zero.unwrap().1) / (2.0 * PI);
+
+    eprintln!(
+        "attitude over {} samples ({scored} off vertical, {turns:.1} turns of roll): \
+         pitch <= {:.2} deg, yaw <= {:.2} deg, roll <= {:.2} deg",
+        r.attitude_track.len(),
+        pitch_err.to_degrees(),
+        yaw_err.to_degrees(),
+        roll_err.to_degrees(),
+    );
+
+    assert!(scored > 1000, "only {scored} samples were off vertical");
+    assert!(pitch_err.to_degrees() < 1.0, "pitch off by {:.2} deg", pitch_err.to_degrees());
+    assert!(yaw_err.to_degrees() < 5.0, "yaw off by {:.2} deg", yaw_err.to_degrees());
+    assert!(roll_err.to_degrees() < 5.0, "roll off by {:.2} deg", roll_err.to_degrees());
+}
+
 /// `approximate_air_density` against an exact f64 ISA reference.
 ///
 /// This test only means anything because the implementation is now
@@ -1996,6 +1839,251 @@ fn air_density_matches_the_isa_reference() {
     );
 }
 
+/// The launch day's own atmosphere, calibrated from the pad's pressure and
+/// temperature and the site's surveyed elevation, against OpenRocket's
+/// atmosphere along the whole ascent — and the standard day against the
+/// same truth, which is what the estimators and the MPC flew on before.
+///
+/// This day is 26 C at a site 363.6 m ASL, 11 K hotter than ISA says for
+/// that pressure. Measured:
+///
+/// | over the ascent       | standard day | calibrated |
+/// |-----------------------|--------------|------------|
+/// | height above the pad  | -144 m       | +2 m       |
+/// | density               | +3.7%        | -0.01%     |
+/// | speed of sound        | -1.9%        | +0.3%      |
+///
+/// and the stowed apogee the MPC's model predicts from the true state,
+/// scored in real metres above the pad, at the Mach 0.8 / 0.6 / 0.4
+/// crossings: -151 / -120 / -144 m on the standard day, +6 / +18 / -13 m
+/// calibrated. What is left calibrated is the Cd table, which is
+/// `in_flight_cd_scale_corrects_a_heavy_table`'s problem.
+#[test]
+fn a_pad_calibrated_atmosphere_matches_the_launch_day() {
+    init_logger();
+    use crate::controller::rocket_dynamics::calculate_state_derivatives;
+    use crate::controller::{Derivative, State};
+    const SITE_ELEVATION_ASL: f32 = 363.6;
+    let truth = Truth::load(O3400_CSV);
+    let pad = &truth.rows[0];
+    let calibrated = Atmosphere::from_pad(pad.pressure, pad.temperature, Some(SITE_ELEVATION_ASL));
+    let (apogee_t, apogee_asl) = truth.apogee();
+    let apogee_agl = truth.at(apogee_t).altitude_agl;
+    assert!((calibrated.altitude_asl(pad.altitude_asl) - SITE_ELEVATION_ASL).abs() < 0.1);
+
+    // Worst error over the ascent of height above the pad (m), density and
+    // speed of sound (relative), for one atmosphere.
+    let worst = |atmosphere: &Atmosphere| {
+        let pad_asl = atmosphere.altitude_asl(pad.altitude_asl);
+        let mut worst = [0.0f32; 3];
+        for r in truth.rows.iter().filter(|r| r.t <= apogee_t) {
+            let altitude_asl = atmosphere.altitude_asl(r.altitude_asl);
+            let errors = [
+                altitude_asl - pad_asl - r.altitude_agl,
+                atmosphere.air_density(altitude_asl) / r.density - 1.0,
+                atmosphere.speed_of_sound(altitude_asl) / r.speed_of_sound - 1.0,
+            ];
+            for (w, e) in worst.iter_mut().zip(errors) {
+                if e.abs() > w.abs() {
+                    *w = e;
+                }
+            }
+        }
+        worst
+    };
+    // Stowed apogee above the pad (m), from the true state at `t` expressed
+    // in the atmosphere's own frame — altitude and the rate of that
+    // altitude, which is what the estimators hand the MPC.
+    let rocket = osiris_rocket_mach_table(&truth);
+    let predicted_apogee_agl = |atmosphere: &Atmosphere, t: f32| {
+        let frame = |t: f32| atmosphere.altitude_asl(truth.at(t).altitude_asl);
+        let mut s = State {
+            altitude_asl: frame(t),
+            velocity: Vector3::new(
+                truth.at(t).lateral_velocity,
+                (frame(t + 0.05) - frame(t - 0.05)) / 0.1,
+                0.0,
+            ),
+        };
+        let dt = 0.02f32;
+        while s.velocity.y > 0.0 {
+            let Derivative(k) = calculate_state_derivatives(-1.0, &s, &rocket, atmosphere, &CALM);
+            s = State {
+                altitude_asl: s.altitude_asl + k.altitude_asl * dt,
+                velocity: s.velocity + k.velocity * dt,
+            };
+        }
+        s.altitude_asl - atmosphere.altitude_asl(pad.altitude_asl)
+    };
+
+    let standard = worst(&ISA);
+    let fitted = worst(&calibrated);
+    eprintln!("              |   height |  density | sound speed");
+    for (name, w) in [("standard day", standard), ("calibrated", fitted)] {
+        eprintln!(
+            "{name:>13} | {:+6.1} m | {:+7.2}% | {:+7.2}%",
+            w[0],
+            w[1] * 100.0,
+            w[2] * 100.0
+        );
+    }
+    assert!(fitted[0].abs() < 5.0, "height off by {:+.1} m", fitted[0]);
+    assert!(fitted[1].abs() < 1e-3, "density off by {:+.3}%", fitted[1] * 100.0);
+    assert!(fitted[2].abs() < 5e-3, "sound speed off by {:+.2}%", fitted[2] * 100.0);
+    for (s, f) in standard.iter().zip(fitted) {
+        assert!(f.abs() < s.abs());
+    }
+    // And the calibrated model's frame holds the apogee itself.
+    let calibrated_apogee_agl =
+        calibrated.altitude_asl(apogee_asl) - calibrated.altitude_asl(pad.altitude_asl);
+    assert!((calibrated_apogee_agl - apogee_agl).abs() < 5.0);
+
+    for mach in [0.8, 0.6, 0.4] {
+        let t = truth.mach_down_crossing(mach);
+        let standard = predicted_apogee_agl(&ISA, t) - apogee_agl;
+        let fitted = predicted_apogee_agl(&calibrated, t) - apogee_agl;
+        eprintln!(
+            "Mach {mach}: stowed apogee AGL off by {standard:+.1} m standard, \
+             {fitted:+.1} m calibrated"
+        );
+        assert!(
+            fitted.abs() < 25.0 && standard.abs() > 100.0,
+            "at Mach {mach}: {standard:+.1} m standard, {fitted:+.1} m calibrated"
+        );
+    }
+}
+
+/// The wind OpenRocket flew Osiris through, as the pre-launch upload would
+/// carry it: [`WIND_BINS`] bins, burnout to apogee, in `atmosphere`'s
+/// altitude frame.
+///
+/// Each row's wind is its ground velocity less its airspeed along the
+/// airframe axis, which is exact wherever the angle of attack is zero and
+/// OpenRocket's is under 2 deg for the whole coast. Its turbulence is
+/// averaged out over each bin's few hundred metres.
+fn openrocket_wind(truth: &Truth, atmosphere: &Atmosphere) -> WindProfile {
+    let (apogee_t, _) = truth.apogee();
+    let coast: Vec<_> = truth
+        .rows
+        .iter()
+        .filter(|r| r.t > truth.burnout_t() && r.t <= apogee_t)
+        .collect();
+    let (bottom, top) = (coast[0].altitude_asl, coast[coast.len() - 1].altitude_asl);
+    let mut sums = [(0.0f32, Vector2::zeros(), 0usize); WIND_BINS];
+    for r in coast {
+        let airspeed = r.mach * r.speed_of_sound;
+        let axis = Vector2::new(r.azimuth.cos(), r.azimuth.sin()) * r.zenith.cos();
+        let ground = Vector2::new(r.lateral_direction.cos(), r.lateral_direction.sin())
+            * r.lateral_velocity;
+        let bin = (((r.altitude_asl - bottom) / (top - bottom) * WIND_BINS as f32) as usize)
+            .min(WIND_BINS - 1);
+        sums[bin].0 += atmosphere.altitude_asl(r.altitude_asl);
+        sums[bin].1 += ground - axis * airspeed;
+        sums[bin].2 += 1;
+    }
+    let bins: Vec<_> = sums
+        .iter()
+        .map(|&(altitude, wind, n)| {
+            let n = n as f32;
+            (altitude / n, wind.x / n, wind.y / n)
+        })
+        .collect();
+    WindProfile::binned(&bins).unwrap()
+}
+
+/// OpenRocket flew Osiris through a wind that strengthens from ~4 m/s at
+/// burnout to ~10 m/s at apogee, toward the north-west, with the airframe
+/// weathercocked into it: from burnout to apogee its heading is never more
+/// than 31 deg off straight upwind. The stowed apogee the MPC's model
+/// predicts from the true state at the Mach 0.8 / 0.6 / 0.4 crossings — fed
+/// what the estimator feeds it, airspeed along the airframe's heading —
+/// against OpenRocket's, on the launch day's calibrated atmosphere and the
+/// Mach-aware table (measured, m):
+///
+/// | from Mach | calm  | uploaded wind | ... with no heading |
+/// |-----------|-------|---------------|---------------------|
+/// | 0.8       | -17.9 | -18.3         | -18.6               |
+/// | 0.6       | -8.2  | -8.2          | -8.3                |
+/// | 0.4       | -2.3  | -2.3          | -2.3                |
+///
+/// i.e. on this flight the wind is worth under a metre. Handed airspeed,
+/// the model only sees the shear, and ~6 m/s of it is little against a
+/// coast that starts at several hundred m/s of airspeed and spends its slow
+/// end, where it would matter, in thin air. What is left is the Cd table's,
+/// as in [`a_pad_calibrated_atmosphere_matches_the_launch_day`]. The test
+/// holds the wind to that size — a wind model moving this prediction by
+/// metres would be a frame or sign error, not weather — and checks that
+/// with no compass the upwind prior lands where the heading does.
+#[test]
+fn wind_shear_moves_the_stowed_prediction() {
+    init_logger();
+    use crate::controller::rocket_dynamics::calculate_state_derivatives;
+    use crate::controller::{Derivative, State};
+    const SITE_ELEVATION_ASL: f32 = 363.6;
+    let truth = Truth::load(O3400_CSV);
+    let pad = &truth.rows[0];
+    let atmosphere = Atmosphere::from_pad(pad.pressure, pad.temperature, Some(SITE_ELEVATION_ASL));
+    let (apogee_t, apogee_asl) = truth.apogee();
+    let apogee_asl = atmosphere.altitude_asl(apogee_asl);
+    let rocket = osiris_rocket_mach_table(&truth);
+    let wind = openrocket_wind(&truth, &atmosphere);
+    eprintln!("wind: {wind:?}");
+
+    // How far the airframe's heading strays from straight upwind.
+    let worst_upwind = truth
+        .rows
+        .iter()
+        .filter(|r| r.t > truth.burnout_t() && r.t <= apogee_t)
+        .map(|r| {
+            let w = wind.at(atmosphere.altitude_asl(r.altitude_asl));
+            let upwind = libm::atan2f(-w.y, -w.x);
+            let off = (r.azimuth - upwind).rem_euclid(2.0 * PI);
+            off.min(2.0 * PI - off)
+        })
+        .fold(0.0f32, f32::max);
+    eprintln!("wind: heading at most {:.1} deg off upwind", worst_upwind.to_degrees());
+    assert!(worst_upwind < 35f32.to_radians());
+
+    // Stowed apogee error (m) from the true state at `t`, handed over the
+    // way `AirBrakesMPC::update_in_wind` hands it to the simulation.
+    let stowed_error = |wind: &WindProfile, heading: Option<f32>, t: f32| {
+        let r = truth.at(t);
+        let altitude_asl = atmosphere.altitude_asl(r.altitude_asl);
+        let wind = wind.in_flight_frame(heading, altitude_asl);
+        let here = wind.at(altitude_asl);
+        let horizontal_airspeed = r.mach * r.speed_of_sound * r.zenith.cos();
+        let mut s = State {
+            altitude_asl,
+            velocity: Vector3::new(horizontal_airspeed + here.x, r.vv, here.y),
+        };
+        let dt = 0.02f32;
+        while s.velocity.y > 0.0 {
+            let Derivative(k) = calculate_state_derivatives(-1.0, &s, &rocket, &atmosphere, &wind);
+            s = State {
+                altitude_asl: s.altitude_asl + k.altitude_asl * dt,
+                velocity: s.velocity + k.velocity * dt,
+            };
+        }
+        s.altitude_asl - apogee_asl
+    };
+
+    for mach in [0.8, 0.6, 0.4] {
+        let t = truth.mach_down_crossing(mach);
+        let heading = PI / 2.0 - truth.at(t).azimuth;
+        let calm = stowed_error(&CALM, None, t);
+        let windy = stowed_error(&wind, Some(heading), t);
+        let blind = stowed_error(&wind, None, t);
+        eprintln!(
+            "Mach {mach}: stowed apogee off by {calm:+.1} m calm, {windy:+.1} m in the \
+             uploaded wind, {blind:+.1} m with no heading"
+        );
+        assert!(
+            (windy - calm).abs() < 1.0 && (blind - windy).abs() < 0.5,
+            "at Mach {mach}: {calm:+.1} m calm, {windy:+.1} m in the wind, {blind:+.1} m blind"
+        );
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Diagnostic, not a regression test (run with --ignored --nocapture): what
 // does the ignition threshold cost on THIS airframe's motors?
@@ -2033,7 +2121,7 @@ fn ignition_latch_time_by_threshold() {
             let mut pyro_t: Option<f32> = None;
             let mut ab_t: Option<f32> = None;
             for s in &samples {
-                let _ = est.update(s.t_us, Some(&s.imu), s.baro_altitude_asl);
+                let _ = est.update(s.t_us, Some(&s.imu), &[Some(s.baro_altitude_asl)], None, None);
                 if pyro_t.is_none() && !matches!(est.state(), crate::RocketState::OnPad) {
                     pyro_t = Some(s.truth_t);
                 }
@@ -2275,7 +2363,7 @@ fn mpc_input_baro_only() {
         );
         let cfg = osiris_config();
         let target_asl = apogee_asl - 150.0;
-        let mpc = AirBrakesMPC::new(cfg.airbrakes.rocket.clone(), target_asl);
+        let mpc = AirBrakesMPC::new(cfg.airbrakes.rocket.clone(), ISA, target_asl);
         let mut est = FlightEstimators::new(cfg);
 
         let mut filters: [Option<BaroOnly>; 4] = [None, None, None, None];
@@ -2286,7 +2374,7 @@ fn mpc_input_baro_only() {
         let mut gate_last_t = 0.0f32;
 
         for s in &samples {
-            let _ = est.update(s.t_us, Some(&s.imu), s.baro_altitude_asl);
+            let _ = est.update(s.t_us, Some(&s.imu), &[Some(s.baro_altitude_asl)], None, None);
             let dt = prev_t.map(|p| (s.truth_t - p).clamp(0.0, 0.25)).unwrap_or(0.0);
             prev_t = Some(s.truth_t);
 
@@ -2408,4 +2496,721 @@ fn mpc_input_baro_only() {
     }
 }
 
+// ===========================================================================
+// Staging
+// ===========================================================================
+
+/// The O3400 flown as a booster and a sustainer: cut at 3 s, relit 4 s
+/// later (see [`Airstart`] for why it is one motor),
+/// with the igniter due 3.2 s after the booster's burnout is called.
+fn staged_flight() -> (Truth, StagingConfig) {
+    let truth = Truth::load(O3400_CSV).dispersed(&TrajectoryDispersion {
+        airstart: Some(Airstart {
+            at_s: 3.0,
+            coast_s: 4.0,
+        }),
+        ..Default::default()
+    });
+    let staging = StagingConfig {
+        sustainer_ignition_delay_us: 3_200_000,
+        sustainer_ignition_acc_threshold: 3.0 * 9.81,
+        max_tilt_rad: 15f32.to_radians(),
+        min_vertical_velocity: 50.0,
+    };
+    (truth, staging)
+}
+
+/// Every burn is called, each once and in order, the sustainer is commanded
+/// once between them, and the brakes stay shut until the second burn is out.
+#[test]
+fn a_staged_flight_calls_every_burn_and_lights_the_sustainer() {
+    init_logger();
+    let (truth, staging) = staged_flight();
+    let samples = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: truth.apogee().0,
+            ..Default::default()
+        },
+    );
+    let r = replay(
+        &samples,
+        FlightConfig {
+            staging: Some(staging),
+            ..osiris_config()
+        },
+        0.0,
+    );
+    eprintln!("staging: {:?}, MPC window {:?}", r.staging, r.mpc_window);
+
+    let events: Vec<StagingEvent> = r.staging.iter().map(|(_, e)| *e).collect();
+    assert_eq!(
+        events,
+        vec![
+            StagingEvent::Ignition { burn: 0 },
+            StagingEvent::Burnout { burn: 0 },
+            StagingEvent::IgniteSustainer,
+            StagingEvent::Ignition { burn: 1 },
+            StagingEvent::Burnout { burn: 1 },
+        ]
+    );
+    let at = |i: usize| r.staging[i].0;
+    assert!(
+        (at(0) - r.ignition_t.expect("no ignition")).abs() < 0.01,
+        "burn 0 is not the deployment half's ignition"
+    );
+    // The cut is abrupt, so the booster's burnout is its 0.3 s sustain late.
+    assert!((3.0..3.6).contains(&at(1)), "booster burnout at {:.2}s", at(1));
+    assert!(
+        (at(2) - at(1) - 3.2).abs() < 0.01,
+        "igniter at {:.2}s, booster burnout at {:.2}s",
+        at(2),
+        at(1)
+    );
+    assert!((7.0..7.3).contains(&at(3)), "sustainer lit at {:.2}s", at(3));
+    // The rest of the O3400's 6.3 s, and its tail-off.
+    let sustainer_out_t = truth.burnout_t();
+    assert!(
+        (sustainer_out_t - 0.5..sustainer_out_t + 1.0).contains(&at(4)),
+        "sustainer burnout at {:.2}s, thrust ends at {sustainer_out_t:.2}s",
+        at(4)
+    );
+
+    let (opened_t, _) = r.mpc_window.expect("the brakes never opened");
+    assert!(opened_t > at(4), "brakes opened at {opened_t:.2}s, under the sustainer");
+}
+
+/// An airframe the sequence will not light a motor on: too far off vertical
+/// on one flight, too slow on the other. The igniter is never commanded.
+/// The sim's sustainer lights anyway — nothing here models the igniter — and
+/// is still called, but the sequence stays inhibited.
+#[test]
+fn an_unfit_airframe_never_commands_the_sustainer() {
+    init_logger();
+    let (truth, staging) = staged_flight();
+    let samples = synthesize(
+        &truth,
+        &SensorModel {
+            until_s: 12.0,
+            ..Default::default()
+        },
+    );
+    for staging in [
+        StagingConfig {
+            max_tilt_rad: 0.1f32.to_radians(),
+            ..staging.clone()
+        },
+        StagingConfig {
+            min_vertical_velocity: 1000.0,
+            ..staging.clone()
+        },
+    ] {
+        let r = replay(
+            &samples,
+            FlightConfig {
+                staging: Some(staging.clone()),
+                ..osiris_config()
+            },
+            0.0,
+        );
+        eprintln!("staging, {staging:?}: {:?}", r.staging);
+        let events: Vec<StagingEvent> = r.staging.iter().map(|(_, e)| *e).collect();
+        assert!(!events.contains(&StagingEvent::IgniteSustainer), "{events:?}");
+        assert_eq!(events[2], StagingEvent::SustainerInhibited, "{events:?}");
+        assert!(
+            (r.staging[2].0 - r.staging[1].0 - 3.2).abs() < 0.01,
+            "inhibited at {:.2}s, not when the igniter was due",
+            r.staging[2].0
+        );
+    }
+}
+
+// ===========================================================================
+// Dispersion
+// ===========================================================================
 
+/// A few flights of the default [`Dispersion`](crate::sim::Dispersion) on
+/// the O3400: the run is the same flight for flight however it is threaded,
+/// and the config flies every one of them without a false call.
+///
+/// A smoke test of the harness, not a statistic — `rocket-cli dispersion`
+/// is where a thousand of these get flown.
+#[test]
+fn a_dispersion_run_is_repeatable_and_flies_every_flight() {
+    use crate::sim::{Dispersion, DispersionRun, Summary};
+
+    init_logger();
+    let truth = Truth::load(O3400_CSV);
+    let config = osiris_config();
+    let dispersion = Dispersion::default();
+    let run = DispersionRun {
+        truth: &truth,
+        config: &config,
+        target_apogee_asl: truth.apogee().1 - 150.0,
+        dispersion: &dispersion,
+        seed: 0x0517_2026_1018_0001,
+    };
+    let outcomes = run.run(4, 2, |_| {});
+    for o in &outcomes {
+        eprintln!("dispersion: {o:?}");
+    }
+
+    let again = run.fly(2);
+    assert_eq!(again.seed, outcomes[2].seed);
+    assert_eq!(again.apogee_error, outcomes[2].apogee_error);
+
+    let summary = Summary::of(&outcomes);
+    assert_eq!(summary.flights, 4);
+    assert_eq!(summary.false_ignitions, 0);
+    assert_eq!(summary.false_apogees, 0);
+    assert_eq!(summary.no_drogue, 0);
+    let apogee = summary.apogee_error.expect("no flight reported an apogee");
+    assert_eq!(apogee.count, 4);
+    assert!(
+        apogee.min > -80.0 && apogee.max < 80.0,
+        "apogee error outside the nominal flight's bound: {apogee:?}"
+    );
+}
+
+// ===========================================================================
+// Closed loop
+// ===========================================================================
+
+fn stowed_cd() -> Vec<(f32, f32)> {
+    use crate::sim::Airframe;
+    use crate::sim::osiris::STOWED_CD_CSV;
+
+    Airframe::load_stowed_cd(STOWED_CD_CSV).unwrap()
+}
+
+/// The O3400 file is OpenRocket's burn: the same impulse, the same burnout,
+/// and with [`osiris_airframe`](crate::sim::osiris::osiris_airframe) around
+/// it the same mass at liftoff and at burnout.
+#[test]
+fn the_eng_file_is_openrockets_motor() {
+    use crate::sim::Motor;
+    use crate::sim::osiris::{O3400_ENG, osiris_airframe};
+
+    let truth = Truth::load(O3400_CSV);
+    let motor = Motor::load(O3400_ENG).unwrap();
+    let airframe = osiris_airframe(&motor, stowed_cd());
+    let impulse: f32 = truth
+        .rows
+        .windows(2)
+        .map(|w| 0.5 * (w[0].thrust + w[1].thrust) * (w[1].t - w[0].t))
+        .sum();
+    eprintln!(
+        "{}: {:.0} N s over {:.3} s, OpenRocket {impulse:.0} N s over {:.3} s",
+        motor.name,
+        motor.total_impulse(),
+        motor.burn_time(),
+        truth.burnout_t()
+    );
+    assert!((motor.total_impulse() - impulse).abs() < 0.005 * impulse);
+    assert!((motor.burn_time() - truth.burnout_t()).abs() < 0.01);
+    let liftoff = airframe.dry_mass_kg + motor.mass_at(0.0);
+    let burnout = airframe.dry_mass_kg + motor.mass_at(motor.burn_time());
+    assert!(
+        (liftoff - truth.rows[0].mass).abs() < 1e-3,
+        "liftoff {liftoff} kg"
+    );
+    assert!(
+        (burnout - osiris_rocket().burnout_mass).abs() < 1e-3,
+        "burnout {burnout} kg"
+    );
+}
+
+/// With the brakes held stowed, the closed loop on the O3400 file is the
+/// OpenRocket flight: apogee and top Mach within a few percent, and the
+/// deployment half flies the whole recovery it would have — drogue, main,
+/// and the ground — into a log with every stage on it.
+///
+/// A few percent and not better because OpenRocket flew through its wind,
+/// which weathercocks the airframe and costs it height, and this flies
+/// calm; and because the stowed Cd is the coast's, which over-drags the
+/// burn.
+#[test]
+fn a_stowed_closed_loop_flight_is_the_openrocket_flight() {
+    use firmware_common_new::can_bus::messages::vl_status::FlightStage;
+    use firmware_common_new::flight_data_record::FlightEvent;
+
+    use crate::sim::osiris::{O3400_ENG, osiris_airframe, osiris_launch};
+    use crate::sim::{ClosedLoop, Motor};
+
+    init_logger();
+    let truth = Truth::load(O3400_CSV);
+    let motor = Motor::load(O3400_ENG).unwrap();
+    let airframe = osiris_airframe(&motor, stowed_cd());
+    let sim = ClosedLoop {
+        motor: &motor,
+        airframe: &airframe,
+        servo: ServoModel::ICARUS,
+        launch: osiris_launch(),
+        config: FlightConfig {
+            controller_mode: ControllerMode::Fixed { extension: 0.0 },
+            ..osiris_config()
+        },
+        target_apogee_agl: 9000.0,
+        sensors: SensorModel {
+            pad_s: 20.0,
+            ..Default::default()
+        },
+    };
+    let flight = sim.fly();
+
+    let or_apogee = truth
+        .rows
+        .iter()
+        .map(|r| r.altitude_agl)
+        .fold(0.0, f32::max);
+    let or_mach = truth.rows.iter().map(|r| r.mach).fold(0.0, f32::max);
+    eprintln!(
+        "closed loop, stowed: apogee {:.0} m AGL at {:.2}s vs OpenRocket {or_apogee:.0} m; \
+         max Mach {:.3} vs {or_mach:.3}; touchdown {:?}",
+        flight.apogee_agl, flight.apogee_t, flight.max_mach, flight.touchdown_t
+    );
+    assert!((flight.apogee_agl - or_apogee).abs() < 0.05 * or_apogee);
+    assert!((flight.max_mach - or_mach).abs() < 0.03 * or_mach);
+    assert!(flight.touchdown_t.is_some(), "never landed");
+    assert!(flight.truth.iter().all(|s| s.extension == 0.0));
+
+    let stages: Vec<FlightStage> = flight
+        .events()
+        .filter_map(|e| match e.event {
+            FlightEvent::StageChanged { to, .. } => Some(to),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        stages,
+        [
+            FlightStage::Ascent,
+            FlightStage::DrogueChute,
+            FlightStage::MainChute,
+            FlightStage::Landed
+        ]
+    );
+    let pyros = flight
+        .events()
+        .filter(|e| matches!(e.event, FlightEvent::PyroCommanded { .. }))
+        .count();
+    assert_eq!(pyros, 2);
+
+    // One fast record per true state, on a clock that only moves forward.
+    let fast: Vec<_> = flight.fast_records().collect();
+    assert_eq!(fast.len(), flight.truth.len());
+    assert!(fast.windows(2).all(|w| w[1].sequence == w[0].sequence + 1));
+    assert!(
+        fast.windows(2)
+            .all(|w| w[1].timestamp_us > w[0].timestamp_us)
+    );
+
+    // The log closes on the landing call, which waits out one whole
+    // landing window on the ground and not much more.
+    let last = fast.last().unwrap();
+    assert_eq!(last.flight_stage, FlightStage::Landed);
+    let touchdown = flight.touchdown_t.unwrap();
+    let end = flight.truth.last().unwrap().t;
+    assert!(
+        end - touchdown > 5.0 && end - touchdown < 10.0,
+        "log ends {:.2} s after touchdown",
+        end - touchdown
+    );
+}
+
+/// The loop closed: the MPC aimed 300 m under the stowed apogee brakes the
+/// flight down to it, through the servo model and on the estimators' own
+/// state.
+#[test]
+fn the_mpc_brakes_a_closed_loop_flight_to_its_target() {
+    use firmware_common_new::flight_data_record::LogRecord;
+
+    use crate::sim::osiris::{O3400_ENG, osiris_airframe, osiris_launch};
+    use crate::sim::{ClosedLoop, Motor};
+
+    init_logger();
+    let motor = Motor::load(O3400_ENG).unwrap();
+    let airframe = osiris_airframe(&motor, stowed_cd());
+    let sensors = SensorModel {
+        pad_s: 20.0,
+        until_s: 45.0,
+        ..Default::default()
+    };
+    let stowed = ClosedLoop {
+        motor: &motor,
+        airframe: &airframe,
+        servo: ServoModel::ICARUS,
+        launch: osiris_launch(),
+        config: FlightConfig {
+            controller_mode: ControllerMode::Fixed { extension: 0.0 },
+            ..osiris_config()
+        },
+        target_apogee_agl: 0.0,
+        sensors: sensors.clone(),
+    }
+    .fly();
+    let target_agl = stowed.apogee_agl - 300.0;
+    let braked = ClosedLoop {
+        motor: &motor,
+        airframe: &airframe,
+        servo: ServoModel::ICARUS,
+        launch: osiris_launch(),
+        config: osiris_config(),
+        target_apogee_agl: target_agl,
+        sensors,
+    }
+    .fly();
+
+    let peak_extension = braked.truth.iter().map(|s| s.extension).fold(0.0, f32::max);
+    let predictions = braked
+        .log
+        .iter()
+        .filter_map(|p| match &p.record {
+            LogRecord::Slow(slow) => slow.air_brakes.predicted_apogee_asl,
+            _ => None,
+        })
+        .count();
+    eprintln!(
+        "closed loop: stowed {:.0} m AGL, target {target_agl:.0} m, braked {:.0} m; \
+         peak extension {peak_extension:.2}, {predictions} MPC tick(s)",
+        stowed.apogee_agl, braked.apogee_agl
+    );
+    assert!(peak_extension > 0.3, "the brakes never opened");
+    assert!(predictions > 20, "the MPC hardly ran");
+    assert!(
+        (braked.apogee_agl - target_agl).abs() < 100.0,
+        "braked apogee {} m vs target {target_agl} m",
+        braked.apogee_agl
+    );
+}
+
+/// The `debug-internals` records a closed-loop flight writes: one every
+/// [`INTERNALS_RECORD_INTERVAL`] fast records, each stamped like the fast
+/// record it precedes, and enough of both filters in them for
+/// [`NisSummary`] to say something.
+///
+/// Deliberately no verdict asserted: whether the filters are consistent on
+/// synthesised sensors is a tuning question the print answers, not a
+/// property the build should fail on.
+///
+/// [`INTERNALS_RECORD_INTERVAL`]: firmware_common_new::flight_data_record::INTERNALS_RECORD_INTERVAL
+/// [`NisSummary`]: crate::NisSummary
+#[cfg(feature = "debug-internals")]
+#[test]
+fn a_closed_loop_flight_logs_both_filters_internals() {
+    use firmware_common_new::flight_data_record::{INTERNALS_RECORD_INTERVAL, merge_log_records};
+
+    use crate::kf_internals::{KfInternals, NisSummary};
+    use crate::sim::osiris::{O3400_ENG, osiris_airframe, osiris_launch};
+    use crate::sim::{ClosedLoop, Motor};
+
+    init_logger();
+    let motor = Motor::load(O3400_ENG).unwrap();
+    let airframe = osiris_airframe(&motor, stowed_cd());
+    let flight = ClosedLoop {
+        motor: &motor,
+        airframe: &airframe,
+        servo: ServoModel::ICARUS,
+        launch: osiris_launch(),
+        config: FlightConfig {
+            controller_mode: ControllerMode::Fixed { extension: 0.0 },
+            ..osiris_config()
+        },
+        target_apogee_agl: 0.0,
+        sensors: SensorModel {
+            pad_s: 20.0,
+            until_s: 45.0,
+            ..Default::default()
+        },
+    }
+    .fly();
+
+    let fast = flight.fast_records().count();
+    let internals: Vec<_> = flight.internals().collect();
+    assert_eq!(
+        internals.len(),
+        fast.div_ceil(INTERNALS_RECORD_INTERVAL as usize)
+    );
+
+    let mut deployment = NisSummary::new();
+    let mut airbrakes = NisSummary::new();
+    for record in &internals {
+        for (kf, summary) in [
+            (&record.deployment, &mut deployment),
+            (&record.airbrakes, &mut airbrakes),
+        ] {
+            if let Some(kf) = kf {
+                assert!(kf.innovation_variance > 0.0);
+                assert!(kf.altitude_variance > 0.0 && kf.velocity_variance > 0.0);
+                // Back through the record, as `rocket-cli` reads a card.
+                summary.add(&KfInternals::from(kf));
+            }
+        }
+    }
+    eprintln!("deployment filter: {deployment}");
+    eprintln!("airbrakes filter: {airbrakes}");
+    assert!(deployment.count() >= NisSummary::MIN_SAMPLES);
+    assert!(airbrakes.count() >= NisSummary::MIN_SAMPLES);
+    assert!(deployment.mean().unwrap().is_finite());
+
+    // Every record lands on the row of the fast record it was written with.
+    let rows = merge_log_records(&flight.log);
+    assert_eq!(
+        rows.iter().filter(|row| row.internals.is_some()).count(),
+        internals.len()
+    );
+}
+
+/// The RTS smoother, run over a closed-loop flight's own fast records, lands
+/// closer to the truth than the airbrakes filter that flew — over that
+/// filter's own life, the stretch it was built for — in altitude and in
+/// vertical speed. It has every reading the filter had and every one after,
+/// so anything else means the smoother's model is wrong.
+#[test]
+fn the_smoothed_trajectory_beats_the_flown_filter() {
+    use firmware_common_new::readings::BaroData;
+
+    use crate::airbrakes_estimator::ImuSample;
+    use crate::sim::osiris::{O3400_ENG, osiris_airframe, osiris_launch};
+    use crate::sim::{ClosedLoop, Motor};
+    use crate::smoother::{SmootherConfig, SmootherSample, smooth};
+
+    init_logger();
+    let motor = Motor::load(O3400_ENG).unwrap();
+    let airframe = osiris_airframe(&motor, stowed_cd());
+    let flight = ClosedLoop {
+        motor: &motor,
+        airframe: &airframe,
+        servo: ServoModel::ICARUS,
+        launch: osiris_launch(),
+        config: FlightConfig {
+            controller_mode: ControllerMode::Fixed { extension: 0.0 },
+            ..osiris_config()
+        },
+        target_apogee_agl: 0.0,
+        sensors: SensorModel {
+            pad_s: 20.0,
+            until_s: 45.0,
+            ..Default::default()
+        },
+    }
+    .fly();
+
+    // The fast record as `rocket-cli smooth-flight-log` reads it off the
+    // CSV: gyro in deg/s, the barometer as pressure.
+    let samples: Vec<SmootherSample> = flight
+        .fast_records()
+        .map(|fast| SmootherSample {
+            timestamp_us: fast.timestamp_us,
+            imu: fast.imu.as_ref().map(|imu| ImuSample {
+                acc: imu.acc.into(),
+                gyro: Vector3::from(imu.gyro) * (PI / 180.0),
+            }),
+            baro_altitude_asl: Some(
+                BaroData {
+                    temperature: 0.0,
+                    pressure: fast.pressure,
+                }
+                .altitude_asl(),
+            ),
+            gps: None,
+        })
+        .collect();
+    let liftoff = flight.truth.iter().position(|s| s.t >= 0.0).unwrap();
+    let config = SmootherConfig {
+        gps: None,
+        ..Default::default()
+    };
+    let smoothed = smooth(&samples, liftoff, &config).unwrap();
+    assert_eq!(smoothed.states.len(), samples.len());
+
+    let (mut flown, mut ours, mut count) = ([0.0f64; 2], [0.0f64; 2], 0);
+    for ((fast, truth), state) in flight
+        .fast_records()
+        .zip(&flight.truth)
+        .zip(&smoothed.states)
+    {
+        let Some(ab) = &fast.airbrakes else { continue };
+        let (Some(altitude), Some(velocity)) = (ab.kf_altitude_asl, ab.kf_vertical_velocity) else {
+            continue;
+        };
+        flown[0] += ((altitude - truth.altitude_asl) as f64).powi(2);
+        flown[1] += ((velocity - truth.velocity.z) as f64).powi(2);
+        ours[0] += ((state.altitude_asl - truth.altitude_asl) as f64).powi(2);
+        ours[1] += ((state.vertical_velocity - truth.velocity.z) as f64).powi(2);
+        count += 1;
+    }
+    assert!(count > 0, "the airbrakes filter was never born");
+    let rms = |sums: [f64; 2]| sums.map(|s| (s / count as f64).sqrt());
+    let (flown, ours) = (rms(flown), rms(ours));
+    eprintln!(
+        "over {count} samples: flown {:.2} m, {:.2} m/s; smoothed {:.2} m, {:.2} m/s (baro residual {:.2} m)",
+        flown[0],
+        flown[1],
+        ours[0],
+        ours[1],
+        smoothed.baro_residual_rms_m.unwrap()
+    );
+    assert!(
+        ours[0] < flown[0],
+        "altitude: smoothed {ours:?} vs flown {flown:?}"
+    );
+    assert!(
+        ours[1] < flown[1],
+        "velocity: smoothed {ours:?} vs flown {flown:?}"
+    );
+}
+
+/// The airframe fit, run over the smoothed trajectory of a stowed
+/// closed-loop flight, gives back the airframe that flew it: the stowed
+/// column of a table 30% heavy comes back to the simulator's stowed Cd at
+/// each Mach row, the stowed curve to the CSV the airframe was built from
+/// across the whole coast, and the thrust curve to the `.eng` file's
+/// impulse. The flaps never open, so the other columns stay the prior's.
+///
+/// To a few percent and not better because the fit reads the smoother's
+/// trajectory, not the truth, and the table is piecewise linear across a
+/// curve that is not; and the burn loses the thrust below the liftoff
+/// weight and after the deceleration latches.
+#[test]
+fn the_airframe_fit_recovers_the_simulated_airframe() {
+    use firmware_common_new::readings::BaroData;
+
+    use crate::airbrakes_estimator::ImuSample;
+    use crate::airframe_fit::{FitConfig, FitSample, fit};
+    use crate::sim::osiris::{O3400_ENG, osiris_airframe, osiris_launch};
+    use crate::sim::{ClosedLoop, Motor};
+    use crate::smoother::{SmootherConfig, SmootherSample, smooth};
+
+    init_logger();
+    let motor = Motor::load(O3400_ENG).unwrap();
+    let airframe = osiris_airframe(&motor, stowed_cd());
+    let flight = ClosedLoop {
+        motor: &motor,
+        airframe: &airframe,
+        servo: ServoModel::ICARUS,
+        launch: osiris_launch(),
+        config: FlightConfig {
+            controller_mode: ControllerMode::Fixed { extension: 0.0 },
+            ..osiris_config()
+        },
+        target_apogee_agl: 0.0,
+        sensors: SensorModel {
+            pad_s: 20.0,
+            until_s: 45.0,
+            ..Default::default()
+        },
+    }
+    .fly();
+
+    let samples: Vec<SmootherSample> = flight
+        .fast_records()
+        .map(|fast| SmootherSample {
+            timestamp_us: fast.timestamp_us,
+            imu: fast.imu.as_ref().map(|imu| ImuSample {
+                acc: imu.acc.into(),
+                gyro: Vector3::from(imu.gyro) * (PI / 180.0),
+            }),
+            baro_altitude_asl: Some(
+                BaroData {
+                    temperature: 0.0,
+                    pressure: fast.pressure,
+                }
+                .altitude_asl(),
+            ),
+            gps: None,
+        })
+        .collect();
+    let liftoff = flight.truth.iter().position(|s| s.t >= 0.0).unwrap();
+    let config = SmootherConfig {
+        gps: None,
+        ..Default::default()
+    };
+    let smoothed = smooth(&samples, liftoff, &config).unwrap();
+    let fit_samples: Vec<FitSample> = samples
+        .iter()
+        .zip(&smoothed.states)
+        .zip(&flight.truth)
+        .map(|((sample, state), truth)| FitSample {
+            timestamp_us: sample.timestamp_us,
+            state: *state,
+            extension: truth.extension,
+        })
+        .collect();
+
+    let prior = RocketParameters {
+        cd_mach: OSIRIS_CD_MACH,
+        ..osiris_rocket().with_cd_scale(1.3)
+    };
+    let config = FitConfig::new(prior.clone(), motor.propellant_kg, ISA);
+    let result = fit(&fit_samples, liftoff, &config).unwrap();
+    let drag = &result.drag;
+    eprintln!(
+        "{} coast samples over Mach {:.2}-{:.2}, residual {:.2} m/s^2 against the prior's {:.2}",
+        drag.samples,
+        drag.mach_range.0,
+        drag.mach_range.1,
+        drag.residual_rms,
+        drag.prior_residual_rms
+    );
+
+    for (row, &mach) in OSIRIS_CD_MACH.iter().enumerate() {
+        let cell = drag.cells[row][0];
+        let truth = airframe.cd(0.0, mach);
+        eprintln!(
+            "Mach {mach}: fitted {:.4} ±{:.4}, simulated {truth:.4}",
+            cell.cd, cell.sigma
+        );
+        assert!(cell.from_flight(), "Mach {mach}: support {}", cell.support);
+        assert!(
+            (cell.cd / truth - 1.0).abs() < 0.05,
+            "Mach {mach}: fitted {} vs simulated {truth}",
+            cell.cd
+        );
+        for column in 1..5 {
+            assert_eq!(drag.cells[row][column].support, 0.0);
+            assert_eq!(drag.rocket.cd[row][column], prior.cd[row][column]);
+        }
+    }
+    assert!(drag.residual_rms < 0.5 * drag.prior_residual_rms);
+    assert!(drag.above_table > 0, "Osiris coasts down from supersonic");
+
+    // The curve covers the coast from the drag rise up; the subsonic bins
+    // near apogee are left to the table's own check above.
+    let supersonic: Vec<&(f32, f32)> = drag
+        .stowed_cd
+        .iter()
+        .filter(|&&(mach, _)| mach >= 0.9)
+        .collect();
+    assert!(supersonic.len() >= 5, "stowed curve {:?}", drag.stowed_cd);
+    for &&(mach, cd) in &supersonic {
+        let truth = airframe.cd(0.0, mach);
+        assert!(
+            (cd / truth - 1.0).abs() < 0.05,
+            "stowed Mach {mach}: fitted {cd} vs simulated {truth}"
+        );
+    }
+
+    // The fitted curve as a motor: what `fit-airframe` writes, read back.
+    let thrust = &result.thrust;
+    let fitted = motor.with_thrust(&thrust.curve).unwrap();
+    let reread = Motor::parse(&fitted.to_eng()).unwrap();
+    eprintln!(
+        "{}: fitted {:.0} N s over {:.2} s, peak {:.0} N; the file's {:.0} N s over {:.2} s",
+        motor.name,
+        reread.total_impulse(),
+        reread.burn_time(),
+        thrust.peak,
+        motor.total_impulse(),
+        motor.burn_time()
+    );
+    assert!((reread.total_impulse() - thrust.total_impulse).abs() < 0.01 * thrust.total_impulse);
+    assert_eq!(reread.propellant_kg, motor.propellant_kg);
+    assert!(
+        (thrust.total_impulse / motor.total_impulse() - 1.0).abs() < 0.05,
+        "fitted {} N s vs {} N s",
+        thrust.total_impulse,
+        motor.total_impulse()
+    );
+    assert_eq!(thrust.missing, 0);
+}
@@ -16,26 +16,54 @@
 /// `libm::powf` called by name, which fixed the resolution but not the cost.
 /// Evaluating the curve directly has neither problem: there is no name to
 /// resolve, and no implementation whose accuracy can vary underneath it.
+///
+/// This is [`Atmosphere::standard`](crate::Atmosphere::standard)'s density;
+/// a calibrated [`Atmosphere`](crate::Atmosphere) evaluates the same kind of
+/// curve, rebased on the pad.
 pub fn approximate_air_density(altitude_asl: f32) -> f32 {
-    // Degree-4 least-squares fit (Chebyshev nodes) of that same curve in the
-    // dimensionless u, replacing the `powf` call it used to make. On the M7
-    // the `powf` measured 1207 cycles and was about a third of an airbrakes
-    // MPC solve; this is ~10 flops with no branch, no table walk and no
-    // transcendental. Max relative error against the exact f64 formula is
-    // 1.6e-6 including f32 Horner rounding, measured on-target at 2.0e-6
-    // worst (h = 10800 m) — f32 round-off, and four orders of magnitude
-    // tighter than the CFD Cd it feeds. Apogee predictions across the flight
-    // envelope are identical to the millimetre.
-    //
-    // `u` is clamped rather than the base being `.max(0.0)`: outside the fit
-    // domain (h in [-1000, 12000] m) a polynomial diverges instead of
-    // decaying, so density saturates at the edge value. That is not a loss of
-    // fidelity — the ISA troposphere model this approximates is itself only
-    // valid to 11 km — and it is strictly safer than the old behaviour, which
-    // returned exactly 0.0 above 44.3 km and so could divide by zero in the
-    // drag inversion in `airbrakes_estimator`.
-    let u = (2.25577e-5 * altitude_asl).clamp(-0.03, 0.28);
-    ((((1.936_849_5 * u - 6.368_716) * u + 8.486_722) * u - 5.213_590) * u) + 1.225_000_4
+    density_curve(&ISA_DENSITY_SERIES, 2.25577e-5 * altitude_asl)
+}
+
+/// `1.225 * (1 - u)^4.256`: the ISA troposphere's density (kg/m^3) as a
+/// polynomial in `u = 1 - T / T_sea_level`, i.e. in how far the lapse rate
+/// has taken the temperature below its base value.
+///
+/// Degree-4 least-squares fit (Chebyshev nodes) of that curve in the
+/// dimensionless u, replacing the `powf` call it used to make. On the M7
+/// the `powf` measured 1207 cycles and was about a third of an airbrakes
+/// MPC solve; this is ~10 flops with no branch, no table walk and no
+/// transcendental. Max relative error against the exact f64 formula is
+/// 1.6e-6 including f32 Horner rounding, measured on-target at 2.0e-6
+/// worst (h = 10800 m) — f32 round-off, and four orders of magnitude
+/// tighter than the CFD Cd it feeds. Apogee predictions across the flight
+/// envelope are identical to the millimetre.
+///
+/// Padded to [`SERIES_LEN`] with zeros so that [`crate::Atmosphere`] can
+/// hold it in the same slot as its own pad-fitted series; the zeros
+/// evaluate away exactly.
+pub(crate) const ISA_DENSITY_SERIES: [f32; SERIES_LEN] =
+    [1.225_000_4, -5.213_59, 8.486_722, -6.368_716, 1.936_849_5, 0.0, 0.0];
+
+/// Length of every polynomial [`horner`] evaluates.
+pub(crate) const SERIES_LEN: usize = 7;
+
+/// A density polynomial in `u` (see [`ISA_DENSITY_SERIES`]), evaluated
+/// within the domain it was fitted on.
+///
+/// `u` is clamped rather than the base being `.max(0.0)`: outside the fit
+/// domain (h in [-1000, 12000] m on the standard day) a polynomial diverges
+/// instead of decaying, so density saturates at the edge value. That is not
+/// a loss of fidelity — the troposphere model this approximates is itself
+/// only valid to 11 km — and it is strictly safer than the old behaviour,
+/// which returned exactly 0.0 above 44.3 km and so could divide by zero in
+/// the drag inversion in `airbrakes_estimator`.
+pub(crate) fn density_curve(series: &[f32; SERIES_LEN], u: f32) -> f32 {
+    horner(series, u.clamp(-0.03, 0.28))
+}
+
+/// `series[0] + series[1] x + series[2] x^2 + ...`, by Horner's rule.
+pub(crate) fn horner(series: &[f32; SERIES_LEN], x: f32) -> f32 {
+    series.iter().rev().fold(0.0, |acc, c| acc * x + c)
 }
 
 /// Square root that reaches the FPU.
@@ -86,6 +114,23 @@ pub fn lerp(
     (1.0 - t) * values[i] + t * values[i + 1]
 }
 
+/// Linear between `(x, y)` points, strictly increasing in x, and held past
+/// either end: a stowed cd curve read at a Mach number. Only the host-side
+/// tools read such curves, so only they build it.
+#[cfg(any(test, feature = "std"))]
+pub(crate) fn interpolate(points: &[(f32, f32)], x: f32) -> f32 {
+    let i = points.partition_point(|&(px, _)| px <= x);
+    if i == 0 {
+        return points[0].1;
+    }
+    if i == points.len() {
+        return points[points.len() - 1].1;
+    }
+    let (x0, y0) = points[i - 1];
+    let (x1, y1) = points[i];
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
+
 #[cfg(test)]
 mod test {
     use approx::assert_relative_eq;
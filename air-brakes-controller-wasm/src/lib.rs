@@ -1,7 +1,10 @@
 use air_brakes_controller_core::airbrakes_estimator::{AirbrakesConfig, MachLockoutConfig};
+use air_brakes_controller_core::flight_config_file;
 use air_brakes_controller_core::{
-    AirBrakesMPC, DeploymentProfile, FlightConfig, FlightEstimators, FlightProfile, ImuSample,
-    RocketParameters,
+    AirBrakesMPC, Atmosphere, AttitudeAngles, ControllerMode, DeploymentPolicy, DeploymentProfile,
+    ExtensionSchedule, FlightConfig, FlightConfigFile, FlightEstimators, FlightProfile, ImuSample,
+    LandingDetection, PyroEvents, RocketParameters, SCHEDULE_POINTS, ServoState, WIND_BINS,
+    WindProfile,
 };
 use nalgebra::{Vector2, Vector3};
 
@@ -27,13 +30,16 @@ pub extern "C" fn get_air_brakes_extension_percentage(
     current_horizontal_velocity: f32,
     current_vertical_velocity: f32,
 ) -> f32 {
-    let rocket_parameters = RocketParameters {
+    // The plugin hands over one cd table, so it holds at every Mach.
+    let rocket_parameters = RocketParameters::mach_independent(
         burnout_mass,
-        cd: [cd_0, cd_25, cd_50, cd_75, cd_100],
+        [cd_0, cd_25, cd_50, cd_75, cd_100],
         reference_area,
-    };
+    );
 
-    AirBrakesMPC::new(rocket_parameters, target_apogee_asl)
+    // The plugin's altitudes are OpenRocket's, in no atmosphere in
+    // particular; it has only ever been flown on the standard day.
+    AirBrakesMPC::new(rocket_parameters, Atmosphere::standard(), target_apogee_asl)
         .update(
             current_altitude_asl,
             Vector2::new(current_horizontal_velocity, current_vertical_velocity),
@@ -54,7 +60,27 @@ pub extern "C" fn get_air_brakes_extension_percentage(
 static mut ESTIMATORS: Option<FlightEstimators> = None;
 static mut ROCKET: Option<RocketParameters> = None;
 static mut MPC: Option<AirBrakesMPC> = None;
+/// The wind `harness_wind_add_bin` has uploaded so far, `(altitude_asl,
+/// east, north)`; the first `WIND_LEN` are used.
+static mut WIND: [(f32, f32, f32); WIND_BINS] = [(0.0, 0.0, 0.0); WIND_BINS];
+static mut WIND_LEN: usize = 0;
+/// The mode `harness_controller_mode` selected, for the next `harness_init`.
+static mut CONTROLLER_MODE: ControllerMode = ControllerMode::TargetApogee;
+/// The breakpoints `harness_schedule_add_point` has uploaded so far,
+/// `(time_s, extension)`; the first `SCHEDULE_LEN` are used.
+static mut SCHEDULE: [(f32, f32); SCHEDULE_POINTS] = [(0.0, 0.0); SCHEDULE_POINTS];
+static mut SCHEDULE_LEN: usize = 0;
+/// The mode behind `harness_mpc_tick`'s last command, -1 before one.
+static mut LAST_MODE: i32 = -1;
 static mut LAST_PREDICTED_APOGEE_ASL: f32 = f32::NAN;
+/// What `harness_mpc_tick` last commanded, fed back into the next
+/// `harness_update` the way firmware feeds the airbrakes record's field.
+static mut LAST_COMMANDED_EXTENSION: Option<f32> = None;
+/// Icarus's latest reported extension, from `harness_update_servo`; `None`
+/// until it has reported, and the MPC then solves without the servo.
+static mut ACTUAL_EXTENSION: Option<f32> = None;
+/// The flight config file `harness_config_buffer` handed the host to write.
+static mut CONFIG_FILE: Vec<u8> = Vec::new();
 
 #[allow(static_mut_refs)]
 fn estimators() -> Option<&'static mut FlightEstimators> {
@@ -68,6 +94,17 @@ fn estimators() -> Option<&'static mut FlightEstimators> {
 /// `mach_lockout_duration_us` and `earliest/force` are `f32` seconds rather
 /// than integer microseconds purely so every argument crossing the ABI is
 /// one 32-bit float; they are converted here, not stored as floats.
+///
+/// The cd table crosses as its `CD_MACH_POINTS` rows in order, each a Mach
+/// number and then the five extension entries at that Mach. A table from a
+/// single Mach number is passed as four identical rows; the Mach values
+/// then only need to be increasing.
+///
+/// The controller mode is whatever `harness_controller_mode` last selected,
+/// the target apogee if nothing did.
+///
+/// [`harness_init_from_config`] is the other way in: the whole flight config
+/// from a file, pyro table, backup channel and all.
 #[unsafe(no_mangle)]
 pub extern "C" fn harness_init(
     ignition_detection_acc_threshold: f32,
@@ -85,16 +122,41 @@ pub extern "C" fn harness_init(
     airbrakes_crossing_altitude_asl: f32,
     max_open_mach: f32,
     burnout_mass: f32,
-    cd_0: f32,
-    cd_25: f32,
-    cd_50: f32,
-    cd_75: f32,
-    cd_100: f32,
+    mach_0: f32,
+    cd_0_0: f32,
+    cd_0_25: f32,
+    cd_0_50: f32,
+    cd_0_75: f32,
+    cd_0_100: f32,
+    mach_1: f32,
+    cd_1_0: f32,
+    cd_1_25: f32,
+    cd_1_50: f32,
+    cd_1_75: f32,
+    cd_1_100: f32,
+    mach_2: f32,
+    cd_2_0: f32,
+    cd_2_25: f32,
+    cd_2_50: f32,
+    cd_2_75: f32,
+    cd_2_100: f32,
+    mach_3: f32,
+    cd_3_0: f32,
+    cd_3_25: f32,
+    cd_3_50: f32,
+    cd_3_75: f32,
+    cd_3_100: f32,
     reference_area: f32,
 ) {
     let rocket = RocketParameters {
         burnout_mass,
-        cd: [cd_0, cd_25, cd_50, cd_75, cd_100],
+        cd_mach: [mach_0, mach_1, mach_2, mach_3],
+        cd: [
+            [cd_0_0, cd_0_25, cd_0_50, cd_0_75, cd_0_100],
+            [cd_1_0, cd_1_25, cd_1_50, cd_1_75, cd_1_100],
+            [cd_2_0, cd_2_25, cd_2_50, cd_2_75, cd_2_100],
+            [cd_3_0, cd_3_25, cd_3_50, cd_3_75, cd_3_100],
+        ],
         reference_area,
     };
 
@@ -131,22 +193,84 @@ pub extern "C" fn harness_init(
                 Some((deployment_mach_lockout_s * 1e6) as u32)
             },
             deployment,
+            landing: LandingDetection::default(),
+            pyro_events: PyroEvents::new(),
+            gps_aiding: None,
         },
         airbrakes: AirbrakesConfig {
             mach_lockout,
             max_open_mach,
-            rocket: rocket.clone(),
+            rocket,
         },
+        controller_mode: unsafe { CONTROLLER_MODE },
+        // The harness replays the deployment half's own decisions; a backup
+        // channel would need a config the plugin has no fields for.
+        deployment_policy: DeploymentPolicy::PrimaryOnly,
+        // Nor has it fields for a second burn.
+        staging: None,
     };
+    install(config);
+}
 
+/// Start a flight on `config`: what both ways of initialising the harness
+/// end in.
+fn install(config: FlightConfig) {
     unsafe {
+        ROCKET = Some(config.airbrakes.rocket.clone());
         ESTIMATORS = Some(FlightEstimators::new(config));
-        ROCKET = Some(rocket);
         MPC = None;
         LAST_PREDICTED_APOGEE_ASL = f32::NAN;
+        LAST_COMMANDED_EXTENSION = None;
+        ACTUAL_EXTENSION = None;
+        LAST_MODE = -1;
+    }
+}
+
+/// A buffer of `len` bytes for the host to write a flight config file into,
+/// for [`harness_init_from_config`]. Replaces any earlier one, so write into
+/// the pointer this call returns and no other.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_config_buffer(len: i32) -> *mut u8 {
+    #[allow(static_mut_refs)]
+    unsafe {
+        CONFIG_FILE = vec![0; len.max(0) as usize];
+        CONFIG_FILE.as_mut_ptr()
     }
 }
 
+/// [`harness_init`] from the flight config TOML the host has written into
+/// [`harness_config_buffer`] — the same file `rocket-cli flight-config`
+/// checks and the replay tools read, so a sweep can fly any airframe the
+/// file can describe instead of the one the positional arguments can.
+///
+/// Everything comes from the file, the controller mode included:
+/// `harness_controller_mode` has no say over a flight started here.
+///
+/// Returns 1 when the flight is started. Returns 0, and leaves any flight
+/// already running alone, when the file is refused: not UTF-8 or not TOML,
+/// a key this build does not know, another file version, or a config that
+/// fails [`flight_config_file::check`]. `rocket-cli flight-config check`
+/// says which.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_init_from_config() -> i32 {
+    #[allow(static_mut_refs)]
+    let text = match core::str::from_utf8(unsafe { &CONFIG_FILE }) {
+        Ok(text) => text,
+        Err(_) => return 0,
+    };
+    let Ok(file) = toml::from_str::<FlightConfigFile>(text) else {
+        return 0;
+    };
+    let Ok(config) = file.to_flight_config() else {
+        return 0;
+    };
+    if !flight_config_file::check(&config, &file.atmosphere.to_atmosphere()).passed() {
+        return 0;
+    }
+    install(config);
+    1
+}
+
 /// One sensor sample. `time_s` is the sample's timestamp on the host's
 /// monotonic clock; the estimators measure every dt from it, so an
 /// irregular feed is integrated honestly rather than assumed away.
@@ -179,7 +303,15 @@ pub extern "C" fn harness_update(
         acc: Vector3::new(acc_x, acc_y, acc_z),
         gyro: Vector3::new(gyro_x, gyro_y, gyro_z),
     };
-    let (pyro, log) = estimators.update((time_s * 1e6) as u64, Some(&imu), baro_altitude_asl);
+    let commanded_extension = unsafe { LAST_COMMANDED_EXTENSION };
+    let (pyro, _staging, log) = estimators.update(
+        (time_s * 1e6) as u64,
+        Some(&imu),
+        &[Some(baro_altitude_asl)],
+        // `harness_init` builds a baro-only profile.
+        None,
+        commanded_extension,
+    );
 
     let mut flags = 0i32;
     if estimators.airbrakes_mpc_states().is_some() {
@@ -205,6 +337,40 @@ pub extern "C" fn harness_update(
     flags
 }
 
+/// One magnetometer reading, in the IMU's axes and any units, on the same
+/// clock as [`harness_update`]. Optional: it only gives the pad attitude a
+/// north, so [`harness_estimated_yaw_rad`] is a compass heading.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_update_mag(time_s: f64, mag_x: f32, mag_y: f32, mag_z: f32) {
+    if let Some(estimators) = estimators() {
+        estimators.update_mag((time_s * 1e6) as u64, &Vector3::new(mag_x, mag_y, mag_z));
+    }
+}
+
+/// One air temperature reading (K), on the same clock as
+/// [`harness_update`]: the simulator's air at the pad, which is what the
+/// flight flies through. Optional, and read on the pad only — the
+/// estimators calibrate the atmosphere from it and the pad pressure at
+/// launch ([`FlightEstimators::atmosphere`]); without it the flight stays on
+/// the standard day. `harness_update`'s baro altitudes stay ISA pressure
+/// altitudes either way — the estimators convert them, as on the board.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_update_air_temperature(time_s: f64, temperature: f32) {
+    if let Some(estimators) = estimators() {
+        estimators.update_air_temperature((time_s * 1e6) as u64, temperature);
+    }
+}
+
+/// Icarus's reported extension (`IcarusStatusMessage`, 0.0 - 1.0), at its
+/// own 100 Hz or whenever it arrives. Optional: without it the MPC holds one
+/// candidate, as it did before it flew the servo.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_update_servo(actual_extension: f32) {
+    unsafe {
+        ACTUAL_EXTENSION = actual_extension.is_finite().then_some(actual_extension);
+    }
+}
+
 /// The pad reference the MPC's AGL target is measured from.
 #[unsafe(no_mangle)]
 pub extern "C" fn harness_launch_pad_altitude_asl() -> f32 {
@@ -241,8 +407,110 @@ pub extern "C" fn harness_estimated_tilt_rad() -> f32 {
         .unwrap_or(f32::NAN)
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_estimated_roll_rad() -> f32 {
+    estimated_attitude().map_or(f32::NAN, |a| a.roll)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_estimated_pitch_rad() -> f32 {
+    estimated_attitude().map_or(f32::NAN, |a| a.pitch)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_estimated_yaw_rad() -> f32 {
+    estimated_attitude().map_or(f32::NAN, |a| a.yaw)
+}
+
+fn estimated_attitude() -> Option<AttitudeAngles> {
+    estimators()
+        .and_then(|e| e.airbrakes_estimator())
+        .and_then(|ab| ab.attitude())
+}
+
+/// Forget any uploaded wind: the MPC flies calm air, as it does when none
+/// was ever uploaded.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_wind_clear() {
+    unsafe { WIND_LEN = 0 }
+}
+
+/// Upload one bin of the wind profile the MPC flies through
+/// ([`WindProfile::binned`]): the velocity the air moves with (m/s, toward
+/// east and toward north) at `altitude_asl`, in the frame
+/// `harness_estimated_altitude_asl` reports. Bins go in increasing altitude; one bin is a wind
+/// that is the same at every altitude. Pass the simulator's own wind — it
+/// is what the flight flies through.
+///
+/// Takes effect at the next [`harness_mpc_latch_target`], like the pre-launch
+/// upload it stands in for. Returns 0 when the bin is refused — more than
+/// [`WIND_BINS`], out of order, or non-finite — and 1 otherwise.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_wind_add_bin(altitude_asl: f32, east: f32, north: f32) -> i32 {
+    #[allow(static_mut_refs)]
+    unsafe {
+        if WIND_LEN == WIND_BINS {
+            return 0;
+        }
+        WIND[WIND_LEN] = (altitude_asl, east, north);
+        if WindProfile::binned(&WIND[..=WIND_LEN]).is_none() {
+            return 0;
+        }
+        WIND_LEN += 1;
+    }
+    1
+}
+
+/// Select what the brakes are flown for, before `harness_init`:
+///   0  the target apogee (the default)
+///   1  as much drag as the target allows, the target a floor
+///   2  the schedule `harness_schedule_add_point` has uploaded
+///   3  `extension` (0.0 - 1.0), held
+///
+/// Returns 0 when the mode is refused — an unknown kind, an extension out
+/// of range, or a schedule with no breakpoints — and 1 otherwise.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_controller_mode(kind: i32, extension: f32) -> i32 {
+    #[allow(static_mut_refs)]
+    let mode = match kind {
+        0 => ControllerMode::TargetApogee,
+        1 => ControllerMode::MaximiseDrag,
+        2 => match ExtensionSchedule::from_points(unsafe { &SCHEDULE[..SCHEDULE_LEN] }) {
+            Some(schedule) => ControllerMode::Scheduled(schedule),
+            None => return 0,
+        },
+        3 if (0.0..=1.0).contains(&extension) => ControllerMode::Fixed { extension },
+        _ => return 0,
+    };
+    unsafe { CONTROLLER_MODE = mode }
+    1
+}
+
+/// Upload one breakpoint of the extension schedule
+/// ([`ExtensionSchedule::from_points`]): `extension` from `time_s` seconds
+/// after the brakes may open. Breakpoints go in increasing time. Takes
+/// effect at the next `harness_controller_mode(2, _)`. Returns 0 when the
+/// breakpoint is refused — more than [`SCHEDULE_POINTS`], out of order, or
+/// out of range — and 1 otherwise.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_schedule_add_point(time_s: f32, extension: f32) -> i32 {
+    #[allow(static_mut_refs)]
+    unsafe {
+        if SCHEDULE_LEN == SCHEDULE_POINTS {
+            return 0;
+        }
+        SCHEDULE[SCHEDULE_LEN] = (time_s, extension);
+        if ExtensionSchedule::from_points(&SCHEDULE[..=SCHEDULE_LEN]).is_none() {
+            return 0;
+        }
+        SCHEDULE_LEN += 1;
+    }
+    1
+}
+
 /// Latch the MPC's target, once, exactly as `armed_mode` does: the pad
-/// reference the deployment half is holding plus the configured AGL.
+/// reference the deployment half is holding plus the configured AGL. The
+/// MPC flies the wind uploaded by then, or calm air if there is none.
 #[unsafe(no_mangle)]
 pub extern "C" fn harness_mpc_latch_target(target_apogee_agl: f32) -> f32 {
     let Some(estimators) = estimators() else {
@@ -251,24 +519,36 @@ pub extern "C" fn harness_mpc_latch_target(target_apogee_agl: f32) -> f32 {
     let target_asl = estimators.launch_pad_altitude_asl() + target_apogee_agl;
     #[allow(static_mut_refs)]
     unsafe {
-        MPC = Some(AirBrakesMPC::new(
-            ROCKET.clone().expect("harness_init first"),
-            target_asl,
-        ));
+        let wind = WindProfile::binned(&WIND[..WIND_LEN]).unwrap_or_default();
+        MPC = Some(
+            AirBrakesMPC::new(
+                ROCKET.clone().expect("harness_init first"),
+                estimators.atmosphere(),
+                target_asl,
+            )
+            .with_wind(wind),
+        );
     }
     target_asl
 }
 
-/// One 10 Hz control tick against the estimator's own state. Returns the
-/// commanded extension, or -1.0 when the gate is shut (which firmware
-/// treats as a commanded 0.0 with no prediction).
+/// One 10 Hz control tick against the estimator's own state, flying the
+/// airframe at the estimator's in-flight cd scale, through the uploaded wind
+/// along the estimator's compass heading, and planning over the servo from
+/// where `harness_update_servo` last put it. Returns the commanded
+/// extension, or -1.0 when the gate is shut (which firmware treats as a
+/// commanded 0.0 with no prediction, and which the next `harness_update`
+/// is told as exactly that).
 #[unsafe(no_mangle)]
 pub extern "C" fn harness_mpc_tick() -> f32 {
     let Some(estimators) = estimators() else {
         return -1.0;
     };
     let Some(states) = estimators.airbrakes_mpc_states() else {
-        unsafe { LAST_PREDICTED_APOGEE_ASL = f32::NAN };
+        unsafe {
+            LAST_PREDICTED_APOGEE_ASL = f32::NAN;
+            LAST_COMMANDED_EXTENSION = Some(0.0);
+        }
         return -1.0;
     };
     #[allow(static_mut_refs)]
@@ -276,8 +556,16 @@ pub extern "C" fn harness_mpc_tick() -> f32 {
     let Some(mpc) = mpc else {
         return -1.0;
     };
-    let solution = mpc.update(states.altitude_asl, states.velocity);
-    unsafe { LAST_PREDICTED_APOGEE_ASL = solution.predicted_apogee_asl };
+    let servo = unsafe { ACTUAL_EXTENSION }.map(|actual_extension| ServoState {
+        actual_extension,
+        commanded_extension: unsafe { LAST_COMMANDED_EXTENSION }.unwrap_or(0.0),
+    });
+    let solution = mpc.update_with_servo(&states, servo);
+    unsafe {
+        LAST_PREDICTED_APOGEE_ASL = solution.predicted_apogee_asl;
+        LAST_COMMANDED_EXTENSION = Some(solution.extension_percentage);
+        LAST_MODE = solution.mode as i32;
+    }
     solution.extension_percentage
 }
 
@@ -285,3 +573,19 @@ pub extern "C" fn harness_mpc_tick() -> f32 {
 pub extern "C" fn harness_mpc_predicted_apogee_asl() -> f32 {
     unsafe { LAST_PREDICTED_APOGEE_ASL }
 }
+
+/// The mode behind the last command (`harness_controller_mode`'s numbering),
+/// or -1 before the first.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_mpc_mode() -> i32 {
+    unsafe { LAST_MODE }
+}
+
+/// The airbrakes half's in-flight cd scale, or NaN before the vertical
+/// filter is born and after the half is retired.
+#[unsafe(no_mangle)]
+pub extern "C" fn harness_cd_scale() -> f32 {
+    estimators()
+        .and_then(|e| e.airbrakes_mpc_states())
+        .map_or(f32::NAN, |s| s.cd_scale)
+}
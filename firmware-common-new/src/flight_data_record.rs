@@ -2,6 +2,7 @@ use crate::can_bus::messages::{
     node_status::{NodeHealth, NodeMode, NodeStatusMessage},
     vl_status::FlightStage,
 };
+use crate::vlp::packets::fire_pyro::PyroSelect;
 
 /// One CAN node's last `NodeStatusMessage`, stored whole.
 ///
@@ -45,6 +46,13 @@ impl NodeStatusRecord {
 pub const RECORD_TAG_FAST: u8 = 0x01;
 /// Low-rate GPS / battery / AMP / temperature / airbrakes-actuation snapshot.
 pub const RECORD_TAG_SLOW: u8 = 0x02;
+/// One discrete event ([`FlightEventRecord`]), written when it happens.
+pub const RECORD_TAG_EVENT: u8 = 0x03;
+/// What the two estimators' Kalman filters did with a measurement
+/// ([`EstimatorInternalsRecord`]), one per [`INTERNALS_RECORD_INTERVAL`] fast
+/// records. Written only by a firmware built with the core crate's
+/// `debug-internals` feature.
+pub const RECORD_TAG_INTERNALS: u8 = 0x04;
 
 /// One IMU sample: both halves come from the same read, so they are present
 /// or absent together.
@@ -66,6 +74,10 @@ pub struct DeploymentEstimatorRecord {
     /// Status bits (`DEPLOYMENT_*` consts): what the baro innovation gate did
     /// with THIS sample.
     pub flags: u8,
+    /// What the baro vote made of each barometer on THIS sample, three bits
+    /// per sensor in the order the firmware hands them to the estimator.
+    /// Decode with [`BaroHealth::unpack`].
+    pub baro_health: u16,
 }
 
 /// The airbrakes estimator's output for one fast sample.
@@ -120,10 +132,11 @@ pub struct FlightDataFastRecord {
     /// at the full fast rate — the chutes' deployment shows up as this
     /// changing stage, and the pyro edges themselves as `pyro_flags`.
     pub flight_stage: FlightStage,
-    /// Bitmask for pyro continuity/fire state (`PYRO_*` consts). Logged at
-    /// the full fast rate so pyro fire edges are timestamped to ±2.3 ms.
-    /// `None` until there is anything on the continuity watch to report.
-    pub pyro_flags: Option<u8>,
+    /// Bitmask for pyro continuity/fire state (`PYRO_*` consts), one pair
+    /// per channel. Logged at the full fast rate so pyro fire edges are
+    /// timestamped to ±2.3 ms. `None` until there is anything on the
+    /// continuity watch to report.
+    pub pyro_flags: Option<u16>,
     /// Brake command and Icarus's reported extension, at the full fast rate
     /// for the same reason as `pyro_flags`: the edges are the measurement.
     ///
@@ -333,10 +346,124 @@ pub struct FlightDataSlowRecord {
     pub payload_sdrm_node: Option<NodeStatusRecord>,
 }
 
+/// Something that happened at an instant, as opposed to a sample.
+///
+/// Most of these used to be inferable only from a flag changing between two
+/// fast records, if at all: an uplink that failed its signature changed no
+/// flag, and a node reboot between two slow snapshots showed only as a lower
+/// `uptime_s`. An event record says what happened and when, to the
+/// microsecond, with nothing to reconstruct.
+///
+/// Enumerations from elsewhere in the crate that have no archived form are
+/// stored as their `u8` discriminants, named in each field's doc.
+#[derive(
+    rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Clone, Copy, PartialEq,
+    serde::Serialize, serde::Deserialize,
+)]
+pub enum FlightEvent {
+    /// `flight_stage` changed. Also visible on the fast records; logged so
+    /// the event stream reads as a complete account on its own.
+    StageChanged { from: FlightStage, to: FlightStage },
+    /// A VLP `ChangeMode` uplink was acted on. `mode` is a
+    /// `vlp::packets::change_mode::Mode` discriminant.
+    ModeChangeCommanded { mode: u8 },
+    /// A VLP uplink arrived and decoded. `packet_type` is its
+    /// `VLPUplinkPacket` type byte. An uplink whose signature did not verify
+    /// is logged and then ignored, which is exactly the case nothing else in
+    /// the log would show.
+    UplinkReceived {
+        packet_type: u8,
+        signature_valid: bool,
+    },
+    /// A radio packet failed Reed-Solomon decoding and was dropped.
+    EccFailure,
+    /// A pyro channel was commanded to fire. `channel` is a
+    /// `vlp::packets::fire_pyro::PyroSelect` discriminant; `by_uplink` tells a
+    /// ground command from the flight computer's own decision.
+    PyroCommanded { channel: u8, by_uplink: bool },
+    /// A CAN node's `uptime_s` stepped backwards. `node_type` is one of the
+    /// `can_bus::node_types` constants; `uptime_s` is what it reported after.
+    NodeRebooted { node_type: u8, uptime_s: u32 },
+    /// A new avionics config block was stored. The target is the setting a
+    /// flight review asks about first; the rest is in the block itself.
+    ConfigChanged { target_apogee_agl: f32 },
+    /// Something in a staged flight's burn sequence: an ignition, a burnout,
+    /// or the sustainer commanded, inhibited or not lit. `kind` and `burn` are
+    /// `air_brakes_controller_core::StagingEvent::to_parts`, which the reader
+    /// turns back with `from_parts`; `burn` counts from 0, the motor that left
+    /// the pad.
+    Staging { kind: u8, burn: u8 },
+}
+
+#[derive(
+    rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Clone, Copy, PartialEq,
+    serde::Serialize, serde::Deserialize,
+)]
+pub struct FlightEventRecord {
+    /// Same boot-relative clock as [`FlightDataFastRecord::timestamp_us`].
+    pub timestamp_us: u64,
+    pub event: FlightEvent,
+}
+
+/// One fast record in this many is shadowed by an [`EstimatorInternalsRecord`]:
+/// ~52 Hz at the sensor task's rate.
+///
+/// A fixed rate rather than every sample because what the record is for is
+/// tuning, and a filter's consistency is a statistic over hundreds of
+/// updates, not a property of any one — while writing it on every sample
+/// would add a third again to the fast stream's SD bandwidth. At one in
+/// eight it adds about 4%, and a tuning flight still yields thousands of
+/// innovations per filter.
+pub const INTERNALS_RECORD_INTERVAL: u32 = 8;
+
+/// One Kalman filter's measurement update, as the core crate's `KfInternals`
+/// reports it: the innovation, the variance the filter expected of it, and
+/// the diagonal of its covariance afterwards.
+///
+/// `innovation^2 / innovation_variance` is the normalised innovation squared,
+/// chi-squared with one degree of freedom in a filter whose noise model is
+/// right — the number the consistency check is made of.
+#[derive(rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Clone, PartialEq)]
+pub struct KfInternalsRecord {
+    /// Baro altitude minus the predicted altitude (m).
+    pub innovation: f32,
+    /// H P Hᵀ + R at the update (m²).
+    pub innovation_variance: f32,
+    /// P's altitude variance after the update (m²).
+    pub altitude_variance: f32,
+    /// P's vertical velocity variance after the update (m²/s²).
+    pub velocity_variance: f32,
+    /// [`DEPLOYMENT_BARO_GATE_REJECT`] and [`DEPLOYMENT_BARO_RESYNC`]: what
+    /// the innovation gate did with the measurement. Always 0 for the
+    /// airbrakes filter, which has no gate.
+    pub gate_flags: u8,
+}
+
+/// Both estimators' [`KfInternalsRecord`]s for the fast record with the same
+/// `timestamp_us`, which is written immediately after this.
+///
+/// Its own record rather than fields on the fast one, so that a board built
+/// without the instrumentation writes exactly the stream it always did, and
+/// a reader gets the columns wherever a card has them.
+#[derive(rkyv::Serialize, rkyv::Deserialize, rkyv::Archive, Debug, Clone, PartialEq)]
+pub struct EstimatorInternalsRecord {
+    /// Same boot-relative clock as [`FlightDataFastRecord::timestamp_us`].
+    pub timestamp_us: u64,
+    /// `None` on a sample where no barometer reading reached the deployment
+    /// filter's update: before it is born, with no reading, or while GPS
+    /// carries altitude. A reading the gate threw out is still here, flagged.
+    pub deployment: Option<KfInternalsRecord>,
+    /// `None` on a sample where the airbrakes filter fused nothing, which
+    /// is every sample outside its life from birth to retirement.
+    pub airbrakes: Option<KfInternalsRecord>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum LogRecord {
     Fast(FlightDataFastRecord),
     Slow(FlightDataSlowRecord),
+    Event(FlightEventRecord),
+    Internals(EstimatorInternalsRecord),
 }
 
 /// One record as read back off the card, tagged with the health of the
@@ -377,8 +504,9 @@ pub struct FlightDataRecord {
     /// means (a drop forwards, a session boundary backwards).
     pub record_count: u32,
     /// The 512-byte block behind some of this row's data failed its CRC32
-    /// trailer, so at least one byte in it is wrong. Set when either the fast
-    /// sample or the slow snapshot the row carries came out of a bad block.
+    /// trailer, so at least one byte in it is wrong. Set when the fast sample,
+    /// the slow snapshot or the internals the row carries came out of a bad
+    /// block.
     ///
     /// Such rows are still exported — one bad block must not make an otherwise
     /// good flight log unrecoverable — but nothing on them is trustworthy.
@@ -414,6 +542,11 @@ pub struct FlightDataRecord {
 
     pub airbrakes: Option<AirbrakesEstimatorRecord>,
 
+    /// The filters' internals for this sample, on the one row in
+    /// [`INTERNALS_RECORD_INTERVAL`] a `debug-internals` build shadowed, and
+    /// `None` on every other row and every row of any other build.
+    pub internals: Option<EstimatorInternalsRecord>,
+
     /// MS5607 die temperature (C), from the slow snapshot.
     pub temperature: Option<f32>,
     pub battery_voltage: Option<f32>,
@@ -435,7 +568,7 @@ pub struct FlightDataRecord {
 
     /// Bitmask for pyro continuity/fire state (see firmware `ContinuityUpdate`).
     /// Full rate, from the fast record.
-    pub pyro_flags: Option<u8>,
+    pub pyro_flags: Option<u16>,
 
     /// Brake command and Icarus's report, from the fast record — so a
     /// commanded step and the extension that follows it are on rows 2.3 ms
@@ -465,9 +598,12 @@ impl FlightDataRecord {
     ///
     /// `source_block_crc_failed` covers the whole row: pass `true` if either
     /// the fast record or the snapshot came from a block that failed its CRC.
+    /// `internals` is the record written just before `fast`, if there was
+    /// one; it lands on this row only if it was taken on the same sample.
     pub fn from_fast_and_slow(
         fast: &FlightDataFastRecord,
         slow: Option<&FlightDataSlowRecord>,
+        internals: Option<&EstimatorInternalsRecord>,
         source_block_crc_failed: bool,
     ) -> Self {
         Self {
@@ -481,6 +617,9 @@ impl FlightDataRecord {
             mag: fast.mag,
             deployment: fast.deployment.clone(),
             airbrakes: fast.airbrakes.clone(),
+            internals: internals
+                .filter(|internals| internals.timestamp_us == fast.timestamp_us)
+                .cloned(),
             temperature: slow.map(|s| s.temperature),
             battery_voltage: slow.and_then(|s| s.battery_voltage),
             lat_lon: slow.and_then(|s| s.lat_lon),
@@ -520,6 +659,11 @@ impl FlightDataRecord {
 /// at most one slow period (~100 ms) of real data at each boundary — the
 /// records interleave at ~42 fast per slow, so a session almost always opens on
 /// a fast record — and it is the only direction that cannot mislabel a row.
+///
+/// An [`EstimatorInternalsRecord`] is never held that long. It is written
+/// immediately ahead of the fast record it shadows, so it goes on the next
+/// fast row if their timestamps agree and is dropped either way: internals
+/// from a sample whose fast record was lost have no row to belong to.
 #[cfg(any(feature = "std", test))]
 pub fn merge_log_records(log: &[ParsedLogRecord]) -> std::vec::Vec<FlightDataRecord> {
     let mut slow: Option<FlightDataSlowRecord> = None;
@@ -527,6 +671,9 @@ pub fn merge_log_records(log: &[ParsedLogRecord]) -> std::vec::Vec<FlightDataRec
     // it came from a bad block, and the snapshot outlives the record it came in.
     let mut slow_from_bad_block = false;
     let mut prev_sequence: Option<u32> = None;
+    // Held for exactly one fast record, the one it was written ahead of, and
+    // carries its block's health onto that row the way the snapshot does.
+    let mut internals: Option<(EstimatorInternalsRecord, bool)> = None;
     let mut out = std::vec::Vec::new();
     for parsed in log {
         match &parsed.record {
@@ -534,16 +681,26 @@ pub fn merge_log_records(log: &[ParsedLogRecord]) -> std::vec::Vec<FlightDataRec
                 slow = Some(s.clone());
                 slow_from_bad_block = !parsed.block_crc_ok;
             }
+            // Exported separately; see `collect_log_events`.
+            LogRecord::Event(_) => {}
+            LogRecord::Internals(record) => {
+                internals = Some((record.clone(), !parsed.block_crc_ok));
+            }
             LogRecord::Fast(fast) => {
                 if prev_sequence.is_some_and(|prev| fast.sequence < prev) {
                     slow = None;
                     slow_from_bad_block = false;
                 }
                 prev_sequence = Some(fast.sequence);
+                let internals = internals
+                    .take()
+                    .filter(|(record, _)| record.timestamp_us == fast.timestamp_us);
+                let internals_from_bad_block = internals.as_ref().is_some_and(|(_, bad)| *bad);
                 out.push(FlightDataRecord::from_fast_and_slow(
                     fast,
                     slow.as_ref(),
-                    !parsed.block_crc_ok || slow_from_bad_block,
+                    internals.as_ref().map(|(record, _)| record),
+                    !parsed.block_crc_ok || slow_from_bad_block || internals_from_bad_block,
                 ))
             }
         }
@@ -551,6 +708,41 @@ pub fn merge_log_records(log: &[ParsedLogRecord]) -> std::vec::Vec<FlightDataRec
     out
 }
 
+/// One [`FlightEventRecord`] placed against the rows of [`merge_log_records`].
+#[cfg(any(feature = "std", test))]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct FlightEventRow {
+    /// Index of the first merged row logged after the event — the number of
+    /// fast records before it — so the event lands in the right session even
+    /// though `timestamp_us` restarts with every boot. Equal to the row count
+    /// when the log ends on events.
+    pub next_row: usize,
+    /// Same meaning as [`FlightDataRecord::source_block_crc_failed`].
+    pub source_block_crc_failed: bool,
+    pub timestamp_us: u64,
+    pub event: FlightEvent,
+}
+
+/// Pull the event records out of a tagged log, in logged order.
+#[cfg(any(feature = "std", test))]
+pub fn collect_log_events(log: &[ParsedLogRecord]) -> std::vec::Vec<FlightEventRow> {
+    let mut fast_rows = 0usize;
+    let mut out = std::vec::Vec::new();
+    for parsed in log {
+        match &parsed.record {
+            LogRecord::Fast(_) => fast_rows += 1,
+            LogRecord::Slow(_) | LogRecord::Internals(_) => {}
+            LogRecord::Event(event) => out.push(FlightEventRow {
+                next_row: fast_rows,
+                source_block_crc_failed: !parsed.block_crc_ok,
+                timestamp_us: event.timestamp_us,
+                event: event.event,
+            }),
+        }
+    }
+    out
+}
+
 /// `DeploymentEstimatorRecord::flags` bits — the deployment estimator's status.
 ///
 /// Both bits describe **this record's sample**, not a running state: they are
@@ -572,7 +764,150 @@ pub const DEPLOYMENT_BARO_GATE_REJECT: u8 = 1 << 0;
 /// `DEPLOYMENT_BARO_GATE_REJECT`, and altitude is discontinuous across the
 /// row it appears on.
 pub const DEPLOYMENT_BARO_RESYNC: u8 = 1 << 1;
-// bits 2-7 unallocated.
+/// The three bits of `DeploymentEstimatorRecord::flags` holding
+/// [`GpsFusion`].
+pub const DEPLOYMENT_GPS_FUSION_SHIFT: u32 = 2;
+pub const DEPLOYMENT_GPS_FUSION_MASK: u8 = 0b0001_1100;
+// bits 5-7 unallocated.
+
+/// What the deployment estimator did with the GPS on this record's sample.
+///
+/// Logged on every row, including the ones without a fresh fix, so a plot of
+/// it says at a glance whether the GPS was ever trusted, when, and — on the
+/// rows it was not — which of the checks it failed. The checks run in the
+/// order the variants are listed, and the first to fail is the one recorded.
+///
+/// Added 2026-10-18 into bits that every earlier build wrote as 0, which
+/// reads back as [`GpsFusion::NoMeasurement`] — true of those builds, which
+/// never fused a fix — so no storage version bump.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GpsFusion {
+    /// No fresh fix on this sample, or GPS aiding is not configured.
+    NoMeasurement = 0,
+    /// The fix failed the configured quality gate: too few satellites, an
+    /// HDOP or VDOP over its limit, or a field the receiver did not report.
+    PoorFix = 1,
+    /// On the pad: the fix went into the GPS pad reference, which is what
+    /// every later fix is measured from.
+    PadReference = 2,
+    /// Boost, coast or the Mach lockout. GPS is only fused under canopy,
+    /// where the barometer is at its worst and the receiver at its best.
+    NotDescending = 3,
+    /// In descent with no GPS pad reference: no fix passed the quality gate
+    /// while the rocket sat on the pad, so there is nothing to put a GPS
+    /// altitude on the barometer's datum with.
+    NoPadReference = 4,
+    /// Fused into the deployment KF.
+    Fused = 5,
+    /// Failed the KF's innovation gate and was dropped.
+    Rejected = 6,
+    /// Ended a rejection run: fused after the KF's altitude was let go, as
+    /// [`DEPLOYMENT_BARO_RESYNC`] does for the baro. Altitude is
+    /// discontinuous across the row it appears on.
+    Resynced = 7,
+}
+
+impl GpsFusion {
+    pub fn from_flags(flags: u8) -> Self {
+        match (flags & DEPLOYMENT_GPS_FUSION_MASK) >> DEPLOYMENT_GPS_FUSION_SHIFT {
+            0 => Self::NoMeasurement,
+            1 => Self::PoorFix,
+            2 => Self::PadReference,
+            3 => Self::NotDescending,
+            4 => Self::NoPadReference,
+            5 => Self::Fused,
+            6 => Self::Rejected,
+            // Three bits, eight states, all eight named.
+            _ => Self::Resynced,
+        }
+    }
+
+    pub fn to_flags(self) -> u8 {
+        (self as u8) << DEPLOYMENT_GPS_FUSION_SHIFT
+    }
+
+    /// The fix moved the KF.
+    pub fn fused(self) -> bool {
+        matches!(self, Self::Fused | Self::Resynced)
+    }
+}
+
+/// Barometer slots in [`DeploymentEstimatorRecord::baro_health`] — the most
+/// sensors the deployment estimator votes over.
+pub const BARO_HEALTH_SLOTS: usize = 3;
+/// Width of one slot in [`DeploymentEstimatorRecord::baro_health`].
+pub const BARO_HEALTH_BITS: u32 = 3;
+const BARO_HEALTH_SLOT_MASK: u16 = (1 << BARO_HEALTH_BITS) - 1;
+
+/// What the deployment estimator's baro vote made of one sensor on one
+/// sample.
+///
+/// Per sample, like the gate bits beside it, and for the same reason: the
+/// question a flight review asks is "which barometer was the filter reading
+/// when the drogue fired", and that is a property of a row. The health
+/// checks behind it do carry state — a stuck run, a noise estimate — so a
+/// sensor that goes bad stays flagged for as long as the evidence lasts,
+/// but the verdict is re-taken every sample and stored every sample.
+///
+/// `Absent` is zero so that an unused slot, and a board with fewer
+/// barometers than [`BARO_HEALTH_SLOTS`], read as what they are.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BaroHealth {
+    /// No reading from this sensor on this sample: its message had not
+    /// arrived, or there is no sensor in this slot.
+    Absent = 0,
+    /// Read and voted.
+    Healthy = 1,
+    /// The reading has not changed by a single bit for long enough that the
+    /// sensor, not the air, is holding it. A stuck barometer in ascent is a
+    /// flat altitude — a peak that never drops, and a drogue that never
+    /// fires.
+    Stuck = 2,
+    /// The sample-to-sample scatter is far beyond what the part produces:
+    /// a failing read, a loose connector, or a port being hit by something.
+    Noisy = 3,
+    /// Steady and moving, but disagreeing with the others by more than any
+    /// honest spread between two static ports.
+    Outlier = 4,
+}
+
+impl BaroHealth {
+    fn from_bits(bits: u16) -> Self {
+        match bits {
+            1 => Self::Healthy,
+            2 => Self::Stuck,
+            3 => Self::Noisy,
+            4 => Self::Outlier,
+            // 5-7 are never written. A card at any other `STORAGE_VERSION` is
+            // rejected before a record is decoded, so the only way here is a
+            // corrupt byte, and the row is already marked for that.
+            _ => Self::Absent,
+        }
+    }
+
+    /// Pack one verdict per slot, slot 0 in the lowest bits.
+    pub fn pack(health: &[Self; BARO_HEALTH_SLOTS]) -> u16 {
+        health
+            .iter()
+            .enumerate()
+            .fold(0, |packed, (i, h)| packed | ((*h as u16) << (i as u32 * BARO_HEALTH_BITS)))
+    }
+
+    pub fn unpack(packed: u16) -> [Self; BARO_HEALTH_SLOTS] {
+        core::array::from_fn(|i| {
+            Self::from_bits((packed >> (i as u32 * BARO_HEALTH_BITS)) & BARO_HEALTH_SLOT_MASK)
+        })
+    }
+
+    /// Whether this sensor's reading went into the vote as a trusted one.
+    pub fn voted(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+}
 
 /// `AirbrakesEstimatorRecord::flags` bits — the airbrakes estimator's status.
 ///
@@ -668,8 +1003,40 @@ impl AirbrakesState {
     }
 }
 
-pub const PYRO_MAIN_CONTINUITY: u8 = 1 << 0;
-pub const PYRO_MAIN_FIRE: u8 = 1 << 1;
-pub const PYRO_DROGUE_CONTINUITY: u8 = 1 << 2;
-pub const PYRO_DROGUE_FIRE: u8 = 1 << 3;
-pub const PYRO_SHORT_CIRCUIT: u8 = 1 << 4;
+pub const PYRO_MAIN_CONTINUITY: u16 = 1 << 0;
+pub const PYRO_MAIN_FIRE: u16 = 1 << 1;
+pub const PYRO_DROGUE_CONTINUITY: u16 = 1 << 2;
+pub const PYRO_DROGUE_FIRE: u16 = 1 << 3;
+pub const PYRO_SHORT_CIRCUIT: u16 = 1 << 4;
+/// The channels past main and drogue, one continuity / fire pair each after
+/// `PYRO_SHORT_CIRCUIT`, which kept its bit so the first five mean what they
+/// always meant. Read them by channel with [`pyro_continuity_flag`] and
+/// [`pyro_fire_flag`] rather than by name.
+pub const PYRO_SEPARATION_CONTINUITY: u16 = 1 << 5;
+pub const PYRO_SEPARATION_FIRE: u16 = 1 << 6;
+pub const PYRO_BACKUP_DROGUE_CONTINUITY: u16 = 1 << 7;
+pub const PYRO_BACKUP_DROGUE_FIRE: u16 = 1 << 8;
+pub const PYRO_BACKUP_MAIN_CONTINUITY: u16 = 1 << 9;
+pub const PYRO_BACKUP_MAIN_FIRE: u16 = 1 << 10;
+
+/// `channel`'s continuity bit in `pyro_flags`.
+pub fn pyro_continuity_flag(channel: PyroSelect) -> u16 {
+    match channel {
+        PyroSelect::PyroMain => PYRO_MAIN_CONTINUITY,
+        PyroSelect::PyroDrogue => PYRO_DROGUE_CONTINUITY,
+        PyroSelect::PyroSeparation => PYRO_SEPARATION_CONTINUITY,
+        PyroSelect::PyroBackupDrogue => PYRO_BACKUP_DROGUE_CONTINUITY,
+        PyroSelect::PyroBackupMain => PYRO_BACKUP_MAIN_CONTINUITY,
+    }
+}
+
+/// `channel`'s fire bit in `pyro_flags`.
+pub fn pyro_fire_flag(channel: PyroSelect) -> u16 {
+    match channel {
+        PyroSelect::PyroMain => PYRO_MAIN_FIRE,
+        PyroSelect::PyroDrogue => PYRO_DROGUE_FIRE,
+        PyroSelect::PyroSeparation => PYRO_SEPARATION_FIRE,
+        PyroSelect::PyroBackupDrogue => PYRO_BACKUP_DROGUE_FIRE,
+        PyroSelect::PyroBackupMain => PYRO_BACKUP_MAIN_FIRE,
+    }
+}
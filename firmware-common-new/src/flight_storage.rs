@@ -1,24 +1,42 @@
 //! On-SD-card and over-USB storage format for flight data records.
 //!
 //! ```text
-//! block 0            : superblock (see [`encode_superblock`])
-//! block 1 .. 1+N     : tagged records packed back-to-back:
+//! blocks 0, 1        : superblock, two copies (see [`encode_superblock`])
+//! block 2 .. 2+N     : tagged records packed back-to-back:
 //!                      [tag:1][rkyv body] [tag:1][rkyv body] ...
 //!                      zero-padded, CRC32 in the last 4 bytes.
 //! ```
 //!
-//! Tags: [`RECORD_TAG_FAST`], [`RECORD_TAG_SLOW`] (see `flight_data_record`).
+//! Tags: [`RECORD_TAG_FAST`], [`RECORD_TAG_SLOW`], [`RECORD_TAG_EVENT`],
+//! [`RECORD_TAG_INTERNALS`] (see `flight_data_record`).
+//!
+//! A log may instead be written in compressed block mode, flagged by
+//! [`SUPERBLOCK_FLAG_COMPRESSED`]. Its data blocks are then either plain
+//! blocks as above or, wherever compression pays, a compressed block:
+//!
+//! ```text
+//! [BLOCK_TAG_COMPRESSED:1][record_count:1][compressed_len:2][decoded_len:2]
+//! [heatshrink stream of the delta-encoded tagged records] zero pad, CRC32.
+//! ```
+//!
+//! See [`CompressedBlockPacker`] for how the two are chosen between.
 //!
 //! Older layouts (v1 fixed records, v2/v3 tagged streams) are NOT readable:
 //! the firmware starts a fresh log over them and rocket-cli reports a clean
 //! "unsupported format" error instead of decoding.
 
 use crate::flight_data_record::{
-    FlightDataFastRecord, FlightDataSlowRecord, LogRecord, RECORD_TAG_FAST, RECORD_TAG_SLOW,
+    EstimatorInternalsRecord, FlightDataFastRecord, FlightDataSlowRecord, FlightEventRecord,
+    LogRecord, RECORD_TAG_EVENT, RECORD_TAG_FAST, RECORD_TAG_INTERNALS, RECORD_TAG_SLOW,
 };
 #[cfg(any(feature = "std", test))]
 use crate::flight_data_record::ParsedLogRecord;
+use crate::heatshrink::HeatshrinkWrapper;
+use crate::vlp::lora_config::{LORA_BANDWIDTHS_HZ, LoraConfig};
 
+#[cfg(any(feature = "std", test))]
+use heatshrink::decoder::HeatshrinkDecoder;
+use heatshrink::encoder::HeatshrinkEncoder;
 use rkyv::{
     api::low::to_bytes_in_with_alloc,
     rancor::Failure,
@@ -38,11 +56,19 @@ pub const BLOCK_SIZE: usize = 512;
 /// CRC32 over the rest of the block.
 pub const USABLE_PER_BLOCK: usize = BLOCK_SIZE - 4;
 
-/// Block index of the superblock.
-pub const SUPERBLOCK_INDEX: u32 = 0;
+/// Block indices of the two superblock copies. Updates alternate between
+/// them ([`superblock_slot`]), so a write torn by a brown-out only ever
+/// destroys the copy being replaced and the previous one still reads.
+pub const SUPERBLOCK_SLOTS: [u32; 2] = [0, 1];
 
 /// Block index of the first data block.
-pub const DATA_START_BLOCK: u32 = 1;
+pub const DATA_START_BLOCK: u32 = 2;
+
+/// How far past a superblock's claimed end the recovery download reads
+/// ([`crate::vlp::usb::CliRequest::DownloadRaw`]). The firmware rewrites the
+/// superblock far more often than this — 1 MiB is tens of seconds of logging
+/// — so a lost tail is always inside the window.
+pub const RECOVERY_SCAN_BLOCKS: u32 = 2048;
 
 /// Identifies a valid superblock written by this firmware.
 pub const SUPERBLOCK_MAGIC: [u8; 4] = *b"VLF5";
@@ -50,14 +76,53 @@ pub const SUPERBLOCK_MAGIC: [u8; 4] = *b"VLF5";
 /// Identifies the avionics config block (last SD block; independent of the flight log).
 pub const CONFIG_BLOCK_MAGIC: [u8; 4] = *b"VLFC";
 
-/// On-disk config block format version.
-pub const CONFIG_BLOCK_VERSION: u32 = 1;
+/// On-disk config block format version. Unlike [`STORAGE_VERSION`], an older
+/// config block is migrated rather than discarded: it holds settings an
+/// operator chose, not data the next flight overwrites anyway.
+/// v2: the whole flight config — deployment profile, both Mach lockouts and
+///     the LoRa link — joins the target apogee. A v1 block decodes with its
+///     target kept and every new field at its default; see
+///     [`decode_config_block`].
+/// v1: target apogee only.
+pub const CONFIG_BLOCK_VERSION: u32 = 2;
 
 /// Default target apogee AGL (m) when no config is stored.
 pub const DEFAULT_TARGET_APOGEE_AGL: f32 = 4000.0;
 
+/// Highest target apogee AGL (m) a config may hold — the top of
+/// `SetTargetApogeePacket`'s fixed-point range, so a target set over USB can
+/// always be re-sent over the radio unchanged.
+pub const MAX_TARGET_APOGEE_AGL: f32 = 10_000.0;
+
 /// On-disk format version. Bump when the record or superblock layout changes;
 /// logs written at any other version are treated as absent.
+/// v27: internals records ([`RECORD_TAG_INTERNALS`]) join the stream, from
+///     firmware built with the core crate's `debug-internals` feature: both
+///     Kalman filters' innovation, its expected variance and the covariance
+///     diagonal, one per `INTERNALS_RECORD_INTERVAL` fast records. A build
+///     without the feature writes none, but a v26 reader would stop at the
+///     first one a build with it wrote, as an unknown tag.
+/// v26: [`FlightEvent::Staging`] joins the event records. No record grows,
+///     but a v25 reader would meet a discriminant it has no variant for.
+/// v25: `pyro_flags` widens from a `u8` to a `u16` for the three pyro
+///     channels past main and drogue, a continuity / fire pair each. The
+///     first five bits are unmoved, but a v24 reader would take the wider
+///     field's bytes for whatever used to follow it.
+/// v24: `baro_health` added to the deployment estimator record — the
+///     estimator now votes over every barometer on the bus, and this is
+///     which of them it believed on each sample. Three bits per sensor in a
+///     `u16` that lands in the padding after the flags byte, so the fast
+///     record does not grow; the bump is because a v23 reader would decode
+///     that padding as a health word.
+/// v23: event records ([`RECORD_TAG_EVENT`]) join the stream. A v22 reader
+///     would stop at the first one as an unknown tag.
+/// v22: two superblock copies with a generation counter; data now starts at
+///     block 2.
+/// v21: compressed block mode. The superblock's reserved word becomes a flag
+///     word ([`SUPERBLOCK_FLAG_COMPRESSED`]) and the USB download header
+///     grows to carry it. A v20 reader would meet a compressed block's
+///     [`BLOCK_TAG_COMPRESSED`] where it expects a record tag and stop, so
+///     the bump is what turns that into a clean "unsupported" instead.
 /// v20: payload fracture load cells and the per-channel experiment flag word
 ///     in the slow record, from `CustomPayloadStatusMessage` growing 20 -> 30
 ///     bytes. The message length is the reason this is a version break and
@@ -148,20 +213,37 @@ pub const DEFAULT_TARGET_APOGEE_AGL: f32 = 4000.0;
 ///     `mpc_predicted_apogee_agl` added to the slow record, `VALID_BARO` dropped.
 /// v8: payload EPM rail currents + SEM actuator steps in the slow record.
 /// v7: tagged FAST/SLOW stream (see `flight_data_record`). Older formats: see git history.
-pub const STORAGE_VERSION: u32 = 20;
+pub const STORAGE_VERSION: u32 = 27;
 
 /// rkyv body sizes for tagged record types.
 pub const FAST_BODY_LEN: usize = size_of::<<FlightDataFastRecord as rkyv::Archive>::Archived>();
 pub const SLOW_BODY_LEN: usize = size_of::<<FlightDataSlowRecord as rkyv::Archive>::Archived>();
+pub const EVENT_BODY_LEN: usize = size_of::<<FlightEventRecord as rkyv::Archive>::Archived>();
+pub const INTERNALS_BODY_LEN: usize =
+    size_of::<<EstimatorInternalsRecord as rkyv::Archive>::Archived>();
 
 pub const FAST_WIRE_LEN: usize = 1 + FAST_BODY_LEN;
 pub const SLOW_WIRE_LEN: usize = 1 + SLOW_BODY_LEN;
+pub const EVENT_WIRE_LEN: usize = 1 + EVENT_BODY_LEN;
+pub const INTERNALS_WIRE_LEN: usize = 1 + INTERNALS_BODY_LEN;
 
 /// Largest tagged record on the wire.
-pub const MAX_WIRE_LEN: usize = if FAST_WIRE_LEN > SLOW_WIRE_LEN {
-    FAST_WIRE_LEN
-} else {
-    SLOW_WIRE_LEN
+pub const MAX_WIRE_LEN: usize = {
+    let fast_or_slow = if FAST_WIRE_LEN > SLOW_WIRE_LEN {
+        FAST_WIRE_LEN
+    } else {
+        SLOW_WIRE_LEN
+    };
+    let event_or_internals = if EVENT_WIRE_LEN > INTERNALS_WIRE_LEN {
+        EVENT_WIRE_LEN
+    } else {
+        INTERNALS_WIRE_LEN
+    };
+    if fast_or_slow > event_or_internals {
+        fast_or_slow
+    } else {
+        event_or_internals
+    }
 };
 
 #[repr(C, align(16))]
@@ -193,6 +275,28 @@ fn serialize_slow_body(slow: &FlightDataSlowRecord) -> [u8; SLOW_BODY_LEN] {
     scratch.0
 }
 
+fn serialize_event_body(event: &FlightEventRecord) -> [u8; EVENT_BODY_LEN] {
+    let mut scratch = AlignedBuf([0u8; EVENT_BODY_LEN]);
+    to_bytes_in_with_alloc::<_, _, Failure>(
+        event,
+        Buffer::from(&mut scratch.0[..]),
+        SubAllocator::empty(),
+    )
+    .expect("EVENT serialization cannot fail");
+    scratch.0
+}
+
+fn serialize_internals_body(internals: &EstimatorInternalsRecord) -> [u8; INTERNALS_BODY_LEN] {
+    let mut scratch = AlignedBuf([0u8; INTERNALS_BODY_LEN]);
+    to_bytes_in_with_alloc::<_, _, Failure>(
+        internals,
+        Buffer::from(&mut scratch.0[..]),
+        SubAllocator::empty(),
+    )
+    .expect("INTERNALS serialization cannot fail");
+    scratch.0
+}
+
 /// Decode one FAST body.
 ///
 /// # Why this is split by feature
@@ -236,6 +340,28 @@ fn deserialize_slow_body(bytes: &[u8]) -> Option<FlightDataSlowRecord> {
     from_bytes::<FlightDataSlowRecord, Failure>(&aligned.0).ok()
 }
 
+/// See [`deserialize_fast_body`] for why the host and firmware paths differ.
+#[cfg(feature = "std")]
+fn deserialize_event_body(bytes: &[u8]) -> Option<FlightEventRecord> {
+    if bytes.len() < EVENT_BODY_LEN {
+        return None;
+    }
+    let mut aligned = AlignedBuf([0u8; EVENT_BODY_LEN]);
+    aligned.0.copy_from_slice(&bytes[..EVENT_BODY_LEN]);
+    from_bytes::<FlightEventRecord, Failure>(&aligned.0).ok()
+}
+
+/// See [`deserialize_fast_body`] for why the host and firmware paths differ.
+#[cfg(feature = "std")]
+fn deserialize_internals_body(bytes: &[u8]) -> Option<EstimatorInternalsRecord> {
+    if bytes.len() < INTERNALS_BODY_LEN {
+        return None;
+    }
+    let mut aligned = AlignedBuf([0u8; INTERNALS_BODY_LEN]);
+    aligned.0.copy_from_slice(&bytes[..INTERNALS_BODY_LEN]);
+    from_bytes::<EstimatorInternalsRecord, Failure>(&aligned.0).ok()
+}
+
 /// Firmware path: no validation. See [`deserialize_fast_body`].
 ///
 /// Safe only against bytes this firmware itself just serialised. Anything that
@@ -261,6 +387,28 @@ fn deserialize_slow_body(bytes: &[u8]) -> Option<FlightDataSlowRecord> {
     unsafe { from_bytes_unchecked::<FlightDataSlowRecord, Failure>(&aligned.0) }.ok()
 }
 
+/// Firmware path: no validation. See [`deserialize_fast_body`].
+#[cfg(not(feature = "std"))]
+fn deserialize_event_body(bytes: &[u8]) -> Option<FlightEventRecord> {
+    if bytes.len() < EVENT_BODY_LEN {
+        return None;
+    }
+    let mut aligned = AlignedBuf([0u8; EVENT_BODY_LEN]);
+    aligned.0.copy_from_slice(&bytes[..EVENT_BODY_LEN]);
+    unsafe { from_bytes_unchecked::<FlightEventRecord, Failure>(&aligned.0) }.ok()
+}
+
+/// Firmware path: no validation. See [`deserialize_fast_body`].
+#[cfg(not(feature = "std"))]
+fn deserialize_internals_body(bytes: &[u8]) -> Option<EstimatorInternalsRecord> {
+    if bytes.len() < INTERNALS_BODY_LEN {
+        return None;
+    }
+    let mut aligned = AlignedBuf([0u8; INTERNALS_BODY_LEN]);
+    aligned.0.copy_from_slice(&bytes[..INTERNALS_BODY_LEN]);
+    unsafe { from_bytes_unchecked::<EstimatorInternalsRecord, Failure>(&aligned.0) }.ok()
+}
+
 /// Serialise a tagged record. Returns the wire bytes and their length.
 pub fn serialize_log_record(record: &LogRecord) -> ([u8; MAX_WIRE_LEN], usize) {
     let mut buf = [0u8; MAX_WIRE_LEN];
@@ -277,6 +425,18 @@ pub fn serialize_log_record(record: &LogRecord) -> ([u8; MAX_WIRE_LEN], usize) {
             buf[1..1 + SLOW_BODY_LEN].copy_from_slice(&body);
             SLOW_WIRE_LEN
         }
+        LogRecord::Event(event) => {
+            buf[0] = RECORD_TAG_EVENT;
+            let body = serialize_event_body(event);
+            buf[1..1 + EVENT_BODY_LEN].copy_from_slice(&body);
+            EVENT_WIRE_LEN
+        }
+        LogRecord::Internals(internals) => {
+            buf[0] = RECORD_TAG_INTERNALS;
+            let body = serialize_internals_body(internals);
+            buf[1..1 + INTERNALS_BODY_LEN].copy_from_slice(&body);
+            INTERNALS_WIRE_LEN
+        }
     };
     (buf, len)
 }
@@ -286,6 +446,8 @@ pub fn log_record_wire_len(bytes: &[u8]) -> Option<usize> {
     match *bytes.first()? {
         RECORD_TAG_FAST => Some(FAST_WIRE_LEN),
         RECORD_TAG_SLOW => Some(SLOW_WIRE_LEN),
+        RECORD_TAG_EVENT => Some(EVENT_WIRE_LEN),
+        RECORD_TAG_INTERNALS => Some(INTERNALS_WIRE_LEN),
         _ => None,
     }
 }
@@ -306,6 +468,10 @@ pub fn deserialize_log_record_at(block: &[u8], offset: usize) -> Option<(LogReco
     let record = match block[offset] {
         RECORD_TAG_FAST => LogRecord::Fast(deserialize_fast_body(&block[offset + 1..end])?),
         RECORD_TAG_SLOW => LogRecord::Slow(deserialize_slow_body(&block[offset + 1..end])?),
+        RECORD_TAG_EVENT => LogRecord::Event(deserialize_event_body(&block[offset + 1..end])?),
+        RECORD_TAG_INTERNALS => {
+            LogRecord::Internals(deserialize_internals_body(&block[offset + 1..end])?)
+        }
         _ => return None,
     };
     Some((record, wire_len))
@@ -355,65 +521,645 @@ pub struct SuperblockInfo {
     pub record_count: u32,
     /// Number of live data blocks (starting at [`DATA_START_BLOCK`]).
     pub block_count: u32,
-    /// Bytes used in the last data block.
+    /// Bytes used in the last data block. Always [`USABLE_PER_BLOCK`] in
+    /// compressed mode: a heatshrink stream cannot be appended to, so a
+    /// resumed compressed log opens a fresh block.
     pub last_block_offset: u32,
+    /// [`SUPERBLOCK_FLAG_COMPRESSED`]: the data blocks were written by a
+    /// [`CompressedBlockPacker`], and a resumed log must keep using one.
+    pub compressed: bool,
+    /// Bumped on every superblock write, and never reset — starting a fresh
+    /// log takes the next generation too, or the old log's surviving copy
+    /// would outrank it. Picks the newer of the two copies; see
+    /// [`latest_superblock`].
+    pub generation: u32,
+}
+
+/// Superblock flag word bit: compressed block mode.
+pub const SUPERBLOCK_FLAG_COMPRESSED: u32 = 1 << 0;
+
+/// First byte of a compressed data block. Never a record tag, so a block
+/// says which kind it is by its first byte alone.
+pub const BLOCK_TAG_COMPRESSED: u8 = 0xC5;
+
+/// `tag(1) | record_count(1) | compressed_len u16 LE | decoded_len u16 LE`.
+const COMPRESSED_HEADER_LEN: usize = 6;
+
+/// Most records one compressed block may hold. Bounds the staging and
+/// decode buffers; a pad wait compresses well past this, so it is the limit
+/// that binds there, not the 508 bytes.
+pub const MAX_RECORDS_PER_COMPRESSED_BLOCK: usize = 24;
+
+/// Bytes of tagged records a compressed block can decode to.
+const MAX_DECODED_LEN: usize = MAX_RECORDS_PER_COMPRESSED_BLOCK * MAX_WIRE_LEN;
+
+/// Wire length of the first `n` tagged records in `stream`, or `None` if
+/// there are fewer than `n` whole records.
+fn records_prefix_len(stream: &[u8], n: usize) -> Option<usize> {
+    let mut off = 0usize;
+    for _ in 0..n {
+        let wire_len = log_record_wire_len(&stream[off..])?;
+        if off + wire_len > stream.len() {
+            return None;
+        }
+        off += wire_len;
+    }
+    Some(off)
+}
+
+/// Delta-encode a tagged record stream in place: every FAST body after the
+/// first is XORed with the FAST body before it.
+///
+/// Consecutive fast records are mostly the same bytes — the sequence and
+/// timestamp step, a sample moves in its low mantissa bits, and on the pad
+/// almost nothing else changes — so the result is mostly zero runs, which is
+/// what heatshrink's back-references are good at. XOR rather than
+/// subtraction because it needs no carries and is exactly invertible per
+/// byte. SLOW records are left alone: one per ~42 fast, there is nothing
+/// adjacent to diff them against.
+fn delta_encode_records(stream: &mut [u8]) {
+    let mut prev: Option<[u8; FAST_BODY_LEN]> = None;
+    let mut off = 0usize;
+    while let Some(wire_len) = log_record_wire_len(&stream[off..]) {
+        if off + wire_len > stream.len() {
+            break;
+        }
+        if stream[off] == RECORD_TAG_FAST {
+            let body = &mut stream[off + 1..off + wire_len];
+            let mut original = [0u8; FAST_BODY_LEN];
+            original.copy_from_slice(body);
+            if let Some(prev) = &prev {
+                body.iter_mut().zip(prev).for_each(|(b, p)| *b ^= p);
+            }
+            prev = Some(original);
+        }
+        off += wire_len;
+    }
+}
+
+/// Inverse of [`delta_encode_records`].
+#[cfg(any(feature = "std", test))]
+fn delta_decode_records(stream: &mut [u8]) {
+    let mut prev: Option<[u8; FAST_BODY_LEN]> = None;
+    let mut off = 0usize;
+    while let Some(wire_len) = log_record_wire_len(&stream[off..]) {
+        if off + wire_len > stream.len() {
+            break;
+        }
+        if stream[off] == RECORD_TAG_FAST {
+            let body = &mut stream[off + 1..off + wire_len];
+            if let Some(prev) = &prev {
+                body.iter_mut().zip(prev).for_each(|(b, p)| *b ^= p);
+            }
+            let mut decoded = [0u8; FAST_BODY_LEN];
+            decoded.copy_from_slice(body);
+            prev = Some(decoded);
+        }
+        off += wire_len;
+    }
+}
+
+/// One finished data block from a [`CompressedBlockPacker`], ready to write.
+pub struct PackedBlock {
+    /// CRC already stamped.
+    pub block: [u8; BLOCK_SIZE],
+    /// Records the block holds, for the superblock's running count.
+    pub record_count: u32,
+    /// `false` when compression did not pay and this is a plain block.
+    pub compressed: bool,
+}
+
+/// Firmware-side writer for compressed block mode.
+///
+/// Records are staged as plain wire images and only turned into a block when
+/// enough are staged: the number that fits depends on how well they
+/// compress, which is only known by compressing them. So the packer aims for
+/// a record count, compresses that many, and adapts — one more next time if
+/// they fit, as many as did fit if they did not. A steady stream costs one
+/// or two compressions per block.
+///
+/// Where compression does not pay, i.e. the records that fit compressed
+/// would have fitted plain anyway, the block is written plain. A compressed
+/// log is therefore never less dense than a plain one.
+///
+/// The cost is that staged records are in RAM until their block is full —
+/// up to [`MAX_RECORDS_PER_COMPRESSED_BLOCK`] of them, ~55 ms at the fast
+/// rate — so call [`Self::flush`] before anything that should not lose them.
+pub struct CompressedBlockPacker {
+    staged: [u8; MAX_DECODED_LEN],
+    staged_len: usize,
+    staged_records: usize,
+    target_records: usize,
+    scratch: [u8; MAX_DECODED_LEN],
+}
+
+impl CompressedBlockPacker {
+    pub const fn new() -> Self {
+        Self {
+            staged: [0u8; MAX_DECODED_LEN],
+            staged_len: 0,
+            staged_records: 0,
+            target_records: MAX_RECORDS_PER_COMPRESSED_BLOCK / 2,
+            scratch: [0u8; MAX_DECODED_LEN],
+        }
+    }
+
+    /// Records staged and not yet in any block.
+    pub fn staged_records(&self) -> usize {
+        self.staged_records
+    }
+
+    /// Stage one record, and return a block once enough are staged for one.
+    pub fn push(&mut self, record: &LogRecord) -> Option<PackedBlock> {
+        let (bytes, len) = serialize_log_record(record);
+        // Staging holds MAX records of the largest kind and a block is
+        // packed whenever the target (never above MAX) is reached, so a
+        // record always has room here.
+        self.staged[self.staged_len..self.staged_len + len].copy_from_slice(&bytes[..len]);
+        self.staged_len += len;
+        self.staged_records += 1;
+        if self.staged_records >= self.target_records {
+            Some(self.pack())
+        } else {
+            None
+        }
+    }
+
+    /// Pack what is staged into a block now, regardless of the target.
+    /// `None` once nothing is staged; a single call can leave records over,
+    /// so drain with `while let Some(block) = packer.flush()`.
+    pub fn flush(&mut self) -> Option<PackedBlock> {
+        if self.staged_records == 0 {
+            None
+        } else {
+            Some(self.pack())
+        }
+    }
+
+    fn pack(&mut self) -> PackedBlock {
+        let mut n = self.staged_records;
+        loop {
+            // `n` only counts staged records, so the prefix always exists.
+            let prefix_len = records_prefix_len(&self.staged[..self.staged_len], n).unwrap_or(0);
+            if let Some(block) = self.try_compress(prefix_len, n) {
+                self.target_records = if n == self.staged_records {
+                    (n + 1).min(MAX_RECORDS_PER_COMPRESSED_BLOCK)
+                } else {
+                    n
+                };
+                self.consume(n, prefix_len);
+                return PackedBlock {
+                    block,
+                    record_count: n as u32,
+                    compressed: true,
+                };
+            }
+            if prefix_len <= USABLE_PER_BLOCK {
+                let mut block = [0u8; BLOCK_SIZE];
+                block[..prefix_len].copy_from_slice(&self.staged[..prefix_len]);
+                finalize_data_block(&mut block);
+                // Keep probing one past what fits plain: the data that did not
+                // compress may be a transient.
+                self.target_records = n + 1;
+                self.consume(n, prefix_len);
+                return PackedBlock {
+                    block,
+                    record_count: n as u32,
+                    compressed: false,
+                };
+            }
+            n -= 1;
+        }
+    }
+
+    fn try_compress(&mut self, prefix_len: usize, n: usize) -> Option<[u8; BLOCK_SIZE]> {
+        self.scratch[..prefix_len].copy_from_slice(&self.staged[..prefix_len]);
+        delta_encode_records(&mut self.scratch[..prefix_len]);
+
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut enc: HeatshrinkWrapper<'_, HeatshrinkEncoder> =
+            HeatshrinkWrapper::new(&mut block[COMPRESSED_HEADER_LEN..USABLE_PER_BLOCK]);
+        enc.sink(&self.scratch[..prefix_len]).ok()?;
+        let compressed_len = enc.finish().ok()?;
+
+        block[0] = BLOCK_TAG_COMPRESSED;
+        block[1] = n as u8;
+        block[2..4].copy_from_slice(&(compressed_len as u16).to_le_bytes());
+        block[4..6].copy_from_slice(&(prefix_len as u16).to_le_bytes());
+        finalize_data_block(&mut block);
+        Some(block)
+    }
+
+    fn consume(&mut self, n: usize, prefix_len: usize) {
+        self.staged.copy_within(prefix_len..self.staged_len, 0);
+        self.staged_len -= prefix_len;
+        self.staged_records -= n;
+    }
+}
+
+impl Default for CompressedBlockPacker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decompress and delta-decode one compressed block into `out`, returning
+/// the decoded length. `None` when the header or stream is inconsistent,
+/// which on a card only a corrupt block produces.
+#[cfg(any(feature = "std", test))]
+fn decode_compressed_block(
+    block: &[u8; BLOCK_SIZE],
+    out: &mut [u8; MAX_DECODED_LEN],
+) -> Option<usize> {
+    if block[0] != BLOCK_TAG_COMPRESSED {
+        return None;
+    }
+    let compressed_len = u16::from_le_bytes([block[2], block[3]]) as usize;
+    let decoded_len = u16::from_le_bytes([block[4], block[5]]) as usize;
+    let compressed = block
+        .get(COMPRESSED_HEADER_LEN..COMPRESSED_HEADER_LEN + compressed_len)
+        .filter(|_| COMPRESSED_HEADER_LEN + compressed_len <= USABLE_PER_BLOCK)?;
+
+    let mut dec: HeatshrinkWrapper<'_, HeatshrinkDecoder> = HeatshrinkWrapper::new(&mut out[..]);
+    dec.sink(compressed).ok()?;
+    if dec.finish().ok()? != decoded_len {
+        return None;
+    }
+    delta_decode_records(&mut out[..decoded_len]);
+    Some(decoded_len)
+}
+
+/// How the chutes come out, as stored in the config block.
+///
+/// Field-for-field the deployment estimator's `DeploymentProfile` in
+/// `air-brakes-controller-core`, which converts from this type. It is
+/// redeclared here because that crate depends on this one, not the other way
+/// round, and the card format cannot wait on the estimator.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeploymentConfig {
+    /// Both pyros at apogee, `delay_us` after descent is detected.
+    Single {
+        minimum_deployment_altitude_agl: f32,
+        delay_us: u32,
+    },
+    /// Drogue at apogee, main on the way down at `main_chute_altitude_agl`.
+    Dual {
+        drogue_chute_minimum_altitude_agl: f32,
+        drogue_chute_delay_us: u32,
+        main_chute_altitude_agl: f32,
+        main_chute_delay_us: u32,
+    },
+}
+
+/// The two Mach lockouts, which are separate because the two estimators leave
+/// theirs by different rules.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MachLockoutDurations {
+    /// Deployment estimator: baro ignored for this long after ignition.
+    /// `None` for a subsonic airframe, which has no lockout at all.
+    pub deployment_us: Option<u32>,
+    /// Airbrakes estimator: the drag-check window after ignition. `None` for
+    /// a subsonic airframe, as above.
+    pub airbrakes: Option<AirbrakesLockoutDurations>,
+}
+
+/// The airbrakes half of [`MachLockoutDurations`]. The crossing altitude the
+/// drag check evaluates air density at is a property of the airframe and the
+/// motor, not of the day, so it stays in the firmware's build config.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AirbrakesLockoutDurations {
+    /// The drag check may not declare the airframe subsonic before this.
+    pub earliest_subsonic_after_ignition_us: u32,
+    /// The vertical filter is born at this point whatever the drag check says.
+    pub force_birth_after_ignition_us: u32,
 }
 
 /// Decoded contents of a valid avionics config block.
+///
+/// Everything about a flight that is chosen on the day rather than fixed by
+/// the airframe. The firmware reads it once at boot; the target apogee can
+/// also be moved later by `SetTargetApogeePacket`, which rewrites this block.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AvionicsConfig {
     pub target_apogee_agl: f32,
+    pub deployment: DeploymentConfig,
+    pub mach_lockout: MachLockoutDurations,
+    pub lora: LoraConfig,
 }
 
 impl Default for AvionicsConfig {
+    /// VLF5's built-in flight config: what a card with no config block, or
+    /// a v1 block, flies with.
     fn default() -> Self {
         Self {
             target_apogee_agl: DEFAULT_TARGET_APOGEE_AGL,
+            deployment: DeploymentConfig::Dual {
+                drogue_chute_minimum_altitude_agl: 2000.0,
+                drogue_chute_delay_us: 1_000_000,
+                main_chute_altitude_agl: 457.2,
+                main_chute_delay_us: 0,
+            },
+            mach_lockout: MachLockoutDurations {
+                deployment_us: Some(26_000_000),
+                airbrakes: Some(AirbrakesLockoutDurations {
+                    earliest_subsonic_after_ignition_us: 17_200_000,
+                    force_birth_after_ignition_us: 25_000_000,
+                }),
+            },
+            lora: LoraConfig {
+                frequency: 915_100_000,
+                sf: 12,
+                bw: 250_000,
+                cr: 8,
+                power: 22,
+            },
         }
     }
 }
 
+/// Why [`AvionicsConfig::validate`] refused a config.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AvionicsConfigError {
+    /// Not within 0..=[`MAX_TARGET_APOGEE_AGL`].
+    TargetApogeeOutOfRange,
+    /// A deployment altitude is negative or not finite.
+    DeploymentAltitudeOutOfRange,
+    /// Dual deployment with the main altitude at or above the drogue's
+    /// minimum: the main would fire on the sample after the drogue, which is
+    /// a single deployment that does not say so.
+    MainNotBelowDrogue,
+    /// The airbrakes' forced birth comes before the drag check may pass.
+    AirbrakesLockoutInverted,
+    /// Spreading factor, bandwidth or coding rate the radio driver does not
+    /// accept (see [`LoraConfig::is_valid`]).
+    LoraModulation,
+    /// TX power outside the SX1262's -9..=22 dBm.
+    LoraPower,
+}
+
+impl core::fmt::Display for AvionicsConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TargetApogeeOutOfRange => write!(
+                f,
+                "target apogee must be between 0 and {MAX_TARGET_APOGEE_AGL} m AGL"
+            ),
+            Self::DeploymentAltitudeOutOfRange => {
+                write!(f, "deployment altitudes must be finite and not negative")
+            }
+            Self::MainNotBelowDrogue => write!(
+                f,
+                "main chute altitude must be below the drogue chute minimum altitude"
+            ),
+            Self::AirbrakesLockoutInverted => write!(
+                f,
+                "airbrakes forced birth must not come before the earliest subsonic time"
+            ),
+            Self::LoraModulation => write!(
+                f,
+                "LoRa spreading factor must be 5-12, coding rate 5-8, and bandwidth one of \
+                 {LORA_BANDWIDTHS_HZ:?} Hz"
+            ),
+            Self::LoraPower => write!(f, "LoRa power must be between -9 and 22 dBm"),
+        }
+    }
+}
+
+impl AvionicsConfig {
+    /// Check everything the firmware would otherwise have to trust.
+    ///
+    /// Both ends run this: the host before it sends a config, the firmware
+    /// before it writes one, and [`decode_config_block`] on the way back off
+    /// the card — so a config the firmware would refuse to store is also one
+    /// it will not fly on.
+    pub fn validate(&self) -> Result<(), AvionicsConfigError> {
+        if !(0.0..=MAX_TARGET_APOGEE_AGL).contains(&self.target_apogee_agl) {
+            return Err(AvionicsConfigError::TargetApogeeOutOfRange);
+        }
+        let altitude_ok = |agl: f32| agl.is_finite() && agl >= 0.0;
+        match self.deployment {
+            DeploymentConfig::Single {
+                minimum_deployment_altitude_agl,
+                ..
+            } => {
+                if !altitude_ok(minimum_deployment_altitude_agl) {
+                    return Err(AvionicsConfigError::DeploymentAltitudeOutOfRange);
+                }
+            }
+            DeploymentConfig::Dual {
+                drogue_chute_minimum_altitude_agl,
+                main_chute_altitude_agl,
+                ..
+            } => {
+                if !altitude_ok(drogue_chute_minimum_altitude_agl)
+                    || !altitude_ok(main_chute_altitude_agl)
+                {
+                    return Err(AvionicsConfigError::DeploymentAltitudeOutOfRange);
+                }
+                if main_chute_altitude_agl >= drogue_chute_minimum_altitude_agl {
+                    return Err(AvionicsConfigError::MainNotBelowDrogue);
+                }
+            }
+        }
+        if let Some(airbrakes) = &self.mach_lockout.airbrakes
+            && airbrakes.force_birth_after_ignition_us
+                < airbrakes.earliest_subsonic_after_ignition_us
+        {
+            return Err(AvionicsConfigError::AirbrakesLockoutInverted);
+        }
+        if !self.lora.is_valid() {
+            return Err(AvionicsConfigError::LoraModulation);
+        }
+        if !(-9..=22).contains(&self.lora.power) {
+            return Err(AvionicsConfigError::LoraPower);
+        }
+        Ok(())
+    }
+}
+
+const DEPLOYMENT_KIND_SINGLE: u8 = 0;
+const DEPLOYMENT_KIND_DUAL: u8 = 1;
+
+fn read_u32(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+fn read_f32(b: &[u8], at: usize) -> f32 {
+    f32::from_bits(read_u32(b, at))
+}
+
+/// An optional duration as a presence byte at `at` and the value at `at + 4`.
+fn read_optional_u32(b: &[u8], at: usize) -> Option<Option<u32>> {
+    match b[at] {
+        0 => Some(None),
+        1 => Some(Some(read_u32(b, at + 4))),
+        _ => None,
+    }
+}
+
+fn write_optional_u32(b: &mut [u8], at: usize, value: Option<u32>) {
+    if let Some(value) = value {
+        b[at] = 1;
+        b[at + 4..at + 8].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
 /// Build a 512-byte config block (stored at the last SD block index).
 ///
-/// Layout: magic(4) | version(4) | target_apogee_agl f32 LE(4) | reserved... | crc32(4).
+/// Layout, all little-endian, unlisted bytes zero:
+///
+/// ```text
+///  0 magic(4) | 4 version(4) | 8 target_apogee_agl f32
+/// 12 deployment kind u8 (0 single, 1 dual)
+/// 16 single: minimum_deployment_altitude_agl f32 | 20 delay_us u32
+///    dual:   drogue_chute_minimum_altitude_agl f32 | 20 drogue_chute_delay_us u32
+///            24 main_chute_altitude_agl f32 | 28 main_chute_delay_us u32
+/// 32 deployment lockout present u8 | 36 deployment lockout us u32
+/// 40 airbrakes lockout present u8 | 44 earliest subsonic us u32 | 48 force birth us u32
+/// 52 lora frequency u32 | 56 sf u8 | 57 cr u8 | 60 bw u32 | 64 power i32
+/// 508 crc32(4)
+/// ```
+///
+/// v1 used only bytes 0..12, which is why v2 keeps them where they were.
 pub fn encode_config_block(config: &AvionicsConfig) -> [u8; BLOCK_SIZE] {
     let mut b = [0u8; BLOCK_SIZE];
     b[0..4].copy_from_slice(&CONFIG_BLOCK_MAGIC);
     b[4..8].copy_from_slice(&CONFIG_BLOCK_VERSION.to_le_bytes());
     b[8..12].copy_from_slice(&config.target_apogee_agl.to_le_bytes());
+    match config.deployment {
+        DeploymentConfig::Single {
+            minimum_deployment_altitude_agl,
+            delay_us,
+        } => {
+            b[12] = DEPLOYMENT_KIND_SINGLE;
+            b[16..20].copy_from_slice(&minimum_deployment_altitude_agl.to_le_bytes());
+            b[20..24].copy_from_slice(&delay_us.to_le_bytes());
+        }
+        DeploymentConfig::Dual {
+            drogue_chute_minimum_altitude_agl,
+            drogue_chute_delay_us,
+            main_chute_altitude_agl,
+            main_chute_delay_us,
+        } => {
+            b[12] = DEPLOYMENT_KIND_DUAL;
+            b[16..20].copy_from_slice(&drogue_chute_minimum_altitude_agl.to_le_bytes());
+            b[20..24].copy_from_slice(&drogue_chute_delay_us.to_le_bytes());
+            b[24..28].copy_from_slice(&main_chute_altitude_agl.to_le_bytes());
+            b[28..32].copy_from_slice(&main_chute_delay_us.to_le_bytes());
+        }
+    }
+    write_optional_u32(&mut b, 32, config.mach_lockout.deployment_us);
+    if let Some(airbrakes) = &config.mach_lockout.airbrakes {
+        b[40] = 1;
+        b[44..48].copy_from_slice(&airbrakes.earliest_subsonic_after_ignition_us.to_le_bytes());
+        b[48..52].copy_from_slice(&airbrakes.force_birth_after_ignition_us.to_le_bytes());
+    }
+    b[52..56].copy_from_slice(&config.lora.frequency.to_le_bytes());
+    b[56] = config.lora.sf;
+    b[57] = config.lora.cr;
+    b[60..64].copy_from_slice(&config.lora.bw.to_le_bytes());
+    b[64..68].copy_from_slice(&config.lora.power.to_le_bytes());
     let crc = crc32(&b[..USABLE_PER_BLOCK]);
     b[USABLE_PER_BLOCK..].copy_from_slice(&crc.to_le_bytes());
     b
 }
 
-/// Parse an avionics config block. Returns `None` if magic/CRC/version are invalid.
-pub fn decode_config_block(block: &[u8; BLOCK_SIZE]) -> Option<AvionicsConfig> {
-    if block[0..4] != CONFIG_BLOCK_MAGIC {
-        return None;
-    }
-    if !verify_data_block(block) {
-        return None;
-    }
-    let version = u32::from_le_bytes(block[4..8].try_into().ok()?);
-    if version != CONFIG_BLOCK_VERSION {
+/// The version a config block was written at, or `None` if it is not an
+/// intact config block at all (bad magic or CRC). Says nothing about whether
+/// this build can decode it — see [`decode_config_block`] for that.
+pub fn config_block_version(block: &[u8; BLOCK_SIZE]) -> Option<u32> {
+    if block[0..4] != CONFIG_BLOCK_MAGIC || !verify_data_block(block) {
         return None;
     }
+    Some(read_u32(block, 4))
+}
+
+/// Parse an avionics config block. Returns `None` if magic/CRC are invalid,
+/// the version is unknown, or the config fails [`AvionicsConfig::validate`].
+///
+/// Older versions migrate: each known version reads the fields it had and
+/// takes the rest from [`AvionicsConfig::default`], so a card configured by
+/// older firmware keeps its target apogee instead of silently reverting it.
+/// A block from a NEWER version is refused — its extra fields are settings
+/// this build cannot honour, and flying without them is not a migration.
+pub fn decode_config_block(block: &[u8; BLOCK_SIZE]) -> Option<AvionicsConfig> {
+    let config = match config_block_version(block)? {
+        1 => AvionicsConfig {
+            target_apogee_agl: read_f32(block, 8),
+            ..AvionicsConfig::default()
+        },
+        2 => decode_config_block_v2(block)?,
+        _ => return None,
+    };
+    config.validate().ok()?;
+    Some(config)
+}
+
+fn decode_config_block_v2(b: &[u8; BLOCK_SIZE]) -> Option<AvionicsConfig> {
+    let deployment = match b[12] {
+        DEPLOYMENT_KIND_SINGLE => DeploymentConfig::Single {
+            minimum_deployment_altitude_agl: read_f32(b, 16),
+            delay_us: read_u32(b, 20),
+        },
+        DEPLOYMENT_KIND_DUAL => DeploymentConfig::Dual {
+            drogue_chute_minimum_altitude_agl: read_f32(b, 16),
+            drogue_chute_delay_us: read_u32(b, 20),
+            main_chute_altitude_agl: read_f32(b, 24),
+            main_chute_delay_us: read_u32(b, 28),
+        },
+        _ => return None,
+    };
+    let airbrakes = match b[40] {
+        0 => None,
+        1 => Some(AirbrakesLockoutDurations {
+            earliest_subsonic_after_ignition_us: read_u32(b, 44),
+            force_birth_after_ignition_us: read_u32(b, 48),
+        }),
+        _ => return None,
+    };
     Some(AvionicsConfig {
-        target_apogee_agl: f32::from_le_bytes(block[8..12].try_into().ok()?),
+        target_apogee_agl: read_f32(b, 8),
+        deployment,
+        mach_lockout: MachLockoutDurations {
+            deployment_us: read_optional_u32(b, 32)?,
+            airbrakes,
+        },
+        lora: LoraConfig {
+            frequency: read_u32(b, 52),
+            sf: b[56],
+            bw: read_u32(b, 60),
+            cr: b[57],
+            power: read_u32(b, 64) as i32,
+        },
     })
 }
 
-/// Build a 512-byte superblock describing the current log state.
+/// Build a 512-byte superblock describing the current log state, to be
+/// written to block [`superblock_slot`]`(generation)`.
 ///
 /// Layout: magic(4) | version(4) | record_count(4) | block_count(4) |
-/// last_block_offset(4) | reserved(4) | crc32(4, last 4 bytes).
-pub fn encode_superblock(record_count: u32, block_count: u32, last_block_offset: u32) -> [u8; BLOCK_SIZE] {
+/// last_block_offset(4) | flags(4) | generation(4) | crc32(4, last 4 bytes).
+pub fn encode_superblock(
+    generation: u32,
+    record_count: u32,
+    block_count: u32,
+    last_block_offset: u32,
+    compressed: bool,
+) -> [u8; BLOCK_SIZE] {
     let mut b = [0u8; BLOCK_SIZE];
     b[0..4].copy_from_slice(&SUPERBLOCK_MAGIC);
     b[4..8].copy_from_slice(&STORAGE_VERSION.to_le_bytes());
     b[8..12].copy_from_slice(&record_count.to_le_bytes());
     b[12..16].copy_from_slice(&block_count.to_le_bytes());
     b[16..20].copy_from_slice(&last_block_offset.to_le_bytes());
+    let flags = if compressed { SUPERBLOCK_FLAG_COMPRESSED } else { 0 };
+    b[20..24].copy_from_slice(&flags.to_le_bytes());
+    b[24..28].copy_from_slice(&generation.to_le_bytes());
     let crc = crc32(&b[..USABLE_PER_BLOCK]);
     b[USABLE_PER_BLOCK..].copy_from_slice(&crc.to_le_bytes());
     b
@@ -437,22 +1183,47 @@ pub fn decode_superblock(block: &[u8; BLOCK_SIZE]) -> Option<SuperblockInfo> {
         record_count: u32::from_le_bytes(block[8..12].try_into().ok()?),
         block_count: u32::from_le_bytes(block[12..16].try_into().ok()?),
         last_block_offset: u32::from_le_bytes(block[16..20].try_into().ok()?),
+        compressed: u32::from_le_bytes(block[20..24].try_into().ok()?)
+            & SUPERBLOCK_FLAG_COMPRESSED
+            != 0,
+        generation: u32::from_le_bytes(block[24..28].try_into().ok()?),
     })
 }
 
-/// Build the 16-byte USB download response header.
+/// Block a superblock of this generation is written to. Alternating means
+/// the copy being overwritten is always the older one.
+pub fn superblock_slot(generation: u32) -> u32 {
+    SUPERBLOCK_SLOTS[(generation % 2) as usize]
+}
+
+/// The log state from whichever of the two superblock copies is valid and
+/// newest. `None` only when neither decodes.
+pub fn latest_superblock(
+    slot_a: &[u8; BLOCK_SIZE],
+    slot_b: &[u8; BLOCK_SIZE],
+) -> Option<SuperblockInfo> {
+    match (decode_superblock(slot_a), decode_superblock(slot_b)) {
+        (Some(a), Some(b)) => Some(if b.generation > a.generation { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Build the 20-byte USB download response header.
 ///
-/// Layout: magic(4) | record_count(4) | storage_version(4) | block_count(4).
+/// Layout: magic(4) | record_count(4) | storage_version(4) | block_count(4) |
+/// superblock flags(4).
 pub fn encode_response_header(
     record_count: u32,
     storage_version: u32,
     block_count: u32,
+    superblock_flags: u32,
 ) -> [u8; HEADER_LEN] {
     let mut h = [0u8; HEADER_LEN];
     h[0..4].copy_from_slice(&RESPONSE_MAGIC);
     h[4..8].copy_from_slice(&record_count.to_le_bytes());
     h[8..12].copy_from_slice(&storage_version.to_le_bytes());
     h[12..16].copy_from_slice(&block_count.to_le_bytes());
+    h[16..20].copy_from_slice(&superblock_flags.to_le_bytes());
     h
 }
 
@@ -460,17 +1231,19 @@ pub fn encode_response_header(
 pub const RESPONSE_MAGIC: [u8; 4] = *b"VLDR";
 
 /// Length of the USB download response header in bytes.
-pub const HEADER_LEN: usize = 16;
+pub const HEADER_LEN: usize = 20;
 
-/// Decoded USB download response header: `(record_count, storage_version, block_count)`.
-pub fn decode_response_header(buf: &[u8]) -> Option<(u32, u32, u32)> {
+/// Decoded USB download response header:
+/// `(record_count, storage_version, block_count, superblock_flags)`.
+pub fn decode_response_header(buf: &[u8]) -> Option<(u32, u32, u32, u32)> {
     if buf.len() < HEADER_LEN || buf[0..4] != RESPONSE_MAGIC {
         return None;
     }
     let record_count = u32::from_le_bytes(buf[4..8].try_into().ok()?);
     let storage_version = u32::from_le_bytes(buf[8..12].try_into().ok()?);
     let block_count = u32::from_le_bytes(buf[12..16].try_into().ok()?);
-    Some((record_count, storage_version, block_count))
+    let superblock_flags = u32::from_le_bytes(buf[16..20].try_into().ok()?);
+    Some((record_count, storage_version, block_count, superblock_flags))
 }
 
 /// Everything one downloaded block stream decodes to.
@@ -490,6 +1263,12 @@ pub struct ParsedLog {
 /// Parse tagged records from block bytes. Host only. Returns `None` when the
 /// stream does not decode cleanly (e.g. a log written by older firmware).
 ///
+/// `superblock_flags` picks the block decoder: with
+/// [`SUPERBLOCK_FLAG_COMPRESSED`] set, a block opening with
+/// [`BLOCK_TAG_COMPRESSED`] is decompressed first and every other block is
+/// read plain. A compressed block that will not decompress loses all of its
+/// records, which are counted as `invalid_records` from its header.
+///
 /// A block that fails its CRC is *not* fatal: its records are parsed and
 /// returned with `block_crc_ok: false` so the caller can mark them, because one
 /// bad block must not make an otherwise good flight log unrecoverable. A record
@@ -497,11 +1276,18 @@ pub struct ParsedLog {
 /// width, so the parser knows where the next one starts and the rest of the
 /// block survives.
 #[cfg(any(feature = "std", test))]
-pub fn parse_log_records(record_count: u32, blocks: &[u8], block_count: u32) -> Option<ParsedLog> {
+pub fn parse_log_records(
+    record_count: u32,
+    blocks: &[u8],
+    block_count: u32,
+    superblock_flags: u32,
+) -> Option<ParsedLog> {
+    let compressed = superblock_flags & SUPERBLOCK_FLAG_COMPRESSED != 0;
     let mut records = std::vec::Vec::with_capacity(record_count as usize);
     let mut crc_failed_blocks = 0u32;
     let mut invalid_records = 0u32;
     let mut read = 0u32;
+    let mut decoded = std::boxed::Box::new([0u8; MAX_DECODED_LEN]);
     for i in 0..block_count as usize {
         let start = i * BLOCK_SIZE;
         let block: &[u8; BLOCK_SIZE] = blocks.get(start..start + BLOCK_SIZE)?.try_into().ok()?;
@@ -509,15 +1295,24 @@ pub fn parse_log_records(record_count: u32, blocks: &[u8], block_count: u32) ->
         if !block_crc_ok {
             crc_failed_blocks += 1;
         }
+        let stream = match block_record_stream(block, compressed, &mut decoded) {
+            Ok(stream) => stream,
+            Err(claimed) => {
+                let lost = claimed.min(record_count - read);
+                invalid_records += lost;
+                read += lost;
+                continue;
+            }
+        };
         let mut off = 0usize;
         while read < record_count {
-            let Some(wire_len) = log_record_wire_len(&block[off..]) else {
+            let Some(wire_len) = log_record_wire_len(&stream[off..]) else {
                 break;
             };
-            if off + wire_len > USABLE_PER_BLOCK {
+            if off + wire_len > stream.len() {
                 break;
             }
-            match deserialize_log_record_at(block, off) {
+            match deserialize_log_record_at(stream, off) {
                 Some((record, _)) => records.push(ParsedLogRecord {
                     record,
                     block_crc_ok,
@@ -539,6 +1334,171 @@ pub fn parse_log_records(record_count: u32, blocks: &[u8], block_count: u32) ->
     }
 }
 
+/// A data block's tagged records: the block itself, or what a compressed
+/// block decodes to. `Err` carries a compressed block's claimed record count
+/// when it will not decode.
+#[cfg(any(feature = "std", test))]
+fn block_record_stream<'a>(
+    block: &'a [u8; BLOCK_SIZE],
+    compressed: bool,
+    decoded: &'a mut [u8; MAX_DECODED_LEN],
+) -> Result<&'a [u8], u32> {
+    if compressed && block[0] == BLOCK_TAG_COMPRESSED {
+        match decode_compressed_block(block, decoded) {
+            Some(decoded_len) => Ok(&decoded[..decoded_len]),
+            None => Err(block[1] as u32),
+        }
+    } else {
+        Ok(&block[..USABLE_PER_BLOCK])
+    }
+}
+
+/// Where a log really ends, as opposed to where its superblock says it does.
+#[cfg(any(feature = "std", test))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogExtent {
+    pub record_count: u32,
+    pub block_count: u32,
+    pub last_block_offset: u32,
+}
+
+/// Largest clock step a recovered fast record may take from the one before
+/// it. Far longer than any stall the logger rides out within a session.
+#[cfg(any(feature = "std", test))]
+const RECOVERY_MAX_GAP_US: u64 = 5_000_000;
+
+/// Whether `fast` can be the record after `last` in the same session.
+#[cfg(any(feature = "std", test))]
+fn continues_session(last: Option<(u32, u64)>, fast: &FlightDataFastRecord) -> bool {
+    last.is_some_and(|(sequence, timestamp_us)| {
+        fast.sequence > sequence
+            && fast.timestamp_us > timestamp_us
+            && fast.timestamp_us - timestamp_us <= RECOVERY_MAX_GAP_US
+    })
+}
+
+/// Whether a slow, event or internals record stamped `timestamp_us` can
+/// follow the fast record `last` in the same session. These carry no
+/// `sequence`, and may share a tick with the fast record before them.
+#[cfg(any(feature = "std", test))]
+fn on_session_clock(last: Option<(u32, u64)>, timestamp_us: u64) -> bool {
+    last.is_some_and(|(_, last_us)| {
+        timestamp_us >= last_us && timestamp_us - last_us <= RECOVERY_MAX_GAP_US
+    })
+}
+
+/// Find the true end of a log whose superblock may be stale. Host only.
+///
+/// The superblock is rewritten periodically, not per block, so a brown-out
+/// — typically at landing — leaves records on the card past the end it
+/// claims. `blocks` is the data area from [`DATA_START_BLOCK`] on, reaching
+/// past the claimed end (see [`crate::vlp::usb::CliRequest::DownloadRaw`]).
+/// The claimed records are walked to find the last fast record, and the scan
+/// then carries on for as long as what follows is the same log:
+///
+/// - a block beyond the claim must pass [`verify_data_block`];
+/// - every record in it must decode, and every fast record must continue the
+///   session: `sequence` rising, the clock rising by at most
+///   [`RECOVERY_MAX_GAP_US`];
+/// - every other record must be stamped on the same clock: no earlier than
+///   the last fast record, and no more than [`RECOVERY_MAX_GAP_US`] after it.
+///
+/// The last two rules are what keep an erased card's stale blocks, which
+/// carry good CRCs, out of the log. Blocks are taken whole or not at all, and the
+/// first one that fails ends the scan. The records after the claim in the
+/// claimed last block are held to the same rules, since in plain mode the
+/// firmware keeps filling that block.
+///
+/// A session that began after the last superblock write is not recovered:
+/// its `sequence` restart looks exactly like stale data. Neither is anything
+/// when the claim holds no fast record to continue from.
+#[cfg(any(feature = "std", test))]
+pub fn recover_log_extent(info: &SuperblockInfo, blocks: &[u8]) -> LogExtent {
+    let mut extent = LogExtent {
+        record_count: info.record_count,
+        block_count: info.block_count,
+        last_block_offset: info.last_block_offset,
+    };
+    let mut decoded = std::boxed::Box::new([0u8; MAX_DECODED_LEN]);
+    // Records walked so far, claimed or recovered.
+    let mut seen = 0u32;
+    let mut last_fast: Option<(u32, u64)> = None;
+    for (i, chunk) in blocks.chunks_exact(BLOCK_SIZE).enumerate() {
+        let Ok(block) = <&[u8; BLOCK_SIZE]>::try_from(chunk) else {
+            break;
+        };
+        let in_claim = (i as u32) < info.block_count;
+        // A claim that does not even walk to its own record count is no
+        // anchor for anything after it.
+        if !in_claim && seen != extent.record_count {
+            break;
+        }
+        let stream = match block_record_stream(block, info.compressed, &mut decoded) {
+            Ok(stream) => stream,
+            Err(claimed) if in_claim => {
+                seen += claimed;
+                continue;
+            }
+            Err(_) => break,
+        };
+
+        let mut recovered = 0u32;
+        let mut recovered_last_fast = last_fast;
+        let mut believable = true;
+        let mut off = 0usize;
+        while let Some(wire_len) = log_record_wire_len(&stream[off..])
+            && off + wire_len <= stream.len()
+        {
+            let record = deserialize_log_record_at(stream, off).map(|(record, _)| record);
+            if seen < info.record_count {
+                seen += 1;
+                if let Some(LogRecord::Fast(fast)) = &record {
+                    last_fast = Some((fast.sequence, fast.timestamp_us));
+                    recovered_last_fast = last_fast;
+                }
+            } else {
+                match &record {
+                    Some(LogRecord::Fast(fast)) if continues_session(recovered_last_fast, fast) => {
+                        recovered_last_fast = Some((fast.sequence, fast.timestamp_us));
+                    }
+                    Some(LogRecord::Slow(slow))
+                        if on_session_clock(recovered_last_fast, slow.timestamp_us) => {}
+                    Some(LogRecord::Event(event))
+                        if on_session_clock(recovered_last_fast, event.timestamp_us) => {}
+                    Some(LogRecord::Internals(internals))
+                        if on_session_clock(recovered_last_fast, internals.timestamp_us) => {}
+                    _ => {
+                        believable = false;
+                        break;
+                    }
+                }
+                recovered += 1;
+            }
+            off += wire_len;
+        }
+
+        if recovered > 0 {
+            if !believable || !verify_data_block(block) {
+                break;
+            }
+            seen += recovered;
+            last_fast = recovered_last_fast;
+            extent = LogExtent {
+                record_count: seen,
+                block_count: i as u32 + 1,
+                last_block_offset: if info.compressed {
+                    USABLE_PER_BLOCK as u32
+                } else {
+                    off as u32
+                },
+            };
+        } else if !in_claim {
+            break;
+        }
+    }
+    extent
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -546,8 +1506,9 @@ mod tests {
     use crate::can_bus::messages::node_status::{NodeHealth, NodeMode};
     use crate::flight_data_record::{
         AirBrakesActuationRecord, AirBrakesRecord, AirbrakesEstimatorRecord, AmpRecord,
-        DeploymentEstimatorRecord, ImuRecord, NodeStatusRecord, ParsedLogRecord, PayloadRecord,
-        merge_log_records,
+        BaroHealth, DEPLOYMENT_BARO_GATE_REJECT, DeploymentEstimatorRecord, FlightEvent,
+        FlightEventRow, ImuRecord, KfInternalsRecord, NodeStatusRecord, ParsedLogRecord,
+        PayloadRecord, collect_log_events, merge_log_records,
     };
 
     /// Records straight out of a block whose CRC checked out.
@@ -570,6 +1531,11 @@ mod tests {
                 kf_altitude_asl: Some(271.5 + i as f32),
                 kf_vertical_velocity: Some(0.25 * i as f32),
                 flags: 0,
+                baro_health: BaroHealth::pack(&[
+                    BaroHealth::Healthy,
+                    BaroHealth::Outlier,
+                    BaroHealth::Absent,
+                ]),
             }),
             airbrakes: Some(AirbrakesEstimatorRecord {
                 kf_altitude_asl: Some(272.0 + i as f32),
@@ -662,6 +1628,16 @@ mod tests {
         assert_eq!(back, r);
     }
 
+    /// Each slot of the health word decodes to the verdict packed into it,
+    /// and a zero word is three empty slots — what a one-barometer board
+    /// writes in the two it does not have.
+    #[test]
+    fn baro_health_packs_one_verdict_per_slot() {
+        let health = [BaroHealth::Stuck, BaroHealth::Healthy, BaroHealth::Noisy];
+        assert_eq!(BaroHealth::unpack(BaroHealth::pack(&health)), health);
+        assert_eq!(BaroHealth::unpack(0), [BaroHealth::Absent; 3]);
+    }
+
     /// Absence has to survive the card, not just the type system.
     ///
     /// [`sample_fast`] populates every field, so on its own it only proves
@@ -679,6 +1655,7 @@ mod tests {
                 kf_altitude_asl: None,
                 kf_vertical_velocity: None,
                 flags: 0,
+                baro_health: 0,
             }),
             airbrakes: None,
             imu: None,
@@ -722,31 +1699,128 @@ mod tests {
 
     #[test]
     fn superblock_round_trips() {
-        let sb = encode_superblock(99, 5, 123);
+        let sb = encode_superblock(7, 99, 5, 123, false);
         let info = decode_superblock(&sb).expect("decode");
+        assert_eq!(info.generation, 7);
         assert_eq!(info.storage_version, STORAGE_VERSION);
         assert_eq!(info.record_count, 99);
         assert_eq!(info.block_count, 5);
         assert_eq!(info.last_block_offset, 123);
+        assert!(!info.compressed);
+
+        let sb = encode_superblock(8, 99, 5, USABLE_PER_BLOCK as u32, true);
+        assert!(decode_superblock(&sb).expect("decode").compressed);
     }
 
     #[test]
     fn old_version_superblock_rejected() {
-        let mut sb = encode_superblock(99, 5, 123);
+        let mut sb = encode_superblock(0, 99, 5, 123, false);
         sb[4..8].copy_from_slice(&3u32.to_le_bytes());
         let crc = super::crc32(&sb[..USABLE_PER_BLOCK]);
         sb[USABLE_PER_BLOCK..].copy_from_slice(&crc.to_le_bytes());
         assert!(decode_superblock(&sb).is_none());
     }
 
+    /// Updates alternate slots, so tearing the write in progress costs one
+    /// update, never the log.
+    #[test]
+    fn the_newest_intact_superblock_copy_wins() {
+        assert_ne!(superblock_slot(41), superblock_slot(42));
+        let older = encode_superblock(41, 100, 10, 300, false);
+        let newer = encode_superblock(42, 103, 11, 0, false);
+        assert_eq!(latest_superblock(&older, &newer).unwrap().record_count, 103);
+        assert_eq!(latest_superblock(&newer, &older).unwrap().record_count, 103);
+
+        let mut torn = newer;
+        torn[200..].fill(0);
+        assert_eq!(latest_superblock(&older, &torn).unwrap().record_count, 100);
+        assert_eq!(latest_superblock(&torn, &torn), None);
+    }
+
     #[test]
     fn config_block_round_trips() {
         let cfg = AvionicsConfig {
             target_apogee_agl: 3500.5,
+            ..AvionicsConfig::default()
         };
         let block = encode_config_block(&cfg);
         let back = decode_config_block(&block).expect("decode");
         assert_eq!(back.target_apogee_agl, 3500.5);
+        assert_eq!(back, cfg);
+
+        // The other deployment variant and both lockouts absent: every
+        // presence byte has to make it back as absence, not as a zero.
+        let cfg = AvionicsConfig {
+            deployment: DeploymentConfig::Single {
+                minimum_deployment_altitude_agl: 300.0,
+                delay_us: 500_000,
+            },
+            mach_lockout: MachLockoutDurations {
+                deployment_us: None,
+                airbrakes: None,
+            },
+            ..AvionicsConfig::default()
+        };
+        assert_eq!(decode_config_block(&encode_config_block(&cfg)), Some(cfg));
+    }
+
+    /// A card configured by v1 firmware keeps its target across the upgrade.
+    /// Reverting it to the default would be the worst kind of migration: the
+    /// operator set a number, nothing says it changed, and the MPC chases a
+    /// different apogee.
+    #[test]
+    fn v1_config_block_migrates_keeping_its_target() {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[0..4].copy_from_slice(&CONFIG_BLOCK_MAGIC);
+        block[4..8].copy_from_slice(&1u32.to_le_bytes());
+        block[8..12].copy_from_slice(&3048.0f32.to_le_bytes());
+        finalize_data_block(&mut block);
+
+        assert_eq!(config_block_version(&block), Some(1));
+        let migrated = decode_config_block(&block).expect("v1 decodes");
+        assert_eq!(
+            migrated,
+            AvionicsConfig {
+                target_apogee_agl: 3048.0,
+                ..AvionicsConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn config_block_from_a_newer_version_is_refused() {
+        let mut block = encode_config_block(&AvionicsConfig::default());
+        block[4..8].copy_from_slice(&(CONFIG_BLOCK_VERSION + 1).to_le_bytes());
+        finalize_data_block(&mut block);
+        assert_eq!(config_block_version(&block), Some(CONFIG_BLOCK_VERSION + 1));
+        assert!(decode_config_block(&block).is_none());
+    }
+
+    /// A config the firmware would refuse to write is one it refuses to fly
+    /// on, even with a good CRC.
+    #[test]
+    fn invalid_config_is_rejected_on_both_ends() {
+        let cfg = AvionicsConfig {
+            deployment: DeploymentConfig::Dual {
+                drogue_chute_minimum_altitude_agl: 400.0,
+                drogue_chute_delay_us: 0,
+                main_chute_altitude_agl: 457.2,
+                main_chute_delay_us: 0,
+            },
+            ..AvionicsConfig::default()
+        };
+        assert_eq!(cfg.validate(), Err(AvionicsConfigError::MainNotBelowDrogue));
+        assert!(decode_config_block(&encode_config_block(&cfg)).is_none());
+
+        let cfg = AvionicsConfig {
+            lora: LoraConfig {
+                bw: 200_000,
+                ..AvionicsConfig::default().lora
+            },
+            ..AvionicsConfig::default()
+        };
+        assert_eq!(cfg.validate(), Err(AvionicsConfigError::LoraModulation));
+        assert_eq!(AvionicsConfig::default().validate(), Ok(()));
     }
 
     #[test]
@@ -769,16 +1843,19 @@ mod tests {
         let (blocks, last_off) = pack_log(&log);
 
         let mut wire = Vec::new();
-        wire.extend_from_slice(&encode_response_header(n, STORAGE_VERSION, blocks.len() as u32));
+        wire.extend_from_slice(&encode_response_header(n, STORAGE_VERSION, blocks.len() as u32, 0));
         for b in &blocks {
             wire.extend_from_slice(b);
         }
 
-        let (record_count, storage_version, block_count) =
+        let (record_count, storage_version, block_count, superblock_flags) =
             decode_response_header(&wire).unwrap();
         assert_eq!(record_count, n);
         assert_eq!(storage_version, STORAGE_VERSION);
-        let parsed = parse_log_records(record_count, &wire[HEADER_LEN..], block_count).unwrap();
+        assert_eq!(superblock_flags, 0);
+        let parsed =
+            parse_log_records(record_count, &wire[HEADER_LEN..], block_count, superblock_flags)
+                .unwrap();
         assert_eq!(parsed.crc_failed_blocks, 0);
         assert_eq!(parsed.invalid_records, 0);
         let recovered: Vec<LogRecord> =
@@ -808,7 +1885,7 @@ mod tests {
             Some(271.5)
         );
 
-        let sb = encode_superblock(n, blocks.len() as u32, last_off);
+        let sb = encode_superblock(0, n, blocks.len() as u32, last_off, false);
         let info = decode_superblock(&sb).unwrap();
         assert_eq!(info.last_block_offset, last_off);
     }
@@ -987,7 +2064,7 @@ mod tests {
         for b in &blocks {
             wire.extend_from_slice(b);
         }
-        let parsed = parse_log_records(n, &wire, blocks.len() as u32).expect("still parses");
+        let parsed = parse_log_records(n, &wire, blocks.len() as u32, 0).expect("still parses");
         assert_eq!(parsed.crc_failed_blocks, blocks.len() as u32);
         // Whatever survived validation is exported, and marked.
         assert!(parsed.records.iter().all(|r| !r.block_crc_ok));
@@ -1000,4 +2077,433 @@ mod tests {
             assert!(row.source_block_crc_failed);
         }
     }
+
+    /// Run `records` through a [`CompressedBlockPacker`] the way the logger
+    /// does, flushing what is left at the end.
+    fn pack_log_compressed(records: &[LogRecord]) -> Vec<PackedBlock> {
+        let mut packer = CompressedBlockPacker::new();
+        let mut blocks: Vec<PackedBlock> = records.iter().filter_map(|r| packer.push(r)).collect();
+        while let Some(block) = packer.flush() {
+            blocks.push(block);
+        }
+        assert_eq!(packer.staged_records(), 0);
+        blocks
+    }
+
+    fn wire_of(blocks: &[PackedBlock]) -> Vec<u8> {
+        blocks.iter().flat_map(|b| b.block).collect()
+    }
+
+    /// A pad wait: the estimators are settled and only the clocks move. This
+    /// is the case compression is for, and it has to pay off properly there.
+    #[test]
+    fn compressed_pad_log_round_trips_at_several_times_plain_density() {
+        let mut log: Vec<LogRecord> = Vec::new();
+        for i in 0..400u32 {
+            if i % 42 == 0 {
+                log.push(LogRecord::Slow(sample_slow(i)));
+            }
+            let mut fast = sample_fast(0);
+            fast.sequence = i;
+            fast.timestamp_us = i as u64 * 2400;
+            fast.unix_time_us = Some(1_750_000_000_000_000 + i as u64 * 2400);
+            log.push(LogRecord::Fast(fast));
+        }
+        let n = log.len() as u32;
+        let blocks = pack_log_compressed(&log);
+        assert_eq!(blocks.iter().map(|b| b.record_count).sum::<u32>(), n);
+        assert!(blocks.iter().all(|b| verify_data_block(&b.block)));
+        let (plain, _) = pack_log(&log);
+        assert!(
+            blocks.len() * 3 <= plain.len(),
+            "{} compressed blocks against {} plain",
+            blocks.len(),
+            plain.len()
+        );
+
+        let parsed = parse_log_records(
+            n,
+            &wire_of(&blocks),
+            blocks.len() as u32,
+            SUPERBLOCK_FLAG_COMPRESSED,
+        )
+        .unwrap();
+        assert_eq!(parsed.invalid_records, 0);
+        let recovered: Vec<LogRecord> = parsed.records.into_iter().map(|r| r.record).collect();
+        assert_eq!(recovered, log);
+    }
+
+    /// In flight every sample moves. Whatever that does to the ratio, a
+    /// compressed log never takes more blocks than a plain one — blocks that
+    /// would not pay are stored plain — and reads back exactly.
+    #[test]
+    fn noisy_records_are_never_stored_less_densely_than_plain() {
+        // xorshift: every sample changes in every bit, so the deltas do too.
+        let mut state = 0x2545_f491_u32;
+        let mut noise = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            // Keep the exponent in range so every value stays finite.
+            f32::from_bits((state & 0x807f_ffff) | 0x3f00_0000)
+        };
+        let log: Vec<LogRecord> = (0..60u32)
+            .map(|i| {
+                let mut fast = sample_fast(i);
+                fast.sequence = i.wrapping_mul(0x9e37_79b9);
+                fast.timestamp_us = (noise().to_bits() as u64) << 20;
+                fast.imu = Some(ImuRecord {
+                    acc: [noise(), noise(), noise()],
+                    gyro: [noise(), noise(), noise()],
+                });
+                fast.pressure = noise();
+                fast.mag = Some([noise(), noise(), noise()]);
+                LogRecord::Fast(fast)
+            })
+            .collect();
+        let blocks = pack_log_compressed(&log);
+        let (plain, _) = pack_log(&log);
+        assert!(blocks.len() <= plain.len());
+
+        let parsed = parse_log_records(
+            log.len() as u32,
+            &wire_of(&blocks),
+            blocks.len() as u32,
+            SUPERBLOCK_FLAG_COMPRESSED,
+        )
+        .unwrap();
+        let recovered: Vec<LogRecord> = parsed.records.into_iter().map(|r| r.record).collect();
+        assert_eq!(recovered, log);
+    }
+
+    /// A compressed block that will not decompress costs its own records,
+    /// counted from its header, and nothing after it.
+    #[test]
+    fn a_corrupt_compressed_block_is_counted_and_skipped() {
+        let log: Vec<LogRecord> = (0..200u32).map(|i| LogRecord::Fast(sample_fast(i))).collect();
+        let mut blocks = pack_log_compressed(&log);
+        assert!(blocks.len() >= 3);
+        assert!(blocks[1].compressed);
+        let lost = blocks[1].record_count;
+        // Claim a decoded length the stream cannot produce.
+        blocks[1].block[4] ^= 0x01;
+
+        let parsed = parse_log_records(
+            log.len() as u32,
+            &wire_of(&blocks),
+            blocks.len() as u32,
+            SUPERBLOCK_FLAG_COMPRESSED,
+        )
+        .expect("still parses");
+        assert_eq!(parsed.crc_failed_blocks, 1);
+        assert_eq!(parsed.invalid_records, lost);
+        assert_eq!(parsed.records.len() as u32 + lost, log.len() as u32);
+        let first_after = blocks[0].record_count as usize;
+        assert_eq!(parsed.records[first_after].record, log[first_after + lost as usize]);
+    }
+
+    /// A pad-wait-then-flight log of fast records with a slow one every 10,
+    /// all on the one clock.
+    fn session_log(len: u32) -> Vec<LogRecord> {
+        let mut log = Vec::new();
+        for i in 0..len {
+            if i % 10 == 0 {
+                log.push(LogRecord::Slow(FlightDataSlowRecord {
+                    timestamp_us: sample_fast(i).timestamp_us,
+                    ..sample_slow(i)
+                }));
+            }
+            log.push(LogRecord::Fast(sample_fast(i)));
+        }
+        log
+    }
+
+    fn claim(record_count: u32, block_count: u32, last_block_offset: u32) -> SuperblockInfo {
+        decode_superblock(&encode_superblock(
+            0,
+            record_count,
+            block_count,
+            last_block_offset,
+            false,
+        ))
+        .unwrap()
+    }
+
+    /// The brown-out case: the superblock was last written a few blocks
+    /// before the end, mid-block, and the card past the log holds an erased
+    /// older log with perfectly good CRCs.
+    #[test]
+    fn recovery_finds_the_tail_past_a_stale_superblock_and_stops_at_stale_data() {
+        let log = session_log(60);
+        let (blocks, last_off) = pack_log(&log);
+        assert!(blocks.len() > 8);
+
+        // Claim the first record of block 5 only.
+        let records_before_5: u32 = blocks[..5]
+            .iter()
+            .map(|b| count_records_in_bytes(b, USABLE_PER_BLOCK))
+            .sum();
+        let first_len = log_record_wire_len(&blocks[5]).unwrap() as u32;
+        let info = claim(records_before_5 + 1, 6, first_len);
+
+        let mut card: Vec<u8> = blocks.iter().flatten().copied().collect();
+        // An older flight, further along its own session than this one — so
+        // its sequence numbers do follow on — but on its own boot's clock.
+        let older_flight: Vec<LogRecord> = session_log(400)
+            .into_iter()
+            .map(|r| match r {
+                LogRecord::Fast(mut fast) => {
+                    fast.timestamp_us += 3_600_000_000;
+                    LogRecord::Fast(fast)
+                }
+                LogRecord::Slow(mut slow) => {
+                    slow.timestamp_us += 3_600_000_000;
+                    LogRecord::Slow(slow)
+                }
+                other => other,
+            })
+            .collect();
+        let (stale, _) = pack_log(&older_flight);
+        card.extend(stale[100..104].iter().flatten());
+        card.extend([0u8; BLOCK_SIZE * 2]);
+
+        let extent = recover_log_extent(&info, &card);
+        assert_eq!(
+            extent,
+            LogExtent {
+                record_count: log.len() as u32,
+                block_count: blocks.len() as u32,
+                last_block_offset: last_off,
+            }
+        );
+        let parsed = parse_log_records(extent.record_count, &card, extent.block_count, 0).unwrap();
+        let recovered: Vec<LogRecord> = parsed.records.into_iter().map(|r| r.record).collect();
+        assert_eq!(recovered, log);
+    }
+
+    #[test]
+    fn recovery_never_extends_past_a_bad_block() {
+        let log = session_log(60);
+        let (mut blocks, _) = pack_log(&log);
+        let records_before_4: u32 = blocks[..4]
+            .iter()
+            .map(|b| count_records_in_bytes(b, USABLE_PER_BLOCK))
+            .sum();
+        let info = claim(records_before_4, 4, USABLE_PER_BLOCK as u32);
+        blocks[6][3] ^= 0xFF;
+        let card: Vec<u8> = blocks.iter().flatten().copied().collect();
+
+        let extent = recover_log_extent(&info, &card);
+        assert_eq!(extent.block_count, 6);
+        assert_eq!(
+            extent.record_count,
+            blocks[..6]
+                .iter()
+                .map(|b| count_records_in_bytes(b, USABLE_PER_BLOCK))
+                .sum::<u32>()
+        );
+    }
+
+    #[test]
+    fn a_compressed_log_recovers_its_tail_blocks() {
+        let log = session_log(300);
+        let blocks = pack_log_compressed(&log);
+        assert!(blocks.len() >= 4);
+        let claimed: u32 = blocks[..2].iter().map(|b| b.record_count).sum();
+        let info = decode_superblock(&encode_superblock(
+            3,
+            claimed,
+            2,
+            USABLE_PER_BLOCK as u32,
+            true,
+        ))
+        .unwrap();
+        let card = wire_of(&blocks);
+
+        let extent = recover_log_extent(&info, &card);
+        assert_eq!(extent.record_count, log.len() as u32);
+        assert_eq!(extent.block_count, blocks.len() as u32);
+        assert_eq!(extent.last_block_offset, USABLE_PER_BLOCK as u32);
+    }
+
+    /// Events carry no sequence to break, but an older boot's are on an
+    /// older boot's clock: a block of them past the claim is not this log's.
+    #[test]
+    fn recovery_stops_at_stale_event_only_blocks() {
+        let log = session_log(60);
+        let (blocks, _) = pack_log(&log);
+        let claimed = blocks.len() - 1;
+        let records_claimed: u32 = blocks[..claimed]
+            .iter()
+            .map(|b| count_records_in_bytes(b, USABLE_PER_BLOCK))
+            .sum();
+        let info = claim(records_claimed, claimed as u32, USABLE_PER_BLOCK as u32);
+        let last_fast_us = log[..records_claimed as usize]
+            .iter()
+            .rev()
+            .find_map(|r| match r {
+                LogRecord::Fast(fast) => Some(fast.timestamp_us),
+                _ => None,
+            })
+            .unwrap();
+        let events = |timestamp_us: u64| {
+            let records: Vec<LogRecord> = (0..20)
+                .map(|_| {
+                    LogRecord::Event(FlightEventRecord {
+                        timestamp_us,
+                        event: FlightEvent::ConfigChanged {
+                            target_apogee_agl: 3048.0,
+                        },
+                    })
+                })
+                .collect();
+            pack_log(&records).0[0]
+        };
+        let card_with = |tail: [u8; BLOCK_SIZE]| -> Vec<u8> {
+            blocks[..claimed]
+                .iter()
+                .chain([&tail, &[0u8; BLOCK_SIZE]])
+                .flatten()
+                .copied()
+                .collect()
+        };
+
+        // Stamped just after the last claimed fast record, the block is taken...
+        let extent = recover_log_extent(&info, &card_with(events(last_fast_us + 1000)));
+        assert_eq!(extent.block_count, claimed as u32 + 1);
+        // ...stamped by an earlier boot, or an hour on, it is not.
+        for stale_us in [1000, last_fast_us + 3_600_000_000] {
+            let extent = recover_log_extent(&info, &card_with(events(stale_us)));
+            assert_eq!(
+                extent,
+                LogExtent {
+                    record_count: records_claimed,
+                    block_count: claimed as u32,
+                    last_block_offset: USABLE_PER_BLOCK as u32,
+                }
+            );
+        }
+    }
+
+    /// With no fast record claimed there is nothing to tell this log's tail
+    /// from any other log's, so nothing is recovered.
+    #[test]
+    fn recovery_needs_a_claimed_fast_record_to_continue_from() {
+        let (blocks, _) = pack_log(&session_log(30));
+        let card: Vec<u8> = blocks.iter().flatten().copied().collect();
+        let info = claim(0, 0, 0);
+        assert_eq!(
+            recover_log_extent(&info, &card),
+            LogExtent {
+                record_count: 0,
+                block_count: 0,
+                last_block_offset: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn event_records_round_trip_and_land_between_their_rows() {
+        let events = [
+            FlightEvent::StageChanged {
+                from: FlightStage::Armed,
+                to: FlightStage::Ascent,
+            },
+            FlightEvent::UplinkReceived {
+                packet_type: 3,
+                signature_valid: false,
+            },
+            FlightEvent::NodeRebooted {
+                node_type: 15,
+                uptime_s: 1,
+            },
+            FlightEvent::ConfigChanged {
+                target_apogee_agl: 3048.0,
+            },
+            FlightEvent::Staging { kind: 1, burn: 1 },
+        ];
+        for event in events {
+            let r = LogRecord::Event(FlightEventRecord {
+                timestamp_us: 1_234_567,
+                event,
+            });
+            let (bytes, len) = serialize_log_record(&r);
+            assert_eq!(len, EVENT_WIRE_LEN);
+            assert_eq!(deserialize_log_record_at(&bytes[..len], 0).unwrap().0, r);
+        }
+
+        let pyro = FlightEventRecord {
+            timestamp_us: 2401,
+            event: FlightEvent::PyroCommanded {
+                channel: 1,
+                by_uplink: false,
+            },
+        };
+        let log = vec![
+            LogRecord::Slow(sample_slow(0)),
+            LogRecord::Fast(sample_fast(0)),
+            LogRecord::Fast(sample_fast(1)),
+            LogRecord::Event(pyro),
+            LogRecord::Fast(sample_fast(2)),
+        ];
+        let (blocks, _) = pack_log(&log);
+        let wire: Vec<u8> = blocks.iter().flatten().copied().collect();
+        let parsed = parse_log_records(log.len() as u32, &wire, blocks.len() as u32, 0).unwrap();
+
+        // The event is not a row...
+        assert_eq!(merge_log_records(&parsed.records).len(), 3);
+        // ...it sits before the third one.
+        assert_eq!(
+            collect_log_events(&parsed.records),
+            vec![FlightEventRow {
+                next_row: 2,
+                source_block_crc_failed: false,
+                timestamp_us: 2401,
+                event: pyro.event,
+            }]
+        );
+    }
+
+    /// An internals record goes on the row of the fast record it was written
+    /// ahead of, and on no other; one whose fast record never made it is
+    /// dropped rather than put on the next row.
+    #[test]
+    fn internals_records_round_trip_and_land_on_their_own_row() {
+        let internals = |timestamp_us| EstimatorInternalsRecord {
+            timestamp_us,
+            deployment: Some(KfInternalsRecord {
+                innovation: -1.5,
+                innovation_variance: 0.9,
+                altitude_variance: 0.4,
+                velocity_variance: 2.5,
+                gate_flags: DEPLOYMENT_BARO_GATE_REJECT,
+            }),
+            airbrakes: None,
+        };
+        let r = LogRecord::Internals(internals(5));
+        let (bytes, len) = serialize_log_record(&r);
+        assert_eq!(len, INTERNALS_WIRE_LEN);
+        assert_eq!(deserialize_log_record_at(&bytes[..len], 0).unwrap().0, r);
+
+        let fast = [sample_fast(0), sample_fast(1), sample_fast(2)];
+        let log = vec![
+            LogRecord::Slow(sample_slow(0)),
+            LogRecord::Internals(internals(fast[0].timestamp_us)),
+            LogRecord::Fast(fast[0].clone()),
+            // Its own fast record was lost: nothing on the next row.
+            LogRecord::Internals(internals(fast[1].timestamp_us + 1)),
+            LogRecord::Fast(fast[1].clone()),
+            LogRecord::Fast(fast[2].clone()),
+        ];
+        let (blocks, _) = pack_log(&log);
+        let wire: Vec<u8> = blocks.iter().flatten().copied().collect();
+        let parsed = parse_log_records(log.len() as u32, &wire, blocks.len() as u32, 0).unwrap();
+
+        let merged = merge_log_records(&parsed.records);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].internals, Some(internals(fast[0].timestamp_us)));
+        assert_eq!(merged[1].internals, None);
+        assert_eq!(merged[2].internals, None);
+        assert!(collect_log_events(&parsed.records).is_empty());
+    }
 }
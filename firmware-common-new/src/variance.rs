@@ -13,6 +13,7 @@ use micromath::F32Ext;
 /// stats.update([ax, ay, az, gx, gy, gz]);
 /// let var = stats.variance();
 /// ```
+#[derive(Debug, Clone)]
 pub struct VarianceEstimator<const N: usize> {
     n: u32,         // sample count
     mean: [f32; N], // running mean μ
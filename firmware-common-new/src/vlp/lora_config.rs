@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub struct LoraConfig {
     pub frequency: u32,
     pub sf: u8,
@@ -10,7 +10,21 @@ pub struct LoraConfig {
     pub power: i32,
 }
 
+/// Every bandwidth (Hz) the `bw_*` conversions accept. Anything else panics
+/// there, so a config from outside the firmware is checked against this first.
+pub const LORA_BANDWIDTHS_HZ: [u32; 10] = [
+    7810, 10420, 15630, 20830, 31250, 41670, 62500, 125000, 250000, 500000,
+];
+
 impl LoraConfig {
+    /// Whether every `*_modulation` / `*_phy` conversion below will succeed
+    /// rather than panic.
+    pub fn is_valid(&self) -> bool {
+        (5..=12).contains(&self.sf)
+            && LORA_BANDWIDTHS_HZ.contains(&self.bw)
+            && (5..=8).contains(&self.cr)
+    }
+
     pub fn sf_modulation(&self) -> lora_modulation::SpreadingFactor {
         use lora_modulation::SpreadingFactor;
 
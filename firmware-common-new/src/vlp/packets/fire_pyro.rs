@@ -3,6 +3,16 @@ use serde::{Deserialize, Serialize};
 
 use super::VLPUplinkPacket;
 
+/// How many pyro channels there are: one per [`PyroSelect`] variant.
+pub const PYRO_CHANNELS: usize = 5;
+
+/// One pyro channel, named for what the board wires to it.
+///
+/// The discriminant is the channel's index everywhere one is stored — the
+/// `PyroCommanded` event, the per-channel arrays in the downlink and the
+/// `PYRO_*` flag pairs in the log — so a variant's number never changes once
+/// it has flown. Main and drogue are the two the deployment profile fires;
+/// the other three fire only from the flight profile's pyro-event table.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(
     PrimitiveEnum_u8, Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize,
@@ -11,14 +21,84 @@ use super::VLPUplinkPacket;
 pub enum PyroSelect {
     PyroMain = 0,
     PyroDrogue = 1,
+    PyroSeparation = 2,
+    PyroBackupDrogue = 3,
+    PyroBackupMain = 4,
+}
+
+impl PyroSelect {
+    /// Every channel, in discriminant order.
+    pub const ALL: [Self; PYRO_CHANNELS] = [
+        Self::PyroMain,
+        Self::PyroDrogue,
+        Self::PyroSeparation,
+        Self::PyroBackupDrogue,
+        Self::PyroBackupMain,
+    ];
+
+    /// The channel's index into a `[_; PYRO_CHANNELS]`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The original two-bit channel field. Main and drogue keep the codes they
+/// have always flown with; every other channel goes out as `Extended`, which
+/// firmware from before the other channels existed fails to decode and
+/// drops, rather than reading it as main or drogue.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PrimitiveEnum_u8, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PyroCode {
+    Main = 0,
+    Drogue = 1,
+    Extended = 2,
 }
 
+/// Which channel an `Extended` code names. Zero, and ignored, under the
+/// other codes.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PrimitiveEnum_u8, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum ExtendedPyro {
+    Separation = 0,
+    BackupDrogue = 1,
+    BackupMain = 2,
+}
+
+/// Fire one channel. Laid out so that main and drogue are the same byte
+/// they were when there were only those two: a ground station and a board
+/// built either side of the other channels agree on them, and the other
+/// channels are dropped by whichever side does not know them.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(PackedStruct, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[packed_struct(bit_numbering = "msb0", endian = "msb", size_bytes = "1")]
 pub struct FirePyroPacket {
     #[packed_field(bits = "0..2", ty = "enum")]
-    pub pyro: PyroSelect,
+    code: PyroCode,
+    #[packed_field(bits = "2..4", ty = "enum")]
+    extension: ExtendedPyro,
+}
+
+impl FirePyroPacket {
+    pub fn new(pyro: PyroSelect) -> Self {
+        let (code, extension) = match pyro {
+            PyroSelect::PyroMain => (PyroCode::Main, ExtendedPyro::Separation),
+            PyroSelect::PyroDrogue => (PyroCode::Drogue, ExtendedPyro::Separation),
+            PyroSelect::PyroSeparation => (PyroCode::Extended, ExtendedPyro::Separation),
+            PyroSelect::PyroBackupDrogue => (PyroCode::Extended, ExtendedPyro::BackupDrogue),
+            PyroSelect::PyroBackupMain => (PyroCode::Extended, ExtendedPyro::BackupMain),
+        };
+        Self { code, extension }
+    }
+
+    pub fn pyro(&self) -> PyroSelect {
+        match (self.code, self.extension) {
+            (PyroCode::Main, _) => PyroSelect::PyroMain,
+            (PyroCode::Drogue, _) => PyroSelect::PyroDrogue,
+            (PyroCode::Extended, ExtendedPyro::Separation) => PyroSelect::PyroSeparation,
+            (PyroCode::Extended, ExtendedPyro::BackupDrogue) => PyroSelect::PyroBackupDrogue,
+            (PyroCode::Extended, ExtendedPyro::BackupMain) => PyroSelect::PyroBackupMain,
+        }
+    }
 }
 
 impl Into<VLPUplinkPacket> for FirePyroPacket {
@@ -33,9 +113,7 @@ mod tests {
 
     #[test]
     fn test_serialize_deserialize() {
-        let packet = FirePyroPacket {
-            pyro: PyroSelect::PyroDrogue,
-        };
+        let packet = FirePyroPacket::new(PyroSelect::PyroDrogue);
         let packet: VLPUplinkPacket = packet.into();
 
         let mut buffer = [0u8; 10];
@@ -45,4 +123,72 @@ mod tests {
 
         assert_eq!(deserialized_packet, packet);
     }
+
+    #[test]
+    fn every_channel_survives_the_wire() {
+        for (i, pyro) in PyroSelect::ALL.into_iter().enumerate() {
+            assert_eq!(pyro.index(), i);
+            let packet: VLPUplinkPacket = FirePyroPacket::new(pyro).into();
+
+            let mut buffer = [0u8; 10];
+            let len = packet.serialize(&mut buffer);
+
+            let deserialized = VLPUplinkPacket::deserialize(&buffer[..len]);
+            assert_eq!(deserialized, Some(packet));
+            let Some(VLPUplinkPacket::FirePyro(deserialized)) = deserialized else {
+                unreachable!()
+            };
+            assert_eq!(deserialized.pyro(), pyro);
+        }
+    }
+
+    /// The packet as it was with two channels, which is what a board or
+    /// ground station that has not been updated still reads.
+    #[derive(PrimitiveEnum_u8, Clone, Copy, Debug, PartialEq, Eq)]
+    enum TwoChannelPyroSelect {
+        PyroMain = 0,
+        PyroDrogue = 1,
+    }
+
+    #[derive(PackedStruct, Debug, Clone, PartialEq, Eq)]
+    #[packed_struct(bit_numbering = "msb0", endian = "msb", size_bytes = "1")]
+    struct TwoChannelFirePyroPacket {
+        #[packed_field(bits = "0..2", ty = "enum")]
+        pyro: TwoChannelPyroSelect,
+    }
+
+    /// Main and drogue are the bytes they always were, both ways, and an
+    /// old reader drops the other channels instead of firing something.
+    #[test]
+    fn main_and_drogue_keep_their_two_channel_encoding() {
+        for (pyro, old) in [
+            (PyroSelect::PyroMain, TwoChannelPyroSelect::PyroMain),
+            (PyroSelect::PyroDrogue, TwoChannelPyroSelect::PyroDrogue),
+        ] {
+            let old = TwoChannelFirePyroPacket { pyro: old };
+            let bytes = FirePyroPacket::new(pyro).pack().unwrap();
+            assert_eq!(bytes, old.pack().unwrap());
+            assert_eq!(
+                TwoChannelFirePyroPacket::unpack(&bytes).ok(),
+                Some(old.clone())
+            );
+            assert_eq!(
+                FirePyroPacket::unpack(&old.pack().unwrap())
+                    .ok()
+                    .map(|packet| packet.pyro()),
+                Some(pyro)
+            );
+        }
+        for pyro in [
+            PyroSelect::PyroSeparation,
+            PyroSelect::PyroBackupDrogue,
+            PyroSelect::PyroBackupMain,
+        ] {
+            let bytes = FirePyroPacket::new(pyro).pack().unwrap();
+            assert!(
+                TwoChannelFirePyroPacket::unpack(&bytes).is_err(),
+                "{pyro:?}"
+            );
+        }
+    }
 }
@@ -15,6 +15,7 @@ use crate::{
     gps::GPSData,
 };
 
+use super::fire_pyro::{PYRO_CHANNELS, PyroSelect};
 use super::{
     BATTERY_V_FAC_BITS, BatteryVFac, BatteryVFacBase, EPM_BATT_V_FAC_BITS, EpmBattVFacBase,
     TEMPERATURE_FAC_BITS, TemperatureFac, TemperatureFacBase, VLPDownlinkPacket,
@@ -123,9 +124,9 @@ fn defined(value: Option<f32>) -> Option<f32> {
     value.filter(|v| !v.is_nan())
 }
 
-// 328 bits of declared fields = exactly 41 bytes, no spare. On air the packet
+// 336 bits of declared fields = exactly 42 bytes, no spare. On air the packet
 // costs `n + 1` bytes of data plus `(n + 1) / 4` of reed-solomon ecc, which
-// puts this at 52 bytes on air.
+// puts this at 53 bytes on air.
 //
 // The link is SF12 / 250kHz / CR4/8, preamble 8, explicit header, **CRC off**
 // (`create_tx_packet_params(8, false, false, false, ..)` in `vlp::lora`), and
@@ -142,6 +143,13 @@ fn defined(value: Option<f32>) -> Option<f32> {
 // mistake hid three bytes of headroom, which the payload's load cells have now
 // spent. The next field costs 131.1ms whatever it is.
 //
+// And the next field was the pyros. Going from two channels to five put three
+// continuity bits and a fired bit per channel on the ground's list, eight bits
+// with nothing left to narrow for them, so the packet took the 42nd byte and
+// its 131.1ms: 1773.6ms on air, 89% of the telemetry period. That byte is
+// full too, and it opens the 42..46 byte bucket — the next four bytes cost
+// nothing more on air.
+//
 // The packet was 300 bits with four spare until 2026-08-18. Narrowing the
 // payload's EPM battery to 12..17V and its six rail currents to 0..1A freed
 // fourteen bits; those plus the four spare bought the payload's arm-sequence
@@ -159,7 +167,7 @@ fn defined(value: Option<f32>) -> Option<f32> {
 // back as an `Option` by its getter, so no caller can confuse "the estimator
 // had nothing to say" with a real zero.
 #[derive(PackedStruct, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-#[packed_struct(bit_numbering = "msb0", endian = "msb", size_bytes = "41")]
+#[packed_struct(bit_numbering = "msb0", endian = "msb", size_bytes = "42")]
 pub struct TelemetryPacket {
     #[packed_field(bits = "0..4")]
     nonce: Integer<u8, packed_bits::Bits<4>>,
@@ -189,6 +197,20 @@ pub struct TelemetryPacket {
 
     pyro_main_continuity: bool,
     pyro_drogue_continuity: bool,
+    /// The channels past main and drogue, then whether each of the five has
+    /// fired. Decode with [`Self::pyro_channels`]. Continuity is what the
+    /// pad crew arms against; a fired bit is the flight computer's own
+    /// record that it commanded the channel, latched, which a continuity
+    /// that merely went open — a charge that blew, or a wire that came
+    /// loose — cannot tell apart.
+    pyro_separation_continuity: bool,
+    pyro_backup_drogue_continuity: bool,
+    pyro_backup_main_continuity: bool,
+    pyro_main_fired: bool,
+    pyro_drogue_fired: bool,
+    pyro_separation_fired: bool,
+    pyro_backup_drogue_fired: bool,
+    pyro_backup_main_fired: bool,
 
     /// Whether the deployment estimator produced an altitude and a vertical
     /// velocity for this packet. One bit for both because they are born and
@@ -488,6 +510,17 @@ impl From<ExperimentChannelFlags> for DownlinkExperimentFlags {
     }
 }
 
+/// One pyro channel as the downlink reports it. Indexed by
+/// [`PyroSelect::index`] wherever it comes in an array.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PyroChannelStatus {
+    /// The continuity watch reads a charge across the channel.
+    pub continuity: bool,
+    /// The flight computer has commanded this channel. Latched.
+    pub fired: bool,
+}
+
 /// The deployment estimator's live state, the pair of numbers the deployment
 /// logic actually acts on. Grouped because they share one validity bit on the
 /// wire: passing them as one `Option` is what makes it impossible to downlink
@@ -523,8 +556,8 @@ impl TelemetryPacket {
         vl_battery_v: f32,
         air_temperature: f32,
 
-        pyro_main_continuity: bool,
-        pyro_drogue_continuity: bool,
+        // Channel index order, see `PyroSelect::index`.
+        pyro: [PyroChannelStatus; PYRO_CHANNELS],
 
         deployment_kf: Option<DeploymentKfState>,
         max_deployment_kf_altitude_agl: Option<f32>,
@@ -608,8 +641,16 @@ impl TelemetryPacket {
             vl_battery_v: BatteryVFac::to_fixed_point_capped(vl_battery_v),
             air_temperature: TemperatureFac::to_fixed_point_capped(air_temperature),
 
-            pyro_main_continuity,
-            pyro_drogue_continuity,
+            pyro_main_continuity: pyro[PyroSelect::PyroMain.index()].continuity,
+            pyro_drogue_continuity: pyro[PyroSelect::PyroDrogue.index()].continuity,
+            pyro_separation_continuity: pyro[PyroSelect::PyroSeparation.index()].continuity,
+            pyro_backup_drogue_continuity: pyro[PyroSelect::PyroBackupDrogue.index()].continuity,
+            pyro_backup_main_continuity: pyro[PyroSelect::PyroBackupMain.index()].continuity,
+            pyro_main_fired: pyro[PyroSelect::PyroMain.index()].fired,
+            pyro_drogue_fired: pyro[PyroSelect::PyroDrogue.index()].fired,
+            pyro_separation_fired: pyro[PyroSelect::PyroSeparation.index()].fired,
+            pyro_backup_drogue_fired: pyro[PyroSelect::PyroBackupDrogue.index()].fired,
+            pyro_backup_main_fired: pyro[PyroSelect::PyroBackupMain.index()].fired,
 
             deployment_kf_valid: deployment_kf.is_some(),
             deployment_kf_altitude_agl: AltitudeFac::to_fixed_point_capped(
@@ -834,6 +875,32 @@ impl TelemetryPacket {
         self.pyro_drogue_continuity
     }
 
+    /// Every pyro channel, in [`PyroSelect::index`] order.
+    pub fn pyro_channels(&self) -> [PyroChannelStatus; PYRO_CHANNELS] {
+        [
+            PyroChannelStatus {
+                continuity: self.pyro_main_continuity,
+                fired: self.pyro_main_fired,
+            },
+            PyroChannelStatus {
+                continuity: self.pyro_drogue_continuity,
+                fired: self.pyro_drogue_fired,
+            },
+            PyroChannelStatus {
+                continuity: self.pyro_separation_continuity,
+                fired: self.pyro_separation_fired,
+            },
+            PyroChannelStatus {
+                continuity: self.pyro_backup_drogue_continuity,
+                fired: self.pyro_backup_drogue_fired,
+            },
+            PyroChannelStatus {
+                continuity: self.pyro_backup_main_continuity,
+                fired: self.pyro_backup_main_fired,
+            },
+        ]
+    }
+
     /// Altitude and signed vertical velocity together, as the deployment
     /// estimator produced them. `None` before the filter is born and through
     /// the Mach lockout, and `Some` everywhere else including the pad — see
@@ -1228,6 +1295,14 @@ impl TelemetryPacket {
             air_temperature: self.air_temperature(),
             pyro_main_continuity: self.pyro_main_continuity(),
             pyro_drogue_continuity: self.pyro_drogue_continuity(),
+            pyro_separation_continuity: self.pyro_separation_continuity,
+            pyro_backup_drogue_continuity: self.pyro_backup_drogue_continuity,
+            pyro_backup_main_continuity: self.pyro_backup_main_continuity,
+            pyro_main_fired: self.pyro_main_fired,
+            pyro_drogue_fired: self.pyro_drogue_fired,
+            pyro_separation_fired: self.pyro_separation_fired,
+            pyro_backup_drogue_fired: self.pyro_backup_drogue_fired,
+            pyro_backup_main_fired: self.pyro_backup_main_fired,
             deployment_kf_altitude_agl: self.deployment_kf_altitude_agl(),
             max_deployment_kf_altitude_agl: self.max_deployment_kf_altitude_agl(),
             deployment_kf_vertical_velocity: self.deployment_kf_vertical_velocity(),
@@ -1329,8 +1404,9 @@ pub struct TelemetryPacketBuilderState {
     pub vl_battery_v: f32,
     pub air_temperature: f32,
 
-    pub pyro_main_continuity: bool,
-    pub pyro_drogue_continuity: bool,
+    /// Continuity and the latched fire command, per channel in
+    /// [`PyroSelect::index`] order.
+    pub pyro: [PyroChannelStatus; PYRO_CHANNELS],
 
     /// The deployment estimator's altitude and signed vertical velocity.
     /// `None` whenever the estimator has nothing to say — before the filter is
@@ -1433,8 +1509,7 @@ impl<M: RawMutex> TelemetryPacketBuilder<M> {
                 vl_battery_v: 0.0,
                 air_temperature: 0.0,
 
-                pyro_main_continuity: false,
-                pyro_drogue_continuity: false,
+                pyro: [PyroChannelStatus::default(); PYRO_CHANNELS],
 
                 deployment_kf: None,
                 max_deployment_kf_altitude_agl: None,
@@ -1503,8 +1578,7 @@ impl<M: RawMutex> TelemetryPacketBuilder<M> {
                 state.gps_location.as_ref().map(|g| g.lat_lon).flatten(),
                 state.vl_battery_v,
                 state.air_temperature,
-                state.pyro_main_continuity,
-                state.pyro_drogue_continuity,
+                state.pyro,
                 state.deployment_kf,
                 state.max_deployment_kf_altitude_agl,
                 state.airbrakes_kf_tilt_deg,
@@ -1576,6 +1650,16 @@ mod tests {
 
     use super::*;
 
+    /// Every channel a different `(continuity, fired)` pair, so a swapped bit
+    /// shows.
+    const PYRO_PAIRS: [(bool, bool); PYRO_CHANNELS] = [
+        (true, true),
+        (true, false),
+        (false, true),
+        (false, false),
+        (true, false),
+    ];
+
     /// Every optional field present, so a round trip exercises the value side
     /// of each validity bit and each sentinel.
     fn packet_with_everything(num_of_fix_satellites: u8) -> TelemetryPacket {
@@ -1586,8 +1670,7 @@ mod tests {
             Some((45.5, -73.6)),
             7.4,
             25.5,
-            true,
-            true,
+            PYRO_PAIRS.map(|(continuity, fired)| PyroChannelStatus { continuity, fired }),
             Some(DeploymentKfState {
                 altitude_agl: 1234.0,
                 vertical_velocity: -150.0,
@@ -1654,8 +1737,10 @@ mod tests {
             None,
             7.4,
             25.5,
-            true,
-            true,
+            [PyroChannelStatus {
+                continuity: true,
+                fired: false,
+            }; PYRO_CHANNELS],
             None,
             None,
             None,
@@ -1695,8 +1780,8 @@ mod tests {
 
         let mut buffer = [0u8; 64];
         let len = packet.serialize(&mut buffer);
-        // 1 byte packet type + the 41 byte packed struct.
-        assert_eq!(len, 42);
+        // 1 byte packet type + the 42 byte packed struct.
+        assert_eq!(len, 43);
 
         let deserialized_packet = VLPDownlinkPacket::deserialize(&buffer[..len]).unwrap();
         assert_eq!(deserialized_packet, packet);
@@ -1717,6 +1802,14 @@ mod tests {
         assert_relative_eq!(lat, 45.5, epsilon = 0.0001);
         assert_relative_eq!(lon, -73.6, epsilon = 0.0001);
 
+        assert_eq!(
+            p.pyro_channels()
+                .map(|status| (status.continuity, status.fired)),
+            PYRO_PAIRS
+        );
+        assert!(p.pyro_main_continuity());
+        assert!(p.pyro_drogue_continuity());
+
         // Deployment-estimator fields, at their widened ranges.
         assert_relative_eq!(
             p.deployment_kf_altitude_agl().unwrap(),
@@ -4,6 +4,21 @@ pub enum CliRequest {
     List = 1,
     Clear = 2,
     Download = 3,
+    /// Reply: a response header with `block_count` 1, then the 512-byte config
+    /// block exactly as stored on the card — all zeros if there is none, which
+    /// `decode_config_block` rejects like any other bad block.
+    ReadConfig = 4,
+    /// The control transfer's data stage carries a whole 512-byte block from
+    /// `encode_config_block`. The firmware stores it only if it decodes and
+    /// validates, and either way replies as to [`CliRequest::ReadConfig`], so
+    /// the host learns what is on the card now rather than what it asked for.
+    WriteConfig = 5,
+    /// Reply: a response header whose `record_count` and flags come from the
+    /// newest superblock copy, then raw card blocks from block 0 — both
+    /// superblock copies, the claimed data blocks and up to
+    /// `RECOVERY_SCAN_BLOCKS` after them — with `block_count` saying how many.
+    /// The host finds the log's real extent itself (`recover_log_extent`).
+    DownloadRaw = 6,
 }
 
 impl From<u16> for CliRequest {
@@ -12,6 +27,9 @@ impl From<u16> for CliRequest {
             1 => CliRequest::List,
             2 => CliRequest::Clear,
             3 => CliRequest::Download,
+            4 => CliRequest::ReadConfig,
+            5 => CliRequest::WriteConfig,
+            6 => CliRequest::DownloadRaw,
             _ => CliRequest::Invalid,
         }
     }
@@ -51,6 +51,55 @@ pub enum ModeSelect {
     )]
     PlotFlightLog(PlotFlightLogArgs),
 
+    #[command(
+        about = "compare two flight-log CSVs aligned on liftoff: estimator error statistics and an overlay PNG"
+    )]
+    CompareFlightLogs(CompareFlightLogsArgs),
+
+    #[command(
+        about = "fly an OpenRocket trajectory many times with randomised motor, drag, wind and \
+                 sensors through the estimators: per-flight CSV, summary CSV and histogram PNG"
+    )]
+    Dispersion(DispersionArgs),
+
+    #[command(
+        about = "fly a thrust curve and an airframe closed loop through the estimators, the MPC \
+                 and the servo, and write the flight as a flight-log CSV plot-flight-log reads"
+    )]
+    Simulate(SimulateArgs),
+
+    #[command(
+        about = "re-fly a downloaded flight-log CSV's sensor data through this build's estimators \
+                 and MPC: a CSV of replayed outputs beside the flown ones, and where the \
+                 transitions and pyro commands differ"
+    )]
+    ReplayEstimators(ReplayEstimatorsArgs),
+
+    #[command(
+        about = "smooth a downloaded flight-log CSV's IMU, barometer and GPS forward and \
+                 backward: the CSV with smoothed altitude, speed and acceleration columns \
+                 plot-flight-log overlays"
+    )]
+    SmoothFlightLog(SmoothFlightLogArgs),
+
+    #[command(
+        about = "fit the cd table and the delivered thrust curve to a downloaded flight-log CSV: \
+                 a flight config with the fitted table, the thrust as a .eng file and the \
+                 stowed Cd as a mach,cd CSV, with how well each fits"
+    )]
+    FitAirframe(FitAirframeArgs),
+
+    #[clap(subcommand)]
+    #[command(about = "show, edit and validate the avionics config stored on a connected VLF5")]
+    Config(ConfigModeSelect),
+
+    #[clap(subcommand)]
+    #[command(
+        about = "check, show and compare flight config files: the whole airframe, profile and \
+                 controller setup the replay tools and the WASM harness load"
+    )]
+    FlightConfig(FlightConfigModeSelect),
+
     #[clap(subcommand)]
     #[command(about = "functions used for testing")]
     Testing(TestingModeSelect),
@@ -60,6 +109,13 @@ pub enum ModeSelect {
 pub struct DownloadFlightLogArgs {
     #[arg(default_value = "flight_log.csv")]
     pub output: String,
+
+    #[arg(
+        long,
+        help = "also read past the superblock's recorded end and keep any records \
+                that continue the log, e.g. after a brown-out at landing"
+    )]
+    pub recover: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -85,6 +141,162 @@ pub struct PlotFlightLogArgs {
     pub lead_in: f64,
 }
 
+#[derive(Parser, Debug)]
+pub struct CompareFlightLogsArgs {
+    #[arg(help = "reference log; errors are reported as b - a")]
+    pub a: String,
+    #[arg(help = "log to compare against it, e.g. a re-fly in simulation")]
+    pub b: String,
+    #[arg(
+        long,
+        help = "where to write the PNG (default: alongside the first CSV)"
+    )]
+    pub out_dir: Option<String>,
+    #[arg(long, help = "flight to use from the first log, 1-based")]
+    pub session_a: Option<usize>,
+    #[arg(long, help = "flight to use from the second log, 1-based")]
+    pub session_b: Option<usize>,
+    #[arg(
+        long,
+        help = "estimator column to compare, repeatable (default: all of them)"
+    )]
+    pub column: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = 5.0,
+        help = "seconds of pad time to show before liftoff; 0 starts exactly at T+0"
+    )]
+    pub lead_in: f64,
+}
+
+#[derive(Parser, Debug)]
+pub struct DispersionArgs {
+    #[arg(
+        default_value = "air-brakes-controller-core/test_data/osiris_o3400.csv",
+        help = "OpenRocket trajectory CSV, in the columns the core crate's test_data carries"
+    )]
+    pub truth: String,
+    #[arg(long, default_value_t = 1000, help = "flights to fly")]
+    pub flights: usize,
+    #[arg(
+        long,
+        help = "MPC target apogee, m AGL (default: 150 m under the undispersed apogee)"
+    )]
+    pub target_apogee_agl: Option<f32>,
+    #[arg(
+        long,
+        default_value_t = 0x0517_2026_1018_0001,
+        help = "run seed; the same seed and flight count fly the same flights"
+    )]
+    pub seed: u64,
+    #[arg(long, help = "threads to fly on (default: one per core)")]
+    pub threads: Option<usize>,
+    #[arg(
+        long,
+        help = "where to write the CSVs and the PNG (default: alongside the trajectory)"
+    )]
+    pub out_dir: Option<String>,
+    #[arg(
+        long,
+        default_value_t = 0.03,
+        help = "1-sigma thrust scatter, as a fraction"
+    )]
+    pub thrust_sigma: f32,
+    #[arg(
+        long,
+        default_value_t = 0.05,
+        help = "1-sigma drag-area scatter, as a fraction"
+    )]
+    pub cd_sigma: f32,
+    #[arg(
+        long,
+        default_value_t = 3.0,
+        help = "1-sigma extra wind along the drift, m/s"
+    )]
+    pub wind_sigma: f32,
+    #[arg(
+        long,
+        default_value_t = 0.3,
+        help = "1-sigma of the log of the sensor noise scale"
+    )]
+    pub noise_sigma: f32,
+    #[arg(
+        long,
+        default_value_t = 1.0,
+        help = "1-sigma gyro bias per axis, deg/s"
+    )]
+    pub gyro_bias_sigma: f32,
+    #[arg(
+        long,
+        help = "keep the IMU at the sensor model's fixed mounting instead of a random one"
+    )]
+    pub fixed_mounting: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigModeSelect {
+    #[command(about = "print the config stored on the VLF5 as TOML")]
+    Show(ConfigShowArgs),
+
+    #[command(about = "check a config TOML without a VLF5 attached")]
+    Validate(ConfigFileArgs),
+
+    #[command(about = "validate a config TOML, store it on the VLF5 and read it back")]
+    Write(ConfigFileArgs),
+
+    #[command(about = "open the VLF5's config in an editor and write it back when saved")]
+    Edit(ConfigEditArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigShowArgs {
+    #[arg(long, help = "write the TOML here instead of printing it")]
+    pub output: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigFileArgs {
+    #[arg(default_value = "avionics-config.toml")]
+    pub input: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigEditArgs {
+    #[arg(long, help = "editor to launch (default: $VISUAL, then $EDITOR)")]
+    pub editor: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FlightConfigModeSelect {
+    #[command(about = "check a flight config TOML for physical sanity")]
+    Check(FlightConfigFileArgs),
+
+    #[command(about = "print a flight config TOML as it converts (default: Osiris's)")]
+    Show(FlightConfigShowArgs),
+
+    #[command(about = "list what differs between two flight config TOMLs")]
+    Diff(FlightConfigDiffArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct FlightConfigFileArgs {
+    #[arg(default_value = "flight-config.toml")]
+    pub input: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct FlightConfigShowArgs {
+    pub input: Option<String>,
+    #[arg(long, help = "write the TOML here instead of printing it")]
+    pub output: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct FlightConfigDiffArgs {
+    pub a: String,
+    pub b: String,
+}
+
 #[derive(Parser, Debug)]
 pub struct ControlArgs {
     #[arg(long, help = "LoRa frequency in Hz (default: ground-station.toml)")]
@@ -215,3 +427,157 @@ impl Into<u8> for NodeTypeEnum {
         }
     }
 }
+
+#[derive(Parser, Debug)]
+pub struct ReplayEstimatorsArgs {
+    #[arg(default_value = "flight_log.csv")]
+    pub input: String,
+    #[arg(
+        long,
+        help = "avionics config TOML the flight was flown on, as `config show` writes it \
+                (default: the firmware's built-in config)"
+    )]
+    pub config: Option<String>,
+    #[arg(
+        long,
+        help = "flight config TOML to replay on instead of the built-in airframe, as \
+                `flight-config show` writes it; --config is laid over it"
+    )]
+    pub flight_config: Option<String>,
+    #[arg(
+        long,
+        help = "CSV to write (default: <input>_replayed.csv beside the input)"
+    )]
+    pub output: Option<String>,
+    #[arg(
+        long,
+        help = "flight to replay, 1-based, as numbered in the listing; \
+                skips the picker when the log holds several"
+    )]
+    pub session: Option<usize>,
+    #[arg(
+        long,
+        help = "fuse the log's GPS fixes into the deployment filter in descent, with the \
+                default quality gate, whether or not the flight did"
+    )]
+    pub gps_aiding: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct SmoothFlightLogArgs {
+    #[arg(default_value = "flight_log.csv")]
+    pub input: String,
+    #[arg(
+        long,
+        help = "CSV to write (default: <input>_smoothed.csv beside the input)"
+    )]
+    pub output: Option<String>,
+    #[arg(
+        long,
+        help = "flight to smooth, 1-based, as numbered in the listing; \
+                skips the picker when the log holds several"
+    )]
+    pub session: Option<usize>,
+    #[arg(
+        long,
+        help = "leave the log's GPS fixes out and smooth on the IMU and barometer alone"
+    )]
+    pub no_gps: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct FitAirframeArgs {
+    #[arg(default_value = "flight_log.csv")]
+    pub input: String,
+    #[arg(long, help = "weighed mass after the flight, motor casing in, kg")]
+    pub burnout_mass: f32,
+    #[arg(
+        long,
+        help = "flight config the fit starts from and writes back with the fitted table \
+                (default: Osiris's)"
+    )]
+    pub flight_config: Option<String>,
+    #[arg(
+        long,
+        default_value = "air-brakes-controller-core/test_data/cti_o3400.eng",
+        help = "RASP .eng file of the motor flown: its propellant mass, and the nominal \
+                curve the fitted one is compared with and written under"
+    )]
+    pub motor: String,
+    #[arg(
+        long,
+        help = "flight config to write (default: <input>_fit.toml beside the input)"
+    )]
+    pub output: Option<String>,
+    #[arg(
+        long,
+        help = "thrust curve to write (default: <input>_thrust.eng beside the input)"
+    )]
+    pub thrust_output: Option<String>,
+    #[arg(
+        long,
+        help = "stowed Cd to write (default: <input>_stowed_cd.csv beside the input)"
+    )]
+    pub cd_output: Option<String>,
+    #[arg(
+        long,
+        help = "flight to fit, 1-based, as numbered in the listing; \
+                skips the picker when the log holds several"
+    )]
+    pub session: Option<usize>,
+    #[arg(
+        long,
+        help = "leave the log's GPS fixes out of the trajectory the fit reads"
+    )]
+    pub no_gps: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct SimulateArgs {
+    #[arg(
+        default_value = "simulated_flight.csv",
+        help = "flight-log CSV to write; the events files go beside it"
+    )]
+    pub output: String,
+    #[arg(
+        long,
+        default_value = "air-brakes-controller-core/test_data/cti_o3400.eng",
+        help = "RASP .eng thrust curve"
+    )]
+    pub motor: String,
+    #[arg(
+        long,
+        default_value = "air-brakes-controller-core/test_data/osiris_cd.csv",
+        help = "stowed Cd against Mach, a `mach,cd` CSV"
+    )]
+    pub cd: String,
+    #[arg(
+        long,
+        help = "MPC target apogee, m AGL (default: none, and the brakes stay stowed)"
+    )]
+    pub target_apogee_agl: Option<f32>,
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        help = "wind blowing toward the east, m/s"
+    )]
+    pub wind_east: f32,
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        help = "wind blowing toward the north, m/s"
+    )]
+    pub wind_north: f32,
+    #[arg(
+        long,
+        default_value_t = 60.0,
+        help = "seconds armed on the pad before ignition"
+    )]
+    pub pad_time: f32,
+    #[arg(
+        long,
+        default_value_t = 0x0517_2026_1018_0039,
+        help = "sensor noise seed; the same seed flies the same flight"
+    )]
+    pub seed: u64,
+}
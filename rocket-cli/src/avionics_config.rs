@@ -0,0 +1,335 @@
+//! `rocket-cli config`: show, edit and validate the avionics config a VLF5
+//! keeps in the `VLFC` block at the end of its SD card.
+//!
+//! The card holds [`AvionicsConfig`] in the binary layout of
+//! [`encode_config_block`]; people edit it as TOML. [`ConfigFile`] is that
+//! TOML, and the only place the two meet: durations are seconds here and
+//! microseconds on the card, and the deployment profile is a tagged table
+//! rather than a kind byte.
+
+use std::path::Path;
+use std::process::Command;
+
+use air_brakes_controller_core::flight_config_file::{s_to_us, us_to_s};
+use anyhow::{Context as _, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use firmware_common_new::flight_storage::{
+    AirbrakesLockoutDurations, AvionicsConfig, BLOCK_SIZE, CONFIG_BLOCK_VERSION, DeploymentConfig,
+    MachLockoutDurations, config_block_version, decode_config_block, encode_config_block,
+};
+use firmware_common_new::vlp::lora_config::LoraConfig;
+
+use crate::args::ConfigModeSelect;
+use crate::usb_storage;
+
+/// [`AvionicsConfig`] as a person edits it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub target_apogee_agl: f32,
+    pub deployment: DeploymentFile,
+    pub mach_lockout: MachLockoutFile,
+    pub lora: LoraConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DeploymentFile {
+    Single {
+        minimum_deployment_altitude_agl: f32,
+        delay_s: f64,
+    },
+    Dual {
+        drogue_chute_minimum_altitude_agl: f32,
+        drogue_chute_delay_s: f64,
+        main_chute_altitude_agl: f32,
+        main_chute_delay_s: f64,
+    },
+}
+
+/// Both lockouts are optional: leave a key out for a subsonic airframe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachLockoutFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment_s: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub airbrakes: Option<AirbrakesLockoutFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AirbrakesLockoutFile {
+    pub earliest_subsonic_after_ignition_s: f64,
+    pub force_birth_after_ignition_s: f64,
+}
+
+impl From<&AvionicsConfig> for ConfigFile {
+    fn from(config: &AvionicsConfig) -> Self {
+        Self {
+            target_apogee_agl: config.target_apogee_agl,
+            deployment: match config.deployment {
+                DeploymentConfig::Single {
+                    minimum_deployment_altitude_agl,
+                    delay_us,
+                } => DeploymentFile::Single {
+                    minimum_deployment_altitude_agl,
+                    delay_s: us_to_s(delay_us),
+                },
+                DeploymentConfig::Dual {
+                    drogue_chute_minimum_altitude_agl,
+                    drogue_chute_delay_us,
+                    main_chute_altitude_agl,
+                    main_chute_delay_us,
+                } => DeploymentFile::Dual {
+                    drogue_chute_minimum_altitude_agl,
+                    drogue_chute_delay_s: us_to_s(drogue_chute_delay_us),
+                    main_chute_altitude_agl,
+                    main_chute_delay_s: us_to_s(main_chute_delay_us),
+                },
+            },
+            mach_lockout: MachLockoutFile {
+                deployment_s: config.mach_lockout.deployment_us.map(us_to_s),
+                airbrakes: config.mach_lockout.airbrakes.map(|a| AirbrakesLockoutFile {
+                    earliest_subsonic_after_ignition_s: us_to_s(
+                        a.earliest_subsonic_after_ignition_us,
+                    ),
+                    force_birth_after_ignition_s: us_to_s(a.force_birth_after_ignition_us),
+                }),
+            },
+            lora: config.lora,
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Convert to the card's form and run the firmware's own validation on
+    /// it, so `validate` here and the device's refusal can never disagree.
+    pub fn to_avionics_config(&self) -> Result<AvionicsConfig> {
+        let deployment = match self.deployment {
+            DeploymentFile::Single {
+                minimum_deployment_altitude_agl,
+                delay_s,
+            } => DeploymentConfig::Single {
+                minimum_deployment_altitude_agl,
+                delay_us: s_to_us("deployment.delay_s", delay_s)?,
+            },
+            DeploymentFile::Dual {
+                drogue_chute_minimum_altitude_agl,
+                drogue_chute_delay_s,
+                main_chute_altitude_agl,
+                main_chute_delay_s,
+            } => DeploymentConfig::Dual {
+                drogue_chute_minimum_altitude_agl,
+                drogue_chute_delay_us: s_to_us(
+                    "deployment.drogue_chute_delay_s",
+                    drogue_chute_delay_s,
+                )?,
+                main_chute_altitude_agl,
+                main_chute_delay_us: s_to_us("deployment.main_chute_delay_s", main_chute_delay_s)?,
+            },
+        };
+        let airbrakes = match &self.mach_lockout.airbrakes {
+            Some(a) => Some(AirbrakesLockoutDurations {
+                earliest_subsonic_after_ignition_us: s_to_us(
+                    "mach_lockout.airbrakes.earliest_subsonic_after_ignition_s",
+                    a.earliest_subsonic_after_ignition_s,
+                )?,
+                force_birth_after_ignition_us: s_to_us(
+                    "mach_lockout.airbrakes.force_birth_after_ignition_s",
+                    a.force_birth_after_ignition_s,
+                )?,
+            }),
+            None => None,
+        };
+        let config = AvionicsConfig {
+            target_apogee_agl: self.target_apogee_agl,
+            deployment,
+            mach_lockout: MachLockoutDurations {
+                deployment_us: self
+                    .mach_lockout
+                    .deployment_s
+                    .map(|s| s_to_us("mach_lockout.deployment_s", s))
+                    .transpose()?,
+                airbrakes,
+            },
+            lora: self.lora,
+        };
+        config
+            .validate()
+            .map_err(|e| anyhow!("invalid config: {e}"))?;
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+/// Decode what the device sent back, saying why when it does not decode.
+///
+/// A block that fails is not an error for `show`: the firmware flies on
+/// [`AvionicsConfig::default`] in exactly that case, so that is what is shown,
+/// with a note.
+fn describe_stored(block: &[u8; BLOCK_SIZE]) -> (AvionicsConfig, Option<String>) {
+    match (config_block_version(block), decode_config_block(block)) {
+        (Some(CONFIG_BLOCK_VERSION), Some(config)) => (config, None),
+        (Some(version), Some(config)) => (
+            config,
+            Some(format!(
+                "stored at config v{version}; fields it predates are shown at their defaults. \
+                 `config write` stores it as v{CONFIG_BLOCK_VERSION}."
+            )),
+        ),
+        (Some(version), None) if version > CONFIG_BLOCK_VERSION => (
+            AvionicsConfig::default(),
+            Some(format!(
+                "stored at config v{version}, newer than this rocket-cli (v{CONFIG_BLOCK_VERSION}); \
+                 update rocket-cli before editing. Defaults shown."
+            )),
+        ),
+        (Some(_), None) => (
+            AvionicsConfig::default(),
+            Some("stored config fails validation; the firmware flies on the defaults shown".into()),
+        ),
+        (None, _) => (
+            AvionicsConfig::default(),
+            Some("no config block on the card; the firmware flies on the defaults shown".into()),
+        ),
+    }
+}
+
+fn print_note(note: Option<String>) {
+    if let Some(note) = note {
+        eprintln!("note: {note}");
+    }
+}
+
+/// Send `config`, then check the device's read-back matches it field for
+/// field: the device replies with what it stored, not with what it was sent.
+fn write_to_device(config: &AvionicsConfig) -> Result<()> {
+    let readback = usb_storage::write_config(&encode_config_block(config))?;
+    match decode_config_block(&readback) {
+        Some(stored) if stored == *config => {
+            println!("Config written to the VLF5 and read back identical.");
+            Ok(())
+        }
+        _ => bail!(
+            "the VLF5 did not store the config (its read-back differs); the card holds \
+             whatever it held before"
+        ),
+    }
+}
+
+fn edit(editor: Option<String>) -> Result<()> {
+    let (current, note) = describe_stored(&usb_storage::read_config()?);
+    print_note(note);
+    let original = ConfigFile::from(&current);
+
+    let path = std::env::temp_dir().join("vlf5-avionics-config.toml");
+    std::fs::write(&path, original.to_toml()?)?;
+    let editor = editor
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let status = Command::new(&editor)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("launching editor `{editor}`"))?;
+    if !status.success() {
+        bail!("editor `{editor}` exited with {status}; nothing written");
+    }
+
+    let edited = ConfigFile::load(&path)?;
+    if edited == original {
+        println!("No changes; nothing written.");
+        return Ok(());
+    }
+    // Keep the file on a validation failure so the edit is not lost.
+    let config = edited
+        .to_avionics_config()
+        .with_context(|| format!("nothing written; your edit is kept at {}", path.display()))?;
+    write_to_device(&config)?;
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
+
+pub fn config_command(mode: ConfigModeSelect) -> Result<()> {
+    match mode {
+        ConfigModeSelect::Show(args) => {
+            let (config, note) = describe_stored(&usb_storage::read_config()?);
+            print_note(note);
+            let text = ConfigFile::from(&config).to_toml()?;
+            match args.output {
+                Some(output) => {
+                    std::fs::write(&output, text)?;
+                    println!("Wrote the VLF5 config to {output}");
+                }
+                None => print!("{text}"),
+            }
+            Ok(())
+        }
+        ConfigModeSelect::Validate(args) => {
+            ConfigFile::load(Path::new(&args.input))?.to_avionics_config()?;
+            println!("{} is a valid avionics config.", args.input);
+            Ok(())
+        }
+        ConfigModeSelect::Write(args) => {
+            let config = ConfigFile::load(Path::new(&args.input))?.to_avionics_config()?;
+            write_to_device(&config)
+        }
+        ConfigModeSelect::Edit(args) => edit(args.editor),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The file is what people edit, so a config has to survive the trip
+    /// through it exactly — including a lockout left out, which must come
+    /// back as no lockout rather than a zero-length one.
+    #[test]
+    fn config_survives_the_toml_round_trip() {
+        for config in [
+            AvionicsConfig::default(),
+            AvionicsConfig {
+                deployment: DeploymentConfig::Single {
+                    minimum_deployment_altitude_agl: 300.0,
+                    delay_us: 1_500_000,
+                },
+                mach_lockout: MachLockoutDurations {
+                    deployment_us: None,
+                    airbrakes: None,
+                },
+                ..AvionicsConfig::default()
+            },
+        ] {
+            let text = ConfigFile::from(&config).to_toml().unwrap();
+            let back: ConfigFile = toml::from_str(&text).unwrap();
+            assert_eq!(back.to_avionics_config().unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn negative_durations_and_firmware_rules_are_both_refused() {
+        let mut file = ConfigFile::from(&AvionicsConfig::default());
+        file.deployment = DeploymentFile::Single {
+            minimum_deployment_altitude_agl: 300.0,
+            delay_s: -1.0,
+        };
+        assert!(file.to_avionics_config().is_err());
+
+        // The firmware's rule, reached through the file.
+        let mut file = ConfigFile::from(&AvionicsConfig::default());
+        file.lora.sf = 13;
+        assert!(file.to_avionics_config().is_err());
+    }
+}
@@ -0,0 +1,416 @@
+//! `dispersion`: fly one OpenRocket trajectory a thousand times with what the
+//! config cannot know in advance drawn at random, and say how the estimators
+//! and the deployments did across all of them.
+//!
+//! The flying is `air_brakes_controller_core::sim::dispersion`; this is the
+//! front end. It flies the Osiris flight config from
+//! `air_brakes_controller_core::sim::osiris`, which is the config the core
+//! crate's own simulation tests hold to the nominal flight — so a config
+//! change is evaluated by making it there and running this before and after.
+//!
+//! Three files come out, named after the trajectory: every flight's draw and
+//! outcome as a CSV, each outcome's distribution as a second CSV, and a PNG
+//! of the four distributions that matter most with the false-call rates in
+//! its header.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use air_brakes_controller_core::sim::osiris::osiris_config;
+use air_brakes_controller_core::sim::{
+    Dispersion, DispersionRun, Distribution, FlightOutcome, Summary, Truth,
+};
+use anyhow::{Context, Result, bail};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+use crate::args::DispersionArgs;
+use crate::plot::figures::{HEIGHT, PlotErr, WIDTH};
+use crate::plot::theme;
+
+/// The default MPC target, below the undispersed apogee: far enough that
+/// most of a run's flights can reach it with the brakes, so the MPC is
+/// exercised rather than saturated.
+const DEFAULT_TARGET_BELOW_APOGEE_M: f32 = 150.0;
+
+/// Histogram bars per panel. Enough to show a shape at a thousand flights
+/// without most bars being empty at a hundred.
+const BINS: usize = 40;
+
+const HEADER_H: u32 = 150;
+
+type Area<'a> = DrawingArea<BitMapBackend<'a>, Shift>;
+
+pub fn dispersion(args: &DispersionArgs) -> Result<()> {
+    let input = Path::new(&args.truth);
+    if !input.is_file() {
+        bail!("{} is not a file", input.display());
+    }
+    if args.flights == 0 {
+        bail!("--flights must be at least 1");
+    }
+    // `Truth::load` is the test loader and panics on a malformed file;
+    // everything it could object to is its own assertion message.
+    let truth = Truth::load(&args.truth);
+    let config = osiris_config();
+    let (_, apogee_asl) = truth.apogee();
+    let target_apogee_asl = match args.target_apogee_agl {
+        Some(agl) => truth.pad_asl() + agl,
+        None => apogee_asl - DEFAULT_TARGET_BELOW_APOGEE_M,
+    };
+    let dispersion = Dispersion {
+        thrust_sigma: args.thrust_sigma,
+        cd_sigma: args.cd_sigma,
+        wind_sigma_mps: args.wind_sigma,
+        noise_sigma: args.noise_sigma,
+        gyro_bias_sigma_rad_s: args.gyro_bias_sigma.to_radians(),
+        random_mounting: !args.fixed_mounting,
+    };
+    let threads = args
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+
+    let source_name = input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| args.truth.clone());
+    println!(
+        "{source_name}: {} flight(s) on {threads} thread(s), undispersed apogee {:.0} m AGL, \
+         MPC target {:.0} m AGL",
+        args.flights,
+        apogee_asl - truth.pad_asl(),
+        target_apogee_asl - truth.pad_asl()
+    );
+
+    let run = DispersionRun {
+        truth: &truth,
+        config: &config,
+        target_apogee_asl,
+        dispersion: &dispersion,
+        seed: args.seed,
+    };
+    let flown = AtomicUsize::new(0);
+    let outcomes = run.run(args.flights, threads, |_| {
+        let n = flown.fetch_add(1, Ordering::Relaxed) + 1;
+        if n % 50 == 0 || n == args.flights {
+            eprintln!("  {n}/{} flown", args.flights);
+        }
+    });
+    let summary = Summary::of(&outcomes);
+
+    let paths = output_paths(input, args.out_dir.as_deref())?;
+    write_outcomes(&paths.flights, &outcomes)
+        .with_context(|| format!("writing {}", paths.flights.display()))?;
+    write_summary(&paths.summary, &summary)
+        .with_context(|| format!("writing {}", paths.summary.display()))?;
+    render(&paths.figure, &source_name, &outcomes, &summary)
+        .with_context(|| format!("writing {}", paths.figure.display()))?;
+
+    report(&summary);
+    println!(
+        "Wrote {}, {} and {} ({}×{})",
+        paths.flights.display(),
+        paths.summary.display(),
+        paths.figure.display(),
+        WIDTH,
+        HEIGHT
+    );
+    Ok(())
+}
+
+struct OutputPaths {
+    flights: PathBuf,
+    summary: PathBuf,
+    figure: PathBuf,
+}
+
+fn output_paths(input: &Path, out_dir: Option<&str>) -> Result<OutputPaths> {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "trajectory".to_string());
+    let dir = match out_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => input.parent().unwrap_or(Path::new(".")).to_path_buf(),
+    };
+    if !dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    Ok(OutputPaths {
+        flights: dir.join(format!("{stem}_dispersion.csv")),
+        summary: dir.join(format!("{stem}_dispersion_summary.csv")),
+        figure: dir.join(format!("{stem}_dispersion.png")),
+    })
+}
+
+fn write_outcomes(path: &Path, outcomes: &[FlightOutcome]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for outcome in outcomes {
+        writer.serialize(outcome)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// One row per outcome column, then the false-call rates as rows of their
+/// own with the rate under `mean`: one table a spreadsheet can diff between
+/// two runs.
+fn write_summary(path: &Path, summary: &Summary) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct Row<'a> {
+        metric: &'a str,
+        count: usize,
+        mean: f32,
+        std_dev: Option<f32>,
+        min: Option<f32>,
+        p05: Option<f32>,
+        p50: Option<f32>,
+        p95: Option<f32>,
+        max: Option<f32>,
+    }
+
+    let mut writer = csv::Writer::from_path(path)?;
+    for (metric, distribution) in distributions(summary) {
+        let Some(d) = distribution else { continue };
+        writer.serialize(Row {
+            metric,
+            count: d.count,
+            mean: d.mean,
+            std_dev: Some(d.std_dev),
+            min: Some(d.min),
+            p05: Some(d.p05),
+            p50: Some(d.p50),
+            p95: Some(d.p95),
+            max: Some(d.max),
+        })?;
+    }
+    for (metric, count, rate) in [
+        (
+            "false_ignition_rate",
+            summary.false_ignitions,
+            summary.false_ignition_rate(),
+        ),
+        (
+            "false_apogee_rate",
+            summary.false_apogees,
+            summary.false_apogee_rate(),
+        ),
+    ] {
+        writer.serialize(Row {
+            metric,
+            count,
+            mean: rate,
+            std_dev: None,
+            min: None,
+            p05: None,
+            p50: None,
+            p95: None,
+            max: None,
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn distributions(summary: &Summary) -> [(&'static str, Option<Distribution>); 5] {
+    [
+        ("apogee_error", summary.apogee_error),
+        ("apogee_time_error_s", summary.apogee_time_error_s),
+        ("apogee_call_delay_s", summary.apogee_call_delay_s),
+        ("drogue_altitude_error", summary.drogue_altitude_error),
+        ("main_altitude_error", summary.main_altitude_error),
+    ]
+}
+
+fn report(summary: &Summary) {
+    println!(
+        "  {:<24} {:>6} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "", "n", "mean", "std", "p05", "p50", "p95"
+    );
+    for (metric, distribution) in distributions(summary) {
+        match distribution {
+            Some(d) => println!(
+                "  {metric:<24} {:>6} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+                d.count, d.mean, d.std_dev, d.p05, d.p50, d.p95
+            ),
+            None => println!("  {metric:<24} {:>6}", 0),
+        }
+    }
+    println!(
+        "  false ignitions {}/{} ({:.2} %), false apogees {}/{} ({:.2} %), no drogue {}",
+        summary.false_ignitions,
+        summary.flights,
+        summary.false_ignition_rate() * 100.0,
+        summary.false_apogees,
+        summary.flights,
+        summary.false_apogee_rate() * 100.0,
+        summary.no_drogue
+    );
+}
+
+// ------------------------------------------------------------------- figure
+
+/// The four panels: the numbers a config change is usually made to move.
+struct Histogram {
+    title: &'static str,
+    unit: &'static str,
+    value: fn(&FlightOutcome) -> Option<f32>,
+    distribution: fn(&Summary) -> Option<Distribution>,
+}
+
+const HISTOGRAMS: [Histogram; 4] = [
+    Histogram {
+        title: "Airbrakes apogee error",
+        unit: "m",
+        value: |o| o.apogee_error,
+        distribution: |s| s.apogee_error,
+    },
+    Histogram {
+        title: "Apogee call after true apogee",
+        unit: "s",
+        value: |o| o.apogee_call_delay_s,
+        distribution: |s| s.apogee_call_delay_s,
+    },
+    Histogram {
+        title: "Drogue altitude below apogee",
+        unit: "m",
+        value: |o| o.drogue_altitude_error,
+        distribution: |s| s.drogue_altitude_error,
+    },
+    Histogram {
+        title: "Main altitude error",
+        unit: "m",
+        value: |o| o.main_altitude_error,
+        distribution: |s| s.main_altitude_error,
+    },
+];
+
+fn render(
+    path: &Path,
+    source_name: &str,
+    outcomes: &[FlightOutcome],
+    summary: &Summary,
+) -> Result<()> {
+    let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&theme::BG).plot()?;
+    let (header, body) = root.split_vertically(HEADER_H);
+    draw_header(&header, source_name, summary)?;
+
+    for (area, histogram) in body.split_evenly((2, 2)).iter().zip(&HISTOGRAMS) {
+        let values: Vec<f32> = outcomes.iter().filter_map(histogram.value).collect();
+        draw_histogram(area, histogram, &values, (histogram.distribution)(summary))?;
+    }
+
+    root.present().plot()?;
+    Ok(())
+}
+
+fn draw_header(area: &Area, source_name: &str, summary: &Summary) -> Result<()> {
+    let title = TextStyle::from((theme::FONT, theme::F_TITLE).into_font())
+        .color(&theme::TEXT)
+        .pos(Pos::new(HPos::Left, VPos::Center));
+    let sub = TextStyle::from((theme::FONT, theme::F_SUBTITLE).into_font())
+        .color(&theme::MUTED)
+        .pos(Pos::new(HPos::Left, VPos::Center));
+    area.draw_text(&format!("Dispersion · {source_name}"), &title, (56, 54))
+        .plot()?;
+
+    let rates = format!(
+        "{} flights · false ignition {:.2} % · false apogee {:.2} % · no drogue {}",
+        summary.flights,
+        summary.false_ignition_rate() * 100.0,
+        summary.false_apogee_rate() * 100.0,
+        summary.no_drogue
+    );
+    // A false call is the one outcome that is never acceptable, so it is
+    // the one thing on the figure allowed the alert colour.
+    let alarming = summary.false_ignitions + summary.false_apogees + summary.no_drogue > 0;
+    let sub = if alarming {
+        sub.color(&theme::ALERT)
+    } else {
+        sub
+    };
+    area.draw_text(&rates, &sub, (56, 112)).plot()?;
+    Ok(())
+}
+
+fn draw_histogram(
+    area: &Area,
+    histogram: &Histogram,
+    values: &[f32],
+    distribution: Option<Distribution>,
+) -> Result<()> {
+    area.fill(&theme::PANEL_BG).plot()?;
+    let caption = TextStyle::from((theme::FONT, theme::F_CAPTION).into_font()).color(&theme::TEXT);
+    let Some(d) = distribution else {
+        area.draw_text(
+            &format!("{}: no flight reported it", histogram.title),
+            &caption.color(&theme::MUTED),
+            (56, 40),
+        )
+        .plot()?;
+        return Ok(());
+    };
+
+    // A run where every flight agrees still gets a bar of some width.
+    let (lo, hi) = if d.max > d.min {
+        (d.min as f64, d.max as f64)
+    } else {
+        (d.min as f64 - 0.5, d.max as f64 + 0.5)
+    };
+    let width = (hi - lo) / BINS as f64;
+    let mut counts = [0usize; BINS];
+    for v in values {
+        counts[(((*v as f64 - lo) / width) as usize).min(BINS - 1)] += 1;
+    }
+    let top = counts.iter().copied().max().unwrap_or(1) as f64 * 1.1;
+
+    let mut chart = ChartBuilder::on(area)
+        .caption(
+            format!(
+                "{} ({}) · mean {:.2} · σ {:.2} · 5–95 % {:.2} … {:.2}",
+                histogram.title, histogram.unit, d.mean, d.std_dev, d.p05, d.p95
+            ),
+            caption,
+        )
+        .margin(40)
+        .x_label_area_size(70)
+        .y_label_area_size(110)
+        .build_cartesian_2d(lo..hi, 0.0..top)
+        .plot()?;
+    let tick = TextStyle::from((theme::FONT, theme::F_TICK).into_font()).color(&theme::MUTED);
+    chart
+        .configure_mesh()
+        .light_line_style(theme::GRID.mix(0.55))
+        .bold_line_style(theme::GRID)
+        .axis_style(theme::AXIS)
+        .label_style(tick.clone())
+        .x_desc(histogram.unit)
+        .y_desc("flights")
+        .axis_desc_style(tick)
+        .draw()
+        .plot()?;
+
+    chart
+        .draw_series(counts.iter().enumerate().map(|(i, &count)| {
+            let x = lo + i as f64 * width;
+            Rectangle::new(
+                [(x, 0.0), (x + width, count as f64)],
+                theme::CYAN.mix(0.8).filled(),
+            )
+        }))
+        .plot()?;
+    // The median solid and the 5th and 95th percentiles faint: the middle
+    // of the run and where its tails start.
+    for (x, alpha) in [(d.p05, 0.5), (d.p50, 1.0), (d.p95, 0.5)] {
+        let x = x as f64;
+        chart
+            .draw_series(LineSeries::new(
+                [(x, 0.0), (x, top)],
+                theme::AMBER.mix(alpha).stroke_width(4),
+            ))
+            .plot()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,352 @@
+//! `fit-airframe`: the cd table and the thrust curve a flight log says the
+//! rocket actually flew with, in the files that fly them.
+//!
+//! The fitting is the core crate's ([`air_brakes_controller_core::airframe_fit`]),
+//! run over the trajectory `smooth-flight-log` would write for the same
+//! session — the same rows, the same readings offered and held back. This
+//! is the front end: it reads the flaps' position off the log, takes the
+//! prior table from a flight config and the propellant from a `.eng` file,
+//! and writes three files beside the input:
+//!
+//! * `<input>_fit.toml`, the flight config with the fitted table in place
+//!   of the prior — what `replay-estimators --flight-config` and
+//!   `harness_init_from_config` load, and what `flight-config diff` against
+//!   the config it started from lists cell by cell;
+//! * `<input>_thrust.eng`, the delivered thrust curve under the motor's own
+//!   header, which `simulate --motor` burns;
+//! * `<input>_stowed_cd.csv`, the stowed cd against Mach as `simulate --cd`
+//!   reads it, over the whole coast and not just the table's Mach range.
+//!
+//! The burnout mass is asked for rather than taken from the config: it is
+//! the one number the fit cannot tell from the flight — a heavier rocket
+//! and a draggier one decelerate alike — and the config's is the last
+//! flight's.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use air_brakes_controller_core::airframe_fit::{AirframeFit, FitConfig, FitSample, fit};
+use air_brakes_controller_core::flight_config_file::{self, RocketParametersFile};
+use air_brakes_controller_core::sim::Motor;
+use air_brakes_controller_core::{CD_MACH_POINTS, RocketParameters};
+use anyhow::{Context as _, Result, anyhow, bail};
+
+use crate::args::FitAirframeArgs;
+use crate::flight_config::{self, print_issues};
+use crate::plot::choose;
+use crate::plot::log_csv::FlightLog;
+use crate::plot::session::find_sessions;
+use crate::replay_estimators::finite;
+use crate::smooth::{SmoothedSession, smooth_session};
+
+/// Column headings of the cd table, as [`RocketParameters::cd`] lays out
+/// each row.
+const EXTENSION_HEADINGS: [&str; 5] = ["0%", "25%", "50%", "75%", "100%"];
+
+/// `output`, or `<input><suffix>` beside the input.
+fn output_path(input: &Path, output: Option<&str>, suffix: &str) -> PathBuf {
+    if let Some(output) = output {
+        return PathBuf::from(output);
+    }
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "flight_log".to_string());
+    input.with_file_name(format!("{stem}{suffix}"))
+}
+
+/// The flaps' position on each of `rows`, and the column it came from.
+///
+/// Icarus's report is what the flaps did; the command is what they were
+/// told, a servo's lag ahead of it, and stands in only for a log with no
+/// report at all — mixing the two row by row would step the position every
+/// time a report went missing. Between readings the last one holds, and
+/// before the first the flaps are stowed.
+fn extensions(log: &FlightLog, rows: Range<usize>) -> (Vec<f32>, &'static str) {
+    let column = [
+        "air_brakes_actual_extension",
+        "air_brakes_commanded_extension",
+    ]
+    .into_iter()
+    .find_map(|name| {
+        log.column(name)
+            .filter(|c| rows.clone().any(|i| finite(c, i).is_some()))
+            .map(|c| (c, name))
+    });
+    let Some((column, name)) = column else {
+        return (vec![0.0; rows.len()], "none: stowed throughout");
+    };
+    let mut last = 0.0;
+    let extensions = rows
+        .map(|i| {
+            if let Some(e) = finite(column, i) {
+                last = e.clamp(0.0, 1.0);
+            }
+            last
+        })
+        .collect();
+    (extensions, name)
+}
+
+/// The stowed curve as a `mach,cd` CSV, `osiris_cd.csv`'s layout.
+fn stowed_cd_csv(points: &[(f32, f32)]) -> String {
+    let mut text = String::from("mach,cd\n");
+    for &(mach, cd) in points {
+        text.push_str(&format!("{mach:.2},{cd:.4}\n"));
+    }
+    text
+}
+
+/// `rocket`'s numbers in the order `harness_init` takes them, from
+/// `burnout_mass` to `reference_area`: each row's Mach, then its five cd.
+fn harness_init_arguments(rocket: &RocketParameters) -> String {
+    let mut values = vec![format!("{}", rocket.burnout_mass)];
+    for row in 0..CD_MACH_POINTS {
+        values.push(format!("{}", rocket.cd_mach[row]));
+        values.extend(rocket.cd[row].iter().map(|cd| format!("{cd:.4}")));
+    }
+    values.push(format!("{}", rocket.reference_area));
+    values.join(", ")
+}
+
+fn print_drag(fit: &AirframeFit) {
+    let drag = &fit.drag;
+    println!("Fitted cd (± 1-sigma; `prior` where no flight was under the cell):");
+    print!("{:>6}", "Mach");
+    for heading in EXTENSION_HEADINGS {
+        print!("{heading:>16}");
+    }
+    println!();
+    for (row, cells) in drag.cells.iter().enumerate() {
+        print!("{:>6.2}", drag.rocket.cd_mach[row]);
+        for cell in cells {
+            let text = if cell.from_flight() {
+                format!("{:.3} ±{:.3}", cell.cd, cell.sigma)
+            } else {
+                format!("{:.3} prior", cell.cd)
+            };
+            print!("{text:>16}");
+        }
+        println!();
+    }
+    println!(
+        "Coast: {} sample(s) over Mach {:.2}-{:.2}, {} above the table's top Mach left out. \
+         Residual RMS {:.2} m/s^2 with the fitted table, {:.2} with the prior.",
+        drag.samples,
+        drag.mach_range.0,
+        drag.mach_range.1,
+        drag.above_table,
+        drag.residual_rms,
+        drag.prior_residual_rms,
+    );
+    match (drag.stowed_cd.first(), drag.stowed_cd.last()) {
+        (Some(&(low, _)), Some(&(high, _))) => println!(
+            "Stowed cd: {} Mach bin(s), {low:.2}-{high:.2}.",
+            drag.stowed_cd.len()
+        ),
+        _ => println!("Stowed cd: the flaps were never stowed long enough to measure it."),
+    }
+}
+
+fn print_thrust(fit: &AirframeFit, motor: &Motor, first_motion_s: f64) {
+    let thrust = &fit.thrust;
+    let nominal = motor.total_impulse();
+    let nominal_peak = motor.thrust.iter().map(|&(_, f)| f).fold(0.0, f32::max);
+    println!(
+        "Thrust: first motion at T{first_motion_s:+.3} s, burnout {:.2} s later against the \
+         nominal {:.2}; {:.0} N s ({:+.1}% on {}'s {nominal:.0}), peak {:.0} N against {nominal_peak:.0}.",
+        thrust.burn_time,
+        motor.burn_time(),
+        thrust.total_impulse,
+        (thrust.total_impulse / nominal - 1.0) * 100.0,
+        motor.name,
+        thrust.peak,
+    );
+    if thrust.missing > 0 {
+        println!(
+            "{} burn sample(s) had no accelerometer reading — clipped, mostly — and the bins \
+             they fell in read low.",
+            thrust.missing
+        );
+    }
+}
+
+pub fn fit_airframe(args: &FitAirframeArgs) -> Result<()> {
+    if !args.burnout_mass.is_finite() || args.burnout_mass <= 0.0 {
+        bail!("--burnout-mass must be a positive mass in kg");
+    }
+    let input = Path::new(&args.input);
+    let log = FlightLog::load(input)?;
+    let source_name = input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| args.input.clone());
+    let mut file = match &args.flight_config {
+        Some(path) => {
+            let file = flight_config::read(Path::new(path))?;
+            file.to_flight_config()
+                .map_err(|e| anyhow!("{path}: {e}"))?;
+            file
+        }
+        None => flight_config::reference(),
+    };
+    let motor = Motor::load(&args.motor).map_err(|e| anyhow!("{e}"))?;
+
+    let sessions = find_sessions(&log, 0.0);
+    let Some(index) = choose(&sessions, &log, &source_name, args.session, "--session")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let session = &sessions[index];
+    let SmoothedSession {
+        samples, smoothed, ..
+    } = smooth_session(&log, session, args.no_gps, &source_name)?;
+    let (extension, extension_source) =
+        extensions(&log, session.start..session.start + samples.len());
+    let fit_samples: Vec<FitSample> = samples
+        .iter()
+        .zip(&smoothed.states)
+        .zip(extension)
+        .map(|((sample, state), extension)| FitSample {
+            timestamp_us: sample.timestamp_us,
+            state: *state,
+            extension,
+        })
+        .collect();
+
+    let prior = RocketParameters::from(&file.airbrakes.rocket);
+    if prior.burnout_mass != args.burnout_mass {
+        println!(
+            "Burnout mass {} kg in place of the config's {} kg.",
+            args.burnout_mass, prior.burnout_mass
+        );
+    }
+    let config = FitConfig::new(
+        RocketParameters {
+            burnout_mass: args.burnout_mass,
+            ..prior
+        },
+        motor.propellant_kg,
+        file.atmosphere.to_atmosphere(),
+    );
+    let fit = fit(&fit_samples, session.flight_start - session.start, &config)
+        .map_err(|e| anyhow!("{source_name}: {e}"))?;
+
+    println!("Flap position from {extension_source}.");
+    print_drag(&fit);
+    let liftoff_us = samples[session.flight_start - session.start].timestamp_us;
+    let first_motion_s = (samples[fit.first_motion].timestamp_us as f64 - liftoff_us as f64) * 1e-6;
+    print_thrust(&fit, &motor, first_motion_s);
+    println!(
+        "harness_init, burnout_mass through reference_area: {}",
+        harness_init_arguments(&fit.drag.rocket)
+    );
+
+    let config_path = output_path(input, args.output.as_deref(), "_fit.toml");
+    let thrust_path = output_path(input, args.thrust_output.as_deref(), "_thrust.eng");
+    let cd_path = output_path(input, args.cd_output.as_deref(), "_stowed_cd.csv");
+
+    file.airbrakes.rocket = RocketParametersFile::from(&fit.drag.rocket);
+    let flight = file
+        .to_flight_config()
+        .map_err(|e| anyhow!("the fitted config: {e}"))?;
+    let check = flight_config_file::check(&flight, &config.atmosphere);
+    print_issues(&config_path.display().to_string(), &check);
+    std::fs::write(&config_path, flight_config::to_toml(&file)?)
+        .with_context(|| format!("writing {}", config_path.display()))?;
+    println!("Wrote {}", config_path.display());
+
+    let fitted_motor = motor
+        .with_thrust(&fit.thrust.curve)
+        .map_err(|e| anyhow!("the fitted thrust curve: {e}"))?;
+    let eng = format!(
+        "; {} as delivered on {source_name}, session {}: fit-airframe\n{}",
+        motor.name,
+        index + 1,
+        fitted_motor.to_eng()
+    );
+    std::fs::write(&thrust_path, eng)
+        .with_context(|| format!("writing {}", thrust_path.display()))?;
+    println!("Wrote {}", thrust_path.display());
+
+    if !fit.drag.stowed_cd.is_empty() {
+        std::fs::write(&cd_path, stowed_cd_csv(&fit.drag.stowed_cd))
+            .with_context(|| format!("writing {}", cd_path.display()))?;
+        println!("Wrote {}", cd_path.display());
+    }
+
+    if !check.passed() {
+        bail!(
+            "{} fails the flight config check: look at the fit before flying it",
+            config_path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::log_csv::test_support::log_from_csv;
+
+    #[test]
+    fn the_outputs_go_beside_the_input_unless_named() {
+        let input = Path::new("logs/flight.csv");
+        assert_eq!(
+            output_path(input, None, "_fit.toml"),
+            PathBuf::from("logs/flight_fit.toml")
+        );
+        assert_eq!(
+            output_path(input, None, "_thrust.eng"),
+            PathBuf::from("logs/flight_thrust.eng")
+        );
+        assert_eq!(
+            output_path(input, Some("cd.csv"), "_stowed_cd.csv"),
+            PathBuf::from("cd.csv")
+        );
+    }
+
+    /// The report wins over the command wherever there is one, holds across
+    /// a gap, and a log with neither flies stowed.
+    #[test]
+    fn the_flap_position_is_icaruss_report_held_across_gaps() {
+        let log = log_from_csv(
+            "fit_airframe_extensions",
+            "timestamp_us,air_brakes_commanded_extension,air_brakes_actual_extension\n\
+             0,0.5,\n\
+             10,0.5,0.25\n\
+             20,1.0,\n\
+             30,1.0,0.75\n",
+        );
+        let (extensions, source) = extensions(&log, 0..4);
+        assert_eq!(source, "air_brakes_actual_extension");
+        assert_eq!(extensions, [0.0, 0.25, 0.25, 0.75]);
+        let (commanded, source) = extensions(&log, 0..1);
+        assert_eq!(source, "air_brakes_commanded_extension");
+        assert_eq!(commanded, [0.5]);
+
+        let bare = log_from_csv("fit_airframe_no_extension", "timestamp_us\n0\n10\n");
+        assert_eq!(extensions(&bare, 0..2).0, [0.0, 0.0]);
+    }
+
+    /// Row by row as the harness takes them: the mass, each Mach followed by
+    /// its five cd, and the area last.
+    #[test]
+    fn the_harness_arguments_run_row_by_row() {
+        let rocket = RocketParameters {
+            burnout_mass: 20.0,
+            cd_mach: [0.4, 0.6, 0.7, 0.8],
+            cd: [[0.5; 5]; CD_MACH_POINTS],
+            reference_area: 0.02,
+        };
+        let arguments = harness_init_arguments(&rocket);
+        let values: Vec<&str> = arguments.split(", ").collect();
+        assert_eq!(values.len(), 2 + CD_MACH_POINTS * 6);
+        assert_eq!(values[0], "20");
+        assert_eq!(values[1], "0.4");
+        assert_eq!(values[2], "0.5000");
+        assert_eq!(values[7], "0.6");
+        assert_eq!(values[values.len() - 1], "0.02");
+        assert_eq!(stowed_cd_csv(&[(0.3, 0.5661)]), "mach,cd\n0.30,0.5661\n");
+    }
+}
@@ -0,0 +1,258 @@
+//! `rocket-cli flight-config`: check, show and compare flight config files.
+//!
+//! A flight config file is [`FlightConfigFile`] as TOML — the whole
+//! [`FlightConfig`], airframe and all, where the avionics config `rocket-cli
+//! config` edits is only the part of it a card can carry. The format, its
+//! version and the physical checks all live in the core crate, so this is
+//! the front end: the same file goes to `replay-estimators --flight-config`
+//! and to the WASM harness's `harness_init_from_config` unchanged.
+
+use std::path::Path;
+
+use air_brakes_controller_core::flight_config_file::{self, ConfigCheck, Severity};
+use air_brakes_controller_core::sim::osiris::osiris_config;
+use air_brakes_controller_core::{FlightConfig, FlightConfigFile};
+use anyhow::{Context as _, Result, anyhow, bail};
+
+use crate::args::FlightConfigModeSelect;
+
+/// Osiris's flight config as a file: the template `show` prints without an
+/// input.
+pub fn reference() -> FlightConfigFile {
+    FlightConfigFile::new(
+        &osiris_config(),
+        flight_config_file::AtmosphereFile::Standard,
+    )
+}
+
+pub fn read(path: &Path) -> Result<FlightConfigFile> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+}
+
+pub fn to_toml(file: &FlightConfigFile) -> Result<String> {
+    Ok(toml::to_string_pretty(file)?)
+}
+
+pub(crate) fn print_issues(source: &str, check: &ConfigCheck) {
+    for issue in &check.issues {
+        let severity = match issue.severity() {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        eprintln!("{severity}: {source}: {issue}");
+    }
+}
+
+/// Read, convert and check `path`: the one way a tool should get a
+/// [`FlightConfig`] out of a file. Warnings are printed and flown; errors
+/// refuse the file.
+pub fn load(path: &Path) -> Result<FlightConfig> {
+    let source = path.display().to_string();
+    let file = read(path)?;
+    let config = file
+        .to_flight_config()
+        .map_err(|e| anyhow!("{source}: {e}"))?;
+    let check = flight_config_file::check(&config, &file.atmosphere.to_atmosphere());
+    print_issues(&source, &check);
+    if !check.passed() {
+        bail!("{source} fails the flight config check");
+    }
+    Ok(config)
+}
+
+/// Every key whose value differs between `a` and `b`, as `path: a -> b`,
+/// with a key only one side has shown against `-`. Arrays of the same
+/// length are walked entry by entry so a cd table names the cell.
+fn differences(
+    path: &str,
+    a: Option<&toml::Value>,
+    b: Option<&toml::Value>,
+    out: &mut Vec<String>,
+) {
+    match (a, b) {
+        (Some(toml::Value::Table(a)), Some(toml::Value::Table(b))) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                differences(&path, a.get(key), b.get(key), out);
+            }
+        }
+        (Some(toml::Value::Array(a)), Some(toml::Value::Array(b))) if a.len() == b.len() => {
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                differences(&format!("{path}[{i}]"), Some(a), Some(b), out);
+            }
+        }
+        (a, b) if a == b => {}
+        (a, b) => {
+            let show = |v: Option<&toml::Value>| v.map_or("-".to_string(), |v| v.to_string());
+            out.push(format!("{path}: {} -> {}", show(a), show(b)));
+        }
+    }
+}
+
+/// [`differences`] between two files as they convert: defaults filled in
+/// and formatting gone, so only what would fly differently is listed.
+pub fn diff(a: &FlightConfigFile, b: &FlightConfigFile) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    differences(
+        "",
+        Some(&toml::Value::try_from(a)?),
+        Some(&toml::Value::try_from(b)?),
+        &mut out,
+    );
+    Ok(out)
+}
+
+pub fn flight_config_command(mode: FlightConfigModeSelect) -> Result<()> {
+    match mode {
+        FlightConfigModeSelect::Check(args) => {
+            let path = Path::new(&args.input);
+            let file = read(path)?;
+            let config = file
+                .to_flight_config()
+                .map_err(|e| anyhow!("{}: {e}", args.input))?;
+            let check = flight_config_file::check(&config, &file.atmosphere.to_atmosphere());
+            print_issues(&args.input, &check);
+            match check.earliest_apogee_s {
+                Some(s) => println!("Earliest apogee the config implies: {s:.1} s after ignition."),
+                None => println!("No earliest apogee: the config has no airbrakes lockout."),
+            }
+            if !check.passed() {
+                bail!("{} fails the flight config check", args.input);
+            }
+            println!("{} is a sane flight config.", args.input);
+            Ok(())
+        }
+        FlightConfigModeSelect::Show(args) => {
+            let file = match &args.input {
+                Some(input) => {
+                    let file = read(Path::new(input))?;
+                    file.to_flight_config()
+                        .map_err(|e| anyhow!("{input}: {e}"))?;
+                    file
+                }
+                None => reference(),
+            };
+            let text = to_toml(&file)?;
+            match args.output {
+                Some(output) => {
+                    std::fs::write(&output, text)?;
+                    println!("Wrote the flight config to {output}");
+                }
+                None => print!("{text}"),
+            }
+            Ok(())
+        }
+        FlightConfigModeSelect::Diff(args) => {
+            let differences = diff(&read(Path::new(&args.a))?, &read(Path::new(&args.b))?)?;
+            if differences.is_empty() {
+                println!("{} and {} fly the same config.", args.a, args.b);
+            }
+            for line in differences {
+                println!("{line}");
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use air_brakes_controller_core::flight_config_file::{
+        AtmosphereFile, DeploymentProfileFile, FLIGHT_CONFIG_FILE_VERSION,
+    };
+
+    use super::*;
+
+    /// What `show` prints is what `check` and the harness read back.
+    #[test]
+    fn the_reference_survives_the_toml_round_trip() {
+        let text = to_toml(&reference()).unwrap();
+        let back: FlightConfigFile = toml::from_str(&text).unwrap();
+        assert_eq!(back, reference());
+        assert!(
+            flight_config_file::check(
+                &back.to_flight_config().unwrap(),
+                &back.atmosphere.to_atmosphere()
+            )
+            .passed()
+        );
+    }
+
+    /// The keys with defaults can be left out, and a key this version does
+    /// not know is refused rather than ignored.
+    #[test]
+    fn a_minimal_file_fills_in_defaults_and_unknown_keys_are_refused() {
+        let minimal = format!(
+            r#"
+version = {FLIGHT_CONFIG_FILE_VERSION}
+ignition_detection_acc_threshold = 39.24
+atmosphere = {{ kind = "standard" }}
+
+[profile.deployment]
+kind = "single"
+minimum_deployment_altitude_agl = 300.0
+delay_s = 1.0
+
+[airbrakes]
+max_open_mach = 0.8
+
+[airbrakes.rocket]
+burnout_mass = 18.0
+reference_area = 0.01
+cd_mach = [0.3, 0.5, 0.7, 0.9]
+cd = [
+    [0.6, 0.7, 0.8, 0.9, 1.0],
+    [0.6, 0.7, 0.8, 0.9, 1.0],
+    [0.6, 0.7, 0.8, 0.9, 1.0],
+    [0.6, 0.7, 0.8, 0.9, 1.0],
+]
+"#
+        );
+        let file: FlightConfigFile = toml::from_str(&minimal).unwrap();
+        assert_eq!(file.atmosphere, AtmosphereFile::Standard);
+        let config = file.to_flight_config().unwrap();
+        assert!(config.profile.pyro_events.is_empty());
+        assert!(config.staging.is_none());
+
+        let unknown = minimal.replace("delay_s = 1.0", "delay_s = 1.0\ndelay_ms = 1000");
+        assert!(toml::from_str::<FlightConfigFile>(&unknown).is_err());
+    }
+
+    #[test]
+    fn diff_names_the_keys_and_cells_that_differ() {
+        let a = reference();
+        let mut b = reference();
+        b.airbrakes.rocket.cd[1][4] = 1.2;
+        b.profile.deployment = DeploymentProfileFile::Single {
+            minimum_deployment_altitude_agl: 300.0,
+            delay_s: 1.0,
+        };
+        b.profile.mach_lockout_s = None;
+        let lines = diff(&a, &b).unwrap();
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("airbrakes.rocket.cd[1][4]: "))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|l| l == "profile.deployment.kind: \"dual\" -> \"single\"")
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|l| l == "profile.mach_lockout_s: 26.0 -> -")
+        );
+        assert!(diff(&a, &a).unwrap().is_empty());
+    }
+}
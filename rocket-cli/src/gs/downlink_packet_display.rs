@@ -13,7 +13,9 @@ use firmware_common_new::{
         },
         messages::{amp_status::PowerOutputStatus, node_status::NodeMode},
     },
-    vlp::packets::{EpmBattV, VLPDownlinkPacket, self_test_result::NodeStatus},
+    vlp::packets::{
+        EpmBattV, VLPDownlinkPacket, fire_pyro::PyroSelect, self_test_result::NodeStatus,
+    },
 };
 use lora_phy::mod_params::PacketStatus;
 use pad::PadStr as _;
@@ -138,6 +140,17 @@ impl DownlinkPacketDisplay {
     /// simply silent. Rendering that as eight red `F`s would put what looks
     /// like eight separate hardware failures on the screen of an operator
     /// whose actual problem is one missing CAN node.
+    /// Column heading for a pyro channel in the "Pyro" section.
+    fn pyro_label(channel: PyroSelect) -> &'static str {
+        match channel {
+            PyroSelect::PyroMain => "main",
+            PyroSelect::PyroDrogue => "drogue",
+            PyroSelect::PyroSeparation => "separation",
+            PyroSelect::PyroBackupDrogue => "backup drogue",
+            PyroSelect::PyroBackupMain => "backup main",
+        }
+    }
+
     fn format_bool_reported(reported: bool, value: bool) -> StyledString {
         if reported {
             Self::format_bool(value)
@@ -530,18 +543,38 @@ impl DownlinkPacketDisplay {
                                 false,
                                 format!("{:.1}C", p.air_temperature()).into(),
                             ),
-                            (
-                                "main continuity",
-                                true,
-                                Self::format_bool(p.pyro_main_continuity()),
-                            ),
-                            (
-                                "drogue continuity",
-                                true,
-                                Self::format_bool(p.pyro_drogue_continuity()),
-                            ),
                         ]],
                     ),
+                    // One column per channel, continuity above fired. A
+                    // channel the flight profile does not use reads F/F and
+                    // that is fine; a used one should be T/F on the rail and
+                    // T/T once its event has passed. Continuity going F after
+                    // the charge fires is normal — the bridge wire is gone.
+                    Section::new(
+                        "Pyro",
+                        vec![
+                            PyroSelect::ALL
+                                .map(|channel| {
+                                    (
+                                        Self::pyro_label(channel),
+                                        true,
+                                        Self::format_bool(
+                                            p.pyro_channels()[channel.index()].continuity,
+                                        ),
+                                    )
+                                })
+                                .to_vec(),
+                            PyroSelect::ALL
+                                .map(|channel| {
+                                    (
+                                        "fired",
+                                        true,
+                                        Self::format_bool(p.pyro_channels()[channel.index()].fired),
+                                    )
+                                })
+                                .to_vec(),
+                        ],
+                    ),
                     Section::new(
                         "AMP",
                         vec![
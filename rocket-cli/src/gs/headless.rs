@@ -142,16 +142,21 @@ fn parse_command(line: &str) -> Result<Command> {
             )
         }
         "fire-pyro" | "fire_pyro" => {
-            let p = *rest
-                .first()
-                .ok_or_else(|| anyhow!("fire-pyro requires: main|drogue"))?;
+            let p = *rest.first().ok_or_else(|| {
+                anyhow!("fire-pyro requires: main|drogue|separation|backup-drogue|backup-main")
+            })?;
             let pyro = match p {
                 "main" => PyroSelect::PyroMain,
                 "drogue" => PyroSelect::PyroDrogue,
-                other => bail!("unknown pyro '{other}' (expected main|drogue)"),
+                "separation" => PyroSelect::PyroSeparation,
+                "backup-drogue" => PyroSelect::PyroBackupDrogue,
+                "backup-main" => PyroSelect::PyroBackupMain,
+                other => bail!(
+                    "unknown pyro '{other}' (expected main|drogue|separation|backup-drogue|backup-main)"
+                ),
             };
             uplink(
-                VLPUplinkPacket::FirePyro(FirePyroPacket { pyro }),
+                VLPUplinkPacket::FirePyro(FirePyroPacket::new(pyro)),
                 format!("fire-pyro {p}"),
             )
         }
@@ -234,6 +239,14 @@ fn downlink_json(packet: &VLPDownlinkPacket, status: &PacketStatus) -> Value {
             "airbrakes_actual_pct": p.air_brakes_actual_extension_percentage(),
             "pyro_main_continuity": p.pyro_main_continuity(),
             "pyro_drogue_continuity": p.pyro_drogue_continuity(),
+            "pyro_channels": PyroSelect::ALL.map(|channel| {
+                let status = p.pyro_channels()[channel.index()];
+                json!({
+                    "channel": format!("{channel:?}"),
+                    "continuity": status.continuity,
+                    "fired": status.fired,
+                })
+            }),
             "vl_battery_v": p.vl_battery_v(),
             "shared_battery_v": p.shared_battery_v(),
             "air_temperature": p.air_temperature(),
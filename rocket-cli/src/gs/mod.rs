@@ -519,18 +519,27 @@ pub fn tui_task(
                                     .child(create_simple_packet_button(
                                         "Fire Main Pyro",
                                         "Manually fire main pyro?",
-                                        FirePyroPacket {
-                                            pyro: PyroSelect::PyroMain,
-                                        }
-                                        .into(),
+                                        FirePyroPacket::new(PyroSelect::PyroMain).into(),
                                     ))
                                     .child(create_simple_packet_button(
                                         "Fire Drogue Pyro",
                                         "Manually fire drogue pyro?",
-                                        FirePyroPacket {
-                                            pyro: PyroSelect::PyroDrogue,
-                                        }
-                                        .into(),
+                                        FirePyroPacket::new(PyroSelect::PyroDrogue).into(),
+                                    ))
+                                    .child(create_simple_packet_button(
+                                        "Fire Separation Pyro",
+                                        "Manually fire separation pyro?",
+                                        FirePyroPacket::new(PyroSelect::PyroSeparation).into(),
+                                    ))
+                                    .child(create_simple_packet_button(
+                                        "Fire Backup Drogue Pyro",
+                                        "Manually fire backup drogue pyro?",
+                                        FirePyroPacket::new(PyroSelect::PyroBackupDrogue).into(),
+                                    ))
+                                    .child(create_simple_packet_button(
+                                        "Fire Backup Main Pyro",
+                                        "Manually fire backup main pyro?",
+                                        FirePyroPacket::new(PyroSelect::PyroBackupMain).into(),
                                     )),
                             )
                             .visible(true)
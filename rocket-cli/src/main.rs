@@ -1,12 +1,19 @@
 mod args;
+mod avionics_config;
 mod connection_method;
+mod dispersion;
 mod elf_locator;
+mod fit_airframe;
+mod flight_config;
 mod gen_key;
 mod gs;
 mod monitor;
 mod plot;
 mod probe;
+mod replay_estimators;
 mod serial_can;
+mod simulate;
+mod smooth;
 mod testing;
 mod usb_storage;
 
@@ -91,9 +98,17 @@ async fn main() -> Result<()> {
             send_fake_vlp_telemetry(args).await
         }
         ModeSelect::ListFlightLog => usb_storage::list_files(),
-        ModeSelect::DownloadFlightLog(args) => usb_storage::download_file(&args.output),
+        ModeSelect::DownloadFlightLog(args) => usb_storage::download_file(&args.output, args.recover),
         ModeSelect::ClearFlightLog => usb_storage::clear_storage(),
         ModeSelect::PlotFlightLog(args) => plot::plot_flight_log(&args),
+        ModeSelect::CompareFlightLogs(args) => plot::compare::compare_flight_logs(&args),
+        ModeSelect::Dispersion(args) => dispersion::dispersion(&args),
+        ModeSelect::Simulate(args) => simulate::simulate(&args),
+        ModeSelect::ReplayEstimators(args) => replay_estimators::replay_estimators(&args),
+        ModeSelect::SmoothFlightLog(args) => smooth::smooth_flight_log(&args),
+        ModeSelect::FitAirframe(args) => fit_airframe::fit_airframe(&args),
+        ModeSelect::Config(mode) => avionics_config::config_command(mode),
+        ModeSelect::FlightConfig(mode) => flight_config::flight_config_command(mode),
     }
 }
 
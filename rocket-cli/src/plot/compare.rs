@@ -0,0 +1,468 @@
+//! `compare-flight-logs`: put two logs of the same flight side by side.
+//!
+//! The usual pair is a real flight and a re-fly of its sensor data through a
+//! changed `FlightConfig` or a newer firmware, so the question is never "are
+//! these the same" — they will not be, that was the point of the change — but
+//! "where do the estimators part company, and by how much".
+//!
+//! The two logs are aligned on liftoff, the one instant both of them detected
+//! from their own data. Boot time is useless for this: a HIL re-fly boots
+//! whenever the bench did, and a flight's pad wait is as long as the range made
+//! it. Every error below is `b - a` at the same T+, with `b` read by linear
+//! interpolation onto `a`'s rows, so `a` is the reference and the order of the
+//! two arguments matters.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::args::CompareFlightLogsArgs;
+use crate::plot::choose;
+use crate::plot::figures::{self, Renderer, Window};
+use crate::plot::log_csv::FlightLog;
+use crate::plot::session::{Session, WindowSource, find_sessions};
+
+/// One estimator output worth comparing, and how far apart the two logs may
+/// drift on it before that counts as a divergence.
+///
+/// The thresholds are in the display unit and sized to the estimator's own
+/// noise rather than to what would matter to the flight: the tool is for
+/// finding where a change started to bite, and a threshold at "this would have
+/// moved a deployment" only fires long after the cause.
+#[derive(Debug)]
+pub struct Compared {
+    pub column: &'static str,
+    pub title: &'static str,
+    pub unit: &'static str,
+    /// Applied before anything is measured, so the statistics are in `unit`.
+    pub scale: f32,
+    pub threshold: f64,
+}
+
+pub static ESTIMATOR_OUTPUTS: [Compared; 7] = [
+    Compared {
+        column: "deployment_kf_altitude_asl",
+        title: "Deployment KF altitude",
+        unit: "m",
+        scale: 1.0,
+        threshold: 5.0,
+    },
+    Compared {
+        column: "deployment_kf_vertical_velocity",
+        title: "Deployment KF vertical speed",
+        unit: "m/s",
+        scale: 1.0,
+        threshold: 2.0,
+    },
+    Compared {
+        column: "airbrakes_kf_altitude_asl",
+        title: "Airbrakes KF altitude",
+        unit: "m",
+        scale: 1.0,
+        threshold: 5.0,
+    },
+    Compared {
+        column: "airbrakes_kf_vertical_velocity",
+        title: "Airbrakes KF vertical speed",
+        unit: "m/s",
+        scale: 1.0,
+        threshold: 2.0,
+    },
+    Compared {
+        column: "airbrakes_kf_tilt_deg",
+        title: "Airbrakes KF tilt",
+        unit: "°",
+        scale: 1.0,
+        threshold: 2.0,
+    },
+    Compared {
+        column: "mpc_predicted_apogee_asl",
+        title: "MPC predicted apogee",
+        unit: "m",
+        scale: 1.0,
+        threshold: 10.0,
+    },
+    Compared {
+        column: "air_brakes_commanded_extension",
+        title: "Air brakes commanded extension",
+        unit: "%",
+        scale: 100.0,
+        threshold: 5.0,
+    },
+];
+
+/// How long an error has to stay over threshold before it is a divergence.
+///
+/// Long enough that a single noisy sample, or the two filters taking a step on
+/// adjacent rows of a barometer update, does not count; short enough that the
+/// reported time is still within a breath of where it actually started.
+const DIVERGENCE_HOLD_S: f64 = 0.5;
+
+/// Widest stretch of `b` that is interpolated across. Wider than any ordinary
+/// sample spacing, narrower than the Mach lockout, so a frozen filter in `b`
+/// is a gap in the comparison rather than a straight line through it.
+const MAX_INTERP_GAP_S: f64 = 0.1;
+
+/// The error statistics for one column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorStats {
+    /// Rows of `a` that had a finite value in both logs to compare.
+    pub samples: usize,
+    pub rmse: f64,
+    /// Largest absolute error, and the T+ it occurred at.
+    pub max_error: f64,
+    pub max_error_at_s: f64,
+    /// Start of the first stretch where the error stayed over the threshold
+    /// for [`DIVERGENCE_HOLD_S`]. `None` means the two never parted.
+    pub diverged_at_s: Option<f64>,
+}
+
+/// One column's result, as the figure and the table both want it.
+pub struct ColumnComparison {
+    pub compared: &'static Compared,
+    /// `None` when either log lacks the column or the two never overlap.
+    pub stats: Option<ErrorStats>,
+}
+
+/// One side of the comparison: a log, the flight chosen from it, and its rows'
+/// times in seconds from that flight's liftoff.
+pub struct Side<'a> {
+    pub log: &'a FlightLog,
+    pub session: &'a Session,
+    pub times: Vec<f64>,
+}
+
+impl<'a> Side<'a> {
+    pub fn new(log: &'a FlightLog, session: &'a Session) -> Self {
+        let t0 = log.timestamp_us[session.flight_start];
+        let times = log.timestamp_us.iter().map(|t| (t - t0) / 1e6).collect();
+        Self {
+            log,
+            session,
+            times,
+        }
+    }
+
+    /// A column scaled into display units, over this side's flight rows.
+    fn series(&self, compared: &Compared) -> Option<(&[f64], Vec<f32>)> {
+        let values = self.log.column(compared.column)?;
+        let rows = self.session.flight_start..self.session.flight_end;
+        let scaled = values[rows.clone()]
+            .iter()
+            .map(|v| v * compared.scale)
+            .collect();
+        Some((&self.times[rows], scaled))
+    }
+}
+
+pub fn compare_flight_logs(args: &CompareFlightLogsArgs) -> Result<()> {
+    let (a_path, b_path) = (Path::new(&args.a), Path::new(&args.b));
+    let a_log = FlightLog::load(a_path)?;
+    let b_log = FlightLog::load(b_path)?;
+    let a_name = file_name(a_path, &args.a);
+    let b_name = file_name(b_path, &args.b);
+
+    let a_sessions = find_sessions(&a_log, args.lead_in);
+    let b_sessions = find_sessions(&b_log, args.lead_in);
+    let Some(a_index) = choose(&a_sessions, &a_log, &a_name, args.session_a, "--session-a")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let Some(b_index) = choose(&b_sessions, &b_log, &b_name, args.session_b, "--session-b")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let a = Side::new(&a_log, &a_sessions[a_index]);
+    let b = Side::new(&b_log, &b_sessions[b_index]);
+    for (name, side) in [(&a_name, &a), (&b_name, &b)] {
+        if side.session.window_source == WindowSource::NeverLeftThePad {
+            bail!(
+                "{name}: the chosen session never left the pad, so there is no liftoff \
+                 to align the two logs on"
+            );
+        }
+    }
+
+    let selected: Vec<&'static Compared> = if args.column.is_empty() {
+        ESTIMATOR_OUTPUTS.iter().collect()
+    } else {
+        args.column
+            .iter()
+            .map(|name| {
+                ESTIMATOR_OUTPUTS
+                    .iter()
+                    .find(|c| c.column == name.as_str())
+                    .with_context(|| {
+                        format!(
+                            "`{name}` is not a compared column; choose from: {}",
+                            ESTIMATOR_OUTPUTS
+                                .iter()
+                                .map(|c| c.column)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<_>>()?
+    };
+    let results: Vec<ColumnComparison> = selected
+        .into_iter()
+        .map(|compared| ColumnComparison {
+            compared,
+            stats: compare_column(&a, &b, compared),
+        })
+        .collect();
+
+    println!(
+        "a = {a_name} (flight of {:.1} s), b = {b_name} (flight of {:.1} s); \
+         errors are b - a, aligned on liftoff",
+        a.session.duration_s(&a_log),
+        b.session.duration_s(&b_log)
+    );
+    print_table(&results);
+
+    let path = output_path(a_path, b_path, args.out_dir.as_deref())?;
+    let window = Window {
+        start: a.session.plot_start,
+        end: a.session.flight_end,
+    };
+    Renderer::new(
+        &a_log,
+        a.session,
+        format!("a: {a_name} · b: {b_name}"),
+        window,
+    )
+    .with_overlay(&b_log, b.session)
+    .render_comparison(&path, &results)
+    .with_context(|| format!("writing {}", path.display()))?;
+    println!(
+        "Wrote {} ({}×{})",
+        path.display(),
+        figures::WIDTH,
+        figures::HEIGHT
+    );
+    Ok(())
+}
+
+fn file_name(path: &Path, fallback: &str) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+fn compare_column(a: &Side, b: &Side, compared: &Compared) -> Option<ErrorStats> {
+    let (a_times, a_values) = a.series(compared)?;
+    let (b_times, b_values) = b.series(compared)?;
+    error_stats(a_times, &a_values, b_times, &b_values, compared.threshold)
+}
+
+/// `b - a` at every row of `a` that `b` can be read at, reduced to statistics.
+///
+/// Both time slices must be non-decreasing. A row of `a` is skipped, not
+/// counted as agreement, when `b` has no finite sample within
+/// [`MAX_INTERP_GAP_S`] on either side of it.
+pub fn error_stats(
+    a_times: &[f64],
+    a_values: &[f32],
+    b_times: &[f64],
+    b_values: &[f32],
+    threshold: f64,
+) -> Option<ErrorStats> {
+    let mut samples = 0usize;
+    let mut sum_sq = 0.0;
+    let mut max_error = 0.0;
+    let mut max_error_at_s = 0.0;
+    let mut diverged_at_s = None;
+    // Start of the current over-threshold stretch.
+    let mut over_since: Option<f64> = None;
+
+    for (&t, &a_value) in a_times.iter().zip(a_values) {
+        // A skipped row breaks the stretch: the hold has to be seen
+        // unbroken, not bridged across rows nothing was compared on.
+        let b_value = if a_value.is_finite() {
+            interpolate(b_times, b_values, t)
+        } else {
+            None
+        };
+        let Some(b_value) = b_value else {
+            over_since = None;
+            continue;
+        };
+        let error = b_value - a_value as f64;
+        samples += 1;
+        sum_sq += error * error;
+        if error.abs() > max_error {
+            max_error = error.abs();
+            max_error_at_s = t;
+        }
+        if error.abs() > threshold {
+            let since = *over_since.get_or_insert(t);
+            if diverged_at_s.is_none() && t - since >= DIVERGENCE_HOLD_S {
+                diverged_at_s = Some(since);
+            }
+        } else {
+            over_since = None;
+        }
+    }
+
+    (samples > 0).then(|| ErrorStats {
+        samples,
+        rmse: (sum_sq / samples as f64).sqrt(),
+        max_error,
+        max_error_at_s,
+        diverged_at_s,
+    })
+}
+
+/// `values` at `t`, linearly between the finite samples either side of it.
+fn interpolate(times: &[f64], values: &[f32], t: f64) -> Option<f64> {
+    let after = times.partition_point(|&x| x < t);
+    let next = (after..times.len()).find(|&i| values[i].is_finite())?;
+    if times[next] == t {
+        return Some(values[next] as f64);
+    }
+    let prev = (0..after).rev().find(|&i| values[i].is_finite())?;
+    let (t0, t1) = (times[prev], times[next]);
+    if t1 - t0 > MAX_INTERP_GAP_S {
+        return None;
+    }
+    let (v0, v1) = (values[prev] as f64, values[next] as f64);
+    Some(v0 + (v1 - v0) * (t - t0) / (t1 - t0))
+}
+
+fn print_table(results: &[ColumnComparison]) {
+    println!(
+        "  {:<34} {:>8} {:>10} {:>18} {:>12}",
+        "column", "samples", "RMSE", "max error", "diverged"
+    );
+    for result in results {
+        let c = result.compared;
+        let Some(s) = &result.stats else {
+            println!("  {:<34} {:>8}", c.column, "—");
+            continue;
+        };
+        let diverged = s
+            .diverged_at_s
+            .map_or_else(|| "never".to_string(), |t| format!("T+{t:.2} s"));
+        println!(
+            "  {:<34} {:>8} {:>10} {:>18} {:>12}",
+            c.column,
+            s.samples,
+            format!("{:.3} {}", s.rmse, c.unit),
+            format!("{:.2} @ T+{:.1} s", s.max_error, s.max_error_at_s),
+            diverged
+        );
+    }
+}
+
+/// `<a>_vs_<b>_comparison.png`, beside `a` unless told otherwise.
+fn output_path(a: &Path, b: &Path, out_dir: Option<&str>) -> Result<PathBuf> {
+    let stem = |p: &Path| {
+        p.file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "flight_log".to_string())
+    };
+    let dir = match out_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => a.parent().unwrap_or(Path::new(".")).to_path_buf(),
+    };
+    if !dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    Ok(dir.join(format!("{}_vs_{}_comparison.png", stem(a), stem(b))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::log_csv::test_support::log_from_csv;
+
+    /// A log that is `b` shifted in boot time but identical in flight time
+    /// compares as identical: the alignment is on liftoff, not on the clock.
+    #[test]
+    fn logs_that_differ_only_in_pad_time_are_identical() {
+        let body = |offset_us: u64| {
+            let mut csv =
+                "record_count,timestamp_us,flight_stage,deployment_kf_altitude_asl\n".to_string();
+            for i in 0..40u64 {
+                let stage = if i < 10 { "Armed" } else { "Ascent" };
+                let altitude = if i < 10 {
+                    100.0
+                } else {
+                    100.0 + ((i - 10) * (i - 10)) as f64
+                };
+                csv.push_str(&format!(
+                    "{i},{},{stage},{altitude}\n",
+                    offset_us + i * 50_000
+                ));
+            }
+            csv
+        };
+        let a_log = log_from_csv("compare_pad_a", &body(0));
+        let b_log = log_from_csv("compare_pad_b", &body(37_000_000));
+        let (a_sessions, b_sessions) = (find_sessions(&a_log, 0.0), find_sessions(&b_log, 0.0));
+        let a = Side::new(&a_log, &a_sessions[0]);
+        let b = Side::new(&b_log, &b_sessions[0]);
+
+        let stats = compare_column(&a, &b, &ESTIMATOR_OUTPUTS[0]).unwrap();
+        assert_eq!(stats.samples, 30);
+        assert_eq!(stats.rmse, 0.0);
+        assert_eq!(stats.diverged_at_s, None);
+        // And a column neither log carries is reported as such, not as zero.
+        assert!(compare_column(&a, &b, &ESTIMATOR_OUTPUTS[1]).is_none());
+    }
+
+    /// `b` is read between its own samples, so logs at different rates still
+    /// compare row for row.
+    #[test]
+    fn b_is_interpolated_onto_a_and_errors_are_b_minus_a() {
+        let a_times = [0.0, 0.01, 0.02, 0.03];
+        let a_values = [0.0, 1.0, 2.0, 3.0];
+        let b_times = [0.0, 0.02, 0.04];
+        let b_values = [1.0, 3.0, 5.0];
+        let stats = error_stats(&a_times, &a_values, &b_times, &b_values, 10.0).unwrap();
+        assert_eq!(stats.samples, 4);
+        assert!((stats.rmse - 1.0).abs() < 1e-9, "{stats:?}");
+        assert!((stats.max_error - 1.0).abs() < 1e-9);
+    }
+
+    /// One bad sample is noise; a stretch is a divergence, reported from where
+    /// it began rather than from where it was confirmed.
+    #[test]
+    fn divergence_needs_the_error_to_hold_and_is_dated_from_its_start() {
+        let times: Vec<f64> = (0..300).map(|i| i as f64 * 0.01).collect();
+        let a = vec![0.0f32; 300];
+        let b: Vec<f32> = (0..300)
+            .map(|i| match i {
+                50 => 20.0,
+                _ if i >= 120 => 8.0,
+                _ => 0.5,
+            })
+            .collect();
+        let stats = error_stats(&times, &a, &times, &b, 5.0).unwrap();
+        let diverged = stats.diverged_at_s.expect("the step at 1.2 s holds");
+        assert!((diverged - 1.2).abs() < 1e-9, "{diverged}");
+        assert_eq!(stats.max_error, 20.0);
+        assert!((stats.max_error_at_s - 0.5).abs() < 1e-9);
+    }
+
+    /// A hole in `b` is not bridged — a frozen filter is not a straight line
+    /// between the values either side of the freeze — and neither is an
+    /// over-threshold stretch: two short ones either side of the hole are not
+    /// one long one.
+    #[test]
+    fn a_gap_in_b_is_skipped_rather_than_interpolated_across() {
+        let times: Vec<f64> = (0..100).map(|i| i as f64 * 0.01).collect();
+        let a = vec![0.0f32; 100];
+        let b: Vec<f32> = (0..100)
+            .map(|i| match i {
+                30..60 => f32::NAN,
+                _ if i < 90 => 8.0,
+                _ => 0.0,
+            })
+            .collect();
+        let stats = error_stats(&times, &a, &times, &b, 1.0).unwrap();
+        assert_eq!(stats.samples, 70);
+        assert_eq!(stats.diverged_at_s, None);
+    }
+}
@@ -10,6 +10,14 @@
 //! transition says when the flight computer *decided* something; the pyro flag
 //! says when the charge actually went. They are usually within a few hundred
 //! milliseconds and get merged, but when they are not, that gap is the finding.
+//!
+//! A third source is the event log `download-flight-log` writes beside the CSV:
+//! what the avionics recorded as it happened — commands, uplinks, reboots —
+//! rather than what this module can infer from a column. See [`load_logged`].
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
 
 use crate::plot::theme::{MACH_LOCKOUT, stage_name};
 
@@ -24,7 +32,7 @@ pub struct Event {
 ///
 /// A column that starts already true counts its first row as an edge: the log
 /// beginning mid-event is still the first time this figure can show it.
-fn rising_edges(values: &[f32], start: usize, end: usize) -> Vec<usize> {
+pub fn rising_edges(values: &[f32], start: usize, end: usize) -> Vec<usize> {
     let end = end.min(values.len());
     let mut edges = Vec::new();
     let mut prev = false;
@@ -119,6 +127,11 @@ pub fn detect(
         raw.push((times[row], "apogee".to_string()));
     }
 
+    merge(raw, merge_within)
+}
+
+/// Sort and merge. Shared by [`detect`] and [`merge_logged`].
+fn merge(mut raw: Vec<(f64, String)>, merge_within: f64) -> Vec<Event> {
     raw.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
 
     // Merge what a reader could not tell apart anyway. "Drogue" and "drogue
@@ -140,6 +153,105 @@ pub fn detect(
     merged
 }
 
+/// One record from the events file beside the log, kept because it is worth
+/// a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedEvent {
+    /// First log row after the event. Places it in its session, which the
+    /// boot-relative `timestamp_us` alone cannot.
+    pub row: usize,
+    pub timestamp_us: f64,
+    pub label: String,
+}
+
+/// The rule label for a logged event, or `None` for a kind that is not drawn.
+///
+/// Stage changes are left out because the stage column already draws them,
+/// and ECC failures because they come in bursts whenever the link is weak:
+/// a rule per packet would wall off the panel they landed on.
+fn logged_label(kind: &str, detail: &str) -> Option<String> {
+    Some(match kind {
+        "ModeChangeCommanded" => format!("mode: {detail}"),
+        "UplinkReceived" => format!("uplink {detail}"),
+        "PyroCommanded" => format!("pyro: {detail}"),
+        "NodeRebooted" => format!("{} reboot", detail.split(',').next().unwrap_or(detail)),
+        "ConfigChanged" => "config written".to_string(),
+        "Staging" => format!("staging: {detail}"),
+        _ => return None,
+    })
+}
+
+/// Read the drawable events from an events CSV. A log downloaded before the
+/// avionics kept an event log has no such file, which is not an error.
+pub fn load_logged(path: &Path) -> Result<Vec<LoggedEvent>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    let headers = reader.headers()?.clone();
+    let index = |name: &str| headers.iter().position(|h| h == name);
+    let (Some(row_i), Some(time_i), Some(kind_i), Some(detail_i)) = (
+        index("row"),
+        index("timestamp_us"),
+        index("event"),
+        index("detail"),
+    ) else {
+        anyhow::bail!("{} is not an events file", path.display());
+    };
+
+    let mut logged = Vec::new();
+    for record in reader.records() {
+        let record = record.with_context(|| format!("reading {}", path.display()))?;
+        let field = |i: usize| record.get(i).unwrap_or("");
+        let (Ok(row), Ok(timestamp_us)) =
+            (field(row_i).parse::<usize>(), field(time_i).parse::<f64>())
+        else {
+            continue;
+        };
+        if let Some(label) = logged_label(field(kind_i), field(detail_i)) {
+            logged.push(LoggedEvent {
+                row,
+                timestamp_us,
+                label,
+            });
+        }
+    }
+    Ok(logged)
+}
+
+/// Add the logged events that fall in `start..end` to events already
+/// detected, placed on the figure's axis and merged with them by the same
+/// rule.
+///
+/// An event is positioned from the row after it, offset by the difference in
+/// the two clocks, so it lands at its own microsecond rather than snapping to
+/// a row. One logged after the last row is measured back from that row.
+pub fn merge_logged(
+    detected: Vec<Event>,
+    logged: &[LoggedEvent],
+    times: &[f64],
+    timestamp_us: &[f64],
+    start: usize,
+    end: usize,
+    merge_within: f64,
+) -> Vec<Event> {
+    let rows = times.len().min(timestamp_us.len());
+    let mut raw: Vec<(f64, String)> = detected.into_iter().map(|e| (e.at_s, e.label)).collect();
+    for event in logged {
+        if rows == 0 || event.row > rows {
+            continue;
+        }
+        let anchor = event.row.min(rows - 1);
+        if anchor < start || anchor >= end {
+            continue;
+        }
+        let at_s = times[anchor] + (event.timestamp_us - timestamp_us[anchor]) / 1e6;
+        raw.push((at_s, event.label.clone()));
+    }
+    merge(raw, merge_within)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,6 +260,68 @@ mod tests {
         (0..n).map(|i| i as f64).collect()
     }
 
+    /// A logged event sits at its own time, and one that lands on a detected
+    /// rule joins it rather than drawing a second line beside it.
+    #[test]
+    fn logged_events_are_placed_by_their_clock_and_merged_with_detected_ones() {
+        let timestamps: Vec<f64> = (0..5).map(|i| 1e6 * i as f64).collect();
+        let stages = vec![Some(3), Some(3), Some(4), Some(4), Some(4)];
+        let detected = detect(&times(5), &stages, None, None, None, None, 0, 5, 0.1);
+        let logged = [
+            LoggedEvent {
+                row: 2,
+                timestamp_us: 1.95e6,
+                label: "pyro: PyroDrogue by flight computer".into(),
+            },
+            LoggedEvent {
+                row: 4,
+                timestamp_us: 3.5e6,
+                label: "ICARUS reboot".into(),
+            },
+            // Another session's event: its row is outside the window.
+            LoggedEvent {
+                row: 9,
+                timestamp_us: 0.0,
+                label: "config written".into(),
+            },
+        ];
+        let events = merge_logged(detected, &logged, &times(5), &timestamps, 0, 5, 0.1);
+        assert_eq!(
+            events,
+            vec![
+                Event {
+                    at_s: 1.95,
+                    label: "pyro: PyroDrogue by flight computer / Drogue".into()
+                },
+                Event { at_s: 3.5, label: "ICARUS reboot".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_event_kinds_worth_a_rule_are_loaded() {
+        let path = std::env::temp_dir().join("rocket_cli_events_load_test.events.csv");
+        std::fs::write(
+            &path,
+            "row,source_block_crc_failed,timestamp_us,event,detail\n\
+             3,false,100,EccFailure,\n\
+             4,false,200,StageChanged,Armed -> Ascent\n\
+             5,false,300,NodeRebooted,\"ICARUS, uptime 1 s\"\n",
+        )
+        .unwrap();
+        let logged = load_logged(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            logged,
+            vec![LoggedEvent {
+                row: 5,
+                timestamp_us: 300.0,
+                label: "ICARUS reboot".into(),
+            }]
+        );
+        assert!(load_logged(Path::new("/nonexistent/x.events.csv")).unwrap().is_empty());
+    }
+
     #[test]
     fn stage_transitions_become_events_but_the_windows_own_start_does_not() {
         let stages = vec![Some(3), Some(3), Some(4), Some(4), Some(5)];
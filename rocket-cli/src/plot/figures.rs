@@ -19,9 +19,13 @@
 //! bottom panel of a column carries tick labels. Repeating an identical row of
 //! numbers under every panel spends about a tenth of the figure's height saying
 //! the same thing five times, and that height is worth more given to the traces.
-//! Vertical rules mark the events — burnout, apogee, deployments — and run
-//! through every panel, which is what makes a feature in one panel locatable in
+//! Vertical rules mark the events — burnout, apogee, deployments, and whatever
+//! the avionics' own event log recorded — and run through every panel, which is what makes a feature in one panel locatable in
 //! the next.
+//!
+//! `compare-flight-logs` borrows the same renderer for a fifth figure, with a
+//! second log overlaid on the first — see [`Renderer::with_overlay`]. Its axes,
+//! bands and rules are the first log's, so it reads exactly like the others.
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -33,6 +37,7 @@ use plotters::coord::types::RangedCoordf64;
 use plotters::prelude::*;
 use plotters::style::text_anchor::{HPos, Pos, VPos};
 
+use crate::plot::compare::ColumnComparison;
 use crate::plot::events::{self, Event};
 use crate::plot::log_csv::FlightLog;
 use crate::plot::series::{Trace, decimate, stage_spans, true_spans, widen_and_merge};
@@ -88,7 +93,7 @@ fn legend_swatch<'a, DB: DrawingBackend + 'a>(series: &mut SeriesAnno<'a, DB>, l
 /// plotters' error type is parameterised by the backend and is awkward to carry
 /// through `?` into `anyhow`. Every drawing failure here means the same thing —
 /// the image could not be produced — so they collapse to one message.
-trait PlotErr<T> {
+pub(crate) trait PlotErr<T> {
     fn plot(self) -> Result<T>;
 }
 
@@ -119,6 +124,8 @@ struct Line {
     /// the others can be read against it, and it must not be what hides them
     /// where they cross.
     behind: bool,
+    /// Read the column from the renderer's overlay log rather than its own.
+    overlay: bool,
 }
 
 impl Line {
@@ -130,6 +137,7 @@ impl Line {
             scale: 1.0,
             dashed: false,
             behind: false,
+            overlay: false,
         }
     }
 
@@ -147,6 +155,11 @@ impl Line {
         self.behind = true;
         self
     }
+
+    fn from_overlay(mut self) -> Self {
+        self.overlay = true;
+        self
+    }
 }
 
 /// A second y axis on the right of a panel.
@@ -178,6 +191,8 @@ struct Panel {
     x_labels: bool,
     /// Top of a column: carries the names of the event rules.
     event_labels: bool,
+    /// A footnote of the caller's own, shown ahead of the panel's.
+    note: Option<String>,
 }
 
 impl Panel {
@@ -190,6 +205,7 @@ impl Panel {
             zero_line: false,
             x_labels: false,
             event_labels: false,
+            note: None,
         }
     }
 
@@ -212,6 +228,11 @@ impl Panel {
         self.event_labels = true;
         self
     }
+
+    fn with_note(mut self, note: String) -> Self {
+        self.note = Some(note);
+        self
+    }
 }
 
 /// A panel's chosen vertical range.
@@ -326,6 +347,19 @@ fn restore_mach_lockout(
         .collect()
 }
 
+/// A second log drawn over the first, for `compare-flight-logs`.
+///
+/// Aligned on its own liftoff rather than on the first log's clock — the two
+/// were booted at unrelated times, and liftoff is the one instant both
+/// detected for themselves.
+struct Overlay<'a> {
+    log: &'a FlightLog,
+    /// Seconds relative to this log's liftoff, per row.
+    times: Vec<f64>,
+    /// `(start, end)` rows of this log that fall inside the figure's x range.
+    window: (usize, usize),
+}
+
 pub struct Renderer<'a> {
     log: &'a FlightLog,
     session: &'a Session,
@@ -360,6 +394,7 @@ pub struct Renderer<'a> {
     x_range: (f64, f64),
     events: Vec<Event>,
     source_name: String,
+    overlay: Option<Overlay<'a>>,
 }
 
 impl<'a> Renderer<'a> {
@@ -466,6 +501,42 @@ impl<'a> Renderer<'a> {
             derived.insert("deployment_kf_vertical_acc", acc);
         }
 
+        // The `debug-internals` columns are logged as variances, which is what
+        // the filter carries; a reader checks an innovation against a
+        // standard deviation. So each filter's innovation gets a 2σ envelope
+        // from its own variance — about one sample in twenty outside it on a
+        // consistent filter — and its covariance diagonal comes back as σ in
+        // the units of the states. The χ² bound is drawn wherever a NIS was
+        // logged, so it reads as part of the data rather than as a rule.
+        for (variance, sigma, scale) in [
+            ("deployment_kf_innovation_variance", "deployment_kf_innovation_2sigma", 2.0),
+            ("deployment_kf_altitude_variance", "deployment_kf_altitude_sigma", 1.0),
+            ("deployment_kf_velocity_variance", "deployment_kf_velocity_sigma", 1.0),
+            ("airbrakes_kf_innovation_variance", "airbrakes_kf_innovation_2sigma", 2.0),
+            ("airbrakes_kf_altitude_variance", "airbrakes_kf_altitude_sigma", 1.0),
+            ("airbrakes_kf_velocity_variance", "airbrakes_kf_velocity_sigma", 1.0),
+        ] {
+            if let Some(v) = log.column(variance) {
+                derived.insert(sigma, v.iter().map(|v| scale * v.sqrt()).collect());
+            }
+        }
+        let nis = [
+            log.column("deployment_kf_nis"),
+            log.column("airbrakes_kf_nis"),
+        ];
+        if nis.iter().any(Option::is_some) {
+            let bound = (0..log.row_count)
+                .map(|i| {
+                    if nis.iter().flatten().any(|c| c[i].is_finite()) {
+                        air_brakes_controller_core::kf_internals::NIS_95_BOUND
+                    } else {
+                        f32::NAN
+                    }
+                })
+                .collect();
+            derived.insert("kf_nis_95_bound", bound);
+        }
+
         // Every altitude on the card is ASL, which is the unit the barometer
         // and the GPS measure in and the only one that needs no reference to
         // interpret. Every altitude a reader of these figures cares about is
@@ -489,6 +560,7 @@ impl<'a> Renderer<'a> {
                 ("deployment_kf_altitude_asl", "deployment_kf_altitude_agl"),
                 ("airbrakes_kf_altitude_asl", "airbrakes_kf_altitude_agl"),
                 ("gps_altitude_asl", "gps_altitude_agl"),
+                ("smoothed_altitude_asl", "smoothed_altitude_agl"),
                 ("mpc_predicted_apogee_asl", "mpc_predicted_apogee_agl"),
                 ("air_brakes_target_apogee_asl", "air_brakes_target_apogee_agl"),
             ] {
@@ -576,6 +648,15 @@ impl<'a> Renderer<'a> {
             window.end,
             merge_within,
         );
+        let events = events::merge_logged(
+            events,
+            &log.events,
+            &times,
+            &log.timestamp_us,
+            window.start,
+            window.end,
+            merge_within,
+        );
 
         Self {
             log,
@@ -591,9 +672,36 @@ impl<'a> Renderer<'a> {
             x_range,
             events,
             source_name,
+            overlay: None,
         }
     }
 
+    /// Draw a second log's columns over this one's, for lines that ask for it.
+    ///
+    /// Only the rows of `session` that land inside this figure's axis are
+    /// drawn; the events, stages and header stay this log's own, so the
+    /// figure is still about the first log, with the second as the thing it
+    /// is being measured against.
+    pub fn with_overlay(mut self, log: &'a FlightLog, session: &Session) -> Self {
+        let t0 = log.timestamp_us[session.flight_start];
+        let times: Vec<f64> = log.timestamp_us.iter().map(|t| (t - t0) / 1e6).collect();
+        let rows = session.start..session.end;
+        let start = rows
+            .clone()
+            .find(|&i| times[i] >= self.x_range.0)
+            .unwrap_or(session.end);
+        let end = rows
+            .rev()
+            .find(|&i| times[i] <= self.x_range.1)
+            .map_or(start, |i| (i + 1).max(start));
+        self.overlay = Some(Overlay {
+            log,
+            times,
+            window: (start, end),
+        });
+        self
+    }
+
     /// Draw the deployment estimator's own states instead of the air brakes'.
     ///
     /// Two changes, both about whose story the figure tells. The Mach lockout
@@ -618,7 +726,8 @@ impl<'a> Renderer<'a> {
             self.log.column("deployment_baro_gate_reject"),
             self.log.column("deployment_kf_altitude_asl"),
         );
-        self.events = events::detect(
+        let merge_within = (self.x_range.1 - self.x_range.0) * 0.005;
+        let detected = events::detect(
             &self.times,
             &self.stages,
             None,
@@ -627,7 +736,16 @@ impl<'a> Renderer<'a> {
             None,
             self.window.0,
             self.window.1,
-            (self.x_range.1 - self.x_range.0) * 0.005,
+            merge_within,
+        );
+        self.events = events::merge_logged(
+            detected,
+            &self.log.events,
+            &self.times,
+            &self.log.timestamp_us,
+            self.window.0,
+            self.window.1,
+            merge_within,
         );
         self
     }
@@ -675,14 +793,24 @@ impl<'a> Renderer<'a> {
     }
 
     fn trace(&self, line: &Line, buckets: usize) -> Option<Trace> {
-        let values = self.column(line.column)?;
-        let mut trace = decimate(
-            &self.times,
-            values,
-            self.window.0,
-            self.window.1,
-            buckets,
-        )?;
+        let mut trace = if line.overlay {
+            let overlay = self.overlay.as_ref()?;
+            decimate(
+                &overlay.times,
+                overlay.log.column(line.column)?,
+                overlay.window.0,
+                overlay.window.1,
+                buckets,
+            )?
+        } else {
+            decimate(
+                &self.times,
+                self.column(line.column)?,
+                self.window.0,
+                self.window.1,
+                buckets,
+            )?
+        };
         if line.scale != 1.0 {
             for run in &mut trace.runs {
                 for point in run.iter_mut() {
@@ -742,6 +870,10 @@ impl<'a> Renderer<'a> {
                     // saying it cannot get there; the two converging is it
                     // saying it can.
                     Line::new("airbrakes KF altitude", "airbrakes_kf_altitude_agl", theme::CYAN),
+                    // Only in a log `smooth-flight-log` has been over. Behind
+                    // the flown trace, since it is the yardstick the flown
+                    // one is read against, not a fourth thing to read.
+                    Line::new("smoothed altitude", "smoothed_altitude_agl", theme::BLUE).behind(),
                     Line::new("MPC predicted apogee", "mpc_predicted_apogee_agl", theme::AMBER),
                     Line::new("target apogee", "air_brakes_target_apogee_agl", theme::VIOLET)
                         .dashed(),
@@ -783,6 +915,9 @@ impl<'a> Renderer<'a> {
                         "vertical_acc_earth",
                         theme::GREEN,
                     ),
+                    Line::new("smoothed speed", "smoothed_vertical_velocity", theme::BLUE).behind(),
+                    Line::new("smoothed acceleration", "smoothed_vertical_acc", theme::CORAL)
+                        .behind(),
                 ],
             )
             // Zero means two different things on this panel and both are
@@ -849,6 +984,10 @@ impl<'a> Renderer<'a> {
                     // two traces separating rather than as a plausible curve.
                     Line::new("deployment KF", "deployment_kf_altitude_agl", theme::CYAN),
                     Line::new("GPS", "gps_altitude_agl", theme::VIOLET),
+                    // The smoother has both of the above and the whole flight
+                    // to weigh them with, so where it parts from the KF is
+                    // where the KF was wrong.
+                    Line::new("smoothed", "smoothed_altitude_agl", theme::BLUE).behind(),
                 ],
             )
             .with_event_labels()
@@ -860,11 +999,10 @@ impl<'a> Renderer<'a> {
             &Panel::new(
                 "Vertical speed & acceleration",
                 "m/s",
-                vec![Line::new(
-                    "deployment KF",
-                    "deployment_kf_vertical_velocity",
-                    theme::CYAN,
-                )],
+                vec![
+                    Line::new("deployment KF", "deployment_kf_vertical_velocity", theme::CYAN),
+                    Line::new("smoothed", "smoothed_vertical_velocity", theme::BLUE).behind(),
+                ],
             )
             // Zero is apogee, and the two descent rates either side of the main
             // are read off this panel against it.
@@ -881,11 +1019,15 @@ impl<'a> Renderer<'a> {
             // derivative than as a change of slope.
             .with_secondary(Secondary::new(
                 "m/s²",
-                vec![Line::new(
-                    "vertical acceleration (d/dt of KF speed)",
-                    "deployment_kf_vertical_acc",
-                    theme::AMBER,
-                )],
+                vec![
+                    Line::new(
+                        "vertical acceleration (d/dt of KF speed)",
+                        "deployment_kf_vertical_acc",
+                        theme::AMBER,
+                    ),
+                    Line::new("smoothed acceleration", "smoothed_vertical_acc", theme::CORAL)
+                        .behind(),
+                ],
             )),
             Y_GUTTER,
         )?;
@@ -1183,6 +1325,180 @@ impl<'a> Renderer<'a> {
         Ok(())
     }
 
+    /// The two Kalman filters from the inside, pad to landing: only from a
+    /// log a `debug-internals` build wrote (see
+    /// [`air_brakes_controller_core::kf_internals`]).
+    ///
+    /// One panel per filter and a third for the test both are judged by.
+    /// Each filter's innovation is drawn inside its own ±2σ envelope, which
+    /// is the whole consistency argument in picture form: a trace that lives
+    /// against the envelope's edge is a filter that believes itself more
+    /// than it should, one that never leaves the middle third is a filter
+    /// that doubts itself into lag, and a trace that wanders to one side for
+    /// seconds at a time is a model error no choice of R will fix. The σ of
+    /// each state rides the right axis, so the envelope's width can be read
+    /// against what made it.
+    ///
+    /// The bottom panel is the NIS both filters' innovations make against
+    /// the χ² bound. It is the number `replay-estimators` and `simulate`
+    /// summarise, drawn per sample so that where the mean went wrong can be
+    /// found, not just that it did.
+    pub fn render_internals(&self, path: &Path) -> Result<()> {
+        let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&theme::BG).plot()?;
+        let (header, body) = root.split_vertically(HEADER_H);
+        self.draw_header(&header, "Estimator internals")?;
+
+        // Equal thirds, with the bottom one taller by the tick labels.
+        let panel_h = (HEIGHT - HEADER_H - X_LABELS_H) / 3;
+        let (p1, rest) = body.split_vertically(panel_h);
+        let (p2, p3) = rest.split_vertically(panel_h);
+
+        for (i, (area, title, [innovation, envelope, altitude, velocity], color)) in [
+            (
+                &p1,
+                "Deployment KF innovation",
+                [
+                    "deployment_kf_innovation",
+                    "deployment_kf_innovation_2sigma",
+                    "deployment_kf_altitude_sigma",
+                    "deployment_kf_velocity_sigma",
+                ],
+                theme::CYAN,
+            ),
+            (
+                &p2,
+                "Airbrakes KF innovation",
+                [
+                    "airbrakes_kf_innovation",
+                    "airbrakes_kf_innovation_2sigma",
+                    "airbrakes_kf_altitude_sigma",
+                    "airbrakes_kf_velocity_sigma",
+                ],
+                theme::GREEN,
+            ),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let panel = Panel::new(
+                title,
+                "m",
+                vec![
+                    // Grey and underneath: the envelope is what the innovation
+                    // is read against, not a result of its own.
+                    Line::new("+2σ", envelope, theme::MUTED).behind(),
+                    Line::new("-2σ", envelope, theme::MUTED)
+                        .scaled(-1.0)
+                        .behind(),
+                    Line::new("innovation", innovation, color),
+                ],
+            )
+            .with_zero()
+            .with_secondary(Secondary::new(
+                "m · m/s",
+                vec![
+                    Line::new("σ altitude", altitude, theme::AMBER),
+                    Line::new("σ velocity", velocity, theme::VIOLET),
+                ],
+            ));
+            // Only the top panel names the rules, as on every other figure.
+            let panel = if i == 0 {
+                panel.with_event_labels()
+            } else {
+                panel
+            };
+            self.draw_panel(area, &panel, Y_GUTTER)?;
+        }
+        self.draw_panel(
+            &p3,
+            &Panel::new(
+                "Normalised innovation squared",
+                "y²/S",
+                vec![
+                    Line::new("deployment KF", "deployment_kf_nis", theme::CYAN),
+                    Line::new("airbrakes KF", "airbrakes_kf_nis", theme::GREEN),
+                    Line::new("χ²(1) 95%", "kf_nis_95_bound", theme::ALERT).dashed(),
+                ],
+            )
+            .with_x_labels(),
+            Y_GUTTER,
+        )?;
+
+        root.present().plot()?;
+        Ok(())
+    }
+
+    /// Two logs of one flight, one panel per compared estimator output.
+    ///
+    /// Needs [`with_overlay`](Self::with_overlay): `a` is this renderer's own
+    /// log and `b` the overlay. Each panel's footnote carries that column's
+    /// error statistics, so the figure can be read without the table printed
+    /// beside it.
+    pub fn render_comparison(&self, path: &Path, results: &[ColumnComparison]) -> Result<()> {
+        let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&theme::BG).plot()?;
+        let (header, body) = root.split_vertically(HEADER_H);
+        self.draw_header(&header, "Comparison")?;
+
+        // Equal heights for the traces, with the bottom panel taller by
+        // exactly the tick labels it alone carries.
+        let count = results.len().max(1) as u32;
+        let each = (HEIGHT - HEADER_H - X_LABELS_H) / count;
+        let mut remaining = Some(body);
+        for (i, result) in results.iter().enumerate() {
+            let last = i + 1 == results.len();
+            let Some(below) = remaining.take() else { break };
+            let area = if last {
+                below
+            } else {
+                let (area, rest) = below.split_vertically(each);
+                remaining = Some(rest);
+                area
+            };
+
+            let c = result.compared;
+            let note = match &result.stats {
+                Some(s) => {
+                    let diverged = s
+                        .diverged_at_s
+                        .map_or_else(|| "never diverged".to_string(), |t| {
+                            format!("diverged at T+{t:.2} s")
+                        });
+                    format!(
+                        "b − a: RMSE {:.3} {unit} · max {:.2} {unit} at T+{:.1} s · {diverged}",
+                        s.rmse,
+                        s.max_error,
+                        s.max_error_at_s,
+                        unit = c.unit
+                    )
+                }
+                None => "no overlap to compare".to_string(),
+            };
+            let mut panel = Panel::new(
+                c.title,
+                c.unit,
+                vec![
+                    Line::new("a", c.column, theme::CYAN).scaled(c.scale),
+                    Line::new("b", c.column, theme::AMBER)
+                        .scaled(c.scale)
+                        .from_overlay(),
+                ],
+            )
+            .with_note(note);
+            if i == 0 {
+                panel = panel.with_event_labels();
+            }
+            if last {
+                panel = panel.with_x_labels();
+            }
+            self.draw_panel(&area, &panel, Y_GUTTER)?;
+        }
+
+        root.present().plot()?;
+        Ok(())
+    }
+
     // ---------------------------------------------------------------- pieces
 
     fn draw_header(&self, area: &Area, kind: &str) -> Result<()> {
@@ -1669,7 +1985,7 @@ impl<'a> Renderer<'a> {
             .plot()?;
 
         // Footnotes for anything the panel is not showing at face value.
-        let mut notes: Vec<String> = Vec::new();
+        let mut notes: Vec<String> = panel.note.iter().cloned().collect();
         // Columns this log simply does not carry are named, so an empty-looking
         // panel is never ambiguous between "not fitted" and "nothing happened".
         let missing: Vec<&str> = traces
@@ -13,6 +13,8 @@ use std::path::Path;
 
 use anyhow::{Context, Result, bail};
 
+use crate::plot::events::{LoggedEvent, load_logged};
+
 /// One flight-log CSV, stored column-wise.
 ///
 /// Column-wise because every consumer wants one series at a time across all
@@ -36,12 +38,17 @@ pub struct FlightLog {
     /// the exporter only reads the current storage version, so every CSV this
     /// tool can produce has it.
     pub airbrakes_state: Vec<Option<u8>>,
+    /// `GpsFusion` discriminant, same rules.
+    pub gps_fusion: Vec<Option<u8>>,
     /// Every other column that parses as a number, absent cells as `NaN`.
     columns: HashMap<String, Vec<f32>>,
     pub row_count: usize,
     /// Rows whose `source_block_crc_failed` was true. Absent from older logs,
     /// which is not the same as zero — hence the `Option`.
     pub crc_failed_rows: Option<usize>,
+    /// The drawable records of the events file beside this CSV, if there is
+    /// one.
+    pub events: Vec<LoggedEvent>,
 }
 
 /// Parse one cell into a plottable number.
@@ -96,13 +103,30 @@ fn parse_airbrakes_state(raw: &str) -> Option<u8> {
     })
 }
 
+/// Map a `GpsFusion` debug name to its discriminant. Written out for the
+/// same reason as [`parse_stage`].
+fn parse_gps_fusion(raw: &str) -> Option<u8> {
+    Some(match raw {
+        "NoMeasurement" => 0,
+        "PoorFix" => 1,
+        "PadReference" => 2,
+        "NotDescending" => 3,
+        "NoPadReference" => 4,
+        "Fused" => 5,
+        "Rejected" => 6,
+        "Resynced" => 7,
+        _ => return None,
+    })
+}
+
 /// Columns that get their own field on [`FlightLog`] and so are skipped by the
 /// generic float path.
-const SPECIAL: [&str; 4] = [
+const SPECIAL: [&str; 5] = [
     "record_count",
     "timestamp_us",
     "flight_stage",
     "airbrakes_state",
+    "deployment_gps_fusion",
 ];
 
 impl FlightLog {
@@ -131,6 +155,7 @@ impl FlightLog {
         let mut timestamp_us = Vec::new();
         let mut stage = Vec::new();
         let mut airbrakes_state = Vec::new();
+        let mut gps_fusion = Vec::new();
         let mut columns: HashMap<String, Vec<f32>> = headers
             .iter()
             .filter(|h| !SPECIAL.contains(&h.as_str()))
@@ -154,6 +179,7 @@ impl FlightLog {
                     "timestamp_us" => timestamp_us.push(raw.parse::<f64>().unwrap_or(f64::NAN)),
                     "flight_stage" => stage.push(parse_stage(raw)),
                     "airbrakes_state" => airbrakes_state.push(parse_airbrakes_state(raw)),
+                    "deployment_gps_fusion" => gps_fusion.push(parse_gps_fusion(raw)),
                     _ => {
                         if name == "source_block_crc_failed" {
                             saw_crc_column = true;
@@ -183,18 +209,32 @@ impl FlightLog {
         if airbrakes_state.len() != row_count {
             airbrakes_state.resize(row_count, None);
         }
+        // Nor does a log downloaded before the GPS aiding existed.
+        if gps_fusion.len() != row_count {
+            gps_fusion.resize(row_count, None);
+        }
         if record_count.len() != row_count {
             record_count.resize(row_count, 0);
         }
 
+        // The rules are a layer over the plot, not part of it: an events file
+        // that will not read costs its rules and nothing else.
+        let (events_path, _) = crate::usb_storage::events_paths(&path.to_string_lossy());
+        let events = load_logged(Path::new(&events_path)).unwrap_or_else(|e| {
+            eprintln!("warning: ignoring the event log: {e:#}");
+            Vec::new()
+        });
+
         Ok(Self {
             record_count,
             timestamp_us,
             stage,
             airbrakes_state,
+            gps_fusion,
             columns,
             row_count,
             crc_failed_rows: saw_crc_column.then_some(crc_failed),
+            events,
         })
     }
 
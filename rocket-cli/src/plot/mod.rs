@@ -1,4 +1,5 @@
-//! `plot-flight-log`: turn a downloaded flight-log CSV into four 4K figures.
+//! `plot-flight-log`: turn a downloaded flight-log CSV into four 4K figures,
+//! and a fifth when the log has the Kalman filters' internals in it.
 //!
 //! The three things this has to get right, in the order a reader meets them:
 //! pick the *right flight* out of a log that may hold several, cut it down to
@@ -22,6 +23,7 @@
 //! target — so it is the unit in which the figures can be checked against the
 //! flight plan.
 
+pub mod compare;
 pub mod events;
 pub mod figures;
 pub mod log_csv;
@@ -66,7 +68,7 @@ pub fn plot_flight_log(args: &PlotFlightLogArgs) -> Result<()> {
         );
     }
 
-    let index = choose(&sessions, &log, &source_name, args)?;
+    let index = choose(&sessions, &log, &source_name, args.session, "--session")?;
     let Some(index) = index else {
         println!("Cancelled.");
         return Ok(());
@@ -95,7 +97,7 @@ pub fn plot_flight_log(args: &PlotFlightLogArgs) -> Result<()> {
     Renderer::new(&log, session, source_name.clone(), to_landing)
         .render_misc(&paths.misc)
         .with_context(|| format!("writing {}", paths.misc.display()))?;
-    Renderer::new(&log, session, source_name, to_landing)
+    Renderer::new(&log, session, source_name.clone(), to_landing)
         .render_payload(&paths.payload)
         .with_context(|| format!("writing {}", paths.payload.display()))?;
 
@@ -108,9 +110,31 @@ pub fn plot_flight_log(args: &PlotFlightLogArgs) -> Result<()> {
         figures::WIDTH,
         figures::HEIGHT
     );
+
+    // A fifth figure only for a log that can fill it: a flight build writes
+    // no filter internals, and a figure of three "not recorded" panels
+    // beside every ordinary flight would teach people to ignore it.
+    if has_kf_internals(&log, session) {
+        Renderer::new(&log, session, source_name, to_landing)
+            .render_internals(&paths.internals)
+            .with_context(|| format!("writing {}", paths.internals.display()))?;
+        println!("Wrote {}", paths.internals.display());
+    }
     Ok(())
 }
 
+/// Whether the session carries any `debug-internals` rows.
+fn has_kf_internals(log: &FlightLog, session: &Session) -> bool {
+    ["deployment_kf_innovation", "airbrakes_kf_innovation"]
+        .into_iter()
+        .filter_map(|name| log.column(name))
+        .any(|column| {
+            column[session.start..session.end]
+                .iter()
+                .any(|v| v.is_finite())
+        })
+}
+
 /// The air-brakes figure's window: the same lead-in, ending at apogee.
 ///
 /// Apogee is included rather than cut just before it — it is the moment the
@@ -133,16 +157,20 @@ fn airbrakes_window(session: &Session) -> Window {
 }
 
 /// Decide which session to plot: the flag, the only one there is, or the picker.
-fn choose(
+///
+/// `flag` is the option `requested` came from, named in the errors so a
+/// command that takes two logs says which of them was the problem.
+pub(crate) fn choose(
     sessions: &[Session],
     log: &FlightLog,
     source_name: &str,
-    args: &PlotFlightLogArgs,
+    requested: Option<usize>,
+    flag: &str,
 ) -> Result<Option<usize>> {
-    if let Some(requested) = args.session {
+    if let Some(requested) = requested {
         if requested == 0 || requested > sessions.len() {
             bail!(
-                "--session {requested} is out of range; this log has {} session(s)",
+                "{flag} {requested} is out of range; this log has {} session(s)",
                 sessions.len()
             );
         }
@@ -155,8 +183,8 @@ fn choose(
     // message beats cursive's own error from inside a pipe or a CI job.
     if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
         bail!(
-            "this log holds {} sessions and there is no terminal to show the picker on; \
-             pass --session <1..{}> to choose one",
+            "{source_name} holds {} sessions and there is no terminal to show the picker on; \
+             pass {flag} <1..{}> to choose one",
             sessions.len(),
             sessions.len()
         );
@@ -194,15 +222,17 @@ fn report_window(session: &Session, log: &FlightLog) {
     }
 }
 
-/// Where the four PNGs go.
+/// Where the PNGs go.
 struct OutputPaths {
     airbrakes: PathBuf,
     deployment: PathBuf,
     misc: PathBuf,
     payload: PathBuf,
+    /// Written only for a log with filter internals in it.
+    internals: PathBuf,
 }
 
-/// Name the four PNGs.
+/// Name the PNGs.
 ///
 /// The session number only enters the filename when there was a choice to make,
 /// so the ordinary one-flight case produces predictable names.
@@ -236,6 +266,7 @@ fn output_paths(
         deployment: dir.join(format!("{stem}_deployment.png")),
         misc: dir.join(format!("{stem}_misc.png")),
         payload: dir.join(format!("{stem}_payload.png")),
+        internals: dir.join(format!("{stem}_internals.png")),
     })
 }
 
@@ -273,6 +304,10 @@ mod tests {
             p.payload.file_name().unwrap(),
             "hil_dual_2026-08-17_payload.png"
         );
+        assert_eq!(
+            p.internals.file_name().unwrap(),
+            "hil_dual_2026-08-17_internals.png"
+        );
 
         let p = output_paths(&input, &args(None), 1, 3).unwrap();
         assert_eq!(
@@ -299,7 +334,9 @@ mod tests {
         let sessions = find_sessions(&log, 0.0);
         let mut a = args(None);
         a.session = Some(4);
-        let err = choose(&sessions, &log, "x.csv", &a).unwrap_err().to_string();
+        let err = choose(&sessions, &log, "x.csv", a.session, "--session")
+            .unwrap_err()
+            .to_string();
         assert!(err.contains("out of range"), "{err}");
         assert!(err.contains("1 session"), "{err}");
     }
@@ -368,6 +405,9 @@ mod tests {
         assert_eq!(sessions.len(), 2);
         let mut a = args(None);
         a.session = Some(2);
-        assert_eq!(choose(&sessions, &log, "x.csv", &a).unwrap(), Some(1));
+        assert_eq!(
+            choose(&sessions, &log, "x.csv", a.session, "--session").unwrap(),
+            Some(1)
+        );
     }
 }
@@ -0,0 +1,895 @@
+//! `replay-estimators`: fly a downloaded flight log's own sensor data back
+//! through this build's estimators and MPC, and say where the result parts
+//! company with what flew.
+//!
+//! The fast record holds everything [`FlightEstimators::update`] was handed —
+//! the raw IMU in the chip's axes, the barometer, the magnetometer and the
+//! brake command — each with the timestamp it was taken at. Feeding those
+//! rows back in order is a re-fly of the flight computer on the flight's own
+//! data, so a tuning change can be judged against the one flight that matters
+//! before it is flashed. It is open loop, necessarily: the airframe flew the
+//! brakes that were commanded on the day, so the airbrakes half's drag fit and
+//! the MPC's servo state read the *flown* command, and the replayed command is
+//! only ever written down beside it.
+//!
+//! Two things keep a replay of unchanged code from being an exact copy:
+//!
+//! * The firmware's estimator saw every sample; the log holds the ones that
+//!   reached the card. A `record_count` gap is a sample the replay never sees.
+//! * The config is whatever `--config` says. A card edited since the flight
+//!   no longer holds what flew, so keep the TOML the flight was flown on.
+//!
+//! The flight config is the airframe `sim::osiris` mirrors from VLF5's
+//! `FLIGHT_CONFIG`, with the day's part — the deployment profile, both Mach
+//! lockouts and the target apogee — taken from the avionics config TOML that
+//! `rocket-cli config` edits, exactly as the firmware overlays its config
+//! block on the build config at boot. `--flight-config` swaps the airframe
+//! for a flight config file (`rocket-cli flight-config`), checked before
+//! anything is replayed; the card is laid over it the same way, and without
+//! `--config` the file flies as it stands.
+//!
+//! The log's GPS fixes go back in too, each once, on the first row of the
+//! slow snapshot that carried it — which is as close as the log gets to when
+//! the receiver produced it. Only a profile with GPS aiding fuses them, and
+//! the card cannot say that it has, so `--gps-aiding` turns it on: the way
+//! to see what the receiver would have done for a flight that flew without.
+//!
+//! The output keeps the download's names for the flown columns, so
+//! `plot-flight-log` still draws the flown flight from it, and puts each
+//! replayed value beside its flown one under a `replayed_` prefix.
+//!
+//! Both Kalman filters' innovations and covariances go in too — replayed on
+//! every sample, flown wherever a `debug-internals` build logged them — and
+//! the report ends with the normalised-innovation-squared check on each
+//! (see [`air_brakes_controller_core::kf_internals`]): a tuning change to Q
+//! or R is judged by whether it makes the filter believe its own covariance,
+//! and the replay is where that is cheap to find out.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use air_brakes_controller_core::airbrakes_estimator::MachLockoutConfig;
+use air_brakes_controller_core::sim::closed_loop::{CONTROL_PERIOD_US, flight_stage};
+use air_brakes_controller_core::sim::osiris::osiris_config;
+use air_brakes_controller_core::{
+    AirBrakesMPC, BaroGateOutcome, EstimatorLogSample, FlightConfig, FlightEstimators,
+    FlightProfile, GpsAiding, ImuSample, KfInternals, NisSummary, RocketState, ServoModel,
+    ServoState,
+};
+use anyhow::{Context, Result, bail};
+use firmware_common_new::can_bus::messages::vl_status::FlightStage;
+use firmware_common_new::flight_data_record::{
+    AIRBRAKES_STATE_SHIFT, AirbrakesState, DEPLOYMENT_GPS_FUSION_SHIFT, GpsFusion,
+};
+use firmware_common_new::gps::GPSData;
+use firmware_common_new::flight_storage::AvionicsConfig;
+use firmware_common_new::readings::BaroData;
+use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
+use packed_struct::PrimitiveEnum as _;
+
+use crate::args::ReplayEstimatorsArgs;
+use crate::avionics_config::ConfigFile;
+use crate::plot::choose;
+use crate::plot::events::rising_edges;
+use crate::plot::log_csv::FlightLog;
+use crate::plot::session::{Session, WindowSource, find_sessions};
+use crate::usb_storage::cell;
+
+/// What the replayed flight computer produced on one row of the log.
+struct ReplayedRow {
+    /// [`flight_stage`] of the deployment half's state after this row, as the
+    /// firmware logs it.
+    stage: FlightStage,
+    sample: EstimatorLogSample,
+    pyro: Option<PyroSelect>,
+    /// The replayed MPC's last command and prediction, held between control
+    /// ticks the way the log holds the flown ones.
+    commanded_extension: Option<f32>,
+    predicted_apogee_asl: Option<f32>,
+}
+
+/// `build` — VLF5's build config, or a flight config file standing in for
+/// it — with `avionics` laid over it, the way the firmware builds its
+/// estimators at boot.
+///
+/// The airbrakes lockout's crossing altitude is not on the card — it is a
+/// property of the airframe and motor, not of the day — so a card lockout
+/// keeps the build config's.
+pub fn flight_config(build: FlightConfig, avionics: &AvionicsConfig) -> FlightConfig {
+    let mut config = build;
+    config.profile = FlightProfile::from(avionics);
+    let crossing = config
+        .airbrakes
+        .mach_lockout
+        .as_ref()
+        .map(|lockout| lockout.subsonic_crossing_altitude_asl);
+    config.airbrakes.mach_lockout = match (avionics.mach_lockout.airbrakes, crossing) {
+        (Some(durations), Some(subsonic_crossing_altitude_asl)) => Some(MachLockoutConfig {
+            earliest_subsonic_after_ignition_us: durations.earliest_subsonic_after_ignition_us,
+            force_birth_after_ignition_us: durations.force_birth_after_ignition_us,
+            subsonic_crossing_altitude_asl,
+        }),
+        _ => None,
+    };
+    config
+}
+
+/// ISA pressure altitude, as `BaroData::altitude_asl` computes it for the
+/// estimators on the board. The temperature does not enter it.
+pub(crate) fn baro_altitude_asl(pressure: f32) -> f32 {
+    BaroData {
+        temperature: 0.0,
+        pressure,
+    }
+    .altitude_asl()
+}
+
+pub(crate) fn finite(values: &[f32], row: usize) -> Option<f32> {
+    Some(values[row]).filter(|v| v.is_finite())
+}
+
+pub(crate) fn required<'a>(log: &'a FlightLog, name: &str) -> Result<&'a [f32]> {
+    log.column(name)
+        .with_context(|| format!("the log has no `{name}` column to replay"))
+}
+
+/// The log's GPS fixes, each handed over once, on the first row of the slow
+/// snapshot that carried it.
+pub(crate) struct LoggedGps<'a> {
+    snapshot: &'a [f32],
+    altitude: &'a [f32],
+    sats: &'a [f32],
+    hdop: &'a [f32],
+    vdop: &'a [f32],
+    last_fix: Option<[f32; 4]>,
+}
+
+impl<'a> LoggedGps<'a> {
+    /// `None` for a log without the slow record's GPS columns.
+    pub fn new(log: &'a FlightLog) -> Option<Self> {
+        Some(Self {
+            snapshot: log.column("slow_timestamp_us")?,
+            altitude: log.column("gps_altitude_asl")?,
+            sats: log.column("num_sats")?,
+            hdop: log.column("hdop")?,
+            vdop: log.column("vdop")?,
+            last_fix: None,
+        })
+    }
+
+    /// The fix that is new on row `i`, if one is. Rows must be asked in
+    /// order.
+    ///
+    /// A fix is new on the first row of a snapshot whose GPS differs from
+    /// the last one handed over: the slow record runs faster than most
+    /// receivers, and the firmware hands a fix over once.
+    pub fn fix(&mut self, i: usize) -> Option<GPSData> {
+        let new_snapshot = i == 0 || self.snapshot[i].to_bits() != self.snapshot[i - 1].to_bits();
+        let fields = [self.altitude[i], self.sats[i], self.hdop[i], self.vdop[i]];
+        if !new_snapshot || !fields[0].is_finite() || self.last_fix == Some(fields) {
+            return None;
+        }
+        self.last_fix = Some(fields);
+        Some(GPSData {
+            timestamp: None,
+            lat_lon: None,
+            gps_altitude_asl: Some(fields[0]),
+            num_of_fix_satellites: if fields[1].is_finite() {
+                fields[1] as u8
+            } else {
+                0
+            },
+            hdop: Some(fields[2]).filter(|v| v.is_finite()),
+            vdop: Some(fields[3]).filter(|v| v.is_finite()),
+            pdop: None,
+        })
+    }
+}
+
+/// Feed `rows` of `log` through a fresh [`FlightEstimators`] and MPC.
+///
+/// The replay stops at the first row without a pressure or a timestamp:
+/// that is a truncated final line, and the estimators cannot step without a
+/// barometer.
+fn replay(
+    log: &FlightLog,
+    rows: Range<usize>,
+    config: &FlightConfig,
+    target_apogee_agl: f32,
+) -> Result<Vec<ReplayedRow>> {
+    let acc = [
+        required(log, "acc_x")?,
+        required(log, "acc_y")?,
+        required(log, "acc_z")?,
+    ];
+    let gyro = [
+        required(log, "gyro_x")?,
+        required(log, "gyro_y")?,
+        required(log, "gyro_z")?,
+    ];
+    let pressure = required(log, "pressure")?;
+    let mag = match (
+        log.column("mag_x"),
+        log.column("mag_y"),
+        log.column("mag_z"),
+    ) {
+        (Some(x), Some(y), Some(z)) => Some([x, y, z]),
+        _ => None,
+    };
+    let flown_command = log.column("air_brakes_commanded_extension");
+    let reported = log.column("air_brakes_actual_extension");
+    let mut gps = LoggedGps::new(log);
+
+    let mut est = FlightEstimators::new(config.clone());
+    let mut mpc: Option<AirBrakesMPC> = None;
+    let mut next_control_us = log.timestamp_us[rows.start] as u64;
+    let mut commanded_extension = None;
+    let mut predicted_apogee_asl = None;
+    let mut out = Vec::with_capacity(rows.len());
+
+    for i in rows {
+        if !pressure[i].is_finite() || !log.timestamp_us[i].is_finite() {
+            break;
+        }
+        let t_us = log.timestamp_us[i] as u64;
+
+        if let Some(mag) = mag
+            && mag.iter().all(|axis| axis[i].is_finite())
+        {
+            // Logged in µT; the estimator reads tesla.
+            let tesla = [mag[0][i], mag[1][i], mag[2][i]].map(|ut| ut * 1e-6);
+            est.update_mag(t_us, &tesla.into());
+        }
+        // Logged in deg/s; the estimator reads rad/s.
+        let imu = acc
+            .iter()
+            .chain(&gyro)
+            .all(|axis| axis[i].is_finite())
+            .then(|| ImuSample {
+                acc: [acc[0][i], acc[1][i], acc[2][i]].into(),
+                gyro: [gyro[0][i], gyro[1][i], gyro[2][i]]
+                    .map(f32::to_radians)
+                    .into(),
+            });
+        let fix = gps.as_mut().and_then(|gps| gps.fix(i));
+        let flown = flown_command.and_then(|c| finite(c, i));
+        let (pyro, _staging, sample) = est.update(
+            t_us,
+            imu.as_ref(),
+            &[Some(baro_altitude_asl(pressure[i]))],
+            fix.as_ref(),
+            flown,
+        );
+
+        let state = est.state();
+        if mpc.is_none() && !matches!(state, RocketState::OnPad) {
+            mpc = Some(
+                AirBrakesMPC::new(
+                    config.airbrakes.rocket.clone(),
+                    est.atmosphere(),
+                    est.launch_pad_altitude_asl() + target_apogee_agl,
+                )
+                .with_servo(ServoModel::ICARUS),
+            );
+        }
+        if t_us >= next_control_us {
+            // The servo the MPC plans from is the one that flew: Icarus's
+            // report and the command it was chasing, both off the log.
+            let servo = reported
+                .and_then(|r| finite(r, i))
+                .map(|actual_extension| ServoState {
+                    actual_extension,
+                    commanded_extension: flown.unwrap_or(0.0),
+                });
+            let solution = est
+                .airbrakes_mpc_states()
+                .zip(mpc.as_ref())
+                .map(|(states, mpc)| mpc.update_with_servo(&states, servo));
+            commanded_extension = Some(solution.map_or(0.0, |s| s.extension_percentage));
+            predicted_apogee_asl = solution.map(|s| s.predicted_apogee_asl);
+            next_control_us = t_us + CONTROL_PERIOD_US;
+        }
+
+        out.push(ReplayedRow {
+            stage: flight_stage(&state),
+            sample,
+            pyro,
+            commanded_extension,
+            predicted_apogee_asl,
+        });
+    }
+    Ok(out)
+}
+
+/// Rows where a per-row state changes, against the last row that named one:
+/// `(row, from, to)`. A blank row neither ends nor starts a state.
+fn transitions(states: impl Iterator<Item = (usize, Option<u8>)>) -> Vec<(usize, (u8, u8))> {
+    let mut previous: Option<u8> = None;
+    let mut out = Vec::new();
+    for (row, state) in states {
+        let Some(state) = state else { continue };
+        if let Some(prev) = previous
+            && prev != state
+        {
+            out.push((row, (prev, state)));
+        }
+        previous = Some(state);
+    }
+    out
+}
+
+/// Match flown events to replayed ones of the same kind, in order: the n-th
+/// flown `k` pairs with the n-th replayed `k`. What is left over on either
+/// side is an event the other never had.
+fn pair_up<K: PartialEq + Copy>(
+    flown: &[(usize, K)],
+    replayed: &[(usize, K)],
+) -> Vec<(K, Option<usize>, Option<usize>)> {
+    let mut taken = vec![false; replayed.len()];
+    let mut out: Vec<(K, Option<usize>, Option<usize>)> = flown
+        .iter()
+        .map(|&(row, kind)| {
+            let matched = replayed
+                .iter()
+                .enumerate()
+                .find(|(j, (_, other))| !taken[*j] && *other == kind)
+                .map(|(j, &(replayed_row, _))| {
+                    taken[j] = true;
+                    replayed_row
+                });
+            (kind, Some(row), matched)
+        })
+        .collect();
+    for (j, &(row, kind)) in replayed.iter().enumerate() {
+        if !taken[j] {
+            out.push((kind, None, Some(row)));
+        }
+    }
+    out.sort_by_key(|&(_, flown, replayed)| flown.or(replayed));
+    out
+}
+
+fn stage_label(stage: u8) -> String {
+    FlightStage::from_primitive(stage)
+        .map_or_else(|| format!("stage {stage}"), |s| format!("{s:?}"))
+}
+
+/// The two bits the flags carry it in are its discriminant, so the CSV's
+/// discriminant goes back through the flags.
+fn airbrakes_state_label(state: u8) -> String {
+    format!(
+        "{:?}",
+        AirbrakesState::from_flags(state << AIRBRAKES_STATE_SHIFT)
+    )
+}
+
+fn gps_fusion_label(fusion: u8) -> String {
+    format!(
+        "{:?}",
+        GpsFusion::from_flags(fusion << DEPLOYMENT_GPS_FUSION_SHIFT)
+    )
+}
+
+/// Print one table of paired events and return how many differ. `at` turns
+/// a row into seconds on the report's axis.
+fn report(
+    title: &str,
+    pairs: &[(String, Option<usize>, Option<usize>)],
+    at: &dyn Fn(usize) -> f64,
+) -> usize {
+    println!("{title}:");
+    if pairs.is_empty() {
+        println!("  none, flown or replayed");
+        return 0;
+    }
+    let mut differences = 0;
+    for (label, flown, replayed) in pairs {
+        let verdict = match (flown, replayed) {
+            (Some(f), Some(r)) if f == r => "same row".to_string(),
+            (Some(f), Some(r)) => {
+                format!("{:+.0} ms  <-- differs", (at(*r) - at(*f)) * 1e3)
+            }
+            (Some(_), None) => "never replayed  <-- differs".to_string(),
+            (None, _) => "never flown  <-- differs".to_string(),
+        };
+        if verdict.ends_with("differs") {
+            differences += 1;
+        }
+        let time = |row: &Option<usize>| {
+            row.map_or_else(|| "—".to_string(), |r| format!("T{:+.3}", at(r)))
+        };
+        println!(
+            "  {label:<36} flown {:>10}  replayed {:>10}  {verdict}",
+            time(flown),
+            time(replayed)
+        );
+    }
+    differences
+}
+
+pub fn replay_estimators(args: &ReplayEstimatorsArgs) -> Result<()> {
+    let input = Path::new(&args.input);
+    let log = FlightLog::load(input)?;
+    let source_name = input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| args.input.clone());
+    let card = match &args.config {
+        Some(path) => Some(ConfigFile::load(Path::new(path))?.to_avionics_config()?),
+        None => None,
+    };
+    let mut config = match (&args.flight_config, &card) {
+        (Some(path), Some(card)) => {
+            flight_config(crate::flight_config::load(Path::new(path))?, card)
+        }
+        (Some(path), None) => {
+            println!(
+                "No --config: replaying on {path} as it stands, with the built-in target apogee."
+            );
+            crate::flight_config::load(Path::new(path))?
+        }
+        (None, Some(card)) => flight_config(osiris_config(), card),
+        (None, None) => {
+            println!(
+                "No --config: replaying with the built-in avionics config, which is only what \
+                 flew if the card held no config block."
+            );
+            flight_config(osiris_config(), &AvionicsConfig::default())
+        }
+    };
+    let avionics = card.unwrap_or_default();
+    if args.gps_aiding {
+        config.profile.gps_aiding = Some(GpsAiding::default());
+    }
+
+    let sessions = find_sessions(&log, 0.0);
+    let Some(index) = choose(&sessions, &log, &source_name, args.session, "--session")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let session = &sessions[index];
+    if session.window_source == WindowSource::NeverLeftThePad {
+        println!("note: this session never left the pad; replaying it anyway.");
+    }
+
+    let replayed = replay(
+        &log,
+        session.start..session.end,
+        &config,
+        avionics.target_apogee_agl,
+    )?;
+    let rows = session.start..session.start + replayed.len();
+    if replayed.is_empty() {
+        bail!("{source_name}: the chosen session has no row with a pressure to replay");
+    }
+    let gaps = rows
+        .clone()
+        .skip(1)
+        .filter(|&i| log.record_count[i] != log.record_count[i - 1] + 1)
+        .count();
+
+    let output = output_path(input, args.output.as_deref(), index, sessions.len());
+    write_replay_csv(&output, &log, session, rows.start, &replayed)?;
+
+    let t0 = log.timestamp_us[session.flight_start];
+    let at = |row: usize| (log.timestamp_us[row] - t0) / 1e6;
+    println!(
+        "Replayed {} row(s) of {source_name} (T+0 is the flown liftoff); {} gap(s) in \
+         record_count, each a sample the flight computer saw and the replay did not.",
+        replayed.len(),
+        gaps
+    );
+
+    let flown_stages = transitions(rows.clone().map(|i| (i, log.stage[i])));
+    let replayed_stages = transitions(
+        replayed
+            .iter()
+            .enumerate()
+            .map(|(j, r)| (rows.start + j, Some(r.stage as u8))),
+    );
+    let stage_pairs: Vec<_> = pair_up(&flown_stages, &replayed_stages)
+        .into_iter()
+        .map(|((from, to), f, r)| {
+            (
+                format!("{} -> {}", stage_label(from), stage_label(to)),
+                f,
+                r,
+            )
+        })
+        .collect();
+
+    let flown_airbrakes = transitions(rows.clone().map(|i| (i, log.airbrakes_state[i])));
+    let replayed_airbrakes = transitions(
+        replayed
+            .iter()
+            .enumerate()
+            .map(|(j, r)| (rows.start + j, r.sample.airbrakes.map(|ab| ab.state as u8))),
+    );
+    let airbrakes_pairs: Vec<_> = pair_up(&flown_airbrakes, &replayed_airbrakes)
+        .into_iter()
+        .map(|((from, to), f, r)| {
+            (
+                format!(
+                    "{} -> {}",
+                    airbrakes_state_label(from),
+                    airbrakes_state_label(to)
+                ),
+                f,
+                r,
+            )
+        })
+        .collect();
+
+    // Flown: the fire bit's rising edge, the first continuity report after
+    // the command. Replayed: the command itself. So a flown pyro reads a
+    // few milliseconds late against an identical replay, never early.
+    let mut flown_pyros = Vec::new();
+    for (column, pyro) in [
+        ("pyro_drogue_fire", PyroSelect::PyroDrogue),
+        ("pyro_main_fire", PyroSelect::PyroMain),
+        ("pyro_separation_fire", PyroSelect::PyroSeparation),
+        ("pyro_backup_drogue_fire", PyroSelect::PyroBackupDrogue),
+        ("pyro_backup_main_fire", PyroSelect::PyroBackupMain),
+    ] {
+        if let Some(values) = log.column(column) {
+            flown_pyros.extend(
+                rising_edges(values, rows.start, rows.end)
+                    .into_iter()
+                    .map(|row| (row, pyro)),
+            );
+        }
+    }
+    flown_pyros.sort_by_key(|&(row, _)| row);
+    let replayed_pyros: Vec<_> = replayed
+        .iter()
+        .enumerate()
+        .filter_map(|(j, r)| r.pyro.map(|p| (rows.start + j, p)))
+        .collect();
+    let pyro_pairs: Vec<_> = pair_up(&flown_pyros, &replayed_pyros)
+        .into_iter()
+        .map(|(pyro, f, r)| (format!("{pyro:?}"), f, r))
+        .collect();
+
+    let differences = report("Flight stage", &stage_pairs, &at)
+        + report("Airbrakes state", &airbrakes_pairs, &at)
+        + report("Pyro commands (flown: fire bit)", &pyro_pairs, &at);
+    match differences {
+        0 => println!("The replay reproduces every flown transition and pyro command."),
+        n => println!("{n} transition(s) or pyro command(s) differ from the flight."),
+    }
+    report_consistency(&log, rows, &replayed);
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+/// The two filters whose `debug-internals` columns the download writes, by
+/// column prefix, and the columns each has — the order
+/// [`write_replay_csv`] pairs them in.
+const KF_FILTERS: [&str; 2] = ["deployment_kf", "airbrakes_kf"];
+const KF_INTERNALS_FIELDS: [&str; 5] = [
+    "innovation",
+    "innovation_variance",
+    "altitude_variance",
+    "velocity_variance",
+    "nis",
+];
+
+/// One filter's flown internals on `row`, back from the download's columns:
+/// `None` on every row the card held no record for. The deployment filter's
+/// gate verdict comes off the same row's gate flags, so a reading the gate
+/// threw out stays out of the NIS.
+fn flown_kf_internals(log: &FlightLog, filter: &str, row: usize) -> Option<KfInternals> {
+    let field = |name: &str| {
+        log.column(&format!("{filter}_{name}"))
+            .and_then(|c| finite(c, row))
+    };
+    let flag = |name: &str| {
+        log.column(name)
+            .and_then(|c| finite(c, row))
+            .is_some_and(|v| v >= 0.5)
+    };
+    let gate = if filter != "deployment_kf" {
+        BaroGateOutcome::Accepted
+    } else if flag("deployment_baro_resync") {
+        BaroGateOutcome::Resynced
+    } else if flag("deployment_baro_gate_reject") {
+        BaroGateOutcome::Rejected
+    } else {
+        BaroGateOutcome::Accepted
+    };
+    Some(KfInternals {
+        innovation: field("innovation")?,
+        innovation_variance: field("innovation_variance")?,
+        altitude_variance: field("altitude_variance")?,
+        velocity_variance: field("velocity_variance")?,
+        gate,
+    })
+}
+
+/// The NIS check on both filters, flown and replayed, one line each.
+///
+/// The replay is summarised over every sample it fused; the flight over the
+/// rows a `debug-internals` build logged, which is every eighth — fewer
+/// samples and a wider band, the same test. A flight build logged none, and
+/// says so rather than printing a verdict on nothing.
+fn report_consistency(log: &FlightLog, rows: Range<usize>, replayed: &[ReplayedRow]) {
+    println!("Filter consistency (normalised innovation squared):");
+    let replayed_internals: [fn(&EstimatorLogSample) -> Option<KfInternals>; 2] = [
+        |s| s.deployment_internals,
+        |s| s.airbrakes.and_then(|ab| ab.internals),
+    ];
+    for (filter, internals) in KF_FILTERS.iter().zip(replayed_internals) {
+        let mut flown = NisSummary::new();
+        for i in rows.clone() {
+            if let Some(kf) = flown_kf_internals(log, filter, i) {
+                flown.add(&kf);
+            }
+        }
+        let mut replay = NisSummary::new();
+        for kf in replayed.iter().filter_map(|r| internals(&r.sample)) {
+            replay.add(&kf);
+        }
+        if flown.count() == 0 {
+            println!("  {filter:<14} flown     not logged (not a debug-internals build)");
+        } else {
+            println!("  {filter:<14} flown     {flown}");
+        }
+        println!("  {filter:<14} replayed  {replay}");
+    }
+}
+
+/// `--output`, or `<input>_replayed.csv` beside the input, numbered by
+/// session only when the log held more than one — the same rule
+/// `plot-flight-log` names its figures by.
+fn output_path(input: &Path, output: Option<&str>, index: usize, session_count: usize) -> PathBuf {
+    if let Some(output) = output {
+        return PathBuf::from(output);
+    }
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "flight_log".to_string());
+    let name = if session_count > 1 {
+        format!("{stem}_s{}_replayed.csv", index + 1)
+    } else {
+        format!("{stem}_replayed.csv")
+    };
+    input.with_file_name(name)
+}
+
+fn write_replay_csv(
+    path: &Path,
+    log: &FlightLog,
+    session: &Session,
+    start: usize,
+    replayed: &[ReplayedRow],
+) -> Result<()> {
+    let mut w =
+        csv::Writer::from_path(path).with_context(|| format!("creating {}", path.display()))?;
+    // Flown column, then its replay, for every estimator output the log has a
+    // flown twin of; the attitude and the drag scale were never logged, so
+    // they are replayed only. `stage_differs` is the one verdict per row:
+    // everything else is for reading side by side.
+    let mut header: Vec<String> = [
+        "record_count",
+        "timestamp_us",
+        "t_s",
+        "flight_stage",
+        "replayed_flight_stage",
+        "stage_differs",
+        "deployment_kf_altitude_asl",
+        "replayed_deployment_kf_altitude_asl",
+        "deployment_kf_vertical_velocity",
+        "replayed_deployment_kf_vertical_velocity",
+        "deployment_baro_gate_reject",
+        "replayed_deployment_baro_gate_reject",
+        "deployment_baro_resync",
+        "replayed_deployment_baro_resync",
+        "deployment_gps_fusion",
+        "replayed_deployment_gps_fusion",
+        "launch_pad_altitude_asl",
+        "replayed_launch_pad_altitude_asl",
+        "airbrakes_kf_altitude_asl",
+        "replayed_airbrakes_kf_altitude_asl",
+        "airbrakes_kf_vertical_velocity",
+        "replayed_airbrakes_kf_vertical_velocity",
+        "airbrakes_kf_tilt_deg",
+        "replayed_airbrakes_kf_tilt_deg",
+        "airbrakes_pad_calibrated",
+        "replayed_airbrakes_pad_calibrated",
+        "airbrakes_burnout",
+        "replayed_airbrakes_burnout",
+        "airbrakes_state",
+        "replayed_airbrakes_state",
+        "replayed_airbrakes_roll_deg",
+        "replayed_airbrakes_pitch_deg",
+        "replayed_airbrakes_yaw_deg",
+        "replayed_airbrakes_cd_scale",
+        "pyro_drogue_fire",
+        "replayed_pyro_drogue_commanded",
+        "pyro_main_fire",
+        "replayed_pyro_main_commanded",
+        "pyro_separation_fire",
+        "replayed_pyro_separation_commanded",
+        "pyro_backup_drogue_fire",
+        "replayed_pyro_backup_drogue_commanded",
+        "pyro_backup_main_fire",
+        "replayed_pyro_backup_main_commanded",
+        "air_brakes_commanded_extension",
+        "replayed_air_brakes_commanded_extension",
+        "mpc_predicted_apogee_asl",
+        "replayed_mpc_predicted_apogee_asl",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    for filter in KF_FILTERS {
+        for field in KF_INTERNALS_FIELDS {
+            header.push(format!("{filter}_{field}"));
+            header.push(format!("replayed_{filter}_{field}"));
+        }
+    }
+    w.write_record(&header)?;
+
+    let t0 = log.timestamp_us[session.flight_start];
+    let number = |name: &str, row: usize| cell(log.column(name).and_then(|c| finite(c, row)));
+    let flag = |name: &str, row: usize| {
+        cell(
+            log.column(name)
+                .and_then(|c| finite(c, row))
+                .map(|v| v >= 0.5),
+        )
+    };
+    for (j, r) in replayed.iter().enumerate() {
+        let i = start + j;
+        let s = &r.sample;
+        let ab = s.airbrakes.as_ref();
+        let attitude = ab.and_then(|ab| ab.attitude);
+        let flown_stage = log.stage[i];
+        let replayed_stage = r.stage as u8;
+        let mut row = vec![
+            log.record_count[i].to_string(),
+            log.timestamp_us[i].to_string(),
+            format!("{:.6}", (log.timestamp_us[i] - t0) / 1e6),
+            flown_stage.map(stage_label).unwrap_or_default(),
+            format!("{:?}", r.stage),
+            cell(flown_stage.map(|f| f != replayed_stage)),
+            number("deployment_kf_altitude_asl", i),
+            cell(s.deployment_altitude_asl),
+            number("deployment_kf_vertical_velocity", i),
+            cell(s.deployment_vertical_velocity),
+            flag("deployment_baro_gate_reject", i),
+            cell(Some(s.deployment_baro_gate.rejected())),
+            flag("deployment_baro_resync", i),
+            cell(Some(s.deployment_baro_gate.resynced())),
+            log.gps_fusion[i].map(gps_fusion_label).unwrap_or_default(),
+            format!("{:?}", s.deployment_gps_fusion),
+            number("launch_pad_altitude_asl", i),
+            cell(Some(s.deployment_launch_pad_altitude_asl)),
+            number("airbrakes_kf_altitude_asl", i),
+            cell(ab.and_then(|ab| ab.altitude_asl)),
+            number("airbrakes_kf_vertical_velocity", i),
+            cell(ab.and_then(|ab| ab.vertical_velocity)),
+            number("airbrakes_kf_tilt_deg", i),
+            cell(ab.and_then(|ab| ab.tilt_rad).map(f32::to_degrees)),
+            flag("airbrakes_pad_calibrated", i),
+            cell(ab.map(|ab| ab.calibration_complete)),
+            flag("airbrakes_burnout", i),
+            cell(ab.map(|ab| ab.burnout_detected)),
+            log.airbrakes_state[i]
+                .map(airbrakes_state_label)
+                .unwrap_or_default(),
+            ab.map(|ab| format!("{:?}", ab.state)).unwrap_or_default(),
+            cell(attitude.map(|a| a.roll.to_degrees())),
+            cell(attitude.map(|a| a.pitch.to_degrees())),
+            cell(attitude.map(|a| a.yaw.to_degrees())),
+            cell(ab.and_then(|ab| ab.cd_scale)),
+            flag("pyro_drogue_fire", i),
+            cell(Some(r.pyro == Some(PyroSelect::PyroDrogue))),
+            flag("pyro_main_fire", i),
+            cell(Some(r.pyro == Some(PyroSelect::PyroMain))),
+            flag("pyro_separation_fire", i),
+            cell(Some(r.pyro == Some(PyroSelect::PyroSeparation))),
+            flag("pyro_backup_drogue_fire", i),
+            cell(Some(r.pyro == Some(PyroSelect::PyroBackupDrogue))),
+            flag("pyro_backup_main_fire", i),
+            cell(Some(r.pyro == Some(PyroSelect::PyroBackupMain))),
+            number("air_brakes_commanded_extension", i),
+            cell(r.commanded_extension),
+            number("mpc_predicted_apogee_asl", i),
+            cell(r.predicted_apogee_asl),
+        ];
+        // Flown only on the rows a `debug-internals` build logged them on;
+        // replayed on every row the filter fused a reading on.
+        for (filter, kf) in KF_FILTERS
+            .iter()
+            .zip([s.deployment_internals, ab.and_then(|ab| ab.internals)])
+        {
+            let values = kf.map(|kf| {
+                [
+                    kf.innovation,
+                    kf.innovation_variance,
+                    kf.altitude_variance,
+                    kf.velocity_variance,
+                    kf.nis(),
+                ]
+            });
+            for (f, field) in KF_INTERNALS_FIELDS.iter().enumerate() {
+                row.extend([
+                    number(&format!("{filter}_{field}"), i),
+                    cell(values.map(|v| v[f])),
+                ]);
+            }
+        }
+        w.write_record(&row)?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use firmware_common_new::flight_storage::{DeploymentConfig, MachLockoutDurations};
+
+    /// A transition is measured against the last row that said anything, so
+    /// a blank row between two stages neither hides the change nor makes two.
+    #[test]
+    fn a_blank_row_neither_hides_nor_doubles_a_transition() {
+        let states = [Some(2), None, Some(3), Some(3), None, None, Some(4)];
+        assert_eq!(
+            transitions(states.into_iter().enumerate()),
+            vec![(2, (2, 3)), (6, (3, 4))]
+        );
+    }
+
+    /// Pairing is by kind, in order: a replay that fires the drogue late and
+    /// never fires the main reports exactly that, not a main paired with a
+    /// drogue because they were both "the second pyro".
+    #[test]
+    fn events_pair_by_kind_and_leftovers_are_reported_on_their_side() {
+        let flown = [(10, 'd'), (20, 'm')];
+        let replayed = [(12, 'd'), (30, 'x')];
+        assert_eq!(
+            pair_up(&flown, &replayed),
+            vec![
+                ('d', Some(10), Some(12)),
+                ('m', Some(20), None),
+                ('x', None, Some(30)),
+            ]
+        );
+    }
+
+    /// The card's lockout durations reach the estimator, and the crossing
+    /// altitude the card cannot carry stays the build config's. A card with
+    /// no airbrakes lockout flies none, rather than the build config's.
+    #[test]
+    fn the_card_config_lays_over_the_build_config() {
+        let mut avionics = AvionicsConfig::default();
+        avionics.deployment = DeploymentConfig::Single {
+            minimum_deployment_altitude_agl: 300.0,
+            delay_us: 1_500_000,
+        };
+        let config = flight_config(osiris_config(), &avionics);
+        let lockout = config.airbrakes.mach_lockout.as_ref().unwrap();
+        let built = osiris_config().airbrakes.mach_lockout.unwrap();
+        let durations = avionics.mach_lockout.airbrakes.unwrap();
+        assert_eq!(
+            lockout.force_birth_after_ignition_us,
+            durations.force_birth_after_ignition_us
+        );
+        assert_eq!(
+            lockout.subsonic_crossing_altitude_asl,
+            built.subsonic_crossing_altitude_asl
+        );
+        assert_eq!(config.profile, FlightProfile::from(&avionics));
+
+        avionics.mach_lockout = MachLockoutDurations {
+            deployment_us: None,
+            airbrakes: None,
+        };
+        let config = flight_config(osiris_config(), &avionics);
+        assert!(config.airbrakes.mach_lockout.is_none());
+        assert_eq!(config.profile.mach_lockout_duration_us, None);
+    }
+}
@@ -0,0 +1,356 @@
+//! `smooth-flight-log`: the best trajectory a downloaded flight log supports,
+//! written back into the log as columns `plot-flight-log` overlays.
+//!
+//! The flown estimators are causal and tuned for what the board needs from
+//! them in the moment; this is the other question — where the rocket
+//! actually was — answered with the whole flight in hand by the core crate's
+//! Rauch-Tung-Striebel smoother ([`air_brakes_controller_core::smoother`]).
+//! The IMU, barometer and GPS go in exactly as `replay-estimators` reads
+//! them off the CSV.
+//!
+//! What the smoother is offered is decided here, from what the flight
+//! computer itself made of each reading:
+//!
+//! * a barometer reading the deployment gate threw out is left out, and so
+//!   is every reading while the airbrakes estimator was dead reckoning —
+//!   the Mach lockout, where the port reads the shock;
+//! * a GPS fix is offered on the pad, where it sets the GPS datum, and from
+//!   the flown apogee on, as the firmware's own aiding fuses them. In boost
+//!   the receiver's tracking loops are the thing being measured.
+//!
+//! The output is the input, every row and column of it, with the smoothed
+//! columns added (or replaced, for a log smoothed before), so the one file
+//! plots with the flown and smoothed traces on the same panels. Only the
+//! chosen session's rows are filled in.
+
+use std::path::{Path, PathBuf};
+
+use air_brakes_controller_core::ImuSample;
+use air_brakes_controller_core::smoother::{
+    Smoothed, SmoothedState, SmootherConfig, SmootherSample, smooth,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use firmware_common_new::flight_data_record::AirbrakesState;
+
+use crate::args::SmoothFlightLogArgs;
+use crate::plot::choose;
+use crate::plot::log_csv::FlightLog;
+use crate::plot::session::{Session, WindowSource, find_sessions};
+use crate::replay_estimators::{LoggedGps, baro_altitude_asl, finite, required};
+use crate::usb_storage::{cell, events_paths};
+
+/// The columns this writes, in order.
+const SMOOTHED_COLUMNS: [&str; 6] = [
+    "smoothed_altitude_asl",
+    "smoothed_vertical_velocity",
+    "smoothed_vertical_acc",
+    "smoothed_altitude_sigma",
+    "smoothed_velocity_sigma",
+    "smoothed_acc_sigma",
+];
+
+/// `--output`, or `<input>_smoothed.csv` beside the input. Not numbered by
+/// session: the file holds every row of the input.
+fn output_path(input: &Path, output: Option<&str>) -> PathBuf {
+    if let Some(output) = output {
+        return PathBuf::from(output);
+    }
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "flight_log".to_string());
+    input.with_file_name(format!("{stem}_smoothed.csv"))
+}
+
+/// A session smoothed, with what was offered and what was held back.
+pub(crate) struct SmoothedSession {
+    pub samples: Vec<SmootherSample>,
+    pub smoothed: Smoothed,
+    pub config: SmootherConfig,
+    /// Barometer readings the deployment gate had thrown out.
+    pub gated: usize,
+    /// Barometer readings inside the Mach lockout.
+    pub locked_out: usize,
+}
+
+/// Offer `session`'s rows of `log` to the smoother by the rules in the
+/// module docs, and smooth them: one state per row from `session.start`.
+/// `fit-airframe` reads the same trajectory.
+pub(crate) fn smooth_session(
+    log: &FlightLog,
+    session: &Session,
+    no_gps: bool,
+    source_name: &str,
+) -> Result<SmoothedSession> {
+    if session.window_source == WindowSource::NeverLeftThePad {
+        bail!("{source_name}: the chosen session never left the pad; there is no flight to smooth");
+    }
+
+    let acc = [
+        required(log, "acc_x")?,
+        required(log, "acc_y")?,
+        required(log, "acc_z")?,
+    ];
+    let gyro = [
+        required(log, "gyro_x")?,
+        required(log, "gyro_y")?,
+        required(log, "gyro_z")?,
+    ];
+    let pressure = required(log, "pressure")?;
+    let gate_reject = log.column("deployment_baro_gate_reject");
+    let mut gps = if no_gps { None } else { LoggedGps::new(log) };
+    // Until apogee a fix is read and dropped, so that the first one after
+    // it is new on its own row rather than on the one it was logged on.
+    let gps_from = session.apogee_row.unwrap_or(session.end);
+
+    // Stops at the first row without a timestamp: a truncated final line.
+    let mut samples = Vec::with_capacity(session.end - session.start);
+    let (mut gated, mut locked_out) = (0, 0);
+    for i in session.start..session.end {
+        if !log.timestamp_us[i].is_finite() {
+            break;
+        }
+        // Logged in deg/s; the smoother reads rad/s.
+        let imu = acc
+            .iter()
+            .chain(&gyro)
+            .all(|axis| axis[i].is_finite())
+            .then(|| ImuSample {
+                acc: [acc[0][i], acc[1][i], acc[2][i]].into(),
+                gyro: [gyro[0][i], gyro[1][i], gyro[2][i]]
+                    .map(f32::to_radians)
+                    .into(),
+            });
+        let rejected = gate_reject
+            .and_then(|c| finite(c, i))
+            .is_some_and(|v| v >= 0.5);
+        let dead_reckoning = log.airbrakes_state[i] == Some(AirbrakesState::DeadReckoning as u8);
+        let baro = finite(pressure, i).map(baro_altitude_asl);
+        if baro.is_some() {
+            gated += rejected as usize;
+            locked_out += (dead_reckoning && !rejected) as usize;
+        }
+        let fix = gps.as_mut().and_then(|gps| gps.fix(i));
+        samples.push(SmootherSample {
+            timestamp_us: log.timestamp_us[i] as u64,
+            imu,
+            baro_altitude_asl: baro.filter(|_| !rejected && !dead_reckoning),
+            gps: fix.filter(|_| i < session.flight_start || i >= gps_from),
+        });
+    }
+
+    let config = SmootherConfig {
+        gps: if no_gps {
+            None
+        } else {
+            SmootherConfig::default().gps
+        },
+        ..SmootherConfig::default()
+    };
+    let smoothed = smooth(&samples, session.flight_start - session.start, &config)
+        .map_err(|e| anyhow!("{source_name}: {e}"))?;
+    Ok(SmoothedSession {
+        samples,
+        smoothed,
+        config,
+        gated,
+        locked_out,
+    })
+}
+
+pub fn smooth_flight_log(args: &SmoothFlightLogArgs) -> Result<()> {
+    let input = Path::new(&args.input);
+    let log = FlightLog::load(input)?;
+    let source_name = input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| args.input.clone());
+
+    let sessions = find_sessions(&log, 0.0);
+    let Some(index) = choose(&sessions, &log, &source_name, args.session, "--session")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let session = &sessions[index];
+    let SmoothedSession {
+        samples,
+        smoothed,
+        config,
+        gated,
+        locked_out,
+    } = smooth_session(&log, session, args.no_gps, &source_name)?;
+
+    let output = output_path(input, args.output.as_deref());
+    write_smoothed_csv(input, &output, session.start, &smoothed.states)?;
+    // The events files go beside the output under its own name, so the
+    // smoothed log plots with the same rules as the one it came from.
+    let (from_csv, from_json) = events_paths(&input.to_string_lossy());
+    let (to_csv, to_json) = events_paths(&output.to_string_lossy());
+    for (from, to) in [(from_csv, to_csv), (from_json, to_json)] {
+        if Path::new(&from).exists() && from != to {
+            std::fs::copy(&from, &to).with_context(|| format!("copying {from} to {to}"))?;
+        }
+    }
+
+    println!(
+        "Smoothed {} row(s) of {source_name}: {} IMU sample(s), {} barometer reading(s) \
+         ({gated} dropped by the deployment gate, {locked_out} inside the Mach lockout), \
+         {} GPS fix(es).",
+        samples.len(),
+        smoothed.accel_fused,
+        smoothed.baro_fused,
+        smoothed.gps_fused,
+    );
+    let show = |v: Option<f32>| v.map_or_else(|| "—".to_string(), |v| format!("{v:.2} m"));
+    println!(
+        "Residual RMS: barometer {} (believed to {:.2} m), GPS {}.",
+        show(smoothed.baro_residual_rms_m),
+        config.baro_sigma_m,
+        show(smoothed.gps_residual_rms_m),
+    );
+    if let Some(offset) = smoothed.gps_datum_offset_m {
+        println!("GPS datum: {offset:+.1} m onto the barometer's, from the pad fixes.");
+    }
+    let t0 = log.timestamp_us[session.flight_start];
+    if let Some((j, apogee)) = smoothed
+        .states
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.altitude_asl.total_cmp(&b.1.altitude_asl))
+    {
+        let at = (log.timestamp_us[session.start + j] - t0) / 1e6;
+        print!(
+            "Smoothed apogee {:.1} m ASL (±{:.1}) at T{at:+.2} s",
+            apogee.altitude_asl, apogee.altitude_sigma
+        );
+        match session.apogee_asl {
+            Some(flown) => println!("; the flown estimators reported {flown:.1} m."),
+            None => println!("."),
+        }
+    }
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+/// `input`, every row and column, with [`SMOOTHED_COLUMNS`] filled in from
+/// `states` on the rows from `start`. Columns already there are overwritten
+/// on those rows and kept on the rest, so smoothing each session of a log in
+/// turn builds up one file.
+fn write_smoothed_csv(
+    input: &Path,
+    output: &Path,
+    start: usize,
+    states: &[SmoothedState],
+) -> Result<()> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(input)
+        .with_context(|| format!("opening {}", input.display()))?;
+    let mut header: Vec<String> = reader.headers()?.iter().map(str::to_owned).collect();
+    let width = header.len();
+    let existing: Option<Vec<usize>> = SMOOTHED_COLUMNS
+        .iter()
+        .map(|name| header.iter().position(|h| h == name))
+        .collect();
+    let columns = match existing {
+        Some(columns) => columns,
+        None => {
+            header.extend(SMOOTHED_COLUMNS.map(String::from));
+            (width..header.len()).collect()
+        }
+    };
+
+    let mut w =
+        csv::Writer::from_path(output).with_context(|| format!("creating {}", output.display()))?;
+    w.write_record(&header)?;
+    let mut record = csv::StringRecord::new();
+    let mut row = 0;
+    while reader
+        .read_record(&mut record)
+        .with_context(|| format!("reading {}", input.display()))?
+    {
+        // A short row — a truncated final line — is padded so the added
+        // columns still line up under their names.
+        let mut cells: Vec<String> = record.iter().map(str::to_owned).collect();
+        cells.resize(header.len(), String::new());
+        if let Some(state) = row.checked_sub(start).and_then(|j| states.get(j)) {
+            let values = [
+                state.altitude_asl,
+                state.vertical_velocity,
+                state.vertical_acceleration,
+                state.altitude_sigma,
+                state.velocity_sigma,
+                state.acceleration_sigma,
+            ];
+            for (&column, value) in columns.iter().zip(values) {
+                cells[column] = cell(Some(value));
+            }
+        }
+        w.write_record(&cells)?;
+        row += 1;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(altitude_asl: f32) -> SmoothedState {
+        SmoothedState {
+            altitude_asl,
+            vertical_velocity: 1.0,
+            vertical_acceleration: 2.0,
+            altitude_sigma: 0.5,
+            velocity_sigma: 0.25,
+            acceleration_sigma: 0.125,
+            axial_specific_force: None,
+            tilt_cos: 1.0,
+        }
+    }
+
+    /// The columns go on the end, only the smoothed rows are filled, and a
+    /// second pass replaces its own rows without disturbing the first's or
+    /// adding a second set of columns.
+    #[test]
+    fn smoothed_columns_are_appended_once_and_filled_per_session() {
+        let dir = std::env::temp_dir().join(format!("smooth_csv_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("log.csv");
+        std::fs::write(&input, "record_count,timestamp_us\n0,0\n1,10\n2,20\n3,30\n").unwrap();
+
+        let once = dir.join("once.csv");
+        write_smoothed_csv(&input, &once, 1, &[state(100.0), state(101.0)]).unwrap();
+        let text = std::fs::read_to_string(&once).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("record_count,timestamp_us,{}", SMOOTHED_COLUMNS.join(","))
+        );
+        assert_eq!(lines[1], "0,0,,,,,,");
+        assert!(lines[2].starts_with("1,10,100,1,2,"));
+        assert_eq!(lines[4], "3,30,,,,,,");
+
+        let twice = dir.join("twice.csv");
+        write_smoothed_csv(&once, &twice, 3, &[state(200.0)]).unwrap();
+        let text = std::fs::read_to_string(&twice).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0].split(',').count(), 2 + SMOOTHED_COLUMNS.len());
+        assert!(lines[2].starts_with("1,10,100,"));
+        assert!(lines[4].starts_with("3,30,200,"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn the_output_goes_beside_the_input_unless_named() {
+        assert_eq!(
+            output_path(Path::new("logs/flight.csv"), None),
+            PathBuf::from("logs/flight_smoothed.csv")
+        );
+        assert_eq!(
+            output_path(Path::new("logs/flight.csv"), Some("out.csv")),
+            PathBuf::from("out.csv")
+        );
+    }
+}
@@ -23,10 +23,11 @@ use firmware_common_new::{
         client::VLPTXError,
         packets::{
             VLPDownlinkPacket, VLPUplinkPacket,
+            fire_pyro::PYRO_CHANNELS,
             landed_telemetry::LandedTelemetryPacket,
             low_power_telemetry::LowPowerTelemetryPacket,
             self_test_result::{NodeStatus, SelfTestResultPacketBuilder},
-            telemetry::TelemetryPacket,
+            telemetry::{PyroChannelStatus, TelemetryPacket},
         },
     },
 };
@@ -191,8 +192,7 @@ impl MockVLPClient {
                     Some((10.1, 20.2)),
                     7.4,
                     25.5,
-                    false,
-                    false,
+                    [PyroChannelStatus::default(); PYRO_CHANNELS],
                     None,
                     // Latched, so the apogee reached so far stays readable
                     // through the lockout that blanks the live altitude.
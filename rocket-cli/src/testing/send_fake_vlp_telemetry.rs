@@ -21,7 +21,10 @@ use firmware_common_new::{
         lora_config::LoraConfig,
         packets::{
             VLPDownlinkPacket,
-            telemetry::{DeploymentKfState, IcarusAirBrakesState, TelemetryPacket},
+            fire_pyro::PYRO_CHANNELS,
+            telemetry::{
+                DeploymentKfState, IcarusAirBrakesState, PyroChannelStatus, TelemetryPacket,
+            },
         },
     },
 };
@@ -78,8 +81,7 @@ pub async fn send_fake_vlp_telemetry(args: SendVLPTelemetryArgs) -> Result<()> {
             Some((args.latitude, args.longitude)),
             7.4,
             25.5,
-            false,
-            false,
+            [PyroChannelStatus::default(); PYRO_CHANNELS],
             Some(DeploymentKfState {
                 altitude_agl,
                 vertical_velocity: 0.0,
@@ -4,31 +4,40 @@
 //! This module speaks the small vendor protocol in
 //! [`firmware_common_new::flight_storage`]: a vendor control transfer carries a
 //! [`CliRequest`] in `wValue`, and the device replies on the bulk-IN endpoint
-//! with a header followed (for downloads) by the raw SD data blocks.
+//! with a header followed (for downloads) by the raw SD data blocks. The same
+//! channel carries the avionics config block; see [`read_config`].
 
 use anyhow::Context as _;
 use anyhow::{Result, anyhow, bail};
 use rusb::{Context, DeviceHandle, Direction, Recipient, RequestType, UsbContext};
 use std::time::{Duration, Instant};
 
+use air_brakes_controller_core::{KfInternals, StagingEvent};
 use firmware_common_new::flight_data_record::{
     FlightDataRecord, NodeStatusRecord, PYRO_DROGUE_CONTINUITY, PYRO_DROGUE_FIRE,
     PYRO_MAIN_CONTINUITY,
-    PYRO_MAIN_FIRE, PYRO_SHORT_CIRCUIT, AIRBRAKES_PAD_CALIBRATED,
+    PYRO_MAIN_FIRE, PYRO_SHORT_CIRCUIT, PYRO_SEPARATION_CONTINUITY, PYRO_SEPARATION_FIRE,
+    PYRO_BACKUP_DROGUE_CONTINUITY, PYRO_BACKUP_DROGUE_FIRE, PYRO_BACKUP_MAIN_CONTINUITY,
+    PYRO_BACKUP_MAIN_FIRE, AIRBRAKES_PAD_CALIBRATED,
     AirbrakesState,
     AIRBRAKES_BURNOUT,
-    DEPLOYMENT_BARO_RESYNC, DEPLOYMENT_BARO_GATE_REJECT,
-    merge_log_records,
+    DEPLOYMENT_BARO_RESYNC, DEPLOYMENT_BARO_GATE_REJECT, BaroHealth, GpsFusion,
+    FlightEvent, FlightEventRow, KfInternalsRecord, collect_log_events, merge_log_records,
 };
 use firmware_common_new::can_bus::messages::amp_status::PowerOutputStatus;
 use firmware_common_new::can_bus::messages::custom_payload_status::ExperimentChannelFlags;
 use firmware_common_new::flight_storage::{
-    BLOCK_SIZE, HEADER_LEN, RESPONSE_MAGIC, STORAGE_VERSION, decode_response_header,
-    parse_log_records,
+    BLOCK_SIZE, DATA_START_BLOCK, HEADER_LEN, RESPONSE_MAGIC, STORAGE_VERSION,
+    SUPERBLOCK_FLAG_COMPRESSED, SUPERBLOCK_SLOTS, decode_response_header, latest_superblock,
+    parse_log_records, recover_log_extent,
 };
+use firmware_common_new::vlp::packets::change_mode::Mode;
+use firmware_common_new::vlp::packets::fire_pyro::PyroSelect;
 use firmware_common_new::vlp::usb::CliRequest;
 use packed_struct::PrimitiveEnum as _;
 
+use crate::args::NodeTypeEnum;
+
 /// USB vendor/product IDs for the WinUSB flight-log interface.
 const VLF5_USB_VID: u16 = 0xc0de;
 const VLF5_USB_PID: u16 = 0xcafe;
@@ -60,12 +69,22 @@ fn find_and_open() -> Result<DeviceHandle<Context>> {
 /// Send a [`CliRequest`] as a vendor control transfer (the command rides in
 /// `wValue`; `bRequest` is unused).
 fn send_request(handle: &DeviceHandle<Context>, request: CliRequest) -> Result<()> {
+    send_request_with_data(handle, request, &[])
+}
+
+/// [`send_request`] with a data stage, for the one request that carries a
+/// payload to the device ([`CliRequest::WriteConfig`]).
+fn send_request_with_data(
+    handle: &DeviceHandle<Context>,
+    request: CliRequest,
+    data: &[u8],
+) -> Result<()> {
     handle.write_control(
         rusb::request_type(Direction::Out, RequestType::Vendor, Recipient::Interface),
         0,
         request as u16,
         INTERFACE as u16,
-        &[],
+        data,
         Duration::from_secs(2),
     )?;
     Ok(())
@@ -142,7 +161,7 @@ fn read_response(handle: &DeviceHandle<Context>) -> Result<Vec<u8>> {
                     data.drain(..off);
                 }
                 if data.len() >= HEADER_LEN {
-                    let (_record_count, storage_version, block_count) =
+                    let (_record_count, storage_version, block_count, _flags) =
                         decode_response_header(&data[..HEADER_LEN])
                             .ok_or_else(|| anyhow!("device sent an invalid response header"))?;
                     if storage_version != STORAGE_VERSION {
@@ -175,6 +194,19 @@ fn read_response(handle: &DeviceHandle<Context>) -> Result<Vec<u8>> {
 /// Read just the response header. Used by `List`/`Clear`, which reply with
 /// metadata only (no data blocks follow).
 fn read_header(handle: &DeviceHandle<Context>) -> Result<[u8; HEADER_LEN]> {
+    let frame = read_short_response(handle, 0)?;
+    let mut header = [0u8; HEADER_LEN];
+    header.copy_from_slice(&frame[..HEADER_LEN]);
+    Ok(header)
+}
+
+/// Read a response header plus a fixed `body_len` bytes after it.
+///
+/// Read as one frame rather than header-then-body because a 64-byte bulk
+/// packet carries the header and the first 48 body bytes together; reading
+/// the header alone would throw those away.
+fn read_short_response(handle: &DeviceHandle<Context>, body_len: usize) -> Result<Vec<u8>> {
+    let frame_len = HEADER_LEN + body_len;
     let mut data: Vec<u8> = Vec::new();
     let mut buf = vec![0u8; 64];
     // Generous deadline: after an interrupted download the device may still be
@@ -186,10 +218,9 @@ fn read_header(handle: &DeviceHandle<Context>) -> Result<[u8; HEADER_LEN]> {
             if off > 0 {
                 data.drain(..off);
             }
-            if data.len() >= HEADER_LEN {
-                let mut header = [0u8; HEADER_LEN];
-                header.copy_from_slice(&data[..HEADER_LEN]);
-                return Ok(header);
+            if data.len() >= frame_len {
+                data.truncate(frame_len);
+                return Ok(data);
             }
         } else if data.len() >= RESPONSE_MAGIC.len() {
             let drop = data.len() - (RESPONSE_MAGIC.len() - 1);
@@ -208,10 +239,15 @@ fn read_header(handle: &DeviceHandle<Context>) -> Result<[u8; HEADER_LEN]> {
     }
 }
 
+/// What one download decodes to: the on-card record count, the merged CSV
+/// rows, and the event records that go beside them.
+type DownloadedLog = (u32, Vec<FlightDataRecord>, Vec<FlightEventRow>);
+
 /// Split the raw block stream into merged CSV rows.
-fn parse_records(data: &[u8]) -> Result<(u32, Vec<FlightDataRecord>)> {
-    let (log_record_count, storage_version, block_count) = decode_response_header(data)
-        .ok_or_else(|| anyhow!("device sent an invalid response header"))?;
+fn parse_records(data: &[u8]) -> Result<DownloadedLog> {
+    let (log_record_count, storage_version, block_count, superblock_flags) =
+        decode_response_header(data)
+            .ok_or_else(|| anyhow!("device sent an invalid response header"))?;
     if storage_version != STORAGE_VERSION {
         bail!(
             "unsupported storage version {storage_version} (this rocket-cli reads \
@@ -227,13 +263,64 @@ fn parse_records(data: &[u8]) -> Result<(u32, Vec<FlightDataRecord>)> {
             expected_bytes
         );
     }
+    let (rows, events) = parse_blocks(log_record_count, blocks, block_count, superblock_flags)?;
+    Ok((log_record_count, rows, events))
+}
+
+/// [`parse_records`] for a [`CliRequest::DownloadRaw`] reply: read the
+/// superblock copies ourselves and take the log out to where it really ends,
+/// not where the superblock last said it did.
+fn parse_raw_records(data: &[u8]) -> Result<DownloadedLog> {
+    let (_record_count, storage_version, block_count, _flags) = decode_response_header(data)
+        .ok_or_else(|| anyhow!("device sent an invalid response header"))?;
+    if storage_version != STORAGE_VERSION {
+        bail!(
+            "unsupported storage version {storage_version} (this rocket-cli reads \
+             v{STORAGE_VERSION})"
+        );
+    }
+    let card = &data[HEADER_LEN..];
+    let card_blocks = (block_count as usize).min(card.len() / BLOCK_SIZE);
+    if card_blocks < DATA_START_BLOCK as usize {
+        bail!("response truncated before the superblocks");
+    }
+    let [slot_a, slot_b] =
+        SUPERBLOCK_SLOTS.map(|i| &card[i as usize * BLOCK_SIZE..][..BLOCK_SIZE]);
+    let info = latest_superblock(slot_a.try_into()?, slot_b.try_into()?)
+        .ok_or_else(|| anyhow!("neither superblock copy on the card is valid"))?;
+
+    let data_blocks = &card[DATA_START_BLOCK as usize * BLOCK_SIZE..card_blocks * BLOCK_SIZE];
+    let extent = recover_log_extent(&info, data_blocks);
+    if extent.record_count > info.record_count {
+        println!(
+            "Recovered {} record(s) in {} block(s) past the superblock's end",
+            extent.record_count - info.record_count,
+            extent.block_count.saturating_sub(info.block_count)
+        );
+    }
+    let flags = if info.compressed {
+        SUPERBLOCK_FLAG_COMPRESSED
+    } else {
+        0
+    };
+    let (rows, events) = parse_blocks(extent.record_count, data_blocks, extent.block_count, flags)?;
+    Ok((extent.record_count, rows, events))
+}
 
+/// Decode `block_count` data blocks into merged rows and events, warning
+/// about what could not be trusted.
+fn parse_blocks(
+    log_record_count: u32,
+    blocks: &[u8],
+    block_count: u32,
+    superblock_flags: u32,
+) -> Result<(Vec<FlightDataRecord>, Vec<FlightEventRow>)> {
     // A CRC failure no longer aborts the export. One bad block out of thousands
     // must not make a whole flight unrecoverable, so the parser keeps going and
     // tags every record it read out of a bad block; those rows carry
     // `source_block_crc_failed` in the CSV. The warning stays, because the
     // per-row column is only useful to someone who knows to look for it.
-    let parsed = parse_log_records(log_record_count, blocks, block_count)
+    let parsed = parse_log_records(log_record_count, blocks, block_count, superblock_flags)
         .ok_or_else(|| anyhow!("failed to decode the log stream — data may be corrupt"))?;
     if parsed.crc_failed_blocks > 0 {
         eprintln!(
@@ -249,9 +336,10 @@ fn parse_records(data: &[u8]) -> Result<(u32, Vec<FlightDataRecord>)> {
             parsed.invalid_records
         );
     }
-    let merged = merge_log_records(&parsed.records);
-
-    Ok((log_record_count, merged))
+    Ok((
+        merge_log_records(&parsed.records),
+        collect_log_events(&parsed.records),
+    ))
 }
 
 /// One optional column. Absence writes an empty cell, which is the only
@@ -259,7 +347,7 @@ fn parse_records(data: &[u8]) -> Result<(u32, Vec<FlightDataRecord>)> {
 /// all sit inside the range of values the column legitimately holds. Every
 /// column fed by an `Option` goes through here, so nothing downstream has to
 /// know which of them can be absent.
-fn cell<T: ToString>(value: Option<T>) -> String {
+pub(crate) fn cell<T: ToString>(value: Option<T>) -> String {
     value.map_or_else(String::new, |v| v.to_string())
 }
 
@@ -267,8 +355,9 @@ fn cell<T: ToString>(value: Option<T>) -> String {
 /// estimator sample backed this row, or the pyro watch had nothing to report
 /// yet. `false` there would be a statement about a sample that never happened,
 /// indistinguishable from a gate that looked at a real sample and passed it.
-fn bit(mask: Option<u8>, flag: u8) -> String {
-    cell(mask.map(|mask| (mask & flag) != 0))
+fn bit<T: Into<u16>>(mask: Option<T>, flag: T) -> String {
+    let flag = flag.into();
+    cell(mask.map(|mask| (mask.into() & flag) != 0))
 }
 
 /// Decode one AMP output's 2-bit `PowerOutputStatus` from the packed
@@ -310,6 +399,23 @@ fn node_cells(node: Option<&NodeStatusRecord>) -> [String; 5] {
     }
 }
 
+/// The five columns of one filter's `debug-internals` record: innovation,
+/// its variance, the covariance diagonal and the NIS they make. Blank on
+/// every row without one — a flight build writes none, and a
+/// `debug-internals` build writes one every
+/// [`INTERNALS_RECORD_INTERVAL`](firmware_common_new::flight_data_record::INTERNALS_RECORD_INTERVAL)
+/// rows. The gate's verdict is not repeated: it is already on every row in
+/// `deployment_baro_gate_reject` and `deployment_baro_resync`.
+fn kf_internals_cells(record: Option<&KfInternalsRecord>) -> [String; 5] {
+    [
+        cell(record.map(|r| r.innovation)),
+        cell(record.map(|r| r.innovation_variance)),
+        cell(record.map(|r| r.altitude_variance)),
+        cell(record.map(|r| r.velocity_variance)),
+        cell(record.map(|r| KfInternals::from(r).nis())),
+    ]
+}
+
 fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
     let mut w = csv::Writer::from_path(path).with_context(|| format!("creating {}", path))?;
     // The pyro columns come from the fast record, so they update at the full
@@ -355,12 +461,26 @@ fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
         "deployment_kf_vertical_velocity",
         "deployment_baro_gate_reject",
         "deployment_baro_resync",
+        "deployment_baro1_health",
+        "deployment_baro2_health",
+        "deployment_baro3_health",
+        "deployment_gps_fusion",
         "airbrakes_kf_altitude_asl",
         "airbrakes_kf_vertical_velocity",
         "airbrakes_kf_tilt_deg",
         "airbrakes_pad_calibrated",
         "airbrakes_burnout",
         "airbrakes_state",
+        "deployment_kf_innovation",
+        "deployment_kf_innovation_variance",
+        "deployment_kf_altitude_variance",
+        "deployment_kf_velocity_variance",
+        "deployment_kf_nis",
+        "airbrakes_kf_innovation",
+        "airbrakes_kf_innovation_variance",
+        "airbrakes_kf_altitude_variance",
+        "airbrakes_kf_velocity_variance",
+        "airbrakes_kf_nis",
         "temperature",
         "battery_voltage",
         "lat",
@@ -376,6 +496,12 @@ fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
         "pyro_main_fire",
         "pyro_drogue_continuity",
         "pyro_drogue_fire",
+        "pyro_separation_continuity",
+        "pyro_separation_fire",
+        "pyro_backup_drogue_continuity",
+        "pyro_backup_drogue_fire",
+        "pyro_backup_main_continuity",
+        "pyro_backup_main_fire",
         "pyro_short_circuit",
         "air_brakes_commanded_extension",
         "air_brakes_actual_extension",
@@ -481,6 +607,19 @@ fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
             cell(deployment.and_then(|d| d.kf_vertical_velocity)),
             bit(deployment.map(|d| d.flags), DEPLOYMENT_BARO_GATE_REJECT),
             bit(deployment.map(|d| d.flags), DEPLOYMENT_BARO_RESYNC),
+        ];
+        // By name, like `airbrakes_state`. Every slot is written, `Absent`
+        // included, whenever the deployment group is: an empty cell means
+        // "no estimator sample", never "no sensor".
+        row.extend((0..3).map(|slot| {
+            deployment
+                .map(|d| format!("{:?}", BaroHealth::unpack(d.baro_health)[slot]))
+                .unwrap_or_default()
+        }));
+        row.extend([
+            deployment
+                .map(|d| format!("{:?}", GpsFusion::from_flags(d.flags)))
+                .unwrap_or_default(),
             cell(airbrakes.and_then(|a| a.kf_altitude_asl)),
             cell(airbrakes.and_then(|a| a.kf_vertical_velocity)),
             cell(airbrakes.and_then(|a| a.kf_tilt_deg)),
@@ -491,6 +630,15 @@ fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
             airbrakes
                 .map(|a| format!("{:?}", AirbrakesState::from_flags(a.flags)))
                 .unwrap_or_default(),
+        ]);
+        let internals = r.internals.as_ref();
+        row.extend(kf_internals_cells(
+            internals.and_then(|i| i.deployment.as_ref()),
+        ));
+        row.extend(kf_internals_cells(
+            internals.and_then(|i| i.airbrakes.as_ref()),
+        ));
+        row.extend([
             cell(r.temperature),
             cell(r.battery_voltage),
             cell(r.lat_lon.map(|(lat, _)| lat)),
@@ -506,6 +654,12 @@ fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
             bit(pyro, PYRO_MAIN_FIRE),
             bit(pyro, PYRO_DROGUE_CONTINUITY),
             bit(pyro, PYRO_DROGUE_FIRE),
+            bit(pyro, PYRO_SEPARATION_CONTINUITY),
+            bit(pyro, PYRO_SEPARATION_FIRE),
+            bit(pyro, PYRO_BACKUP_DROGUE_CONTINUITY),
+            bit(pyro, PYRO_BACKUP_DROGUE_FIRE),
+            bit(pyro, PYRO_BACKUP_MAIN_CONTINUITY),
+            bit(pyro, PYRO_BACKUP_MAIN_FIRE),
             bit(pyro, PYRO_SHORT_CIRCUIT),
             cell(air_brakes.commanded_extension),
             cell(air_brakes.actual_extension),
@@ -513,7 +667,7 @@ fn write_csv(path: &str, records: &[FlightDataRecord]) -> Result<()> {
             cell(Some(air_brakes.validation_deploy as u8)),
             cell(air_brakes_mpc.and_then(|a| a.predicted_apogee_asl)),
             cell(air_brakes_mpc.and_then(|a| a.target_apogee_asl)),
-        ];
+        ]);
         row.extend(node_cells(r.amp_node.as_ref()));
         row.extend(node_cells(r.icarus_node.as_ref()));
         row.extend(node_cells(r.ozys_node.as_ref()));
@@ -565,8 +719,9 @@ pub fn list_files() -> Result<()> {
     drain_stale(&handle);
     send_request(&handle, CliRequest::List)?;
     let header = read_header(&handle)?;
-    let (record_count, storage_version, block_count) = decode_response_header(&header)
-        .ok_or_else(|| anyhow!("device sent an invalid response header"))?;
+    let (record_count, storage_version, block_count, superblock_flags) =
+        decode_response_header(&header)
+            .ok_or_else(|| anyhow!("device sent an invalid response header"))?;
 
     println!("VLF5 flight log:");
     println!("  records      : {}", record_count);
@@ -575,6 +730,11 @@ pub fn list_files() -> Result<()> {
         block_count,
         block_count as usize * BLOCK_SIZE
     );
+    if superblock_flags & SUPERBLOCK_FLAG_COMPRESSED != 0 {
+        println!("  block mode   : compressed");
+    } else {
+        println!("  block mode   : plain");
+    }
     if storage_version == STORAGE_VERSION {
         println!("  storage ver  : {}", storage_version);
     } else {
@@ -590,22 +750,150 @@ pub fn list_files() -> Result<()> {
 }
 
 /// `download-flight-log <out.csv>`: pull the whole log and write it as CSV.
-pub fn download_file(output: &str) -> Result<()> {
+/// With `recover`, also pull the card past the superblock's claimed end and
+/// keep whatever continues the log — the tail a brown-out left unrecorded.
+pub fn download_file(output: &str, recover: bool) -> Result<()> {
     let handle = find_and_open()?;
     drain_stale(&handle);
-    send_request(&handle, CliRequest::Download)?;
-    let data = read_response(&handle)?;
-    let (log_record_count, records) = parse_records(&data)?;
-    write_csv(output, &records)?;
+    let (log_record_count, records, events) = if recover {
+        send_request(&handle, CliRequest::DownloadRaw)?;
+        parse_raw_records(&read_response(&handle)?)?
+    } else {
+        send_request(&handle, CliRequest::Download)?;
+        parse_records(&read_response(&handle)?)?
+    };
     println!(
-        "Wrote {} fast row(s) from {} on-card record(s) to {}",
-        records.len(),
+        "Read {} on-card record(s) holding {} fast row(s)",
         log_record_count,
-        output
+        records.len()
+    );
+    write_flight_log(output, &records, &events)
+}
+
+/// Write merged rows as the flight-log CSV at `output`, and the events as
+/// the `.events.csv` / `.events.json` pair beside it (see [`events_paths`]).
+/// A real card's log and a simulated one (`simulate`) both go out through
+/// here, which is what lets `plot-flight-log` read either.
+pub fn write_flight_log(
+    output: &str,
+    records: &[FlightDataRecord],
+    events: &[FlightEventRow],
+) -> Result<()> {
+    write_csv(output, records)?;
+    println!("Wrote {} fast row(s) to {}", records.len(), output);
+    // Written even when empty: a stale events file left beside a fresh CSV
+    // would put another flight's rules on this one's plots.
+    let (events_csv, events_json) = events_paths(output);
+    write_events_csv(&events_csv, events)?;
+    std::fs::write(&events_json, serde_json::to_string_pretty(events)?)
+        .with_context(|| format!("creating {}", events_json))?;
+    println!(
+        "Wrote {} event(s) to {} and {}",
+        events.len(),
+        events_csv,
+        events_json
     );
     Ok(())
 }
 
+/// `flight_log.csv` -> `flight_log.events.csv`, `flight_log.events.json`.
+/// `plot-flight-log` looks for the CSV there.
+pub fn events_paths(output: &str) -> (String, String) {
+    let path = std::path::Path::new(output);
+    (
+        path.with_extension("events.csv").to_string_lossy().into_owned(),
+        path.with_extension("events.json").to_string_lossy().into_owned(),
+    )
+}
+
+/// `VLPUplinkPacket` type byte to its variant name.
+fn uplink_name(packet_type: u8) -> String {
+    match packet_type {
+        0 => "ChangeMode".to_string(),
+        1 => "Reset".to_string(),
+        2 => "AMPOutputOverwrite".to_string(),
+        3 => "FirePyro".to_string(),
+        4 => "SetTargetApogee".to_string(),
+        other => format!("packet type {other}"),
+    }
+}
+
+/// An event's kind and a readable account of its fields. The JSON beside the
+/// CSV carries the fields raw; this is the column a person reads.
+fn event_cells(event: &FlightEvent) -> [String; 2] {
+    let (kind, detail) = match *event {
+        FlightEvent::StageChanged { from, to } => ("StageChanged", format!("{from:?} -> {to:?}")),
+        FlightEvent::ModeChangeCommanded { mode } => (
+            "ModeChangeCommanded",
+            Mode::from_primitive(mode).map_or_else(|| format!("mode {mode}"), |m| format!("{m:?}")),
+        ),
+        FlightEvent::UplinkReceived {
+            packet_type,
+            signature_valid,
+        } => (
+            "UplinkReceived",
+            if signature_valid {
+                uplink_name(packet_type)
+            } else {
+                format!("{}, signature invalid", uplink_name(packet_type))
+            },
+        ),
+        FlightEvent::EccFailure => ("EccFailure", String::new()),
+        FlightEvent::PyroCommanded { channel, by_uplink } => (
+            "PyroCommanded",
+            format!(
+                "{} by {}",
+                PyroSelect::from_primitive(channel)
+                    .map_or_else(|| format!("channel {channel}"), |p| format!("{p:?}")),
+                if by_uplink { "uplink" } else { "flight computer" }
+            ),
+        ),
+        FlightEvent::NodeRebooted {
+            node_type,
+            uptime_s,
+        } => (
+            "NodeRebooted",
+            format!("{}, uptime {uptime_s} s", NodeTypeEnum::from(node_type)),
+        ),
+        FlightEvent::ConfigChanged { target_apogee_agl } => (
+            "ConfigChanged",
+            format!("target apogee {target_apogee_agl} m AGL"),
+        ),
+        FlightEvent::Staging { kind, burn } => (
+            "Staging",
+            StagingEvent::from_parts(kind, burn)
+                .map_or_else(|| format!("kind {kind}, burn {burn}"), |e| format!("{e:?}")),
+        ),
+    };
+    [kind.to_string(), detail]
+}
+
+/// The event records, one per line. `row` is the index of the first flight-log
+/// row logged after the event ([`FlightEventRow::next_row`]), which is what
+/// places it in the right session of a CSV that holds several.
+fn write_events_csv(path: &str, events: &[FlightEventRow]) -> Result<()> {
+    let mut w = csv::Writer::from_path(path).with_context(|| format!("creating {}", path))?;
+    w.write_record([
+        "row",
+        "source_block_crc_failed",
+        "timestamp_us",
+        "event",
+        "detail",
+    ])?;
+    for event in events {
+        let [kind, detail] = event_cells(&event.event);
+        w.write_record([
+            event.next_row.to_string(),
+            event.source_block_crc_failed.to_string(),
+            event.timestamp_us.to_string(),
+            kind,
+            detail,
+        ])?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
 /// `clear-storage`: erase the log on the VLF5.
 pub fn clear_storage() -> Result<()> {
     let handle = find_and_open()?;
@@ -616,13 +904,41 @@ pub fn clear_storage() -> Result<()> {
     Ok(())
 }
 
+/// Pull the frame [`CliRequest::ReadConfig`] and [`CliRequest::WriteConfig`]
+/// both answer with, and return the config block inside it.
+fn read_config_block(handle: &DeviceHandle<Context>) -> Result<[u8; BLOCK_SIZE]> {
+    let frame = read_short_response(handle, BLOCK_SIZE)?;
+    let mut block = [0u8; BLOCK_SIZE];
+    block.copy_from_slice(&frame[HEADER_LEN..]);
+    Ok(block)
+}
+
+/// `config show`: the raw config block currently on the VLF5's card.
+pub fn read_config() -> Result<[u8; BLOCK_SIZE]> {
+    let handle = find_and_open()?;
+    drain_stale(&handle);
+    send_request(&handle, CliRequest::ReadConfig)?;
+    read_config_block(&handle)
+}
+
+/// `config write`: store `block` and return what the card holds afterwards.
+///
+/// The reply is the device's own read-back, not an echo, so a block it
+/// refused comes back as whatever was there before — the caller compares.
+pub fn write_config(block: &[u8; BLOCK_SIZE]) -> Result<[u8; BLOCK_SIZE]> {
+    let handle = find_and_open()?;
+    drain_stale(&handle);
+    send_request_with_data(&handle, CliRequest::WriteConfig, block)?;
+    read_config_block(&handle)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use firmware_common_new::can_bus::messages::vl_status::FlightStage;
     use firmware_common_new::flight_data_record::{
-        AirBrakesActuationRecord, DeploymentEstimatorRecord, FlightDataFastRecord, LogRecord,
-        ParsedLogRecord, merge_log_records,
+        AirBrakesActuationRecord, DeploymentEstimatorRecord, EstimatorInternalsRecord,
+        FlightDataFastRecord, LogRecord, ParsedLogRecord, merge_log_records,
     };
 
     /// A CSV whose rows are narrower or wider than its header silently
@@ -644,7 +960,13 @@ mod tests {
             deployment: Some(DeploymentEstimatorRecord {
                 kf_altitude_asl: None,
                 kf_vertical_velocity: None,
-                flags: 0,
+                // A good fix, which the lockout only watches.
+                flags: GpsFusion::NotDescending.to_flags(),
+                baro_health: BaroHealth::pack(&[
+                    BaroHealth::Healthy,
+                    BaroHealth::Stuck,
+                    BaroHealth::Absent,
+                ]),
             }),
             airbrakes: None,
             flight_stage: FlightStage::Ascent,
@@ -660,8 +982,20 @@ mod tests {
         };
         // No slow record ahead of it, so every slow column is absent too. The
         // second row is the same sample read out of a block that failed its
-        // CRC, which is exported all the same — but marked.
+        // CRC, which is exported all the same — but marked. Only the first
+        // has a `debug-internals` record, and only for the deployment filter.
         let records = merge_log_records(&[
+            ParsedLogRecord::good(LogRecord::Internals(EstimatorInternalsRecord {
+                timestamp_us: 1000,
+                deployment: Some(KfInternalsRecord {
+                    innovation: 2.0,
+                    innovation_variance: 0.5,
+                    altitude_variance: 0.25,
+                    velocity_variance: 1.5,
+                    gate_flags: 0,
+                }),
+                airbrakes: None,
+            })),
             ParsedLogRecord::good(LogRecord::Fast(lockout.clone())),
             ParsedLogRecord {
                 record: LogRecord::Fast(FlightDataFastRecord {
@@ -702,6 +1036,13 @@ mod tests {
         // The whole point: frozen filter reads empty, not zero.
         assert_eq!(col("deployment_kf_altitude_asl"), "");
         assert_eq!(col("deployment_kf_vertical_velocity"), "");
+        // The vote's verdicts are per slot, and an empty slot says so by
+        // name rather than by an empty cell.
+        assert_eq!(col("deployment_baro1_health"), "Healthy");
+        assert_eq!(col("deployment_baro2_health"), "Stuck");
+        assert_eq!(col("deployment_baro3_health"), "Absent");
+        assert_eq!(col("deployment_gps_fusion"), "NotDescending");
+        assert_eq!(col("deployment_baro_gate_reject"), "false");
         assert_eq!(col("acc_x"), "");
         assert_eq!(col("unix_time_us"), "");
         // A value that is genuinely present still prints.
@@ -717,5 +1058,12 @@ mod tests {
         assert_eq!(col("source_block_crc_failed"), "false");
         assert_eq!(bad_row[col_index("source_block_crc_failed")], "true");
         assert_eq!(bad_row[col_index("pressure")], "101325");
+
+        // The internals land on their own row, NIS worked out, and a filter
+        // the record did not carry is blank like any other absence.
+        assert_eq!(col("deployment_kf_innovation"), "2");
+        assert_eq!(col("deployment_kf_nis"), "8");
+        assert_eq!(col("airbrakes_kf_nis"), "");
+        assert_eq!(bad_row[col_index("deployment_kf_innovation")], "");
     }
 }